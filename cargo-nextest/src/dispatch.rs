@@ -17,23 +17,28 @@ use nextest_runner::{
     cargo_config::{CargoConfigs, EnvironmentMap, TargetTriple},
     config::{
         get_num_cpus, NextestConfig, NextestProfile, PreBuildPlatform, RetryPolicy, TestGroup,
-        TestThreads, ToolConfigFile,
+        TestOrder, TestThreads, ToolConfigFile,
     },
     double_spawn::DoubleSpawnInfo,
     errors::WriteTestListError,
     list::{
         BinaryList, OutputFormat, RustTestArtifact, SerializableFormat, TestExecuteContext,
-        TestList,
+        TestList, TestListEvent,
     },
+    order_check::{self, OutcomeChangeKind, RunOutcomes},
     partition::PartitionerBuilder,
     platform::BuildPlatforms,
-    reporter::{FinalStatusLevel, StatusLevel, TestOutputDisplay, TestReporterBuilder},
+    reporter::{
+        EventFormat, FinalStatusLevel, StatusLevel, TestOutputDisplay, TestReporterBuilder,
+    },
     reuse_build::{archive_to_file, ArchiveReporter, MetadataOrPath, PathMapper, ReuseBuildInfo},
-    runner::{configure_handle_inheritance, TestRunnerBuilder},
+    run_summary::RunSummary,
+    runner::{configure_handle_inheritance, FinalRunStatus, TestRunnerBuilder},
     show_config::{ShowTestGroupSettings, ShowTestGroups, ShowTestGroupsMode},
     signal::SignalHandlerKind,
     target_runner::{PlatformRunner, TargetRunner},
     test_filter::{RunIgnored, TestFilterBuilder},
+    timings::RecordedTimings,
 };
 use once_cell::sync::OnceCell;
 use owo_colors::{OwoColorize, Stream, Style};
@@ -41,8 +46,12 @@ use std::{
     fmt::Write as _,
     io::{Cursor, Write},
     sync::Arc,
+    time::Duration,
 };
 
+/// How often to report test binaries that are taking a long time to list their tests.
+const LIST_SLOW_PERIOD: Duration = Duration::from_secs(30);
+
 /// A next-generation test runner for Rust.
 ///
 /// This binary should typically be invoked as `cargo nextest` (in which case
@@ -496,7 +505,7 @@ impl TestBuildFilter {
             &path_mapper,
             self.platform_filter.into(),
         )?;
-        TestList::new(
+        TestList::new_with_progress(
             ctx,
             test_artifacts,
             rust_build_meta,
@@ -504,6 +513,27 @@ impl TestBuildFilter {
             env,
             // TODO: do we need to allow customizing this?
             get_num_cpus(),
+            Some(LIST_SLOW_PERIOD),
+            |event| match event {
+                TestListEvent::BinarySlow { binary_id, elapsed } => {
+                    log::warn!(
+                        "listing tests in {binary_id} has taken {:.3}s so far -- \
+                         the binary may be hung",
+                        elapsed.as_secs_f64(),
+                    );
+                }
+                TestListEvent::BinaryFinished {
+                    binary_id,
+                    test_count,
+                    elapsed,
+                } => {
+                    log::debug!(
+                        "listed {test_count} tests in {binary_id} in {:.3}s",
+                        elapsed.as_secs_f64(),
+                    );
+                }
+                _ => {}
+            },
         )
         .map_err(|err| ExpectedError::CreateTestListError { err })
     }
@@ -657,7 +687,8 @@ pub struct TestRunnerOpts {
     #[arg(long, name = "no-run")]
     no_run: bool,
 
-    /// Number of tests to run simultaneously [possible values: integer or "num-cpus"]
+    /// Number of tests to run simultaneously [possible values: integer, "num-cpus",
+    /// "num-cpus-N" or a percentage like "50%"]
     /// [default: from profile]
     #[arg(
         long,
@@ -681,12 +712,47 @@ pub struct TestRunnerOpts {
     /// Run all tests regardless of failure
     #[arg(long, conflicts_with = "no-run", overrides_with = "fail-fast")]
     no_fail_fast: bool,
+
+    /// Run tests twice, in the usual order then shuffled, and warn about tests whose outcome
+    /// changed
+    #[arg(long, conflicts_with = "no-run")]
+    check_order_dependence: bool,
+
+    /// Seed used to shuffle tests with --check-order-dependence [default: random]
+    #[arg(long, value_name = "SEED", requires = "check_order_dependence")]
+    order_seed: Option<u64>,
+
+    /// Skip tests that took longer than this in the last run, as per the profile's CSV timings
+    #[arg(long, value_name = "DURATION", conflicts_with = "no-run")]
+    skip_slower_than: Option<humantime::Duration>,
+
+    /// Run the tests in this suite, with the suite's settings layered over the profile's
+    #[arg(long, value_name = "SUITE", env = "NEXTEST_SUITE")]
+    suite: Option<String>,
+
+    /// Don't cancel the run for tests that failed in this run summary, as written by
+    /// --write-run-summary
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with = "no-run",
+        env = "NEXTEST_KNOWN_FAILURES"
+    )]
+    known_failures: Option<Utf8PathBuf>,
+
+    /// Write a summary of the run to this path, as JSON
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = &["no-run", "check_order_dependence"],
+    )]
+    write_run_summary: Option<Utf8PathBuf>,
 }
 
 impl TestRunnerOpts {
-    fn to_builder(&self, no_capture: bool) -> Option<TestRunnerBuilder> {
+    fn to_builder(&self, no_capture: bool) -> Result<Option<TestRunnerBuilder>> {
         if self.no_run {
-            return None;
+            return Ok(None);
         }
 
         let mut builder = TestRunnerBuilder::default();
@@ -695,15 +761,24 @@ impl TestRunnerOpts {
             builder.set_retries(RetryPolicy::new_without_delay(retries));
         }
         if self.no_fail_fast {
-            builder.set_fail_fast(false);
+            builder.set_fail_fast(false.into());
         } else if self.fail_fast {
-            builder.set_fail_fast(true);
+            builder.set_fail_fast(true.into());
         }
         if let Some(test_threads) = self.test_threads {
             builder.set_test_threads(test_threads);
         }
+        if let Some(path) = &self.known_failures {
+            let contents = std::fs::read_to_string(path).map_err(|err| {
+                ExpectedError::argument_file_read_error("known-failures", path, err)
+            })?;
+            let summary: RunSummary = serde_json::from_str(&contents).map_err(|err| {
+                ExpectedError::argument_json_parse_error("known-failures", path, err)
+            })?;
+            builder.set_known_failures(summary.known_failures());
+        }
 
-        Some(builder)
+        Ok(Some(builder))
     }
 }
 
@@ -760,6 +835,29 @@ struct TestReporterOpts {
     /// Do not display the progress bar
     #[arg(long, env = "NEXTEST_HIDE_PROGRESS_BAR")]
     hide_progress_bar: bool,
+
+    /// Order results at the end of the run by package, with dependencies first
+    #[arg(long, conflicts_with = "no-run", env = "NEXTEST_TOPOLOGICAL_ORDER")]
+    topological_order: bool,
+
+    /// Format for test events written to stderr
+    #[arg(
+        long,
+        value_enum,
+        conflicts_with = "no-run",
+        value_name = "FORMAT",
+        env = "NEXTEST_EVENT_FORMAT"
+    )]
+    event_format: Option<EventFormatOpt>,
+
+    /// Emit GitHub Actions annotations for failing tests
+    #[arg(long, conflicts_with = "no-run", env = "NEXTEST_GITHUB_ANNOTATIONS")]
+    github_annotations: bool,
+
+    /// Annotate each test with its shard under this partitioning, e.g. hash:1/3 (the shard number
+    /// is ignored and tests are not filtered)
+    #[arg(long, conflicts_with = "no-run", value_name = "PARTITION")]
+    annotate_partition: Option<PartitionerBuilder>,
 }
 
 impl TestReporterOpts {
@@ -779,10 +877,37 @@ impl TestReporterOpts {
             builder.set_final_status_level(final_status_level.into());
         }
         builder.set_hide_progress_bar(self.hide_progress_bar);
+        builder.set_topological_order(self.topological_order);
+        builder.set_github_annotations(self.github_annotations);
+        if let Some(event_format) = self.event_format {
+            builder.set_event_format(event_format.into());
+        }
+        if let Some(partitioner) = &self.annotate_partition {
+            builder.set_shard_annotations(partitioner.clone());
+        }
         builder
     }
 }
 
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum EventFormatOpt {
+    Human,
+    JsonLines,
+    Tap,
+    TestResults,
+}
+
+impl From<EventFormatOpt> for EventFormat {
+    fn from(opt: EventFormatOpt) -> Self {
+        match opt {
+            EventFormatOpt::Human => EventFormat::Human,
+            EventFormatOpt::JsonLines => EventFormat::JsonLines,
+            EventFormatOpt::Tap => EventFormat::Tap,
+            EventFormatOpt::TestResults => EventFormat::TestResults,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, ValueEnum)]
 enum TestOutputDisplayOpt {
     Immediate,
@@ -973,9 +1098,19 @@ impl BaseApp {
         })
     }
 
-    fn load_runner(&self, build_platforms: &BuildPlatforms) -> &TargetRunner {
-        self.target_runner
-            .get_or_init(|| runner_for_target(&self.cargo_configs, build_platforms))
+    fn load_runner(
+        &self,
+        build_platforms: &BuildPlatforms,
+        config: &NextestConfig,
+    ) -> &TargetRunner {
+        self.target_runner.get_or_init(|| {
+            runner_for_target(
+                &self.cargo_configs,
+                build_platforms,
+                config,
+                &self.workspace_root,
+            )
+        })
     }
 
     fn exec_archive(
@@ -1077,12 +1212,18 @@ impl App {
         Ok(Self { base, build_filter })
     }
 
-    fn build_filtering_expressions(&self) -> Result<Vec<FilteringExpr>> {
+    fn build_filtering_expressions(&self, config: &NextestConfig) -> Result<Vec<FilteringExpr>> {
         let (exprs, all_errors): (Vec<_>, Vec<_>) = self
             .build_filter
             .filter_expr
             .iter()
-            .map(|input| FilteringExpr::parse(input.clone(), self.base.graph()))
+            .map(|input| {
+                FilteringExpr::parse_with_sets(
+                    input.clone(),
+                    self.base.graph(),
+                    config.filter_sets(),
+                )
+            })
             .partition_result();
 
         if !all_errors.is_empty() {
@@ -1140,7 +1281,11 @@ impl App {
         list_type: ListType,
         output_writer: &mut OutputWriter,
     ) -> Result<()> {
-        let filter_exprs = self.build_filtering_expressions()?;
+        let config = self
+            .base
+            .config_opts
+            .make_config(&self.base.workspace_root, self.base.graph())?;
+        let filter_exprs = self.build_filtering_expressions(&config)?;
         let test_filter_builder = self.build_filter.make_test_filter_builder(filter_exprs)?;
 
         let binary_list = self.base.build_binary_list()?;
@@ -1162,7 +1307,7 @@ impl App {
                 let double_spawn = self.base.load_double_spawn();
                 let target_runner = self
                     .base
-                    .load_runner(&binary_list.rust_build_meta.build_platforms()?);
+                    .load_runner(&binary_list.rust_build_meta.build_platforms()?, &config);
                 let ctx = TestExecuteContext {
                     double_spawn,
                     target_runner,
@@ -1207,8 +1352,9 @@ impl App {
         };
         let settings = ShowTestGroupSettings { mode, show_default };
 
-        let filter_exprs = self.build_filtering_expressions()?;
-        let test_filter_builder = self.build_filter.make_test_filter_builder(filter_exprs)?;
+        let filter_exprs = self.build_filtering_expressions(&config)?;
+        let mut test_filter_builder = self.build_filter.make_test_filter_builder(filter_exprs)?;
+        test_filter_builder.set_default_filter(profile.default_filter());
 
         let binary_list = self.base.build_binary_list()?;
         let build_platforms = binary_list.rust_build_meta.build_platforms()?;
@@ -1216,7 +1362,7 @@ impl App {
         let double_spawn = self.base.load_double_spawn();
         let target_runner = self
             .base
-            .load_runner(&binary_list.rust_build_meta.build_platforms()?);
+            .load_runner(&binary_list.rust_build_meta.build_platforms()?, &config);
         let ctx = TestExecuteContext {
             double_spawn,
             target_runner,
@@ -1257,38 +1403,92 @@ impl App {
             .make_config(&self.base.workspace_root, self.base.graph())?;
         let profile = self.load_profile(profile_name, &config)?;
 
-        let filter_exprs = self.build_filtering_expressions()?;
-        let test_filter_builder = self.build_filter.make_test_filter_builder(filter_exprs)?;
+        let filter_exprs = self.build_filtering_expressions(&config)?;
+        let mut test_filter_builder = self.build_filter.make_test_filter_builder(filter_exprs)?;
+        test_filter_builder.set_default_filter(profile.default_filter());
+
+        let profile = match &runner_opts.suite {
+            Some(suite_name) => {
+                let suite = config
+                    .suite(suite_name)
+                    .map_err(ExpectedError::suite_not_found)?;
+                test_filter_builder.set_suite(&suite);
+                profile.with_suite(&suite)
+            }
+            None => profile,
+        };
 
         let binary_list = self.base.build_binary_list()?;
         let build_platforms = binary_list.rust_build_meta.build_platforms()?;
         let double_spawn = self.base.load_double_spawn();
-        let target_runner = self.base.load_runner(&build_platforms);
+        let target_runner = self.base.load_runner(&build_platforms, &config);
         let ctx = TestExecuteContext {
             double_spawn,
             target_runner,
         };
 
+        let profile = profile.apply_build_platforms(&build_platforms);
+
+        if let Some(threshold) = runner_opts.skip_slower_than {
+            match profile.csv() {
+                Some(csv) => match RecordedTimings::read_csv(csv.path())? {
+                    Some(timings) => {
+                        test_filter_builder.set_skip_slower_than(threshold.into(), timings);
+                    }
+                    None => {
+                        log::info!(
+                            "no timings recorded at `{}` yet, so no tests will be skipped \
+                             by --skip-slower-than",
+                            csv.path(),
+                        );
+                    }
+                },
+                None => {
+                    log::warn!(
+                        "--skip-slower-than requires CSV timings to be enabled for this profile \
+                         (see <https://nexte.st/book/csv-timings>), so no tests will be skipped",
+                    );
+                }
+            }
+        }
+
         let test_list = self.build_test_list(&ctx, binary_list, test_filter_builder)?;
 
-        let output = output_writer.reporter_output();
-        let profile = profile.apply_build_platforms(&build_platforms);
+        if runner_opts.check_order_dependence {
+            return self.exec_order_check(
+                &test_list,
+                &profile,
+                &ctx,
+                no_capture,
+                runner_opts,
+                reporter_opts,
+                output_writer,
+            );
+        }
 
-        let mut reporter = reporter_opts
-            .to_builder(no_capture)
-            .set_verbose(self.base.output.verbose)
-            .build(&test_list, &profile, output);
-        if self
-            .base
-            .output
-            .color
-            .should_colorize(supports_color::Stream::Stderr)
-        {
-            reporter.colorize();
+        let output = output_writer.reporter_output();
+        let color = self.base.output.color;
+        let mut reporter_builder = reporter_opts.to_builder(no_capture);
+        reporter_builder.set_verbose(self.base.output.verbose);
+        color.configure_reporter(&mut reporter_builder);
+        if let Some(csv) = profile.csv() {
+            // Recorded timings are only used to estimate the time remaining, so errors reading
+            // them aren't fatal.
+            match RecordedTimings::read_csv(csv.path()) {
+                Ok(Some(timings)) => {
+                    reporter_builder.set_recorded_timings(timings);
+                }
+                Ok(None) => {}
+                Err(error) => {
+                    log::warn!("not estimating the time remaining from recorded timings: {error}");
+                }
+            }
         }
+        let mut reporter = reporter_builder.build(&test_list, &profile, output);
+        color.finish_reporter(&mut reporter);
 
         let handler = SignalHandlerKind::Standard;
-        let runner_builder = match runner_opts.to_builder(no_capture) {
+        let runner_builder = match runner_opts.to_builder(no_capture)? {
             Some(runner_builder) => runner_builder,
             None => {
                 // This means --no-run was passed in. Exit.
@@ -1296,6 +1496,7 @@ impl App {
             }
         };
 
+        let flaky_as_failure = profile.flaky_as_failure();
         let runner = runner_builder.build(
             &test_list,
             profile,
@@ -1304,15 +1505,141 @@ impl App {
             target_runner.clone(),
         )?;
 
+        runner.run_pre_flight(&self.base.workspace_root)?;
+
         configure_handle_inheritance(no_capture)?;
+        let mut run_summary = runner_opts
+            .write_run_summary
+            .as_ref()
+            .map(|_| RunSummary::new());
         let run_stats = runner.try_execute(|event| {
+            if let Some(run_summary) = &mut run_summary {
+                run_summary.record(&event);
+            }
             // Write and flush the event.
             reporter.report_event(event)
         })?;
-        if !run_stats.is_success() {
-            return Err(ExpectedError::test_run_failed());
+        if let (Some(path), Some(run_summary)) = (&runner_opts.write_run_summary, &run_summary) {
+            let json = serde_json::to_string_pretty(run_summary)
+                .expect("run summaries can always be serialized");
+            std::fs::write(path, json)
+                .map_err(|err| ExpectedError::run_summary_write_error(path, err))?;
+        }
+        match run_stats.final_status(flaky_as_failure) {
+            FinalRunStatus::Success => Ok(()),
+            FinalRunStatus::Flaky => Err(ExpectedError::test_run_flaky(run_stats.flaky)),
+            FinalRunStatus::Failed => Err(ExpectedError::test_run_failed()),
+        }
+    }
+
+    /// Runs tests once in the usual order and once shuffled, then warns about tests whose outcome
+    /// changed between the two runs.
+    fn exec_order_check(
+        &self,
+        test_list: &TestList<'_>,
+        profile: &NextestProfile<'_>,
+        ctx: &TestExecuteContext<'_>,
+        no_capture: bool,
+        runner_opts: &TestRunnerOpts,
+        reporter_opts: &TestReporterOpts,
+        output_writer: &mut OutputWriter,
+    ) -> Result<()> {
+        let seed = runner_opts
+            .order_seed
+            .unwrap_or_else(order_check::random_seed);
+        configure_handle_inheritance(no_capture)?;
+
+        let flaky_as_failure = profile.flaky_as_failure();
+        let mut final_status = FinalRunStatus::Success;
+        let mut flaky = 0;
+        let mut outcomes = Vec::with_capacity(2);
+        for shuffle_seed in [None, Some(seed)] {
+            match shuffle_seed {
+                None => log::info!("checking for order dependence: running tests in order"),
+                Some(seed) => log::info!(
+                    "checking for order dependence: running tests shuffled with seed {seed} \
+                     (pass in `--order-seed {seed}` to reproduce)"
+                ),
+            }
+
+            let color = self.base.output.color;
+            let mut reporter_builder = reporter_opts.to_builder(no_capture);
+            reporter_builder.set_verbose(self.base.output.verbose);
+            // Reports are written out for the in-order run, which is what a normal run would do.
+            // The shuffled run only checks for changed outcomes, so it leaves them alone.
+            reporter_builder.set_skip_reports(shuffle_seed.is_some());
+            color.configure_reporter(&mut reporter_builder);
+            let mut reporter =
+                reporter_builder.build(test_list, profile, output_writer.reporter_output());
+            color.finish_reporter(&mut reporter);
+
+            let mut runner_builder = runner_opts
+                .to_builder(no_capture)?
+                .expect("--no-run conflicts with --check-order-dependence");
+            match shuffle_seed {
+                Some(seed) => {
+                    runner_builder.set_shuffle_seed(seed);
+                }
+                None => {
+                    // The first run is in order, even if the profile shuffles tests.
+                    runner_builder.set_test_order(TestOrder::default());
+                }
+            }
+            let runner = runner_builder.build(
+                test_list,
+                profile.clone(),
+                SignalHandlerKind::Standard,
+                ctx.double_spawn.clone(),
+                ctx.target_runner.clone(),
+            )?;
+
+            if shuffle_seed.is_none() {
+                // The pre-flight command only needs to be run once.
+                runner.run_pre_flight(&self.base.workspace_root)?;
+            }
+
+            let mut run_outcomes = RunOutcomes::new();
+            let run_stats = runner.try_execute(|event| {
+                run_outcomes.record(&event);
+                reporter.report_event(event)
+            })?;
+            // The worst status across both runs is reported.
+            match run_stats.final_status(flaky_as_failure) {
+                FinalRunStatus::Success => {}
+                FinalRunStatus::Flaky => {
+                    flaky = flaky.max(run_stats.flaky);
+                    if final_status == FinalRunStatus::Success {
+                        final_status = FinalRunStatus::Flaky;
+                    }
+                }
+                FinalRunStatus::Failed => final_status = FinalRunStatus::Failed,
+            }
+            outcomes.push(run_outcomes);
+        }
+
+        let changes = outcomes[0].compare(&outcomes[1]);
+        for change in &changes {
+            let cause = match change.kind() {
+                OutcomeChangeKind::OrderDependent => "may depend on test order",
+                OutcomeChangeKind::Flaky => "is flaky",
+            };
+            log::warn!(
+                "{} {}: {} in order, {} when shuffled with seed {seed} -- test {cause}",
+                change.binary_id,
+                change.test_name,
+                change.in_order,
+                change.shuffled,
+            );
+        }
+        if changes.is_empty() {
+            log::info!("no test outcomes changed when shuffled with seed {seed}");
+        }
+
+        match final_status {
+            FinalRunStatus::Success => Ok(()),
+            FinalRunStatus::Flaky => Err(ExpectedError::test_run_flaky(flaky)),
+            FinalRunStatus::Failed => Err(ExpectedError::test_run_failed()),
         }
-        Ok(())
     }
 }
 
@@ -1518,8 +1845,13 @@ fn discover_target_triple(
 fn runner_for_target(
     cargo_configs: &CargoConfigs,
     build_platforms: &BuildPlatforms,
+    config: &NextestConfig,
+    workspace_root: &Utf8Path,
 ) -> TargetRunner {
-    match TargetRunner::new(cargo_configs, build_platforms) {
+    match TargetRunner::new(cargo_configs, build_platforms)
+        .and_then(|runner| runner.with_rules(config.target_runner_rules(), workspace_root))
+        .map(|runner| runner.with_extra_args(config.target_runner_args(), workspace_root))
+    {
         Ok(runner) => {
             if build_platforms.target.is_some() {
                 if let Some(runner) = runner.target() {
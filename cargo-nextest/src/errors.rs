@@ -3,7 +3,7 @@
 
 use camino::Utf8PathBuf;
 use itertools::Itertools;
-use nextest_filtering::errors::FilterExpressionParseErrors;
+use nextest_filtering::errors::{FilterExpressionParseErrors, FilterSetsError};
 use nextest_metadata::NextestExitCode;
 use nextest_runner::errors::*;
 use owo_colors::{OwoColorize, Stream};
@@ -46,6 +46,11 @@ pub enum ExpectedError {
         #[from]
         err: ProfileNotFound,
     },
+    #[error("test suite not found")]
+    SuiteNotFound {
+        #[from]
+        err: SuiteNotFound,
+    },
     #[error("failed to create store directory")]
     StoreDirCreateError {
         store_dir: Utf8PathBuf,
@@ -147,16 +152,32 @@ pub enum ExpectedError {
         #[from]
         err: TestRunnerBuildError,
     },
+    #[error("pre-flight command failed")]
+    PreFlightError {
+        #[from]
+        err: PreFlightError,
+    },
     #[error("writing test list to output failed")]
     WriteTestListError {
         #[from]
         err: WriteTestListError,
     },
+    #[error("reading timings failed")]
+    ReadTimingsError {
+        #[from]
+        err: ReadTimingsError,
+    },
     #[error("writing event failed")]
     WriteEventError {
         #[from]
         err: WriteEventError,
     },
+    #[error("writing run summary failed")]
+    RunSummaryWriteError {
+        file_name: Utf8PathBuf,
+        #[source]
+        err: std::io::Error,
+    },
     #[error(transparent)]
     ConfigureHandleInheritanceError {
         #[from]
@@ -169,6 +190,8 @@ pub enum ExpectedError {
     },
     #[error("test run failed")]
     TestRunFailed,
+    #[error("test run had flaky tests")]
+    TestRunFlaky { flaky: usize },
     #[cfg(feature = "self-update")]
     #[error("failed to parse --version")]
     UpdateVersionParseError {
@@ -186,11 +209,6 @@ pub enum ExpectedError {
         #[source]
         err: std::io::Error,
     },
-    #[error("failed to set up Ctrl-C handler")]
-    SignalHandlerSetupError {
-        #[from]
-        err: SignalHandlerSetupError,
-    },
     #[error("experimental feature not enabled")]
     ExperimentalFeatureNotEnabled {
         name: &'static str,
@@ -241,6 +259,10 @@ impl ExpectedError {
         Self::ProfileNotFound { err }
     }
 
+    pub(crate) fn suite_not_found(err: SuiteNotFound) -> Self {
+        Self::SuiteNotFound { err }
+    }
+
     pub(crate) fn config_parse_error(err: ConfigParseError) -> Self {
         Self::ConfigParseError { err }
     }
@@ -269,6 +291,16 @@ impl ExpectedError {
         }
     }
 
+    pub(crate) fn run_summary_write_error(
+        file_name: impl Into<Utf8PathBuf>,
+        err: std::io::Error,
+    ) -> Self {
+        Self::RunSummaryWriteError {
+            file_name: file_name.into(),
+            err,
+        }
+    }
+
     pub(crate) fn cargo_metadata_parse_error(
         file_name: impl Into<Option<Utf8PathBuf>>,
         err: guppy::Error,
@@ -314,6 +346,10 @@ impl ExpectedError {
         Self::TestRunFailed
     }
 
+    pub(crate) fn test_run_flaky(flaky: usize) -> Self {
+        Self::TestRunFlaky { flaky }
+    }
+
     pub(crate) fn test_binary_args_parse_error(reason: &'static str, args: Vec<String>) -> Self {
         Self::TestBinaryArgsParseError { reason, args }
     }
@@ -326,6 +362,7 @@ impl ExpectedError {
             }
             Self::SetCurrentDirFailed { .. }
             | Self::ProfileNotFound { .. }
+            | Self::SuiteNotFound { .. }
             | Self::StoreDirCreateError { .. }
             | Self::RootManifestNotFound { .. }
             | Self::CargoConfigError { .. }
@@ -343,8 +380,8 @@ impl ExpectedError {
             | Self::CargoMetadataParseError { .. }
             | Self::TestBinaryArgsParseError { .. }
             | Self::DialoguerError { .. }
-            | Self::SignalHandlerSetupError { .. }
-            | Self::ShowTestGroupsError { .. } => NextestExitCode::SETUP_ERROR,
+            | Self::ShowTestGroupsError { .. }
+            | Self::ReadTimingsError { .. } => NextestExitCode::SETUP_ERROR,
             #[cfg(feature = "self-update")]
             Self::UpdateVersionParseError { .. } => NextestExitCode::SETUP_ERROR,
             Self::DoubleSpawnParseArgsError { .. } | Self::DoubleSpawnExecError { .. } => {
@@ -357,10 +394,12 @@ impl ExpectedError {
                 NextestExitCode::BUILD_FAILED
             }
             Self::TestRunFailed => NextestExitCode::TEST_RUN_FAILED,
+            Self::TestRunFlaky { .. } => NextestExitCode::TEST_RUN_FLAKY,
+            Self::PreFlightError { .. } => NextestExitCode::PRE_FLIGHT_FAILED,
             Self::ArchiveCreateError { .. } => NextestExitCode::ARCHIVE_CREATION_FAILED,
-            Self::WriteTestListError { .. } | Self::WriteEventError { .. } => {
-                NextestExitCode::WRITE_OUTPUT_ERROR
-            }
+            Self::WriteTestListError { .. }
+            | Self::WriteEventError { .. }
+            | Self::RunSummaryWriteError { .. } => NextestExitCode::WRITE_OUTPUT_ERROR,
             #[cfg(feature = "self-update")]
             Self::UpdateError { .. } => NextestExitCode::UPDATE_ERROR,
             Self::ExperimentalFeatureNotEnabled { .. } => {
@@ -392,6 +431,10 @@ impl ExpectedError {
                 log::error!("{}", err);
                 err.source()
             }
+            Self::SuiteNotFound { err } => {
+                log::error!("{}", err);
+                err.source()
+            }
             Self::RootManifestNotFound {
                 path,
                 reuse_build_kind,
@@ -446,6 +489,88 @@ impl ExpectedError {
                         }
                         None
                     }
+                    ConfigParseErrorKind::SuiteError(errors) => {
+                        // Suite errors are printed out using miette, like override errors.
+                        for suite_error in errors {
+                            log::error!(
+                                "for config file `{}`{}, failed to parse test suite: {}",
+                                err.config_file(),
+                                provided_by_tool(err.tool()),
+                                suite_error
+                                    .profile_name
+                                    .if_supports_color(Stream::Stderr, |p| p.bold()),
+                            );
+                            for report in suite_error.reports() {
+                                log::error!(target: "cargo_nextest::no_heading", "{report:?}");
+                            }
+                        }
+                        None
+                    }
+                    ConfigParseErrorKind::QuarantineError(errors) => {
+                        for quarantine_error in errors {
+                            log::error!(
+                                "for config file `{}`{}, failed to parse quarantine list for profile: {}",
+                                err.config_file(),
+                                provided_by_tool(err.tool()),
+                                quarantine_error
+                                    .profile_name
+                                    .if_supports_color(Stream::Stderr, |p| p.bold()),
+                            );
+                            for report in quarantine_error.reports() {
+                                log::error!(target: "cargo_nextest::no_heading", "{report:?}");
+                            }
+                        }
+                        None
+                    }
+                    ConfigParseErrorKind::DefaultFilterError(errors) => {
+                        for default_filter_error in errors {
+                            log::error!(
+                                "for config file `{}`{}, failed to parse default filter for profile: {}",
+                                err.config_file(),
+                                provided_by_tool(err.tool()),
+                                default_filter_error
+                                    .profile_name
+                                    .if_supports_color(Stream::Stderr, |p| p.bold()),
+                            );
+                            for report in default_filter_error.reports() {
+                                log::error!(target: "cargo_nextest::no_heading", "{report:?}");
+                            }
+                        }
+                        None
+                    }
+                    ConfigParseErrorKind::TestGroupMatchError(errors) => {
+                        for match_error in errors {
+                            log::error!(
+                                "for config file `{}`{}, failed to parse match filter for test group: {}",
+                                err.config_file(),
+                                provided_by_tool(err.tool()),
+                                match_error
+                                    .profile_name
+                                    .if_supports_color(Stream::Stderr, |p| p.bold()),
+                            );
+                            for report in match_error.reports() {
+                                log::error!(target: "cargo_nextest::no_heading", "{report:?}");
+                            }
+                        }
+                        None
+                    }
+                    ConfigParseErrorKind::FilterSetError(FilterSetsError::ParseError {
+                        name,
+                        errors,
+                    }) => {
+                        log::error!(
+                            "for config file `{}`{}, failed to parse filter set: {}",
+                            err.config_file(),
+                            provided_by_tool(err.tool()),
+                            name.if_supports_color(Stream::Stderr, |p| p.bold()),
+                        );
+                        for single_error in &errors.errors {
+                            let report = miette::Report::new(single_error.clone())
+                                .with_source_code(errors.input.to_owned());
+                            log::error!(target: "cargo_nextest::no_heading", "{report:?}");
+                        }
+                        None
+                    }
                     ConfigParseErrorKind::UnknownTestGroups {
                         errors,
                         known_groups,
@@ -600,18 +725,69 @@ impl ExpectedError {
                 log::error!("{err}");
                 err.source()
             }
+            Self::PreFlightError { err } => match err {
+                PreFlightError::Failed {
+                    command,
+                    exit_status,
+                    stdout,
+                    stderr,
+                } => {
+                    log::error!(
+                        "pre-flight command `{}` failed with {}, not running tests",
+                        command.if_supports_color(Stream::Stderr, |x| x.bold()),
+                        exit_status,
+                    );
+                    for (name, output) in [("stdout", stdout), ("stderr", stderr)] {
+                        if !output.is_empty() {
+                            log::error!(
+                                target: "cargo_nextest::no_heading",
+                                "--- {name} ---\n{}",
+                                String::from_utf8_lossy(output).trim_end()
+                            );
+                        }
+                    }
+                    None
+                }
+                _ => {
+                    log::error!("{err}");
+                    err.source()
+                }
+            },
             Self::WriteTestListError { err } => {
                 log::error!("failed to write test list to output");
                 Some(err as &dyn Error)
             }
+            Self::ReadTimingsError { err } => {
+                log::error!("{}", err);
+                err.source()
+            }
             Self::WriteEventError { err } => {
                 log::error!("failed to write event to output");
                 Some(err as &dyn Error)
             }
+            Self::RunSummaryWriteError { file_name, err } => {
+                log::error!(
+                    "failed to write run summary to `{}`",
+                    file_name.if_supports_color(Stream::Stderr, |x| x.bold())
+                );
+                Some(err as &dyn Error)
+            }
             Self::TestRunFailed => {
                 log::error!("test run failed");
                 None
             }
+            Self::TestRunFlaky { flaky } => {
+                let tests_str = if *flaky == 1 {
+                    "test was"
+                } else {
+                    "tests were"
+                };
+                log::error!(
+                    "{flaky} {tests_str} flaky, and {} is enabled",
+                    "flaky-as-failure".if_supports_color(Stream::Stderr, |x| x.bold()),
+                );
+                None
+            }
             Self::ShowTestGroupsError { err } => {
                 log::error!("{err}");
                 err.source()
@@ -633,10 +809,6 @@ impl ExpectedError {
                 log::error!("error reading input prompt");
                 Some(err as &dyn Error)
             }
-            Self::SignalHandlerSetupError { err } => {
-                log::error!("error setting up signal handler");
-                Some(err as &dyn Error)
-            }
             Self::ExperimentalFeatureNotEnabled { name, var_name } => {
                 log::error!(
                     "{} is an experimental feature and must be enabled with {}=1",
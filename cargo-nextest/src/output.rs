@@ -6,7 +6,7 @@ use clap::{Args, ValueEnum};
 use env_logger::fmt::Formatter;
 use log::{Level, LevelFilter, Record};
 use miette::{GraphicalTheme, MietteHandlerOpts, ThemeStyles};
-use nextest_runner::reporter::ReporterStderr;
+use nextest_runner::reporter::{ColorMode, ReporterStderr, TestReporter, TestReporterBuilder};
 use owo_colors::{style, OwoColorize, Style};
 use std::{
     io::{BufWriter, Stderr, Stdout, Write},
@@ -115,6 +115,32 @@ impl Color {
         }
     }
 
+    /// Sets up colorization before a test reporter is built.
+    ///
+    /// With `--color=auto`, the profile's `color` setting is used.
+    pub(crate) fn configure_reporter(self, builder: &mut TestReporterBuilder) {
+        builder.set_stderr_supports_color(
+            supports_color::on_cached(supports_color::Stream::Stderr).is_some(),
+        );
+        match self {
+            Color::Auto => {}
+            Color::Always => {
+                builder.set_color(ColorMode::Always);
+            }
+            Color::Never => {
+                builder.set_color(ColorMode::Never);
+            }
+        }
+    }
+
+    /// Finishes setting up colorization after a test reporter is built.
+    pub(crate) fn finish_reporter(self, reporter: &mut TestReporter<'_>) {
+        // An explicit --color=always is a per-invocation choice, so it takes priority over NO_COLOR.
+        if self == Color::Always {
+            reporter.colorize();
+        }
+    }
+
     pub(crate) fn to_arg(self) -> &'static str {
         match self {
             Color::Auto => "--color=auto",
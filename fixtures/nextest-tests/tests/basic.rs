@@ -66,6 +66,18 @@ fn test_cwd() {
     assert_eq!(runtime_cwd, compile_time_cwd, "current dir matches");
 }
 
+#[test]
+fn test_relative_cwd() {
+    // The working directory for this test is set to tests/fixtures through an override in this
+    // fixture's config.
+    let contents = std::fs::read_to_string("cwd-marker.txt")
+        .expect("cwd-marker.txt should be readable from the current dir");
+    assert_eq!(
+        contents.trim_end(),
+        "this file is read by test_relative_cwd"
+    );
+}
+
 #[test]
 #[ignore]
 fn test_ignored() {}
@@ -137,6 +149,23 @@ fn test_cargo_env_vars() {
         Ok("process-per-test"),
         "NEXTEST_EXECUTION_MODE set to process-per-test"
     );
+    // TERM is set through an override in this fixture's config, and the locale is canonical.
+    assert_eq!(
+        std::env::var("TERM").as_deref(),
+        Ok("nextest-fixture-term"),
+        "TERM set through override"
+    );
+    assert_eq!(
+        std::env::var("LANG").as_deref(),
+        Ok("C.UTF-8"),
+        "LANG set to canonical value"
+    );
+    assert_eq!(
+        std::env::var("LC_ALL").as_deref(),
+        Ok("C.UTF-8"),
+        "LC_ALL set to canonical value"
+    );
+
     // https://doc.rust-lang.org/cargo/reference/environment-variables.html#environment-variables-cargo-sets-for-crates
     assert_env!("CARGO");
     assert_env!(
@@ -283,6 +312,31 @@ fn test_subprocess_doesnt_exit() {
     cmd.spawn().unwrap();
 }
 
+#[test]
+fn test_subprocess_writes_after_exit() {
+    // Note: this is synchronized with the with-output-grace-period profile in the main nextest
+    // repo. The child process inherits stdout, and writes to it about a second after this test
+    // exits.
+    trailing_output_cmd("trailing output from subprocess")
+        .spawn()
+        .unwrap();
+}
+
+#[cfg(windows)]
+fn trailing_output_cmd(message: &str) -> std::process::Command {
+    let mut cmd = std::process::Command::new("cmd");
+    // See sleep_cmd for why ping is used to sleep.
+    cmd.args(["/c", &format!("ping /n 2 127.0.0.1 >nul & echo {message}")]);
+    cmd
+}
+
+#[cfg(unix)]
+fn trailing_output_cmd(message: &str) -> std::process::Command {
+    let mut cmd = std::process::Command::new("sh");
+    cmd.args(["-c", &format!("sleep 1; echo '{message}'")]);
+    cmd
+}
+
 #[cfg(windows)]
 fn sleep_cmd(secs: usize) -> std::process::Command {
     // Apparently, this is the most reliable way to sleep for a bit on Windows.
@@ -314,3 +368,14 @@ fn test_stdin_closed() {
             .expect("reading from /dev/null succeeded")
     );
 }
+
+#[test]
+fn test_stdin_read_to_end() {
+    // By default this reads nothing, since stdin is attached to /dev/null. With `stdin =
+    // "inherit"`, it reads whatever nextest's standard input contains.
+    let mut input = String::new();
+    std::io::stdin()
+        .read_to_string(&mut input)
+        .expect("reading stdin to the end succeeded");
+    println!("read {} bytes from stdin: {:?}", input.len(), input);
+}
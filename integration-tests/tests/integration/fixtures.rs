@@ -58,12 +58,15 @@ pub static EXPECTED_LIST: Lazy<Vec<TestInfo>> = Lazy::new(|| {
                 ("test_flaky_mod_6", false),
                 ("test_ignored", true),
                 ("test_ignored_fail", true),
+                ("test_relative_cwd", false),
                 ("test_result_failure", false),
                 ("test_slow_timeout", true),
                 ("test_slow_timeout_2", true),
                 ("test_slow_timeout_subprocess", true),
                 ("test_stdin_closed", false),
+                ("test_stdin_read_to_end", false),
                 ("test_subprocess_doesnt_exit", false),
+                ("test_subprocess_writes_after_exit", false),
                 ("test_success", false),
                 ("test_success_should_panic", false),
             ],
@@ -389,6 +392,7 @@ pub fn check_run_output(stderr: &[u8], relocated: bool) {
         (false, "nextest-tests::basic test_failure_should_panic"),
         (true, "nextest-tests::bin/other tests::other_bin_success"),
         (false, "nextest-tests::basic test_result_failure"),
+        (true, "nextest-tests::basic test_relative_cwd"),
         (true, "nextest-tests::basic test_success_should_panic"),
         (false, "nextest-tests::basic test_failure_assert"),
         (true, "nextest-tests::basic test_stdin_closed"),
@@ -415,10 +419,10 @@ pub fn check_run_output(stderr: &[u8], relocated: bool) {
     }
 
     let summary_reg = if relocated {
-        Regex::new(r"Summary \[.*\] *26 tests run: 18 passed \(1 leaky\), 8 failed, 5 skipped")
+        Regex::new(r"Summary \[.*\] *29 tests run: 21 passed \(2 leaky\), 8 failed, 5 skipped")
             .unwrap()
     } else {
-        Regex::new(r"Summary \[.*\] *26 tests run: 19 passed \(1 leaky\), 7 failed, 5 skipped")
+        Regex::new(r"Summary \[.*\] *29 tests run: 22 passed \(2 leaky\), 7 failed, 5 skipped")
             .unwrap()
     };
     assert!(
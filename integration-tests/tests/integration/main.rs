@@ -24,6 +24,7 @@
 
 use camino::{Utf8Path, Utf8PathBuf};
 use nextest_metadata::{BuildPlatform, NextestExitCode};
+use regex::Regex;
 
 mod fixtures;
 mod temp_project;
@@ -393,6 +394,78 @@ fn test_run_from_archive() {
     check_run_output(&output.stderr, true);
 }
 
+#[test]
+fn test_run_known_failures() {
+    set_env_vars();
+
+    let p = TempProject::new().unwrap();
+    let summary_path = p.temp_root().join("run-summary.json");
+
+    // Record a baseline in which test_failure_assert fails.
+    let output = CargoNextestCli::new()
+        .args([
+            "--manifest-path",
+            p.manifest_path().as_str(),
+            "run",
+            "--workspace",
+            "--all-targets",
+            "-E",
+            "test(=test_failure_assert)",
+            "--write-run-summary",
+            summary_path.as_str(),
+        ])
+        .unchecked(true)
+        .output();
+    assert_eq!(
+        output.exit_code,
+        Some(NextestExitCode::TEST_RUN_FAILED),
+        "correct exit code for command\n{output}"
+    );
+    assert!(summary_path.is_file(), "run summary was written");
+
+    // With a single thread, test_failure_assert runs before test_success, so fail-fast cancels
+    // the run before test_success unless test_failure_assert is a known failure.
+    let run_with_fail_fast = |known_failures: Option<&Utf8Path>| {
+        let mut cli = CargoNextestCli::new();
+        cli.args([
+            "--manifest-path",
+            p.manifest_path().as_str(),
+            "run",
+            "--workspace",
+            "--all-targets",
+            "-E",
+            "test(=test_failure_assert) | test(=test_success)",
+            "--fail-fast",
+            "--test-threads",
+            "1",
+        ]);
+        if let Some(known_failures) = known_failures {
+            cli.args(["--known-failures", known_failures.as_str()]);
+        }
+        let output = cli.unchecked(true).output();
+        assert_eq!(
+            output.exit_code,
+            Some(NextestExitCode::TEST_RUN_FAILED),
+            "known failures still fail the run\n{output}"
+        );
+        String::from_utf8(output.stderr).unwrap()
+    };
+
+    let stderr = run_with_fail_fast(None);
+    let summary_reg = Regex::new(r"Summary \[.*\] *1/2 tests run: 0 passed, 1 failed").unwrap();
+    assert!(
+        summary_reg.is_match(&stderr),
+        "run is canceled without known failures (actual output: {stderr})"
+    );
+
+    let stderr = run_with_fail_fast(Some(&summary_path));
+    let summary_reg = Regex::new(r"Summary \[.*\] *2 tests run: 1 passed, 1 failed").unwrap();
+    assert!(
+        summary_reg.is_match(&stderr),
+        "known failure doesn't cancel the run (actual output: {stderr})"
+    );
+}
+
 #[test]
 fn test_show_config_test_groups() {
     set_env_vars();
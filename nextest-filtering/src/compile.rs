@@ -5,6 +5,7 @@ use crate::{
     errors::ParseSingleError,
     expression::*,
     parsing::{ParsedExpr, SetDef},
+    FilterSets,
 };
 use guppy::{
     graph::{DependsCache, PackageGraph, PackageMetadata},
@@ -14,9 +15,15 @@ use miette::SourceSpan;
 use recursion::Collapse;
 use std::collections::HashSet;
 
+/// The kinds of test binaries that nextest can run, as computed while building the binary list.
+///
+/// Cargo library crate types (`rlib`, `cdylib` etc) are all treated as `lib`.
+const BINARY_KINDS: &[&str] = &["lib", "test", "bench", "bin", "example", "proc-macro"];
+
 pub(crate) fn compile(
     expr: &ParsedExpr,
     graph: &PackageGraph,
+    filter_sets: &FilterSets,
 ) -> Result<CompiledExpr, Vec<ParseSingleError>> {
     let in_workspace_packages: Vec<_> = graph
         .resolve_workspace()
@@ -24,7 +31,13 @@ pub(crate) fn compile(
         .collect();
     let mut cache = graph.new_depends_cache();
     let mut errors = vec![];
-    let expr = compile_expr(expr, &in_workspace_packages, &mut cache, &mut errors);
+    let expr = compile_expr(
+        expr,
+        &in_workspace_packages,
+        &mut cache,
+        filter_sets,
+        &mut errors,
+    );
 
     if errors.is_empty() {
         Ok(expr)
@@ -90,9 +103,10 @@ fn compile_set_def(
     set: &SetDef,
     packages: &[PackageMetadata],
     cache: &mut DependsCache,
+    filter_sets: &FilterSets,
     errors: &mut Vec<ParseSingleError>,
-) -> FilteringSet {
-    match set {
+) -> CompiledExpr {
+    let set = match set {
         SetDef::Package(matcher, span) => FilteringSet::Packages(expect_non_empty(
             matching_packages(matcher, packages),
             *span,
@@ -108,13 +122,30 @@ fn compile_set_def(
             *span,
             errors,
         )),
-        SetDef::Kind(matcher, span) => FilteringSet::Kind(matcher.clone(), *span),
+        SetDef::Kind(matcher, span) => {
+            // A typo like `kind(benches)` would otherwise silently match nothing.
+            if !BINARY_KINDS.iter().any(|kind| matcher.is_match(kind)) {
+                errors.push(ParseSingleError::NoBinaryKindMatch(*span));
+            }
+            FilteringSet::Kind(matcher.clone(), *span)
+        }
         SetDef::Binary(matcher, span) => FilteringSet::Binary(matcher.clone(), *span),
         SetDef::Platform(platform, span) => FilteringSet::Platform(*platform, *span),
         SetDef::Test(matcher, span) => FilteringSet::Test(matcher.clone(), *span),
+        SetDef::Named(name, span) => {
+            // Named sets are substituted with their already-compiled expressions.
+            return match filter_sets.get(name) {
+                Some(expr) => expr.compiled.clone(),
+                None => {
+                    errors.push(ParseSingleError::UnknownFilterSet(*span));
+                    CompiledExpr::Set(FilteringSet::None)
+                }
+            };
+        }
         SetDef::All => FilteringSet::All,
         SetDef::None => FilteringSet::None,
-    }
+    };
+    CompiledExpr::Set(set)
 }
 
 fn expect_non_empty(
@@ -132,11 +163,12 @@ fn compile_expr(
     expr: &ParsedExpr,
     packages: &[PackageMetadata],
     cache: &mut DependsCache,
+    filter_sets: &FilterSets,
     errors: &mut Vec<ParseSingleError>,
 ) -> CompiledExpr {
     use crate::expression::ExprLayer::*;
     Wrapped(expr).collapse_layers(|layer: ExprLayer<&SetDef, CompiledExpr>| match layer {
-        Set(set) => CompiledExpr::Set(compile_set_def(set, packages, cache, errors)),
+        Set(set) => compile_set_def(set, packages, cache, filter_sets, errors),
         Not(expr) => CompiledExpr::Not(Box::new(expr)),
         Union(expr_1, expr_2) => CompiledExpr::Union(Box::new(expr_1), Box::new(expr_2)),
         Intersection(expr_1, expr_2) => {
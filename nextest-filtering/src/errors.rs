@@ -26,6 +26,28 @@ impl FilterExpressionParseErrors {
     }
 }
 
+/// An error that occurred while resolving named filter sets.
+#[derive(Clone, Debug, Error)]
+#[non_exhaustive]
+pub enum FilterSetsError {
+    /// The expression for a filter set failed to parse.
+    #[error("failed to parse filter set `{name}`")]
+    ParseError {
+        /// The name of the filter set.
+        name: String,
+
+        /// The expression, and the errors that occurred.
+        errors: FilterExpressionParseErrors,
+    },
+
+    /// Filter sets refer to each other in a cycle.
+    #[error("filter sets refer to each other in a cycle: {}", .cycle.join(" -> "))]
+    Cycle {
+        /// The names of the sets in the cycle, starting and ending with the same set.
+        cycle: Vec<String>,
+    },
+}
+
 /// An individual error that occurred while parsing a filter expression.
 #[derive(Clone, Debug, Error, Diagnostic, PartialEq, Eq)]
 #[non_exhaustive]
@@ -81,6 +103,19 @@ pub enum ParseSingleError {
     #[error("operator didn't match any packages")]
     NoPackageMatch(#[label("no packages matched this")] SourceSpan),
 
+    /// This matcher didn't match any binary kinds.
+    #[error("operator didn't match any binary kinds")]
+    NoBinaryKindMatch(
+        #[label(
+            "expected one of \"lib\", \"test\", \"bench\", \"bin\", \"example\" or \"proc-macro\""
+        )]
+        SourceSpan,
+    ),
+
+    /// No filter set with this name is defined.
+    #[error("unknown filter set")]
+    UnknownFilterSet(#[label("no filter set with this name is defined")] SourceSpan),
+
     /// Expected "host" or "target" for a `platform()` predicate.
     #[error("invalid argument for platform")]
     InvalidPlatformArgument(#[label("expected \"target\" or \"host\"")] SourceSpan),
@@ -6,6 +6,7 @@ use crate::{
     parsing::{
         parse, DisplayParsedRegex, DisplayParsedString, ExprResult, ParsedExpr, SetDef, Span,
     },
+    FilterSets,
 };
 use guppy::{
     graph::{cargo::BuildPlatform, PackageGraph},
@@ -22,6 +23,7 @@ use std::{cell::RefCell, collections::HashSet, fmt};
 ///
 /// Used both for package name and test name
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum NameMatcher {
     /// Exact value
     Equal { value: String, implicit: bool },
@@ -29,6 +31,13 @@ pub enum NameMatcher {
     Contains { value: String, implicit: bool },
     /// Test against a regex
     Regex(regex::Regex),
+    /// Test against a glob, which must match the entire name
+    Glob {
+        /// The glob as written.
+        glob: String,
+        /// The regex the glob is translated to.
+        regex: regex::Regex,
+    },
 }
 
 impl NameMatcher {
@@ -45,6 +54,30 @@ impl NameMatcher {
             implicit: true,
         }
     }
+
+    /// Creates a glob matcher. `*` matches any sequence of characters (including none), `?`
+    /// matches any single character, a backslash matches the character after it literally, and
+    /// all other characters match themselves.
+    pub(crate) fn glob(glob: String) -> Self {
+        let mut regex = String::with_capacity(glob.len() + 2);
+        regex.push('^');
+        let mut chars = glob.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '*' => regex.push_str(".*"),
+                '?' => regex.push('.'),
+                // A trailing backslash matches itself.
+                '\\' => {
+                    let c = chars.next().unwrap_or('\\');
+                    regex.push_str(&regex::escape(c.encode_utf8(&mut [0; 4])));
+                }
+                c => regex.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+            }
+        }
+        regex.push('$');
+        let regex = regex::Regex::new(&regex).expect("regexes translated from globs are valid");
+        Self::Glob { glob, regex }
+    }
 }
 
 impl PartialEq for NameMatcher {
@@ -71,6 +104,7 @@ impl PartialEq for NameMatcher {
                 },
             ) => s1 == s2 && default1 == default2,
             (Self::Regex(r1), Self::Regex(r2)) => r1.as_str() == r2.as_str(),
+            (Self::Glob { glob: g1, .. }, Self::Glob { glob: g2, .. }) => g1 == g2,
             _ => false,
         }
     }
@@ -94,6 +128,11 @@ impl fmt::Display for NameMatcher {
                 DisplayParsedString(value)
             ),
             Self::Regex(r) => write!(f, "/{}/", DisplayParsedRegex(r)),
+            Self::Glob { glob, .. } => match glob.strip_prefix(':') {
+                // `glob::` is parsed as the start of a name, so escape a leading colon.
+                Some(rest) => write!(f, "glob:\\u{{3a}}{}", DisplayParsedString(rest)),
+                None => write!(f, "glob:{}", DisplayParsedString(glob)),
+            },
         }
     }
 }
@@ -176,6 +215,7 @@ impl NameMatcher {
             Self::Equal { value, .. } => value == input,
             Self::Contains { value, .. } => input.contains(value),
             Self::Regex(reg) => reg.is_match(input),
+            Self::Glob { regex, .. } => regex.is_match(input),
         }
     }
 }
@@ -209,6 +249,16 @@ impl FilteringSet {
 impl FilteringExpr {
     /// Parse a filtering expression
     pub fn parse(input: String, graph: &PackageGraph) -> Result<Self, FilterExpressionParseErrors> {
+        Self::parse_with_sets(input, graph, &FilterSets::default())
+    }
+
+    /// Parse a filtering expression, resolving references to named sets with `set(name)` against
+    /// `filter_sets`.
+    pub fn parse_with_sets(
+        input: String,
+        graph: &PackageGraph,
+        filter_sets: &FilterSets,
+    ) -> Result<Self, FilterExpressionParseErrors> {
         let errors = RefCell::new(Vec::new());
         match parse(Span::new_extra(&input, State::new(&errors))) {
             Ok(parsed_expr) => {
@@ -220,8 +270,8 @@ impl FilteringExpr {
 
                 match parsed_expr {
                     ExprResult::Valid(parsed) => {
-                        let compiled =
-                            crate::compile::compile(&parsed, graph).map_err(|errors| {
+                        let compiled = crate::compile::compile(&parsed, graph, filter_sets)
+                            .map_err(|errors| {
                                 FilterExpressionParseErrors::new(input.clone(), errors)
                             })?;
                         Ok(Self {
@@ -291,8 +341,9 @@ impl FilteringExpr {
 
     /// Returns true if the given expression needs dependencies information to work
     pub fn needs_deps(raw_expr: &str) -> bool {
-        // the expression needs dependencies expression if it uses deps(..) or rdeps(..)
-        raw_expr.contains("deps")
+        // the expression needs dependencies expression if it uses deps(..) or rdeps(..), or
+        // refers to a named set with set(..) which might use them
+        raw_expr.contains("deps") || raw_expr.contains("set")
     }
 }
 
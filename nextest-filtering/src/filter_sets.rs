@@ -0,0 +1,140 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    errors::{FilterExpressionParseErrors, FilterSetsError},
+    expression::{ExprLayer, Wrapped},
+    parsing::{ParsedExpr, SetDef},
+    FilteringExpr,
+};
+use guppy::graph::PackageGraph;
+use recursion::Collapse;
+use std::collections::BTreeMap;
+
+/// A collection of named filter expressions, which other filter expressions can refer to with
+/// `set(name)`.
+///
+/// Filter sets can refer to each other, as long as they don't form a cycle.
+#[derive(Clone, Debug, Default)]
+pub struct FilterSets {
+    sets: BTreeMap<String, FilteringExpr>,
+}
+
+impl FilterSets {
+    /// Parses and resolves the given filter set definitions, as pairs of `(name, expression)`.
+    ///
+    /// If a name is defined more than once, the last definition wins.
+    pub fn new(
+        definitions: impl IntoIterator<Item = (String, String)>,
+        graph: &PackageGraph,
+    ) -> Result<Self, FilterSetsError> {
+        let definitions: BTreeMap<_, _> = definitions.into_iter().collect();
+
+        // Parse all the definitions first, so that references between sets can be followed before
+        // any of them are compiled.
+        let mut parsed = BTreeMap::new();
+        for (name, input) in definitions {
+            match ParsedExpr::parse(&input) {
+                Ok(expr) => {
+                    parsed.insert(name, (input, expr));
+                }
+                Err(errors) => {
+                    return Err(FilterSetsError::ParseError {
+                        name,
+                        errors: FilterExpressionParseErrors::new(input, errors),
+                    });
+                }
+            }
+        }
+
+        let mut resolver = Resolver {
+            parsed: &parsed,
+            graph,
+            resolved: Self::default(),
+            stack: Vec::new(),
+        };
+        for name in parsed.keys() {
+            resolver.resolve(name)?;
+        }
+
+        Ok(resolver.resolved)
+    }
+
+    /// Returns the filter set with the given name, if it's defined.
+    pub fn get(&self, name: &str) -> Option<&FilteringExpr> {
+        self.sets.get(name)
+    }
+
+    /// Returns the names of all the filter sets, in sorted order.
+    pub fn names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.sets.keys().map(|name| name.as_str())
+    }
+}
+
+struct Resolver<'a, 'g> {
+    parsed: &'a BTreeMap<String, (String, ParsedExpr)>,
+    graph: &'g PackageGraph,
+    resolved: FilterSets,
+    // The sets currently being resolved, used to detect cycles.
+    stack: Vec<&'a str>,
+}
+
+impl<'a, 'g> Resolver<'a, 'g> {
+    fn resolve(&mut self, name: &'a str) -> Result<(), FilterSetsError> {
+        if self.resolved.sets.contains_key(name) {
+            return Ok(());
+        }
+        if let Some(pos) = self.stack.iter().position(|n| *n == name) {
+            let cycle = self.stack[pos..]
+                .iter()
+                .chain(std::iter::once(&name))
+                .map(|n| (*n).to_owned())
+                .collect();
+            return Err(FilterSetsError::Cycle { cycle });
+        }
+
+        let all_parsed = self.parsed;
+        let (input, parsed) = &all_parsed[name];
+
+        // Resolve the sets this one refers to first. References to unknown sets are reported
+        // while compiling.
+        self.stack.push(name);
+        for referenced in referenced_sets(parsed) {
+            if let Some((referenced, _)) = all_parsed.get_key_value(referenced) {
+                self.resolve(referenced)?;
+            }
+        }
+        self.stack.pop();
+
+        let compiled =
+            crate::compile::compile(parsed, self.graph, &self.resolved).map_err(|errors| {
+                FilterSetsError::ParseError {
+                    name: name.to_owned(),
+                    errors: FilterExpressionParseErrors::new(input.clone(), errors),
+                }
+            })?;
+        self.resolved.sets.insert(
+            name.to_owned(),
+            FilteringExpr {
+                input: input.clone(),
+                parsed: parsed.clone(),
+                compiled,
+            },
+        );
+        Ok(())
+    }
+}
+
+/// Returns the names of the filter sets referred to by `expr`.
+fn referenced_sets(expr: &ParsedExpr) -> Vec<&str> {
+    use ExprLayer::*;
+    Wrapped(expr).collapse_layers(|layer: ExprLayer<&SetDef, Vec<&str>>| match layer {
+        Set(SetDef::Named(name, _)) => vec![name.as_str()],
+        Set(_) => Vec::new(),
+        Not(a) | Parens(a) => a,
+        Union(mut a, b) | Intersection(mut a, b) | Difference(mut a, b) => {
+            a.extend(b);
+            a
+        }
+    })
+}
@@ -7,6 +7,7 @@
 mod compile;
 pub mod errors;
 mod expression;
+mod filter_sets;
 mod parsing;
 #[cfg(any(test, feature = "internal-testing"))]
 mod proptest_helpers;
@@ -14,4 +15,5 @@ mod proptest_helpers;
 pub use expression::{
     BinaryQuery, CompiledExpr, FilteringExpr, FilteringSet, NameMatcher, TestQuery,
 };
+pub use filter_sets::FilterSets;
 pub use parsing::ParsedExpr;
@@ -19,7 +19,7 @@ use nom::{
     branch::alt,
     bytes::complete::{is_not, tag, take_till},
     character::complete::{char, line_ending},
-    combinator::{eof, map, peek, recognize, value, verify},
+    combinator::{eof, map, not, peek, recognize, value, verify},
     multi::{fold_many0, many0},
     sequence::{delimited, pair, preceded, terminated},
     Slice,
@@ -53,6 +53,7 @@ pub enum SetDef<S = SourceSpan> {
     Binary(NameMatcher, S),
     Platform(BuildPlatform, S),
     Test(NameMatcher, S),
+    Named(String, S),
     All,
     None,
 }
@@ -67,6 +68,7 @@ impl SetDef {
             Self::Binary(matcher, _) => SetDef::Binary(matcher, ()),
             Self::Platform(platform, _) => SetDef::Platform(platform, ()),
             Self::Test(matcher, _) => SetDef::Test(matcher, ()),
+            Self::Named(name, _) => SetDef::Named(name, ()),
             Self::All => SetDef::All,
             Self::None => SetDef::None,
         }
@@ -83,6 +85,7 @@ impl<S> fmt::Display for SetDef<S> {
             Self::Binary(matcher, _) => write!(f, "binary({matcher})"),
             Self::Platform(platform, _) => write!(f, "platform({platform})"),
             Self::Test(matcher, _) => write!(f, "test({matcher})"),
+            Self::Named(name, _) => write!(f, "set({})", DisplayParsedString(name)),
             Self::All => write!(f, "all()"),
             Self::None => write!(f, "none()"),
         }
@@ -348,6 +351,15 @@ fn parse_equal_matcher(input: Span) -> IResult<Option<NameMatcher>> {
     ))(input)
 }
 
+// `glob::` isn't a prefix, so that names starting with a module called `glob` keep their meaning.
+#[tracable_parser]
+fn parse_glob_matcher(input: Span) -> IResult<Option<NameMatcher>> {
+    ws(map(
+        preceded(terminated(tag("glob:"), not(char(':'))), parse_matcher_text),
+        |res: Option<String>| res.map(NameMatcher::glob),
+    ))(input)
+}
+
 // This parse will never fail
 fn default_matcher(
     make: fn(String) -> NameMatcher,
@@ -451,6 +463,7 @@ fn set_matcher(
             parse_regex_matcher,
             parse_equal_matcher,
             parse_contains_matcher,
+            parse_glob_matcher,
             default_matcher(make),
         )))(input)
     }
@@ -546,6 +559,23 @@ fn platform_def(i: Span) -> IResult<Option<SetDef>> {
     ))
 }
 
+fn named_set_def(i: Span) -> IResult<Option<SetDef>> {
+    let (i, _) = tag("set")(i)?;
+    let (i, _) = expect_char('(', ParseSingleError::ExpectedOpenParenthesis)(i)?;
+    let start = i.location_offset();
+    let (i, res) = ws(parse_matcher_text)(i)?;
+    let end = i.location_offset();
+    let (i, _) = recover_unexpected_comma(i)?;
+    let (i, _) = expect_char(')', ParseSingleError::ExpectedCloseParenthesis)(i)?;
+
+    // The returned string will include leading and trailing whitespace. An empty name was already
+    // reported by parse_matcher_text.
+    Ok((
+        i,
+        res.map(|name| SetDef::Named(name.trim().to_owned(), (start, end - start).into())),
+    ))
+}
+
 #[tracable_parser]
 fn parse_set_def(input: Span) -> IResult<Option<SetDef>> {
     ws(alt((
@@ -556,6 +586,7 @@ fn parse_set_def(input: Span) -> IResult<Option<SetDef>> {
         unary_set_def("binary", NameMatcher::implicit_equal, SetDef::Binary),
         unary_set_def("test", NameMatcher::implicit_contains, SetDef::Test),
         platform_def,
+        named_set_def,
         nullary_set_def("all", || SetDef::All),
         nullary_set_def("none", || SetDef::None),
     )))(input)
@@ -866,6 +897,34 @@ mod tests {
             Test,
             NameMatcher::Regex(regex::Regex::new("some.*").unwrap())
         );
+        assert_set_def!(
+            parse_set("binary(glob:some*)"),
+            Binary,
+            NameMatcher::glob("some*".to_string())
+        );
+        assert_set_def!(
+            parse_set(r"test(glob:\u{3a}some*)"),
+            Test,
+            NameMatcher::glob(":some*".to_string())
+        );
+
+        // `#` and `glob::` aren't glob prefixes, so these are default matchers.
+        assert_set_def!(
+            parse_set("test(#some*)"),
+            Test,
+            NameMatcher::Contains {
+                value: "#some*".to_string(),
+                implicit: true,
+            }
+        );
+        assert_set_def!(
+            parse_set("test(glob::some)"),
+            Test,
+            NameMatcher::Contains {
+                value: "glob::some".to_string(),
+                implicit: true,
+            }
+        );
 
         // Default matchers
         assert_set_def!(
@@ -918,6 +977,14 @@ mod tests {
                 implicit: false,
             }
         );
+        assert_set_def!(
+            parse_set("test(~glob:something)"),
+            Test,
+            NameMatcher::Contains {
+                value: "glob:something".to_string(),
+                implicit: false,
+            }
+        );
 
         // Explicit equals matching.
         assert_set_def!(
@@ -1032,6 +1099,8 @@ mod tests {
             Platform,
             BuildPlatform::Host
         );
+        assert_set_def!(parse_set("set(smoke)"), Named, "smoke");
+        assert_set_def!(parse_set("set( smoke )"), Named, "smoke");
     }
 
     #[track_caller]
@@ -111,6 +111,7 @@ impl SetDef<()> {
             1 => NameMatcher::default_equal_strategy().prop_map(|s| Self::Binary(s, ())),
             1 => build_platform_strategy().prop_map(|p| Self::Platform(p, ())),
             1 => NameMatcher::default_contains_strategy().prop_map(|s| Self::Test(s, ())),
+            1 => "[a-z][a-z0-9_-]{0,10}".prop_map(|name| Self::Named(name, ())),
             1 => Just(Self::All),
             1 => Just(Self::None),
         ]
@@ -135,6 +136,7 @@ impl NameMatcher {
                 Self::Contains { value, implicit: false }
             }),
             1 => regex_strategy().prop_map(Self::Regex),
+            1 => name_strategy().prop_map(Self::glob),
         ]
     }
 
@@ -154,12 +156,17 @@ impl NameMatcher {
                     accept.then_some(Self::Contains { value, implicit })
             }),
             1 => regex_strategy().prop_map(Self::Regex),
+            1 => name_strategy().prop_map(Self::glob),
         ]
     }
 }
 
 fn begins_with_operator(value: &str) -> bool {
-    value.starts_with('=') || value.starts_with('~') || value.starts_with('/')
+    value.starts_with('=')
+        || value.starts_with('~')
+        || value.starts_with('/')
+        // This should match parse_glob_matcher.
+        || (value.starts_with("glob:") && !value.starts_with("glob::"))
 }
 
 pub(crate) fn build_platform_strategy() -> impl Strategy<Value = BuildPlatform> {
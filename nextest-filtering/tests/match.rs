@@ -6,8 +6,8 @@ use guppy::{
     PackageId,
 };
 use nextest_filtering::{
-    errors::{FilterExpressionParseErrors, ParseSingleError},
-    BinaryQuery, FilteringExpr, TestQuery,
+    errors::{FilterExpressionParseErrors, FilterSetsError, ParseSingleError},
+    BinaryQuery, FilterSets, FilteringExpr, TestQuery,
 };
 use test_case::test_case;
 
@@ -32,6 +32,18 @@ fn parse(input: &str, graph: &PackageGraph) -> FilteringExpr {
     expr
 }
 
+fn query<'a>(package_id: &'a PackageId, binary_name: &'a str, test_name: &'a str) -> TestQuery<'a> {
+    TestQuery {
+        binary_query: BinaryQuery {
+            package_id,
+            kind: "test",
+            binary_name,
+            platform: BuildPlatform::Target,
+        },
+        test_name,
+    }
+}
+
 #[test]
 fn test_expr_package_contains() {
     let graph = load_graph();
@@ -380,6 +392,33 @@ fn test_expr_kind() {
     }));
 }
 
+#[test]
+fn test_expr_kind_unknown() {
+    #[track_caller]
+    fn assert_error(errors: &FilterExpressionParseErrors) {
+        assert_eq!(errors.errors.len(), 1);
+        assert!(matches!(
+            errors.errors[0],
+            ParseSingleError::NoBinaryKindMatch(_)
+        ));
+    }
+
+    let graph = load_graph();
+    let errors = FilteringExpr::parse("kind(benches)".to_owned(), &graph).unwrap_err();
+    assert_error(&errors);
+
+    let errors = FilteringExpr::parse("kind(~unit)".to_owned(), &graph).unwrap_err();
+    assert_error(&errors);
+
+    let errors = FilteringExpr::parse("kind(/^integration/)".to_owned(), &graph).unwrap_err();
+    assert_error(&errors);
+
+    // Every known kind is accepted.
+    for kind in ["lib", "test", "bench", "bin", "example", "proc-macro"] {
+        parse(&format!("kind({kind})"), &graph);
+    }
+}
+
 #[test]
 fn test_expr_binary() {
     let graph = load_graph();
@@ -418,6 +457,208 @@ fn test_expr_binary() {
     }));
 }
 
+#[test]
+fn test_expr_binary_glob() {
+    let graph = load_graph();
+    let expr = parse("binary(glob:integration_*)", &graph);
+
+    let pid_a = mk_pid('a');
+    assert!(expr.matches_test(&query(&pid_a, "integration_db", "test_something")));
+    assert!(expr.matches_test(&query(&pid_a, "integration_", "test_something")));
+    // Globs match the entire binary name.
+    assert!(!expr.matches_test(&query(&pid_a, "my_integration_db", "test_something")));
+
+    let expr = parse("binary(glob:int?gration)", &graph);
+    assert!(expr.matches_test(&query(&pid_a, "integration", "test_something")));
+    assert!(!expr.matches_test(&query(&pid_a, "intgration", "test_something")));
+
+    // Characters other than `*` and `?` are matched literally, not as regex syntax.
+    let expr = parse("binary(glob:my.binary)", &graph);
+    assert!(expr.matches_test(&query(&pid_a, "my.binary", "test_something")));
+    assert!(!expr.matches_test(&query(&pid_a, "my-binary", "test_something")));
+}
+
+#[test]
+fn test_expr_test_glob_escapes() {
+    let graph = load_graph();
+    let pid_a = mk_pid('a');
+
+    // A backslash makes the next character match literally. Backslashes are themselves escaped
+    // within filter expressions, so `\\*` in the expression is `\*` in the glob.
+    let expr = parse(r"test(glob:parse\\*)", &graph);
+    assert!(expr.matches_test(&query(&pid_a, "my-binary", "parse*")));
+    assert!(!expr.matches_test(&query(&pid_a, "my-binary", "parse_slow")));
+
+    let expr = parse(r"test(glob:parse\\?)", &graph);
+    assert!(expr.matches_test(&query(&pid_a, "my-binary", "parse?")));
+    assert!(!expr.matches_test(&query(&pid_a, "my-binary", "parse_")));
+
+    let expr = parse(r"test(glob:a\\\\b*)", &graph);
+    assert!(expr.matches_test(&query(&pid_a, "my-binary", r"a\b_slow")));
+
+    // `#` isn't a glob prefix, so this keeps its meaning from before globs were supported.
+    let expr = parse("test(#parse*)", &graph);
+    assert!(expr.matches_test(&query(&pid_a, "my-binary", "tests::#parse*")));
+    assert!(!expr.matches_test(&query(&pid_a, "my-binary", "parse_slow")));
+}
+
+#[test]
+fn test_expr_test_regex() {
+    let graph = load_graph();
+    let pid_a = mk_pid('a');
+
+    // Regexes aren't anchored, so they can match any part of the test name.
+    let expr = parse("test(/_slow/)", &graph);
+    assert!(expr.matches_test(&query(&pid_a, "my-binary", "tests::parse_slow")));
+    assert!(expr.matches_test(&query(&pid_a, "my-binary", "tests::parse_slow_path")));
+    assert!(!expr.matches_test(&query(&pid_a, "my-binary", "tests::parse_fast")));
+
+    // Anchors restrict where the regex can match.
+    let expr = parse("test(/_slow$/)", &graph);
+    assert!(expr.matches_test(&query(&pid_a, "my-binary", "tests::parse_slow")));
+    assert!(!expr.matches_test(&query(&pid_a, "my-binary", "tests::parse_slow_path")));
+}
+
+#[test]
+fn test_expr_invalid_regex() {
+    let graph = load_graph();
+    let errors = FilteringExpr::parse("test(/_slow(/)".to_owned(), &graph).unwrap_err();
+    assert_eq!(errors.errors.len(), 1);
+    match &errors.errors[0] {
+        ParseSingleError::InvalidRegex { span, message } => {
+            // The span points at the unclosed group within the expression.
+            assert_eq!(*span, (11, 1).into(), "span matches");
+            assert_eq!(message, "unclosed group");
+        }
+        other => panic!("expected invalid regex, found {other}"),
+    }
+}
+
+#[test_case("test(/_slow$/) and binary(glob:integration_*)"; "with and")]
+#[test_case("not (not test(/_slow$/) or not binary(glob:integration_*))"; "with or and not")]
+fn test_expr_regex_glob_compose(input: &str) {
+    let graph = load_graph();
+    let expr = parse(input, &graph);
+
+    let pid_a = mk_pid('a');
+    assert!(expr.matches_test(&query(&pid_a, "integration_db", "tests::parse_slow")));
+    assert!(!expr.matches_test(&query(&pid_a, "integration_db", "tests::parse_fast")));
+    assert!(!expr.matches_test(&query(&pid_a, "unit", "tests::parse_slow")));
+}
+
+fn filter_sets(definitions: &[(&str, &str)], graph: &PackageGraph) -> FilterSets {
+    let definitions = definitions
+        .iter()
+        .map(|(name, input)| ((*name).to_owned(), (*input).to_owned()));
+    FilterSets::new(definitions, graph).unwrap()
+}
+
+#[test]
+fn test_expr_filter_sets() {
+    let graph = load_graph();
+    let sets = filter_sets(
+        &[
+            ("smoke", "test(smoke) or package(crate_a)"),
+            ("quick-smoke", "set(smoke) - test(slow)"),
+        ],
+        &graph,
+    );
+    assert_eq!(sets.names().collect::<Vec<_>>(), ["quick-smoke", "smoke"]);
+
+    let pid_a = mk_pid('a');
+    let pid_b = mk_pid('b');
+
+    let smoke = sets.get("smoke").expect("smoke is defined");
+    assert!(smoke.matches_test(&query(&pid_a, "my-binary", "tests::slow_thing")));
+    assert!(smoke.matches_test(&query(&pid_b, "my-binary", "tests::smoke_slow")));
+    assert!(!smoke.matches_test(&query(&pid_b, "my-binary", "tests::other")));
+
+    // quick-smoke refers to smoke, which is substituted into it.
+    let quick_smoke = sets.get("quick-smoke").expect("quick-smoke is defined");
+    assert!(quick_smoke.matches_test(&query(&pid_a, "my-binary", "tests::thing")));
+    assert!(!quick_smoke.matches_test(&query(&pid_a, "my-binary", "tests::slow_thing")));
+    assert!(!quick_smoke.matches_test(&query(&pid_b, "my-binary", "tests::smoke_slow")));
+
+    // Filter expressions can refer to sets as well.
+    let expr = FilteringExpr::parse_with_sets(
+        "set(quick-smoke) and not package(crate_a)".to_owned(),
+        &graph,
+        &sets,
+    )
+    .unwrap();
+    assert!(expr.matches_test(&query(&pid_b, "my-binary", "tests::smoke")));
+    assert!(!expr.matches_test(&query(&pid_a, "my-binary", "tests::smoke")));
+}
+
+#[test]
+fn test_expr_filter_sets_unknown() {
+    let graph = load_graph();
+    let sets = filter_sets(&[("smoke", "test(smoke)")], &graph);
+
+    let errors =
+        FilteringExpr::parse_with_sets("set(nightly)".to_owned(), &graph, &sets).unwrap_err();
+    assert_eq!(
+        errors.errors,
+        vec![ParseSingleError::UnknownFilterSet((4, 7).into())]
+    );
+
+    // Sets aren't resolved at all without any definitions.
+    let errors = FilteringExpr::parse("set(smoke)".to_owned(), &graph).unwrap_err();
+    assert_eq!(
+        errors.errors,
+        vec![ParseSingleError::UnknownFilterSet((4, 5).into())]
+    );
+
+    // Unknown sets referred to by other sets are reported against the referring set.
+    let error =
+        FilterSets::new([("smoke".to_owned(), "set(nightly)".to_owned())], &graph).unwrap_err();
+    match error {
+        FilterSetsError::ParseError { name, errors } => {
+            assert_eq!(name, "smoke");
+            assert_eq!(
+                errors.errors,
+                vec![ParseSingleError::UnknownFilterSet((4, 7).into())]
+            );
+        }
+        other => panic!("expected parse error, found {other}"),
+    }
+}
+
+#[test]
+fn test_expr_filter_sets_cycle() {
+    let graph = load_graph();
+    let definitions = [
+        ("nightly", "set(perf) or test(nightly)"),
+        ("perf", "set(smoke) and test(perf)"),
+        ("smoke", "set(nightly)"),
+    ];
+    let error = FilterSets::new(
+        definitions
+            .iter()
+            .map(|(name, input)| ((*name).to_owned(), (*input).to_owned())),
+        &graph,
+    )
+    .unwrap_err();
+    match &error {
+        FilterSetsError::Cycle { cycle } => {
+            assert_eq!(cycle, &["nightly", "perf", "smoke", "nightly"]);
+        }
+        other => panic!("expected cycle error, found {other}"),
+    }
+    assert_eq!(
+        error.to_string(),
+        "filter sets refer to each other in a cycle: nightly -> perf -> smoke -> nightly"
+    );
+
+    // A set can't refer to itself either.
+    let error =
+        FilterSets::new([("smoke".to_owned(), "set(smoke)".to_owned())], &graph).unwrap_err();
+    assert!(
+        matches!(&error, FilterSetsError::Cycle { cycle } if cycle == &["smoke", "smoke"]),
+        "unexpected error: {error}"
+    );
+}
+
 #[test]
 fn test_expr_platform() {
     let graph = load_graph();
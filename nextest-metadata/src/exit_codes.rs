@@ -28,6 +28,13 @@ impl NextestExitCode {
     /// Creating a test list produced an error.
     pub const TEST_LIST_CREATION_FAILED: i32 = 104;
 
+    /// The pre-flight command configured for the profile failed, so no tests were run.
+    pub const PRE_FLIGHT_FAILED: i32 = 105;
+
+    /// No tests failed, but some tests were flaky (passed only after being retried), and the
+    /// profile has `flaky-as-failure` enabled.
+    pub const TEST_RUN_FLAKY: i32 = 106;
+
     /// Writing data to stdout or stderr produced an error.
     pub const WRITE_OUTPUT_ERROR: i32 = 110;
 
@@ -602,6 +602,12 @@ pub enum MismatchReason {
 
     /// This test is in a different partition.
     Partition,
+
+    /// This test took longer than the `--skip-slower-than` threshold in the last run.
+    Slow,
+
+    /// This test is in the `quarantine` list of the profile being used.
+    Quarantined,
 }
 
 impl fmt::Display for MismatchReason {
@@ -613,6 +619,8 @@ impl fmt::Display for MismatchReason {
                 write!(f, "does not match the provided expression filters")
             }
             MismatchReason::Partition => write!(f, "is in a different partition"),
+            MismatchReason::Slow => write!(f, "was slower than the threshold in the last run"),
+            MismatchReason::Quarantined => write!(f, "is quarantined"),
         }
     }
 }
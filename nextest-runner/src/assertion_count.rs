@@ -0,0 +1,115 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Parsing of assertion counts reported by test harnesses.
+//!
+//! Harnesses that count assertions can report the count by printing a line of the form
+//! `nextest-assertions: <count>` to standard output. This is used to flag passing tests that
+//! didn't run any assertions, if `detect-no-assertions` is enabled.
+
+use crate::runner::ExecutionResult;
+
+/// The prefix of lines that report an assertion count.
+const ASSERTION_COUNT_MARKER: &str = "nextest-assertions:";
+
+/// Returns the number of assertions reported in `stdout`, or `None` if no count was reported.
+///
+/// If the count is reported more than once, for example as a running total, the last count wins.
+/// Lines with a count that isn't a non-negative integer are ignored.
+pub(crate) fn parse_assertion_count(stdout: &[u8]) -> Option<usize> {
+    String::from_utf8_lossy(stdout)
+        .lines()
+        .filter_map(|line| {
+            line.strip_prefix(ASSERTION_COUNT_MARKER)
+                .and_then(|count| count.trim().parse::<usize>().ok())
+        })
+        .last()
+}
+
+/// Returns true if a test with the given result and standard output passed, but reported that it
+/// ran no assertions.
+///
+/// Only passing tests are flagged: the assertion count doesn't matter for tests that failed.
+pub(crate) fn is_pass_without_assertions(result: ExecutionResult, stdout: &[u8]) -> bool {
+    matches!(result, ExecutionResult::Pass | ExecutionResult::Leak)
+        && parse_assertion_count(stdout) == Some(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+    use test_case::test_case;
+
+    #[test_case(
+        "",
+        None
+        ; "empty output"
+    )]
+    #[test_case(
+        indoc! {"
+            running 1 test
+            test tests::foo ... ok
+        "},
+        None
+        ; "no marker"
+    )]
+    #[test_case(
+        indoc! {"
+            running 1 test
+            nextest-assertions: 0
+            test tests::foo ... ok
+        "},
+        Some(0)
+        ; "zero assertions"
+    )]
+    #[test_case(
+        indoc! {"
+            nextest-assertions: 2
+            nextest-assertions:5
+        "},
+        Some(5)
+        ; "last count wins"
+    )]
+    #[test_case(
+        indoc! {"
+            nextest-assertions: 3
+            nextest-assertions: many
+              nextest-assertions: 0
+        "},
+        Some(3)
+        ; "invalid and indented lines are ignored"
+    )]
+    fn test_parse_assertion_count(stdout: &str, expected: Option<usize>) {
+        assert_eq!(parse_assertion_count(stdout.as_bytes()), expected);
+    }
+
+    #[test]
+    fn test_is_pass_without_assertions() {
+        let fail = ExecutionResult::Fail {
+            abort_status: None,
+            leaked: false,
+        };
+
+        assert!(
+            is_pass_without_assertions(ExecutionResult::Pass, b"nextest-assertions: 0\n"),
+            "zero-assertion pass is flagged"
+        );
+        assert!(
+            is_pass_without_assertions(ExecutionResult::Leak, b"nextest-assertions: 0\n"),
+            "zero-assertion leaky pass is flagged"
+        );
+        assert!(
+            !is_pass_without_assertions(ExecutionResult::Pass, b"nextest-assertions: 4\n"),
+            "pass with assertions isn't flagged"
+        );
+        assert!(
+            !is_pass_without_assertions(ExecutionResult::Pass, b"test foo ... ok\n"),
+            "pass without a reported count isn't flagged"
+        );
+        assert!(
+            !is_pass_without_assertions(fail, b"nextest-assertions: 0\n"),
+            "zero-assertion failure isn't flagged"
+        );
+    }
+}
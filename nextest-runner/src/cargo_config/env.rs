@@ -16,6 +16,15 @@ pub struct EnvironmentMap {
 }
 
 impl EnvironmentMap {
+    /// Creates an `EnvironmentMap` that doesn't set any variables.
+    ///
+    /// This is useful for running tests without reading Cargo configs.
+    pub fn empty() -> Self {
+        Self {
+            map: BTreeMap::new(),
+        }
+    }
+
     /// Creates a new `EnvironmentMap` from the given Cargo configs.
     pub fn new(configs: &CargoConfigs) -> Self {
         let env_configs = configs
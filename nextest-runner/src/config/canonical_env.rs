@@ -0,0 +1,231 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+use std::fmt;
+
+/// Type for the `canonical-env` config key.
+///
+/// Nextest sets the terminal and locale environment variables for tests to the values here, so
+/// that tests which depend on them behave the same way across machines.
+///
+/// This is specified either as a boolean, or as a table with `term`, `lang` and `lc-all` keys.
+/// `true` sets the default canonical values, and `false` leaves the variables as they are in
+/// nextest's environment. Keys missing from a table are set to their default canonical values.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CanonicalEnv {
+    /// The value to set `TERM` to, or `None` to leave it unchanged.
+    pub term: Option<String>,
+
+    /// The value to set `LANG` to, or `None` to leave it unchanged.
+    pub lang: Option<String>,
+
+    /// The value to set `LC_ALL` to, or `None` to leave it unchanged.
+    pub lc_all: Option<String>,
+}
+
+impl CanonicalEnv {
+    /// The default canonical value of `TERM`.
+    pub const DEFAULT_TERM: &'static str = "dumb";
+
+    /// The default canonical value of `LANG` and `LC_ALL`.
+    pub const DEFAULT_LOCALE: &'static str = "C.UTF-8";
+
+    /// Returns a `CanonicalEnv` that leaves all variables unchanged.
+    pub fn disabled() -> Self {
+        Self {
+            term: None,
+            lang: None,
+            lc_all: None,
+        }
+    }
+
+    /// Returns the environment variables to set for a test.
+    pub fn vars(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        [
+            ("TERM", &self.term),
+            ("LANG", &self.lang),
+            ("LC_ALL", &self.lc_all),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.as_deref().map(|value| (name, value)))
+    }
+}
+
+impl Default for CanonicalEnv {
+    fn default() -> Self {
+        Self {
+            term: Some(Self::DEFAULT_TERM.to_owned()),
+            lang: Some(Self::DEFAULT_LOCALE.to_owned()),
+            lc_all: Some(Self::DEFAULT_LOCALE.to_owned()),
+        }
+    }
+}
+
+impl From<bool> for CanonicalEnv {
+    fn from(enabled: bool) -> Self {
+        if enabled {
+            Self::default()
+        } else {
+            Self::disabled()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct CanonicalEnvTable {
+    #[serde(default)]
+    term: Option<String>,
+    #[serde(default)]
+    lang: Option<String>,
+    #[serde(default)]
+    lc_all: Option<String>,
+}
+
+pub(super) fn deserialize_canonical_env<'de, D>(
+    deserializer: D,
+) -> Result<Option<CanonicalEnv>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    struct V;
+
+    impl<'de2> serde::de::Visitor<'de2> for V {
+        type Value = Option<CanonicalEnv>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            write!(
+                formatter,
+                "a table ({{ term = \"dumb\", lang = \"C.UTF-8\", lc-all = \"C.UTF-8\" }}) \
+                 or a boolean (true or false)"
+            )
+        }
+
+        fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+        {
+            Ok(Some(v.into()))
+        }
+
+        fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+        where
+            A: serde::de::MapAccess<'de2>,
+        {
+            let table =
+                CanonicalEnvTable::deserialize(serde::de::value::MapAccessDeserializer::new(map))?;
+            let default = CanonicalEnv::default();
+            Ok(Some(CanonicalEnv {
+                term: table.term.or(default.term),
+                lang: table.lang.or(default.lang),
+                lc_all: table.lc_all.or(default.lc_all),
+            }))
+        }
+    }
+
+    deserializer.deserialize_any(V)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{test_helpers::*, NextestConfig};
+    use camino_tempfile::tempdir;
+    use indoc::indoc;
+    use test_case::test_case;
+
+    #[test_case(
+        "",
+        Ok(CanonicalEnv::default())
+        ; "enabled by default"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            canonical-env = false
+        "#},
+        Ok(CanonicalEnv::disabled())
+        ; "disabled"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            canonical-env = true
+        "#},
+        Ok(CanonicalEnv::default())
+        ; "enabled"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            canonical-env = { term = "xterm-256color", lc-all = "en_US.UTF-8" }
+        "#},
+        Ok(CanonicalEnv {
+            term: Some("xterm-256color".to_owned()),
+            lang: Some("C.UTF-8".to_owned()),
+            lc_all: Some("en_US.UTF-8".to_owned()),
+        })
+        ; "partial table"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            canonical-env = { lc-ctype = "C" }
+        "#},
+        Err("unknown field `lc-ctype`")
+        ; "unknown field"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            canonical-env = "C.UTF-8"
+        "#},
+        Err("or a boolean (true or false)")
+        ; "invalid type"
+    )]
+    fn parse_canonical_env(config_contents: &str, expected: Result<CanonicalEnv, &str>) {
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+
+        let config_result = NextestConfig::from_sources(graph.workspace().root(), &graph, None, []);
+        match expected {
+            Ok(expected) => {
+                let config = config_result.expect("config is valid");
+                let profile = config
+                    .profile(NextestConfig::DEFAULT_PROFILE)
+                    .expect("default profile exists")
+                    .apply_build_platforms(&build_platforms());
+                assert_eq!(profile.canonical_env(), &expected);
+            }
+            Err(message) => {
+                let error = config_result.expect_err("config is invalid");
+                let error_message = format!("{:?}", error);
+                assert!(
+                    error_message.contains(message),
+                    "expected error message to contain {message:?}, found: {error_message}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn vars() {
+        let vars: Vec<_> = CanonicalEnv::default().vars().collect();
+        assert_eq!(
+            vars,
+            vec![("TERM", "dumb"), ("LANG", "C.UTF-8"), ("LC_ALL", "C.UTF-8")]
+        );
+
+        assert_eq!(CanonicalEnv::disabled().vars().count(), 0);
+
+        let term_only = CanonicalEnv {
+            term: Some("xterm".to_owned()),
+            ..CanonicalEnv::disabled()
+        };
+        assert_eq!(
+            term_only.vars().collect::<Vec<_>>(),
+            vec![("TERM", "xterm")]
+        );
+    }
+}
@@ -2,26 +2,36 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use super::{
-    CompiledOverride, CompiledOverridesByProfile, CustomTestGroup, DeserializedOverride,
-    RetryPolicy, SettingSource, SlowTimeout, TestGroup, TestGroupConfig, TestSettings, TestThreads,
-    ThreadsRequired, ToolConfigFile,
+    default_filter::CompiledDefaultFilter,
+    locked_settings::LockedSettings,
+    profile_env::{current_env_vars, overrides_table, profile_env_overrides},
+    quarantine::CompiledQuarantineByProfile,
+    test_group::CompiledTestGroupMatches,
+    CanonicalEnv, CompiledOverride, CompiledOverridesByProfile, CompiledSuites, CustomTestGroup,
+    DeserializedOverride, DeserializedReport, DurationFormat, FailFast, FlakyConfirmation,
+    HealthCheck, JunitGroupBy, LeakTimeout, LeakedProcesses, MaxOutputBytes, NextestReport,
+    OutcomeSource, OverrideResolution, PreFlightCommand, ReportFormat, RetryPolicy, SettingSource,
+    SettingsExplanation, SlowTimeout, StdinMode, StoreDiscriminator, TargetRunnerConfig,
+    TargetRunnerRule, TestGroup, TestGroupConfig, TestOrder, TestSettings, TestSuiteConfig,
+    TestThreads, ThreadsRequired, ToolConfigFile,
 };
 use crate::{
     errors::{
-        provided_by_tool, ConfigParseError, ConfigParseErrorKind, ProfileNotFound,
-        UnknownTestGroupError,
+        provided_by_tool, ConfigParseError, ConfigParseErrorKind, ConfigProblem, ProfileNotFound,
+        SuiteNotFound, UnknownTestGroupError,
     },
     platform::BuildPlatforms,
-    reporter::{FinalStatusLevel, StatusLevel, TestOutputDisplay},
+    reporter::{ColorMode, FinalStatusLevel, StatusLevel, SuccessStderr, TestOutputDisplay},
 };
 use camino::{Utf8Path, Utf8PathBuf};
-use config::{builder::DefaultState, Config, ConfigBuilder, File, FileFormat, FileSourceFile};
+use config::{builder::DefaultState, Config, ConfigBuilder, File, FileFormat, Value, ValueKind};
 use guppy::graph::PackageGraph;
-use nextest_filtering::TestQuery;
+use nextest_filtering::{FilterSets, FilteringExpr, TestQuery};
 use once_cell::sync::Lazy;
 use serde::Deserialize;
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap},
+    num::NonZeroUsize,
     time::Duration,
 };
 
@@ -52,6 +62,11 @@ pub struct NextestConfig {
     workspace_root: Utf8PathBuf,
     inner: NextestConfigImpl,
     overrides: CompiledOverridesByProfile,
+    quarantine: CompiledQuarantineByProfile,
+    default_filter: CompiledDefaultFilter,
+    test_group_matches: CompiledTestGroupMatches,
+    suites: CompiledSuites,
+    filter_sets: FilterSets,
 }
 
 impl NextestConfig {
@@ -67,6 +82,10 @@ impl NextestConfig {
     /// Environment configuration uses this prefix, plus a _.
     pub const ENVIRONMENT_PREFIX: &'static str = "NEXTEST";
 
+    /// The name of the file that per-test timings are written to when `store-timings` is enabled,
+    /// within the profile's store directory.
+    pub const STORED_TIMINGS_FILE_NAME: &'static str = "timings.json";
+
     /// The name of the default profile.
     pub const DEFAULT_PROFILE: &'static str = "default";
 
@@ -100,6 +119,7 @@ impl NextestConfig {
             graph,
             config_file,
             tool_config_files.into_iter(),
+            current_env_vars(),
             |config_file, tool, unknown| {
                 let mut unknown_str = String::new();
                 if unknown.len() == 1 {
@@ -122,12 +142,47 @@ impl NextestConfig {
         )
     }
 
-    // A custom unknown_callback can be passed in while testing.
+    /// Checks the nextest config read from the given sources, returning all the problems found
+    /// rather than just the first one.
+    ///
+    /// This takes the same arguments as [`Self::from_sources`], and is meant for tools such as
+    /// editor integrations that show every issue with a config at once. Problems in one config file
+    /// don't stop other files from being checked. Unknown configuration keys aren't treated as
+    /// problems.
+    pub fn validate<'a, I>(
+        workspace_root: impl Into<Utf8PathBuf>,
+        graph: &PackageGraph,
+        config_file: Option<&Utf8Path>,
+        tool_config_files: impl IntoIterator<IntoIter = I>,
+    ) -> Result<(), Vec<ConfigProblem>>
+    where
+        I: Iterator<Item = &'a ToolConfigFile> + DoubleEndedIterator,
+    {
+        let workspace_root = workspace_root.into();
+        Self::read_from_sources(
+            graph,
+            &workspace_root,
+            config_file,
+            tool_config_files.into_iter().rev(),
+            current_env_vars(),
+            &mut |_, _, _| {},
+        )
+        .map(|_| ())
+        .map_err(|errors| {
+            errors
+                .into_iter()
+                .flat_map(ConfigProblem::from_error)
+                .collect()
+        })
+    }
+
+    // Custom environment variables and a custom unknown_callback can be passed in while testing.
     fn from_sources_impl<'a, I>(
         workspace_root: impl Into<Utf8PathBuf>,
         graph: &PackageGraph,
         config_file: Option<&Utf8Path>,
         tool_config_files: impl IntoIterator<IntoIter = I>,
+        env_vars: impl IntoIterator<Item = (String, String)>,
         mut unknown_callback: impl FnMut(&Utf8Path, Option<&str>, &BTreeSet<String>),
     ) -> Result<Self, ConfigParseError>
     where
@@ -135,23 +190,40 @@ impl NextestConfig {
     {
         let workspace_root = workspace_root.into();
         let tool_config_files_rev = tool_config_files.into_iter().rev();
-        let (inner, overrides) = Self::read_from_sources(
-            graph,
-            &workspace_root,
-            config_file,
-            tool_config_files_rev,
-            &mut unknown_callback,
-        )?;
+        let (inner, overrides, quarantine, default_filter, test_group_matches, suites, filter_sets) =
+            Self::read_from_sources(
+                graph,
+                &workspace_root,
+                config_file,
+                tool_config_files_rev,
+                env_vars,
+                &mut unknown_callback,
+            )
+            .map_err(|errors| {
+                // Report the first error, which is the one that would have been found first if
+                // reading had stopped there.
+                errors
+                    .into_iter()
+                    .next()
+                    .expect("reading config failed with at least one error")
+            })?;
         Ok(Self {
             workspace_root,
             inner,
             overrides,
+            quarantine,
+            default_filter,
+            test_group_matches,
+            suites,
+            filter_sets,
         })
     }
 
-    /// Returns the default nextest config.
-    #[cfg(test)]
-    pub(crate) fn default_config(workspace_root: impl Into<Utf8PathBuf>) -> Self {
+    /// Returns the default nextest config, without reading any config files.
+    ///
+    /// This is useful when embedding nextest to run a [`TestList`](crate::list::TestList) that
+    /// doesn't come with a nextest config.
+    pub fn default_config(workspace_root: impl Into<Utf8PathBuf>) -> Self {
         use itertools::Itertools;
 
         let config = Self::make_default_config()
@@ -177,11 +249,30 @@ impl NextestConfig {
         Self {
             workspace_root: workspace_root.into(),
             inner: deserialized.into_config_impl(),
-            // The default config does not (cannot) have overrides.
+            // The default config does not (cannot) have overrides, quarantined tests, default
+            // filters, test groups, suites or filter sets.
             overrides: CompiledOverridesByProfile::default(),
+            quarantine: CompiledQuarantineByProfile::default(),
+            default_filter: CompiledDefaultFilter::default(),
+            test_group_matches: CompiledTestGroupMatches::default(),
+            suites: CompiledSuites::default(),
+            filter_sets: FilterSets::default(),
         }
     }
 
+    /// Returns the rules in the `[target-runner]` section, in the order they're checked.
+    pub fn target_runner_rules(&self) -> &[TargetRunnerRule] {
+        &self.inner.target_runner.rules
+    }
+
+    /// Returns the additional arguments to pass to target runners, as specified in the config.
+    ///
+    /// Placeholders like `{workspace-root}` are not substituted: see
+    /// [`TargetRunner::with_extra_args`](crate::target_runner::TargetRunner::with_extra_args).
+    pub fn target_runner_args(&self) -> &[String] {
+        &self.inner.target_runner_args
+    }
+
     /// Returns the profile with the given name, or an error if a profile was specified but not
     /// found.
     pub fn profile(
@@ -191,6 +282,25 @@ impl NextestConfig {
         self.make_profile(name.as_ref())
     }
 
+    /// Returns the test suite with the given name, or an error if it isn't defined.
+    ///
+    /// To run the suite, select tests with its [filter](TestSuiteConfig::filter), and layer its
+    /// settings over a profile with [`NextestProfile::with_suite`].
+    pub fn suite(&self, name: impl AsRef<str>) -> Result<TestSuiteConfig<'_>, SuiteNotFound> {
+        let name = name.as_ref();
+        self.suites
+            .suites
+            .get_key_value(name)
+            .map(|(name, compiled)| TestSuiteConfig::new(name, compiled))
+            .ok_or_else(|| SuiteNotFound::new(name, self.suites.suites.keys()))
+    }
+
+    /// Returns the filter sets defined in the `[filter-sets]` table, which filter expressions can
+    /// refer to with `set(name)`.
+    pub fn filter_sets(&self) -> &FilterSets {
+        &self.filter_sets
+    }
+
     // ---
     // Helper methods
     // ---
@@ -200,8 +310,20 @@ impl NextestConfig {
         workspace_root: &Utf8Path,
         file: Option<&Utf8Path>,
         tool_config_files_rev: impl Iterator<Item = &'a ToolConfigFile>,
+        env_vars: impl IntoIterator<Item = (String, String)>,
         unknown_callback: &mut impl FnMut(&Utf8Path, Option<&str>, &BTreeSet<String>),
-    ) -> Result<(NextestConfigImpl, CompiledOverridesByProfile), ConfigParseError> {
+    ) -> Result<
+        (
+            NextestConfigImpl,
+            CompiledOverridesByProfile,
+            CompiledQuarantineByProfile,
+            CompiledDefaultFilter,
+            CompiledTestGroupMatches,
+            CompiledSuites,
+            FilterSets,
+        ),
+        Vec<ConfigParseError>,
+    > {
         // First, get the default config.
         let mut composite_builder = Self::make_default_config();
 
@@ -209,21 +331,70 @@ impl NextestConfig {
         // Note that they're stored in reverse order here, and are flipped over at the end.
         let mut overrides = CompiledOverridesByProfile::default();
 
+        // Quarantine lists are also additive, but their order doesn't matter.
+        let mut quarantine = CompiledQuarantineByProfile::default();
+
+        // Default filters in higher-priority files replace those in lower-priority ones.
+        let mut default_filter = CompiledDefaultFilter::default();
+
+        // Test groups from different files have different names, so their match filters are just
+        // combined.
+        let mut test_group_matches = CompiledTestGroupMatches::default();
+
+        // A suite defined in a higher-priority file replaces any suite with the same name defined
+        // in a lower-priority one.
+        let mut suites = CompiledSuites::default();
+
         let mut known_groups = BTreeSet::new();
 
+        // Like test groups, filter sets defined in lower-priority files can be referred to from
+        // higher-priority ones. A set defined in a higher-priority file replaces any set with the
+        // same name.
+        let mut filter_set_definitions = BTreeMap::new();
+
+        // Tool config files can lock settings, so that higher-priority files can't change them.
+        let mut locked_settings = LockedSettings::default();
+
+        // Problems in one file don't stop the others from being read, so that all of them can be
+        // reported at once.
+        let mut errors = Vec::new();
+
         // Next, merge in tool configs.
         for ToolConfigFile { config_file, tool } in tool_config_files_rev {
-            let source = File::new(config_file.as_str(), FileFormat::Toml);
-            Self::deserialize_individual_config(
+            let source = match locked_settings.apply(
+                File::new(config_file.as_str(), FileFormat::Toml),
+                config_file,
+                Some(tool),
+            ) {
+                Ok((source, warnings)) => {
+                    for warning in warnings {
+                        log::warn!("{warning}");
+                    }
+                    source
+                }
+                Err(kind) => {
+                    errors.push(ConfigParseError::new(config_file, Some(tool), kind));
+                    continue;
+                }
+            };
+            let res = Self::deserialize_individual_config(
                 graph,
                 workspace_root,
                 config_file,
                 Some(tool),
                 source.clone(),
                 &mut overrides,
+                &mut quarantine,
+                &mut default_filter,
+                &mut test_group_matches,
+                &mut suites,
                 unknown_callback,
                 &mut known_groups,
-            )?;
+                &mut filter_set_definitions,
+            );
+            if let Err(file_errors) = res {
+                errors.extend(file_errors);
+            }
 
             // This is the final, composite builder used at the end.
             composite_builder = composite_builder.add_source(source);
@@ -238,24 +409,84 @@ impl NextestConfig {
                 (config_file, source)
             }
         };
+        let source = match locked_settings.apply(source, &config_file, None) {
+            Ok((source, warnings)) => {
+                for warning in warnings {
+                    log::warn!("{warning}");
+                }
+                source
+            }
+            Err(kind) => {
+                errors.push(ConfigParseError::new(config_file, None, kind));
+                return Err(errors);
+            }
+        };
 
-        Self::deserialize_individual_config(
+        let res = Self::deserialize_individual_config(
             graph,
             workspace_root,
             &config_file,
             None,
             source.clone(),
             &mut overrides,
+            &mut quarantine,
+            &mut default_filter,
+            &mut test_group_matches,
+            &mut suites,
             unknown_callback,
             &mut known_groups,
-        )?;
+            &mut filter_set_definitions,
+        );
+        let filter_sets = match res {
+            Ok(filter_sets) if errors.is_empty() => filter_sets,
+            Ok(_) => return Err(errors),
+            Err(file_errors) => {
+                errors.extend(file_errors);
+                return Err(errors);
+            }
+        };
 
         composite_builder = composite_builder.add_source(source);
 
+        // Environment variables override settings in config files, except for locked ones. Only
+        // profiles defined in config files can be overridden.
+        let known_profiles: BTreeSet<_> = composite_builder
+            .build_cloned()
+            .and_then(|config| config.get_table("profile"))
+            .map(|profiles| profiles.into_keys().collect())
+            .unwrap_or_default();
+        let mut env_overrides = profile_env_overrides(env_vars, &known_profiles);
+        env_overrides.retain(|override_| {
+            match locked_settings.locking_tool(&override_.profile_name, override_.setting) {
+                Some(locking_tool) => {
+                    log::warn!(
+                        "ignoring environment variable `{}`: `profile.{}.{}` is locked by tool \
+                         `{locking_tool}`",
+                        override_.var,
+                        override_.profile_name,
+                        override_.setting,
+                    );
+                    false
+                }
+                None => true,
+            }
+        });
+        if !env_overrides.is_empty() {
+            composite_builder = composite_builder
+                .set_override(
+                    "profile",
+                    Value::new(None, ValueKind::Table(overrides_table(&env_overrides))),
+                )
+                .expect("`profile` is a valid key");
+        }
+
         // The unknown set is ignored here because any values in it have already been reported in
         // deserialize_individual_config.
-        let (config, _unknown) = Self::build_and_deserialize_config(&composite_builder)
-            .map_err(|kind| ConfigParseError::new(config_file, None, kind))?;
+        let (mut config, _unknown) = Self::build_and_deserialize_config(&composite_builder)
+            .map_err(|kind| vec![ConfigParseError::new(config_file.clone(), None, kind)])?;
+        config
+            .resolve_extends()
+            .map_err(|kind| vec![ConfigParseError::new(config_file, None, kind)])?;
 
         // Reverse all the overrides at the end.
         overrides.default.reverse();
@@ -263,7 +494,67 @@ impl NextestConfig {
             override_.reverse();
         }
 
-        Ok((config.into_config_impl(), overrides))
+        let config = config.into_config_impl();
+
+        // An override that never takes effect is almost certainly a mistake. Shadowed overrides are
+        // found assuming that the first override to set a setting wins, so those that could be
+        // used with `override-resolution = "last-wins"` aren't reported.
+        let uses_last_wins = |profile: Option<&CustomProfileImpl>| {
+            profile
+                .and_then(|profile| profile.override_resolution)
+                .unwrap_or(config.default_profile().override_resolution)
+                == OverrideResolution::LastWins
+        };
+        overrides.shadowed.retain(|shadowed| {
+            if shadowed.profile_name == NextestConfig::DEFAULT_PROFILE {
+                // The default profile's overrides are used by every profile.
+                !uses_last_wins(None)
+                    && !config
+                        .other_profiles()
+                        .any(|(_, profile)| uses_last_wins(Some(profile)))
+            } else {
+                // A custom profile's overrides are also used by the profiles that extend it.
+                !config.other_profiles().any(|(name, profile)| {
+                    uses_last_wins(Some(profile))
+                        && config
+                            .profile_chain(name)
+                            .contains(&shadowed.profile_name.as_str())
+                })
+            }
+        });
+        for shadowed in &overrides.shadowed {
+            log::warn!(
+                "in config file {}{}, [[profile.{}.overrides]] #{}{} never takes effect: \
+                 earlier overrides ({}) set all of its settings for every test it matches",
+                shadowed
+                    .config_file
+                    .strip_prefix(workspace_root)
+                    .unwrap_or(&shadowed.config_file),
+                provided_by_tool(shadowed.tool.as_deref()),
+                shadowed.profile_name,
+                shadowed.index + 1,
+                shadowed
+                    .filter
+                    .as_ref()
+                    .map_or_else(String::new, |filter| format!(" with filter `{filter}`")),
+                shadowed
+                    .shadowed_by
+                    .iter()
+                    .map(|index| format!("#{}", index + 1))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            );
+        }
+
+        Ok((
+            config,
+            overrides,
+            quarantine,
+            default_filter,
+            test_group_matches,
+            suites,
+            filter_sets,
+        ))
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -272,17 +563,24 @@ impl NextestConfig {
         workspace_root: &Utf8Path,
         config_file: &Utf8Path,
         tool: Option<&str>,
-        source: File<FileSourceFile, FileFormat>,
+        source: Config,
         overrides_out: &mut CompiledOverridesByProfile,
+        quarantine_out: &mut CompiledQuarantineByProfile,
+        default_filter_out: &mut CompiledDefaultFilter,
+        test_group_matches_out: &mut CompiledTestGroupMatches,
+        suites_out: &mut CompiledSuites,
         unknown_callback: &mut impl FnMut(&Utf8Path, Option<&str>, &BTreeSet<String>),
         known_groups: &mut BTreeSet<CustomTestGroup>,
-    ) -> Result<(), ConfigParseError> {
+        filter_set_definitions: &mut BTreeMap<String, String>,
+    ) -> Result<FilterSets, Vec<ConfigParseError>> {
+        let make_error = |kind| ConfigParseError::new(config_file, tool, kind);
+
         // Try building default builder + this file to get good error attribution and handle
         // overrides additively.
         let default_builder = Self::make_default_config();
         let this_builder = default_builder.add_source(source);
         let (this_config, unknown) = Self::build_and_deserialize_config(&this_builder)
-            .map_err(|kind| ConfigParseError::new(config_file, tool, kind))?;
+            .map_err(|kind| vec![make_error(kind)])?;
 
         if !unknown.is_empty() {
             unknown_callback(config_file, tool, &unknown);
@@ -309,7 +607,7 @@ impl NextestConfig {
             } else {
                 ConfigParseErrorKind::InvalidTestGroupsDefined(invalid_groups)
             };
-            return Err(ConfigParseError::new(config_file, tool, kind));
+            return Err(vec![make_error(kind)]);
         }
 
         known_groups.extend(valid_groups);
@@ -334,9 +632,46 @@ impl NextestConfig {
             }
         }
 
-        // Compile the overrides for this file.
-        let this_overrides = CompiledOverridesByProfile::new(graph, &this_config)
-            .map_err(|kind| ConfigParseError::new(config_file, tool, kind))?;
+        // Resolve the filter sets known up to this point, so that filters in this file can refer
+        // to them. If this file's sets are invalid, they're left out so that the same error isn't
+        // reported again for later files.
+        let mut all_definitions = filter_set_definitions.clone();
+        all_definitions.extend(this_config.filter_sets.clone());
+        let filter_sets = FilterSets::new(all_definitions.clone(), graph)
+            .map_err(|err| vec![make_error(ConfigParseErrorKind::FilterSetError(err))])?;
+        *filter_set_definitions = all_definitions;
+
+        // Compile the overrides for this file. All of these are compiled even if some fail, so that
+        // every problem in the file is reported.
+        fn ok_or_push<T>(
+            res: Result<T, ConfigParseError>,
+            errors: &mut Vec<ConfigParseError>,
+        ) -> Option<T> {
+            res.map_err(|error| errors.push(error)).ok()
+        }
+
+        let mut errors = Vec::new();
+        let this_overrides = ok_or_push(
+            CompiledOverridesByProfile::new(graph, &filter_sets, &this_config).map_err(make_error),
+            &mut errors,
+        );
+        let this_quarantine = ok_or_push(
+            CompiledQuarantineByProfile::new(graph, &filter_sets, &this_config).map_err(make_error),
+            &mut errors,
+        );
+        let this_default_filter = ok_or_push(
+            CompiledDefaultFilter::new(graph, &filter_sets, &this_config).map_err(make_error),
+            &mut errors,
+        );
+        let this_test_group_matches = ok_or_push(
+            CompiledTestGroupMatches::new(graph, &filter_sets, &this_config.test_groups)
+                .map_err(make_error),
+            &mut errors,
+        );
+        let this_suites = ok_or_push(
+            CompiledSuites::new(graph, &filter_sets, &this_config).map_err(make_error),
+            &mut errors,
+        );
 
         // Check that all overrides specify known test groups.
         let mut unknown_group_errors = Vec::new();
@@ -351,33 +686,67 @@ impl NextestConfig {
             }
         };
 
-        this_overrides.default.iter().for_each(|override_| {
-            check_test_group("default", override_.data.test_group.as_ref());
-        });
+        if let Some(this_overrides) = &this_overrides {
+            this_overrides.default.iter().for_each(|override_| {
+                check_test_group("default", override_.data.test_group.as_ref());
+            });
 
-        // Check that override test groups are known.
-        this_overrides
-            .other
-            .iter()
-            .for_each(|(profile_name, overrides)| {
-                overrides.iter().for_each(|override_| {
-                    check_test_group(profile_name, override_.data.test_group.as_ref());
+            // Check that override test groups are known.
+            this_overrides
+                .other
+                .iter()
+                .for_each(|(profile_name, overrides)| {
+                    overrides.iter().for_each(|override_| {
+                        check_test_group(profile_name, override_.data.test_group.as_ref());
+                    });
                 });
+        }
+
+        // Check that suite test groups are known.
+        if let Some(this_suites) = &this_suites {
+            this_suites.suites.iter().for_each(|(suite_name, suite)| {
+                check_test_group(
+                    &format!("suite.{suite_name}"),
+                    suite.data.test_group.as_ref(),
+                );
             });
+        }
 
-        // If there were any unknown groups, error out.
         if !unknown_group_errors.is_empty() {
             let known_groups = TestGroup::make_all_groups(known_groups.iter().cloned()).collect();
-            return Err(ConfigParseError::new(
-                config_file,
-                tool,
-                ConfigParseErrorKind::UnknownTestGroups {
-                    errors: unknown_group_errors,
-                    known_groups,
-                },
-            ));
+            errors.push(make_error(ConfigParseErrorKind::UnknownTestGroups {
+                errors: unknown_group_errors,
+                known_groups,
+            }));
+        }
+
+        // If there were any errors, this file's config isn't used.
+        let (
+            Some(this_overrides),
+            Some(this_quarantine),
+            Some(this_default_filter),
+            Some(this_test_group_matches),
+            Some(this_suites),
+        ) = (
+            this_overrides,
+            this_quarantine,
+            this_default_filter,
+            this_test_group_matches,
+            this_suites,
+        )
+        else {
+            return Err(errors);
+        };
+        if !errors.is_empty() {
+            return Err(errors);
         }
 
+        overrides_out.shadowed.extend(this_overrides.find_shadowed(
+            &this_config,
+            config_file,
+            tool,
+        ));
+
         // Grab the overrides for this config. Add them in reversed order (we'll flip it around at the end).
         overrides_out
             .default
@@ -390,7 +759,12 @@ impl NextestConfig {
                 .extend(overrides.into_iter().rev());
         }
 
-        Ok(())
+        quarantine_out.extend(this_quarantine);
+        default_filter_out.extend(this_default_filter);
+        test_group_matches_out.extend(this_test_group_matches);
+        suites_out.suites.extend(this_suites.suites);
+
+        Ok(filter_sets)
     }
 
     fn make_default_config() -> ConfigBuilder<DefaultState> {
@@ -405,25 +779,42 @@ impl NextestConfig {
 
         // The profile was found: construct the NextestProfile.
         let mut store_dir = self.workspace_root.join(&self.inner.store.dir);
+        if let Some(discriminator) = self.inner.store.discriminator.resolve() {
+            store_dir.push(discriminator);
+        }
         store_dir.push(name);
 
-        // Grab the overrides as well.
-        let overrides = self
-            .overrides
-            .other
-            .get(name)
-            .into_iter()
+        // Grab the overrides as well. A profile's own overrides come before those of the profiles
+        // it extends, which come before the default profile's.
+        let chain = self.inner.profile_chain(name);
+        let overrides = chain
+            .iter()
+            .filter_map(|name| self.overrides.other.get(*name))
             .flatten()
             .chain(self.overrides.default.iter())
             .cloned()
             .collect();
+        let quarantine = chain
+            .iter()
+            .filter_map(|name| self.quarantine.other.get(*name))
+            .flatten()
+            .chain(self.quarantine.default.iter())
+            .cloned()
+            .collect();
+        let default_filter = self.default_filter.for_profile_chain(&chain).cloned();
 
         Ok(NextestProfile {
+            name: name.to_owned(),
+            workspace_root: &self.workspace_root,
             store_dir,
             default_profile: &self.inner.default_profile,
             custom_profile,
             test_groups: &self.inner.test_groups,
+            test_group_matches: &self.test_group_matches,
             overrides,
+            quarantine,
+            default_filter,
+            suite: None,
         })
     }
 
@@ -463,14 +854,30 @@ pub struct FinalConfig {
 /// Returned by [`NextestConfig::profile`].
 #[derive(Clone, Debug)]
 pub struct NextestProfile<'cfg, State = FinalConfig> {
+    name: String,
+    workspace_root: &'cfg Utf8Path,
     store_dir: Utf8PathBuf,
     default_profile: &'cfg DefaultProfileImpl,
     custom_profile: Option<&'cfg CustomProfileImpl>,
     test_groups: &'cfg BTreeMap<CustomTestGroup, TestGroupConfig>,
+    test_group_matches: &'cfg CompiledTestGroupMatches,
     pub(super) overrides: Vec<CompiledOverride<State>>,
+    quarantine: Vec<FilteringExpr>,
+    default_filter: Option<FilteringExpr>,
+    pub(super) suite: Option<CompiledOverride<State>>,
 }
 
 impl<'cfg, State> NextestProfile<'cfg, State> {
+    /// Returns the name of this profile.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the workspace root that this profile's configuration was read for.
+    pub fn workspace_root(&self) -> &'cfg Utf8Path {
+        self.workspace_root
+    }
+
     /// Returns the absolute profile-specific store directory.
     pub fn store_dir(&self) -> &Utf8Path {
         &self.store_dir
@@ -481,6 +888,12 @@ impl<'cfg, State> NextestProfile<'cfg, State> {
         self.test_groups
     }
 
+    /// Returns the profile's `default-filter`, which tests must match in addition to any filters
+    /// specified on the command line.
+    pub fn default_filter(&self) -> Option<&FilteringExpr> {
+        self.default_filter.as_ref()
+    }
+
     #[allow(dead_code)]
     pub(super) fn custom_profile(&self) -> Option<&'cfg CustomProfileImpl> {
         self.custom_profile
@@ -488,6 +901,16 @@ impl<'cfg, State> NextestProfile<'cfg, State> {
 }
 
 impl<'cfg> NextestProfile<'cfg, PreBuildPlatform> {
+    /// Layers the settings of a test suite over this profile.
+    ///
+    /// For tests that the suite applies to, the suite's settings take precedence over those of the
+    /// profile and its overrides. Settings that the suite doesn't specify are taken from the
+    /// profile as usual.
+    pub fn with_suite(mut self, suite: &TestSuiteConfig<'cfg>) -> Self {
+        self.suite = Some(suite.compiled.clone());
+        self
+    }
+
     /// Applies build platforms to make the profile ready for evaluation.
     ///
     /// This is a separate step from parsing the config and reading a profile so that cargo-nextest
@@ -498,12 +921,21 @@ impl<'cfg> NextestProfile<'cfg, PreBuildPlatform> {
             .into_iter()
             .map(|override_| override_.apply_build_platforms(build_platforms))
             .collect();
+        let suite = self
+            .suite
+            .map(|suite| suite.apply_build_platforms(build_platforms));
         NextestProfile {
+            name: self.name,
+            workspace_root: self.workspace_root,
             store_dir: self.store_dir,
             default_profile: self.default_profile,
             custom_profile: self.custom_profile,
             test_groups: self.test_groups,
+            test_group_matches: self.test_group_matches,
             overrides,
+            quarantine: self.quarantine,
+            default_filter: self.default_filter,
+            suite,
         }
     }
 }
@@ -523,6 +955,16 @@ impl<'cfg> NextestProfile<'cfg, FinalConfig> {
             .unwrap_or(self.default_profile.test_threads)
     }
 
+    /// Returns the maximum number of threads used by tests across all test groups, if set.
+    ///
+    /// This caps [`Self::test_threads`], including when it's overridden on the command line.
+    pub fn global_max_threads(&self) -> Option<usize> {
+        self.custom_profile
+            .and_then(|profile| profile.global_max_threads)
+            .or(self.default_profile.global_max_threads)
+            .map(NonZeroUsize::get)
+    }
+
     /// Returns the number of threads required for each test.
     pub fn threads_required(&self) -> ThreadsRequired {
         self.custom_profile
@@ -537,14 +979,134 @@ impl<'cfg> NextestProfile<'cfg, FinalConfig> {
             .unwrap_or(self.default_profile.slow_timeout)
     }
 
-    /// Returns the time after which a child process that hasn't closed its handles is marked as
-    /// leaky.
-    pub fn leak_timeout(&self) -> Duration {
+    /// Returns the leak timeout for this profile: the time after which a child process that hasn't
+    /// closed its handles is marked as leaky, and whether leaky tests are treated as failing.
+    pub fn leak_timeout(&self) -> LeakTimeout {
         self.custom_profile
             .and_then(|profile| profile.leak_timeout)
             .unwrap_or(self.default_profile.leak_timeout)
     }
 
+    /// Returns the wall-clock time after which the whole run is canceled, if any.
+    pub fn global_timeout(&self) -> Option<Duration> {
+        self.custom_profile
+            .and_then(|profile| profile.global_timeout)
+            .or(self.default_profile.global_timeout)
+    }
+
+    /// Returns the interval at which progress updates are reported for slow tests, if enabled.
+    pub fn slow_report_interval(&self) -> Option<Duration> {
+        self.custom_profile
+            .and_then(|profile| profile.slow_report_interval)
+            .or(self.default_profile.slow_report_interval)
+    }
+
+    /// Returns the configuration for re-running flaky tests in isolation, if enabled.
+    pub fn flaky_confirmation(&self) -> Option<FlakyConfirmation> {
+        self.custom_profile
+            .and_then(|profile| profile.flaky_confirmation)
+            .or(self.default_profile.flaky_confirmation)
+    }
+
+    /// Returns true if a run with flaky tests, but no failures, should exit with a dedicated exit
+    /// code.
+    pub fn flaky_as_failure(&self) -> bool {
+        self.custom_profile
+            .and_then(|profile| profile.flaky_as_failure)
+            .unwrap_or(self.default_profile.flaky_as_failure)
+    }
+
+    /// Returns what determines whether tests pass for this profile.
+    pub fn outcome_source(&self) -> OutcomeSource {
+        self.custom_profile
+            .and_then(|profile| profile.outcome_source)
+            .unwrap_or(self.default_profile.outcome_source)
+    }
+
+    /// Returns what tests' standard input is connected to for this profile.
+    pub fn stdin(&self) -> StdinMode {
+        self.custom_profile
+            .and_then(|profile| profile.stdin)
+            .unwrap_or(self.default_profile.stdin)
+    }
+
+    /// Returns whether tests wait for their entire process group to exit, rather than just the
+    /// test process itself.
+    pub fn wait_for_process_group(&self) -> bool {
+        self.custom_profile
+            .and_then(|profile| profile.wait_for_process_group)
+            .unwrap_or(self.default_profile.wait_for_process_group)
+    }
+
+    /// Returns whether passing tests that report running no assertions are flagged.
+    pub fn detect_no_assertions(&self) -> bool {
+        self.custom_profile
+            .and_then(|profile| profile.detect_no_assertions)
+            .unwrap_or(self.default_profile.detect_no_assertions)
+    }
+
+    /// Returns whether to check for processes started by tests that are still running once all
+    /// tests have finished, and which of them to ignore.
+    pub fn leaked_processes(&self) -> &'cfg LeakedProcesses {
+        self.custom_profile
+            .and_then(|profile| profile.detect_leaked_processes.as_ref())
+            .unwrap_or(&self.default_profile.detect_leaked_processes)
+    }
+
+    /// Returns the command to run once before any tests, if configured.
+    pub fn pre_flight(&self) -> Option<&'cfg PreFlightCommand> {
+        self.custom_profile
+            .and_then(|profile| profile.pre_flight.as_ref())
+            .or(self.default_profile.pre_flight.as_ref())
+    }
+
+    /// Returns the command to run periodically while tests are running, if configured.
+    pub fn health_check(&self) -> Option<&'cfg HealthCheck> {
+        self.custom_profile
+            .and_then(|profile| profile.health_check.as_ref())
+            .or(self.default_profile.health_check.as_ref())
+    }
+
+    /// Returns extra arguments appended to the command line of each test that's run.
+    pub fn test_args(&self) -> &'cfg [String] {
+        self.custom_profile
+            .and_then(|profile| profile.test_args.as_deref())
+            .unwrap_or(&self.default_profile.test_args)
+    }
+
+    /// Returns the number of slowest tests listed in the timing statistics at the end of a run.
+    pub fn slow_test_report_count(&self) -> usize {
+        self.custom_profile
+            .and_then(|profile| profile.slow_test_report_count)
+            .unwrap_or(self.default_profile.slow_test_report_count)
+    }
+
+    /// Returns the path that per-test timings and outcomes are written to at the end of each run,
+    /// if `store-timings` is enabled.
+    pub fn stored_timings_path(&self) -> Option<Utf8PathBuf> {
+        let store_timings = self
+            .custom_profile
+            .and_then(|profile| profile.store_timings)
+            .unwrap_or(self.default_profile.store_timings);
+        store_timings.then(|| self.store_dir.join(NextestConfig::STORED_TIMINGS_FILE_NAME))
+    }
+
+    /// Returns the path that a short JSON summary of each run is written to, if `summary-path` is
+    /// set.
+    pub fn summary_path(&self) -> Option<Utf8PathBuf> {
+        self.custom_profile
+            .and_then(|profile| profile.summary_path.as_deref())
+            .or(self.default_profile.summary_path.as_deref())
+            .map(|path| self.store_dir.join(path))
+    }
+
+    /// Returns the maximum number of bytes of each output stream displayed for a test.
+    pub fn max_output_bytes(&self) -> MaxOutputBytes {
+        self.custom_profile
+            .and_then(|profile| profile.max_output_bytes)
+            .unwrap_or(self.default_profile.max_output_bytes)
+    }
+
     /// Returns the test status level.
     pub fn status_level(&self) -> StatusLevel {
         self.custom_profile
@@ -559,6 +1121,20 @@ impl<'cfg> NextestProfile<'cfg, FinalConfig> {
             .unwrap_or(self.default_profile.final_status_level)
     }
 
+    /// Returns when the reporter's output is colorized for this profile.
+    pub fn color(&self) -> ColorMode {
+        self.custom_profile
+            .and_then(|profile| profile.color)
+            .unwrap_or(self.default_profile.color)
+    }
+
+    /// Returns the format in which durations are displayed for this profile.
+    pub fn duration_format(&self) -> DurationFormat {
+        self.custom_profile
+            .and_then(|profile| profile.duration_format)
+            .unwrap_or(self.default_profile.duration_format)
+    }
+
     /// Returns the failure output config for this profile.
     pub fn failure_output(&self) -> TestOutputDisplay {
         self.custom_profile
@@ -573,13 +1149,57 @@ impl<'cfg> NextestProfile<'cfg, FinalConfig> {
             .unwrap_or(self.default_profile.success_output)
     }
 
+    /// Returns how standard error is displayed for passing tests in this profile.
+    pub fn success_stderr(&self) -> SuccessStderr {
+        self.custom_profile
+            .and_then(|profile| profile.success_stderr)
+            .unwrap_or(self.default_profile.success_stderr)
+    }
+
     /// Returns the fail-fast config for this profile.
-    pub fn fail_fast(&self) -> bool {
+    pub fn fail_fast(&self) -> FailFast {
         self.custom_profile
             .and_then(|profile| profile.fail_fast)
             .unwrap_or(self.default_profile.fail_fast)
     }
 
+    /// Returns the canonical terminal and locale environment for tests in this profile.
+    ///
+    /// This can be overridden for individual tests through [`Self::settings_for`].
+    pub fn canonical_env(&self) -> &'cfg CanonicalEnv {
+        self.custom_profile
+            .and_then(|profile| profile.canonical_env.as_ref())
+            .unwrap_or(&self.default_profile.canonical_env)
+    }
+
+    /// Returns the order in which tests are run for this profile.
+    pub fn test_order(&self) -> TestOrder {
+        self.custom_profile
+            .and_then(|profile| profile.test_order)
+            .unwrap_or(self.default_profile.test_order)
+    }
+
+    /// Returns how settings are resolved for tests matched by several overrides.
+    pub fn override_resolution(&self) -> OverrideResolution {
+        self.custom_profile
+            .and_then(|profile| profile.override_resolution)
+            .unwrap_or(self.default_profile.override_resolution)
+    }
+
+    /// Returns true if the test described by `query` is matched by this profile's `quarantine`
+    /// list, or by that of the default profile.
+    pub(super) fn is_quarantined(&self, query: &TestQuery<'_>) -> bool {
+        self.quarantine.iter().any(|expr| expr.matches_test(query))
+    }
+
+    /// Returns the test group whose `match` filter matches the test described by `query`, if any.
+    pub(super) fn matched_test_group(
+        &self,
+        query: &TestQuery<'_>,
+    ) -> Option<&'cfg CustomTestGroup> {
+        self.test_group_matches.group_for(query)
+    }
+
     /// Returns settings for individual tests.
     pub fn settings_for(&self, query: &TestQuery<'_>) -> TestSettings {
         TestSettings::new(self, query)
@@ -593,34 +1213,99 @@ impl<'cfg> NextestProfile<'cfg, FinalConfig> {
         TestSettings::new(self, query)
     }
 
-    /// Returns the JUnit configuration for this profile.
-    pub fn junit(&self) -> Option<NextestJunitConfig<'cfg>> {
-        let path = self
+    /// Returns where each setting for the test described by `query` came from: the profile, the
+    /// test's group, the test suite being run, or the override that supplied it.
+    ///
+    /// This is useful for debugging overrides that don't apply as expected.
+    pub fn explain_settings_for(&self, query: &TestQuery<'_>) -> SettingsExplanation {
+        let settings = self.settings_with_source_for(query);
+        SettingsExplanation::new(self, settings.sources())
+    }
+
+    /// Returns the reports to write out for each run with this profile.
+    ///
+    /// These are the reports in the profile's `reports` list, preceded by a JUnit report if the
+    /// deprecated `junit.path` is set.
+    pub fn reports(&self) -> Vec<NextestReport<'cfg>> {
+        let junit_path = self
             .custom_profile
             .map(|profile| &profile.junit.path)
             .unwrap_or(&self.default_profile.junit.path)
             .as_deref();
+        let reports = self
+            .custom_profile
+            .and_then(|profile| profile.reports.as_deref())
+            .unwrap_or(&self.default_profile.reports);
 
-        path.map(|path| {
-            let path = self.store_dir.join(path);
-            let report_name = self
-                .custom_profile
-                .and_then(|profile| profile.junit.report_name.as_deref())
-                .unwrap_or(&self.default_profile.junit.report_name);
-            let store_success_output = self
-                .custom_profile
-                .and_then(|profile| profile.junit.store_success_output)
-                .unwrap_or(self.default_profile.junit.store_success_output);
-            let store_failure_output = self
-                .custom_profile
-                .and_then(|profile| profile.junit.store_failure_output)
-                .unwrap_or(self.default_profile.junit.store_failure_output);
-            NextestJunitConfig {
-                path,
-                report_name,
-                store_success_output,
-                store_failure_output,
+        let mut out: Vec<NextestReport<'cfg>> = Vec::new();
+        let junit_alias = junit_path.map(|path| DeserializedReport {
+            format: ReportFormat::Junit,
+            path: path.to_owned(),
+        });
+        for report in junit_alias.iter().chain(reports) {
+            let path = self.store_dir.join(&report.path);
+            // A report that's configured twice, for example through both `junit.path` and the
+            // reports list, is only written out once.
+            if out
+                .iter()
+                .any(|other| other.format() == report.format && other.path() == path)
+            {
+                continue;
             }
+            out.push(match report.format {
+                ReportFormat::Junit => NextestReport::Junit(self.junit_config(path)),
+                ReportFormat::Json => NextestReport::Json { path },
+            });
+        }
+        out
+    }
+
+    /// Returns the configuration for the first JUnit report written out with this profile, if any.
+    ///
+    /// See [`Self::reports`] for all the reports.
+    pub fn junit(&self) -> Option<NextestJunitConfig<'cfg>> {
+        self.reports().into_iter().find_map(|report| match report {
+            NextestReport::Junit(junit) => Some(junit),
+            _ => None,
+        })
+    }
+
+    /// Returns the JUnit configuration for this profile, for a report at `path`.
+    fn junit_config(&self, path: Utf8PathBuf) -> NextestJunitConfig<'cfg> {
+        let report_name = self
+            .custom_profile
+            .and_then(|profile| profile.junit.report_name.as_deref())
+            .unwrap_or(&self.default_profile.junit.report_name);
+        let store_success_output = self
+            .custom_profile
+            .and_then(|profile| profile.junit.store_success_output)
+            .unwrap_or(self.default_profile.junit.store_success_output);
+        let store_failure_output = self
+            .custom_profile
+            .and_then(|profile| profile.junit.store_failure_output)
+            .unwrap_or(self.default_profile.junit.store_failure_output);
+        let group_by = self
+            .custom_profile
+            .and_then(|profile| profile.junit.group_by)
+            .unwrap_or(self.default_profile.junit.group_by);
+        NextestJunitConfig {
+            path,
+            report_name,
+            store_success_output,
+            store_failure_output,
+            group_by,
+        }
+    }
+
+    /// Returns the CSV timings configuration for this profile.
+    pub fn csv(&self) -> Option<NextestCsvConfig> {
+        let path = self
+            .custom_profile
+            .and_then(|profile| profile.csv.path.as_deref())
+            .or(self.default_profile.csv.path.as_deref());
+
+        path.map(|path| NextestCsvConfig {
+            path: self.store_dir.join(path),
         })
     }
 }
@@ -632,6 +1317,7 @@ pub struct NextestJunitConfig<'cfg> {
     report_name: &'cfg str,
     store_success_output: bool,
     store_failure_output: bool,
+    group_by: JunitGroupBy,
 }
 
 impl<'cfg> NextestJunitConfig<'cfg> {
@@ -654,14 +1340,36 @@ impl<'cfg> NextestJunitConfig<'cfg> {
     pub fn store_failure_output(&self) -> bool {
         self.store_failure_output
     }
+
+    /// Returns how tests are grouped into test suites.
+    pub fn group_by(&self) -> JunitGroupBy {
+        self.group_by
+    }
+}
+
+/// CSV timings configuration for nextest, returned by a [`NextestProfile`].
+#[derive(Clone, Debug)]
+pub struct NextestCsvConfig {
+    path: Utf8PathBuf,
+}
+
+impl NextestCsvConfig {
+    /// Returns the absolute path to the CSV file.
+    pub fn path(&self) -> &Utf8Path {
+        &self.path
+    }
 }
 
 #[derive(Clone, Debug)]
 pub(super) struct NextestConfigImpl {
     store: StoreConfigImpl,
     test_groups: BTreeMap<CustomTestGroup, TestGroupConfig>,
+    target_runner: TargetRunnerConfig,
+    target_runner_args: Vec<String>,
     default_profile: DefaultProfileImpl,
     other_profiles: HashMap<String, CustomProfileImpl>,
+    suites: BTreeMap<String, DeserializedOverride>,
+    filter_sets: BTreeMap<String, String>,
 }
 
 impl NextestConfigImpl {
@@ -693,6 +1401,27 @@ impl NextestConfigImpl {
             .iter()
             .map(|(key, value)| (key.as_str(), value))
     }
+
+    pub(super) fn suites(&self) -> impl Iterator<Item = (&str, &DeserializedOverride)> {
+        self.suites.iter().map(|(key, value)| (key.as_str(), value))
+    }
+
+    /// Returns the given custom profile followed by the profiles it extends, nearest first. The
+    /// default profile isn't included.
+    ///
+    /// Unknown parents and cycles are rejected while reading the config, so this always terminates.
+    fn profile_chain<'a>(&'a self, name: &'a str) -> Vec<&'a str> {
+        let mut chain = Vec::new();
+        let mut next = Some(name);
+        while let Some(name) = next {
+            let Some(profile) = self.other_profiles.get(name) else {
+                break;
+            };
+            chain.push(name);
+            next = profile.extends.as_deref();
+        }
+        chain
+    }
 }
 
 // This is the form of `NextestConfig` that gets deserialized.
@@ -702,11 +1431,71 @@ struct NextestConfigDeserialize {
     store: StoreConfigImpl,
     #[serde(default)]
     test_groups: BTreeMap<CustomTestGroup, TestGroupConfig>,
+    #[serde(default)]
+    target_runner: TargetRunnerConfig,
+    #[serde(default)]
+    target_runner_args: Vec<String>,
     #[serde(rename = "profile")]
     profiles: HashMap<String, CustomProfileImpl>,
+    #[serde(default, rename = "suite")]
+    suites: BTreeMap<String, DeserializedOverride>,
+    #[serde(default)]
+    filter_sets: BTreeMap<String, String>,
 }
 
 impl NextestConfigDeserialize {
+    /// Fills in the settings of profiles that extend other profiles.
+    ///
+    /// This is done on the composite config, since a profile can extend one defined in another
+    /// config file.
+    fn resolve_extends(&mut self) -> Result<(), ConfigParseErrorKind> {
+        if let Some(parent) = self
+            .profiles
+            .get(NextestConfig::DEFAULT_PROFILE)
+            .and_then(|profile| profile.extends.clone())
+        {
+            return Err(ConfigParseErrorKind::DefaultProfileExtends { parent });
+        }
+
+        // Go through profiles in sorted order, so that errors are reported deterministically.
+        let mut names: Vec<_> = self.profiles.keys().collect();
+        names.sort();
+
+        let mut resolved = Vec::new();
+        for name in names {
+            let mut profile = self.profiles[name].clone();
+            let mut chain = vec![name.as_str()];
+            let mut next = profile.extends.as_deref();
+            while let Some(parent_name) = next {
+                // Extending the default profile is the same as not extending anything.
+                if parent_name == NextestConfig::DEFAULT_PROFILE {
+                    break;
+                }
+                if let Some(pos) = chain.iter().position(|n| *n == parent_name) {
+                    let cycle = chain[pos..]
+                        .iter()
+                        .chain(std::iter::once(&parent_name))
+                        .map(|n| (*n).to_owned())
+                        .collect();
+                    return Err(ConfigParseErrorKind::ProfileExtendsCycle { cycle });
+                }
+                let Some(parent) = self.profiles.get(parent_name) else {
+                    return Err(ConfigParseErrorKind::UnknownParentProfile {
+                        profile_name: chain[chain.len() - 1].to_owned(),
+                        parent: parent_name.to_owned(),
+                    });
+                };
+                profile.inherit_from(parent);
+                chain.push(parent_name);
+                next = parent.extends.as_deref();
+            }
+            resolved.push((name.clone(), profile));
+        }
+
+        self.profiles.extend(resolved);
+        Ok(())
+    }
+
     fn into_config_impl(mut self) -> NextestConfigImpl {
         let p = self
             .profiles
@@ -718,7 +1507,11 @@ impl NextestConfigDeserialize {
             store: self.store,
             default_profile,
             test_groups: self.test_groups,
+            target_runner: self.target_runner,
+            target_runner_args: self.target_runner_args,
             other_profiles: self.profiles,
+            suites: self.suites,
+            filter_sets: self.filter_sets,
         }
     }
 }
@@ -727,22 +1520,51 @@ impl NextestConfigDeserialize {
 #[serde(rename_all = "kebab-case")]
 struct StoreConfigImpl {
     dir: Utf8PathBuf,
+    #[serde(default)]
+    discriminator: StoreDiscriminator,
 }
 
 #[derive(Clone, Debug)]
 pub(super) struct DefaultProfileImpl {
     test_threads: TestThreads,
+    global_max_threads: Option<NonZeroUsize>,
     threads_required: ThreadsRequired,
     retries: RetryPolicy,
     status_level: StatusLevel,
     final_status_level: FinalStatusLevel,
     failure_output: TestOutputDisplay,
     success_output: TestOutputDisplay,
-    fail_fast: bool,
+    success_stderr: SuccessStderr,
+    max_output_bytes: MaxOutputBytes,
+    color: ColorMode,
+    duration_format: DurationFormat,
+    fail_fast: FailFast,
+    test_order: TestOrder,
+    override_resolution: OverrideResolution,
+    canonical_env: CanonicalEnv,
     slow_timeout: SlowTimeout,
-    leak_timeout: Duration,
+    leak_timeout: LeakTimeout,
+    global_timeout: Option<Duration>,
+    slow_report_interval: Option<Duration>,
+    flaky_confirmation: Option<FlakyConfirmation>,
+    flaky_as_failure: bool,
+    outcome_source: OutcomeSource,
+    stdin: StdinMode,
+    wait_for_process_group: bool,
+    detect_no_assertions: bool,
+    detect_leaked_processes: LeakedProcesses,
+    pre_flight: Option<PreFlightCommand>,
+    health_check: Option<HealthCheck>,
+    test_args: Vec<String>,
+    slow_test_report_count: usize,
+    store_timings: bool,
+    summary_path: Option<Utf8PathBuf>,
+    quarantine: Vec<String>,
+    default_filter: Option<String>,
     overrides: Vec<DeserializedOverride>,
+    reports: Vec<DeserializedReport>,
     junit: DefaultJunitImpl,
+    csv: CsvImpl,
 }
 
 impl DefaultProfileImpl {
@@ -751,6 +1573,7 @@ impl DefaultProfileImpl {
             test_threads: p
                 .test_threads
                 .expect("test-threads present in default profile"),
+            global_max_threads: p.global_max_threads,
             threads_required: p
                 .threads_required
                 .expect("threads-required present in default profile"),
@@ -767,14 +1590,63 @@ impl DefaultProfileImpl {
             success_output: p
                 .success_output
                 .expect("success-output present in default profile"),
+            success_stderr: p
+                .success_stderr
+                .expect("success-stderr present in default profile"),
+            max_output_bytes: p
+                .max_output_bytes
+                .expect("max-output-bytes present in default profile"),
+            color: p.color.expect("color present in default profile"),
+            duration_format: p
+                .duration_format
+                .expect("duration-format present in default profile"),
             fail_fast: p.fail_fast.expect("fail-fast present in default profile"),
+            test_order: p.test_order.expect("test-order present in default profile"),
+            override_resolution: p
+                .override_resolution
+                .expect("override-resolution present in default profile"),
+            canonical_env: p
+                .canonical_env
+                .expect("canonical-env present in default profile"),
             slow_timeout: p
                 .slow_timeout
                 .expect("slow-timeout present in default profile"),
             leak_timeout: p
                 .leak_timeout
                 .expect("leak-timeout present in default profile"),
+            global_timeout: p.global_timeout,
+            slow_report_interval: p.slow_report_interval,
+            flaky_confirmation: p.flaky_confirmation,
+            flaky_as_failure: p
+                .flaky_as_failure
+                .expect("flaky-as-failure present in default profile"),
+            outcome_source: p
+                .outcome_source
+                .expect("outcome-source present in default profile"),
+            stdin: p.stdin.expect("stdin present in default profile"),
+            wait_for_process_group: p
+                .wait_for_process_group
+                .expect("wait-for-process-group present in default profile"),
+            detect_no_assertions: p
+                .detect_no_assertions
+                .expect("detect-no-assertions present in default profile"),
+            detect_leaked_processes: p
+                .detect_leaked_processes
+                .expect("detect-leaked-processes present in default profile"),
+            pre_flight: p.pre_flight,
+            health_check: p.health_check,
+            test_args: p.test_args.expect("test-args present in default profile"),
+            slow_test_report_count: p
+                .slow_test_report_count
+                .expect("slow-test-report-count present in default profile"),
+            store_timings: p
+                .store_timings
+                .expect("store-timings present in default profile"),
+            summary_path: p.summary_path,
+            quarantine: p.quarantine,
+            default_filter: p.default_filter,
             overrides: p.overrides,
+            reports: p.reports.expect("reports present in default profile"),
             junit: DefaultJunitImpl {
                 path: p.junit.path,
                 report_name: p
@@ -789,10 +1661,23 @@ impl DefaultProfileImpl {
                     .junit
                     .store_failure_output
                     .expect("junit.store-failure-output present in default profile"),
+                group_by: p
+                    .junit
+                    .group_by
+                    .expect("junit.group-by present in default profile"),
             },
+            csv: p.csv,
         }
     }
 
+    pub(super) fn quarantine(&self) -> &[String] {
+        &self.quarantine
+    }
+
+    pub(super) fn default_filter(&self) -> Option<&str> {
+        self.default_filter.as_deref()
+    }
+
     pub(super) fn overrides(&self) -> &[DeserializedOverride] {
         &self.overrides
     }
@@ -804,16 +1689,21 @@ struct DefaultJunitImpl {
     report_name: String,
     store_success_output: bool,
     store_failure_output: bool,
+    group_by: JunitGroupBy,
 }
 
 #[derive(Clone, Debug, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub(super) struct CustomProfileImpl {
+    #[serde(default)]
+    extends: Option<String>,
     #[serde(default, deserialize_with = "super::deserialize_retry_policy")]
     retries: Option<RetryPolicy>,
     #[serde(default)]
     test_threads: Option<TestThreads>,
     #[serde(default)]
+    global_max_threads: Option<NonZeroUsize>,
+    #[serde(default)]
     threads_required: Option<ThreadsRequired>,
     #[serde(default)]
     status_level: Option<StatusLevel>,
@@ -824,15 +1714,67 @@ pub(super) struct CustomProfileImpl {
     #[serde(default)]
     success_output: Option<TestOutputDisplay>,
     #[serde(default)]
-    fail_fast: Option<bool>,
+    success_stderr: Option<SuccessStderr>,
+    #[serde(default)]
+    max_output_bytes: Option<MaxOutputBytes>,
+    #[serde(default)]
+    color: Option<ColorMode>,
+    #[serde(default)]
+    duration_format: Option<DurationFormat>,
+    #[serde(default, deserialize_with = "super::deserialize_fail_fast")]
+    fail_fast: Option<FailFast>,
+    #[serde(default)]
+    test_order: Option<TestOrder>,
+    #[serde(default)]
+    override_resolution: Option<OverrideResolution>,
+    #[serde(default, deserialize_with = "super::deserialize_canonical_env")]
+    canonical_env: Option<CanonicalEnv>,
     #[serde(default, deserialize_with = "super::deserialize_slow_timeout")]
     slow_timeout: Option<SlowTimeout>,
+    #[serde(default, deserialize_with = "super::deserialize_leak_timeout")]
+    leak_timeout: Option<LeakTimeout>,
     #[serde(default, with = "humantime_serde::option")]
-    leak_timeout: Option<Duration>,
+    global_timeout: Option<Duration>,
+    #[serde(default, with = "humantime_serde::option")]
+    slow_report_interval: Option<Duration>,
+    #[serde(default)]
+    flaky_confirmation: Option<FlakyConfirmation>,
+    #[serde(default)]
+    flaky_as_failure: Option<bool>,
+    #[serde(default)]
+    outcome_source: Option<OutcomeSource>,
+    #[serde(default)]
+    stdin: Option<StdinMode>,
+    #[serde(default)]
+    wait_for_process_group: Option<bool>,
+    #[serde(default)]
+    detect_no_assertions: Option<bool>,
+    #[serde(default, deserialize_with = "super::deserialize_leaked_processes")]
+    detect_leaked_processes: Option<LeakedProcesses>,
+    #[serde(default)]
+    pre_flight: Option<PreFlightCommand>,
+    #[serde(default)]
+    health_check: Option<HealthCheck>,
+    #[serde(default)]
+    test_args: Option<Vec<String>>,
+    #[serde(default)]
+    slow_test_report_count: Option<usize>,
+    #[serde(default)]
+    store_timings: Option<bool>,
+    #[serde(default)]
+    summary_path: Option<Utf8PathBuf>,
+    #[serde(default)]
+    quarantine: Vec<String>,
+    #[serde(default)]
+    default_filter: Option<String>,
     #[serde(default)]
     overrides: Vec<DeserializedOverride>,
     #[serde(default)]
+    reports: Option<Vec<DeserializedReport>>,
+    #[serde(default)]
     junit: JunitImpl,
+    #[serde(default)]
+    csv: CsvImpl,
 }
 
 #[allow(dead_code)]
@@ -841,9 +1783,85 @@ impl CustomProfileImpl {
         self.test_threads
     }
 
+    pub(super) fn quarantine(&self) -> &[String] {
+        &self.quarantine
+    }
+
+    pub(super) fn default_filter(&self) -> Option<&str> {
+        self.default_filter.as_deref()
+    }
+
     pub(super) fn overrides(&self) -> &[DeserializedOverride] {
         &self.overrides
     }
+
+    /// Fills in settings that aren't set in this profile with those set in `parent`.
+    ///
+    /// Overrides, quarantine lists and default filters are compiled separately for each profile, so
+    /// they're combined while making the profile instead.
+    fn inherit_from(&mut self, parent: &CustomProfileImpl) {
+        fn inherit<T: Clone>(value: &mut Option<T>, parent: &Option<T>) {
+            if value.is_none() {
+                value.clone_from(parent);
+            }
+        }
+
+        inherit(&mut self.retries, &parent.retries);
+        inherit(&mut self.test_threads, &parent.test_threads);
+        inherit(&mut self.global_max_threads, &parent.global_max_threads);
+        inherit(&mut self.threads_required, &parent.threads_required);
+        inherit(&mut self.status_level, &parent.status_level);
+        inherit(&mut self.final_status_level, &parent.final_status_level);
+        inherit(&mut self.failure_output, &parent.failure_output);
+        inherit(&mut self.success_output, &parent.success_output);
+        inherit(&mut self.success_stderr, &parent.success_stderr);
+        inherit(&mut self.max_output_bytes, &parent.max_output_bytes);
+        inherit(&mut self.color, &parent.color);
+        inherit(&mut self.duration_format, &parent.duration_format);
+        inherit(&mut self.fail_fast, &parent.fail_fast);
+        inherit(&mut self.test_order, &parent.test_order);
+        inherit(&mut self.override_resolution, &parent.override_resolution);
+        inherit(&mut self.canonical_env, &parent.canonical_env);
+        inherit(&mut self.slow_timeout, &parent.slow_timeout);
+        inherit(&mut self.leak_timeout, &parent.leak_timeout);
+        inherit(&mut self.global_timeout, &parent.global_timeout);
+        inherit(&mut self.slow_report_interval, &parent.slow_report_interval);
+        inherit(&mut self.flaky_confirmation, &parent.flaky_confirmation);
+        inherit(&mut self.flaky_as_failure, &parent.flaky_as_failure);
+        inherit(&mut self.outcome_source, &parent.outcome_source);
+        inherit(&mut self.stdin, &parent.stdin);
+        inherit(
+            &mut self.wait_for_process_group,
+            &parent.wait_for_process_group,
+        );
+        inherit(&mut self.detect_no_assertions, &parent.detect_no_assertions);
+        inherit(
+            &mut self.detect_leaked_processes,
+            &parent.detect_leaked_processes,
+        );
+        inherit(&mut self.pre_flight, &parent.pre_flight);
+        inherit(&mut self.health_check, &parent.health_check);
+        inherit(&mut self.test_args, &parent.test_args);
+        inherit(
+            &mut self.slow_test_report_count,
+            &parent.slow_test_report_count,
+        );
+        inherit(&mut self.store_timings, &parent.store_timings);
+        inherit(&mut self.summary_path, &parent.summary_path);
+        inherit(&mut self.reports, &parent.reports);
+        inherit(&mut self.junit.path, &parent.junit.path);
+        inherit(&mut self.junit.report_name, &parent.junit.report_name);
+        inherit(
+            &mut self.junit.store_success_output,
+            &parent.junit.store_success_output,
+        );
+        inherit(
+            &mut self.junit.store_failure_output,
+            &parent.junit.store_failure_output,
+        );
+        inherit(&mut self.junit.group_by, &parent.junit.group_by);
+        inherit(&mut self.csv.path, &parent.csv.path);
+    }
 }
 
 #[derive(Clone, Debug, Default, Deserialize)]
@@ -857,6 +1875,15 @@ struct JunitImpl {
     store_success_output: Option<bool>,
     #[serde(default)]
     store_failure_output: Option<bool>,
+    #[serde(default)]
+    group_by: Option<JunitGroupBy>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct CsvImpl {
+    #[serde(default)]
+    path: Option<Utf8PathBuf>,
 }
 
 #[cfg(test)]
@@ -864,6 +1891,10 @@ mod tests {
     use super::*;
     use crate::config::test_helpers::*;
     use camino_tempfile::tempdir;
+    use guppy::graph::cargo::BuildPlatform;
+    use indoc::indoc;
+    use nextest_filtering::{errors::FilterSetsError, BinaryQuery};
+    use test_case::test_case;
 
     #[test]
     fn default_config_is_valid() {
@@ -873,6 +1904,881 @@ mod tests {
             .expect("default profile should exist");
     }
 
+    #[test]
+    fn global_timeout() {
+        let config_contents = indoc! {r#"
+            [profile.default]
+            global-timeout = "20m"
+
+            [profile.ci]
+            global-timeout = "90s"
+
+            [profile.local]
+            retries = 1
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let config = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect("config is valid");
+
+        let global_timeout = |profile_name: &str| {
+            config
+                .profile(profile_name)
+                .unwrap_or_else(|_| panic!("{profile_name} profile exists"))
+                .apply_build_platforms(&build_platforms())
+                .global_timeout()
+        };
+        assert_eq!(
+            global_timeout("default"),
+            Some(Duration::from_secs(20 * 60))
+        );
+        assert_eq!(global_timeout("ci"), Some(Duration::from_secs(90)));
+        assert_eq!(
+            global_timeout("local"),
+            Some(Duration::from_secs(20 * 60)),
+            "custom profile inherits the global timeout from the default profile"
+        );
+
+        let default_config = NextestConfig::default_config("foo");
+        let default_profile = default_config
+            .profile(NextestConfig::DEFAULT_PROFILE)
+            .expect("default profile exists")
+            .apply_build_platforms(&build_platforms());
+        assert_eq!(
+            default_profile.global_timeout(),
+            None,
+            "no global timeout by default"
+        );
+    }
+
+    #[test]
+    fn global_max_threads() {
+        let config_contents = indoc! {r#"
+            [profile.default]
+            global-max-threads = 8
+
+            [profile.ci]
+            global-max-threads = 2
+
+            [profile.local]
+            test-threads = 16
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let config = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect("config is valid");
+
+        let global_max_threads = |profile_name: &str| {
+            config
+                .profile(profile_name)
+                .unwrap_or_else(|_| panic!("{profile_name} profile exists"))
+                .apply_build_platforms(&build_platforms())
+                .global_max_threads()
+        };
+        assert_eq!(global_max_threads("default"), Some(8));
+        assert_eq!(global_max_threads("ci"), Some(2));
+        assert_eq!(
+            global_max_threads("local"),
+            Some(8),
+            "custom profile inherits the cap from the default profile"
+        );
+
+        let default_config = NextestConfig::default_config("foo");
+        let default_profile = default_config
+            .profile(NextestConfig::DEFAULT_PROFILE)
+            .expect("default profile exists")
+            .apply_build_platforms(&build_platforms());
+        assert_eq!(
+            default_profile.global_max_threads(),
+            None,
+            "no global cap by default"
+        );
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(
+            workspace_dir.path(),
+            indoc! {r#"
+                [profile.default]
+                global-max-threads = 0
+            "#},
+        );
+        NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect_err("a cap of 0 threads is invalid");
+    }
+
+    #[test]
+    fn stdin_mode() {
+        let config_contents = indoc! {r#"
+            [profile.ci]
+            stdin = "inherit"
+
+            [[profile.default.overrides]]
+            filter = "test(=interactive)"
+            stdin = "inherit"
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let package_id = graph.workspace().iter().next().unwrap().id();
+        let config = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect("config is valid");
+
+        let stdin_for = |profile_name: &str, test_name: &str| {
+            let query = TestQuery {
+                binary_query: BinaryQuery {
+                    package_id,
+                    kind: "lib",
+                    binary_name: "my-binary",
+                    platform: BuildPlatform::Target,
+                },
+                test_name,
+            };
+            config
+                .profile(profile_name)
+                .unwrap_or_else(|_| panic!("{profile_name} profile exists"))
+                .apply_build_platforms(&build_platforms())
+                .settings_for(&query)
+                .stdin()
+        };
+        assert_eq!(
+            stdin_for("default", "other"),
+            StdinMode::Null,
+            "stdin is connected to /dev/null by default"
+        );
+        assert_eq!(stdin_for("default", "interactive"), StdinMode::Inherit);
+        assert_eq!(stdin_for("ci", "other"), StdinMode::Inherit);
+    }
+
+    #[test]
+    fn test_args() {
+        let config_contents = indoc! {r#"
+            [profile.default]
+            test-args = ["--show-output"]
+
+            [profile.ci]
+            test-args = ["--color=never", "--show-output"]
+
+            [profile.local]
+            retries = 1
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let config = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect("config is valid");
+
+        let test_args = |profile_name: &str| {
+            config
+                .profile(profile_name)
+                .unwrap_or_else(|_| panic!("{profile_name} profile exists"))
+                .apply_build_platforms(&build_platforms())
+                .test_args()
+                .to_vec()
+        };
+        assert_eq!(test_args("default"), ["--show-output"]);
+        assert_eq!(
+            test_args("ci"),
+            ["--color=never", "--show-output"],
+            "custom profile replaces the default profile's test args"
+        );
+        assert_eq!(
+            test_args("local"),
+            ["--show-output"],
+            "custom profile inherits test args from the default profile"
+        );
+
+        let default_config = NextestConfig::default_config("foo");
+        let default_profile = default_config
+            .profile(NextestConfig::DEFAULT_PROFILE)
+            .expect("default profile exists")
+            .apply_build_platforms(&build_platforms());
+        assert!(
+            default_profile.test_args().is_empty(),
+            "no test args by default"
+        );
+    }
+
+    #[test]
+    fn reports() {
+        let config_contents = indoc! {r#"
+            [[profile.default.reports]]
+            format = "json"
+            path = "run.json"
+
+            [profile.ci.junit]
+            path = "junit.xml"
+
+            [[profile.ci.reports]]
+            format = "junit"
+            path = "junit.xml"
+
+            [[profile.ci.reports]]
+            format = "junit"
+            path = "other-junit.xml"
+
+            [profile.local]
+            retries = 1
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let config = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect("config is valid");
+
+        let reports = |profile_name: &str| {
+            let profile = config
+                .profile(profile_name)
+                .unwrap_or_else(|_| panic!("{profile_name} profile exists"))
+                .apply_build_platforms(&build_platforms());
+            let store_dir = profile.store_dir().to_owned();
+            profile
+                .reports()
+                .iter()
+                .map(|report| {
+                    let path = report.path().strip_prefix(&store_dir).unwrap();
+                    (report.format(), path.to_string())
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            reports("default"),
+            [(ReportFormat::Json, "run.json".to_owned())]
+        );
+        assert_eq!(
+            reports("ci"),
+            [
+                (ReportFormat::Junit, "junit.xml".to_owned()),
+                (ReportFormat::Junit, "other-junit.xml".to_owned()),
+            ],
+            "custom profile replaces the default profile's reports, and junit.path is only \
+             written out once"
+        );
+        assert_eq!(
+            reports("local"),
+            [(ReportFormat::Json, "run.json".to_owned())],
+            "custom profile inherits reports from the default profile"
+        );
+
+        let default_config = NextestConfig::default_config("foo");
+        let default_profile = default_config
+            .profile(NextestConfig::DEFAULT_PROFILE)
+            .expect("default profile exists")
+            .apply_build_platforms(&build_platforms());
+        assert!(
+            default_profile.reports().is_empty(),
+            "no reports by default"
+        );
+    }
+
+    #[test]
+    fn filter_sets() {
+        let config_contents = indoc! {r#"
+            [filter-sets]
+            smoke = 'test(smoke) or set(tool-slow)'
+            quick-smoke = 'set(smoke) - test(slow)'
+
+            [profile.default]
+            quarantine = ["set(tool-slow)"]
+
+            [[profile.default.overrides]]
+            filter = 'set(quick-smoke)'
+            retries = 3
+        "#};
+
+        let tool_config_contents = indoc! {r#"
+            [filter-sets]
+            tool-slow = 'test(/^slow_/)'
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let workspace_root = graph.workspace().root();
+        let package_id = graph.workspace().iter().next().unwrap().id();
+        let tool_path = workspace_root.join(".config/tool.toml");
+        std::fs::write(&tool_path, tool_config_contents).unwrap();
+
+        let config = NextestConfig::from_sources(
+            workspace_root,
+            &graph,
+            None,
+            &[ToolConfigFile {
+                tool: "my-tool".to_owned(),
+                config_file: tool_path,
+            }][..],
+        )
+        .expect("config is valid");
+
+        assert_eq!(
+            config.filter_sets().names().collect::<Vec<_>>(),
+            ["quick-smoke", "smoke", "tool-slow"],
+        );
+
+        let query = |test_name| TestQuery {
+            binary_query: BinaryQuery {
+                package_id,
+                kind: "lib",
+                binary_name: "my-binary",
+                platform: BuildPlatform::Target,
+            },
+            test_name,
+        };
+        let smoke = config.filter_sets().get("smoke").expect("smoke is defined");
+        assert!(smoke.matches_test(&query("smoke_db")));
+        assert!(smoke.matches_test(&query("slow_db")));
+        assert!(!smoke.matches_test(&query("other")));
+
+        let profile = config
+            .profile("default")
+            .expect("default profile exists")
+            .apply_build_platforms(&build_platforms());
+        let settings = |test_name| profile.settings_for(&query(test_name));
+        assert_eq!(
+            settings("smoke_db").retries(),
+            RetryPolicy::new_without_delay(3)
+        );
+        assert_eq!(
+            settings("smoke_slow").retries(),
+            RetryPolicy::new_without_delay(0),
+            "quick-smoke excludes slow tests"
+        );
+        assert!(settings("slow_db").quarantined());
+        assert!(!settings("smoke_db").quarantined());
+    }
+
+    #[test]
+    fn filter_sets_cycle() {
+        let config_contents = indoc! {r#"
+            [filter-sets]
+            smoke = 'test(smoke) or set(nightly)'
+            nightly = 'set(smoke) and test(nightly)'
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let error = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect_err("filter sets form a cycle");
+        match error.kind() {
+            ConfigParseErrorKind::FilterSetError(FilterSetsError::Cycle { cycle }) => {
+                assert_eq!(cycle, &["nightly", "smoke", "nightly"]);
+            }
+            other => panic!("unexpected error kind: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn validate_reports_all_problems() {
+        let config_contents = indoc! {r#"
+            [profile.ci]
+            quarantine = ['test(flaky']
+            default-filter = 'not package('
+
+            [[profile.default.overrides]]
+            filter = 'test(=my_test'
+            retries = 2
+
+            [suite.nightly]
+            filter = 'all()'
+            test-group = 'missing'
+        "#};
+        let tool_config_contents = indoc! {r#"
+            [profile.default]
+            slow-timeout = "not a duration"
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let workspace_root = graph.workspace().root();
+        let tool_path = workspace_root.join(".config/tool.toml");
+        std::fs::write(&tool_path, tool_config_contents).unwrap();
+        let tool_config_files = [ToolConfigFile {
+            tool: "my-tool".to_owned(),
+            config_file: tool_path.clone(),
+        }];
+
+        let problems = NextestConfig::validate(workspace_root, &graph, None, &tool_config_files)
+            .expect_err("config has problems");
+        let problems: Vec<_> = problems
+            .iter()
+            .map(|problem| {
+                (
+                    problem.config_file().file_name().unwrap(),
+                    problem.profile_name(),
+                    problem.location(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            problems,
+            [
+                (
+                    "tool.toml",
+                    Some("default"),
+                    Some("profile.default.slow-timeout")
+                ),
+                (
+                    "nextest.toml",
+                    Some("default"),
+                    Some("profile.default.overrides")
+                ),
+                ("nextest.toml", Some("ci"), Some("profile.ci.quarantine")),
+                (
+                    "nextest.toml",
+                    Some("ci"),
+                    Some("profile.ci.default-filter")
+                ),
+                ("nextest.toml", None, Some("suite.nightly.test-group")),
+            ],
+        );
+
+        // Reading the config still fails on the first problem.
+        let error = NextestConfig::from_sources(workspace_root, &graph, None, &tool_config_files)
+            .expect_err("config is invalid");
+        assert_eq!(error.config_file(), &tool_path);
+        assert!(
+            matches!(error.kind(), ConfigParseErrorKind::DeserializeError(_)),
+            "unexpected error kind: {:?}",
+            error.kind()
+        );
+
+        // A valid config has no problems.
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), "");
+        NextestConfig::validate(graph.workspace().root(), &graph, None, [])
+            .expect("config is valid");
+    }
+
+    #[test]
+    fn shadowed_overrides() {
+        let config_contents = indoc! {r#"
+            [[profile.default.overrides]]
+            filter = 'test(=foo) | test(=bar)'
+            retries = 2
+
+            # Shadowed: only matches tests that the first override matches.
+            [[profile.default.overrides]]
+            filter = 'test(=foo)'
+            retries = 3
+
+            # Not shadowed: no earlier override sets slow-timeout.
+            [[profile.default.overrides]]
+            filter = 'test(=foo) & kind(lib)'
+            retries = 4
+            slow-timeout = "120s"
+
+            # Not shadowed: the earlier overrides that set slow-timeout match fewer tests.
+            [[profile.default.overrides]]
+            platform = 'cfg(unix)'
+            slow-timeout = "60s"
+
+            # Shadowed: the same filter as the third override, written differently.
+            [[profile.default.overrides]]
+            platform = 'cfg(unix)'
+            filter = 'test(=foo)  and  kind(lib)'
+            slow-timeout = "1s"
+
+            [[profile.ci.overrides]]
+            filter = 'all()'
+            retries = 1
+
+            [[profile.ci.overrides]]
+            filter = 'test(=foo)'
+            retries = 5
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+
+        let config = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect("config is valid");
+        let shadowed: Vec<_> = config
+            .overrides
+            .shadowed
+            .iter()
+            .map(|shadowed| {
+                (
+                    shadowed.profile_name.as_str(),
+                    shadowed.index,
+                    shadowed.shadowed_by.as_slice(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            shadowed,
+            [
+                ("default", 1, &[0][..]),
+                ("default", 4, &[2]),
+                ("ci", 1, &[0])
+            ],
+        );
+
+        // With last-wins resolution, later overrides take precedence, so none of them are
+        // shadowed. Since the default profile's overrides are also used by the ci profile, they
+        // aren't reported either.
+        let config_contents =
+            format!("[profile.ci]\noverride-resolution = \"last-wins\"\n\n{config_contents}");
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), &config_contents);
+
+        let config = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect("config is valid");
+        assert_eq!(config.overrides.shadowed, []);
+    }
+
+    #[test]
+    fn profile_extends() {
+        let config_contents = indoc! {r#"
+            [profile.ci-base]
+            retries = 2
+            slow-timeout = "60s"
+            global-max-threads = 8
+            default-filter = 'not test(=skipped_test)'
+
+            [[profile.ci-base.overrides]]
+            filter = 'test(=my_test)'
+            retries = 4
+            leak-timeout = "1s"
+
+            [profile.ci-fast]
+            extends = "ci-base"
+            slow-timeout = "30s"
+
+            [[profile.ci-fast.overrides]]
+            filter = 'test(=my_test)'
+            retries = 5
+
+            [profile.ci-full]
+            extends = "ci-fast"
+            retries = 3
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let package_id = graph.workspace().iter().next().unwrap().id();
+
+        let config = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect("config is valid");
+        let profile = config
+            .profile("ci-full")
+            .expect("ci-full profile exists")
+            .apply_build_platforms(&build_platforms());
+
+        // The profile's own settings come first, then those of the profiles it extends, nearest
+        // first.
+        assert_eq!(profile.retries(), RetryPolicy::new_without_delay(3));
+        assert_eq!(profile.slow_timeout().period, Duration::from_secs(30));
+        assert_eq!(profile.global_max_threads(), Some(8));
+        assert_eq!(
+            profile.default_filter().map(|expr| expr.input.as_str()),
+            Some("not test(=skipped_test)"),
+        );
+
+        // Overrides are concatenated, with the profile's own overrides first.
+        let query = TestQuery {
+            binary_query: BinaryQuery {
+                package_id,
+                kind: "lib",
+                binary_name: "my-binary",
+                platform: BuildPlatform::Target,
+            },
+            test_name: "my_test",
+        };
+        let settings = profile.settings_for(&query);
+        assert_eq!(settings.retries(), RetryPolicy::new_without_delay(5));
+        assert_eq!(settings.leak_timeout().period, Duration::from_secs(1));
+
+        // Profiles that don't extend anything are unaffected.
+        let profile = config
+            .profile("ci-base")
+            .expect("ci-base profile exists")
+            .apply_build_platforms(&build_platforms());
+        assert_eq!(profile.slow_timeout().period, Duration::from_secs(60));
+        assert_eq!(
+            profile.settings_for(&query).retries(),
+            RetryPolicy::new_without_delay(4)
+        );
+    }
+
+    #[test_case(
+        indoc! {r#"
+            [profile.a]
+            extends = "b"
+
+            [profile.b]
+            extends = "c"
+
+            [profile.c]
+            extends = "a"
+        "#},
+        "profile.a.extends",
+        "profiles extend each other in a cycle: a -> b -> c -> a"
+
+        ; "cycle"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.a]
+            extends = "a"
+        "#},
+        "profile.a.extends",
+        "profiles extend each other in a cycle: a -> a"
+
+        ; "self cycle"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.a]
+            extends = "b"
+
+            [profile.b]
+            extends = "missing"
+        "#},
+        "profile.b.extends",
+        "profile `b` extends unknown profile `missing`"
+
+        ; "unknown parent"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            extends = "ci"
+
+            [profile.ci]
+        "#},
+        "profile.default.extends",
+        "the default profile cannot extend another profile (found extends = `ci`)"
+
+        ; "default profile"
+    )]
+    fn profile_extends_invalid(config_contents: &str, location: &str, message: &str) {
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+
+        let error = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect_err("config is invalid");
+        assert_eq!(error.kind().to_string(), message);
+
+        let problems = NextestConfig::validate(graph.workspace().root(), &graph, None, [])
+            .expect_err("config is invalid");
+        assert_eq!(problems.len(), 1, "one problem found: {problems:?}");
+        assert_eq!(problems[0].location(), Some(location));
+    }
+
+    #[test]
+    fn locked_settings() {
+        let config_contents = indoc! {r#"
+            [profile.default]
+            slow-timeout = "30s"
+            leak-timeout = "1s"
+
+            [[profile.default.overrides]]
+            filter = 'all()'
+            slow-timeout = "5s"
+            retries = 2
+
+            [profile.ci]
+            slow-timeout = { period = "10s", terminate-after = 2 }
+
+            [[profile.ci.overrides]]
+            filter = 'all()'
+            slow-timeout = "5s"
+        "#};
+        let tool_config_contents = indoc! {r#"
+            [profile.default]
+            slow-timeout = { period = "60s", locked = true }
+            leak-timeout = { period = "500ms", locked = false }
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let workspace_root = graph.workspace().root();
+        let tool_path = workspace_root.join(".config/tool.toml");
+        std::fs::write(&tool_path, tool_config_contents).unwrap();
+        let tool_config_files = [ToolConfigFile {
+            tool: "my-tool".to_owned(),
+            config_file: tool_path.clone(),
+        }];
+
+        let package_id = graph.workspace().iter().next().unwrap().id();
+        let query = TestQuery {
+            binary_query: BinaryQuery {
+                package_id,
+                kind: "lib",
+                binary_name: "my-binary",
+                platform: BuildPlatform::Target,
+            },
+            test_name: "my_test",
+        };
+
+        // Settings locked by the tool config are warned about when the project config sets them.
+        let mut locked_settings = LockedSettings::default();
+        let (_, tool_warnings) = locked_settings
+            .apply(
+                File::new(tool_path.as_str(), FileFormat::Toml),
+                &tool_path,
+                Some("my-tool"),
+            )
+            .expect("tool config is valid");
+        assert_eq!(
+            tool_warnings,
+            Vec::<String>::new(),
+            "the locking file itself isn't warned about"
+        );
+        let config_path = workspace_root.join(NextestConfig::CONFIG_PATH);
+        let (_, warnings) = locked_settings
+            .apply(
+                File::new(config_path.as_str(), FileFormat::Toml),
+                &config_path,
+                None,
+            )
+            .expect("project config is valid");
+        for expected in [
+            "ignoring `profile.default.slow-timeout` in config file",
+            "ignoring `profile.ci.slow-timeout` in config file",
+            "ignoring `slow-timeout` in override 0 of profile `default` in config file",
+            "ignoring `slow-timeout` in override 0 of profile `ci` in config file",
+        ] {
+            assert!(
+                warnings.iter().any(|warning| warning.contains(expected)
+                    && warning.contains("it is locked by tool `my-tool`")),
+                "warning `{expected}` was produced, found: {warnings:#?}"
+            );
+        }
+        assert_eq!(
+            warnings.len(),
+            4,
+            "only locked settings are warned about: {warnings:#?}"
+        );
+
+        let config = NextestConfig::from_sources(workspace_root, &graph, None, &tool_config_files)
+            .expect("config is valid");
+
+        // The locked slow timeout can't be changed by the project config, in the default profile
+        // or any other profile, including through overrides.
+        for profile_name in ["default", "ci"] {
+            let profile = config
+                .profile(profile_name)
+                .unwrap()
+                .apply_build_platforms(&build_platforms());
+            let slow_timeout = profile.slow_timeout();
+            assert_eq!(
+                slow_timeout.period,
+                Duration::from_secs(60),
+                "slow-timeout is locked for profile {profile_name}"
+            );
+            assert_eq!(
+                slow_timeout.terminate_after, None,
+                "slow-timeout is locked for profile {profile_name}"
+            );
+            assert_eq!(
+                profile.settings_for(&query).slow_timeout().period,
+                Duration::from_secs(60),
+                "slow-timeout is locked for overrides in profile {profile_name}"
+            );
+        }
+
+        // Settings that aren't locked can be changed as usual.
+        let profile = config
+            .profile("default")
+            .unwrap()
+            .apply_build_platforms(&build_platforms());
+        assert_eq!(profile.leak_timeout().period, Duration::from_secs(1));
+        assert_eq!(
+            profile.settings_for(&query).retries(),
+            RetryPolicy::new_without_delay(2),
+            "overrides of settings that aren't locked still apply"
+        );
+    }
+
+    #[test]
+    fn profile_env_overrides() {
+        let config_contents = indoc! {r#"
+            [profile.default]
+            retries = 2
+            slow-timeout = "30s"
+
+            [[profile.default.overrides]]
+            filter = 'test(=my_test)'
+            retries = 7
+
+            [profile.ci-fast]
+            fail-fast = true
+        "#};
+        let tool_config_contents = indoc! {r#"
+            [profile.default]
+            leak-timeout = { period = "500ms", locked = true }
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let workspace_root = graph.workspace().root();
+        let tool_path = workspace_root.join(".config/tool.toml");
+        std::fs::write(&tool_path, tool_config_contents).unwrap();
+
+        let env_vars = [
+            ("NEXTEST_PROFILE_DEFAULT_RETRIES", "5"),
+            ("NEXTEST_PROFILE_CI_FAST_SLOW_TIMEOUT", "90s"),
+            ("NEXTEST_PROFILE_CI_FAST_FAIL_FAST", "false"),
+            // Locked by the tool config, so this is ignored.
+            ("NEXTEST_PROFILE_DEFAULT_LEAK_TIMEOUT", "1s"),
+            // Profiles can't be defined by environment variables.
+            ("NEXTEST_PROFILE_UNKNOWN_RETRIES", "3"),
+            ("NEXTEST_PROFILE", "ci-fast"),
+        ]
+        .map(|(var, value)| (var.to_owned(), value.to_owned()));
+
+        let config = NextestConfig::from_sources_impl(
+            workspace_root,
+            &graph,
+            None,
+            &[ToolConfigFile {
+                tool: "my-tool".to_owned(),
+                config_file: tool_path,
+            }][..],
+            env_vars,
+            |_, _, _| {},
+        )
+        .expect("config is valid");
+        assert!(
+            config.profile("unknown").is_err(),
+            "unknown profile isn't defined by environment variables"
+        );
+
+        let profile = config
+            .profile("default")
+            .unwrap()
+            .apply_build_platforms(&build_platforms());
+        assert_eq!(profile.retries(), RetryPolicy::new_without_delay(5));
+        assert_eq!(profile.slow_timeout().period, Duration::from_secs(30));
+        assert_eq!(profile.leak_timeout().period, Duration::from_millis(500));
+
+        let profile = config
+            .profile("ci-fast")
+            .unwrap()
+            .apply_build_platforms(&build_platforms());
+        assert_eq!(profile.retries(), RetryPolicy::new_without_delay(5));
+        assert_eq!(profile.slow_timeout().period, Duration::from_secs(90));
+        assert_eq!(profile.fail_fast(), FailFast::from(false));
+
+        // Environment variables act like profile settings, so per-test overrides still take
+        // priority over them.
+        let package_id = graph.workspace().iter().next().unwrap().id();
+        let query = TestQuery {
+            binary_query: BinaryQuery {
+                package_id,
+                kind: "lib",
+                binary_name: "my-binary",
+                platform: BuildPlatform::Target,
+            },
+            test_name: "my_test",
+        };
+        assert_eq!(
+            profile.settings_for(&query).retries(),
+            RetryPolicy::new_without_delay(7)
+        );
+    }
+
     #[test]
     fn ignored_keys() {
         let config_contents = r#"
@@ -922,6 +2828,7 @@ mod tests {
                 tool: "my-tool".to_owned(),
                 config_file: tool_path,
             }][..],
+            std::iter::empty(),
             |_path, tool, ignored| {
                 unknown_keys.insert(tool.map(|s| s.to_owned()), ignored.clone());
             },
@@ -0,0 +1,96 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use super::NextestConfigImpl;
+use crate::errors::{ConfigParseErrorKind, ConfigParseOverrideError};
+use guppy::graph::PackageGraph;
+use nextest_filtering::{FilterSets, FilteringExpr};
+use std::collections::HashMap;
+
+/// The `default-filter` of each profile, compiled into filter expressions.
+///
+/// Unlike quarantine lists, default filters aren't additive: a custom profile's default filter
+/// replaces that of the default profile, and a default filter defined in a higher-priority config
+/// file replaces one defined in a lower-priority file.
+#[derive(Clone, Debug, Default)]
+pub(super) struct CompiledDefaultFilter {
+    pub(super) default: Option<FilteringExpr>,
+    pub(super) other: HashMap<String, FilteringExpr>,
+}
+
+impl CompiledDefaultFilter {
+    pub(super) fn new(
+        graph: &PackageGraph,
+        filter_sets: &FilterSets,
+        config: &NextestConfigImpl,
+    ) -> Result<Self, ConfigParseErrorKind> {
+        let mut errors = vec![];
+        let default = config
+            .default_profile()
+            .default_filter()
+            .and_then(|filter| {
+                Self::compile_default_filter(graph, filter_sets, "default", filter, &mut errors)
+            });
+        let other: HashMap<_, _> = config
+            .other_profiles()
+            .filter_map(|(profile_name, profile)| {
+                let filter = profile.default_filter()?;
+                let expr = Self::compile_default_filter(
+                    graph,
+                    filter_sets,
+                    profile_name,
+                    filter,
+                    &mut errors,
+                )?;
+                Some((profile_name.to_owned(), expr))
+            })
+            .collect();
+
+        if errors.is_empty() {
+            Ok(Self { default, other })
+        } else {
+            Err(ConfigParseErrorKind::DefaultFilterError(errors))
+        }
+    }
+
+    /// Replaces default filters with those compiled for a higher-priority config file.
+    pub(super) fn extend(&mut self, other: Self) {
+        if other.default.is_some() {
+            self.default = other.default;
+        }
+        self.other.extend(other.other);
+    }
+
+    /// Returns the default filter for the first profile in `chain` that has one, falling back to
+    /// that of the default profile.
+    ///
+    /// `chain` is a custom profile followed by the profiles it extends. It's empty for the default
+    /// profile.
+    pub(super) fn for_profile_chain(&self, chain: &[&str]) -> Option<&FilteringExpr> {
+        chain
+            .iter()
+            .find_map(|profile_name| self.other.get(*profile_name))
+            .or(self.default.as_ref())
+    }
+
+    fn compile_default_filter(
+        graph: &PackageGraph,
+        filter_sets: &FilterSets,
+        profile_name: &str,
+        filter: &str,
+        errors: &mut Vec<ConfigParseOverrideError>,
+    ) -> Option<FilteringExpr> {
+        match FilteringExpr::parse_with_sets(filter.to_owned(), graph, filter_sets) {
+            Ok(expr) => Some(expr),
+            Err(parse_errors) => {
+                errors.push(ConfigParseOverrideError {
+                    profile_name: profile_name.to_owned(),
+                    not_specified: false,
+                    platform_parse_error: None,
+                    parse_errors: Some(parse_errors),
+                });
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,224 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+use std::{fmt, time::Duration};
+
+/// Type for the `duration-format` config key.
+///
+/// This controls how durations are displayed by the reporter. Machine-readable output, such as
+/// JUnit reports, isn't affected.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct DurationFormat {
+    /// The unit to display durations in.
+    #[serde(default)]
+    pub unit: DurationUnit,
+
+    /// The number of digits to display after the decimal point.
+    #[serde(
+        default = "default_precision",
+        deserialize_with = "deserialize_precision"
+    )]
+    pub precision: u8,
+}
+
+impl DurationFormat {
+    /// The maximum supported precision: durations are tracked to the nanosecond.
+    pub const MAX_PRECISION: u8 = 9;
+
+    /// Returns a value that displays `duration` in this format.
+    ///
+    /// The returned value respects width and alignment, so it can be padded with e.g. `{:>9}`.
+    pub fn display(self, duration: Duration) -> DisplayDuration {
+        DisplayDuration {
+            format: self,
+            duration,
+        }
+    }
+}
+
+impl Default for DurationFormat {
+    fn default() -> Self {
+        Self {
+            unit: DurationUnit::default(),
+            precision: default_precision(),
+        }
+    }
+}
+
+/// The unit to display durations in, as part of a [`DurationFormat`].
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+pub enum DurationUnit {
+    /// Seconds.
+    #[default]
+    #[serde(rename = "s")]
+    Seconds,
+
+    /// Milliseconds.
+    #[serde(rename = "ms")]
+    Milliseconds,
+}
+
+impl DurationUnit {
+    fn suffix(self) -> &'static str {
+        match self {
+            Self::Seconds => "s",
+            Self::Milliseconds => "ms",
+        }
+    }
+
+    fn value(self, duration: Duration) -> f64 {
+        match self {
+            Self::Seconds => duration.as_secs_f64(),
+            Self::Milliseconds => duration.as_secs_f64() * 1000.0,
+        }
+    }
+}
+
+/// Displays a duration in a [`DurationFormat`].
+///
+/// Returned by [`DurationFormat::display`].
+#[derive(Clone, Copy, Debug)]
+pub struct DisplayDuration {
+    format: DurationFormat,
+    duration: Duration,
+}
+
+impl fmt::Display for DisplayDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = format!(
+            "{:.precision$}{}",
+            self.format.unit.value(self.duration),
+            self.format.unit.suffix(),
+            precision = self.format.precision as usize,
+        );
+        f.pad(&s)
+    }
+}
+
+fn default_precision() -> u8 {
+    3
+}
+
+fn deserialize_precision<'de, D>(deserializer: D) -> Result<u8, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let precision = u8::deserialize(deserializer)?;
+    if precision > DurationFormat::MAX_PRECISION {
+        return Err(serde::de::Error::invalid_value(
+            serde::de::Unexpected::Unsigned(precision.into()),
+            &"a precision between 0 and 9",
+        ));
+    }
+    Ok(precision)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{test_helpers::*, NextestConfig};
+    use camino_tempfile::tempdir;
+    use indoc::indoc;
+    use test_case::test_case;
+
+    #[test_case(
+        "",
+        Ok(DurationFormat { unit: DurationUnit::Seconds, precision: 3 })
+        ; "default"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            duration-format = { unit = "ms", precision = 0 }
+        "#},
+        Ok(DurationFormat { unit: DurationUnit::Milliseconds, precision: 0 })
+        ; "milliseconds"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            duration-format = { precision = 1 }
+        "#},
+        Ok(DurationFormat { unit: DurationUnit::Seconds, precision: 1 })
+        ; "precision only"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            duration-format = { unit = "ms" }
+        "#},
+        Ok(DurationFormat { unit: DurationUnit::Milliseconds, precision: 3 })
+        ; "unit only"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            duration-format = { precision = 10 }
+        "#},
+        Err("a precision between 0 and 9")
+        ; "precision too high"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            duration-format = { unit = "min" }
+        "#},
+        Err("unknown variant `min`")
+        ; "unknown unit"
+    )]
+    fn parse_duration_format(config_contents: &str, expected: Result<DurationFormat, &str>) {
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+
+        let config_result = NextestConfig::from_sources(graph.workspace().root(), &graph, None, []);
+        match expected {
+            Ok(expected) => {
+                let config = config_result.expect("config is valid");
+                let profile = config
+                    .profile(NextestConfig::DEFAULT_PROFILE)
+                    .expect("default profile exists")
+                    .apply_build_platforms(&build_platforms());
+                assert_eq!(profile.duration_format(), expected);
+            }
+            Err(message) => {
+                let error = config_result.expect_err("config is invalid");
+                let error_message = format!("{:?}", error);
+                assert!(
+                    error_message.contains(message),
+                    "expected error message to contain {message:?}, found: {error_message}"
+                );
+            }
+        }
+    }
+
+    #[test_case(DurationUnit::Seconds, 3, Duration::ZERO, "0.000s"; "zero")]
+    #[test_case(DurationUnit::Seconds, 3, Duration::from_nanos(1_234_567_800), "1.235s"; "seconds")]
+    #[test_case(DurationUnit::Seconds, 0, Duration::from_millis(1_499), "1s"; "seconds no decimals")]
+    #[test_case(DurationUnit::Seconds, 3, Duration::from_nanos(300), "0.000s"; "very short seconds")]
+    #[test_case(DurationUnit::Seconds, 9, Duration::from_nanos(300), "0.000000300s"; "nanosecond precision")]
+    #[test_case(DurationUnit::Seconds, 1, Duration::from_secs(4 * 3600 + 1), "14401.0s"; "very long seconds")]
+    #[test_case(DurationUnit::Milliseconds, 0, Duration::from_nanos(1_234_567_800), "1235ms"; "milliseconds")]
+    #[test_case(DurationUnit::Milliseconds, 2, Duration::from_micros(1_500), "1.50ms"; "milliseconds decimals")]
+    #[test_case(DurationUnit::Milliseconds, 0, Duration::from_micros(400), "0ms"; "very short milliseconds")]
+    #[test_case(DurationUnit::Milliseconds, 0, Duration::from_secs(4 * 3600), "14400000ms"; "very long milliseconds")]
+    fn display(unit: DurationUnit, precision: u8, duration: Duration, expected: &str) {
+        let format = DurationFormat { unit, precision };
+        assert_eq!(format.display(duration).to_string(), expected);
+    }
+
+    #[test]
+    fn display_padding() {
+        let format = DurationFormat::default();
+        assert_eq!(
+            format!("[{:>9}]", format.display(Duration::from_millis(1_500))),
+            "[   1.500s]"
+        );
+        // Durations that are too long for the width aren't truncated.
+        assert_eq!(
+            format!("[{:>9}]", format.display(Duration::from_secs(123_456))),
+            "[123456.000s]"
+        );
+    }
+}
@@ -0,0 +1,178 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+use std::{fmt, num::NonZeroUsize};
+
+/// Type for the `fail-fast` config key.
+///
+/// This is specified either as a boolean, or as a table with a `max-failures` key. `true` is the
+/// same as `max-failures = 1`, and `false` means that the run is never canceled because of test
+/// failures.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FailFast {
+    /// The number of test failures after which the run is canceled, or `None` if the run is
+    /// never canceled because of test failures.
+    pub max_failures: Option<NonZeroUsize>,
+}
+
+impl FailFast {
+    /// Returns true if a run that has seen `failed` test failures so far should be canceled.
+    pub fn should_cancel(&self, failed: usize) -> bool {
+        self.max_failures
+            .map_or(false, |max_failures| failed >= max_failures.get())
+    }
+}
+
+impl From<bool> for FailFast {
+    fn from(fail_fast: bool) -> Self {
+        Self {
+            max_failures: fail_fast.then(|| NonZeroUsize::new(1).unwrap()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct FailFastTable {
+    max_failures: NonZeroUsize,
+}
+
+pub(super) fn deserialize_fail_fast<'de, D>(deserializer: D) -> Result<Option<FailFast>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    struct V;
+
+    impl<'de2> serde::de::Visitor<'de2> for V {
+        type Value = Option<FailFast>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            write!(
+                formatter,
+                "a table ({{ max-failures = 5 }}) or a boolean (true or false)"
+            )
+        }
+
+        fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+        {
+            Ok(Some(v.into()))
+        }
+
+        fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+        where
+            A: serde::de::MapAccess<'de2>,
+        {
+            let table =
+                FailFastTable::deserialize(serde::de::value::MapAccessDeserializer::new(map))?;
+            Ok(Some(FailFast {
+                max_failures: Some(table.max_failures),
+            }))
+        }
+    }
+
+    deserializer.deserialize_any(V)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{test_helpers::*, NextestConfig};
+    use camino_tempfile::tempdir;
+    use indoc::indoc;
+    use test_case::test_case;
+
+    #[test_case(
+        "",
+        Ok(FailFast { max_failures: NonZeroUsize::new(1) })
+        ; "enabled by default"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            fail-fast = false
+        "#},
+        Ok(FailFast { max_failures: None })
+        ; "false is unlimited"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            fail-fast = true
+        "#},
+        Ok(FailFast { max_failures: NonZeroUsize::new(1) })
+        ; "true is one failure"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            fail-fast = { max-failures = 5 }
+        "#},
+        Ok(FailFast { max_failures: NonZeroUsize::new(5) })
+        ; "max failures"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            fail-fast = { max-failures = 0 }
+        "#},
+        Err("invalid value: integer `0`, expected a nonzero usize")
+        ; "zero max failures"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            fail-fast = { max-fail = 5 }
+        "#},
+        Err("unknown field `max-fail`")
+        ; "unknown field"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            fail-fast = "yes"
+        "#},
+        Err("expected a table ({ max-failures = 5 }) or a boolean (true or false)")
+        ; "invalid type"
+    )]
+    fn parse_fail_fast(config_contents: &str, expected: Result<FailFast, &str>) {
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+
+        let config_result = NextestConfig::from_sources(graph.workspace().root(), &graph, None, []);
+        match expected {
+            Ok(expected) => {
+                let config = config_result.expect("config is valid");
+                let profile = config
+                    .profile(NextestConfig::DEFAULT_PROFILE)
+                    .expect("default profile exists")
+                    .apply_build_platforms(&build_platforms());
+                assert_eq!(profile.fail_fast(), expected);
+            }
+            Err(message) => {
+                let error = config_result.expect_err("config is invalid");
+                let error_message = format!("{:?}", error);
+                assert!(
+                    error_message.contains(message),
+                    "expected error message to contain {message:?}, found: {error_message}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn should_cancel() {
+        let unlimited = FailFast::from(false);
+        assert!(!unlimited.should_cancel(0));
+        assert!(!unlimited.should_cancel(100));
+
+        let fail_fast = FailFast {
+            max_failures: NonZeroUsize::new(5),
+        };
+        assert!(!fail_fast.should_cancel(4));
+        assert!(fail_fast.should_cancel(5));
+        assert!(fail_fast.should_cancel(6));
+    }
+}
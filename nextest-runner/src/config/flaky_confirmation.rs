@@ -0,0 +1,68 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+use std::{num::NonZeroUsize, time::Duration};
+
+/// Type for the `flaky-confirmation` config key.
+///
+/// If set, tests that were flaky (failed, then passed on a retry) are re-run in isolation at the
+/// end of the test run, to measure how often they actually fail.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct FlakyConfirmation {
+    /// The number of times each flaky test is re-run.
+    pub runs: NonZeroUsize,
+
+    /// The maximum amount of time to spend on confirmation runs. Once this time has elapsed, no
+    /// further runs are started.
+    #[serde(with = "humantime_serde", default = "default_timeout")]
+    pub timeout: Duration,
+}
+
+fn default_timeout() -> Duration {
+    Duration::from_secs(300)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{test_helpers::*, NextestConfig};
+    use camino_tempfile::tempdir;
+    use indoc::indoc;
+    use test_case::test_case;
+
+    #[test_case(
+        "",
+        None
+        ; "not set by default"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            flaky-confirmation = { runs = 10 }
+        "#},
+        Some(FlakyConfirmation { runs: NonZeroUsize::new(10).unwrap(), timeout: Duration::from_secs(300) })
+        ; "default timeout"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            flaky-confirmation = { runs = 5, timeout = "1m" }
+        "#},
+        Some(FlakyConfirmation { runs: NonZeroUsize::new(5).unwrap(), timeout: Duration::from_secs(60) })
+        ; "custom timeout"
+    )]
+    fn parse_flaky_confirmation(config_contents: &str, expected: Option<FlakyConfirmation>) {
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+
+        let config = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect("config is valid");
+        let profile = config
+            .profile(NextestConfig::DEFAULT_PROFILE)
+            .expect("default profile exists")
+            .apply_build_platforms(&build_platforms());
+        assert_eq!(profile.flaky_confirmation(), expected);
+    }
+}
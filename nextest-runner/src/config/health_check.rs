@@ -0,0 +1,114 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use super::PreFlightCommand;
+use serde::Deserialize;
+use std::num::NonZeroUsize;
+
+/// Type for the health-check config key: a command run periodically while tests are running.
+///
+/// If the command fails, the test run is canceled.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct HealthCheck {
+    /// The command to run. This accepts the same values as [`PreFlightCommand`].
+    pub command: PreFlightCommand,
+
+    /// The command is run each time this many more tests have finished.
+    pub every: NonZeroUsize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{test_helpers::*, NextestConfig};
+    use camino_tempfile::tempdir;
+    use indoc::indoc;
+    use test_case::test_case;
+
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+        "#},
+        Ok(None)
+
+        ; "not specified"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            health-check = { command = "curl -sf localhost:8080/health", every = 50 }
+        "#},
+        Ok(Some(("curl", &["-sf", "localhost:8080/health"][..], 50)))
+
+        ; "string command"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            health-check = { command = ["sh", "-c", "pg_isready"], every = 1 }
+        "#},
+        Ok(Some(("sh", &["-c", "pg_isready"][..], 1)))
+
+        ; "list command"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            health-check = { command = "pg_isready", every = 0 }
+        "#},
+        Err(())
+
+        ; "zero every"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            health-check = { command = "pg_isready" }
+        "#},
+        Err(())
+
+        ; "missing every"
+    )]
+    fn parse_health_check(
+        config_contents: &str,
+        expected: Result<Option<(&str, &[&str], usize)>, ()>,
+    ) {
+        let workspace_dir = tempdir().unwrap();
+
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+
+        let config = NextestConfig::from_sources(graph.workspace().root(), &graph, None, []);
+        match expected {
+            Err(()) => assert!(config.is_err(), "config is invalid"),
+            Ok(expected) => {
+                let config = config.unwrap();
+                let profile = config
+                    .profile("custom")
+                    .unwrap()
+                    .apply_build_platforms(&build_platforms());
+                let health_check = profile.health_check().map(|health_check| {
+                    (
+                        health_check.command.program(),
+                        health_check.command.args(),
+                        health_check.every.get(),
+                    )
+                });
+                match (health_check, expected) {
+                    (None, None) => {}
+                    (
+                        Some((program, args, every)),
+                        Some((expected_program, expected_args, expected_every)),
+                    ) => {
+                        assert_eq!(program, expected_program);
+                        assert_eq!(args, expected_args);
+                        assert_eq!(every, expected_every);
+                    }
+                    (actual, expected) => {
+                        panic!("expected {expected:?}, found {actual:?}")
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,21 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+
+/// Type for the `junit.group-by` config key: how tests are grouped into `<testsuite>` elements in
+/// JUnit reports.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum JunitGroupBy {
+    /// There's one test suite for each test binary, named after the binary ID.
+    #[default]
+    Binary,
+
+    /// There's one test suite for each package, named after the package.
+    Package,
+
+    /// There's one test suite for each [test group](super::TestGroup), named after the group.
+    /// Tests that aren't in a custom group are in the `@global` test suite.
+    TestGroup,
+}
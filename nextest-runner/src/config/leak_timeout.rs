@@ -0,0 +1,166 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::{de::IntoDeserializer, Deserialize};
+use std::{fmt, time::Duration};
+
+/// Type for the leak-timeout config key.
+///
+/// This is specified either as a duration, or as a table with a `period` and optional `fail` and
+/// `output-grace-period` keys.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct LeakTimeout {
+    /// The time after which a test whose standard output and standard error haven't been closed
+    /// is marked as leaky.
+    #[serde(with = "humantime_serde")]
+    pub period: Duration,
+
+    /// Whether a test that passes but leaks handles is treated as failing.
+    #[serde(default)]
+    pub fail: bool,
+
+    /// How long to keep collecting output from a leaky test once it has been marked as leaky.
+    ///
+    /// Processes spawned by the test may keep writing to its standard output and standard error
+    /// after the test itself has exited. Output written within this period is captured. This
+    /// doesn't count towards the test's duration.
+    #[serde(default, with = "humantime_serde")]
+    pub output_grace_period: Duration,
+}
+
+pub(super) fn deserialize_leak_timeout<'de, D>(
+    deserializer: D,
+) -> Result<Option<LeakTimeout>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    struct V;
+
+    impl<'de2> serde::de::Visitor<'de2> for V {
+        type Value = Option<LeakTimeout>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            write!(
+                formatter,
+                "a table ({{ period = \"100ms\", fail = true }}) or a string (\"100ms\")"
+            )
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+        {
+            let period = humantime_serde::deserialize(v.into_deserializer())?;
+            Ok(Some(LeakTimeout {
+                period,
+                fail: false,
+                output_grace_period: Duration::ZERO,
+            }))
+        }
+
+        fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+        where
+            A: serde::de::MapAccess<'de2>,
+        {
+            LeakTimeout::deserialize(serde::de::value::MapAccessDeserializer::new(map)).map(Some)
+        }
+    }
+
+    deserializer.deserialize_any(V)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{test_helpers::*, NextestConfig};
+    use camino_tempfile::tempdir;
+    use indoc::indoc;
+    use test_case::test_case;
+
+    #[test_case(
+        "",
+        Ok(LeakTimeout { period: Duration::from_millis(100), fail: false, output_grace_period: Duration::ZERO })
+        ; "default"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            leak-timeout = "200ms"
+        "#},
+        Ok(LeakTimeout { period: Duration::from_millis(200), fail: false, output_grace_period: Duration::ZERO })
+        ; "string"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            leak-timeout = { period = "300ms", fail = true }
+        "#},
+        Ok(LeakTimeout { period: Duration::from_millis(300), fail: true, output_grace_period: Duration::ZERO })
+        ; "table"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            leak-timeout = { period = "300ms" }
+        "#},
+        Ok(LeakTimeout { period: Duration::from_millis(300), fail: false, output_grace_period: Duration::ZERO })
+        ; "table without fail"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            leak-timeout = { period = "300ms", output-grace-period = "2s" }
+        "#},
+        Ok(LeakTimeout { period: Duration::from_millis(300), fail: false, output_grace_period: Duration::from_secs(2) })
+        ; "table with output grace period"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            leak-timeout = { fail = true }
+        "#},
+        Err("missing field `period`")
+        ; "table without period"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            leak-timeout = { period = "300ms", result = "fail" }
+        "#},
+        Err("unknown field `result`")
+        ; "unknown field"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            leak-timeout = 100
+        "#},
+        Err("expected a table ({ period = \"100ms\", fail = true }) or a string (\"100ms\")")
+        ; "invalid type"
+    )]
+    fn parse_leak_timeout(config_contents: &str, expected: Result<LeakTimeout, &str>) {
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+
+        let config_result = NextestConfig::from_sources(graph.workspace().root(), &graph, None, []);
+        match expected {
+            Ok(expected) => {
+                let config = config_result.expect("config is valid");
+                let profile = config
+                    .profile(NextestConfig::DEFAULT_PROFILE)
+                    .expect("default profile exists")
+                    .apply_build_platforms(&build_platforms());
+                assert_eq!(profile.leak_timeout(), expected);
+            }
+            Err(message) => {
+                let error = config_result.expect_err("config is invalid");
+                let error_message = format!("{:?}", error);
+                assert!(
+                    error_message.contains(message),
+                    "expected error message to contain {message:?}, found: {error_message}"
+                );
+            }
+        }
+    }
+}
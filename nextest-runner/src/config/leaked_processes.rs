@@ -0,0 +1,190 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use regex::Regex;
+use serde::Deserialize;
+use std::fmt;
+
+/// Type for the `detect-leaked-processes` config key.
+///
+/// If enabled, nextest checks for processes started by each test that are still running after the
+/// test finishes, and reports them along with their command lines once all tests have finished.
+///
+/// This is specified either as a boolean, or as a table with an `ignore` key, which enables
+/// detection. `ignore` is a list of regular expressions: leaked processes whose command lines
+/// match any of them aren't reported, for example because they're intentionally daemonized.
+#[derive(Clone, Debug, Default)]
+pub struct LeakedProcesses {
+    /// Whether to check for leaked processes after each test finishes.
+    pub enabled: bool,
+
+    /// Regular expressions matching the command lines of processes that aren't reported.
+    pub ignore: Vec<Regex>,
+}
+
+impl LeakedProcesses {
+    /// Returns true if a leaked process with the given command line shouldn't be reported.
+    pub fn is_ignored(&self, command: &str) -> bool {
+        self.ignore.iter().any(|regex| regex.is_match(command))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct LeakedProcessesTable {
+    #[serde(default)]
+    ignore: Vec<String>,
+}
+
+pub(super) fn deserialize_leaked_processes<'de, D>(
+    deserializer: D,
+) -> Result<Option<LeakedProcesses>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    struct V;
+
+    impl<'de2> serde::de::Visitor<'de2> for V {
+        type Value = Option<LeakedProcesses>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            write!(
+                formatter,
+                "a table ({{ ignore = [\"^my-daemon \"] }}) or a boolean (true or false)"
+            )
+        }
+
+        fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+        {
+            Ok(Some(LeakedProcesses {
+                enabled: v,
+                ignore: Vec::new(),
+            }))
+        }
+
+        fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+        where
+            A: serde::de::MapAccess<'de2>,
+        {
+            let table = LeakedProcessesTable::deserialize(
+                serde::de::value::MapAccessDeserializer::new(map),
+            )?;
+            let ignore = table
+                .ignore
+                .iter()
+                .map(|pattern| {
+                    Regex::new(pattern).map_err(|error| {
+                        serde::de::Error::custom(format!(
+                            "invalid regex `{pattern}` in ignore: {error}"
+                        ))
+                    })
+                })
+                .collect::<Result<_, _>>()?;
+            Ok(Some(LeakedProcesses {
+                enabled: true,
+                ignore,
+            }))
+        }
+    }
+
+    deserializer.deserialize_any(V)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{test_helpers::*, NextestConfig};
+    use camino_tempfile::tempdir;
+    use indoc::indoc;
+    use test_case::test_case;
+
+    #[test_case(
+        "",
+        Ok((false, &[]))
+        ; "disabled by default"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            detect-leaked-processes = true
+        "#},
+        Ok((true, &[]))
+        ; "enabled"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            detect-leaked-processes = { ignore = ["^sccache", "my-daemon --foreground"] }
+        "#},
+        Ok((true, &["^sccache", "my-daemon --foreground"]))
+        ; "table"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            detect-leaked-processes = { ignore = ["("] }
+        "#},
+        Err("invalid regex `(` in ignore")
+        ; "invalid regex"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            detect-leaked-processes = { allow = [] }
+        "#},
+        Err("unknown field `allow`")
+        ; "unknown field"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            detect-leaked-processes = "yes"
+        "#},
+        Err("or a boolean (true or false)")
+        ; "invalid type"
+    )]
+    fn parse_leaked_processes(config_contents: &str, expected: Result<(bool, &[&str]), &str>) {
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+
+        let config_result = NextestConfig::from_sources(graph.workspace().root(), &graph, None, []);
+        match expected {
+            Ok((enabled, ignore)) => {
+                let config = config_result.expect("config is valid");
+                let profile = config
+                    .profile(NextestConfig::DEFAULT_PROFILE)
+                    .expect("default profile exists")
+                    .apply_build_platforms(&build_platforms());
+                let leaked_processes = profile.leaked_processes();
+                assert_eq!(leaked_processes.enabled, enabled);
+                let patterns: Vec<_> = leaked_processes
+                    .ignore
+                    .iter()
+                    .map(|regex| regex.as_str())
+                    .collect();
+                assert_eq!(patterns, ignore);
+            }
+            Err(message) => {
+                let error = config_result.expect_err("config is invalid");
+                let error_message = format!("{:?}", error);
+                assert!(
+                    error_message.contains(message),
+                    "expected error message to contain {message:?}, found: {error_message}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn is_ignored() {
+        let leaked_processes = LeakedProcesses {
+            enabled: true,
+            ignore: vec![Regex::new("^sccache").unwrap()],
+        };
+        assert!(leaked_processes.is_ignored("sccache --start-server"));
+        assert!(!leaked_processes.is_ignored("/usr/bin/sccache"));
+        assert!(!LeakedProcesses::default().is_ignored("sccache"));
+    }
+}
@@ -0,0 +1,166 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use super::NextestConfig;
+use crate::errors::{provided_by_tool, ConfigParseErrorKind};
+use camino::Utf8Path;
+use config::{Config, File, FileFormat, FileSourceFile, Map, Source, Value, ValueKind};
+use std::collections::BTreeMap;
+
+/// Profile settings that tool config files have locked, so that higher-priority config files can't
+/// change them.
+///
+/// A tool locks a setting by specifying it as a table with `locked = true`, for example
+/// `slow-timeout = { period = "60s", locked = true }`. A setting locked in the default profile is
+/// also locked in every other profile.
+///
+/// Locked settings also can't be changed through per-test overrides in higher-priority files, since
+/// an override matching every test would otherwise have the same effect.
+#[derive(Clone, Debug, Default)]
+pub(super) struct LockedSettings {
+    // Maps (profile name, setting name) to the tool that locked the setting.
+    locked: BTreeMap<(String, String), String>,
+}
+
+impl LockedSettings {
+    /// Reads `source`, removing any settings that were locked by lower-priority config files, and
+    /// if the file is provided by a tool, recording the settings that it locks.
+    ///
+    /// Returns the config along with a warning for each setting that was removed, for the caller
+    /// to report.
+    pub(super) fn apply(
+        &mut self,
+        source: File<FileSourceFile, FileFormat>,
+        config_file: &Utf8Path,
+        tool: Option<&str>,
+    ) -> Result<(Config, Vec<String>), ConfigParseErrorKind> {
+        let config = Config::builder()
+            .add_source(source)
+            .build()
+            .map_err(|error| ConfigParseErrorKind::BuildError(Box::new(error)))?;
+        let mut root = config
+            .collect()
+            .map_err(|error| ConfigParseErrorKind::BuildError(Box::new(error)))?;
+        let Some(ValueKind::Table(profiles)) = root.get_mut("profile").map(|value| &mut value.kind)
+        else {
+            return Ok((config, Vec::new()));
+        };
+
+        let mut warnings = Vec::new();
+        let mut changed = false;
+        for (profile_name, profile) in profiles.iter_mut() {
+            let ValueKind::Table(settings) = &mut profile.kind else {
+                continue;
+            };
+            for (setting, locking_tool) in self.ignored_settings(profile_name, settings) {
+                settings.remove(&setting);
+                changed = true;
+                warnings.push(format!(
+                    "ignoring `profile.{profile_name}.{setting}` in config file \
+                     {config_file}{}: it is locked by tool `{locking_tool}`",
+                    provided_by_tool(tool),
+                ));
+            }
+
+            let Some(ValueKind::Array(overrides)) =
+                settings.get_mut("overrides").map(|value| &mut value.kind)
+            else {
+                continue;
+            };
+            for (index, override_) in overrides.iter_mut().enumerate() {
+                let ValueKind::Table(override_settings) = &mut override_.kind else {
+                    continue;
+                };
+                for (setting, locking_tool) in
+                    self.ignored_settings(profile_name, override_settings)
+                {
+                    override_settings.remove(&setting);
+                    changed = true;
+                    warnings.push(format!(
+                        "ignoring `{setting}` in override {index} of profile `{profile_name}` in \
+                         config file {config_file}{}: it is locked by tool `{locking_tool}`",
+                        provided_by_tool(tool),
+                    ));
+                }
+            }
+        }
+
+        // Locks only apply to higher-priority files, so they're recorded once all the settings in
+        // this file have been checked.
+        if let Some(tool) = tool {
+            for (profile_name, profile) in profiles.iter_mut() {
+                if let ValueKind::Table(settings) = &mut profile.kind {
+                    changed |= self.lock_settings(tool, profile_name, settings);
+                }
+            }
+        }
+
+        if !changed {
+            return Ok((config, warnings));
+        }
+
+        // Rebuild the config from the modified values. These keep track of where they came from,
+        // so errors still point to the original file.
+        root.into_iter()
+            .try_fold(Config::builder(), |builder, (key, value)| {
+                builder.set_override(key, value)
+            })
+            .and_then(|builder| builder.build())
+            .map(|config| (config, warnings))
+            .map_err(|error| ConfigParseErrorKind::BuildError(Box::new(error)))
+    }
+
+    /// Returns the tool that locked `setting` for `profile_name`, if any.
+    pub(super) fn locking_tool(&self, profile_name: &str, setting: &str) -> Option<&str> {
+        [profile_name, NextestConfig::DEFAULT_PROFILE]
+            .into_iter()
+            .find_map(|name| self.locked.get(&(name.to_owned(), setting.to_owned())))
+            .map(|tool| tool.as_str())
+    }
+
+    /// Returns the settings in `settings` that are locked for `profile_name`, along with the tools
+    /// that locked them.
+    fn ignored_settings(
+        &self,
+        profile_name: &str,
+        settings: &Map<String, Value>,
+    ) -> Vec<(String, String)> {
+        settings
+            .keys()
+            .filter_map(|setting| {
+                let locking_tool = self.locking_tool(profile_name, setting)?;
+                Some((setting.clone(), locking_tool.to_owned()))
+            })
+            .collect()
+    }
+
+    /// Records the settings in `settings` marked with `locked = true`, and removes the `locked`
+    /// key so that the settings can be deserialized as usual. Returns true if any `locked` keys
+    /// were removed.
+    fn lock_settings(
+        &mut self,
+        tool: &str,
+        profile_name: &str,
+        settings: &mut Map<String, Value>,
+    ) -> bool {
+        let mut changed = false;
+        for (setting, value) in settings.iter_mut() {
+            let ValueKind::Table(table) = &mut value.kind else {
+                continue;
+            };
+            // A `locked` key that isn't a boolean is left in place, and reported as an unknown key.
+            let Some(Ok(locked)) = table.get("locked").map(|locked| locked.clone().into_bool())
+            else {
+                continue;
+            };
+
+            table.remove("locked");
+            changed = true;
+            if locked {
+                self.locked
+                    .insert((profile_name.to_owned(), setting.clone()), tool.to_owned());
+            }
+        }
+        changed
+    }
+}
@@ -0,0 +1,210 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+use std::fmt;
+
+/// Type for the max-output-bytes config key.
+///
+/// This controls how much of the captured standard output and standard error of a test is
+/// displayed by the reporter.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MaxOutputBytes {
+    /// Display at most this many bytes of each output stream.
+    Limited(usize),
+
+    /// Display all output.
+    Unlimited,
+}
+
+impl MaxOutputBytes {
+    /// Returns the limit in bytes, or `None` if output isn't limited.
+    pub fn limit(self) -> Option<usize> {
+        match self {
+            Self::Limited(limit) => Some(limit),
+            Self::Unlimited => None,
+        }
+    }
+}
+
+/// Parses a size like `65536`, `64KiB` or `1.5 MB` into a number of bytes.
+fn parse_size(s: &str) -> Option<usize> {
+    let s = s.trim();
+    let unit_start = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(unit_start);
+    let multiplier: u64 = match unit.trim_start() {
+        "" | "B" => 1,
+        "KB" | "kB" => 1000,
+        "KiB" => 1 << 10,
+        "MB" => 1000 * 1000,
+        "MiB" => 1 << 20,
+        "GB" => 1000 * 1000 * 1000,
+        "GiB" => 1 << 30,
+        _ => return None,
+    };
+
+    if number.contains('.') {
+        let value: f64 = number.parse().ok()?;
+        let bytes = (value * multiplier as f64).round();
+        (bytes.is_finite() && bytes <= usize::MAX as f64).then_some(bytes as usize)
+    } else {
+        let value: u64 = number.parse().ok()?;
+        value
+            .checked_mul(multiplier)
+            .and_then(|bytes| usize::try_from(bytes).ok())
+    }
+}
+
+impl<'de> Deserialize<'de> for MaxOutputBytes {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct V;
+
+        impl<'de2> serde::de::Visitor<'de2> for V {
+            type Value = MaxOutputBytes;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(
+                    formatter,
+                    "a positive integer, a size such as \"64KiB\", or the string \"unlimited\""
+                )
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                if v == "unlimited" {
+                    return Ok(MaxOutputBytes::Unlimited);
+                }
+                match parse_size(v) {
+                    Some(bytes) if bytes > 0 => Ok(MaxOutputBytes::Limited(bytes)),
+                    _ => Err(serde::de::Error::invalid_value(
+                        serde::de::Unexpected::Str(v),
+                        &self,
+                    )),
+                }
+            }
+
+            // Note that TOML uses i64, not u64.
+            fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                if v > 0 {
+                    Ok(MaxOutputBytes::Limited(v as usize))
+                } else {
+                    Err(serde::de::Error::invalid_value(
+                        serde::de::Unexpected::Signed(v),
+                        &self,
+                    ))
+                }
+            }
+        }
+
+        deserializer.deserialize_any(V)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{test_helpers::*, NextestConfig};
+    use camino_tempfile::tempdir;
+    use indoc::indoc;
+    use test_case::test_case;
+
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+        "#},
+        Some(MaxOutputBytes::Unlimited)
+
+        ; "default"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            max-output-bytes = 4096
+        "#},
+        Some(MaxOutputBytes::Limited(4096))
+
+        ; "integer"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            max-output-bytes = "64KiB"
+        "#},
+        Some(MaxOutputBytes::Limited(64 * 1024))
+
+        ; "kibibytes"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            max-output-bytes = "1.5 MB"
+        "#},
+        Some(MaxOutputBytes::Limited(1_500_000))
+
+        ; "fractional megabytes with space"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            max-output-bytes = "unlimited"
+        "#},
+        Some(MaxOutputBytes::Unlimited)
+
+        ; "unlimited"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            max-output-bytes = 0
+        "#},
+        None
+
+        ; "zero"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            max-output-bytes = "64 potatoes"
+        "#},
+        None
+
+        ; "unknown unit"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            max-output-bytes = "KiB"
+        "#},
+        None
+
+        ; "missing number"
+    )]
+    fn parse_max_output_bytes(config_contents: &str, expected: Option<MaxOutputBytes>) {
+        let workspace_dir = tempdir().unwrap();
+
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+
+        let config = NextestConfig::from_sources(graph.workspace().root(), &graph, None, []);
+        match expected {
+            None => assert!(config.is_err()),
+            Some(expected) => {
+                let config = config.unwrap();
+                let profile = config
+                    .profile("custom")
+                    .unwrap()
+                    .apply_build_platforms(&build_platforms());
+                assert_eq!(profile.max_output_bytes(), expected);
+            }
+        }
+    }
+}
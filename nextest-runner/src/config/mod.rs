@@ -3,25 +3,73 @@
 
 //! Configuration support for nextest.
 
+mod canonical_env;
 mod config_impl;
+mod default_filter;
+mod duration_format;
+mod fail_fast;
+mod flaky_confirmation;
+mod health_check;
 mod identifier;
+mod junit_group_by;
+mod leak_timeout;
+mod leaked_processes;
+mod locked_settings;
+mod max_output_bytes;
+mod outcome_source;
+mod override_resolution;
 mod overrides;
+mod pre_flight;
+mod profile_env;
+mod quarantine;
+mod reports;
 mod retry_policy;
+mod settings_explanation;
 mod slow_timeout;
+mod stdin_mode;
+mod store_discriminator;
+mod success_signals;
+mod target_runner_rule;
+mod test_expectation;
 mod test_group;
+mod test_order;
+mod test_suite;
 mod test_threads;
 mod threads_required;
 mod tool_config;
+mod tracer;
 
+pub use canonical_env::*;
 pub use config_impl::*;
+pub use duration_format::*;
+pub use fail_fast::*;
+pub use flaky_confirmation::*;
+pub use health_check::*;
 pub use identifier::*;
+pub use junit_group_by::*;
+pub use leak_timeout::*;
+pub use leaked_processes::*;
+pub use max_output_bytes::*;
+pub use outcome_source::*;
+pub use override_resolution::*;
 pub use overrides::*;
+pub use pre_flight::*;
+pub use reports::*;
 pub use retry_policy::*;
+pub use settings_explanation::*;
 pub use slow_timeout::*;
+pub use stdin_mode::*;
+pub use store_discriminator::*;
+pub use success_signals::*;
+pub use target_runner_rule::*;
+pub use test_expectation::*;
 pub use test_group::*;
+pub use test_order::*;
+pub use test_suite::*;
 pub use test_threads::*;
 pub use threads_required::*;
 pub use tool_config::*;
+pub use tracer::*;
 
 #[cfg(test)]
 mod test_helpers;
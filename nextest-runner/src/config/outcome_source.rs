@@ -0,0 +1,19 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+
+/// Type for the `outcome-source` config key: what determines whether a test passed.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutcomeSource {
+    /// The test passes if it exits with code 0.
+    #[default]
+    ExitCode,
+
+    /// The test's standard output is parsed as [TAP](https://testanything.org/), and the test passes
+    /// if it exits with code 0 and has no failing test points.
+    ///
+    /// If the output isn't valid TAP, a warning is printed and the exit code is used instead.
+    Tap,
+}
@@ -0,0 +1,31 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+
+/// Type for the `override-resolution` config key: how settings are resolved for a test matched by
+/// several overrides.
+///
+/// Overrides are always considered in order of precedence: overrides for a custom profile come
+/// before those for the default profile, and within a profile, overrides in the workspace's
+/// config come before those in tool configs. The policy only determines how overrides within this
+/// order are combined.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OverrideResolution {
+    /// All matching overrides are layered, and for each setting, the first override that sets it
+    /// wins.
+    #[default]
+    FirstWins,
+
+    /// All matching overrides are layered, and for each setting, the last override within a
+    /// config file that sets it wins.
+    ///
+    /// Overrides in a higher-precedence profile or config file still win over those in lower-
+    /// precedence ones.
+    LastWins,
+
+    /// Only the first matching override is used. Settings it doesn't set are taken from the
+    /// profile.
+    FirstMatch,
+}
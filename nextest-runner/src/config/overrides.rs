@@ -3,16 +3,21 @@
 
 use super::{NextestConfigImpl, NextestProfile};
 use crate::{
-    config::{FinalConfig, PreBuildPlatform, RetryPolicy, SlowTimeout, TestGroup, ThreadsRequired},
+    config::{
+        CanonicalEnv, FinalConfig, LeakTimeout, OutcomeSource, OverrideResolution,
+        PreBuildPlatform, RetryPolicy, SlowTimeout, StdinMode, SuccessSignals, TestExpectation,
+        TestGroup, ThreadsRequired, TracerCommand,
+    },
     errors::{ConfigParseErrorKind, ConfigParseOverrideError},
     platform::BuildPlatforms,
     reporter::TestOutputDisplay,
 };
+use camino::{Utf8Path, Utf8PathBuf};
 use guppy::graph::{cargo::BuildPlatform, PackageGraph};
-use nextest_filtering::{FilteringExpr, TestQuery};
+use nextest_filtering::{CompiledExpr, FilterSets, FilteringExpr, FilteringSet, TestQuery};
 use serde::Deserialize;
 use smol_str::SmolStr;
-use std::{collections::HashMap, time::Duration};
+use std::collections::{BTreeSet, HashMap};
 use target_spec::TargetSpec;
 
 /// Settings for individual tests.
@@ -24,18 +29,34 @@ use target_spec::TargetSpec;
 #[derive(Clone, Debug)]
 pub struct TestSettings<Source = ()> {
     threads_required: (ThreadsRequired, Source),
+    exclusive: (bool, Source),
+    priority: (i32, Source),
     retries: (RetryPolicy, Source),
+    retry_on_exit_codes: (Vec<i32>, Source),
     slow_timeout: (SlowTimeout, Source),
-    leak_timeout: (Duration, Source),
+    leak_timeout: (LeakTimeout, Source),
+    outcome_source: (OutcomeSource, Source),
+    expect: (TestExpectation, Source),
+    success_signals: (SuccessSignals, Source),
+    wait_for_process_group: (bool, Source),
+    detect_no_assertions: (bool, Source),
+    capture: (bool, Source),
+    pty: (bool, Source),
+    stdin: (StdinMode, Source),
+    canonical_env: (CanonicalEnv, Source),
+    cwd: (Option<Utf8PathBuf>, Source),
+    tracer: (Option<TracerCommand>, Source),
     test_group: (TestGroup, Source),
     success_output: (TestOutputDisplay, Source),
     failure_output: (TestOutputDisplay, Source),
     junit_store_success_output: (bool, Source),
     junit_store_failure_output: (bool, Source),
+    quarantined: (bool, Source),
 }
 
 pub(crate) trait TrackSource<'p>: Sized {
     fn track_profile<T>(value: T) -> (T, Self);
+    fn track_test_group<T>(value: T) -> (T, Self);
     fn track_override<T>(value: T, source: &'p CompiledOverride<FinalConfig>) -> (T, Self);
 }
 
@@ -44,6 +65,10 @@ impl<'p> TrackSource<'p> for () {
         (value, ())
     }
 
+    fn track_test_group<T>(value: T) -> (T, Self) {
+        (value, ())
+    }
+
     fn track_override<T>(value: T, _source: &'p CompiledOverride<FinalConfig>) -> (T, Self) {
         (value, ())
     }
@@ -52,6 +77,7 @@ impl<'p> TrackSource<'p> for () {
 #[derive(Copy, Clone, Debug)]
 pub(crate) enum SettingSource<'p> {
     Profile,
+    TestGroup,
     Override(&'p CompiledOverride<FinalConfig>),
 }
 
@@ -60,6 +86,10 @@ impl<'p> TrackSource<'p> for SettingSource<'p> {
         (value, SettingSource::Profile)
     }
 
+    fn track_test_group<T>(value: T) -> (T, Self) {
+        (value, SettingSource::TestGroup)
+    }
+
     fn track_override<T>(value: T, source: &'p CompiledOverride<FinalConfig>) -> (T, Self) {
         (value, SettingSource::Override(source))
     }
@@ -71,21 +101,104 @@ impl TestSettings {
         self.threads_required.0
     }
 
+    /// Returns true if this test must run with no other tests running at the same time.
+    pub fn exclusive(&self) -> bool {
+        self.exclusive.0
+    }
+
+    /// Returns the priority of this test.
+    ///
+    /// Tests with a higher priority are started before tests with a lower priority.
+    pub fn priority(&self) -> i32 {
+        self.priority.0
+    }
+
     /// Returns the number of retries for this test.
     pub fn retries(&self) -> RetryPolicy {
         self.retries.0
     }
 
+    /// Returns the exit codes that failing attempts of this test are retried on.
+    ///
+    /// If empty, failing attempts are retried regardless of how the test failed.
+    pub fn retry_on_exit_codes(&self) -> &[i32] {
+        &self.retry_on_exit_codes.0
+    }
+
     /// Returns the slow timeout for this test.
     pub fn slow_timeout(&self) -> SlowTimeout {
         self.slow_timeout.0
     }
 
     /// Returns the leak timeout for this test.
-    pub fn leak_timeout(&self) -> Duration {
+    pub fn leak_timeout(&self) -> LeakTimeout {
         self.leak_timeout.0
     }
 
+    /// Returns what determines whether this test passes.
+    pub fn outcome_source(&self) -> OutcomeSource {
+        self.outcome_source.0
+    }
+
+    /// Returns the outcome this test is expected to have.
+    pub fn expect(&self) -> TestExpectation {
+        self.expect.0
+    }
+
+    /// Returns the termination signals that are treated as this test passing.
+    pub fn success_signals(&self) -> &SuccessSignals {
+        &self.success_signals.0
+    }
+
+    /// Returns whether to wait for this test's entire process group to exit.
+    pub fn wait_for_process_group(&self) -> bool {
+        self.wait_for_process_group.0
+    }
+
+    /// Returns whether to flag this test if it passes, but reports running no assertions.
+    pub fn detect_no_assertions(&self) -> bool {
+        self.detect_no_assertions.0
+    }
+
+    /// Returns whether this test's standard output and standard error are captured.
+    ///
+    /// If this is false, the test inherits nextest's standard output and standard error, and is
+    /// run with no other tests running at the same time.
+    pub fn capture(&self) -> bool {
+        self.capture.0
+    }
+
+    /// Returns whether this test is run attached to a pseudo-terminal.
+    ///
+    /// If this is true and the test's output is captured, the test's standard output and standard
+    /// error are both attached to the terminal, and their combined output is captured as standard
+    /// output.
+    pub fn pty(&self) -> bool {
+        self.pty.0
+    }
+
+    /// Returns what this test's standard input is connected to.
+    pub fn stdin(&self) -> StdinMode {
+        self.stdin.0
+    }
+
+    /// Returns the canonical terminal and locale environment for this test.
+    pub fn canonical_env(&self) -> &CanonicalEnv {
+        &self.canonical_env.0
+    }
+
+    /// Returns the working directory to run this test in, if it's been set through an override.
+    ///
+    /// If this is `None`, the test is run in the directory of the package it's in.
+    pub fn cwd(&self) -> Option<&Utf8Path> {
+        self.cwd.0.as_deref()
+    }
+
+    /// Returns the tracer to wrap this test with, if it's been set through an override.
+    pub fn tracer(&self) -> Option<&TracerCommand> {
+        self.tracer.0.as_ref()
+    }
+
     /// Returns the test group for this test.
     pub fn test_group(&self) -> &TestGroup {
         &self.test_group.0
@@ -110,6 +223,13 @@ impl TestSettings {
     pub fn junit_store_failure_output(&self) -> bool {
         self.junit_store_failure_output.0
     }
+
+    /// Returns true if this test is quarantined through the profile's `quarantine` list.
+    ///
+    /// Quarantined tests are reported as skipped rather than being run.
+    pub fn quarantined(&self) -> bool {
+        self.quarantined.0
+    }
 }
 
 #[allow(dead_code)]
@@ -122,40 +242,56 @@ impl<Source: Copy> TestSettings<Source> {
         Source: TrackSource<'p>,
     {
         let mut threads_required = None;
+        let mut exclusive = None;
+        let mut priority = None;
         let mut retries = None;
+        let mut retry_on_exit_codes = None;
         let mut slow_timeout = None;
         let mut leak_timeout = None;
+        let mut outcome_source = None;
+        let mut expect = None;
+        let mut success_signals = None;
+        let mut wait_for_process_group = None;
+        let mut detect_no_assertions = None;
+        let mut capture = None;
+        let mut pty = None;
+        let mut stdin = None;
+        let mut canonical_env = None;
+        let mut cwd = None;
+        let mut tracer = None;
         let mut test_group = None;
         let mut success_output = None;
         let mut failure_output = None;
         let mut junit_store_success_output = None;
         let mut junit_store_failure_output = None;
 
-        for override_ in &profile.overrides {
-            if query.binary_query.platform == BuildPlatform::Host && !override_.state.host_eval {
-                continue;
-            }
-            if query.binary_query.platform == BuildPlatform::Target && !override_.state.target_eval
-            {
-                continue;
-            }
-
-            if let Some(expr) = &override_.data.expr {
-                if !expr.matches_test(query) {
-                    continue;
-                }
-                // If no expression is present, it's equivalent to "all()".
-            }
+        // Overrides are arranged so that for each setting, the first override that sets it wins.
+        for override_ in Self::resolution_order(profile, query) {
             if threads_required.is_none() {
                 if let Some(t) = override_.data.threads_required {
                     threads_required = Some(Source::track_override(t, override_));
                 }
             }
+            if exclusive.is_none() {
+                if let Some(e) = override_.data.exclusive {
+                    exclusive = Some(Source::track_override(e, override_));
+                }
+            }
+            if priority.is_none() {
+                if let Some(p) = override_.data.priority {
+                    priority = Some(Source::track_override(p, override_));
+                }
+            }
             if retries.is_none() {
                 if let Some(r) = override_.data.retries {
                     retries = Some(Source::track_override(r, override_));
                 }
             }
+            if retry_on_exit_codes.is_none() {
+                if let Some(r) = &override_.data.retry_on_exit_codes {
+                    retry_on_exit_codes = Some(Source::track_override(r.clone(), override_));
+                }
+            }
             if slow_timeout.is_none() {
                 if let Some(s) = override_.data.slow_timeout {
                     slow_timeout = Some(Source::track_override(s, override_));
@@ -166,6 +302,63 @@ impl<Source: Copy> TestSettings<Source> {
                     leak_timeout = Some(Source::track_override(l, override_));
                 }
             }
+            if outcome_source.is_none() {
+                if let Some(o) = override_.data.outcome_source {
+                    outcome_source = Some(Source::track_override(o, override_));
+                }
+            }
+            if expect.is_none() {
+                if let Some(e) = override_.data.expect {
+                    expect = Some(Source::track_override(e, override_));
+                }
+            }
+            if success_signals.is_none() {
+                if let Some(s) = &override_.data.success_signals {
+                    success_signals = Some(Source::track_override(s.clone(), override_));
+                }
+            }
+            if wait_for_process_group.is_none() {
+                if let Some(w) = override_.data.wait_for_process_group {
+                    wait_for_process_group = Some(Source::track_override(w, override_));
+                }
+            }
+            if detect_no_assertions.is_none() {
+                if let Some(d) = override_.data.detect_no_assertions {
+                    detect_no_assertions = Some(Source::track_override(d, override_));
+                }
+            }
+            if capture.is_none() {
+                if let Some(c) = override_.data.capture {
+                    capture = Some(Source::track_override(c, override_));
+                }
+            }
+            if pty.is_none() {
+                if let Some(p) = override_.data.pty {
+                    pty = Some(Source::track_override(p, override_));
+                }
+            }
+            if stdin.is_none() {
+                if let Some(s) = override_.data.stdin {
+                    stdin = Some(Source::track_override(s, override_));
+                }
+            }
+            if canonical_env.is_none() {
+                if let Some(c) = &override_.data.canonical_env {
+                    canonical_env = Some(Source::track_override(c.clone(), override_));
+                }
+            }
+            if cwd.is_none() {
+                if let Some(c) = &override_.data.cwd {
+                    // The working directory is relative to the workspace root.
+                    let c = profile.workspace_root().join(c);
+                    cwd = Some(Source::track_override(Some(c), override_));
+                }
+            }
+            if tracer.is_none() {
+                if let Some(t) = &override_.data.tracer {
+                    tracer = Some(Source::track_override(Some(t.clone()), override_));
+                }
+            }
             if test_group.is_none() {
                 if let Some(t) = &override_.data.test_group {
                     test_group = Some(Source::track_override(t.clone(), override_));
@@ -196,12 +389,53 @@ impl<Source: Copy> TestSettings<Source> {
         // If no overrides were found, use the profile defaults.
         let threads_required =
             threads_required.unwrap_or_else(|| Source::track_profile(profile.threads_required()));
+        let exclusive = exclusive.unwrap_or_else(|| Source::track_profile(false));
+        let priority = priority.unwrap_or_else(|| Source::track_profile(0));
         let retries = retries.unwrap_or_else(|| Source::track_profile(profile.retries()));
-        let slow_timeout =
-            slow_timeout.unwrap_or_else(|| Source::track_profile(profile.slow_timeout()));
+        let retry_on_exit_codes =
+            retry_on_exit_codes.unwrap_or_else(|| Source::track_profile(Vec::new()));
         let leak_timeout =
             leak_timeout.unwrap_or_else(|| Source::track_profile(profile.leak_timeout()));
-        let test_group = test_group.unwrap_or_else(|| Source::track_profile(TestGroup::Global));
+        let outcome_source =
+            outcome_source.unwrap_or_else(|| Source::track_profile(profile.outcome_source()));
+        let expect = expect.unwrap_or_else(|| Source::track_profile(TestExpectation::Pass));
+        let success_signals =
+            success_signals.unwrap_or_else(|| Source::track_profile(SuccessSignals::default()));
+        let wait_for_process_group = wait_for_process_group
+            .unwrap_or_else(|| Source::track_profile(profile.wait_for_process_group()));
+        let detect_no_assertions = detect_no_assertions
+            .unwrap_or_else(|| Source::track_profile(profile.detect_no_assertions()));
+        let capture = capture.unwrap_or_else(|| Source::track_profile(true));
+        let pty = pty.unwrap_or_else(|| Source::track_profile(false));
+        let stdin = stdin.unwrap_or_else(|| Source::track_profile(profile.stdin()));
+        let canonical_env =
+            canonical_env.unwrap_or_else(|| Source::track_profile(profile.canonical_env().clone()));
+        let cwd = cwd.unwrap_or_else(|| Source::track_profile(None));
+        let tracer = tracer.unwrap_or_else(|| Source::track_profile(None));
+        // A test group set by an override takes precedence over a group whose `match` filter
+        // matches the test.
+        let test_group = test_group
+            .or_else(|| {
+                profile
+                    .matched_test_group(query)
+                    .map(|group| Source::track_test_group(TestGroup::Custom(group.clone())))
+            })
+            .unwrap_or_else(|| Source::track_profile(TestGroup::Global));
+        // The slow timeout is resolved in this order of precedence:
+        //
+        // 1. The first matching override that sets slow-timeout.
+        // 2. The slow-timeout of the custom test group this test is in, if set.
+        // 3. The profile's slow-timeout.
+        let slow_timeout = slow_timeout
+            .or_else(|| match &test_group.0 {
+                TestGroup::Custom(group) => profile
+                    .test_group_config()
+                    .get(group)
+                    .and_then(|config| config.slow_timeout)
+                    .map(Source::track_test_group),
+                TestGroup::Global => None,
+            })
+            .unwrap_or_else(|| Source::track_profile(profile.slow_timeout()));
         let success_output =
             success_output.unwrap_or_else(|| Source::track_profile(profile.success_output()));
         let failure_output =
@@ -214,17 +448,87 @@ impl<Source: Copy> TestSettings<Source> {
             // If the profile doesn't have JUnit enabled, failure output can just be false.
             Source::track_profile(profile.junit().map_or(false, |j| j.store_failure_output()))
         });
+        // Quarantine lists can't be set through overrides.
+        let quarantined = Source::track_profile(profile.is_quarantined(query));
 
         TestSettings {
             threads_required,
+            exclusive,
+            priority,
             retries,
+            retry_on_exit_codes,
             slow_timeout,
             leak_timeout,
+            outcome_source,
+            expect,
+            success_signals,
+            wait_for_process_group,
+            detect_no_assertions,
+            capture,
+            pty,
+            stdin,
+            canonical_env,
+            cwd,
+            tracer,
             test_group,
             success_output,
             failure_output,
             junit_store_success_output,
             junit_store_failure_output,
+            quarantined,
+        }
+    }
+
+    /// Returns the overrides matching `query`, arranged according to the profile's
+    /// [`OverrideResolution`] so that for each setting, the first override that sets it wins.
+    ///
+    /// The settings of a test suite, if any, are layered over the profile's overrides regardless of
+    /// how they're resolved.
+    fn resolution_order<'p>(
+        profile: &'p NextestProfile<'_, FinalConfig>,
+        query: &TestQuery<'_>,
+    ) -> Vec<&'p CompiledOverride<FinalConfig>> {
+        let mut order: Vec<_> = profile
+            .suite
+            .iter()
+            .filter(|suite| suite.matches(query))
+            .collect();
+        order.extend(Self::profile_resolution_order(profile, query));
+        order
+    }
+
+    fn profile_resolution_order<'p>(
+        profile: &'p NextestProfile<'_, FinalConfig>,
+        query: &TestQuery<'_>,
+    ) -> Vec<&'p CompiledOverride<FinalConfig>> {
+        let overrides = &profile.overrides;
+        match profile.override_resolution() {
+            OverrideResolution::FirstWins => overrides
+                .iter()
+                .filter(|override_| override_.matches(query))
+                .collect(),
+            OverrideResolution::LastWins => {
+                // Reverse the overrides within each config file, but not the order of the files
+                // themselves, so that higher-precedence profiles and config files still win.
+                //
+                // Each config file's overrides for a profile are contiguous and start at index 0.
+                let mut ordered = Vec::with_capacity(overrides.len());
+                let mut layer_start = 0;
+                for (i, override_) in overrides.iter().enumerate().skip(1) {
+                    if override_.id.index == 0 {
+                        ordered.extend(overrides[layer_start..i].iter().rev());
+                        layer_start = i;
+                    }
+                }
+                ordered.extend(overrides[layer_start..].iter().rev());
+                ordered.retain(|override_| override_.matches(query));
+                ordered
+            }
+            OverrideResolution::FirstMatch => overrides
+                .iter()
+                .find(|override_| override_.matches(query))
+                .into_iter()
+                .collect(),
         }
     }
 
@@ -244,7 +548,7 @@ impl<Source: Copy> TestSettings<Source> {
     }
 
     /// Returns the leak timeout for this test, with the source attached.
-    pub(crate) fn leak_timeout_with_source(&self) -> (Duration, Source) {
+    pub(crate) fn leak_timeout_with_source(&self) -> (LeakTimeout, Source) {
         self.leak_timeout
     }
 
@@ -252,22 +556,63 @@ impl<Source: Copy> TestSettings<Source> {
     pub(crate) fn test_group_with_source(&self) -> &(TestGroup, Source) {
         &self.test_group
     }
+
+    /// Returns the source of each setting, along with the setting's name in the config.
+    pub(super) fn sources(&self) -> [(&'static str, Source); 24] {
+        [
+            ("threads-required", self.threads_required.1),
+            ("exclusive", self.exclusive.1),
+            ("priority", self.priority.1),
+            ("retries", self.retries.1),
+            ("retry-on-exit-codes", self.retry_on_exit_codes.1),
+            ("slow-timeout", self.slow_timeout.1),
+            ("leak-timeout", self.leak_timeout.1),
+            ("outcome-source", self.outcome_source.1),
+            ("expect", self.expect.1),
+            ("success-signals", self.success_signals.1),
+            ("wait-for-process-group", self.wait_for_process_group.1),
+            ("detect-no-assertions", self.detect_no_assertions.1),
+            ("capture", self.capture.1),
+            ("pty", self.pty.1),
+            ("stdin", self.stdin.1),
+            ("canonical-env", self.canonical_env.1),
+            ("cwd", self.cwd.1),
+            ("tracer", self.tracer.1),
+            ("test-group", self.test_group.1),
+            ("success-output", self.success_output.1),
+            ("failure-output", self.failure_output.1),
+            (
+                "junit.store-success-output",
+                self.junit_store_success_output.1,
+            ),
+            (
+                "junit.store-failure-output",
+                self.junit_store_failure_output.1,
+            ),
+            ("quarantine", self.quarantined.1),
+        ]
+    }
 }
 
 #[derive(Clone, Debug, Default)]
 pub(super) struct CompiledOverridesByProfile {
     pub(super) default: Vec<CompiledOverride<PreBuildPlatform>>,
     pub(super) other: HashMap<String, Vec<CompiledOverride<PreBuildPlatform>>>,
+    /// Overrides that never take effect, in the order they were found. These are only used to
+    /// warn about them.
+    pub(super) shadowed: Vec<ShadowedOverride>,
 }
 
 impl CompiledOverridesByProfile {
     pub(super) fn new(
         graph: &PackageGraph,
+        filter_sets: &FilterSets,
         config: &NextestConfigImpl,
     ) -> Result<Self, ConfigParseErrorKind> {
         let mut errors = vec![];
         let default = Self::compile_overrides(
             graph,
+            filter_sets,
             "default",
             config.default_profile().overrides(),
             &mut errors,
@@ -277,13 +622,23 @@ impl CompiledOverridesByProfile {
             .map(|(profile_name, profile)| {
                 (
                     profile_name.to_owned(),
-                    Self::compile_overrides(graph, profile_name, profile.overrides(), &mut errors),
+                    Self::compile_overrides(
+                        graph,
+                        filter_sets,
+                        profile_name,
+                        profile.overrides(),
+                        &mut errors,
+                    ),
                 )
             })
             .collect();
 
         if errors.is_empty() {
-            Ok(Self { default, other })
+            Ok(Self {
+                default,
+                other,
+                shadowed: Vec::new(),
+            })
         } else {
             Err(ConfigParseErrorKind::OverrideError(errors))
         }
@@ -291,6 +646,7 @@ impl CompiledOverridesByProfile {
 
     fn compile_overrides(
         graph: &PackageGraph,
+        filter_sets: &FilterSets,
         profile_name: &str,
         overrides: &[DeserializedOverride],
         errors: &mut Vec<ConfigParseOverrideError>,
@@ -299,10 +655,163 @@ impl CompiledOverridesByProfile {
             .iter()
             .enumerate()
             .filter_map(|(index, source)| {
-                CompiledOverride::new(graph, profile_name, index, source, errors)
+                CompiledOverride::new(graph, filter_sets, profile_name, index, source, errors)
             })
             .collect()
     }
+
+    /// Finds the overrides compiled from `config` that never take effect, assuming that for each
+    /// setting, the first override that sets it wins.
+    pub(super) fn find_shadowed(
+        &self,
+        config: &NextestConfigImpl,
+        config_file: &Utf8Path,
+        tool: Option<&str>,
+    ) -> Vec<ShadowedOverride> {
+        let mut shadowed = Self::find_shadowed_in_profile(
+            config_file,
+            tool,
+            "default",
+            config.default_profile().overrides(),
+            &self.default,
+        );
+        for (profile_name, profile) in config.other_profiles() {
+            if let Some(compiled) = self.other.get(profile_name) {
+                shadowed.extend(Self::find_shadowed_in_profile(
+                    config_file,
+                    tool,
+                    profile_name,
+                    profile.overrides(),
+                    compiled,
+                ));
+            }
+        }
+        shadowed
+    }
+
+    /// Finds overrides that never take effect, because for each setting they set, an earlier
+    /// override sets it for every test they match.
+    ///
+    /// Deciding whether one filter expression matches every test that another one does is hard in
+    /// general, so this is conservative: see [`compiled_implies`].
+    fn find_shadowed_in_profile(
+        config_file: &Utf8Path,
+        tool: Option<&str>,
+        profile_name: &str,
+        sources: &[DeserializedOverride],
+        compiled: &[CompiledOverride<PreBuildPlatform>],
+    ) -> Vec<ShadowedOverride> {
+        compiled
+            .iter()
+            .enumerate()
+            .filter_map(|(pos, later)| {
+                let settings = later.data.settings();
+                if settings.is_empty() {
+                    return None;
+                }
+
+                // An earlier override covers this one if it applies on at least the same platforms
+                // and to at least the same tests.
+                let later_platform = &sources[later.id.index].platform;
+                let covering: Vec<_> = compiled[..pos]
+                    .iter()
+                    .filter(|earlier| {
+                        let earlier_platform = &sources[earlier.id.index].platform;
+                        (earlier_platform.is_none() || earlier_platform == later_platform)
+                            && filter_implies(later.filter(), earlier.filter())
+                    })
+                    .collect();
+
+                let mut shadowed_by = BTreeSet::new();
+                for setting in settings {
+                    let earlier = covering
+                        .iter()
+                        .find(|earlier| earlier.data.settings().contains(&setting))?;
+                    shadowed_by.insert(earlier.id.index);
+                }
+
+                Some(ShadowedOverride {
+                    config_file: config_file.to_owned(),
+                    tool: tool.map(ToOwned::to_owned),
+                    profile_name: profile_name.to_owned(),
+                    index: later.id.index,
+                    filter: sources[later.id.index].filter.clone(),
+                    shadowed_by: shadowed_by.into_iter().collect(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// An override that never takes effect, because earlier overrides in the same profile set all of
+/// its settings for every test it matches.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct ShadowedOverride {
+    /// The config file the override is in.
+    pub(crate) config_file: Utf8PathBuf,
+    /// The tool that provided the config file, if any.
+    pub(crate) tool: Option<String>,
+    /// The profile the override is in.
+    pub(crate) profile_name: String,
+    /// The index of the override among those for its profile.
+    pub(crate) index: usize,
+    /// The filter of the override, if any.
+    pub(crate) filter: Option<String>,
+    /// The indexes of the earlier overrides that take precedence over this one.
+    pub(crate) shadowed_by: Vec<usize>,
+}
+
+/// Returns true if `broad` matches every test that `narrow` matches. No filter is equivalent to
+/// `all()`.
+fn filter_implies(narrow: Option<&FilteringExpr>, broad: Option<&FilteringExpr>) -> bool {
+    match (narrow, broad) {
+        (_, None) => true,
+        (None, Some(broad)) => {
+            compiled_implies(&CompiledExpr::Set(FilteringSet::All), &broad.compiled)
+        }
+        (Some(narrow), Some(broad)) => compiled_implies(&narrow.compiled, &broad.compiled),
+    }
+}
+
+/// Returns true if every test matched by `narrow` is known to be matched by `broad`.
+///
+/// This only handles identical sets and the trivial implications of `and`, `or` and `not`, so it
+/// may return false even if the implication holds. For example, `test(=foo)` isn't recognized as
+/// implying `test(foo)`.
+fn compiled_implies(narrow: &CompiledExpr, broad: &CompiledExpr) -> bool {
+    use CompiledExpr::*;
+
+    let direct = match (narrow, broad) {
+        (_, Set(FilteringSet::All)) | (Set(FilteringSet::None), _) => true,
+        (Set(a), Set(b)) => same_set(a, b),
+        // `not a` implies `not b` if `b` implies `a`.
+        (Not(a), Not(b)) => compiled_implies(b, a),
+        _ => false,
+    };
+    direct
+        || match narrow {
+            Intersection(a, b) => compiled_implies(a, broad) || compiled_implies(b, broad),
+            Union(a, b) => compiled_implies(a, broad) && compiled_implies(b, broad),
+            _ => false,
+        }
+        || match broad {
+            Union(a, b) => compiled_implies(narrow, a) || compiled_implies(narrow, b),
+            Intersection(a, b) => compiled_implies(narrow, a) && compiled_implies(narrow, b),
+            _ => false,
+        }
+}
+
+/// Compares two sets, ignoring where they were written in their expressions.
+fn same_set(a: &FilteringSet, b: &FilteringSet) -> bool {
+    match (a, b) {
+        (FilteringSet::Packages(a), FilteringSet::Packages(b)) => a == b,
+        (FilteringSet::Kind(a, _), FilteringSet::Kind(b, _))
+        | (FilteringSet::Binary(a, _), FilteringSet::Binary(b, _))
+        | (FilteringSet::Test(a, _), FilteringSet::Test(b, _)) => a == b,
+        (FilteringSet::Platform(a, _), FilteringSet::Platform(b, _)) => a == b,
+        (FilteringSet::All, FilteringSet::All) | (FilteringSet::None, FilteringSet::None) => true,
+        _ => false,
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -316,6 +825,11 @@ impl<State> CompiledOverride<State> {
     pub(crate) fn id(&self) -> &OverrideId {
         &self.id
     }
+
+    /// Returns the filter expression, if any.
+    pub(crate) fn filter(&self) -> Option<&FilteringExpr> {
+        self.data.expr.as_ref()
+    }
 }
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
@@ -324,23 +838,88 @@ pub(crate) struct OverrideId {
     index: usize,
 }
 
+impl OverrideId {
+    /// Returns the index of the override among those for its profile in its config file.
+    pub(crate) fn index(&self) -> usize {
+        self.index
+    }
+}
+
 #[derive(Clone, Debug)]
 pub(super) struct ProfileOverrideData {
     target_spec: Option<TargetSpec>,
     expr: Option<FilteringExpr>,
     threads_required: Option<ThreadsRequired>,
+    exclusive: Option<bool>,
+    priority: Option<i32>,
     retries: Option<RetryPolicy>,
+    retry_on_exit_codes: Option<Vec<i32>>,
     slow_timeout: Option<SlowTimeout>,
-    leak_timeout: Option<Duration>,
+    leak_timeout: Option<LeakTimeout>,
+    outcome_source: Option<OutcomeSource>,
+    expect: Option<TestExpectation>,
+    success_signals: Option<SuccessSignals>,
+    wait_for_process_group: Option<bool>,
+    detect_no_assertions: Option<bool>,
+    capture: Option<bool>,
+    pty: Option<bool>,
+    stdin: Option<StdinMode>,
+    canonical_env: Option<CanonicalEnv>,
+    cwd: Option<Utf8PathBuf>,
+    tracer: Option<TracerCommand>,
     pub(super) test_group: Option<TestGroup>,
     success_output: Option<TestOutputDisplay>,
     failure_output: Option<TestOutputDisplay>,
     junit: DeserializedJunitOutput,
 }
 
+impl ProfileOverrideData {
+    /// Returns the names of the settings this override sets.
+    fn settings(&self) -> Vec<&'static str> {
+        [
+            ("threads-required", self.threads_required.is_some()),
+            ("exclusive", self.exclusive.is_some()),
+            ("priority", self.priority.is_some()),
+            ("retries", self.retries.is_some()),
+            ("retry-on-exit-codes", self.retry_on_exit_codes.is_some()),
+            ("slow-timeout", self.slow_timeout.is_some()),
+            ("leak-timeout", self.leak_timeout.is_some()),
+            ("outcome-source", self.outcome_source.is_some()),
+            ("expect", self.expect.is_some()),
+            ("success-signals", self.success_signals.is_some()),
+            (
+                "wait-for-process-group",
+                self.wait_for_process_group.is_some(),
+            ),
+            ("detect-no-assertions", self.detect_no_assertions.is_some()),
+            ("capture", self.capture.is_some()),
+            ("pty", self.pty.is_some()),
+            ("stdin", self.stdin.is_some()),
+            ("canonical-env", self.canonical_env.is_some()),
+            ("cwd", self.cwd.is_some()),
+            ("tracer", self.tracer.is_some()),
+            ("test-group", self.test_group.is_some()),
+            ("success-output", self.success_output.is_some()),
+            ("failure-output", self.failure_output.is_some()),
+            (
+                "junit.store-success-output",
+                self.junit.store_success_output.is_some(),
+            ),
+            (
+                "junit.store-failure-output",
+                self.junit.store_failure_output.is_some(),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(name, is_set)| is_set.then_some(name))
+        .collect()
+    }
+}
+
 impl CompiledOverride<PreBuildPlatform> {
-    fn new(
+    pub(super) fn new(
         graph: &PackageGraph,
+        filter_sets: &FilterSets,
         profile_name: &str,
         index: usize,
         source: &DeserializedOverride,
@@ -362,7 +941,12 @@ impl CompiledOverride<PreBuildPlatform> {
             .map(|platform_str| TargetSpec::new(platform_str.to_owned()))
             .transpose();
         let filter_expr = source.filter.as_ref().map_or(Ok(None), |filter| {
-            Some(FilteringExpr::parse(filter.clone(), graph)).transpose()
+            Some(FilteringExpr::parse_with_sets(
+                filter.clone(),
+                graph,
+                filter_sets,
+            ))
+            .transpose()
         });
 
         match (target_spec, filter_expr) {
@@ -376,9 +960,23 @@ impl CompiledOverride<PreBuildPlatform> {
                     target_spec,
                     expr,
                     threads_required: source.threads_required,
+                    exclusive: source.exclusive,
+                    priority: source.priority,
                     retries: source.retries,
+                    retry_on_exit_codes: source.retry_on_exit_codes.clone(),
                     slow_timeout: source.slow_timeout,
                     leak_timeout: source.leak_timeout,
+                    outcome_source: source.outcome_source,
+                    expect: source.expect,
+                    success_signals: source.success_signals.clone(),
+                    wait_for_process_group: source.wait_for_process_group,
+                    detect_no_assertions: source.detect_no_assertions,
+                    capture: source.capture,
+                    pty: source.pty,
+                    stdin: source.stdin,
+                    canonical_env: source.canonical_env.clone(),
+                    cwd: source.cwd.clone(),
+                    tracer: source.tracer.clone(),
                     test_group: source.test_group.clone(),
                     success_output: source.success_output,
                     failure_output: source.failure_output,
@@ -441,15 +1039,25 @@ impl CompiledOverride<PreBuildPlatform> {
 }
 
 impl CompiledOverride<FinalConfig> {
+    /// Returns true if this override applies to the test described by `query`.
+    fn matches(&self, query: &TestQuery<'_>) -> bool {
+        if query.binary_query.platform == BuildPlatform::Host && !self.state.host_eval {
+            return false;
+        }
+        if query.binary_query.platform == BuildPlatform::Target && !self.state.target_eval {
+            return false;
+        }
+        // If no expression is present, it's equivalent to "all()".
+        self.data
+            .expr
+            .as_ref()
+            .map_or(true, |expr| expr.matches_test(query))
+    }
+
     /// Returns the target spec.
     pub(crate) fn target_spec(&self) -> Option<&TargetSpec> {
         self.data.target_spec.as_ref()
     }
-
-    /// Returns the filter expression, if any.
-    pub(crate) fn filter(&self) -> Option<&FilteringExpr> {
-        self.data.expr.as_ref()
-    }
 }
 
 /// Deserialized form of profile overrides before compilation.
@@ -466,12 +1074,40 @@ pub(super) struct DeserializedOverride {
     /// https://github.com/serde-rs/serde/issues/2312.)
     #[serde(default)]
     threads_required: Option<ThreadsRequired>,
+    #[serde(default)]
+    exclusive: Option<bool>,
+    #[serde(default)]
+    priority: Option<i32>,
     #[serde(default, deserialize_with = "super::deserialize_retry_policy")]
     retries: Option<RetryPolicy>,
+    #[serde(default)]
+    retry_on_exit_codes: Option<Vec<i32>>,
     #[serde(default, deserialize_with = "super::deserialize_slow_timeout")]
     slow_timeout: Option<SlowTimeout>,
-    #[serde(default, with = "humantime_serde::option")]
-    leak_timeout: Option<Duration>,
+    #[serde(default, deserialize_with = "super::deserialize_leak_timeout")]
+    leak_timeout: Option<LeakTimeout>,
+    #[serde(default)]
+    outcome_source: Option<OutcomeSource>,
+    #[serde(default)]
+    expect: Option<TestExpectation>,
+    #[serde(default)]
+    success_signals: Option<SuccessSignals>,
+    #[serde(default)]
+    wait_for_process_group: Option<bool>,
+    #[serde(default)]
+    detect_no_assertions: Option<bool>,
+    #[serde(default)]
+    capture: Option<bool>,
+    #[serde(default)]
+    pty: Option<bool>,
+    #[serde(default)]
+    stdin: Option<StdinMode>,
+    #[serde(default, deserialize_with = "super::deserialize_canonical_env")]
+    canonical_env: Option<CanonicalEnv>,
+    #[serde(default)]
+    cwd: Option<Utf8PathBuf>,
+    #[serde(default)]
+    tracer: Option<TracerCommand>,
     #[serde(default)]
     test_group: Option<TestGroup>,
     #[serde(default)]
@@ -492,12 +1128,12 @@ pub(super) struct DeserializedJunitOutput {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::{test_helpers::*, NextestConfig};
+    use crate::config::{test_helpers::*, NextestConfig, ToolConfigFile};
     use camino::Utf8Path;
     use camino_tempfile::tempdir;
     use indoc::indoc;
     use nextest_filtering::BinaryQuery;
-    use std::num::NonZeroUsize;
+    use std::{num::NonZeroUsize, time::Duration};
     use test_case::test_case;
 
     /// Basic test to ensure overrides work. Add new override parameters to this test.
@@ -509,15 +1145,35 @@ mod tests {
             filter = "test(test)"
             retries = { backoff = "exponential", count = 20, delay = "1s", max-delay = "20s" }
             slow-timeout = { period = "120s", terminate-after = 1, grace-period = "0s" }
+            retry-on-exit-codes = []
+            exclusive = false
+            priority = -5
+            wait-for-process-group = false
+            detect-no-assertions = true
+            canonical-env = { term = "xterm" }
+            leak-timeout = { period = "500ms", fail = true }
             success-output = "immediate-final"
             junit = { store-success-output = true }
 
             [[profile.default.overrides]]
             filter = "test(test)"
             threads-required = 8
+            exclusive = true
+            priority = 10
             retries = 3
+            retry-on-exit-codes = [75, 111]
             slow-timeout = "60s"
             leak-timeout = "300ms"
+            outcome-source = "tap"
+            expect = "fail"
+            success-signals = ["SIGTERM"]
+            wait-for-process-group = true
+            detect-no-assertions = false
+            capture = false
+            pty = true
+            stdin = "inherit"
+            canonical-env = false
+            cwd = "tests/fixtures"
             test-group = "my-group"
             failure-output = "final"
             junit = { store-failure-output = false }
@@ -555,7 +1211,10 @@ mod tests {
         let overrides = profile.settings_for(&query);
 
         assert_eq!(overrides.threads_required(), ThreadsRequired::Count(8));
+        assert!(overrides.exclusive());
+        assert_eq!(overrides.priority(), 10);
         assert_eq!(overrides.retries(), RetryPolicy::new_without_delay(3));
+        assert_eq!(overrides.retry_on_exit_codes(), &[75, 111]);
         assert_eq!(
             overrides.slow_timeout(),
             SlowTimeout {
@@ -564,7 +1223,27 @@ mod tests {
                 grace_period: Duration::from_secs(10),
             }
         );
-        assert_eq!(overrides.leak_timeout(), Duration::from_millis(300));
+        assert_eq!(
+            overrides.leak_timeout(),
+            LeakTimeout {
+                period: Duration::from_millis(300),
+                fail: false,
+                output_grace_period: Duration::ZERO,
+            }
+        );
+        assert_eq!(overrides.outcome_source(), OutcomeSource::Tap);
+        assert_eq!(overrides.expect(), TestExpectation::Fail);
+        assert_eq!(overrides.success_signals().names(), ["SIGTERM"]);
+        assert!(overrides.wait_for_process_group());
+        assert!(!overrides.detect_no_assertions());
+        assert!(!overrides.capture());
+        assert!(overrides.pty());
+        assert_eq!(overrides.stdin(), StdinMode::Inherit);
+        assert_eq!(overrides.canonical_env(), &CanonicalEnv::disabled());
+        assert_eq!(
+            overrides.cwd(),
+            Some(graph.workspace().root().join("tests/fixtures").as_path())
+        );
         assert_eq!(overrides.test_group(), &test_group("my-group"));
         assert_eq!(overrides.success_output(), TestOutputDisplay::Never);
         assert_eq!(overrides.failure_output(), TestOutputDisplay::Final);
@@ -588,6 +1267,8 @@ mod tests {
         let overrides = profile.settings_for(&query);
 
         assert_eq!(overrides.threads_required(), ThreadsRequired::Count(8));
+        assert!(!overrides.exclusive());
+        assert_eq!(overrides.priority(), -5);
         assert_eq!(
             overrides.retries(),
             RetryPolicy::Exponential {
@@ -595,8 +1276,10 @@ mod tests {
                 delay: Duration::from_secs(1),
                 jitter: false,
                 max_delay: Some(Duration::from_secs(20)),
+                reset_backoff_on_change: false,
             }
         );
+        assert!(overrides.retry_on_exit_codes().is_empty());
         assert_eq!(
             overrides.slow_timeout(),
             SlowTimeout {
@@ -605,7 +1288,33 @@ mod tests {
                 grace_period: Duration::ZERO,
             }
         );
-        assert_eq!(overrides.leak_timeout(), Duration::from_millis(300));
+        assert_eq!(
+            overrides.leak_timeout(),
+            LeakTimeout {
+                period: Duration::from_millis(500),
+                fail: true,
+                output_grace_period: Duration::ZERO,
+            }
+        );
+        assert_eq!(overrides.outcome_source(), OutcomeSource::Tap);
+        assert_eq!(overrides.expect(), TestExpectation::Fail);
+        assert_eq!(overrides.success_signals().names(), ["SIGTERM"]);
+        assert!(!overrides.wait_for_process_group());
+        assert!(overrides.detect_no_assertions());
+        assert!(!overrides.capture());
+        assert!(overrides.pty());
+        assert_eq!(overrides.stdin(), StdinMode::Inherit);
+        assert_eq!(
+            overrides.canonical_env(),
+            &CanonicalEnv {
+                term: Some("xterm".to_owned()),
+                ..CanonicalEnv::default()
+            }
+        );
+        assert_eq!(
+            overrides.cwd(),
+            Some(graph.workspace().root().join("tests/fixtures").as_path())
+        );
         assert_eq!(overrides.test_group(), &test_group("my-group"));
         assert_eq!(
             overrides.success_output(),
@@ -638,6 +1347,112 @@ mod tests {
         length: usize,
     }
 
+    #[test_case(
+        "first-wins",
+        20, 1, 5, 4
+        ; "first wins"
+    )]
+    #[test_case(
+        "last-wins",
+        30, 2, 5, 4
+        ; "last wins"
+    )]
+    #[test_case(
+        "first-match",
+        20, 0, 0, 1
+        ; "first match"
+    )]
+    fn test_override_resolution(
+        policy: &str,
+        slow_timeout_secs: u64,
+        retries: usize,
+        priority: i32,
+        threads_required: usize,
+    ) {
+        let config_contents = format!(
+            indoc! {r#"
+                [[profile.default.overrides]]
+                filter = "test(test)"
+                retries = 1
+
+                [[profile.default.overrides]]
+                filter = "test(test)"
+                retries = 2
+                priority = 5
+
+                [profile.ci]
+                override-resolution = "{}"
+
+                [[profile.ci.overrides]]
+                filter = "test(test)"
+                slow-timeout = "20s"
+
+                [[profile.ci.overrides]]
+                filter = "test(test)"
+                slow-timeout = "30s"
+            "#},
+            policy
+        );
+        let tool_config_contents = indoc! {r#"
+            [[profile.default.overrides]]
+            filter = "all()"
+            retries = 3
+            priority = 7
+            threads-required = 4
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), &config_contents);
+        let workspace_root = graph.workspace().root();
+        let tool_path = workspace_root.join(".config/tool.toml");
+        std::fs::write(&tool_path, tool_config_contents).unwrap();
+        let package_id = graph.workspace().iter().next().unwrap().id();
+
+        let config = NextestConfig::from_sources(
+            workspace_root,
+            &graph,
+            None,
+            &[ToolConfigFile {
+                tool: "tool".to_owned(),
+                config_file: tool_path,
+            }],
+        )
+        .expect("config is valid");
+        let profile = config
+            .profile("ci")
+            .expect("ci profile is present")
+            .apply_build_platforms(&build_platforms());
+
+        // This query matches every override, across both profiles and the tool config.
+        let query = TestQuery {
+            binary_query: BinaryQuery {
+                package_id,
+                kind: "lib",
+                binary_name: "my-binary",
+                platform: BuildPlatform::Target,
+            },
+            test_name: "test",
+        };
+        let settings = profile.settings_for(&query);
+
+        assert_eq!(
+            settings.slow_timeout().period,
+            Duration::from_secs(slow_timeout_secs),
+            "slow timeout matches"
+        );
+        assert_eq!(
+            settings.retries(),
+            RetryPolicy::new_without_delay(retries),
+            "retries match"
+        );
+        assert_eq!(settings.priority(), priority, "priority matches");
+        assert_eq!(
+            settings.threads_required(),
+            ThreadsRequired::Count(threads_required),
+            "threads required matches"
+        );
+    }
+
     #[test_case(
         indoc! {r#"
             [[profile.default.overrides]]
@@ -683,6 +1498,22 @@ mod tests {
 
         ; "invalid filter expression"
     )]
+    #[test_case(
+        indoc! {r#"
+            [[profile.ci.overrides]]
+            filter = 'test(/_slow(/) and binary(#integration_*)'
+            retries = 2
+        "#},
+        "ci",
+        &[MietteJsonReport {
+            message: "invalid regex".to_owned(),
+            labels: vec![
+                MietteJsonLabel { label: "unclosed group".to_owned(), span: MietteJsonSpan { offset: 11, length: 1 } }
+            ]
+        }]
+
+        ; "invalid regex in filter expression"
+    )]
     fn parse_overrides_invalid(
         config_contents: &str,
         faulty_profile: &str,
@@ -0,0 +1,176 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+use std::fmt;
+
+/// Type for the pre-flight config key: a command run once before any tests.
+///
+/// This is also the type of the `setup` and `teardown` commands for test groups.
+///
+/// This is specified either as a string, which is split on whitespace, or as a list of strings.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PreFlightCommand {
+    program: String,
+    args: Vec<String>,
+}
+
+impl PreFlightCommand {
+    /// Returns the program to run.
+    pub fn program(&self) -> &str {
+        &self.program
+    }
+
+    /// Returns the arguments passed to the program.
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+}
+
+impl fmt::Display for PreFlightCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.program)?;
+        for arg in &self.args {
+            write!(f, " {arg}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for PreFlightCommand {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct V;
+
+        impl<'de2> serde::de::Visitor<'de2> for V {
+            type Value = PreFlightCommand;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(
+                    formatter,
+                    "a command string or a non-empty list of program and arguments"
+                )
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                // As with target runners, this doesn't take quoting into account.
+                let mut words = v.split_whitespace().map(String::from);
+                match words.next() {
+                    Some(program) => Ok(PreFlightCommand {
+                        program,
+                        args: words.collect(),
+                    }),
+                    None => Err(serde::de::Error::invalid_value(
+                        serde::de::Unexpected::Str(v),
+                        &self,
+                    )),
+                }
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::SeqAccess<'de2>,
+            {
+                let program: String = seq
+                    .next_element()?
+                    .ok_or_else(|| serde::de::Error::invalid_length(0, &self))?;
+                let mut args = Vec::new();
+                while let Some(arg) = seq.next_element()? {
+                    args.push(arg);
+                }
+                Ok(PreFlightCommand { program, args })
+            }
+        }
+
+        deserializer.deserialize_any(V)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{test_helpers::*, NextestConfig};
+    use camino_tempfile::tempdir;
+    use indoc::indoc;
+    use test_case::test_case;
+
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+        "#},
+        Ok(None)
+
+        ; "not specified"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            pre-flight = "pg_isready  -h localhost"
+        "#},
+        Ok(Some(("pg_isready", &["-h", "localhost"][..])))
+
+        ; "string"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            pre-flight = ["sh", "-c", "test -f db.sqlite"]
+        "#},
+        Ok(Some(("sh", &["-c", "test -f db.sqlite"][..])))
+
+        ; "list"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            pre-flight = " "
+        "#},
+        Err(())
+
+        ; "empty string"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            pre-flight = []
+        "#},
+        Err(())
+
+        ; "empty list"
+    )]
+    fn parse_pre_flight(config_contents: &str, expected: Result<Option<(&str, &[&str])>, ()>) {
+        let workspace_dir = tempdir().unwrap();
+
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+
+        let config = NextestConfig::from_sources(graph.workspace().root(), &graph, None, []);
+        match expected {
+            Err(()) => assert!(config.is_err(), "config is invalid"),
+            Ok(expected) => {
+                let config = config.unwrap();
+                let profile = config
+                    .profile("custom")
+                    .unwrap()
+                    .apply_build_platforms(&build_platforms());
+                let pre_flight = profile
+                    .pre_flight()
+                    .map(|command| (command.program(), command.args()));
+                match (pre_flight, expected) {
+                    (None, None) => {}
+                    (Some((program, args)), Some((expected_program, expected_args))) => {
+                        assert_eq!(program, expected_program);
+                        assert_eq!(args, expected_args);
+                    }
+                    (actual, expected) => {
+                        panic!("expected {expected:?}, found {actual:?}")
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,233 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Overriding profile settings with environment variables.
+//!
+//! A setting is overridden with an environment variable of the form
+//! `NEXTEST_PROFILE_<PROFILE>_<SETTING>`, where the profile and setting names are upper-cased, and
+//! dashes are replaced by underscores. For example, `NEXTEST_PROFILE_CI_SLOW_TIMEOUT=90s` sets
+//! `slow-timeout` for the `ci` profile.
+
+use super::NextestConfig;
+use config::{Map, Value, ValueKind};
+use std::collections::BTreeSet;
+
+/// The profile settings that can be overridden by environment variables.
+///
+/// These are the settings that can be specified as a single string, integer or boolean.
+pub(super) const ENV_SETTINGS: &[&str] = &[
+    "retries",
+    "test-threads",
+    "global-max-threads",
+    "threads-required",
+    "status-level",
+    "final-status-level",
+    "failure-output",
+    "success-output",
+    "success-stderr",
+    "max-output-bytes",
+    "color",
+    "fail-fast",
+    "test-order",
+    "override-resolution",
+    "canonical-env",
+    "slow-timeout",
+    "leak-timeout",
+    "global-timeout",
+    "slow-report-interval",
+    "flaky-as-failure",
+    "outcome-source",
+    "stdin",
+    "wait-for-process-group",
+    "detect-no-assertions",
+    "detect-leaked-processes",
+    "slow-test-report-count",
+    "store-timings",
+    "default-filter",
+];
+
+/// A profile setting overridden by an environment variable.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(super) struct ProfileEnvOverride {
+    /// The name of the environment variable.
+    pub(super) var: String,
+    /// The profile the setting is overridden for.
+    pub(super) profile_name: String,
+    /// The setting that's overridden.
+    pub(super) setting: &'static str,
+    /// The value of the environment variable.
+    pub(super) value: String,
+}
+
+impl ProfileEnvOverride {
+    /// Returns the value as a config value, for layering over config files.
+    ///
+    /// Like values in TOML files, integers and booleans are parsed as such. Everything else,
+    /// including durations like `90s`, is passed through as a string.
+    pub(super) fn config_value(&self) -> Value {
+        let origin = format!("environment variable `{}`", self.var);
+        let kind = if let Ok(value) = self.value.parse::<i64>() {
+            ValueKind::I64(value)
+        } else if let Ok(value) = self.value.parse::<bool>() {
+            ValueKind::Boolean(value)
+        } else {
+            ValueKind::String(self.value.clone())
+        };
+        Value::new(Some(&origin), kind)
+    }
+}
+
+/// Finds the environment variables among `env_vars` that override settings in `known_profiles`.
+///
+/// Variables that start with the prefix but don't name a known profile and setting are warned
+/// about and skipped.
+pub(super) fn profile_env_overrides(
+    env_vars: impl IntoIterator<Item = (String, String)>,
+    known_profiles: &BTreeSet<String>,
+) -> Vec<ProfileEnvOverride> {
+    let prefix = format!("{}_PROFILE_", NextestConfig::ENVIRONMENT_PREFIX);
+
+    let mut overrides: Vec<_> = env_vars
+        .into_iter()
+        .filter_map(|(var, value)| {
+            let rest = var.strip_prefix(&prefix)?;
+
+            // Profile names can contain dashes or underscores, so try every known setting as a
+            // suffix and see if what's left is a known profile.
+            let mut matches = ENV_SETTINGS.iter().filter_map(|setting| {
+                let profile_part = rest
+                    .strip_suffix(&to_env_name(setting))?
+                    .strip_suffix('_')?;
+                let profile_name = known_profiles
+                    .iter()
+                    .find(|name| to_env_name(name) == profile_part)?;
+                Some((profile_name.clone(), *setting))
+            });
+
+            match (matches.next(), matches.next()) {
+                (Some((profile_name, setting)), None) => Some(ProfileEnvOverride {
+                    var,
+                    profile_name,
+                    setting,
+                    value,
+                }),
+                (Some(_), Some(_)) => {
+                    log::warn!(
+                        "ignoring environment variable `{var}`: \
+                         it could refer to more than one profile setting"
+                    );
+                    None
+                }
+                (None, _) => {
+                    log::warn!(
+                        "ignoring environment variable `{var}`: \
+                         it doesn't refer to a known profile and setting"
+                    );
+                    None
+                }
+            }
+        })
+        .collect();
+
+    // Sort the overrides so that they're applied in a consistent order.
+    overrides.sort_by(|a, b| a.var.cmp(&b.var));
+    overrides
+}
+
+/// Returns the environment variables of the current process that are valid UTF-8.
+pub(super) fn current_env_vars() -> impl Iterator<Item = (String, String)> {
+    std::env::vars_os()
+        .filter_map(|(var, value)| Some((var.into_string().ok()?, value.into_string().ok()?)))
+}
+
+/// Layers `overrides` into a `profile` table, for use with
+/// [`ConfigBuilder::set_override`](config::ConfigBuilder::set_override).
+pub(super) fn overrides_table(overrides: &[ProfileEnvOverride]) -> Map<String, Value> {
+    let mut profiles = Map::new();
+    for override_ in overrides {
+        let profile = profiles
+            .entry(override_.profile_name.clone())
+            .or_insert_with(|| Value::new(None, ValueKind::Table(Map::new())));
+        if let ValueKind::Table(settings) = &mut profile.kind {
+            settings.insert(override_.setting.to_owned(), override_.config_value());
+        }
+    }
+    profiles
+}
+
+/// Converts a profile or setting name to the form used in environment variable names.
+fn to_env_name(name: &str) -> String {
+    name.to_ascii_uppercase().replace('-', "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(
+        "NEXTEST_PROFILE_DEFAULT_RETRIES",
+        Some(("default", "retries"))
+        ; "default profile"
+    )]
+    #[test_case(
+        "NEXTEST_PROFILE_CI_SLOW_TIMEOUT",
+        Some(("ci", "slow-timeout"))
+        ; "dashed setting"
+    )]
+    #[test_case(
+        "NEXTEST_PROFILE_CI_FAST_SLOW_TIMEOUT",
+        Some(("ci-fast", "slow-timeout"))
+        ; "dashed profile"
+    )]
+    #[test_case(
+        "NEXTEST_PROFILE_MY_PROFILE_FAIL_FAST",
+        Some(("my_profile", "fail-fast"))
+        ; "underscored profile"
+    )]
+    #[test_case(
+        "NEXTEST_PROFILE_UNKNOWN_RETRIES",
+        None
+        ; "unknown profile"
+    )]
+    #[test_case(
+        "NEXTEST_PROFILE_CI_JUNIT_PATH",
+        None
+        ; "unsupported setting"
+    )]
+    #[test_case(
+        "NEXTEST_PROFILE",
+        None
+        ; "profile selection"
+    )]
+    fn parse_profile_env_var(var: &str, expected: Option<(&str, &str)>) {
+        let known_profiles = ["default", "ci", "ci-fast", "my_profile"]
+            .into_iter()
+            .map(|name| name.to_owned())
+            .collect();
+        let overrides = profile_env_overrides([(var.to_owned(), "1".to_owned())], &known_profiles);
+        let actual: Vec<_> = overrides
+            .iter()
+            .map(|override_| (override_.profile_name.as_str(), override_.setting))
+            .collect();
+        assert_eq!(actual, expected.into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn config_value_types() {
+        let value = |value: &str| {
+            ProfileEnvOverride {
+                var: "NEXTEST_PROFILE_DEFAULT_RETRIES".to_owned(),
+                profile_name: "default".to_owned(),
+                setting: "retries",
+                value: value.to_owned(),
+            }
+            .config_value()
+            .kind
+        };
+
+        assert_eq!(value("5"), ValueKind::I64(5));
+        assert_eq!(value("true"), ValueKind::Boolean(true));
+        assert_eq!(value("90s"), ValueKind::String("90s".to_owned()));
+    }
+}
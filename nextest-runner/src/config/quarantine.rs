@@ -0,0 +1,165 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use super::NextestConfigImpl;
+use crate::errors::{ConfigParseErrorKind, ConfigParseOverrideError};
+use guppy::graph::PackageGraph;
+use nextest_filtering::{FilterSets, FilteringExpr};
+use std::collections::HashMap;
+
+/// The `quarantine` lists of each profile, compiled into filter expressions.
+///
+/// Like overrides, quarantine lists are additive: a custom profile quarantines the tests matched by
+/// its own list as well as those matched by the default profile's list, across all config files.
+#[derive(Clone, Debug, Default)]
+pub(super) struct CompiledQuarantineByProfile {
+    pub(super) default: Vec<FilteringExpr>,
+    pub(super) other: HashMap<String, Vec<FilteringExpr>>,
+}
+
+impl CompiledQuarantineByProfile {
+    pub(super) fn new(
+        graph: &PackageGraph,
+        filter_sets: &FilterSets,
+        config: &NextestConfigImpl,
+    ) -> Result<Self, ConfigParseErrorKind> {
+        let mut errors = vec![];
+        let default = Self::compile_quarantine(
+            graph,
+            filter_sets,
+            "default",
+            config.default_profile().quarantine(),
+            &mut errors,
+        );
+        let other: HashMap<_, _> = config
+            .other_profiles()
+            .map(|(profile_name, profile)| {
+                (
+                    profile_name.to_owned(),
+                    Self::compile_quarantine(
+                        graph,
+                        filter_sets,
+                        profile_name,
+                        profile.quarantine(),
+                        &mut errors,
+                    ),
+                )
+            })
+            .collect();
+
+        if errors.is_empty() {
+            Ok(Self { default, other })
+        } else {
+            Err(ConfigParseErrorKind::QuarantineError(errors))
+        }
+    }
+
+    /// Adds the quarantine lists compiled for another config file to this one.
+    pub(super) fn extend(&mut self, other: Self) {
+        self.default.extend(other.default);
+        for (name, exprs) in other.other {
+            self.other.entry(name).or_default().extend(exprs);
+        }
+    }
+
+    fn compile_quarantine(
+        graph: &PackageGraph,
+        filter_sets: &FilterSets,
+        profile_name: &str,
+        filters: &[String],
+        errors: &mut Vec<ConfigParseOverrideError>,
+    ) -> Vec<FilteringExpr> {
+        filters
+            .iter()
+            .filter_map(|filter| {
+                match FilteringExpr::parse_with_sets(filter.clone(), graph, filter_sets) {
+                    Ok(expr) => Some(expr),
+                    Err(parse_errors) => {
+                        errors.push(ConfigParseOverrideError {
+                            profile_name: profile_name.to_owned(),
+                            not_specified: false,
+                            platform_parse_error: None,
+                            parse_errors: Some(parse_errors),
+                        });
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{test_helpers::*, NextestConfig};
+    use camino_tempfile::tempdir;
+    use guppy::graph::cargo::BuildPlatform;
+    use indoc::indoc;
+    use nextest_filtering::{BinaryQuery, TestQuery};
+
+    #[test]
+    fn quarantine_is_additive() {
+        let config_contents = indoc! {r#"
+            [profile.default]
+            quarantine = ["test(test_flaky_db)"]
+
+            [profile.ci]
+            quarantine = ["test(/^net_/)"]
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let package_id = graph.workspace().iter().next().unwrap().id();
+        let config = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect("config is valid");
+
+        let query = |test_name| TestQuery {
+            binary_query: BinaryQuery {
+                package_id,
+                kind: "lib",
+                binary_name: "my-binary",
+                platform: BuildPlatform::Target,
+            },
+            test_name,
+        };
+        let quarantined = |profile_name: &str, test_name| {
+            config
+                .profile(profile_name)
+                .unwrap_or_else(|_| panic!("{profile_name} profile exists"))
+                .apply_build_platforms(&build_platforms())
+                .settings_for(&query(test_name))
+                .quarantined()
+        };
+
+        assert!(quarantined("default", "test_flaky_db"));
+        assert!(!quarantined("default", "net_connect"));
+        assert!(!quarantined("default", "test_ok"));
+
+        // The ci profile quarantines its own tests, as well as those of the default profile.
+        assert!(quarantined("ci", "test_flaky_db"));
+        assert!(quarantined("ci", "net_connect"));
+        assert!(!quarantined("ci", "test_ok"));
+    }
+
+    #[test]
+    fn quarantine_invalid_filter() {
+        let config_contents = indoc! {r#"
+            [profile.ci]
+            quarantine = ["test(ok)", "test(broken"]
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let error = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect_err("quarantine filter is invalid");
+        match error.kind() {
+            ConfigParseErrorKind::QuarantineError(errors) => {
+                assert_eq!(errors.len(), 1);
+                assert_eq!(errors[0].profile_name, "ci");
+                assert!(errors[0].parse_errors.is_some(), "filter failed to parse");
+            }
+            other => panic!("unexpected error kind: {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,59 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use super::NextestJunitConfig;
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::Deserialize;
+
+/// The format of a report configured in a profile's `reports` list.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReportFormat {
+    /// A JUnit XML report, configured by the profile's `junit` section.
+    Junit,
+
+    /// A JSON summary of the run, in the same format as stored timings.
+    Json,
+}
+
+/// A report written out for each run, returned by
+/// [`NextestProfile::reports`](super::NextestProfile::reports).
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum NextestReport<'cfg> {
+    /// A JUnit XML report.
+    Junit(NextestJunitConfig<'cfg>),
+
+    /// A JSON summary of the run.
+    Json {
+        /// The absolute path to the report.
+        path: Utf8PathBuf,
+    },
+}
+
+impl<'cfg> NextestReport<'cfg> {
+    /// Returns the format of this report.
+    pub fn format(&self) -> ReportFormat {
+        match self {
+            Self::Junit(_) => ReportFormat::Junit,
+            Self::Json { .. } => ReportFormat::Json,
+        }
+    }
+
+    /// Returns the absolute path to this report.
+    pub fn path(&self) -> &Utf8Path {
+        match self {
+            Self::Junit(junit) => junit.path(),
+            Self::Json { path } => path,
+        }
+    }
+}
+
+/// A report as specified in a profile's `reports` list.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(super) struct DeserializedReport {
+    pub(super) format: ReportFormat,
+    /// The path to the report, relative to the profile's store directory.
+    pub(super) path: Utf8PathBuf,
+}
@@ -40,6 +40,11 @@ pub enum RetryPolicy {
         /// If set, limits the delay between retries.
         #[serde(default, with = "humantime_serde")]
         max_delay: Option<Duration>,
+
+        /// If set to true, the delay goes back to `delay` whenever an attempt fails for a
+        /// different reason (exit code or signal) than the previous attempt.
+        #[serde(default)]
+        reset_backoff_on_change: bool,
     },
 }
 
@@ -130,6 +135,7 @@ where
             delay,
             jitter: _,
             max_delay,
+            reset_backoff_on_change: _,
         }) => {
             // Count can't be zero.
             if *count == 0 {
@@ -200,6 +206,9 @@ mod tests {
 
             [profile.exp-with-max-delay-and-jitter]
             retries = { backoff = "exponential", count = 6, delay = "4s", max-delay = "1m", jitter = true }
+
+            [profile.exp-with-reset]
+            retries = { backoff = "exponential", count = 3, delay = "1s", reset-backoff-on-change = true }
         "#};
 
         let workspace_dir = tempdir().unwrap();
@@ -257,6 +266,7 @@ mod tests {
                 delay: Duration::from_secs(2),
                 jitter: false,
                 max_delay: None,
+                reset_backoff_on_change: false,
             },
             "exp retries matches"
         );
@@ -272,6 +282,7 @@ mod tests {
                 delay: Duration::from_secs(3),
                 jitter: false,
                 max_delay: Some(Duration::from_secs(10)),
+                reset_backoff_on_change: false,
             },
             "exp-with-max-delay retries matches"
         );
@@ -287,9 +298,26 @@ mod tests {
                 delay: Duration::from_secs(4),
                 jitter: true,
                 max_delay: Some(Duration::from_secs(60)),
+                reset_backoff_on_change: false,
             },
             "exp-with-max-delay-and-jitter retries matches"
         );
+
+        assert_eq!(
+            config
+                .profile("exp-with-reset")
+                .expect("profile exists")
+                .apply_build_platforms(&build_platforms())
+                .retries(),
+            RetryPolicy::Exponential {
+                count: 3,
+                delay: Duration::from_secs(1),
+                jitter: false,
+                max_delay: None,
+                reset_backoff_on_change: true,
+            },
+            "exp-with-reset retries matches"
+        );
     }
 
     #[test_case(
@@ -595,6 +623,62 @@ mod tests {
 
         ; "earlier config ignored because it doesn't match target cfg expr"
     )]
+    #[test_case(
+        indoc! {r#"
+            [[profile.default.overrides]]
+            filter = "test(=my_test)"
+            retries = { backoff = "fixed", count = 2, delay = "1s" }
+
+            [[profile.default.overrides]]
+            filter = "test(=other_test)"
+            retries = { backoff = "fixed", count = 3, delay = "5s" }
+
+            [profile.ci]
+        "#},
+        BuildPlatform::Target,
+        RetryPolicy::Fixed { count: 2, delay: Duration::from_secs(1), jitter: false }
+
+        ; "override delay is resolved for the matching filter"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [[profile.default.overrides]]
+            filter = "test(my_)"
+            retries = { backoff = "exponential", count = 4, delay = "2s", max-delay = "10s" }
+
+            [[profile.default.overrides]]
+            filter = "test(=my_test)"
+            retries = { backoff = "fixed", count = 3, delay = "5s" }
+
+            [profile.ci]
+        "#},
+        BuildPlatform::Target,
+        RetryPolicy::Exponential {
+            count: 4,
+            delay: Duration::from_secs(2),
+            jitter: false,
+            max_delay: Some(Duration::from_secs(10)),
+            reset_backoff_on_change: false,
+        }
+
+        ; "first matching override wins including its delay"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            retries = { backoff = "fixed", count = 1, delay = "3s" }
+
+            [[profile.default.overrides]]
+            filter = "test(=other_test)"
+            retries = { backoff = "fixed", count = 5, delay = "1s" }
+
+            [profile.ci]
+        "#},
+        BuildPlatform::Target,
+        RetryPolicy::Fixed { count: 1, delay: Duration::from_secs(3), jitter: false }
+
+        ; "profile delay is used if no override matches"
+    )]
     fn overrides_retries(
         config_contents: &str,
         build_platform: BuildPlatform,
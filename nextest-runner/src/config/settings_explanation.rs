@@ -0,0 +1,207 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use super::{FinalConfig, NextestProfile, SettingSource};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Describes where each setting for a test came from, returned by
+/// [`NextestProfile::explain_settings_for`].
+///
+/// This is meant to help debug overrides that don't apply as expected. Settings are keyed by their
+/// names in the config, for example `retries` or `junit.store-success-output`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct SettingsExplanation {
+    sources: BTreeMap<&'static str, ExplainedSource>,
+}
+
+impl SettingsExplanation {
+    pub(super) fn new<'p>(
+        profile: &'p NextestProfile<'_, FinalConfig>,
+        sources: impl IntoIterator<Item = (&'static str, SettingSource<'p>)>,
+    ) -> Self {
+        let sources = sources
+            .into_iter()
+            .map(|(name, source)| (name, ExplainedSource::new(profile, source)))
+            .collect();
+        Self { sources }
+    }
+
+    /// Returns the source of the setting with the given name, or `None` if there's no such
+    /// setting.
+    pub fn source(&self, setting: &str) -> Option<&ExplainedSource> {
+        self.sources.get(setting)
+    }
+
+    /// Iterates over the names of all the settings along with their sources, in order of name.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &ExplainedSource)> + '_ {
+        self.sources.iter().map(|(name, source)| (*name, source))
+    }
+}
+
+/// Where a setting for a test came from. Part of [`SettingsExplanation`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum ExplainedSource {
+    /// The setting came from the profile, or from the default profile.
+    Profile,
+
+    /// The setting came from the configuration of the test group that the test is in.
+    TestGroup,
+
+    /// The setting came from the test suite being run.
+    Suite {
+        /// The name of the suite.
+        name: String,
+    },
+
+    /// The setting came from an override.
+    Override {
+        /// The name of the profile the override is defined for.
+        #[serde(rename = "profile-name")]
+        profile_name: String,
+
+        /// The index of the override among the overrides for this profile, starting from 0.
+        ///
+        /// Overrides are counted separately in each config file.
+        index: usize,
+
+        /// The override's filter expression, if specified.
+        filter: Option<String>,
+
+        /// The override's platform specification, if specified.
+        platform: Option<String>,
+    },
+}
+
+impl ExplainedSource {
+    fn new(profile: &NextestProfile<'_, FinalConfig>, source: SettingSource<'_>) -> Self {
+        match source {
+            SettingSource::Profile => Self::Profile,
+            SettingSource::TestGroup => Self::TestGroup,
+            SettingSource::Override(override_) => {
+                let is_suite = profile
+                    .suite
+                    .as_ref()
+                    .map_or(false, |suite| std::ptr::eq(suite, override_));
+                if is_suite {
+                    Self::Suite {
+                        name: override_.id().profile_name.to_string(),
+                    }
+                } else {
+                    Self::Override {
+                        profile_name: override_.id().profile_name.to_string(),
+                        index: override_.id().index(),
+                        filter: override_.filter().map(|expr| expr.parsed.to_string()),
+                        platform: override_.target_spec().map(|spec| spec.to_string()),
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{test_helpers::*, NextestConfig};
+    use camino_tempfile::tempdir;
+    use guppy::graph::cargo::BuildPlatform;
+    use indoc::indoc;
+    use nextest_filtering::{BinaryQuery, TestQuery};
+
+    #[test]
+    fn explain_overlapping_overrides() {
+        let config_contents = indoc! {r#"
+            [profile.default]
+            retries = 1
+
+            [[profile.default.overrides]]
+            filter = 'test(=my_test)'
+            slow-timeout = "60s"
+
+            [[profile.default.overrides]]
+            filter = 'test(my_)'
+            retries = 3
+            slow-timeout = "30s"
+
+            [profile.ci]
+
+            [[profile.ci.overrides]]
+            filter = 'all()'
+            retries = 5
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let package_id = graph.workspace().iter().next().unwrap().id();
+        let config = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect("config is valid");
+        let query = TestQuery {
+            binary_query: BinaryQuery {
+                package_id,
+                kind: "lib",
+                binary_name: "my-binary",
+                platform: BuildPlatform::Target,
+            },
+            test_name: "my_test",
+        };
+        let override_source = |profile_name: &str, index, filter: &str| ExplainedSource::Override {
+            profile_name: profile_name.to_owned(),
+            index,
+            filter: Some(filter.to_owned()),
+            platform: None,
+        };
+
+        let profile = config
+            .profile("default")
+            .unwrap()
+            .apply_build_platforms(&build_platforms());
+        let explanation = profile.explain_settings_for(&query);
+        assert_eq!(
+            explanation.source("slow-timeout"),
+            Some(&override_source("default", 0, "test(=my_test)")),
+            "the first matching override sets slow-timeout"
+        );
+        assert_eq!(
+            explanation.source("retries"),
+            Some(&override_source("default", 1, "test(my_)")),
+            "the first override that sets retries is the second one"
+        );
+        assert_eq!(
+            explanation.source("test-group"),
+            Some(&ExplainedSource::Profile)
+        );
+        assert_eq!(explanation.source("no-such-setting"), None);
+
+        // The custom profile's overrides come before those of the default profile.
+        let profile = config
+            .profile("ci")
+            .unwrap()
+            .apply_build_platforms(&build_platforms());
+        let explanation = profile.explain_settings_for(&query);
+        assert_eq!(
+            explanation.source("retries"),
+            Some(&override_source("ci", 0, "all()")),
+        );
+        assert_eq!(
+            explanation.source("slow-timeout"),
+            Some(&override_source("default", 0, "test(=my_test)")),
+        );
+
+        let json = serde_json::to_value(&explanation).expect("explanation is serializable");
+        assert_eq!(
+            json["retries"],
+            serde_json::json!({
+                "kind": "override",
+                "profile-name": "ci",
+                "index": 0,
+                "filter": "all()",
+                "platform": null,
+            }),
+        );
+        assert_eq!(json["priority"], serde_json::json!({ "kind": "profile" }));
+    }
+}
@@ -16,6 +16,22 @@ pub struct SlowTimeout {
     pub(crate) grace_period: Duration,
 }
 
+impl SlowTimeout {
+    /// Returns the amount of time after which the test is terminated, or `None` if it's never
+    /// terminated.
+    ///
+    /// Saturates at `Duration::MAX` rather than overflowing for very large values of
+    /// `terminate-after`.
+    pub(crate) fn timeout(&self) -> Option<Duration> {
+        self.terminate_after.map(|terminate_after| {
+            u32::try_from(terminate_after.get())
+                .ok()
+                .and_then(|terminate_after| self.period.checked_mul(terminate_after))
+                .unwrap_or(Duration::MAX)
+        })
+    }
+}
+
 fn default_grace_period() -> Duration {
     Duration::from_secs(10)
 }
@@ -73,7 +89,9 @@ mod tests {
         NextestConfig,
     };
     use camino_tempfile::tempdir;
+    use guppy::graph::cargo::BuildPlatform;
     use indoc::indoc;
+    use nextest_filtering::{BinaryQuery, TestQuery};
     use test_case::test_case;
 
     #[test_case(
@@ -212,4 +230,80 @@ mod tests {
             }
         }
     }
+
+    #[test_case("integration_foo", Duration::from_secs(120) ; "group slow-timeout is used")]
+    #[test_case("integration_override", Duration::from_secs(300) ; "override takes precedence over group")]
+    #[test_case("unit_test", Duration::from_secs(30) ; "tests outside the group use the profile")]
+    fn slow_timeout_by_test_group(test_name: &str, expected_period: Duration) {
+        let config_contents = indoc! {r#"
+            [profile.default]
+            slow-timeout = "30s"
+
+            [[profile.default.overrides]]
+            filter = 'test(=integration_override)'
+            slow-timeout = "300s"
+            test-group = "integration"
+
+            [[profile.default.overrides]]
+            filter = 'test(/^integration_/)'
+            test-group = "integration"
+
+            [test-groups.integration]
+            max-threads = 4
+            slow-timeout = "120s"
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let package_id = graph.workspace().iter().next().unwrap().id();
+
+        let nextest_config =
+            NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+                .expect("config is valid");
+        let profile = nextest_config
+            .profile("default")
+            .expect("default profile should exist")
+            .apply_build_platforms(&build_platforms());
+
+        let query = TestQuery {
+            binary_query: BinaryQuery {
+                package_id,
+                kind: "lib",
+                binary_name: "my-binary",
+                platform: BuildPlatform::Target,
+            },
+            test_name,
+        };
+        assert_eq!(
+            profile.settings_for(&query).slow_timeout().period,
+            expected_period
+        );
+    }
+
+    #[test_case(60, None, None ; "never terminated")]
+    #[test_case(60, Some(3), Some(Duration::from_secs(180)) ; "terminated after three periods")]
+    #[test_case(
+        u64::MAX / 2,
+        Some(usize::MAX),
+        Some(Duration::MAX)
+        ; "large terminate-after saturates"
+    )]
+    #[test_case(
+        u64::MAX / 2,
+        Some(3),
+        Some(Duration::MAX)
+        ; "multiplication overflow saturates"
+    )]
+    fn slow_timeout_timeout(
+        period_secs: u64,
+        terminate_after: Option<usize>,
+        expected: Option<Duration>,
+    ) {
+        let slow_timeout = SlowTimeout {
+            period: Duration::from_secs(period_secs),
+            terminate_after: terminate_after.map(|n| NonZeroUsize::new(n).unwrap()),
+            grace_period: default_grace_period(),
+        };
+        assert_eq!(slow_timeout.timeout(), expected);
+    }
 }
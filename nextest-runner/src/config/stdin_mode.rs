@@ -0,0 +1,30 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+
+/// Type for the `stdin` config key: what a test's standard input is connected to.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StdinMode {
+    /// Standard input is connected to `/dev/null` (or `NUL` on Windows), so tests that read from
+    /// it see end-of-file immediately rather than hanging.
+    #[default]
+    Null,
+
+    /// Standard input is inherited from nextest.
+    ///
+    /// Tests running at the same time share standard input, so this is generally only useful for
+    /// specific tests that are run exclusively.
+    Inherit,
+}
+
+impl StdinMode {
+    /// Returns the [`Stdio`](std::process::Stdio) to spawn a test with.
+    pub(crate) fn to_stdio(self) -> std::process::Stdio {
+        match self {
+            Self::Null => std::process::Stdio::null(),
+            Self::Inherit => std::process::Stdio::inherit(),
+        }
+    }
+}
@@ -0,0 +1,325 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+use std::fmt;
+
+/// Type for the `store.discriminator` config key.
+///
+/// A discriminator adds an environment-specific component to the store directory, so that
+/// environments sharing a target directory (for example, a local checkout and CI running on a
+/// shared volume) don't write to the same store.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum StoreDiscriminator {
+    /// The store directory is shared across all environments.
+    #[default]
+    None,
+
+    /// Use the hostname of the machine nextest is running on.
+    Hostname,
+
+    /// Use the name of the user nextest is running as.
+    User,
+
+    /// Use a fixed, configured tag.
+    ///
+    /// Tags are checked to be valid path components while the config is parsed.
+    Tag(String),
+}
+
+impl StoreDiscriminator {
+    /// The path component used if the discriminator's value couldn't be determined.
+    pub const UNKNOWN: &'static str = "unknown";
+
+    /// Resolves this discriminator to a path component, or `None` if no discriminator is
+    /// configured.
+    ///
+    /// The returned string is always a valid, single path component. Hostnames and user names are
+    /// escaped reversibly, so distinct values always map to distinct store directories.
+    pub fn resolve(&self) -> Option<String> {
+        let value = match self {
+            Self::None => return None,
+            Self::Hostname => imp::hostname(),
+            Self::User => std::env::var("USER")
+                .or_else(|_| std::env::var("USERNAME"))
+                .ok(),
+            Self::Tag(tag) => return Some(tag.clone()),
+        };
+
+        match value {
+            Some(value) if !value.is_empty() => Some(escape_path_component(&value)),
+            _ => {
+                log::warn!(
+                    "unable to determine {self} for the store directory, using `{}`",
+                    Self::UNKNOWN,
+                );
+                Some(Self::UNKNOWN.to_owned())
+            }
+        }
+    }
+}
+
+impl fmt::Display for StoreDiscriminator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::Hostname => write!(f, "hostname"),
+            Self::User => write!(f, "user"),
+            Self::Tag(tag) => write!(f, "tag `{tag}`"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for StoreDiscriminator {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct V;
+
+        impl<'de2> serde::de::Visitor<'de2> for V {
+            type Value = StoreDiscriminator;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(
+                    formatter,
+                    "one of \"none\", \"hostname\" or \"user\", or a table ({{ tag = \"my-tag\" }})"
+                )
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                match v {
+                    "none" => Ok(StoreDiscriminator::None),
+                    "hostname" => Ok(StoreDiscriminator::Hostname),
+                    "user" => Ok(StoreDiscriminator::User),
+                    _ => Err(serde::de::Error::invalid_value(
+                        serde::de::Unexpected::Str(v),
+                        &self,
+                    )),
+                }
+            }
+
+            fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::MapAccess<'de2>,
+            {
+                #[derive(Deserialize)]
+                #[serde(deny_unknown_fields)]
+                struct TagTable {
+                    tag: String,
+                }
+
+                let table =
+                    TagTable::deserialize(serde::de::value::MapAccessDeserializer::new(map))?;
+                // Tags are used as-is, so that distinct tags always map to distinct store
+                // directories.
+                if table.tag.is_empty() {
+                    return Err(serde::de::Error::custom("`tag` cannot be empty"));
+                }
+                if let Some(c) = table.tag.chars().find(|&c| !is_valid_path_char(c)) {
+                    return Err(serde::de::Error::custom(format!(
+                        "`tag` \"{}\" contains invalid character {c:?} (only ASCII letters, \
+                         digits, `-`, `_` and `.` are allowed)",
+                        table.tag,
+                    )));
+                }
+                if table.tag == "." || table.tag == ".." {
+                    return Err(serde::de::Error::custom(format!(
+                        "`tag` cannot be \"{}\"",
+                        table.tag
+                    )));
+                }
+                Ok(StoreDiscriminator::Tag(table.tag))
+            }
+        }
+
+        deserializer.deserialize_any(V)
+    }
+}
+
+fn is_valid_path_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')
+}
+
+/// Converts a hostname or user name into something that can be used as a single path component.
+///
+/// Bytes other than ASCII alphanumerics, `-`, `_` and `.` are percent-encoded (including `%`
+/// itself), so the conversion is reversible. Strings that would otherwise refer to the current or
+/// parent directory have their dots encoded as well.
+fn escape_path_component(value: &str) -> String {
+    use std::fmt::Write;
+
+    // "." and ".." aren't valid path components.
+    let escape_dots = value.bytes().all(|b| b == b'.');
+
+    let mut escaped = String::with_capacity(value.len());
+    for b in value.bytes() {
+        if is_valid_path_char(b as char) && !(escape_dots && b == b'.') {
+            escaped.push(b as char);
+        } else {
+            write!(escaped, "%{b:02X}").expect("writing to a String never fails");
+        }
+    }
+    escaped
+}
+
+#[cfg(unix)]
+mod imp {
+    pub(super) fn hostname() -> Option<String> {
+        let mut buf = [0u8; 256];
+        // gethostname writes at most buf.len() bytes, and the buffer outlives the call.
+        let ret = unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) };
+        if ret != 0 {
+            return None;
+        }
+        let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+        String::from_utf8(buf[..len].to_vec()).ok()
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    pub(super) fn hostname() -> Option<String> {
+        std::env::var("COMPUTERNAME").ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{test_helpers::*, NextestConfig},
+        errors::ConfigParseErrorKind,
+    };
+    use camino_tempfile::tempdir;
+    use config::ConfigError;
+    use indoc::indoc;
+    use test_case::test_case;
+
+    #[test_case("ci-runner", "ci-runner" ; "already valid")]
+    #[test_case("my host/name", "my%20host%2Fname" ; "separators and spaces")]
+    #[test_case("my_host_name", "my_host_name" ; "distinct from escaped separators")]
+    #[test_case("user@example.com", "user%40example.com" ; "email-like")]
+    #[test_case("50%", "50%25" ; "percent sign")]
+    #[test_case("DOMAIN\\müller", "DOMAIN%5Cm%C3%BCller" ; "backslash and non-ASCII")]
+    #[test_case("..", "%2E%2E" ; "parent directory")]
+    #[test_case(".", "%2E" ; "current directory")]
+    #[test_case("a..b", "a..b" ; "dots within a name")]
+    fn escape(input: &str, expected: &str) {
+        assert_eq!(escape_path_component(input), expected);
+    }
+
+    #[test]
+    fn distinct_store_dirs() {
+        let store_dir_for = |config_contents: &str| {
+            let workspace_dir = tempdir().unwrap();
+            let graph = temp_workspace(workspace_dir.path(), config_contents);
+            let config = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+                .expect("config is valid");
+            let profile = config
+                .profile(NextestConfig::DEFAULT_PROFILE)
+                .expect("default profile exists");
+            profile
+                .store_dir()
+                .strip_prefix(graph.workspace().root())
+                .expect("store dir is within the workspace root")
+                .to_owned()
+        };
+
+        let no_discriminator = store_dir_for("");
+        let tag_a = store_dir_for(indoc! {r#"
+            [store]
+            discriminator = { tag = "local" }
+        "#});
+        let tag_b = store_dir_for(indoc! {r#"
+            [store]
+            discriminator = { tag = "ci-linux" }
+        "#});
+        let tag_c = store_dir_for(indoc! {r#"
+            [store]
+            discriminator = { tag = "ci_linux" }
+        "#});
+
+        assert_eq!(no_discriminator, "target/nextest/default");
+        assert_eq!(tag_a, "target/nextest/local/default");
+        assert_eq!(tag_b, "target/nextest/ci-linux/default");
+        assert_eq!(tag_c, "target/nextest/ci_linux/default");
+    }
+
+    #[test_case(
+        indoc! {r#"
+            [store]
+            discriminator = "foo"
+        "#},
+        "invalid value: string \"foo\""
+        ; "unknown discriminator")]
+    #[test_case(
+        indoc! {r#"
+            [store]
+            discriminator = { tag = "" }
+        "#},
+        "`tag` cannot be empty"
+        ; "empty tag")]
+    #[test_case(
+        indoc! {r#"
+            [store]
+            discriminator = { tag = "a", foo = "b" }
+        "#},
+        "unknown field `foo`"
+        ; "unknown key")]
+    #[test_case(
+        indoc! {r#"
+            [store]
+            discriminator = { tag = "ci/linux" }
+        "#},
+        r#"`tag` "ci/linux" contains invalid character '/'"#
+        ; "path separator in tag")]
+    #[test_case(
+        indoc! {r#"
+            [store]
+            discriminator = { tag = 'ci\linux' }
+        "#},
+        r#"`tag` "ci\linux" contains invalid character '\\'"#
+        ; "backslash in tag")]
+    #[test_case(
+        indoc! {r#"
+            [store]
+            discriminator = { tag = "ci linux" }
+        "#},
+        r#"`tag` "ci linux" contains invalid character ' '"#
+        ; "space in tag")]
+    #[test_case(
+        indoc! {r#"
+            [store]
+            discriminator = { tag = ".." }
+        "#},
+        r#"`tag` cannot be "..""#
+        ; "parent directory tag")]
+    fn parse_invalid(config_contents: &str, expected_message: &str) {
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+
+        let config_err = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect_err("config expected to be invalid");
+        let message = match config_err.kind() {
+            ConfigParseErrorKind::DeserializeError(path_error) => match path_error.inner() {
+                ConfigError::Message(message) => message,
+                other => {
+                    panic!("for config error {config_err:?}, expected ConfigError::Message for inner error {other:?}");
+                }
+            },
+            other => {
+                panic!(
+                    "for config error {other:?}, expected ConfigParseErrorKind::DeserializeError"
+                );
+            }
+        };
+        assert!(
+            message.contains(expected_message),
+            "expected message \"{message}\" to contain \"{expected_message}\""
+        );
+    }
+}
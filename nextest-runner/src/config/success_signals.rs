@@ -0,0 +1,192 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+
+/// Type for the `success-signals` override key: termination signals that are treated as the test
+/// passing.
+///
+/// This is useful for tests that intentionally exit via a signal, for example to test signal
+/// handlers that re-raise the signal. Signals are specified by name, such as `"SIGTERM"`.
+///
+/// `SIGKILL` can't be treated as success: nextest itself uses it to terminate tests, and a test
+/// killed by the OOM killer shouldn't be reported as passing. Signals don't exist on Windows, so
+/// this is ignored there.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SuccessSignals {
+    names: Vec<String>,
+    #[cfg(unix)]
+    signals: Vec<i32>,
+}
+
+impl SuccessSignals {
+    /// Returns the names of the signals, as specified in the config.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Returns true if a test that was terminated by `signal` is treated as passing.
+    #[cfg(unix)]
+    pub fn contains(&self, signal: i32) -> bool {
+        self.signals.contains(&signal)
+    }
+}
+
+impl<'de> Deserialize<'de> for SuccessSignals {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let names = Vec::<String>::deserialize(deserializer)?;
+
+        #[cfg(unix)]
+        let signals = names
+            .iter()
+            .map(|name| {
+                use nix::sys::signal::Signal;
+                use std::str::FromStr;
+
+                let signal = Signal::from_str(name).map_err(|_| {
+                    serde::de::Error::custom(format!(
+                        "unknown signal `{name}` in success-signals \
+                         (expected a name such as \"SIGTERM\")"
+                    ))
+                })?;
+                if signal == Signal::SIGKILL {
+                    return Err(sigkill_error());
+                }
+                Ok(signal as i32)
+            })
+            .collect::<Result<Vec<_>, D::Error>>()?;
+
+        // Signal numbers can't be resolved on other platforms, but SIGKILL is still rejected so
+        // that configs are valid everywhere or nowhere.
+        #[cfg(not(unix))]
+        if names.iter().any(|name| name == "SIGKILL") {
+            return Err(sigkill_error());
+        }
+
+        Ok(Self {
+            names,
+            #[cfg(unix)]
+            signals,
+        })
+    }
+}
+
+fn sigkill_error<E: serde::de::Error>() -> E {
+    E::custom(
+        "SIGKILL can't be treated as success, since nextest uses it to terminate tests \
+         that time out or leak processes",
+    )
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use crate::{
+        config::{test_helpers::*, NextestConfig},
+        errors::ConfigParseErrorKind,
+    };
+    use camino_tempfile::tempdir;
+    use config::ConfigError;
+    use guppy::graph::cargo::BuildPlatform;
+    use indoc::indoc;
+    use nextest_filtering::{BinaryQuery, TestQuery};
+    use test_case::test_case;
+
+    #[test_case(
+        indoc! {r#"
+            [[profile.default.overrides]]
+            filter = "test(=my_test)"
+            success-signals = ["SIGTERM", "SIGUSR1"]
+        "#},
+        Ok(vec![libc::SIGTERM, libc::SIGUSR1])
+
+        ; "valid signals"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [[profile.default.overrides]]
+            filter = "test(=other_test)"
+            success-signals = ["SIGTERM"]
+        "#},
+        Ok(vec![])
+
+        ; "not matched"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [[profile.default.overrides]]
+            filter = "test(=my_test)"
+            success-signals = ["SIGTERM", "SIGKILL"]
+        "#},
+        Err("SIGKILL can't be treated as success")
+
+        ; "sigkill"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [[profile.default.overrides]]
+            filter = "test(=my_test)"
+            success-signals = ["TERM"]
+        "#},
+        Err("unknown signal `TERM`")
+
+        ; "unknown signal"
+    )]
+    fn parse_success_signals(config_contents: &str, expected: Result<Vec<i32>, &str>) {
+        let workspace_dir = tempdir().unwrap();
+
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let package_id = graph.workspace().iter().next().unwrap().id();
+
+        let config = NextestConfig::from_sources(graph.workspace().root(), &graph, None, []);
+        match expected {
+            Err(expected_message) => {
+                let config_err = config.expect_err("config is invalid");
+                let message = match config_err.kind() {
+                    ConfigParseErrorKind::DeserializeError(path_error) => {
+                        match path_error.inner() {
+                            ConfigError::Message(message) => message,
+                            other => panic!(
+                                "for config error {config_err:?}, expected ConfigError::Message \
+                                 for inner error {other:?}"
+                            ),
+                        }
+                    }
+                    other => panic!(
+                        "for config error {other:?}, expected ConfigParseErrorKind::DeserializeError"
+                    ),
+                };
+                assert!(
+                    message.contains(expected_message),
+                    "expected message \"{message}\" to contain \"{expected_message}\""
+                );
+            }
+            Ok(expected) => {
+                let config = config.expect("config is valid");
+                let profile = config
+                    .profile("default")
+                    .unwrap()
+                    .apply_build_platforms(&build_platforms());
+                let query = TestQuery {
+                    binary_query: BinaryQuery {
+                        package_id,
+                        kind: "lib",
+                        binary_name: "my-binary",
+                        platform: BuildPlatform::Target,
+                    },
+                    test_name: "my_test",
+                };
+                let settings = profile.settings_for(&query);
+                for signal in [libc::SIGTERM, libc::SIGUSR1, libc::SIGSEGV] {
+                    assert_eq!(
+                        settings.success_signals().contains(signal),
+                        expected.contains(&signal),
+                        "signal {signal} is treated as success as expected"
+                    );
+                }
+            }
+        }
+    }
+}
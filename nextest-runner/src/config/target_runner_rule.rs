@@ -0,0 +1,108 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::cargo_config::Runner;
+use nextest_metadata::RustTestBinaryKind;
+use serde::Deserialize;
+
+/// A rule in the `[target-runner]` section, which selects a target runner for matching test
+/// binaries.
+///
+/// Rules are checked in the order they're specified, and the first matching rule wins. Test
+/// binaries that don't match any rules use the target runner configured through Cargo, if any.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct TargetRunnerRule {
+    /// The kind of test binary this rule matches, e.g. `lib` or `bench`. If not specified, all
+    /// kinds are matched.
+    #[serde(default)]
+    pub kind: Option<RustTestBinaryKind>,
+
+    /// The name of the test binary this rule matches. If not specified, all binaries are matched.
+    #[serde(default)]
+    pub binary: Option<String>,
+
+    pub(crate) runner: Runner,
+}
+
+impl TargetRunnerRule {
+    /// Returns true if this rule matches a test binary with the given kind and name.
+    pub fn matches(&self, kind: &RustTestBinaryKind, binary_name: &str) -> bool {
+        self.kind
+            .as_ref()
+            .map_or(true, |rule_kind| rule_kind == kind)
+            && self
+                .binary
+                .as_deref()
+                .map_or(true, |rule_binary| rule_binary == binary_name)
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(super) struct TargetRunnerConfig {
+    #[serde(default)]
+    pub(super) rules: Vec<TargetRunnerRule>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{test_helpers::*, NextestConfig};
+    use camino_tempfile::tempdir;
+    use indoc::indoc;
+
+    #[test]
+    fn parse_target_runner_rules() {
+        let config_contents = indoc! {r#"
+            [[target-runner.rules]]
+            kind = "bench"
+            runner = ["taskset", "-c", "0"]
+
+            [[target-runner.rules]]
+            binary = "integration"
+            runner = "valgrind --quiet"
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let config = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect("config is valid");
+
+        let rules = config.target_runner_rules();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].kind, Some(RustTestBinaryKind::BENCH));
+        assert_eq!(rules[0].binary, None);
+        assert_eq!(
+            rules[0].runner,
+            Runner::List(vec!["taskset".to_owned(), "-c".to_owned(), "0".to_owned()])
+        );
+        assert_eq!(rules[1].kind, None);
+        assert_eq!(rules[1].binary.as_deref(), Some("integration"));
+
+        assert!(rules[0].matches(&RustTestBinaryKind::BENCH, "my-bench"));
+        assert!(!rules[0].matches(&RustTestBinaryKind::LIB, "my-bench"));
+        assert!(rules[1].matches(&RustTestBinaryKind::TEST, "integration"));
+        assert!(!rules[1].matches(&RustTestBinaryKind::TEST, "other"));
+    }
+
+    #[test]
+    fn parse_target_runner_rules_unknown_field() {
+        let config_contents = indoc! {r#"
+            [[target-runner.rules]]
+            kind = "bench"
+            filter = "test(foo)"
+            runner = "taskset -c 0"
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let error = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect_err("config is invalid");
+        let error_message = format!("{error:?}");
+        assert!(
+            error_message.contains("unknown field `filter`"),
+            "unexpected error: {error_message}"
+        );
+    }
+}
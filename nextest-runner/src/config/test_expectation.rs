@@ -0,0 +1,20 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+
+/// Type for the `expect` config key: the outcome a test is expected to have.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TestExpectation {
+    /// The test is expected to pass.
+    #[default]
+    Pass,
+
+    /// The test is expected to fail, for example because it exercises a known bug.
+    ///
+    /// A failing test is reported as an expected failure (XFAIL) and counts as passing, while a
+    /// passing test is reported as an unexpected pass (XPASS) and counts as failing. Tests that
+    /// time out or can't be executed are still treated as failures.
+    Fail,
+}
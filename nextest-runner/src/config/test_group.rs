@@ -1,11 +1,13 @@
 // Copyright (c) The nextest Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use super::{ConfigIdentifier, TestThreads};
-use crate::errors::InvalidCustomTestGroupName;
+use super::{ConfigIdentifier, PreFlightCommand, SlowTimeout, TestThreads};
+use crate::errors::{ConfigParseErrorKind, ConfigParseOverrideError, InvalidCustomTestGroupName};
+use guppy::graph::PackageGraph;
+use nextest_filtering::{FilterSets, FilteringExpr, TestQuery};
 use serde::Deserialize;
 use smol_str::SmolStr;
-use std::{fmt, str::FromStr};
+use std::{collections::BTreeMap, fmt, str::FromStr};
 
 /// Represents the test group a test is in.
 #[derive(Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
@@ -117,6 +119,94 @@ impl fmt::Display for CustomTestGroup {
 pub struct TestGroupConfig {
     /// The maximum number of threads allowed for this test group.
     pub max_threads: TestThreads,
+
+    /// A filter expression for the tests that are in this group, specified as `match`.
+    ///
+    /// Matching tests are put in this group without needing a separate override. A `test-group`
+    /// set by an override takes precedence over this.
+    #[serde(default, rename = "match")]
+    pub match_filter: Option<String>,
+
+    /// The slow timeout for tests in this group.
+    ///
+    /// If set, this takes precedence over the profile's slow timeout, but not over per-test
+    /// overrides.
+    #[serde(default, deserialize_with = "super::deserialize_slow_timeout")]
+    pub slow_timeout: Option<SlowTimeout>,
+
+    /// A command run before the first test in this group starts.
+    ///
+    /// Lines of the form `KEY=VALUE` in the command's standard output are passed to tests in the
+    /// group as environment variables.
+    #[serde(default)]
+    pub setup: Option<PreFlightCommand>,
+
+    /// A command run after the last test in this group finishes, even if tests failed or the run
+    /// was canceled.
+    #[serde(default)]
+    pub teardown: Option<PreFlightCommand>,
+}
+
+impl TestGroupConfig {
+    /// Returns true if this group has a setup or teardown command.
+    pub fn has_scripts(&self) -> bool {
+        self.setup.is_some() || self.teardown.is_some()
+    }
+}
+
+/// The `match` filters of test groups, compiled into filter expressions.
+///
+/// A test that isn't assigned a group by an override is put in the first group, in order of name,
+/// whose filter matches it.
+#[derive(Clone, Debug, Default)]
+pub(super) struct CompiledTestGroupMatches {
+    matches: BTreeMap<CustomTestGroup, FilteringExpr>,
+}
+
+impl CompiledTestGroupMatches {
+    pub(super) fn new(
+        graph: &PackageGraph,
+        filter_sets: &FilterSets,
+        test_groups: &BTreeMap<CustomTestGroup, TestGroupConfig>,
+    ) -> Result<Self, ConfigParseErrorKind> {
+        let mut errors = vec![];
+        let matches = test_groups
+            .iter()
+            .filter_map(|(group, config)| {
+                let filter = config.match_filter.as_ref()?;
+                match FilteringExpr::parse_with_sets(filter.clone(), graph, filter_sets) {
+                    Ok(expr) => Some((group.clone(), expr)),
+                    Err(parse_errors) => {
+                        errors.push(ConfigParseOverrideError {
+                            profile_name: group.to_string(),
+                            not_specified: false,
+                            platform_parse_error: None,
+                            parse_errors: Some(parse_errors),
+                        });
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        if errors.is_empty() {
+            Ok(Self { matches })
+        } else {
+            Err(ConfigParseErrorKind::TestGroupMatchError(errors))
+        }
+    }
+
+    /// Adds the filters compiled for another config file to this one.
+    pub(super) fn extend(&mut self, other: Self) {
+        self.matches.extend(other.matches);
+    }
+
+    /// Returns the first group whose `match` filter matches the test, if any.
+    pub(super) fn group_for(&self, query: &TestQuery<'_>) -> Option<&CustomTestGroup> {
+        self.matches
+            .iter()
+            .find_map(|(group, expr)| expr.matches_test(query).then_some(group))
+    }
 }
 
 #[cfg(test)]
@@ -128,9 +218,11 @@ mod tests {
     };
     use camino::Utf8Path;
     use camino_tempfile::tempdir;
+    use guppy::graph::cargo::BuildPlatform;
     use indoc::indoc;
     use maplit::btreeset;
-    use std::collections::BTreeSet;
+    use nextest_filtering::BinaryQuery;
+    use std::{collections::BTreeSet, time::Duration};
     use test_case::test_case;
 
     #[derive(Debug)]
@@ -468,4 +560,111 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn group_scripts() {
+        let config_contents = indoc! {r#"
+            [test-groups.db]
+            max-threads = 4
+            setup = "scripts/start-db.sh --port 0"
+            teardown = ["scripts/stop-db.sh"]
+
+            [test-groups.plain]
+            max-threads = 1
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let config = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect("config is valid");
+        let profile = config
+            .profile(NextestConfig::DEFAULT_PROFILE)
+            .expect("default profile exists")
+            .apply_build_platforms(&build_platforms());
+
+        let db = &profile.test_group_config()[&custom_test_group("db")];
+        assert!(db.has_scripts());
+        let setup = db.setup.as_ref().expect("setup is set");
+        assert_eq!(setup.program(), "scripts/start-db.sh");
+        assert_eq!(setup.args(), ["--port", "0"]);
+        let teardown = db.teardown.as_ref().expect("teardown is set");
+        assert_eq!(teardown.program(), "scripts/stop-db.sh");
+        assert!(teardown.args().is_empty());
+
+        let plain = &profile.test_group_config()[&custom_test_group("plain")];
+        assert!(!plain.has_scripts());
+    }
+
+    #[test]
+    fn group_match_filter() {
+        let config_contents = indoc! {r#"
+            [test-groups.slow]
+            max-threads = 2
+            match = 'test(/_slow$/)'
+            slow-timeout = "5m"
+
+            [test-groups.serial]
+            max-threads = 1
+
+            # An explicit override takes precedence over the slow group's match filter.
+            [[profile.default.overrides]]
+            filter = 'test(=db_slow)'
+            test-group = 'serial'
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let package_id = graph.workspace().iter().next().unwrap().id();
+        let config = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect("config is valid");
+        let profile = config
+            .profile(NextestConfig::DEFAULT_PROFILE)
+            .expect("default profile exists")
+            .apply_build_platforms(&build_platforms());
+
+        let settings_for = |test_name| {
+            profile.settings_for(&TestQuery {
+                binary_query: BinaryQuery {
+                    package_id,
+                    kind: "lib",
+                    binary_name: "my-binary",
+                    platform: BuildPlatform::Target,
+                },
+                test_name,
+            })
+        };
+
+        let settings = settings_for("parse_slow");
+        assert_eq!(settings.test_group(), &test_group("slow"));
+        assert_eq!(
+            settings.slow_timeout().period,
+            Duration::from_secs(5 * 60),
+            "the slow group's slow-timeout applies to tests matched by its filter"
+        );
+        assert_eq!(settings_for("db_slow").test_group(), &test_group("serial"));
+        assert_eq!(settings_for("parse_fast").test_group(), &TestGroup::Global);
+    }
+
+    #[test]
+    fn group_match_filter_invalid() {
+        let config_contents = indoc! {r#"
+            [test-groups.slow]
+            max-threads = 2
+            match = 'test(/_slow$/'
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let error = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect_err("config is invalid");
+        match error.kind() {
+            ConfigParseErrorKind::TestGroupMatchError(errors) => {
+                assert_eq!(errors.len(), 1, "one error: {errors:?}");
+                assert_eq!(errors[0].profile_name, "slow");
+            }
+            other => {
+                panic!("expected ConfigParseErrorKind::TestGroupMatchError, got {other}");
+            }
+        }
+    }
 }
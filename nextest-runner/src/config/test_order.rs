@@ -0,0 +1,100 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+
+/// Type for the `test-order` config key.
+///
+/// Controls the order in which tests are started. By default, tests are run in the order they're
+/// listed in.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct TestOrder {
+    /// Whether to shuffle the order in which tests are run.
+    #[serde(default)]
+    pub shuffle: bool,
+
+    /// The seed to shuffle tests with. If unspecified and `shuffle` is true, a random seed is
+    /// generated for each run.
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+impl TestOrder {
+    /// Returns the seed to shuffle tests with, generating one if necessary, or `None` if tests
+    /// aren't shuffled.
+    pub fn resolve_seed(&self) -> Option<u64> {
+        self.shuffle
+            .then(|| self.seed.unwrap_or_else(crate::order_check::random_seed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{test_helpers::*, NextestConfig};
+    use camino_tempfile::tempdir;
+    use indoc::indoc;
+    use test_case::test_case;
+
+    #[test_case(
+        "",
+        TestOrder { shuffle: false, seed: None }
+        ; "not shuffled by default"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            test-order = { shuffle = true }
+        "#},
+        TestOrder { shuffle: true, seed: None }
+        ; "shuffle without seed"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            test-order = { shuffle = true, seed = 12345 }
+        "#},
+        TestOrder { shuffle: true, seed: Some(12345) }
+        ; "shuffle with seed"
+    )]
+    fn parse_test_order(config_contents: &str, expected: TestOrder) {
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+
+        let config = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect("config is valid");
+        let profile = config
+            .profile(NextestConfig::DEFAULT_PROFILE)
+            .expect("default profile exists")
+            .apply_build_platforms(&build_platforms());
+        assert_eq!(profile.test_order(), expected);
+    }
+
+    #[test]
+    fn resolve_seed() {
+        assert_eq!(TestOrder::default().resolve_seed(), None);
+        assert_eq!(
+            TestOrder {
+                shuffle: false,
+                seed: Some(5)
+            }
+            .resolve_seed(),
+            None
+        );
+        assert_eq!(
+            TestOrder {
+                shuffle: true,
+                seed: Some(5)
+            }
+            .resolve_seed(),
+            Some(5)
+        );
+        assert!(TestOrder {
+            shuffle: true,
+            seed: None
+        }
+        .resolve_seed()
+        .is_some());
+    }
+}
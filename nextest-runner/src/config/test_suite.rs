@@ -0,0 +1,191 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use super::{CompiledOverride, NextestConfigImpl, PreBuildPlatform};
+use crate::errors::ConfigParseErrorKind;
+use guppy::graph::PackageGraph;
+use nextest_filtering::{FilterSets, FilteringExpr};
+use std::collections::BTreeMap;
+
+/// A named test suite, defined in the `[suite.<name>]` section of the config.
+///
+/// A suite bundles a filter expression that selects tests with settings for them. Suites compose
+/// with profiles: the settings of a suite are layered over those of the active profile through
+/// [`NextestProfile::with_suite`](super::NextestProfile::with_suite).
+///
+/// Returned by [`NextestConfig::suite`](super::NextestConfig::suite).
+#[derive(Clone, Debug)]
+pub struct TestSuiteConfig<'cfg> {
+    name: &'cfg str,
+    pub(super) compiled: &'cfg CompiledOverride<PreBuildPlatform>,
+}
+
+impl<'cfg> TestSuiteConfig<'cfg> {
+    pub(super) fn new(name: &'cfg str, compiled: &'cfg CompiledOverride<PreBuildPlatform>) -> Self {
+        Self { name, compiled }
+    }
+
+    /// Returns the name of this suite.
+    pub fn name(&self) -> &'cfg str {
+        self.name
+    }
+
+    /// Returns the filter expression that selects the tests in this suite.
+    ///
+    /// If this is `None`, the suite only specifies a platform, and selects all tests.
+    pub fn filter(&self) -> Option<&'cfg FilteringExpr> {
+        self.compiled.filter()
+    }
+}
+
+/// Test suites defined in a config, compiled.
+#[derive(Clone, Debug, Default)]
+pub(super) struct CompiledSuites {
+    pub(super) suites: BTreeMap<String, CompiledOverride<PreBuildPlatform>>,
+}
+
+impl CompiledSuites {
+    pub(super) fn new(
+        graph: &PackageGraph,
+        filter_sets: &FilterSets,
+        config: &NextestConfigImpl,
+    ) -> Result<Self, ConfigParseErrorKind> {
+        let mut errors = vec![];
+        let suites = config
+            .suites()
+            .filter_map(|(name, suite)| {
+                CompiledOverride::new(graph, filter_sets, name, 0, suite, &mut errors)
+                    .map(|compiled| (name.to_owned(), compiled))
+            })
+            .collect();
+
+        if errors.is_empty() {
+            Ok(Self { suites })
+        } else {
+            Err(ConfigParseErrorKind::SuiteError(errors))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{
+        test_helpers::*, NextestConfig, RetryPolicy, SlowTimeout, ThreadsRequired,
+    };
+    use camino_tempfile::tempdir;
+    use guppy::graph::cargo::BuildPlatform;
+    use indoc::indoc;
+    use nextest_filtering::{BinaryQuery, TestQuery};
+    use std::time::Duration;
+
+    #[test]
+    fn suite_layers_over_profile() {
+        let config_contents = indoc! {r#"
+            [profile.default]
+            retries = 2
+            slow-timeout = "60s"
+
+            [[profile.default.overrides]]
+            filter = "test(smoke_net)"
+            retries = 5
+            threads-required = 4
+
+            [suite.smoke]
+            filter = "test(/^smoke_/)"
+            slow-timeout = "5s"
+            retries = 0
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let package_id = graph.workspace().iter().next().unwrap().id();
+        let config = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect("config is valid");
+
+        let suite = config.suite("smoke").expect("smoke suite exists");
+        assert_eq!(suite.name(), "smoke");
+
+        let profile = config
+            .profile("default")
+            .expect("default profile exists")
+            .with_suite(&suite)
+            .apply_build_platforms(&build_platforms());
+
+        let query = |test_name| TestQuery {
+            binary_query: BinaryQuery {
+                package_id,
+                kind: "lib",
+                binary_name: "my-binary",
+                platform: BuildPlatform::Target,
+            },
+            test_name,
+        };
+
+        let filter = suite.filter().expect("smoke suite has a filter");
+        assert!(filter.matches_test(&query("smoke_net")));
+        assert!(!filter.matches_test(&query("slow_net")));
+
+        // The suite's settings win over the profile and its overrides, but settings the suite
+        // doesn't set still come from them.
+        let settings = profile.settings_for(&query("smoke_net"));
+        assert_eq!(settings.retries(), RetryPolicy::new_without_delay(0));
+        assert_eq!(
+            settings.slow_timeout(),
+            SlowTimeout {
+                period: Duration::from_secs(5),
+                terminate_after: None,
+                grace_period: Duration::from_secs(10),
+            }
+        );
+        assert_eq!(settings.threads_required(), ThreadsRequired::Count(4));
+
+        // Tests outside the suite aren't affected by its settings.
+        let settings = profile.settings_for(&query("slow_net"));
+        assert_eq!(settings.retries(), RetryPolicy::new_without_delay(2));
+        assert_eq!(settings.slow_timeout().period, Duration::from_secs(60));
+    }
+
+    #[test]
+    fn suite_not_found() {
+        let config_contents = indoc! {r#"
+            [suite.smoke]
+            filter = "test(smoke)"
+
+            [suite.nightly]
+            filter = "all()"
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let config = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect("config is valid");
+
+        let error = config.suite("fast").expect_err("fast suite isn't defined");
+        assert_eq!(
+            error.to_string(),
+            "test suite `fast` not found (known suites: nightly, smoke)"
+        );
+    }
+
+    #[test]
+    fn suite_invalid_filter() {
+        let config_contents = indoc! {r#"
+            [suite.smoke]
+            filter = "test(smoke"
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let error = NextestConfig::from_sources(graph.workspace().root(), &graph, None, [])
+            .expect_err("suite filter is invalid");
+        match error.kind() {
+            ConfigParseErrorKind::SuiteError(errors) => {
+                assert_eq!(errors.len(), 1);
+                assert_eq!(errors[0].profile_name, "smoke");
+                assert!(errors[0].parse_errors.is_some(), "filter failed to parse");
+            }
+            other => panic!("unexpected error kind: {other:?}"),
+        }
+    }
+}
@@ -7,6 +7,10 @@ use serde::Deserialize;
 use std::{cmp::Ordering, fmt, str::FromStr};
 
 /// Type for the test-threads config key.
+///
+/// Apart from a number of threads, this can be specified relative to the logical CPU count as
+/// `"num-cpus"`, `"num-cpus-N"` (the CPU count minus N) or a percentage such as `"50%"`. Relative
+/// values other than `"num-cpus"` are resolved to a count while parsing, and are always at least 1.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum TestThreads {
     /// Run tests with a specified number of threads.
@@ -24,14 +28,39 @@ impl TestThreads {
             Self::NumCpus => get_num_cpus(),
         }
     }
+
+    /// Parses the forms of test-threads that are relative to the logical CPU count.
+    ///
+    /// Returns `None` if `s` isn't one of these forms.
+    fn parse_relative(s: &str) -> Option<Result<Self, String>> {
+        if s == "num-cpus" {
+            return Some(Ok(Self::NumCpus));
+        }
+
+        if let Some(minus) = s.strip_prefix("num-cpus-") {
+            let res = match minus.parse::<usize>() {
+                Ok(minus) => Ok(Self::Count(get_num_cpus().saturating_sub(minus).max(1))),
+                Err(e) => Err(format!("{e} parsing `{minus}` in `{s}`")),
+            };
+            return Some(res);
+        }
+
+        let percent = s.strip_suffix('%')?;
+        let res = match percent.parse::<usize>() {
+            Ok(0) => Err(format!("`{s}` would run no tests")),
+            Ok(percent) => Ok(Self::Count((get_num_cpus() * percent / 100).max(1))),
+            Err(e) => Err(format!("{e} parsing `{percent}` in `{s}`")),
+        };
+        Some(res)
+    }
 }
 
 impl FromStr for TestThreads {
     type Err = TestThreadsParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s == "num-cpus" {
-            return Ok(Self::NumCpus);
+        if let Some(res) = Self::parse_relative(s) {
+            return res.map_err(|_| TestThreadsParseError::new(s));
         }
 
         match s.parse::<isize>() {
@@ -67,20 +96,23 @@ impl<'de> Deserialize<'de> for TestThreads {
             type Value = TestThreads;
 
             fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                write!(formatter, "an integer or the string \"num-cpus\"")
+                write!(
+                    formatter,
+                    "an integer, the string \"num-cpus\", a string like \"num-cpus-2\", \
+                     or a percentage like \"50%\""
+                )
             }
 
             fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
             where
                 E: serde::de::Error,
             {
-                if v == "num-cpus" {
-                    Ok(TestThreads::NumCpus)
-                } else {
-                    Err(serde::de::Error::invalid_value(
+                match TestThreads::parse_relative(v) {
+                    Some(res) => res.map_err(serde::de::Error::custom),
+                    None => Err(serde::de::Error::invalid_value(
                         serde::de::Unexpected::Str(v),
                         &self,
-                    ))
+                    )),
                 }
             }
 
@@ -150,6 +182,60 @@ mod tests {
 
         ; "num-cpus"
     )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            test-threads = "num-cpus-2"
+        "#},
+        Some(get_num_cpus().saturating_sub(2).max(1))
+
+        ; "num-cpus minus"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            test-threads = "50%"
+        "#},
+        Some((get_num_cpus() / 2).max(1))
+
+        ; "half"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            test-threads = "100%"
+        "#},
+        Some(get_num_cpus())
+
+        ; "hundred percent"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            test-threads = "1%"
+        "#},
+        Some((get_num_cpus() / 100).max(1))
+
+        ; "one percent is at least one thread"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            test-threads = "0%"
+        "#},
+        None
+
+        ; "zero percent"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            test-threads = "half%"
+        "#},
+        None
+
+        ; "invalid percent"
+    )]
     fn parse_test_threads(config_contents: &str, n_threads: Option<usize>) {
         let workspace_dir = tempdir().unwrap();
 
@@ -173,4 +259,19 @@ mod tests {
             ),
         }
     }
+
+    #[test_case("4", Some(4); "count")]
+    #[test_case("-1", Some(get_num_cpus().saturating_sub(1).max(1)); "negative")]
+    #[test_case("num-cpus", Some(get_num_cpus()); "num-cpus")]
+    #[test_case("num-cpus-1", Some(get_num_cpus().saturating_sub(1).max(1)); "num-cpus minus")]
+    #[test_case("25%", Some((get_num_cpus() / 4).max(1)); "percent")]
+    #[test_case("0%", None; "zero percent")]
+    #[test_case("num-cpus-x", None; "invalid num-cpus minus")]
+    fn test_threads_from_str(s: &str, n_threads: Option<usize>) {
+        let threads = s.parse::<TestThreads>();
+        match n_threads {
+            None => assert!(threads.is_err(), "{s} is invalid"),
+            Some(n) => assert_eq!(threads.expect("valid test threads").compute(), n),
+        }
+    }
 }
@@ -11,7 +11,10 @@ pub enum ThreadsRequired {
     /// Take up "slots" equal to the number of threads.
     Count(usize),
 
-    /// Take up as many slots as the number of CPUs.
+    /// Take up as many slots as the number of CPUs, or as the number of test threads if that's
+    /// greater.
+    ///
+    /// This reserves the whole machine for the test, so that no other tests run alongside it.
     NumCpus,
 
     /// Take up as many slots as the number of test threads specified.
@@ -23,7 +26,9 @@ impl ThreadsRequired {
     pub fn compute(self, test_threads: usize) -> usize {
         match self {
             Self::Count(threads) => threads,
-            Self::NumCpus => get_num_cpus(),
+            // If there are more test threads than CPUs, taking up just the CPUs would leave room
+            // for other tests to run.
+            Self::NumCpus => get_num_cpus().max(test_threads),
             Self::NumTestThreads => test_threads,
         }
     }
@@ -143,6 +148,16 @@ mod tests {
 
         ; "num-cpus-with-custom-test-threads"
     )]
+    #[test_case(
+        indoc! {r#"
+            [profile.custom]
+            test-threads = "200%"
+            threads-required = "num-cpus"
+        "#},
+        Some(get_num_cpus() * 2)
+
+        ; "num-cpus-with-more-test-threads-than-cpus"
+    )]
     #[test_case(
         indoc! {r#"
             [profile.custom]
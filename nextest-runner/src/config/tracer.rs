@@ -0,0 +1,164 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use super::PreFlightCommand;
+use camino::Utf8Path;
+use serde::Deserialize;
+use std::fmt;
+
+/// Type for the `tracer` override key: a command that wraps a test, such as `strace` or `dtrace`,
+/// and writes a trace to a file.
+///
+/// This is specified in the same way as [`PreFlightCommand`]. One of the arguments must contain
+/// `{trace-file}`, which is replaced with the path that nextest collects the trace from. This keeps
+/// the tracer's output separate from the test's output.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TracerCommand {
+    command: PreFlightCommand,
+}
+
+impl TracerCommand {
+    /// The placeholder that's replaced with the path to the trace file.
+    pub const TRACE_FILE_PLACEHOLDER: &'static str = "{trace-file}";
+
+    /// Returns the tracer program to run.
+    pub fn program(&self) -> &str {
+        self.command.program()
+    }
+
+    /// Returns the arguments passed to the tracer, with `{trace-file}` not substituted.
+    pub fn args(&self) -> &[String] {
+        self.command.args()
+    }
+
+    /// Returns the arguments passed to the tracer, with `{trace-file}` replaced by `trace_file`.
+    pub(crate) fn args_for(&self, trace_file: &Utf8Path) -> Vec<String> {
+        self.args()
+            .iter()
+            .map(|arg| arg.replace(Self::TRACE_FILE_PLACEHOLDER, trace_file.as_str()))
+            .collect()
+    }
+}
+
+impl fmt::Display for TracerCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.command.fmt(f)
+    }
+}
+
+impl<'de> Deserialize<'de> for TracerCommand {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let command = PreFlightCommand::deserialize(deserializer)?;
+        if !command
+            .args()
+            .iter()
+            .any(|arg| arg.contains(Self::TRACE_FILE_PLACEHOLDER))
+        {
+            return Err(serde::de::Error::custom(format!(
+                "tracer command `{command}` must write its output to `{}`, \
+                 so it isn't mixed up with the test's output",
+                Self::TRACE_FILE_PLACEHOLDER,
+            )));
+        }
+        Ok(Self { command })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{test_helpers::*, NextestConfig};
+    use camino_tempfile::tempdir;
+    use guppy::graph::cargo::BuildPlatform;
+    use indoc::indoc;
+    use nextest_filtering::{BinaryQuery, TestQuery};
+    use test_case::test_case;
+
+    #[test_case(
+        indoc! {r#"
+            [[profile.default.overrides]]
+            filter = "test(=my_test)"
+            tracer = "strace -f -o {trace-file}"
+        "#},
+        Ok(Some(("strace", &["-f", "-o", "/traces/my_test.trace"][..])))
+
+        ; "string"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [[profile.default.overrides]]
+            filter = "test(=my_test)"
+            tracer = ["dtrace", "-q", "-o", "{trace-file}", "-c"]
+        "#},
+        Ok(Some(("dtrace", &["-q", "-o", "/traces/my_test.trace", "-c"][..])))
+
+        ; "list"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [[profile.default.overrides]]
+            filter = "test(=other_test)"
+            tracer = "strace -f -o {trace-file}"
+        "#},
+        Ok(None)
+
+        ; "not matched"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [[profile.default.overrides]]
+            filter = "test(=my_test)"
+            tracer = "strace -f"
+        "#},
+        Err(())
+
+        ; "missing trace file"
+    )]
+    fn parse_tracer(config_contents: &str, expected: Result<Option<(&str, &[&str])>, ()>) {
+        let workspace_dir = tempdir().unwrap();
+
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let package_id = graph.workspace().iter().next().unwrap().id();
+
+        let config = NextestConfig::from_sources(graph.workspace().root(), &graph, None, []);
+        match expected {
+            Err(()) => assert!(config.is_err(), "config is invalid"),
+            Ok(expected) => {
+                let config = config.unwrap();
+                let profile = config
+                    .profile("default")
+                    .unwrap()
+                    .apply_build_platforms(&build_platforms());
+                let query = TestQuery {
+                    binary_query: BinaryQuery {
+                        package_id,
+                        kind: "lib",
+                        binary_name: "my-binary",
+                        platform: BuildPlatform::Target,
+                    },
+                    test_name: "my_test",
+                };
+                let settings = profile.settings_for(&query);
+                let tracer = settings.tracer().map(|tracer| {
+                    (
+                        tracer.program(),
+                        tracer.args_for(Utf8Path::new("/traces/my_test.trace")),
+                    )
+                });
+                match (tracer, expected) {
+                    (None, None) => {}
+                    (Some((program, args)), Some((expected_program, expected_args))) => {
+                        assert_eq!(program, expected_program);
+                        assert_eq!(args, expected_args);
+                    }
+                    (actual, expected) => {
+                        panic!("expected {expected:?}, found {actual:?}")
+                    }
+                }
+            }
+        }
+    }
+}
@@ -14,7 +14,7 @@ use crate::{
 use camino::{FromPathBufError, Utf8Path, Utf8PathBuf};
 use config::ConfigError;
 use itertools::Itertools;
-use nextest_filtering::errors::FilterExpressionParseErrors;
+use nextest_filtering::errors::{FilterExpressionParseErrors, FilterSetsError};
 use nextest_metadata::RustBinaryId;
 use smol_str::SmolStr;
 use std::{borrow::Cow, collections::BTreeSet, env::JoinPathsError, fmt, process::ExitStatus};
@@ -64,6 +64,221 @@ impl ConfigParseError {
     }
 }
 
+/// A problem found in the config by
+/// [`NextestConfig::validate`](crate::config::NextestConfig::validate).
+///
+/// Unlike a [`ConfigParseError`], which can describe several issues at once, a problem is about a
+/// single profile, test suite, test group or filter set where that can be determined.
+#[derive(Debug, Error)]
+#[error(
+    "problem in nextest config at `{config_file}`{}{}",
+    provided_by_tool(tool.as_deref()),
+    location.as_ref().map_or_else(String::new, |location| format!(" (at `{location}`)"))
+)]
+#[non_exhaustive]
+pub struct ConfigProblem {
+    config_file: Utf8PathBuf,
+    tool: Option<String>,
+    profile_name: Option<String>,
+    location: Option<String>,
+    #[source]
+    kind: ConfigParseErrorKind,
+}
+
+impl ConfigProblem {
+    /// Splits a parse error into one problem for each profile, suite or group it's about.
+    pub(crate) fn from_error(error: ConfigParseError) -> Vec<Self> {
+        let ConfigParseError {
+            config_file,
+            tool,
+            kind,
+        } = error;
+        let problem = |profile_name: Option<String>, location: String, kind| Self {
+            config_file: config_file.clone(),
+            tool: tool.clone(),
+            profile_name,
+            location: Some(location),
+            kind,
+        };
+
+        match kind {
+            ConfigParseErrorKind::DeserializeError(error) => {
+                // The path is of the form `profile.<name>.<key>` for errors within profiles.
+                let location = error.path().to_string();
+                let profile_name = location
+                    .strip_prefix("profile.")
+                    .and_then(|rest| rest.split('.').next())
+                    .map(|name| name.to_owned());
+                vec![problem(
+                    profile_name,
+                    location,
+                    ConfigParseErrorKind::DeserializeError(error),
+                )]
+            }
+            ConfigParseErrorKind::OverrideError(errors) => errors
+                .into_iter()
+                .map(|error| {
+                    let name = error.profile_name.clone();
+                    let location = format!("profile.{name}.overrides");
+                    problem(
+                        Some(name),
+                        location,
+                        ConfigParseErrorKind::OverrideError(vec![error]),
+                    )
+                })
+                .collect(),
+            ConfigParseErrorKind::QuarantineError(errors) => errors
+                .into_iter()
+                .map(|error| {
+                    let name = error.profile_name.clone();
+                    let location = format!("profile.{name}.quarantine");
+                    problem(
+                        Some(name),
+                        location,
+                        ConfigParseErrorKind::QuarantineError(vec![error]),
+                    )
+                })
+                .collect(),
+            ConfigParseErrorKind::DefaultFilterError(errors) => errors
+                .into_iter()
+                .map(|error| {
+                    let name = error.profile_name.clone();
+                    let location = format!("profile.{name}.default-filter");
+                    problem(
+                        Some(name),
+                        location,
+                        ConfigParseErrorKind::DefaultFilterError(vec![error]),
+                    )
+                })
+                .collect(),
+            ConfigParseErrorKind::SuiteError(errors) => errors
+                .into_iter()
+                .map(|error| {
+                    let location = format!("suite.{}", error.profile_name);
+                    problem(
+                        None,
+                        location,
+                        ConfigParseErrorKind::SuiteError(vec![error]),
+                    )
+                })
+                .collect(),
+            ConfigParseErrorKind::TestGroupMatchError(errors) => errors
+                .into_iter()
+                .map(|error| {
+                    let location = format!("test-groups.{}.match", error.profile_name);
+                    problem(
+                        None,
+                        location,
+                        ConfigParseErrorKind::TestGroupMatchError(vec![error]),
+                    )
+                })
+                .collect(),
+            ConfigParseErrorKind::UnknownTestGroups {
+                errors,
+                known_groups,
+            } => errors
+                .into_iter()
+                .map(|error| {
+                    // Unknown groups in suites are reported with a profile name of the form
+                    // `suite.<name>`.
+                    let (profile_name, location) = if error.profile_name.starts_with("suite.") {
+                        (None, format!("{}.test-group", error.profile_name))
+                    } else {
+                        let location = format!("profile.{}.overrides", error.profile_name);
+                        (Some(error.profile_name.clone()), location)
+                    };
+                    problem(
+                        profile_name,
+                        location,
+                        ConfigParseErrorKind::UnknownTestGroups {
+                            errors: vec![error],
+                            known_groups: known_groups.clone(),
+                        },
+                    )
+                })
+                .collect(),
+            ConfigParseErrorKind::FilterSetError(error) => {
+                let location = match &error {
+                    FilterSetsError::ParseError { name, .. } => format!("filter-sets.{name}"),
+                    _ => "filter-sets".to_owned(),
+                };
+                vec![problem(
+                    None,
+                    location,
+                    ConfigParseErrorKind::FilterSetError(error),
+                )]
+            }
+            kind @ (ConfigParseErrorKind::InvalidTestGroupsDefined(_)
+            | ConfigParseErrorKind::InvalidTestGroupsDefinedByTool(_)) => {
+                vec![problem(None, "test-groups".to_owned(), kind)]
+            }
+            ConfigParseErrorKind::UnknownParentProfile {
+                profile_name,
+                parent,
+            } => {
+                let location = format!("profile.{profile_name}.extends");
+                vec![problem(
+                    Some(profile_name.clone()),
+                    location,
+                    ConfigParseErrorKind::UnknownParentProfile {
+                        profile_name,
+                        parent,
+                    },
+                )]
+            }
+            ConfigParseErrorKind::ProfileExtendsCycle { cycle } => {
+                let profile_name = cycle[0].clone();
+                let location = format!("profile.{profile_name}.extends");
+                vec![problem(
+                    Some(profile_name),
+                    location,
+                    ConfigParseErrorKind::ProfileExtendsCycle { cycle },
+                )]
+            }
+            kind @ ConfigParseErrorKind::DefaultProfileExtends { .. } => {
+                vec![problem(
+                    Some("default".to_owned()),
+                    "profile.default.extends".to_owned(),
+                    kind,
+                )]
+            }
+            kind => vec![Self {
+                config_file,
+                tool,
+                profile_name: None,
+                location: None,
+                kind,
+            }],
+        }
+    }
+
+    /// Returns the config file the problem was found in.
+    pub fn config_file(&self) -> &Utf8Path {
+        &self.config_file
+    }
+
+    /// Returns the tool that provided the config file, if any.
+    pub fn tool(&self) -> Option<&str> {
+        self.tool.as_deref()
+    }
+
+    /// Returns the name of the profile the problem is in, if it's within a profile.
+    pub fn profile_name(&self) -> Option<&str> {
+        self.profile_name.as_deref()
+    }
+
+    /// Returns the location of the problem within the config file as a dotted path of keys, such
+    /// as `profile.ci.slow-timeout`, if known.
+    pub fn location(&self) -> Option<&str> {
+        self.location.as_deref()
+    }
+
+    /// Returns the kind of problem this is.
+    pub fn kind(&self) -> &ConfigParseErrorKind {
+        &self.kind
+    }
+}
+
 /// Returns the string ` provided by tool <tool>`, if `tool` is `Some`.
 pub fn provided_by_tool(tool: Option<&str>) -> String {
     match tool {
@@ -87,6 +302,21 @@ pub enum ConfigParseErrorKind {
     /// Errors occurred while parsing overrides.
     #[error("error parsing overrides (destructure this variant for more details)")]
     OverrideError(Vec<ConfigParseOverrideError>),
+    /// Errors occurred while parsing the filters or platforms of test suites.
+    #[error("error parsing test suites (destructure this variant for more details)")]
+    SuiteError(Vec<ConfigParseOverrideError>),
+    /// Errors occurred while parsing the filters in a profile's quarantine list.
+    #[error("error parsing quarantine lists (destructure this variant for more details)")]
+    QuarantineError(Vec<ConfigParseOverrideError>),
+    /// Errors occurred while parsing a profile's default filter.
+    #[error("error parsing default filters (destructure this variant for more details)")]
+    DefaultFilterError(Vec<ConfigParseOverrideError>),
+    /// Errors occurred while parsing the `match` filters of test groups.
+    #[error("error parsing test group match filters (destructure this variant for more details)")]
+    TestGroupMatchError(Vec<ConfigParseOverrideError>),
+    /// An error occurred while parsing or resolving the `[filter-sets]` table.
+    #[error(transparent)]
+    FilterSetError(FilterSetsError),
     /// An invalid set of test groups was defined by the user.
     #[error("invalid test groups defined: {}\n(test groups cannot start with '@tool:' unless specified by a tool)", .0.iter().join(", "))]
     InvalidTestGroupsDefined(BTreeSet<CustomTestGroup>),
@@ -103,15 +333,40 @@ pub enum ConfigParseErrorKind {
         /// Known groups up to this point.
         known_groups: BTreeSet<TestGroup>,
     },
+    /// A profile extends a profile that isn't defined.
+    #[error("profile `{profile_name}` extends unknown profile `{parent}`")]
+    UnknownParentProfile {
+        /// The name of the profile with the `extends` key.
+        profile_name: String,
+
+        /// The name of the profile it extends.
+        parent: String,
+    },
+    /// Profiles extend each other in a cycle.
+    #[error("profiles extend each other in a cycle: {}", .cycle.join(" -> "))]
+    ProfileExtendsCycle {
+        /// The profiles in the cycle, starting and ending with the same profile.
+        cycle: Vec<String>,
+    },
+    /// The default profile extends another profile.
+    #[error("the default profile cannot extend another profile (found extends = `{parent}`)")]
+    DefaultProfileExtends {
+        /// The name of the profile the default profile extends.
+        parent: String,
+    },
 }
 
 /// An error that occurred while parsing config overrides.
 ///
-/// Part of [`ConfigParseErrorKind::OverrideError`].
+/// Part of [`ConfigParseErrorKind::OverrideError`], [`ConfigParseErrorKind::SuiteError`],
+/// [`ConfigParseErrorKind::QuarantineError`], [`ConfigParseErrorKind::DefaultFilterError`] and
+/// [`ConfigParseErrorKind::TestGroupMatchError`].
 #[derive(Debug)]
 #[non_exhaustive]
 pub struct ConfigParseOverrideError {
-    /// The name of the profile under which the override was found.
+    /// The name of the profile under which the override was found, or for
+    /// [`ConfigParseErrorKind::SuiteError`], the name of the test suite, or for
+    /// [`ConfigParseErrorKind::TestGroupMatchError`], the name of the test group.
     pub profile_name: String,
 
     /// True if neither the platform nor the filter have been specified.
@@ -184,6 +439,28 @@ impl ProfileNotFound {
     }
 }
 
+/// An error which indicates that a test suite was requested but not defined in the config.
+#[derive(Clone, Debug, Error)]
+#[error("test suite `{suite}` not found (known suites: {})", .all_suites.join(", "))]
+pub struct SuiteNotFound {
+    suite: String,
+    all_suites: Vec<String>,
+}
+
+impl SuiteNotFound {
+    pub(crate) fn new(
+        suite: impl Into<String>,
+        all_suites: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        let mut all_suites: Vec<_> = all_suites.into_iter().map(|s| s.into()).collect();
+        all_suites.sort_unstable();
+        Self {
+            suite: suite.into(),
+            all_suites,
+        }
+    }
+}
+
 /// An identifier is invalid.
 #[derive(Clone, Debug, Error, Eq, PartialEq)]
 pub enum InvalidIdentifier {
@@ -250,7 +527,7 @@ pub enum ToolConfigFileParseError {
 /// Error returned while parsing a [`TestThreads`](crate::config::TestThreads) value.
 #[derive(Clone, Debug, Error)]
 #[error(
-    "unrecognized value for test-threads: {input}\n(hint: expected either an integer or \"num-cpus\")"
+    "unrecognized value for test-threads: {input}\n(hint: expected an integer, \"num-cpus\", \"num-cpus-N\" or a percentage like \"50%\")"
 )]
 pub struct TestThreadsParseError {
     /// The input that failed to parse.
@@ -615,10 +892,174 @@ pub enum TestRunnerBuildError {
     /// An error occurred while creating the Tokio runtime.
     #[error("error creating Tokio runtime")]
     TokioRuntimeCreate(#[source] std::io::Error),
+}
+
+/// An error that occurs while running the pre-flight command configured for a profile.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum PreFlightError {
+    /// The pre-flight command could not be started.
+    #[error("error spawning pre-flight command `{command}`")]
+    Spawn {
+        /// The command that was run.
+        command: String,
+
+        /// The underlying error.
+        #[source]
+        error: std::io::Error,
+    },
+
+    /// The pre-flight command exited unsuccessfully.
+    #[error("pre-flight command `{command}` failed with {exit_status}")]
+    Failed {
+        /// The command that was run.
+        command: String,
+
+        /// The exit status of the command.
+        exit_status: ExitStatus,
+
+        /// The captured standard output of the command.
+        stdout: Vec<u8>,
+
+        /// The captured standard error of the command.
+        stderr: Vec<u8>,
+    },
+}
+
+/// An error that occurs while merging run summaries with
+/// [`RunSummary::merge`](crate::run_summary::RunSummary::merge).
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MergeSummariesError {
+    /// The same tests were run in more than one summary.
+    ///
+    /// This usually means that shards were misconfigured, for example with different partitioning
+    /// strategies.
+    #[error(
+        "{} tests were run in more than one summary: {}",
+        overlapping.len(),
+        overlapping.iter().map(|test| test.to_string()).join(", ")
+    )]
+    OverlappingTests {
+        /// The tests that were run more than once, sorted by binary ID and test name.
+        overlapping: Vec<OverlappingTest>,
+    },
+
+    /// The summaries were produced from test lists with different numbers of tests.
+    #[error(
+        "summary {index} has {actual} tests, but the first summary has {expected} \
+         (were the summaries produced from the same build?)"
+    )]
+    TestCountMismatch {
+        /// The index of the summary with a different test count.
+        index: usize,
+
+        /// The number of tests listed in the first summary.
+        expected: usize,
 
-    /// An error occurred while setting up signals.
-    #[error("error setting up signals")]
-    SignalHandlerSetupError(#[from] SignalHandlerSetupError),
+        /// The number of tests listed in this summary.
+        actual: usize,
+    },
+}
+
+/// A test that was run in more than one summary, returned in
+/// [`MergeSummariesError::OverlappingTests`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OverlappingTest {
+    /// The binary ID of the test.
+    pub binary_id: RustBinaryId,
+
+    /// The name of the test.
+    pub test_name: String,
+
+    /// The indexes of the summaries that this test was run in.
+    pub summary_indexes: Vec<usize>,
+}
+
+impl fmt::Display for OverlappingTest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} (in summaries {})",
+            self.binary_id,
+            self.test_name,
+            self.summary_indexes.iter().join(", ")
+        )
+    }
+}
+
+/// An error that occurs while reading recorded test timings with
+/// [`RecordedTimings::read_csv`](crate::timings::RecordedTimings::read_csv).
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ReadTimingsError {
+    /// An error occurred while reading the timings file.
+    #[error("error reading timings from `{path}`")]
+    Read {
+        /// The path to the timings file.
+        path: Utf8PathBuf,
+
+        /// The underlying error.
+        #[source]
+        error: std::io::Error,
+    },
+
+    /// An error occurred while parsing the timings file.
+    #[error("error parsing timings from `{path}`")]
+    Parse {
+        /// The path to the timings file.
+        path: Utf8PathBuf,
+
+        /// The underlying error.
+        #[source]
+        error: TimingsParseError,
+    },
+}
+
+/// An error that occurs while parsing recorded test timings with
+/// [`RecordedTimings::parse_csv`](crate::timings::RecordedTimings::parse_csv).
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TimingsParseError {
+    /// The header is missing a required column.
+    #[error("header is missing column `{name}`")]
+    MissingColumn {
+        /// The name of the missing column.
+        name: &'static str,
+    },
+
+    /// A record has a different number of fields than the header.
+    #[error("line {line}: expected {expected} fields, found {actual}")]
+    FieldCount {
+        /// The line the record starts on.
+        line: usize,
+
+        /// The number of fields in the header.
+        expected: usize,
+
+        /// The number of fields in the record.
+        actual: usize,
+    },
+
+    /// A field has an invalid value.
+    #[error("line {line}: invalid value for `{name}`: {value:?}")]
+    InvalidField {
+        /// The line the record starts on.
+        line: usize,
+
+        /// The name of the column.
+        name: &'static str,
+
+        /// The invalid value.
+        value: String,
+    },
+
+    /// A quoted field isn't terminated.
+    #[error("line {line}: unterminated quoted field")]
+    UnterminatedQuote {
+        /// The line the record starts on.
+        line: usize,
+    },
 }
 
 /// Represents an unknown archive format.
@@ -681,6 +1122,30 @@ pub enum ArchiveCreateError {
     /// An error occurred in the reporter.
     #[error("error reporting archive status")]
     ReporterIo(#[source] std::io::Error),
+
+    /// The host platform could not be determined while writing the archive manifest.
+    #[error("error creating archive manifest")]
+    UnknownHostPlatform(#[source] UnknownHostPlatform),
+
+    /// No test binaries were added to an [`ArchiveBuilder`](crate::reuse_build::ArchiveBuilder).
+    #[error("no test binaries were added to the archive")]
+    NoBinaries,
+
+    /// A test list added to an [`ArchiveBuilder`](crate::reuse_build::ArchiveBuilder) was built
+    /// differently from the test lists added before it.
+    #[error(
+        "test list has a different {field} (`{actual}`) from earlier test lists (`{expected}`)"
+    )]
+    MismatchedTestList {
+        /// The build metadata field that differs.
+        field: &'static str,
+
+        /// The value of the field in earlier test lists.
+        expected: String,
+
+        /// The value of the field in this test list.
+        actual: String,
+    },
 }
 
 fn kind_str(is_dir: Option<bool>) -> &'static str {
@@ -772,7 +1237,8 @@ pub enum ArchiveReadError {
 
 /// An error occurred while extracting a file.
 ///
-/// Returned by [`extract_archive`](crate::reuse_build::ReuseBuildInfo::extract_archive).
+/// Returned by [`ArchiveReader::extract`](crate::reuse_build::ArchiveReader::extract) and
+/// [`extract_archive`](crate::reuse_build::ReuseBuildInfo::extract_archive).
 #[derive(Debug, Error)]
 #[non_exhaustive]
 pub enum ArchiveExtractError {
@@ -817,6 +1283,23 @@ pub enum ArchiveExtractError {
     /// An error occurred while reporting the extraction status.
     #[error("error reporting extract status")]
     ReporterIo(std::io::Error),
+
+    /// The host platform could not be determined.
+    #[error("error checking archive platform")]
+    UnknownHostPlatform(#[source] UnknownHostPlatform),
+
+    /// The archive was built for a platform other than the current one.
+    #[error(
+        "archive was built for `{archive_platform}`, \
+         but the current platform is `{current_platform}`"
+    )]
+    PlatformMismatch {
+        /// The platform the test binaries in the archive were built for.
+        archive_platform: String,
+
+        /// The current platform.
+        current_platform: String,
+    },
 }
 
 /// An error that occurs while writing an event.
@@ -838,6 +1321,10 @@ pub enum WriteEventError {
         error: std::io::Error,
     },
 
+    /// An error occurred while reading timings recorded by an earlier run.
+    #[error("error reading earlier timings")]
+    ReadTimings(#[source] ReadTimingsError),
+
     /// An error occurred while producing JUnit XML.
     #[error("error writing JUnit output to {file}")]
     Junit {
@@ -1030,11 +1517,6 @@ pub enum TargetRunnerError {
     },
 }
 
-/// An error that occurred while setting up the signal handler.
-#[derive(Debug, Error)]
-#[error("error setting up signal handler")]
-pub struct SignalHandlerSetupError(#[from] std::io::Error);
-
 /// An error occurred while showing test groups.
 #[derive(Debug, Error)]
 pub enum ShowTestGroupsError {
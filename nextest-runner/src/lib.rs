@@ -9,6 +9,7 @@
 //! For the basic flow of operations in nextest, see [this blog
 //! post](https://sunshowers.io/posts/nextest-and-tokio/).
 
+mod assertion_count;
 pub mod cargo_config;
 pub mod config;
 #[cfg(feature = "experimental-tokio-console")]
@@ -17,16 +18,20 @@ pub mod double_spawn;
 pub mod errors;
 mod helpers;
 pub mod list;
+pub mod order_check;
 pub mod partition;
 pub mod platform;
 pub mod reporter;
 pub mod reuse_build;
+pub mod run_summary;
 pub mod runner;
 pub mod show_config;
 pub mod signal;
+mod tap;
 pub mod target_runner;
 mod test_command;
 pub mod test_filter;
 mod time;
+pub mod timings;
 #[cfg(feature = "self-update")]
 pub mod update;
@@ -12,12 +12,14 @@ mod display_filter;
 mod output_format;
 mod rust_build_meta;
 mod test_list;
+mod test_list_json;
 
 pub use binary_list::*;
 pub(crate) use display_filter::*;
 pub use output_format::*;
 pub use rust_build_meta::*;
 pub use test_list::*;
+pub use test_list_json::*;
 
 /// Typestate for [`BinaryList`].
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -91,6 +91,21 @@ impl RustBuildMeta<TestListState> {
         }
     }
 
+    /// Converts this metadata back to [`BinaryListState`], for example to archive the binaries in
+    /// a test list.
+    ///
+    /// The target directory is the one the test list was built from, after any path remapping.
+    pub(crate) fn to_binary_list_state(&self) -> RustBuildMeta<BinaryListState> {
+        RustBuildMeta {
+            target_directory: self.target_directory.clone(),
+            base_output_directories: self.base_output_directories.clone(),
+            non_test_binaries: self.non_test_binaries.clone(),
+            linked_paths: self.linked_paths.clone(),
+            state: PhantomData,
+            target_triple: self.target_triple.clone(),
+        }
+    }
+
     /// Returns the dynamic library paths corresponding to this metadata.
     ///
     /// [See this Cargo documentation for more.](https://doc.rust-lang.org/cargo/reference/environment-variables.html#dynamic-library-paths)
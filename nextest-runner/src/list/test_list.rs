@@ -4,16 +4,20 @@
 use super::{DisplayFilterMatcher, TestListDisplayFilter};
 use crate::{
     cargo_config::EnvironmentMap,
+    config::NextestProfile,
     double_spawn::DoubleSpawnInfo,
     errors::{CreateTestListError, FromMessagesError, WriteTestListError},
     helpers::{convert_build_platform, dylib_path, dylib_path_envvar, write_test_name},
-    list::{BinaryList, OutputFormat, RustBuildMeta, Styles, TestListState},
+    list::{
+        BinaryList, OutputFormat, RustBuildMeta, Styles, TestCaseJson, TestListJson, TestListState,
+        TestSettingsJson, TestSuiteJson,
+    },
     reuse_build::PathMapper,
     target_runner::{PlatformRunner, TargetRunner},
     test_command::{LocalExecuteContext, TestCommand},
     test_filter::TestFilterBuilder,
 };
-use camino::Utf8PathBuf;
+use camino::{Utf8Path, Utf8PathBuf};
 use futures::prelude::*;
 use guppy::{
     graph::{PackageGraph, PackageMetadata},
@@ -27,12 +31,14 @@ use nextest_metadata::{
 use once_cell::sync::{Lazy, OnceCell};
 use owo_colors::OwoColorize;
 use std::{
+    cell::RefCell,
     collections::{BTreeMap, BTreeSet},
     ffi::{OsStr, OsString},
     io,
     io::Write,
     path::PathBuf,
     sync::Arc,
+    time::{Duration, Instant},
 };
 use tokio::runtime::Runtime;
 
@@ -187,6 +193,56 @@ pub struct TestList<'g> {
     skip_count: OnceCell<usize>,
 }
 
+/// An event that occurs while a [`TestList`] is being built.
+///
+/// Produced by [`TestList::new_with_progress`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TestListEvent<'a> {
+    /// Listing has started.
+    ListStarted {
+        /// The number of test binaries to list.
+        binary_count: usize,
+    },
+
+    /// A test binary has started being run to list its tests.
+    BinaryStarted {
+        /// The binary ID.
+        binary_id: &'a RustBinaryId,
+    },
+
+    /// A test binary has been running for a while without its tests being listed.
+    ///
+    /// This is produced each time the slow period passes, so a binary that hangs is reported
+    /// repeatedly.
+    BinarySlow {
+        /// The binary ID.
+        binary_id: &'a RustBinaryId,
+
+        /// The time since the binary started being listed.
+        elapsed: Duration,
+    },
+
+    /// The tests in a test binary have been listed.
+    BinaryFinished {
+        /// The binary ID.
+        binary_id: &'a RustBinaryId,
+
+        /// The number of tests found in the binary.
+        test_count: usize,
+
+        /// The time it took to list tests in the binary.
+        elapsed: Duration,
+    },
+
+    /// A test binary was skipped, because the filter doesn't match any tests within it.
+    ///
+    /// The binary isn't run in this case.
+    BinarySkipped {
+        /// The binary ID.
+        binary_id: &'a RustBinaryId,
+    },
+}
+
 impl<'g> TestList<'g> {
     /// Creates a new test list by running the given command and applying the specified filter.
     pub fn new<I>(
@@ -200,6 +256,39 @@ impl<'g> TestList<'g> {
     where
         I: IntoIterator<Item = RustTestArtifact<'g>>,
         I::IntoIter: Send,
+    {
+        Self::new_with_progress(
+            ctx,
+            test_artifacts,
+            rust_build_meta,
+            filter,
+            env,
+            list_threads,
+            None,
+            |_| {},
+        )
+    }
+
+    /// Creates a new test list, calling `callback` as each test binary is listed.
+    ///
+    /// If `slow_period` is specified, a [`TestListEvent::BinarySlow`] event is produced each time
+    /// that period elapses while a binary is still being listed. This can be used to report
+    /// binaries that hang while their tests are being listed.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_progress<I, F>(
+        ctx: &TestExecuteContext<'_>,
+        test_artifacts: I,
+        rust_build_meta: RustBuildMeta<TestListState>,
+        filter: &TestFilterBuilder,
+        env: EnvironmentMap,
+        list_threads: usize,
+        slow_period: Option<Duration>,
+        callback: F,
+    ) -> Result<Self, CreateTestListError>
+    where
+        I: IntoIterator<Item = RustTestArtifact<'g>>,
+        I::IntoIter: Send,
+        F: FnMut(TestListEvent<'_>),
     {
         let updated_dylib_path = Self::create_dylib_path(&rust_build_meta)?;
         log::debug!(
@@ -216,19 +305,59 @@ impl<'g> TestList<'g> {
 
         let runtime = Runtime::new().map_err(CreateTestListError::TokioRuntimeCreate)?;
 
-        let stream = futures::stream::iter(test_artifacts.into_iter()).map(|test_binary| {
+        let test_artifacts: Vec<_> = test_artifacts.into_iter().collect();
+        // All the listing futures are polled from within the same task, so the callback is never
+        // called concurrently.
+        let callback = RefCell::new(callback);
+        let send_event = |event: TestListEvent<'_>| (callback.borrow_mut())(event);
+        send_event(TestListEvent::ListStarted {
+            binary_count: test_artifacts.len(),
+        });
+
+        let stream = futures::stream::iter(test_artifacts).map(|test_binary| {
             async {
                 if filter.should_obtain_test_list_from_binary(&test_binary) {
-                    // Run the binary to obtain the test list.
-                    let (non_ignored, ignored) = test_binary.exec(&ctx).await?;
+                    send_event(TestListEvent::BinaryStarted {
+                        binary_id: &test_binary.binary_id,
+                    });
+                    let start_time = Instant::now();
+
+                    // Run the binary to obtain the test list, reporting it if it's slow.
+                    let (non_ignored, ignored) = {
+                        let exec_fut = test_binary.exec(&ctx);
+                        tokio::pin!(exec_fut);
+                        let mut slow_interval = slow_period.map(|period| {
+                            tokio::time::interval_at(tokio::time::Instant::now() + period, period)
+                        });
+                        loop {
+                            tokio::select! {
+                                result = &mut exec_fut => break result?,
+                                _ = tick_interval(&mut slow_interval) => {
+                                    send_event(TestListEvent::BinarySlow {
+                                        binary_id: &test_binary.binary_id,
+                                        elapsed: start_time.elapsed(),
+                                    });
+                                }
+                            }
+                        }
+                    };
+
                     let (bin, info) = Self::process_output(
                         test_binary,
                         filter,
                         non_ignored.as_str(),
                         ignored.as_str(),
                     )?;
+                    send_event(TestListEvent::BinaryFinished {
+                        binary_id: &bin,
+                        test_count: info.status.test_count(),
+                        elapsed: start_time.elapsed(),
+                    });
                     Ok::<_, CreateTestListError>((bin, info))
                 } else {
+                    send_event(TestListEvent::BinarySkipped {
+                        binary_id: &test_binary.binary_id,
+                    });
                     // Skipped means no tests, so test_count doesn't need to be modified.
                     Ok(Self::process_skipped(test_binary))
                 }
@@ -366,6 +495,72 @@ impl<'g> TestList<'g> {
         summary
     }
 
+    /// Constructs a versioned, serializable representation of this test list, including the
+    /// settings each test will be run with under `profile`.
+    ///
+    /// Unlike [`Self::to_summary`], the returned [`TestListJson`] has a `format-version` field and
+    /// includes per-test settings.
+    pub fn to_json(&self, profile: &NextestProfile<'_>) -> TestListJson {
+        let rust_suites = self
+            .rust_suites
+            .values()
+            .map(|test_suite| {
+                let (status, _) = test_suite.status.to_summary();
+                let testcases = test_suite
+                    .status
+                    .test_cases()
+                    .map(|(name, test_info)| {
+                        let test_instance = TestInstance::new(name, test_suite, test_info);
+                        let settings = profile.settings_for(&test_instance.to_test_query());
+                        let slow_timeout = settings.slow_timeout();
+                        let settings = TestSettingsJson {
+                            test_group: settings.test_group().to_string(),
+                            retries: settings.retries().count(),
+                            slow_timeout: slow_timeout.period,
+                            timeout: slow_timeout.timeout(),
+                            terminate_after: slow_timeout.terminate_after,
+                            grace_period: slow_timeout.grace_period,
+                            leak_timeout: settings.leak_timeout().period,
+                            exclusive: settings.exclusive(),
+                        };
+                        let test_case = TestCaseJson {
+                            ignored: test_info.ignored,
+                            filter_match: test_info.filter_match,
+                            settings,
+                        };
+                        (name.to_owned(), test_case)
+                    })
+                    .collect();
+                let test_suite_json = TestSuiteJson {
+                    binary_id: test_suite.binary_id.clone(),
+                    binary_name: test_suite.binary_name.clone(),
+                    package_name: test_suite.package.name().to_owned(),
+                    package_id: test_suite.package.id().repr().to_owned(),
+                    kind: test_suite.kind.clone(),
+                    binary_path: test_suite.binary_path.clone(),
+                    build_platform: test_suite.build_platform,
+                    cwd: test_suite.cwd.clone(),
+                    non_test_binaries: test_suite.non_test_binaries.clone(),
+                    status,
+                    testcases,
+                };
+                (test_suite.binary_id.clone(), test_suite_json)
+            })
+            .collect();
+
+        TestListJson {
+            format_version: TestListJson::FORMAT_VERSION,
+            rust_build_meta: self.rust_build_meta.to_summary(),
+            test_count: self.test_count,
+            rust_suites,
+        }
+    }
+
+    /// Returns [`Self::to_json`] as a [`serde_json::Value`].
+    pub fn to_json_value(&self, profile: &NextestProfile<'_>) -> serde_json::Value {
+        serde_json::to_value(self.to_json(profile)).expect("TestListJson is always serializable")
+    }
+
     /// Outputs this list to the given writer.
     pub fn write(
         &self,
@@ -673,6 +868,16 @@ pub struct RustTestSuite<'g> {
     pub status: RustTestSuiteStatus,
 }
 
+/// Waits for the next tick of `interval`, or forever if it isn't set.
+async fn tick_interval(interval: &mut Option<tokio::time::Interval>) {
+    match interval {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => future::pending().await,
+    }
+}
+
 impl<'g> RustTestArtifact<'g> {
     /// Run this binary with and without --ignored and get the corresponding outputs.
     async fn exec(
@@ -687,7 +892,9 @@ impl<'g> RustTestArtifact<'g> {
                 cwd: self.cwd.clone(),
             });
         }
-        let platform_runner = ctx.runner.for_build_platform(self.build_platform);
+        let platform_runner =
+            ctx.runner
+                .for_binary(self.build_platform, &self.kind, &self.binary_name);
 
         let non_ignored = self.exec_single(false, ctx, platform_runner);
         let ignored = self.exec_single(true, ctx, platform_runner);
@@ -706,6 +913,7 @@ impl<'g> RustTestArtifact<'g> {
 
         let program: String = if let Some(runner) = runner {
             argv.extend(runner.args());
+            argv.extend(ctx.runner.extra_args());
             argv.push(self.binary_path.as_str());
             runner.binary().into()
         } else {
@@ -878,31 +1086,50 @@ impl<'a> TestInstance<'a> {
     }
 
     /// Creates the command for this test instance.
+    ///
+    /// `binary_path` is the path to execute, which is usually the suite's binary path.
     pub(crate) fn make_command(
         &self,
         ctx: &TestExecuteContext<'_>,
         test_list: &TestList<'_>,
+        binary_path: &Utf8Path,
+        test_args: &[String],
+        tracer: Option<(&str, &[String])>,
     ) -> TestCommand {
-        let platform_runner = ctx
-            .target_runner
-            .for_build_platform(self.suite_info.build_platform);
+        let platform_runner = ctx.target_runner.for_binary(
+            self.suite_info.build_platform,
+            &self.suite_info.kind,
+            &self.suite_info.binary_name,
+        );
         // TODO: non-rust tests
 
-        let mut args = Vec::new();
-
-        let program: String = match platform_runner {
+        let mut command_line = Vec::new();
+        match platform_runner {
             Some(runner) => {
-                args.extend(runner.args());
-                args.push(self.suite_info.binary_path.as_str());
-                runner.binary().into()
+                command_line.push(runner.binary());
+                command_line.extend(runner.args());
+                command_line.extend(ctx.target_runner.extra_args());
+                command_line.push(binary_path.as_str());
             }
-            None => self.suite_info.binary_path.to_owned().into(),
-        };
+            None => command_line.push(binary_path.as_str()),
+        }
+
+        // A tracer wraps the entire command line, including any target runner.
+        if let Some((tracer_program, tracer_args)) = tracer {
+            let mut traced = vec![tracer_program];
+            traced.extend(tracer_args.iter().map(|arg| arg.as_str()));
+            traced.append(&mut command_line);
+            command_line = traced;
+        }
+
+        let mut args = command_line.split_off(1);
+        let program: String = command_line[0].into();
 
         args.extend(["--exact", self.name, "--nocapture"]);
         if self.test_info.ignored {
             args.push("--ignored");
         }
+        args.extend(test_args.iter().map(|arg| arg.as_str()));
 
         let ctx = LocalExecuteContext {
             double_spawn: ctx.double_spawn,
@@ -922,6 +1149,26 @@ impl<'a> TestInstance<'a> {
     }
 }
 
+/// Returns true if `arg` conflicts with the arguments nextest passes to test binaries.
+///
+/// These are either passed in by nextest itself, or change which tests are run or how their output
+/// is formatted.
+pub(crate) fn is_harness_arg(arg: &str) -> bool {
+    const HARNESS_ARGS: &[&str] = &[
+        "--exact",
+        "--nocapture",
+        "--ignored",
+        "--include-ignored",
+        "--list",
+        "--format",
+        "--test-threads",
+    ];
+
+    // Options may also be specified as e.g. --format=json.
+    let name = arg.split_once('=').map_or(arg, |(name, _)| name);
+    HARNESS_ARGS.contains(&name)
+}
+
 /// Context required for test execution.
 #[derive(Clone, Debug)]
 pub struct TestExecuteContext<'a> {
@@ -936,8 +1183,11 @@ pub struct TestExecuteContext<'a> {
 mod tests {
     use super::*;
     use crate::{
-        cargo_config::{TargetDefinitionLocation, TargetTriple, TargetTripleSource},
+        cargo_config::{Runner, TargetDefinitionLocation, TargetTriple, TargetTripleSource},
+        config::{NextestConfig, TargetRunnerRule},
         list::SerializableFormat,
+        partition::{PartitionerBuilder, ShardAssignments, TimingAssignments},
+        platform::BuildPlatforms,
         test_filter::RunIgnored,
     };
     use guppy::CargoMetadata;
@@ -947,7 +1197,7 @@ mod tests {
     use nextest_metadata::{FilterMatch, MismatchReason};
     use once_cell::sync::Lazy;
     use pretty_assertions::assert_eq;
-    use std::iter;
+    use std::{iter, time::Duration};
     use target_spec::Platform;
 
     #[test]
@@ -1218,6 +1468,433 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_to_json() {
+        let non_ignored_output = indoc! {"
+            tests::foo::test_bar: test
+            tests::baz::test_quux: test
+        "};
+        let ignored_output = indoc! {"
+            tests::ignored::test_bar: test
+        "};
+
+        let test_filter = TestFilterBuilder::any(RunIgnored::Default);
+        let fake_binary_id = RustBinaryId::new("fake-package::fake-binary");
+        let test_binary = RustTestArtifact {
+            binary_path: "/fake/binary".into(),
+            cwd: "/fake/cwd".into(),
+            package: package_metadata(),
+            binary_name: "fake-binary".to_owned(),
+            binary_id: fake_binary_id.clone(),
+            kind: RustTestBinaryKind::LIB,
+            non_test_binaries: BTreeSet::new(),
+            build_platform: BuildPlatform::Target,
+        };
+        let rust_build_meta = RustBuildMeta::new("/fake", None).map_paths(&PathMapper::noop());
+        let test_list = TestList::new_with_outputs(
+            [(test_binary, &non_ignored_output, &ignored_output)],
+            rust_build_meta,
+            &test_filter,
+            EnvironmentMap::empty(),
+        )
+        .expect("valid output");
+
+        let config_dir = camino_tempfile::tempdir().unwrap();
+        let config_path = config_dir.path().join("nextest.toml");
+        std::fs::write(
+            &config_path,
+            indoc! {r#"
+                [[profile.default.overrides]]
+                filter = 'test(=tests::foo::test_bar)'
+                retries = 3
+                slow-timeout = { period = "30s", terminate-after = 2, grace-period = "5s" }
+                test-group = "serial"
+
+                [test-groups.serial]
+                max-threads = 1
+            "#},
+        )
+        .unwrap();
+        let config = NextestConfig::from_sources(
+            config_dir.path(),
+            &PACKAGE_GRAPH_FIXTURE,
+            Some(&config_path),
+            [],
+        )
+        .expect("config is valid");
+        let profile = config
+            .profile(NextestConfig::DEFAULT_PROFILE)
+            .expect("default profile exists")
+            .apply_build_platforms(&BuildPlatforms::new(None).unwrap());
+
+        let value = test_list.to_json_value(&profile);
+        assert_eq!(value["format-version"], TestListJson::FORMAT_VERSION);
+        assert_eq!(value["test-count"], 3);
+
+        let testcases = &value["rust-suites"]["fake-package::fake-binary"]["testcases"];
+        assert_eq!(
+            testcases["tests::foo::test_bar"]["settings"],
+            serde_json::json!({
+                "test-group": "serial",
+                "retries": 3,
+                "slow-timeout": "30s",
+                "timeout": "1m",
+                "terminate-after": 2,
+                "grace-period": "5s",
+                "leak-timeout": "100ms",
+                "exclusive": false,
+            }),
+        );
+        assert_eq!(
+            testcases["tests::baz::test_quux"]["settings"],
+            serde_json::json!({
+                "test-group": "@global",
+                "retries": 0,
+                "slow-timeout": "1m",
+                "timeout": null,
+                "terminate-after": null,
+                "grace-period": "10s",
+                "leak-timeout": "100ms",
+                "exclusive": false,
+            }),
+        );
+        assert_eq!(testcases["tests::ignored::test_bar"]["ignored"], true);
+
+        // The value can be deserialized back into the typed representation.
+        let roundtrip: TestListJson =
+            serde_json::from_value(value).expect("TestListJson deserializes");
+        assert_eq!(roundtrip, test_list.to_json(&profile));
+    }
+
+    #[test]
+    fn test_make_command_target_runner_args() {
+        let suite = RustTestSuite {
+            binary_id: RustBinaryId::new("fake-package::fake-binary"),
+            binary_path: "/fake/binary".into(),
+            package: package_metadata(),
+            binary_name: "fake-binary".to_owned(),
+            kind: RustTestBinaryKind::LIB,
+            cwd: "/fake/cwd".into(),
+            build_platform: BuildPlatform::Target,
+            non_test_binaries: BTreeSet::new(),
+            status: RustTestSuiteStatus::Listed {
+                test_cases: btreemap! {
+                    "tests::foo".to_owned() => RustTestCaseSummary {
+                        ignored: false,
+                        filter_match: FilterMatch::Matches,
+                    },
+                },
+            },
+        };
+        let (name, test_info) = suite.status.test_cases().next().unwrap();
+        let test_instance = TestInstance::new(name, &suite, test_info);
+
+        let workspace_root = Utf8Path::new("/fake/workspace");
+        let target_runner = TargetRunner::empty()
+            .with_rules(
+                &[TargetRunnerRule {
+                    kind: None,
+                    binary: None,
+                    runner: Runner::List(vec![
+                        "qemu-aarch64".to_owned(),
+                        "-cpu".to_owned(),
+                        "max".to_owned(),
+                    ]),
+                }],
+                workspace_root,
+            )
+            .expect("rule is valid")
+            .with_extra_args(["-L", "{workspace-root}/sysroot"], workspace_root);
+        let double_spawn = DoubleSpawnInfo::disabled();
+        let ctx = TestExecuteContext {
+            double_spawn: &double_spawn,
+            target_runner: &target_runner,
+        };
+
+        let mut command =
+            test_instance.make_command(&ctx, &TestList::empty(), &suite.binary_path, &[], None);
+        let command = command.command_mut();
+        assert_eq!(command.get_program(), "qemu-aarch64");
+        let args: Vec<_> = command
+            .get_args()
+            .map(|arg| arg.to_str().unwrap())
+            .collect();
+        assert_eq!(
+            args,
+            [
+                "-cpu",
+                "max",
+                "-L",
+                "/fake/workspace/sysroot",
+                "/fake/binary",
+                "--exact",
+                "tests::foo",
+                "--nocapture",
+            ],
+            "runner args come first, then extra args, then the test binary and its args"
+        );
+    }
+
+    #[test]
+    fn test_make_command_test_args() {
+        let suite = RustTestSuite {
+            binary_id: RustBinaryId::new("fake-package::fake-binary"),
+            binary_path: "/fake/binary".into(),
+            package: package_metadata(),
+            binary_name: "fake-binary".to_owned(),
+            kind: RustTestBinaryKind::LIB,
+            cwd: "/fake/cwd".into(),
+            build_platform: BuildPlatform::Target,
+            non_test_binaries: BTreeSet::new(),
+            status: RustTestSuiteStatus::Listed {
+                test_cases: btreemap! {
+                    "tests::foo".to_owned() => RustTestCaseSummary {
+                        ignored: false,
+                        filter_match: FilterMatch::Matches,
+                    },
+                    "tests::ignored".to_owned() => RustTestCaseSummary {
+                        ignored: true,
+                        filter_match: FilterMatch::Matches,
+                    },
+                },
+            },
+        };
+
+        let target_runner = TargetRunner::empty();
+        let double_spawn = DoubleSpawnInfo::disabled();
+        let ctx = TestExecuteContext {
+            double_spawn: &double_spawn,
+            target_runner: &target_runner,
+        };
+        let test_args = vec!["--show-output".to_owned(), "--color=never".to_owned()];
+
+        for (name, test_info) in suite.status.test_cases() {
+            let test_instance = TestInstance::new(name, &suite, test_info);
+            let mut command = test_instance.make_command(
+                &ctx,
+                &TestList::empty(),
+                &suite.binary_path,
+                &test_args,
+                None,
+            );
+            let command = command.command_mut();
+            assert_eq!(command.get_program(), "/fake/binary");
+            let args: Vec<_> = command
+                .get_args()
+                .map(|arg| arg.to_str().unwrap())
+                .collect();
+
+            let mut expected = vec!["--exact", name, "--nocapture"];
+            if test_info.ignored {
+                expected.push("--ignored");
+            }
+            expected.extend(["--show-output", "--color=never"]);
+            assert_eq!(args, expected, "for {name}, test args are appended last");
+        }
+    }
+
+    #[test]
+    fn test_make_command_tracer() {
+        let suite = RustTestSuite {
+            binary_id: RustBinaryId::new("fake-package::fake-binary"),
+            binary_path: "/fake/binary".into(),
+            package: package_metadata(),
+            binary_name: "fake-binary".to_owned(),
+            kind: RustTestBinaryKind::LIB,
+            cwd: "/fake/cwd".into(),
+            build_platform: BuildPlatform::Target,
+            non_test_binaries: BTreeSet::new(),
+            status: RustTestSuiteStatus::Listed {
+                test_cases: btreemap! {
+                    "tests::foo".to_owned() => RustTestCaseSummary {
+                        ignored: false,
+                        filter_match: FilterMatch::Matches,
+                    },
+                },
+            },
+        };
+
+        let target_runner = TargetRunner::empty();
+        let double_spawn = DoubleSpawnInfo::disabled();
+        let ctx = TestExecuteContext {
+            double_spawn: &double_spawn,
+            target_runner: &target_runner,
+        };
+        let tracer_args = vec!["-f".to_owned(), "-o".to_owned(), "/traces/foo".to_owned()];
+
+        let (name, test_info) = suite.status.test_cases().next().unwrap();
+        let test_instance = TestInstance::new(name, &suite, test_info);
+        let mut command = test_instance.make_command(
+            &ctx,
+            &TestList::empty(),
+            &suite.binary_path,
+            &[],
+            Some(("strace", &tracer_args)),
+        );
+        let command = command.command_mut();
+        assert_eq!(command.get_program(), "strace");
+        let args: Vec<_> = command
+            .get_args()
+            .map(|arg| arg.to_str().unwrap())
+            .collect();
+        assert_eq!(
+            args,
+            [
+                "-f",
+                "-o",
+                "/traces/foo",
+                "/fake/binary",
+                "--exact",
+                "tests::foo",
+                "--nocapture",
+            ],
+            "tracer args come first, then the test binary and its args"
+        );
+    }
+
+    #[test]
+    fn test_is_harness_arg() {
+        for arg in ["--exact", "--include-ignored", "--format", "--format=json"] {
+            assert!(is_harness_arg(arg), "{arg} is a harness arg");
+        }
+        for arg in ["--show-output", "--color=never", "--formatted", "exact"] {
+            assert!(!is_harness_arg(arg), "{arg} isn't a harness arg");
+        }
+    }
+
+    #[test]
+    fn test_shard_assignments() {
+        let non_ignored_output = indoc! {"
+            tests::alpha::test_one: test
+            tests::alpha::test_two: test
+            tests::beta::test_one: test
+            tests::beta::test_two: test
+            tests::gamma::test_one: test
+            tests::gamma::test_two: test
+            tests::delta: test
+            other::test_filtered_out: test
+        "};
+        let ignored_output = indoc! {"
+            tests::alpha::test_ignored: test
+            tests::beta::test_ignored: test
+            tests::gamma::test_ignored: test
+        "};
+        let binary_id = RustBinaryId::new("fake-package::fake-binary");
+
+        let make_list = |partitioner: Option<PartitionerBuilder>| {
+            let test_filter =
+                TestFilterBuilder::new(RunIgnored::All, partitioner, ["tests::"], Vec::new())
+                    .unwrap();
+            let test_binary = RustTestArtifact {
+                binary_path: "/fake/binary".into(),
+                cwd: "/fake/cwd".into(),
+                package: package_metadata(),
+                binary_name: "fake-binary".to_owned(),
+                binary_id: binary_id.clone(),
+                kind: RustTestBinaryKind::LIB,
+                non_test_binaries: BTreeSet::new(),
+                build_platform: BuildPlatform::Target,
+            };
+            TestList::new_with_outputs(
+                [(test_binary, &non_ignored_output, &ignored_output)],
+                RustBuildMeta::new("/fake", None).map_paths(&PathMapper::noop()),
+                &test_filter,
+                EnvironmentMap::empty(),
+            )
+            .expect("valid output")
+        };
+
+        // Only some tests have recorded durations, so that timings-based partitioning falls back
+        // to hashing for the rest.
+        let durations = btreemap! {
+            (binary_id.to_string(), "tests::alpha::test_one".to_owned()) => Duration::from_secs(5),
+            (binary_id.to_string(), "tests::beta::test_two".to_owned()) => Duration::from_secs(3),
+            (binary_id.to_string(), "tests::gamma::test_ignored".to_owned()) => Duration::from_secs(1),
+        };
+        let timing_assignments = TimingAssignments::new(&durations, 3);
+        let builders: Vec<(&str, Box<dyn Fn(u64) -> PartitionerBuilder>)> = vec![
+            (
+                "count",
+                Box::new(|shard| {
+                    format!("count:{shard}/3")
+                        .parse::<PartitionerBuilder>()
+                        .unwrap()
+                }),
+            ),
+            (
+                "hash",
+                Box::new(|shard| {
+                    format!("hash:{shard}/3")
+                        .parse::<PartitionerBuilder>()
+                        .unwrap()
+                }),
+            ),
+            (
+                "prefix",
+                Box::new(|shard| {
+                    format!("prefix:{shard}/3:2")
+                        .parse::<PartitionerBuilder>()
+                        .unwrap()
+                }),
+            ),
+            (
+                "timings",
+                Box::new(move |shard| PartitionerBuilder::Timings {
+                    shard,
+                    total_shards: 3,
+                    assignments: timing_assignments.clone(),
+                }),
+            ),
+        ];
+
+        for (mode, builder) in builders {
+            // The shard number of the builder doesn't affect assignments.
+            let assignments = ShardAssignments::new(&builder(2), &make_list(None));
+            assert_eq!(assignments.total_shards(), 3, "for mode {mode}");
+            assert_eq!(
+                assignments.shard_for(&binary_id, "other::test_filtered_out"),
+                None,
+                "for mode {mode}, tests filtered out for other reasons have no shard"
+            );
+
+            let mut seen = 0;
+            for shard in 1..=3 {
+                let partitioned = make_list(Some(builder(shard)));
+                for test in partitioned.iter_tests() {
+                    let assigned = assignments.shard_for(&binary_id, test.name);
+                    match test.test_info.filter_match {
+                        FilterMatch::Matches => {
+                            seen += 1;
+                            assert_eq!(
+                                assigned,
+                                Some(shard),
+                                "for mode {mode}, test {} runs on shard {shard}",
+                                test.name,
+                            );
+                        }
+                        FilterMatch::Mismatch {
+                            reason: MismatchReason::Partition,
+                        } => {
+                            assert!(
+                                matches!(assigned, Some(assigned) if assigned != shard),
+                                "for mode {mode}, test {} doesn't run on shard {shard}, \
+                                 but is assigned to {assigned:?}",
+                                test.name,
+                            );
+                        }
+                        FilterMatch::Mismatch { .. } => {
+                            assert_eq!(assigned, None, "for mode {mode}, test {}", test.name);
+                        }
+                    }
+                }
+            }
+            assert_eq!(
+                seen, 10,
+                "for mode {mode}, each test runs on exactly one shard"
+            );
+        }
+    }
+
     static PACKAGE_GRAPH_FIXTURE: Lazy<PackageGraph> = Lazy::new(|| {
         static FIXTURE_JSON: &str = include_str!("../../../fixtures/cargo-metadata.json");
         let metadata = CargoMetadata::parse_json(FIXTURE_JSON).expect("fixture is valid JSON");
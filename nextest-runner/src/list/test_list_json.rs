@@ -0,0 +1,135 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! A versioned, machine-readable representation of a [`TestList`](super::TestList).
+//!
+//! These types are deliberately kept separate from the runner's internal types, so that changes
+//! to the latter don't silently change the format consumers depend on.
+
+use camino::Utf8PathBuf;
+use nextest_metadata::{
+    BuildPlatform, FilterMatch, RustBinaryId, RustBuildMetaSummary, RustTestBinaryKind,
+    RustTestSuiteStatusSummary,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    num::NonZeroUsize,
+    time::Duration,
+};
+
+/// A test list along with the settings each test will be run with, as returned by
+/// [`TestList::to_json`](super::TestList::to_json).
+///
+/// Contains enough information to reconstruct the test list.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TestListJson {
+    /// The version of this format. This is [`Self::FORMAT_VERSION`] for lists produced by this
+    /// version of nextest.
+    pub format_version: u32,
+
+    /// Rust metadata used for builds and test runs.
+    pub rust_build_meta: RustBuildMetaSummary,
+
+    /// The number of tests in the list, across all binaries.
+    pub test_count: usize,
+
+    /// The test suites in the list, keyed by binary ID.
+    pub rust_suites: BTreeMap<RustBinaryId, TestSuiteJson>,
+}
+
+impl TestListJson {
+    /// The current format version.
+    ///
+    /// This is incremented whenever a field is removed or its meaning changes. Adding new fields
+    /// doesn't change the version.
+    pub const FORMAT_VERSION: u32 = 1;
+}
+
+/// A test suite within a [`TestListJson`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TestSuiteJson {
+    /// A unique identifier for this binary.
+    pub binary_id: RustBinaryId,
+
+    /// The unique binary name defined in `Cargo.toml` or inferred by the filename.
+    pub binary_name: String,
+
+    /// The name of the package this binary is a part of.
+    pub package_name: String,
+
+    /// The ID of the package this binary is a part of.
+    pub package_id: String,
+
+    /// The kind of Rust test binary this is.
+    pub kind: RustTestBinaryKind,
+
+    /// The path to the binary.
+    pub binary_path: Utf8PathBuf,
+
+    /// The platform the binary was built for.
+    pub build_platform: BuildPlatform,
+
+    /// The working directory that tests in this binary are executed in.
+    pub cwd: Utf8PathBuf,
+
+    /// Non-test binaries exposed to this binary at runtime (name, path).
+    pub non_test_binaries: BTreeSet<(String, Utf8PathBuf)>,
+
+    /// Whether the binary was listed or skipped.
+    pub status: RustTestSuiteStatusSummary,
+
+    /// The tests in this binary, keyed by name.
+    pub testcases: BTreeMap<String, TestCaseJson>,
+}
+
+/// A test case within a [`TestSuiteJson`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TestCaseJson {
+    /// Whether the test is marked ignored.
+    pub ignored: bool,
+
+    /// Whether the test matches the provided filters.
+    pub filter_match: FilterMatch,
+
+    /// The settings the test will be run with, as resolved from the profile.
+    pub settings: TestSettingsJson,
+}
+
+/// The settings for a test, resolved from a profile and its overrides.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TestSettingsJson {
+    /// The test group the test is in, or `@global` if it isn't in a custom group.
+    pub test_group: String,
+
+    /// The number of times the test is retried on failure.
+    pub retries: usize,
+
+    /// The amount of time after which the test is marked slow.
+    #[serde(with = "humantime_serde")]
+    pub slow_timeout: Duration,
+
+    /// The amount of time after which the test is terminated, or `None` if the test is never
+    /// terminated.
+    #[serde(default, with = "humantime_serde")]
+    pub timeout: Option<Duration>,
+
+    /// The number of slow-timeout periods after which the test is terminated.
+    pub terminate_after: Option<NonZeroUsize>,
+
+    /// The amount of time the test is given to exit after being asked to terminate.
+    #[serde(with = "humantime_serde")]
+    pub grace_period: Duration,
+
+    /// The amount of time to wait for the test's standard output and standard error to be closed
+    /// after it exits.
+    #[serde(with = "humantime_serde")]
+    pub leak_timeout: Duration,
+
+    /// Whether the test is run with no other tests running.
+    pub exclusive: bool,
+}
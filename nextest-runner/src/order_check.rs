@@ -0,0 +1,213 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Detection of tests whose outcome depends on the order tests are run in.
+//!
+//! Tests are run twice: once in the usual order, and once shuffled with
+//! [`TestRunnerBuilder::set_shuffle_seed`](crate::runner::TestRunnerBuilder::set_shuffle_seed).
+//! The outcomes of the two runs are collected into [`RunOutcomes`], then compared with
+//! [`RunOutcomes::compare`].
+
+use crate::{
+    reporter::TestEvent,
+    runner::{ExecutionDescription, ExecutionStatuses},
+};
+use nextest_metadata::RustBinaryId;
+use std::{collections::BTreeMap, fmt};
+
+/// The outcome of a single test within a run.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RunOutcome {
+    /// The test passed on the first attempt.
+    Passed,
+
+    /// The test failed, then passed on a retry.
+    Flaky,
+
+    /// The test failed on every attempt.
+    Failed,
+}
+
+impl RunOutcome {
+    /// Returns the outcome corresponding to these execution statuses.
+    pub fn new(run_statuses: &ExecutionStatuses) -> Self {
+        match run_statuses.describe() {
+            ExecutionDescription::Success { .. } => Self::Passed,
+            ExecutionDescription::Flaky { .. } => Self::Flaky,
+            ExecutionDescription::Failure { .. } => Self::Failed,
+        }
+    }
+}
+
+impl fmt::Display for RunOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Passed => write!(f, "passed"),
+            Self::Flaky => write!(f, "flaky"),
+            Self::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+/// The outcomes of all tests that finished in a run.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RunOutcomes {
+    outcomes: BTreeMap<(RustBinaryId, String), RunOutcome>,
+}
+
+impl RunOutcomes {
+    /// Creates a new, empty set of outcomes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the outcome of a test if `event` is a [`TestEvent::TestFinished`] event. Other
+    /// events are ignored.
+    pub fn record(&mut self, event: &TestEvent<'_>) {
+        if let TestEvent::TestFinished {
+            test_instance,
+            run_statuses,
+            ..
+        } = event
+        {
+            self.insert(
+                test_instance.suite_info.binary_id.clone(),
+                test_instance.name,
+                RunOutcome::new(run_statuses),
+            );
+        }
+    }
+
+    /// Records the outcome of a test.
+    pub fn insert(
+        &mut self,
+        binary_id: RustBinaryId,
+        test_name: impl Into<String>,
+        outcome: RunOutcome,
+    ) {
+        self.outcomes.insert((binary_id, test_name.into()), outcome);
+    }
+
+    /// Returns the number of recorded outcomes.
+    pub fn len(&self) -> usize {
+        self.outcomes.len()
+    }
+
+    /// Returns true if no outcomes were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.outcomes.is_empty()
+    }
+
+    /// Compares outcomes from a run in the usual order (`self`) with outcomes from a shuffled run,
+    /// returning the tests whose outcomes changed.
+    ///
+    /// Tests that only finished in one of the runs (for example, because a run was canceled) are
+    /// ignored. The returned changes are sorted by binary ID and test name.
+    pub fn compare(&self, shuffled: &RunOutcomes) -> Vec<OutcomeChange> {
+        self.outcomes
+            .iter()
+            .filter_map(|(key, &in_order)| {
+                let &shuffled = shuffled.outcomes.get(key)?;
+                (in_order != shuffled).then(|| {
+                    let (binary_id, test_name) = key.clone();
+                    OutcomeChange {
+                        binary_id,
+                        test_name,
+                        in_order,
+                        shuffled,
+                    }
+                })
+            })
+            .collect()
+    }
+}
+
+/// A test whose outcome changed between a run in the usual order and a shuffled run.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OutcomeChange {
+    /// The binary ID of the test.
+    pub binary_id: RustBinaryId,
+
+    /// The name of the test.
+    pub test_name: String,
+
+    /// The outcome of the test when run in the usual order.
+    pub in_order: RunOutcome,
+
+    /// The outcome of the test when run shuffled.
+    pub shuffled: RunOutcome,
+}
+
+impl OutcomeChange {
+    /// Returns the likely cause of this change.
+    pub fn kind(&self) -> OutcomeChangeKind {
+        if self.in_order == RunOutcome::Flaky || self.shuffled == RunOutcome::Flaky {
+            // A test that passed on a retry within a single run is flaky regardless of order, so
+            // the change can't be attributed to order.
+            OutcomeChangeKind::Flaky
+        } else {
+            OutcomeChangeKind::OrderDependent
+        }
+    }
+}
+
+/// The likely cause of an [`OutcomeChange`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OutcomeChangeKind {
+    /// The test consistently passed in one run and consistently failed in the other, so its
+    /// outcome likely depends on the order tests are run in.
+    ///
+    /// If retries are disabled, a flaky test may also show up as order-dependent: enable retries
+    /// to tell them apart.
+    OrderDependent,
+
+    /// The test was flaky in at least one of the runs, so the change is likely due to flakiness.
+    Flaky,
+}
+
+/// Returns a random seed, suitable for
+/// [`TestRunnerBuilder::set_shuffle_seed`](crate::runner::TestRunnerBuilder::set_shuffle_seed).
+pub fn random_seed() -> u64 {
+    rand::random()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_outcomes() {
+        let binary_id = RustBinaryId::new("my-crate::tests");
+        let mut in_order = RunOutcomes::new();
+        let mut shuffled = RunOutcomes::new();
+        for (test_name, first, second) in [
+            ("test_stable_pass", RunOutcome::Passed, RunOutcome::Passed),
+            ("test_stable_fail", RunOutcome::Failed, RunOutcome::Failed),
+            // Synthetic order-dependent test: passes only if another test ran first.
+            ("test_needs_setup", RunOutcome::Passed, RunOutcome::Failed),
+            ("test_flaky", RunOutcome::Passed, RunOutcome::Flaky),
+            ("test_very_flaky", RunOutcome::Flaky, RunOutcome::Failed),
+        ] {
+            in_order.insert(binary_id.clone(), test_name, first);
+            shuffled.insert(binary_id.clone(), test_name, second);
+        }
+        // Only finished in the first run.
+        in_order.insert(binary_id.clone(), "test_canceled", RunOutcome::Passed);
+
+        let changes = in_order.compare(&shuffled);
+        let summary: Vec<_> = changes
+            .iter()
+            .map(|change| (change.test_name.as_str(), change.kind()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("test_flaky", OutcomeChangeKind::Flaky),
+                ("test_needs_setup", OutcomeChangeKind::OrderDependent),
+                ("test_very_flaky", OutcomeChangeKind::Flaky),
+            ]
+        );
+        assert_eq!(changes[1].in_order, RunOutcome::Passed);
+        assert_eq!(changes[1].shuffled, RunOutcome::Failed);
+    }
+}
@@ -3,15 +3,22 @@
 
 //! Support for partitioning test runs across several machines.
 //!
-//! At the moment this only supports simple hash-based and count-based sharding. In the future it
-//! could potentially be made smarter: e.g. using data to pick different sets of binaries and tests
-//! to run, with an aim to minimize total build and test times.
+//! This supports simple hash-based and count-based sharding, as well as sharding based on test
+//! durations recorded in a prior run's JUnit report. In the future it could potentially be made
+//! smarter: e.g. using data to pick different sets of binaries and tests to run, with an aim to
+//! minimize total build and test times.
 
-use crate::errors::PartitionerBuilderParseError;
+use crate::{errors::PartitionerBuilderParseError, list::TestList};
+use camino::Utf8Path;
+use nextest_metadata::{FilterMatch, MismatchReason, RustBinaryId};
+use quick_xml::events::{BytesStart, Event};
 use std::{
+    collections::{BTreeMap, HashMap},
     fmt,
-    hash::{Hash, Hasher},
+    hash::Hasher,
     str::FromStr,
+    sync::Arc,
+    time::Duration,
 };
 use twox_hash::XxHash64;
 
@@ -39,15 +46,59 @@ pub enum PartitionerBuilder {
         /// The total number of shards.
         total_shards: u64,
     },
+
+    /// Partition based on hashing the first few segments of test names, so that tests in the same
+    /// module are on the same shard. Individual partitions are stateless.
+    Prefix {
+        /// The shard this is in, counting up from 1.
+        shard: u64,
+
+        /// The total number of shards.
+        total_shards: u64,
+
+        /// The number of `::`-separated segments of the test name that are hashed. Tests whose
+        /// names have this many segments or fewer are partitioned as with
+        /// [`Self::Hash`].
+        segments: usize,
+    },
+
+    /// Partition based on test durations recorded in a prior run, so that each shard takes
+    /// roughly the same amount of time.
+    Timings {
+        /// The shard this is in, counting up from 1.
+        shard: u64,
+
+        /// The total number of shards.
+        total_shards: u64,
+
+        /// The shards that tests with recorded durations are assigned to.
+        assignments: TimingAssignments,
+    },
 }
 
 /// Represents an individual partitioner, typically scoped to a test binary.
 pub trait Partitioner: fmt::Debug {
-    /// Returns true if the given test name matches the partition.
-    fn test_matches(&mut self, test_name: &str) -> bool;
+    /// Returns true if the given test matches the partition.
+    fn test_matches(&mut self, binary_id: &RustBinaryId, test_name: &str) -> bool;
+
+    /// Returns the shard the given test is assigned to, counting up from 1.
+    ///
+    /// As with [`Self::test_matches`], this may update the state of the partitioner, so each test
+    /// should be passed in exactly once.
+    fn shard_for(&mut self, binary_id: &RustBinaryId, test_name: &str) -> u64;
 }
 
 impl PartitionerBuilder {
+    /// Returns the total number of shards.
+    pub fn total_shards(&self) -> u64 {
+        match self {
+            PartitionerBuilder::Count { total_shards, .. }
+            | PartitionerBuilder::Hash { total_shards, .. }
+            | PartitionerBuilder::Prefix { total_shards, .. }
+            | PartitionerBuilder::Timings { total_shards, .. } => *total_shards,
+        }
+    }
+
     /// Creates a new `Partitioner` from this `PartitionerBuilder`.
     pub fn build(&self) -> Box<dyn Partitioner> {
         // Note we don't use test_binary at the moment but might in the future.
@@ -60,6 +111,20 @@ impl PartitionerBuilder {
                 shard,
                 total_shards,
             } => Box::new(HashPartitioner::new(*shard, *total_shards)),
+            PartitionerBuilder::Prefix {
+                shard,
+                total_shards,
+                segments,
+            } => Box::new(PrefixPartitioner::new(*shard, *total_shards, *segments)),
+            PartitionerBuilder::Timings {
+                shard,
+                total_shards,
+                assignments,
+            } => Box::new(TimingsPartitioner::new(
+                *shard,
+                *total_shards,
+                assignments.clone(),
+            )),
         }
     }
 }
@@ -83,10 +148,52 @@ impl FromStr for PartitionerBuilder {
                 shard,
                 total_shards,
             })
+        } else if let Some(input) = s.strip_prefix("prefix:") {
+            let (shards, segments) = input.split_once(':').ok_or_else(|| {
+                PartitionerBuilderParseError::new(
+                    Some("prefix:M/N:K"),
+                    format!("expected input '{input}' to be in the format M/N:K"),
+                )
+            })?;
+            let (shard, total_shards) = parse_shards(shards, "prefix:M/N:K")?;
+            let segments = match segments.parse::<usize>() {
+                Ok(segments) if segments > 0 => segments,
+                _ => {
+                    return Err(PartitionerBuilderParseError::new(
+                        Some("prefix:M/N:K"),
+                        format!("number of segments '{segments}' must be a positive integer"),
+                    ))
+                }
+            };
+
+            Ok(PartitionerBuilder::Prefix {
+                shard,
+                total_shards,
+                segments,
+            })
+        } else if let Some(input) = s.strip_prefix("timings:") {
+            // The path comes last, since it may contain ':' and '/'.
+            let (shards, path) = input.split_once(':').ok_or_else(|| {
+                PartitionerBuilderParseError::new(
+                    Some("timings:M/N:PATH"),
+                    format!("expected input '{input}' to be in the format M/N:PATH"),
+                )
+            })?;
+            let (shard, total_shards) = parse_shards(shards, "timings:M/N:PATH")?;
+            let durations = read_junit_durations(Utf8Path::new(path))?;
+
+            Ok(PartitionerBuilder::Timings {
+                shard,
+                total_shards,
+                assignments: TimingAssignments::new(&durations, total_shards),
+            })
         } else {
             Err(PartitionerBuilderParseError::new(
                 None,
-                format!("partition input '{s}' must begin with \"hash:\" or \"count:\""),
+                format!(
+                    "partition input '{s}' must begin with \"hash:\", \"count:\", \"prefix:\" \
+                     or \"timings:\""
+                ),
             ))
         }
     }
@@ -153,10 +260,14 @@ impl CountPartitioner {
 }
 
 impl Partitioner for CountPartitioner {
-    fn test_matches(&mut self, _test_name: &str) -> bool {
-        let matches = self.curr == self.shard_minus_one;
+    fn test_matches(&mut self, binary_id: &RustBinaryId, test_name: &str) -> bool {
+        self.shard_for(binary_id, test_name) - 1 == self.shard_minus_one
+    }
+
+    fn shard_for(&mut self, _binary_id: &RustBinaryId, _test_name: &str) -> u64 {
+        let shard = self.curr + 1;
         self.curr = (self.curr + 1) % self.total_shards;
-        matches
+        shard
     }
 }
 
@@ -177,16 +288,299 @@ impl HashPartitioner {
 }
 
 impl Partitioner for HashPartitioner {
-    fn test_matches(&mut self, test_name: &str) -> bool {
-        let mut hasher = XxHash64::default();
-        test_name.hash(&mut hasher);
-        hasher.finish() % self.total_shards == self.shard_minus_one
+    fn test_matches(&mut self, binary_id: &RustBinaryId, test_name: &str) -> bool {
+        self.shard_for(binary_id, test_name) - 1 == self.shard_minus_one
+    }
+
+    fn shard_for(&mut self, binary_id: &RustBinaryId, test_name: &str) -> u64 {
+        partition_hash(binary_id.as_str(), test_name) % self.total_shards + 1
+    }
+}
+
+#[derive(Clone, Debug)]
+struct PrefixPartitioner {
+    shard_minus_one: u64,
+    total_shards: u64,
+    segments: usize,
+}
+
+impl PrefixPartitioner {
+    fn new(shard: u64, total_shards: u64, segments: usize) -> Self {
+        Self {
+            shard_minus_one: shard - 1,
+            total_shards,
+            segments,
+        }
     }
 }
 
+impl Partitioner for PrefixPartitioner {
+    fn test_matches(&mut self, binary_id: &RustBinaryId, test_name: &str) -> bool {
+        self.shard_for(binary_id, test_name) - 1 == self.shard_minus_one
+    }
+
+    fn shard_for(&mut self, binary_id: &RustBinaryId, test_name: &str) -> u64 {
+        let prefix = test_name_prefix(test_name, self.segments);
+        partition_hash(binary_id.as_str(), prefix) % self.total_shards + 1
+    }
+}
+
+/// Returns the first `segments` `::`-separated segments of `test_name`, or the entire name if it
+/// has `segments` segments or fewer.
+fn test_name_prefix(test_name: &str, segments: usize) -> &str {
+    match test_name.match_indices("::").nth(segments - 1) {
+        Some((idx, _)) => &test_name[..idx],
+        None => test_name,
+    }
+}
+
+/// Returns the hash used to assign a test to a shard with hashed partitioning.
+///
+/// This is the 64-bit [xxHash](https://xxhash.com/) (XXH64, seed 0) of the binary ID, a NUL byte,
+/// and the test name. Only the test's own identity is hashed, so adding or removing other tests
+/// never moves a test to a different shard.
+///
+/// The algorithm and the encoding of its input are pinned, and must not change: doing so would
+/// move tests between shards. The `partition_hash_is_stable` test checks against known values.
+fn partition_hash(binary_id: &str, test_name: &str) -> u64 {
+    // Bytes are fed to the hasher directly rather than through `Hash` impls, since the latter
+    // aren't guaranteed to be stable across Rust versions.
+    let mut hasher = XxHash64::with_seed(0);
+    hasher.write(binary_id.as_bytes());
+    hasher.write(&[0]);
+    hasher.write(test_name.as_bytes());
+    hasher.finish()
+}
+
+/// The shards that tests are assigned to, computed from the durations recorded in a prior run.
+///
+/// Tests are assigned greedily, longest first, to the shard with the least total duration so
+/// far. Ties are broken by test name and by shard index, so the same durations always produce the
+/// same assignments.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TimingAssignments {
+    // (binary ID, test name) -> shard index, counting up from 0.
+    shards: Arc<BTreeMap<(String, String), u64>>,
+}
+
+impl TimingAssignments {
+    /// Computes assignments from a map of (binary ID, test name) to recorded duration.
+    pub fn new(durations: &BTreeMap<(String, String), Duration>, total_shards: u64) -> Self {
+        let mut tests: Vec<_> = durations.iter().collect();
+        // Longest first, then by name. The sort is stable and the map is ordered by name, so
+        // sorting by duration alone is enough.
+        tests.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+        let mut totals = vec![Duration::ZERO; total_shards as usize];
+        let shards = tests
+            .into_iter()
+            .map(|(key, duration)| {
+                // min_by_key returns the first minimum, i.e. the lowest shard index.
+                let (shard, total) = totals
+                    .iter_mut()
+                    .enumerate()
+                    .min_by_key(|(_, total)| **total)
+                    .expect("total_shards is at least 1");
+                *total += *duration;
+                (key.clone(), shard as u64)
+            })
+            .collect();
+
+        Self {
+            shards: Arc::new(shards),
+        }
+    }
+
+    /// Returns the shard this test is assigned to, counting up from 1, or `None` if the test has
+    /// no recorded duration.
+    pub fn shard_for(&self, binary_id: &str, test_name: &str) -> Option<u64> {
+        self.shards
+            .get(&(binary_id.to_owned(), test_name.to_owned()))
+            .map(|shard| shard + 1)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct TimingsPartitioner {
+    shard_minus_one: u64,
+    total_shards: u64,
+    assignments: TimingAssignments,
+    // Tests without recorded durations are distributed round-robin.
+    fallback: CountPartitioner,
+}
+
+impl TimingsPartitioner {
+    fn new(shard: u64, total_shards: u64, assignments: TimingAssignments) -> Self {
+        Self {
+            shard_minus_one: shard - 1,
+            total_shards,
+            assignments,
+            fallback: CountPartitioner::new(shard, total_shards),
+        }
+    }
+}
+
+impl Partitioner for TimingsPartitioner {
+    fn test_matches(&mut self, binary_id: &RustBinaryId, test_name: &str) -> bool {
+        self.shard_for(binary_id, test_name) - 1 == self.shard_minus_one
+    }
+
+    fn shard_for(&mut self, binary_id: &RustBinaryId, test_name: &str) -> u64 {
+        match self.assignments.shard_for(binary_id.as_str(), test_name) {
+            Some(shard) => shard,
+            None => self.fallback.shard_for(binary_id, test_name),
+        }
+    }
+}
+
+/// The shards that the tests in a [`TestList`] would be assigned to under a partitioning, without
+/// the test list itself being partitioned that way.
+///
+/// This is used to annotate test output with shard assignments, for example to find out which shard
+/// a test that failed in CI runs on.
+#[derive(Clone, Debug)]
+pub struct ShardAssignments {
+    total_shards: u64,
+    // (binary ID, test name) -> shard, counting up from 1.
+    shards: HashMap<(RustBinaryId, String), u64>,
+}
+
+impl ShardAssignments {
+    /// Computes the assignments for the tests in `test_list` under the partitioning described by
+    /// `builder`.
+    ///
+    /// Only tests that would have been considered for partitioning have an assignment: tests that
+    /// were filtered out for other reasons, such as not matching a filter expression, don't.
+    pub fn new(builder: &PartitionerBuilder, test_list: &TestList<'_>) -> Self {
+        let mut shards = HashMap::new();
+        for suite in test_list.iter() {
+            // As with filtering, non-ignored and ignored tests are partitioned separately, in
+            // sorted order.
+            for ignored in [false, true] {
+                let mut partitioner = builder.build();
+                let tests = suite
+                    .status
+                    .test_cases()
+                    .filter(|(_, test_info)| test_info.ignored == ignored);
+                for (test_name, test_info) in tests {
+                    // Partitioning applies after all other kinds of filtering, so count-based
+                    // partitioning only counts tests that got that far.
+                    let partitioned = matches!(
+                        test_info.filter_match,
+                        FilterMatch::Matches
+                            | FilterMatch::Mismatch {
+                                reason: MismatchReason::Partition
+                            }
+                    );
+                    if partitioned {
+                        let shard = partitioner.shard_for(&suite.binary_id, test_name);
+                        shards.insert((suite.binary_id.clone(), test_name.to_owned()), shard);
+                    }
+                }
+            }
+        }
+
+        Self {
+            total_shards: builder.total_shards(),
+            shards,
+        }
+    }
+
+    /// Returns the total number of shards.
+    pub fn total_shards(&self) -> u64 {
+        self.total_shards
+    }
+
+    /// Returns the shard this test is assigned to, counting up from 1, or `None` if the test
+    /// wasn't considered for partitioning.
+    pub fn shard_for(&self, binary_id: &RustBinaryId, test_name: &str) -> Option<u64> {
+        self.shards
+            .get(&(binary_id.clone(), test_name.to_owned()))
+            .copied()
+    }
+}
+
+/// Reads per-test durations from a JUnit report written by a prior run.
+///
+/// Tests are keyed by their binary ID (the `classname` attribute, falling back to the enclosing
+/// test suite's name) and test name. Test cases without a `time` attribute are ignored.
+fn read_junit_durations(
+    path: &Utf8Path,
+) -> Result<BTreeMap<(String, String), Duration>, PartitionerBuilderParseError> {
+    let contents = std::fs::read_to_string(path).map_err(|err| {
+        PartitionerBuilderParseError::new(
+            None,
+            format!("failed to read JUnit report at '{path}': {err}"),
+        )
+    })?;
+    parse_junit_durations(&contents).map_err(|message| {
+        PartitionerBuilderParseError::new(
+            None,
+            format!("failed to parse JUnit report at '{path}': {message}"),
+        )
+    })
+}
+
+fn parse_junit_durations(xml: &str) -> Result<BTreeMap<(String, String), Duration>, String> {
+    let mut reader = quick_xml::Reader::from_str(xml);
+    let mut durations = BTreeMap::new();
+    let mut current_suite: Option<String> = None;
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|err| format!("at position {}: {err}", reader.buffer_position()))?;
+        match event {
+            Event::Start(tag) | Event::Empty(tag) => match tag.name().as_ref() {
+                b"testsuite" => current_suite = attribute(&tag, "name")?,
+                b"testcase" => {
+                    let name = attribute(&tag, "name")?
+                        .ok_or_else(|| "testcase is missing a name".to_owned())?;
+                    let binary_id = match attribute(&tag, "classname")? {
+                        Some(classname) => classname,
+                        None => current_suite.clone().ok_or_else(|| {
+                            format!("testcase '{name}' has no classname or enclosing testsuite")
+                        })?,
+                    };
+                    if let Some(time) = attribute(&tag, "time")? {
+                        let duration = time
+                            .parse::<f64>()
+                            .ok()
+                            .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                            .ok_or_else(|| {
+                                format!("testcase '{name}' has invalid time '{time}'")
+                            })?;
+                        durations.insert((binary_id, name), duration);
+                    }
+                }
+                _ => {}
+            },
+            Event::End(tag) if tag.name().as_ref() == b"testsuite" => current_suite = None,
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(durations)
+}
+
+fn attribute(tag: &BytesStart<'_>, name: &str) -> Result<Option<String>, String> {
+    let attr = tag
+        .try_get_attribute(name)
+        .map_err(|err| format!("invalid attribute '{name}': {err}"))?;
+    attr.map(|attr| {
+        attr.unescape_value()
+            .map(|value| value.into_owned())
+            .map_err(|err| format!("invalid value for attribute '{name}': {err}"))
+    })
+    .transpose()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use indoc::indoc;
+    use std::collections::BTreeSet;
 
     #[test]
     fn partitioner_builder_from_str() {
@@ -212,6 +606,14 @@ mod tests {
                     total_shards: 200,
                 },
             ),
+            (
+                "prefix:2/3:1",
+                PartitionerBuilder::Prefix {
+                    shard: 2,
+                    total_shards: 3,
+                    segments: 1,
+                },
+            ),
         ];
 
         let failures = vec![
@@ -225,6 +627,14 @@ mod tests {
             "hash:m/2",
             "hash:1/n",
             "hash:1/2/3",
+            "prefix:1/2",
+            "prefix:1/2:0",
+            "prefix:1/2:x",
+            "prefix:3/2:1",
+            "timings:",
+            "timings:1/2",
+            "timings:0/2:junit.xml",
+            "timings:1/2:/nonexistent/junit.xml",
         ];
 
         for (input, output) in successes {
@@ -242,4 +652,222 @@ mod tests {
                 .expect_err(&format!("expected input '{input}' to fail"));
         }
     }
+
+    #[test]
+    fn timings_partitioning() {
+        // A report from a prior run: one slow test, ten fast ones, and a test case without a time.
+        let mut junit = String::from(indoc! {r#"
+            <?xml version="1.0" encoding="UTF-8"?>
+            <testsuites name="nextest-run" tests="12" failures="0" errors="0" time="20.000">
+                <testsuite name="my-crate::slow" tests="2" disabled="0" errors="0" failures="0">
+                    <testcase name="test_slow" classname="my-crate::slow" time="10.000">
+                    </testcase>
+                    <testcase name="test_no_time" classname="my-crate::slow"/>
+                </testsuite>
+                <testsuite name="my-crate::fast" tests="10" disabled="0" errors="0" failures="0">
+        "#});
+        for i in 0..10 {
+            junit.push_str(&format!(
+                "        <testcase name=\"test_fast_{i}\" classname=\"my-crate::fast\" time=\"1.000\"/>\n"
+            ));
+        }
+        junit.push_str("    </testsuite>\n</testsuites>\n");
+
+        let temp_dir = camino_tempfile::tempdir().unwrap();
+        let junit_path = temp_dir.path().join("junit.xml");
+        std::fs::write(&junit_path, &junit).unwrap();
+
+        let durations = parse_junit_durations(&junit).expect("JUnit report is valid");
+        assert_eq!(durations.len(), 11, "test_no_time is skipped");
+
+        let slow_id = RustBinaryId::new("my-crate::slow");
+        let fast_id = RustBinaryId::new("my-crate::fast");
+        let mut shard_durations = [Duration::ZERO; 2];
+        for shard in 1..=2 {
+            let builder = PartitionerBuilder::from_str(&format!("timings:{shard}/2:{junit_path}"))
+                .expect("timings partition is valid");
+            let mut partitioner = builder.build();
+            for ((binary_id, test_name), duration) in &durations {
+                let binary_id = RustBinaryId::new(binary_id);
+                if partitioner.test_matches(&binary_id, test_name) {
+                    shard_durations[shard as usize - 1] += *duration;
+                }
+            }
+        }
+        // The slow test is on its own, and the fast tests are on the other shard. (Hash or count
+        // partitioning would put the slow test together with about half the fast tests.)
+        assert_eq!(
+            shard_durations,
+            [Duration::from_secs(10), Duration::from_secs(10)],
+            "max shard duration is minimized"
+        );
+
+        // Assignments are deterministic.
+        let assignments = TimingAssignments::new(&durations, 2);
+        assert_eq!(assignments, TimingAssignments::new(&durations, 2));
+        assert_eq!(
+            assignments.shard_for("my-crate::slow", "test_slow"),
+            Some(1)
+        );
+        for i in 0..10 {
+            assert_eq!(
+                assignments.shard_for("my-crate::fast", &format!("test_fast_{i}")),
+                Some(2)
+            );
+        }
+        assert_eq!(
+            assignments.shard_for("my-crate::slow", "test_no_time"),
+            None
+        );
+
+        // Tests without recorded durations are distributed round-robin.
+        let mut partitioner = TimingsPartitioner::new(1, 2, assignments);
+        let fallback_matches: Vec<_> = ["test_new_1", "test_new_2", "test_new_3", "test_new_4"]
+            .into_iter()
+            .map(|test_name| partitioner.test_matches(&slow_id, test_name))
+            .collect();
+        assert_eq!(fallback_matches, [true, false, true, false]);
+        assert!(
+            !partitioner.test_matches(&fast_id, "test_fast_0"),
+            "recorded tests don't affect round-robin state"
+        );
+        assert!(partitioner.test_matches(&slow_id, "test_new_5"));
+    }
+
+    #[test]
+    fn partition_hash_is_stable() {
+        // These values were computed with a reference implementation of XXH64. If this test fails,
+        // tests would move between shards across nextest versions.
+        assert_eq!(
+            partition_hash("nextest-tests::basic", "test_success"),
+            0x0589_d4ca_f366_1adc
+        );
+        assert_eq!(
+            partition_hash("my-crate", "tests::foo::test_bar"),
+            0xbce1_d18d_ab7f_ae4c
+        );
+        assert_eq!(
+            partition_hash(
+                "my-crate::integration",
+                "a_much_longer_test_name_that_spans_more_than_32_bytes"
+            ),
+            0x9ad8_2a46_b1f1_89b0
+        );
+    }
+
+    #[test]
+    fn hash_partitioning_ignores_other_tests() {
+        let binary_id = RustBinaryId::new("my-crate::tests");
+        let assign = |test_names: &[String]| {
+            let mut assignments = BTreeMap::new();
+            for shard in 1..=4 {
+                let mut partitioner = PartitionerBuilder::Hash {
+                    shard,
+                    total_shards: 4,
+                }
+                .build();
+                for test_name in test_names {
+                    if partitioner.test_matches(&binary_id, test_name) {
+                        assert_eq!(
+                            assignments.insert(test_name.clone(), shard),
+                            None,
+                            "{test_name} is in exactly one shard"
+                        );
+                    }
+                }
+            }
+            assignments
+        };
+
+        let test_names: Vec<_> = (0..50).map(|i| format!("tests::test_{i}")).collect();
+        let before = assign(&test_names);
+        assert_eq!(before.len(), test_names.len(), "every test is assigned");
+
+        // Adding tests (including at the start, which shifts positions) doesn't move other tests.
+        let mut more_test_names = vec!["tests::aaa_new_test".to_owned()];
+        more_test_names.extend(test_names.iter().cloned());
+        more_test_names.push("tests::zzz_new_test".to_owned());
+        let after = assign(&more_test_names);
+        for (test_name, shard) in &before {
+            assert_eq!(
+                after.get(test_name),
+                Some(shard),
+                "{test_name} stays in its shard"
+            );
+        }
+    }
+
+    #[test]
+    fn prefix_partitioning() {
+        assert_eq!(test_name_prefix("db::pool::test_connect", 1), "db");
+        assert_eq!(test_name_prefix("db::pool::test_connect", 2), "db::pool");
+        assert_eq!(
+            test_name_prefix("db::pool::test_connect", 3),
+            "db::pool::test_connect"
+        );
+        assert_eq!(test_name_prefix("test_top_level", 1), "test_top_level");
+
+        let binary_id = RustBinaryId::new("my-crate");
+        let test_names: Vec<_> = ["db", "http", "cache", "auth", "fs"]
+            .into_iter()
+            .flat_map(|module| (0..5).map(move |i| format!("{module}::tests::test_{i}")))
+            .chain((0..20).map(|i| format!("test_top_level_{i}")))
+            .collect();
+
+        let assign = || {
+            let mut assignments = BTreeMap::new();
+            for shard in 1..=3 {
+                let mut partitioner = PartitionerBuilder::from_str(&format!("prefix:{shard}/3:2"))
+                    .unwrap()
+                    .build();
+                for test_name in &test_names {
+                    if partitioner.test_matches(&binary_id, test_name) {
+                        assert_eq!(
+                            assignments.insert(test_name.clone(), shard),
+                            None,
+                            "{test_name} is in exactly one shard"
+                        );
+                    }
+                }
+            }
+            assignments
+        };
+
+        let assignments = assign();
+        assert_eq!(
+            assignments.len(),
+            test_names.len(),
+            "every test is assigned"
+        );
+        assert_eq!(assignments, assign(), "assignments are deterministic");
+
+        // Tests in the same module are on the same shard.
+        for module in ["db", "http", "cache", "auth", "fs"] {
+            let shards: BTreeSet<_> = assignments
+                .iter()
+                .filter(|(test_name, _)| test_name.starts_with(&format!("{module}::")))
+                .map(|(_, shard)| *shard)
+                .collect();
+            assert_eq!(shards.len(), 1, "tests in {module} are on one shard");
+        }
+
+        // Tests without a common prefix are distributed as with hashed partitioning.
+        for test_name in test_names.iter().filter(|name| !name.contains("::")) {
+            let shard = partition_hash("my-crate", test_name) % 3 + 1;
+            assert_eq!(
+                assignments[test_name], shard,
+                "{test_name} is hashed normally"
+            );
+        }
+        let top_level_shards: BTreeSet<_> = assignments
+            .iter()
+            .filter(|(test_name, _)| !test_name.contains("::"))
+            .map(|(_, shard)| *shard)
+            .collect();
+        assert_eq!(
+            top_level_shards.len(),
+            3,
+            "top-level tests are spread across shards"
+        );
+    }
 }
@@ -6,29 +6,51 @@
 //! The main structure in this module is [`TestReporter`].
 
 mod aggregator;
+mod eta;
+mod github_annotations;
+mod json_lines;
+mod tap_output;
+mod test_results;
 use crate::{
-    config::NextestProfile,
+    config::{CustomTestGroup, DurationFormat, NextestProfile},
     errors::WriteEventError,
     helpers::write_test_name,
     list::{TestInstance, TestList},
-    reporter::aggregator::EventAggregator,
+    partition::{PartitionerBuilder, ShardAssignments},
+    reporter::{
+        aggregator::EventAggregator,
+        eta::{format_eta, EtaEstimator},
+        json_lines::JsonLinesWriter,
+        tap_output::TapWriter,
+        test_results::TestResultsWriter,
+    },
     runner::{
         AbortStatus, ExecuteStatus, ExecutionDescription, ExecutionResult, ExecutionStatuses,
-        RetryData, RunStats,
+        FinalRunStatus, FinishReason, LeakedProcess, RetryData, RunStatistics, RunStats,
+        RunningTest,
     },
+    signal::FORCE_KILL_WINDOW,
+    timings::RecordedTimings,
 };
 pub use aggregator::heuristic_extract_description;
 use debug_ignore::DebugIgnore;
+use guppy::{
+    graph::{DependencyDirection, PackageGraph},
+    PackageId,
+};
 use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+pub use json_lines::JSON_LINES_FORMAT_VERSION;
 use nextest_metadata::MismatchReason;
 use owo_colors::{OwoColorize, Style};
 use serde::Deserialize;
 use std::{
     borrow::Cow,
     cmp::Reverse,
+    collections::HashMap,
     fmt::{self, Write as _},
     io,
-    io::{BufWriter, Write},
+    io::Write,
+    num::NonZeroUsize,
     time::{Duration, SystemTime},
 };
 use uuid::Uuid;
@@ -70,6 +92,57 @@ impl TestOutputDisplay {
     }
 }
 
+/// How standard error is displayed for passing tests, independently of [`TestOutputDisplay`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SuccessStderr {
+    /// Show standard error along with standard output.
+    ///
+    /// This is the default.
+    Show,
+
+    /// Never show standard error for passing tests.
+    Hide,
+
+    /// Show standard error along with standard output, and flag passing tests that wrote anything
+    /// to standard error.
+    Warn,
+}
+
+/// When the reporter's human-readable output is colorized.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorMode {
+    /// Colorize output if standard error is a terminal that supports color.
+    ///
+    /// This is the default.
+    #[default]
+    Auto,
+
+    /// Always colorize output, unless the `NO_COLOR` environment variable is set.
+    ///
+    /// This is useful if output is piped to a file that's later rendered, for example by a CI
+    /// system.
+    Always,
+
+    /// Never colorize output.
+    Never,
+}
+
+impl ColorMode {
+    /// Returns true if output should be colorized.
+    ///
+    /// `stderr_supports_color` is used for [`Self::Auto`], and `no_color` is whether the `NO_COLOR`
+    /// environment variable is set to a non-empty value.
+    pub fn should_colorize(self, stderr_supports_color: bool, no_color: bool) -> bool {
+        match self {
+            Self::Auto => stderr_supports_color && !no_color,
+            Self::Always => !no_color,
+            Self::Never => false,
+        }
+    }
+}
+
 /// Status level to show in the reporter output.
 ///
 /// Status levels are incremental: each level causes all the statuses listed above it to be output. For example,
@@ -78,7 +151,11 @@ impl TestOutputDisplay {
 #[serde(rename_all = "kebab-case")]
 #[non_exhaustive]
 pub enum StatusLevel {
-    /// No output.
+    /// No per-test status lines.
+    ///
+    /// The output of failing tests is still displayed as configured by the failure output, and the
+    /// progress bar and the summary at the end of the run are still shown. This is useful for very
+    /// large test suites.
     None,
 
     /// Only output test failures.
@@ -140,6 +217,36 @@ pub enum FinalStatusLevel {
     All,
 }
 
+/// The format in which the reporter writes out test events.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum EventFormat {
+    /// Human-readable output, with a progress bar if writing to a terminal.
+    #[default]
+    Human,
+
+    /// One JSON object per line for each [`TestEvent`], flushed as soon as it's written.
+    ///
+    /// Each object has a `format-version` field, currently [`JSON_LINES_FORMAT_VERSION`], and a
+    /// `timestamp` field with the number of seconds since the reporter was created, measured with
+    /// a monotonic clock.
+    JsonLines,
+
+    /// [TAP version 14](https://testanything.org/tap-version-14-specification.html), with one
+    /// test point per test.
+    ///
+    /// Skipped tests are reported with the `# SKIP` directive. Tests that were retried are
+    /// reported with their final result, and failing tests have a YAML diagnostic block with the
+    /// standard error of their last attempt.
+    Tap,
+
+    /// One JSON object per line for each completed test, flushed as soon as it's written.
+    ///
+    /// This is a minimal format meant for live dashboards: there are no records for tests
+    /// starting, being slow, or being retried. Tests that were canceled, including ones that never
+    /// started, have a record with the status `cancelled`.
+    TestResults,
+}
+
 /// Standard error destination for the reporter.
 ///
 /// This is usually a terminal, but can be an in-memory buffer for tests.
@@ -151,6 +258,12 @@ pub enum ReporterStderr<'a> {
 
     /// Write output to a buffer.
     Buffer(&'a mut Vec<u8>),
+
+    /// Write output to an arbitrary writer.
+    ///
+    /// Each event, along with any test output displayed with it, is written out with a single
+    /// `write_all` call, and the writer is flushed afterwards.
+    Writer(Box<dyn Write + Send + 'a>),
 }
 
 /// Test reporter builder.
@@ -163,6 +276,14 @@ pub struct TestReporterBuilder {
     final_status_level: Option<FinalStatusLevel>,
     verbose: bool,
     hide_progress_bar: bool,
+    topological_order: bool,
+    event_format: EventFormat,
+    github_annotations: bool,
+    shard_annotations: Option<PartitionerBuilder>,
+    color: Option<ColorMode>,
+    stderr_supports_color: bool,
+    recorded_timings: Option<RecordedTimings>,
+    skip_reports: bool,
 }
 
 impl TestReporterBuilder {
@@ -211,9 +332,84 @@ impl TestReporterBuilder {
         self.hide_progress_bar = hide_progress_bar;
         self
     }
+
+    /// Sets whether test results at the end of the run are grouped by package in topological
+    /// order, with dependencies reported before their dependents.
+    ///
+    /// By default, results are ordered by binary ID.
+    pub fn set_topological_order(&mut self, topological_order: bool) -> &mut Self {
+        self.topological_order = topological_order;
+        self
+    }
+
+    /// Sets the format in which test events are written out.
+    ///
+    /// With formats other than [`EventFormat::Human`], the progress bar is always hidden. JUnit and other
+    /// configured reports are written out regardless of the format.
+    pub fn set_event_format(&mut self, event_format: EventFormat) -> &mut Self {
+        self.event_format = event_format;
+        self
+    }
+
+    /// Sets whether to emit [GitHub Actions workflow
+    /// commands](https://docs.github.com/en/actions/using-workflows/workflow-commands-for-github-actions)
+    /// for failing tests, so that they show up as annotations.
+    ///
+    /// Annotations are written in addition to the normal human-readable output, and are ignored
+    /// with other event formats.
+    pub fn set_github_annotations(&mut self, github_annotations: bool) -> &mut Self {
+        self.github_annotations = github_annotations;
+        self
+    }
+
+    /// Sets when output is colorized, overriding the profile's `color` setting.
+    ///
+    /// [`ColorMode::Always`] still respects the `NO_COLOR` environment variable. To colorize output
+    /// regardless, use [`TestReporter::colorize`].
+    pub fn set_color(&mut self, color: ColorMode) -> &mut Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// Sets whether standard error is a terminal that supports color. This is used to decide
+    /// whether to colorize output with [`ColorMode::Auto`].
+    ///
+    /// Defaults to false.
+    pub fn set_stderr_supports_color(&mut self, stderr_supports_color: bool) -> &mut Self {
+        self.stderr_supports_color = stderr_supports_color;
+        self
+    }
+
+    /// Sets timings recorded by an earlier run, used to estimate the time remaining in the progress
+    /// bar.
+    ///
+    /// Without recorded timings, the time remaining is extrapolated from the tests that have
+    /// finished so far.
+    pub fn set_recorded_timings(&mut self, timings: RecordedTimings) -> &mut Self {
+        self.recorded_timings = Some(timings);
+        self
+    }
+
+    /// Sets a partitioning to annotate each test with the shard it would be assigned to.
+    ///
+    /// This is display-only: the shard number of `partitioner` is ignored, and tests are not
+    /// filtered by it.
+    pub fn set_shard_annotations(&mut self, partitioner: PartitionerBuilder) -> &mut Self {
+        self.shard_annotations = Some(partitioner);
+        self
+    }
 }
 
 impl TestReporterBuilder {
+    /// Sets whether to skip writing out the reports configured in the profile: JUnit, JSON and CSV
+    /// reports, stored timings and the run summary.
+    ///
+    /// This is useful for additional runs whose results shouldn't replace those of the main run.
+    pub fn set_skip_reports(&mut self, skip_reports: bool) -> &mut Self {
+        self.skip_reports = skip_reports;
+        self
+    }
+
     /// Creates a new test reporter.
     pub fn build<'a>(
         &self,
@@ -221,7 +417,12 @@ impl TestReporterBuilder {
         profile: &NextestProfile<'a>,
         output: ReporterStderr<'a>,
     ) -> TestReporter<'a> {
-        let styles = Box::default();
+        let mut styles: Box<Styles> = Box::default();
+        let color = self.color.unwrap_or_else(|| profile.color());
+        let no_color = std::env::var_os("NO_COLOR").map_or(false, |value| !value.is_empty());
+        if color.should_colorize(self.stderr_supports_color, no_color) {
+            styles.colorize();
+        }
         let binary_id_width = test_list
             .iter()
             .filter_map(|test_suite| {
@@ -229,7 +430,20 @@ impl TestReporterBuilder {
             })
             .max()
             .unwrap_or_default();
-        let aggregator = EventAggregator::new(profile);
+        let aggregator = if self.skip_reports {
+            EventAggregator::empty(profile)
+        } else {
+            EventAggregator::new(profile)
+        };
+        let package_ranks = if self.topological_order {
+            test_list
+                .iter()
+                .next()
+                .map(|test_suite| package_topological_ranks(test_suite.package.graph()))
+                .unwrap_or_default()
+        } else {
+            HashMap::new()
+        };
 
         let status_level = self.status_level.unwrap_or_else(|| profile.status_level());
         let status_level = match self.no_capture {
@@ -269,7 +483,9 @@ impl TestReporterBuilder {
                 // in these environments.
                 ReporterStderrImpl::TerminalWithoutBar
             }
-            ReporterStderr::Terminal if self.hide_progress_bar => {
+            ReporterStderr::Terminal
+                if self.hide_progress_bar || self.event_format != EventFormat::Human =>
+            {
                 ReporterStderrImpl::TerminalWithoutBar
             }
 
@@ -301,9 +517,23 @@ impl TestReporterBuilder {
                 progress_bar.set_draw_target(ProgressDrawTarget::stderr_with_hz(20));
                 // Enable a steady tick 10 times a second.
                 progress_bar.enable_steady_tick(Duration::from_millis(100));
-                ReporterStderrImpl::TerminalWithBar(progress_bar)
+                let eta = EtaEstimator::new(
+                    test_list,
+                    self.recorded_timings.as_ref(),
+                    profile.test_threads().compute(),
+                );
+                ReporterStderrImpl::TerminalWithBar(progress_bar, eta)
             }
             ReporterStderr::Buffer(buf) => ReporterStderrImpl::Buffer(buf),
+            ReporterStderr::Writer(writer) => ReporterStderrImpl::Writer(writer),
+        };
+        let structured = match self.event_format {
+            EventFormat::Human => None,
+            EventFormat::JsonLines => Some(StructuredWriter::JsonLines(JsonLinesWriter::new())),
+            EventFormat::Tap => Some(StructuredWriter::Tap(TapWriter::new())),
+            EventFormat::TestResults => {
+                Some(StructuredWriter::TestResults(TestResultsWriter::new()))
+            }
         };
 
         TestReporter {
@@ -313,32 +543,63 @@ impl TestReporterBuilder {
                 force_success_output,
                 force_failure_output,
                 no_capture: self.no_capture,
+                github_annotations: self.github_annotations,
+                max_output_bytes: profile.max_output_bytes().limit(),
+                duration_format: profile.duration_format(),
+                flaky_as_failure: profile.flaky_as_failure(),
+                success_stderr: profile.success_stderr(),
                 binary_id_width,
+                package_ranks,
                 styles,
+                shard_assignments: self
+                    .shard_annotations
+                    .as_ref()
+                    .map(|partitioner| ShardAssignments::new(partitioner, test_list)),
                 cancel_status: None,
                 final_outputs: DebugIgnore(vec![]),
             },
             stderr,
+            structured,
             metadata_reporter: aggregator,
         }
     }
 }
 
 enum ReporterStderrImpl<'a> {
-    TerminalWithBar(ProgressBar),
+    TerminalWithBar(ProgressBar, EtaEstimator),
     TerminalWithoutBar,
     Buffer(&'a mut Vec<u8>),
+    Writer(Box<dyn Write + Send + 'a>),
+}
+
+/// Writes events in a machine-readable format, in place of human-readable output.
+#[derive(Clone, Debug)]
+enum StructuredWriter {
+    JsonLines(JsonLinesWriter),
+    Tap(TapWriter),
+    TestResults(TestResultsWriter),
+}
+
+impl StructuredWriter {
+    fn write_event(&mut self, event: &TestEvent<'_>, writer: impl Write) -> io::Result<()> {
+        match self {
+            Self::JsonLines(json_lines) => json_lines.write_event(event, writer),
+            Self::Tap(tap) => tap.write_event(event, writer),
+            Self::TestResults(test_results) => test_results.write_event(event, writer),
+        }
+    }
 }
 
 /// Functionality to report test results to stderr and JUnit
 pub struct TestReporter<'a> {
     inner: TestReporterImpl<'a>,
     stderr: ReporterStderrImpl<'a>,
+    structured: Option<StructuredWriter>,
     metadata_reporter: EventAggregator<'a>,
 }
 
 impl<'a> TestReporter<'a> {
-    /// Colorizes output.
+    /// Colorizes output, regardless of the color mode and the `NO_COLOR` environment variable.
     pub fn colorize(&mut self) {
         self.inner.styles.colorize();
     }
@@ -354,8 +615,22 @@ impl<'a> TestReporter<'a> {
 
     /// Report this test event to the given writer.
     fn write_event(&mut self, event: TestEvent<'a>) -> Result<(), WriteEventError> {
+        if let Some(structured) = &mut self.structured {
+            let res = match &mut self.stderr {
+                ReporterStderrImpl::TerminalWithBar(..)
+                | ReporterStderrImpl::TerminalWithoutBar => {
+                    structured.write_event(&event, std::io::stderr().lock())
+                }
+                ReporterStderrImpl::Buffer(buf) => structured.write_event(&event, &mut **buf),
+                ReporterStderrImpl::Writer(writer) => structured.write_event(&event, writer),
+            };
+            res.map_err(WriteEventError::Io)?;
+            self.metadata_reporter.write_event(event)?;
+            return Ok(());
+        }
+
         match &mut self.stderr {
-            ReporterStderrImpl::TerminalWithBar(progress_bar) => {
+            ReporterStderrImpl::TerminalWithBar(progress_bar, eta) => {
                 // Write to a string that will be printed as a log line.
                 let mut buf: Vec<u8> = Vec::new();
                 self.inner
@@ -367,28 +642,53 @@ impl<'a> TestReporter<'a> {
                     _ = std::io::stderr().write_all(&buf);
                 });
 
-                update_progress_bar(&event, &self.inner.styles, progress_bar);
+                if let TestEvent::TestFinished {
+                    test_instance,
+                    run_statuses,
+                    ..
+                } = &event
+                {
+                    let time_taken = run_statuses.iter().map(|status| status.time_taken).sum();
+                    eta.test_finished(test_instance, time_taken);
+                }
+                update_progress_bar(&event, &self.inner.styles, eta.estimate(), progress_bar);
             }
             ReporterStderrImpl::TerminalWithoutBar => {
-                // Write to a buffered stderr.
-                let mut writer = BufWriter::new(std::io::stderr());
+                // Buffer the whole event, including any test output, and write it out at once so
+                // that it isn't interleaved with other output.
+                let mut buf: Vec<u8> = Vec::new();
                 self.inner
-                    .write_event_impl(&event, &mut writer)
+                    .write_event_impl(&event, &mut buf)
                     .map_err(WriteEventError::Io)?;
-                writer.flush().map_err(WriteEventError::Io)?;
+                let mut stderr = std::io::stderr().lock();
+                stderr.write_all(&buf).map_err(WriteEventError::Io)?;
+                stderr.flush().map_err(WriteEventError::Io)?;
             }
             ReporterStderrImpl::Buffer(buf) => {
                 self.inner
                     .write_event_impl(&event, buf)
                     .map_err(WriteEventError::Io)?;
             }
+            ReporterStderrImpl::Writer(writer) => {
+                let mut buf: Vec<u8> = Vec::new();
+                self.inner
+                    .write_event_impl(&event, &mut buf)
+                    .map_err(WriteEventError::Io)?;
+                writer.write_all(&buf).map_err(WriteEventError::Io)?;
+                writer.flush().map_err(WriteEventError::Io)?;
+            }
         }
         self.metadata_reporter.write_event(event)?;
         Ok(())
     }
 }
 
-fn update_progress_bar(event: &TestEvent<'_>, styles: &Styles, progress_bar: &mut ProgressBar) {
+fn update_progress_bar(
+    event: &TestEvent<'_>,
+    styles: &Styles,
+    eta: Option<Duration>,
+    progress_bar: &mut ProgressBar,
+) {
     match event {
         TestEvent::TestStarted {
             current_stats,
@@ -404,7 +704,7 @@ fn update_progress_bar(event: &TestEvent<'_>, styles: &Styles, progress_bar: &mu
         } => {
             let running_state = RunningState::new(*cancel_state, current_stats);
             progress_bar.set_prefix(running_state.progress_bar_prefix(styles));
-            progress_bar.set_message(progress_bar_msg(current_stats, *running, styles));
+            progress_bar.set_message(progress_bar_msg(current_stats, *running, eta, styles));
             // If there are skipped tests, the initial run count will be lower than when constructed
             // in ProgressBar::new.
             progress_bar.set_length(current_stats.initial_run_count as u64);
@@ -449,10 +749,18 @@ impl<'a> RunningState<'a> {
     }
 }
 
-fn progress_bar_msg(current_stats: &RunStats, running: usize, styles: &Styles) -> String {
+fn progress_bar_msg(
+    current_stats: &RunStats,
+    running: usize,
+    eta: Option<Duration>,
+    styles: &Styles,
+) -> String {
     let mut s = format!("{} running, ", running.style(styles.count));
     // Writing to strings is infallible.
     let _ = write_summary_str(current_stats, styles, &mut s);
+    if let Some(eta) = eta {
+        let _ = write!(s, ", ETA {}", format_eta(eta).style(styles.count));
+    }
     s
 }
 
@@ -464,8 +772,13 @@ fn write_summary_str(run_stats: &RunStats, styles: &Styles, out: &mut String) ->
         "passed".style(styles.pass)
     )?;
 
-    if run_stats.passed_slow > 0 || run_stats.flaky > 0 || run_stats.leaky > 0 {
-        let mut text = Vec::with_capacity(3);
+    if run_stats.passed_slow > 0
+        || run_stats.flaky > 0
+        || run_stats.leaky > 0
+        || run_stats.expected_failures > 0
+        || run_stats.no_assertions > 0
+    {
+        let mut text = Vec::with_capacity(5);
         if run_stats.passed_slow > 0 {
             text.push(format!(
                 "{} {}",
@@ -487,6 +800,20 @@ fn write_summary_str(run_stats: &RunStats, styles: &Styles, out: &mut String) ->
                 "leaky".style(styles.skip),
             ));
         }
+        if run_stats.expected_failures > 0 {
+            text.push(format!(
+                "{} {}",
+                run_stats.expected_failures.style(styles.count),
+                "expected to fail".style(styles.skip),
+            ));
+        }
+        if run_stats.no_assertions > 0 {
+            text.push(format!(
+                "{} {}",
+                run_stats.no_assertions.style(styles.count),
+                "without assertions".style(styles.skip),
+            ));
+        }
         write!(out, " ({})", text.join(", "))?;
     }
     write!(out, ", ")?;
@@ -494,10 +821,19 @@ fn write_summary_str(run_stats: &RunStats, styles: &Styles, out: &mut String) ->
     if run_stats.failed > 0 {
         write!(
             out,
-            "{} {}, ",
+            "{} {}",
             run_stats.failed.style(styles.count),
             "failed".style(styles.fail),
         )?;
+        if run_stats.unexpected_passes > 0 {
+            write!(
+                out,
+                " ({} {})",
+                run_stats.unexpected_passes.style(styles.count),
+                "unexpectedly passed".style(styles.fail),
+            )?;
+        }
+        write!(out, ", ")?;
     }
 
     if run_stats.exec_failed > 0 {
@@ -518,6 +854,15 @@ fn write_summary_str(run_stats: &RunStats, styles: &Styles, out: &mut String) ->
         )?;
     }
 
+    if run_stats.quarantined > 0 {
+        write!(
+            out,
+            "{} {}, ",
+            run_stats.quarantined.style(styles.count),
+            "quarantined".style(styles.skip),
+        )?;
+    }
+
     write!(
         out,
         "{} {}",
@@ -528,6 +873,58 @@ fn write_summary_str(run_stats: &RunStats, styles: &Styles, out: &mut String) ->
     Ok(())
 }
 
+/// Returns the percentage of `timeout` that `elapsed` represents, rounded down.
+fn percent_elapsed(elapsed: Duration, timeout: Duration) -> u128 {
+    if timeout.is_zero() {
+        return 100;
+    }
+    (elapsed.as_millis() * 100 / timeout.as_millis().max(1)).min(100)
+}
+
+/// Captured output to display for a test, possibly with the middle left out.
+#[derive(Debug, Eq, PartialEq)]
+enum TruncatedOutput<'a> {
+    Full(&'a [u8]),
+    Truncated {
+        head: &'a [u8],
+        truncated_bytes: usize,
+        tail: &'a [u8],
+    },
+}
+
+/// Truncates `output` to at most `max_bytes`, keeping both the head and the tail since the panic
+/// message is usually at the end.
+///
+/// The split points are moved so that they don't fall in the middle of a UTF-8 character.
+fn truncate_output(output: &[u8], max_bytes: Option<usize>) -> TruncatedOutput<'_> {
+    let max_bytes = match max_bytes {
+        Some(max_bytes) if output.len() > max_bytes => max_bytes,
+        _ => return TruncatedOutput::Full(output),
+    };
+
+    // UTF-8 continuation bytes are of the form 0b10xxxxxx. A character is at most 4 bytes long, so
+    // give up after 3 continuation bytes in case the output isn't valid UTF-8.
+    let is_continuation = |b: u8| b & 0b1100_0000 == 0b1000_0000;
+    let mut head_end = max_bytes / 2;
+    for _ in 0..3 {
+        if head_end > 0 && is_continuation(output[head_end]) {
+            head_end -= 1;
+        }
+    }
+    let mut tail_start = output.len() - (max_bytes - max_bytes / 2);
+    for _ in 0..3 {
+        if tail_start < output.len() && is_continuation(output[tail_start]) {
+            tail_start += 1;
+        }
+    }
+
+    TruncatedOutput::Truncated {
+        head: &output[..head_end],
+        truncated_bytes: tail_start - head_end,
+        tail: &output[tail_start..],
+    }
+}
+
 #[derive(Debug)]
 enum FinalOutput {
     Skipped(MismatchReason),
@@ -552,8 +949,16 @@ struct TestReporterImpl<'a> {
     force_success_output: Option<TestOutputDisplay>,
     force_failure_output: Option<TestOutputDisplay>,
     no_capture: bool,
+    github_annotations: bool,
+    max_output_bytes: Option<usize>,
+    duration_format: DurationFormat,
+    flaky_as_failure: bool,
+    success_stderr: SuccessStderr,
     binary_id_width: usize,
+    // Empty unless topological ordering is enabled.
+    package_ranks: HashMap<PackageId, usize>,
     styles: Box<Styles>,
+    shard_assignments: Option<ShardAssignments>,
     cancel_status: Option<CancelReason>,
     final_outputs: DebugIgnore<Vec<(TestInstance<'a>, FinalOutput)>>,
 }
@@ -565,7 +970,11 @@ impl<'a> TestReporterImpl<'a> {
         writer: &mut impl Write,
     ) -> io::Result<()> {
         match event {
-            TestEvent::RunStarted { test_list, .. } => {
+            TestEvent::RunStarted {
+                test_list,
+                shuffle_seed,
+                ..
+            } => {
                 write!(writer, "{:>12} ", "Starting".style(self.styles.pass))?;
 
                 let count_style = self.styles.count;
@@ -593,6 +1002,9 @@ impl<'a> TestReporterImpl<'a> {
                 if skip_count > 0 {
                     write!(writer, " ({} skipped)", skip_count.style(count_style))?;
                 }
+                if let Some(seed) = shuffle_seed {
+                    write!(writer, " (shuffled with seed {})", seed.style(count_style))?;
+                }
 
                 writeln!(writer)?;
             }
@@ -647,6 +1059,38 @@ impl<'a> TestReporterImpl<'a> {
                 writeln!(writer)?;
             }
 
+            TestEvent::TestStillRunning {
+                test_instance,
+                retry_data,
+                elapsed,
+                remaining,
+            } => {
+                if self.status_level >= StatusLevel::Slow {
+                    if retry_data.total_attempts > 1 {
+                        write!(
+                            writer,
+                            "{:>12} ",
+                            format!("TRY {} RUNNING", retry_data.attempt).style(self.styles.skip)
+                        )?;
+                    } else {
+                        write!(writer, "{:>12} ", "RUNNING".style(self.styles.skip))?;
+                    }
+                    self.write_slow_duration(*elapsed, writer)?;
+                    self.write_instance(*test_instance, writer)?;
+                    if let Some(remaining) = remaining {
+                        let timeout = *elapsed + *remaining;
+                        write!(
+                            writer,
+                            " ({}% of {} timeout, {} left)",
+                            percent_elapsed(*elapsed, timeout),
+                            self.duration_format.display(timeout),
+                            self.duration_format.display(*remaining),
+                        )?;
+                    }
+                    writeln!(writer)?;
+                }
+            }
+
             TestEvent::TestAttemptFailedWillRetry {
                 test_instance,
                 run_status,
@@ -743,6 +1187,24 @@ impl<'a> TestReporterImpl<'a> {
                     {
                         self.write_stdout_stderr(test_instance, last_status, false, writer)?;
                     }
+                } else if self.status_level == StatusLevel::None
+                    && !last_status.result.is_success()
+                    && self.cancel_status < Some(CancelReason::Signal)
+                    && test_output_display.is_immediate()
+                {
+                    // Status lines are suppressed, but the output of failing tests is still shown.
+                    // The output headers include the name of the test.
+                    self.write_stdout_stderr(test_instance, last_status, false, writer)?;
+                }
+
+                if self.github_annotations
+                    && matches!(describe, ExecutionDescription::Failure { .. })
+                {
+                    github_annotations::write_failure_annotation(
+                        test_instance,
+                        run_statuses,
+                        &mut *writer,
+                    )?;
                 }
 
                 // Store the output in final_outputs if test output display is requested, or if
@@ -764,19 +1226,98 @@ impl<'a> TestReporterImpl<'a> {
                 reason,
             } => {
                 if self.status_level >= StatusLevel::Skip {
-                    self.write_skip_line(*test_instance, writer)?;
+                    self.write_skip_line(*test_instance, *reason, writer)?;
                 }
                 if self.final_status_level >= FinalStatusLevel::Skip {
                     self.final_outputs
                         .push((*test_instance, FinalOutput::Skipped(*reason)));
                 }
             }
+            TestEvent::RunGlobalTimeout {
+                global_timeout,
+                running,
+            } => {
+                write!(writer, "{:>12} ", "TIMEOUT".style(self.styles.fail))?;
+                self.write_duration(*global_timeout, writer)?;
+                writeln!(
+                    writer,
+                    "run exceeded {}: {} tests still running",
+                    "global timeout".style(self.styles.fail),
+                    running.style(self.styles.count),
+                )?;
+            }
+            TestEvent::RunMaxFailures {
+                failed,
+                max_failures,
+                running,
+            } => {
+                // With the default of one failure, the failure itself is reason enough.
+                if max_failures.get() > 1 {
+                    write!(writer, "{:>12} ", "MAX FAIL".style(self.styles.fail))?;
+                    writeln!(
+                        writer,
+                        "{} tests failed, reaching {} of {}: {} tests still running",
+                        failed.style(self.styles.count),
+                        "max-failures".style(self.styles.fail),
+                        max_failures.style(self.styles.count),
+                        running.style(self.styles.count),
+                    )?;
+                }
+            }
+            TestEvent::FlakyConfirmationStarted { test_count, runs } => {
+                write!(writer, "{:>12} ", "Confirming".style(self.styles.retry))?;
+                writeln!(
+                    writer,
+                    "{} flaky {} by running {} {} each",
+                    test_count.style(self.styles.count),
+                    if *test_count == 1 { "test" } else { "tests" },
+                    runs.style(self.styles.count),
+                    if *runs == 1 { "time" } else { "times" },
+                )?;
+            }
+            TestEvent::FlakyConfirmationFinished {
+                test_instance,
+                runs,
+                failures,
+            } => {
+                write!(writer, "{:>12} ", "FLAKE RATE".style(self.styles.retry))?;
+                if *runs == 0 {
+                    write!(writer, "[{:>9}] ", "-")?;
+                } else {
+                    let rate = *failures as f64 / *runs as f64 * 100.0;
+                    write!(writer, "[{:>8.1}%] ", rate)?;
+                }
+                self.write_instance(*test_instance, writer)?;
+                writeln!(
+                    writer,
+                    " ({}/{} runs failed)",
+                    failures.style(self.styles.count),
+                    runs.style(self.styles.count),
+                )?;
+            }
+            TestEvent::RunHealthCheckFailed {
+                command,
+                message,
+                running,
+            } => {
+                writeln!(
+                    writer,
+                    "{:>12} `{command}` failed: {} tests still running",
+                    "HEALTH CHECK".style(self.styles.fail),
+                    running.style(self.styles.count),
+                )?;
+                for line in message.lines() {
+                    writeln!(writer, "{:>12} {line}", "")?;
+                }
+            }
             TestEvent::RunBeginCancel { running, reason } => {
                 self.cancel_status = self.cancel_status.max(Some(*reason));
 
                 write!(writer, "{:>12} ", "Canceling".style(self.styles.fail))?;
                 let reason_str = match reason {
                     CancelReason::TestFailure => "test failure",
+                    CancelReason::GlobalTimeout => "global timeout",
+                    CancelReason::HealthCheck => "health check failure",
                     CancelReason::ReportError => "error",
                     CancelReason::Signal => "signal",
                     CancelReason::Interrupt => "interrupt",
@@ -788,6 +1329,15 @@ impl<'a> TestReporterImpl<'a> {
                     reason_str.style(self.styles.fail),
                     running.style(self.styles.count)
                 )?;
+
+                if *reason == CancelReason::Interrupt && *running > 0 {
+                    writeln!(
+                        writer,
+                        "{:>12} press Ctrl-C again within {}s to force-kill running tests",
+                        "",
+                        FORCE_KILL_WINDOW.as_secs(),
+                    )?;
+                }
             }
             TestEvent::RunPaused { running } => {
                 writeln!(
@@ -807,13 +1357,73 @@ impl<'a> TestReporterImpl<'a> {
                     "signal".style(self.styles.count),
                 )?;
             }
+            TestEvent::RunSchedulingPaused { running } => {
+                writeln!(
+                    writer,
+                    "{:>12} starting new tests due to {}, {} running tests will continue",
+                    "Pausing".style(self.styles.pass),
+                    "signal".style(self.styles.count),
+                    running.style(self.styles.count),
+                )?;
+            }
+            TestEvent::RunSchedulingResumed { running } => {
+                writeln!(
+                    writer,
+                    "{:>12} starting new tests due to {}, {} tests running",
+                    "Resuming".style(self.styles.pass),
+                    "signal".style(self.styles.count),
+                    running.style(self.styles.count),
+                )?;
+            }
+            TestEvent::RunningSnapshot { run_elapsed, tests } => {
+                writeln!(
+                    writer,
+                    "{:>12} {} tests running after {}",
+                    "Snapshot".style(self.styles.pass),
+                    tests.len().style(self.styles.count),
+                    self.duration_format
+                        .display(*run_elapsed)
+                        .style(self.styles.count),
+                )?;
+                for test in tests {
+                    if test.retry_data.total_attempts > 1 {
+                        write!(
+                            writer,
+                            "{:>12} ",
+                            format!("TRY {} RUNNING", test.retry_data.attempt)
+                                .style(self.styles.skip)
+                        )?;
+                    } else {
+                        write!(writer, "{:>12} ", "RUNNING".style(self.styles.skip))?;
+                    }
+                    self.write_slow_duration(test.elapsed, writer)?;
+                    self.write_instance(test.test_instance, writer)?;
+                    writeln!(writer)?;
+                }
+            }
+            TestEvent::TestGroupSaturated { .. } | TestEvent::TestGroupFreed { .. } => {
+                // Test group saturation is only reported through machine-readable output.
+            }
+            TestEvent::RunLeakedProcesses { processes } => {
+                for process in processes {
+                    write!(writer, "{:>12} ", "LEAKED PROC".style(self.styles.fail))?;
+                    write!(writer, "[{:>7}] ", process.pid.style(self.styles.count))?;
+                    self.write_instance(process.test_instance, writer)?;
+                    writeln!(writer, ": {}", process.command)?;
+                }
+            }
             TestEvent::RunFinished {
                 start_time: _start_time,
                 elapsed,
                 run_stats,
+                statistics,
                 ..
             } => {
-                let summary_style = if run_stats.any_failed() {
+                // Flaky tests are highlighted as failures if they cause the run to exit with a
+                // non-zero code.
+                let summary_style = if run_stats.any_failed()
+                    || run_stats.final_status(self.flaky_as_failure) == FinalRunStatus::Flaky
+                {
                     self.styles.fail
                 } else {
                     self.styles.pass
@@ -825,10 +1435,7 @@ impl<'a> TestReporterImpl<'a> {
                 )?;
 
                 // Next, print the total time taken.
-                // * > means right-align.
-                // * 8 is the number of characters to pad to.
-                // * .3 means print two digits after the decimal point.
-                write!(writer, "[{:>8.3?}s] ", elapsed.as_secs_f64())?;
+                self.write_duration(*elapsed, writer)?;
 
                 write!(
                     writer,
@@ -855,14 +1462,24 @@ impl<'a> TestReporterImpl<'a> {
                 let _ = write_summary_str(run_stats, &self.styles, &mut summary_str);
                 writeln!(writer, " {tests_str} run: {summary_str}")?;
 
+                if self.final_status_level >= FinalStatusLevel::Slow {
+                    self.write_statistics(statistics, writer)?;
+                }
+
                 // Don't print out final outputs if canceled due to Ctrl-C.
                 if self.cancel_status < Some(CancelReason::Signal) {
                     // Sort the final outputs for a friendlier experience.
                     self.final_outputs
                         .sort_by_key(|(test_instance, final_output)| {
                             // Use the final status level, reversed (i.e. failing tests are printed at the very end).
+                            // Within a status level, group by package rank if topological
+                            // ordering is enabled (otherwise all ranks are 0).
                             (
                                 Reverse(final_output.final_status_level()),
+                                self.package_ranks
+                                    .get(test_instance.suite_info.package.id())
+                                    .copied()
+                                    .unwrap_or_default(),
                                 test_instance.sort_key(),
                             )
                         });
@@ -870,8 +1487,8 @@ impl<'a> TestReporterImpl<'a> {
                     for (test_instance, final_output) in &*self.final_outputs {
                         let final_status_level = final_output.final_status_level();
                         match final_output {
-                            FinalOutput::Skipped(_) => {
-                                self.write_skip_line(*test_instance, writer)?;
+                            FinalOutput::Skipped(reason) => {
+                                self.write_skip_line(*test_instance, *reason, writer)?;
                             }
                             FinalOutput::Executed {
                                 run_statuses,
@@ -911,9 +1528,14 @@ impl<'a> TestReporterImpl<'a> {
     fn write_skip_line(
         &self,
         test_instance: TestInstance<'a>,
+        reason: MismatchReason,
         writer: &mut impl Write,
     ) -> io::Result<()> {
-        write!(writer, "{:>12} ", "SKIP".style(self.styles.skip))?;
+        let status_str = match reason {
+            MismatchReason::Quarantined => "QUARANTINED",
+            _ => "SKIP",
+        };
+        write!(writer, "{:>12} ", status_str.style(self.styles.skip))?;
         // same spacing [   0.034s]
         write!(writer, "[         ] ")?;
 
@@ -931,13 +1553,23 @@ impl<'a> TestReporterImpl<'a> {
     ) -> io::Result<()> {
         let last_status = describe.last_status();
         match describe {
-            ExecutionDescription::Success { .. } => {
-                if last_status.result == ExecutionResult::Leak {
+            ExecutionDescription::Success { .. } => match last_status.result {
+                ExecutionResult::Leak => {
                     write!(writer, "{:>12} ", "LEAK".style(self.styles.skip))?;
-                } else {
+                }
+                ExecutionResult::ExpectedFail => {
+                    write!(writer, "{:>12} ", "XFAIL".style(self.styles.pass))?;
+                }
+                _ if last_status.no_assertions => {
+                    write!(writer, "{:>12} ", "NO ASSERT".style(self.styles.skip))?;
+                }
+                _ if self.is_stderr_warning(last_status) => {
+                    write!(writer, "{:>12} ", "STDERR".style(self.styles.skip))?;
+                }
+                _ => {
                     write!(writer, "{:>12} ", "PASS".style(self.styles.pass))?;
                 }
-            }
+            },
             ExecutionDescription::Flaky { .. } => {
                 // Use the skip color to also represent a flaky test.
                 write!(
@@ -1004,6 +1636,15 @@ impl<'a> TestReporterImpl<'a> {
                     (false, ExecutionResult::Leak) => {
                         write!(writer, "{:>12} ", "LEAK".style(self.styles.skip))?;
                     }
+                    (false, ExecutionResult::ExpectedFail) => {
+                        write!(writer, "{:>12} ", "XFAIL".style(self.styles.pass))?;
+                    }
+                    (false, _) if last_status.no_assertions => {
+                        write!(writer, "{:>12} ", "NO ASSERT".style(self.styles.skip))?;
+                    }
+                    (false, _) if self.is_stderr_warning(last_status) => {
+                        write!(writer, "{:>12} ", "STDERR".style(self.styles.skip))?;
+                    }
                     (false, _) => {
                         write!(writer, "{:>12} ", "PASS".style(self.styles.pass))?;
                     }
@@ -1065,6 +1706,21 @@ impl<'a> TestReporterImpl<'a> {
         instance: TestInstance<'a>,
         writer: &mut impl Write,
     ) -> io::Result<()> {
+        if let Some(assignments) = &self.shard_assignments {
+            let total_shards = assignments.total_shards();
+            // Pad to the width of the total so that test names line up.
+            let width = total_shards.to_string().len();
+            match assignments.shard_for(&instance.suite_info.binary_id, instance.name) {
+                Some(shard) => write!(
+                    writer,
+                    "[{}] ",
+                    format!("shard {shard:>width$}/{total_shards}")
+                        .style(self.styles.list_styles.binary_id)
+                )?,
+                None => write!(writer, "[{:>pad$}] ", "", pad = 7 + 2 * width)?,
+            }
+        }
+
         write!(
             writer,
             "{:>width$} ",
@@ -1078,26 +1734,59 @@ impl<'a> TestReporterImpl<'a> {
         write_test_name(instance.name, &self.styles.list_styles, writer)
     }
 
+    fn write_statistics(
+        &self,
+        statistics: &RunStatistics<'a>,
+        writer: &mut impl Write,
+    ) -> io::Result<()> {
+        let mean = match statistics.mean() {
+            Some(mean) => mean,
+            // No tests were run.
+            None => return Ok(()),
+        };
+        let percentile = |percentile| {
+            let duration = statistics
+                .percentile(percentile)
+                .expect("at least one test was run");
+            self.duration_format.display(duration)
+        };
+        writeln!(
+            writer,
+            "{:>12} mean {}, p50 {}, p90 {}, p99 {}",
+            "Timings".style(self.styles.pass),
+            self.duration_format.display(mean).style(self.styles.count),
+            percentile(50).style(self.styles.count),
+            percentile(90).style(self.styles.count),
+            percentile(99).style(self.styles.count),
+        )?;
+
+        for (test_instance, duration) in statistics.slowest() {
+            write!(writer, "{:>12} ", "SLOWEST".style(self.styles.skip))?;
+            self.write_duration(*duration, writer)?;
+            self.write_instance(*test_instance, writer)?;
+            writeln!(writer)?;
+        }
+
+        Ok(())
+    }
+
     fn write_duration(&self, duration: Duration, writer: &mut impl Write) -> io::Result<()> {
         // * > means right-align.
-        // * 8 is the number of characters to pad to.
-        // * .3 means print three digits after the decimal point.
-        write!(writer, "[{:>8.3?}s] ", duration.as_secs_f64())
+        // * 9 is the number of characters to pad to, including the unit.
+        write!(writer, "[{:>9}] ", self.duration_format.display(duration))
     }
 
     fn write_duration_by(&self, duration: Duration, writer: &mut impl Write) -> io::Result<()> {
         // * > means right-align.
-        // * 7 is the number of characters to pad to.
-        // * .3 means print three digits after the decimal point.
-        write!(writer, "by {:>7.3?}s ", duration.as_secs_f64())
+        // * 8 is the number of characters to pad to, including the unit.
+        write!(writer, "by {:>8} ", self.duration_format.display(duration))
     }
 
     fn write_slow_duration(&self, duration: Duration, writer: &mut impl Write) -> io::Result<()> {
         // Inside the curly braces:
         // * > means right-align.
-        // * 7 is the number of characters to pad to.
-        // * .3 means print three digits after the decimal point.
-        write!(writer, "[>{:>7.3?}s] ", duration.as_secs_f64())
+        // * 8 is the number of characters to pad to, including the unit.
+        write!(writer, "[>{:>8}] ", self.duration_format.display(duration))
     }
 
     #[cfg(windows)]
@@ -1148,7 +1837,9 @@ impl<'a> TestReporterImpl<'a> {
             self.write_test_output(&run_status.stdout, writer)?;
         }
 
-        if !run_status.stderr.is_empty() {
+        let hide_stderr =
+            run_status.result.is_success() && self.success_stderr == SuccessStderr::Hide;
+        if !run_status.stderr.is_empty() && !hide_stderr {
             write!(writer, "\n{}", "--- ".style(header_style))?;
             let out_len = self.write_attempt(run_status, header_style, writer)?;
             // The width is to align test instances.
@@ -1168,6 +1859,28 @@ impl<'a> TestReporterImpl<'a> {
     }
 
     fn write_test_output(&self, output: &[u8], writer: &mut impl Write) -> io::Result<()> {
+        match truncate_output(output, self.max_output_bytes) {
+            TruncatedOutput::Full(output) => self.write_output_bytes(output, writer),
+            TruncatedOutput::Truncated {
+                head,
+                truncated_bytes,
+                tail,
+            } => {
+                self.write_output_bytes(head, writer)?;
+                if !head.ends_with(b"\n") {
+                    writeln!(writer)?;
+                }
+                writeln!(
+                    writer,
+                    "{}",
+                    format!("... ({truncated_bytes} bytes truncated)").style(self.styles.skip)
+                )?;
+                self.write_output_bytes(tail, writer)
+            }
+        }
+    }
+
+    fn write_output_bytes(&self, output: &[u8], writer: &mut impl Write) -> io::Result<()> {
         if self.styles.is_colorized {
             const RESET_COLOR: &[u8] = b"\x1b[0m";
             // Output the text without stripping ANSI escapes, then reset the color afterwards in case
@@ -1206,6 +1919,14 @@ impl<'a> TestReporterImpl<'a> {
         }
     }
 
+    /// Returns true if the test passed but wrote to standard error, and that's configured to be
+    /// flagged.
+    fn is_stderr_warning(&self, run_status: &ExecuteStatus) -> bool {
+        self.success_stderr == SuccessStderr::Warn
+            && run_status.result.is_success()
+            && !run_status.stderr.is_empty()
+    }
+
     fn success_output(&self, test_setting: TestOutputDisplay) -> TestOutputDisplay {
         self.force_success_output.unwrap_or(test_setting)
     }
@@ -1224,6 +1945,20 @@ impl<'a> fmt::Debug for TestReporter<'a> {
     }
 }
 
+/// Returns a rank for each package in the graph, in topological order with dependencies (leaves)
+/// first.
+///
+/// Cycles, e.g. through dev-dependencies, are broken deterministically by guppy: packages in a
+/// cycle are returned in a stable order for a given graph.
+fn package_topological_ranks(graph: &PackageGraph) -> HashMap<PackageId, usize> {
+    graph
+        .resolve_all()
+        .packages(DependencyDirection::Reverse)
+        .enumerate()
+        .map(|(rank, package)| (package.id().clone(), rank))
+        .collect()
+}
+
 fn status_str(result: ExecutionResult) -> Cow<'static, str> {
     // Max 12 characters here.
     match result {
@@ -1252,10 +1987,13 @@ fn status_str(result: ExecutionResult) -> Cow<'static, str> {
             abort_status: None,
             leaked: false,
         } => "FAIL".into(),
-        ExecutionResult::ExecFail => "XFAIL".into(),
+        ExecutionResult::ExecFail => "EXEC FAIL".into(),
         ExecutionResult::Pass => "PASS".into(),
         ExecutionResult::Leak => "LEAK".into(),
+        ExecutionResult::LeakFail => "LEAK-FAIL".into(),
         ExecutionResult::Timeout => "TIMEOUT".into(),
+        ExecutionResult::ExpectedFail => "XFAIL".into(),
+        ExecutionResult::UnexpectedPass => "XPASS".into(),
     }
 }
 
@@ -1283,10 +2021,13 @@ fn short_status_str(result: ExecutionResult) -> Cow<'static, str> {
             abort_status: None,
             leaked: _,
         } => "FAIL".into(),
-        ExecutionResult::ExecFail => "XFAIL".into(),
+        ExecutionResult::ExecFail => "EXEC".into(),
         ExecutionResult::Pass => "PASS".into(),
         ExecutionResult::Leak => "LEAK".into(),
+        ExecutionResult::LeakFail => "LKFAIL".into(),
         ExecutionResult::Timeout => "TMT".into(),
+        ExecutionResult::ExpectedFail => "XFAIL".into(),
+        ExecutionResult::UnexpectedPass => "XPASS".into(),
     }
 }
 
@@ -1304,6 +2045,11 @@ pub enum TestEvent<'a> {
 
         /// The UUID for this run.
         run_id: Uuid,
+
+        /// The seed tests are shuffled with, if they're run in a random order.
+        ///
+        /// Passing this seed back in reproduces the order of this run.
+        shuffle_seed: Option<u64>,
     },
 
     // TODO: add events for BinaryStarted and BinaryFinished? May want a slightly different way to
@@ -1339,6 +2085,25 @@ pub enum TestEvent<'a> {
         will_terminate: bool,
     },
 
+    /// A slow test is still running.
+    ///
+    /// This is emitted periodically once a test has been marked slow, if `slow-report-interval` is
+    /// configured, and never after the test has finished.
+    TestStillRunning {
+        /// The test instance that is still running.
+        test_instance: TestInstance<'a>,
+
+        /// Retry data.
+        retry_data: RetryData,
+
+        /// The amount of time that has elapsed since the beginning of the test.
+        elapsed: Duration,
+
+        /// The amount of time left before the test is terminated, if it has a termination
+        /// deadline.
+        remaining: Option<Duration>,
+    },
+
     /// A test attempt failed and will be retried in the future.
     ///
     /// This event does not occur on the final run of a failing test.
@@ -1404,6 +2169,48 @@ pub enum TestEvent<'a> {
         reason: MismatchReason,
     },
 
+    /// The run exceeded the profile's global timeout.
+    ///
+    /// This is followed by a [`TestEvent::RunBeginCancel`] event, after which tests that are already
+    /// running are allowed to finish.
+    RunGlobalTimeout {
+        /// The configured global timeout.
+        global_timeout: Duration,
+
+        /// The number of tests still running.
+        running: usize,
+    },
+
+    /// The configured health-check command failed.
+    ///
+    /// This is followed by a [`TestEvent::RunBeginCancel`] event, after which tests that are already
+    /// running are allowed to finish.
+    RunHealthCheckFailed {
+        /// The health-check command.
+        command: String,
+
+        /// A description of the failure, including the output of the command.
+        message: String,
+
+        /// The number of tests still running.
+        running: usize,
+    },
+
+    /// The configured maximum number of test failures was reached.
+    ///
+    /// This is followed by a [`TestEvent::RunBeginCancel`] event, after which tests that are already
+    /// running are allowed to finish.
+    RunMaxFailures {
+        /// The number of tests that failed.
+        failed: usize,
+
+        /// The configured maximum number of failures.
+        max_failures: NonZeroUsize,
+
+        /// The number of tests still running.
+        running: usize,
+    },
+
     /// A cancellation notice was received.
     RunBeginCancel {
         /// The number of tests still running.
@@ -1425,22 +2232,140 @@ pub enum TestEvent<'a> {
         running: usize,
     },
 
+    /// A SIGUSR2 event was received, and no new tests will be started until the run is resumed.
+    ///
+    /// Tests that are already running continue to run. Only supported on Unix.
+    RunSchedulingPaused {
+        /// The number of tests currently running.
+        running: usize,
+    },
+
+    /// A SIGUSR2 event was received while the run was paused, and new tests will be started again.
+    RunSchedulingResumed {
+        /// The number of tests currently running.
+        running: usize,
+    },
+
+    /// A SIGUSR1 event was received, and a snapshot of the tests that are currently running was
+    /// taken.
+    ///
+    /// This doesn't otherwise affect the run. Snapshots are only taken on Unix.
+    RunningSnapshot {
+        /// The amount of time the run has taken so far.
+        run_elapsed: Duration,
+
+        /// The tests that are currently running, longest-running first.
+        tests: Vec<RunningTest<'a>>,
+    },
+
+    /// All the threads available to a custom test group are in use.
+    ///
+    /// Further tests in this group won't be started until a running test in the group finishes.
+    TestGroupSaturated {
+        /// The test group that is saturated.
+        group: CustomTestGroup,
+
+        /// The maximum number of threads available to the group.
+        max_threads: usize,
+
+        /// The time since the start of the run at which the group became saturated.
+        run_elapsed: Duration,
+    },
+
+    /// A saturated test group has threads available again.
+    ///
+    /// To avoid a flood of events when tests in a busy group finish and are immediately replaced
+    /// by new ones, this is only sent if the group stays unsaturated for a short period of time.
+    /// As a result, this event is delayed relative to when the group was freed up: `run_elapsed`
+    /// records the actual time.
+    TestGroupFreed {
+        /// The test group that is no longer saturated.
+        group: CustomTestGroup,
+
+        /// The maximum number of threads available to the group.
+        max_threads: usize,
+
+        /// The time since the start of the run at which threads became available again.
+        run_elapsed: Duration,
+
+        /// How long the group was saturated for.
+        saturated_for: Duration,
+    },
+
+    /// Flaky tests are about to be re-run in isolation to measure how often they fail.
+    ///
+    /// This is only sent if `flaky-confirmation` is configured, the run wasn't canceled, and at
+    /// least one test was flaky.
+    FlakyConfirmationStarted {
+        /// The number of flaky tests that will be re-run.
+        test_count: usize,
+
+        /// The number of times each test will be re-run.
+        runs: usize,
+    },
+
+    /// A flaky test finished its confirmation runs.
+    FlakyConfirmationFinished {
+        /// The test instance that was re-run.
+        test_instance: TestInstance<'a>,
+
+        /// The number of runs that were completed. This may be less than the configured number
+        /// of runs if the confirmation timeout elapsed or the run was interrupted.
+        runs: usize,
+
+        /// The number of runs that failed.
+        failures: usize,
+    },
+
+    /// Processes started by tests were still running after those tests finished.
+    ///
+    /// This is sent once all tests have finished.
+    ///
+    /// This is only sent if `detect-leaked-processes` is enabled, and only on Unix.
+    RunLeakedProcesses {
+        /// The processes that were still running, excluding ignored ones.
+        processes: Vec<LeakedProcess<'a>>,
+    },
+
     /// The test run finished.
     RunFinished {
         /// The unique ID for this run.
         run_id: Uuid,
 
-        /// The time at which the run was started.
+        /// The time at which the run was started, according to the system clock.
         start_time: SystemTime,
 
         /// The amount of time it took for the tests to run.
+        ///
+        /// This is measured using the monotonic clock, so it isn't affected by changes to the
+        /// system clock during the run.
         elapsed: Duration,
 
         /// Statistics for the run.
         run_stats: RunStats,
+
+        /// Timing statistics for the tests in the run.
+        statistics: RunStatistics<'a>,
     },
 }
 
+impl<'a> TestEvent<'a> {
+    /// For [`TestEvent::TestFinished`] and [`TestEvent::TestSkipped`], returns why the test
+    /// finished or was skipped.
+    ///
+    /// For finished tests, this is the reason for the last attempt. Returns `None` for all other
+    /// events.
+    pub fn finish_reason(&self) -> Option<FinishReason> {
+        match self {
+            TestEvent::TestFinished { run_statuses, .. } => {
+                Some(run_statuses.last_status().finish_reason())
+            }
+            TestEvent::TestSkipped { reason, .. } => Some(FinishReason::Skipped(*reason)),
+            _ => None,
+        }
+    }
+}
+
 // Note: the order here matters -- it indicates severity of cancellation
 /// The reason why a test run is being cancelled.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
@@ -1448,6 +2373,12 @@ pub enum CancelReason {
     /// A test failed and --no-fail-fast wasn't specified.
     TestFailure,
 
+    /// The run exceeded the configured global timeout.
+    GlobalTimeout,
+
+    /// The configured health-check command failed.
+    HealthCheck,
+
     /// An error occurred while reporting results.
     ReportError,
 
@@ -1490,7 +2421,26 @@ impl Styles {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{config::NextestConfig, platform::BuildPlatforms};
+    use crate::{
+        config::NextestConfig,
+        list::{RustTestSuite, RustTestSuiteStatus},
+        platform::BuildPlatforms,
+    };
+    use bytes::Bytes;
+    use guppy::CargoMetadata;
+    use nextest_metadata::{
+        BuildPlatform, FilterMatch, RustBinaryId, RustTestBinaryKind, RustTestCaseSummary,
+    };
+    use once_cell::sync::Lazy;
+    use std::collections::BTreeSet;
+
+    static PACKAGE_GRAPH_FIXTURE: Lazy<PackageGraph> = Lazy::new(|| {
+        static FIXTURE_JSON: &str = include_str!("../../fixtures/cargo-metadata.json");
+        let metadata = CargoMetadata::parse_json(FIXTURE_JSON).expect("fixture is valid JSON");
+        metadata
+            .build_graph()
+            .expect("fixture is valid PackageGraph")
+    });
 
     #[test]
     fn no_capture_settings() {
@@ -1530,4 +2480,374 @@ mod tests {
             "status level is pass, overriding other settings"
         );
     }
+
+    #[test]
+    fn interrupt_force_kill_hint() {
+        let test_list = TestList::empty();
+        let config = NextestConfig::default_config("/fake/dir");
+        let profile = config.profile(NextestConfig::DEFAULT_PROFILE).unwrap();
+        let build_platforms = BuildPlatforms::new(None).unwrap();
+        let profile = profile.apply_build_platforms(&build_platforms);
+
+        let cancel_output = |reason: CancelReason, running: usize| {
+            let mut buf: Vec<u8> = Vec::new();
+            let mut reporter = TestReporterBuilder::default().build(
+                &test_list,
+                &profile,
+                ReporterStderr::Buffer(&mut buf),
+            );
+            reporter
+                .report_event(TestEvent::RunBeginCancel { running, reason })
+                .expect("writing to a buffer succeeds");
+            drop(reporter);
+            String::from_utf8(buf).expect("output is valid UTF-8")
+        };
+
+        assert!(
+            cancel_output(CancelReason::Interrupt, 2).contains("press Ctrl-C again"),
+            "hint is shown for interrupts with tests running"
+        );
+        assert!(
+            !cancel_output(CancelReason::Interrupt, 0).contains("press Ctrl-C again"),
+            "hint is not shown with no tests running"
+        );
+        assert!(
+            !cancel_output(CancelReason::Signal, 2).contains("press Ctrl-C again"),
+            "hint is not shown for other signals"
+        );
+    }
+
+    #[test]
+    fn color_mode() {
+        let test_list = TestList::empty();
+        let config = NextestConfig::default_config("/fake/dir");
+        let profile = config.profile(NextestConfig::DEFAULT_PROFILE).unwrap();
+        let build_platforms = BuildPlatforms::new(None).unwrap();
+        let profile = profile.apply_build_platforms(&build_platforms);
+        let no_color = std::env::var_os("NO_COLOR").map_or(false, |value| !value.is_empty());
+
+        let cancel_output = |color: Option<ColorMode>, stderr_supports_color: bool| {
+            let mut buf: Vec<u8> = Vec::new();
+            let mut builder = TestReporterBuilder::default();
+            builder.set_stderr_supports_color(stderr_supports_color);
+            if let Some(color) = color {
+                builder.set_color(color);
+            }
+            let mut reporter =
+                builder.build(&test_list, &profile, ReporterStderr::Buffer(&mut buf));
+            reporter
+                .report_event(TestEvent::RunBeginCancel {
+                    running: 1,
+                    reason: CancelReason::TestFailure,
+                })
+                .expect("writing to a buffer succeeds");
+            drop(reporter);
+            buf
+        };
+        let has_ansi = |buf: &[u8]| buf.windows(2).any(|window| window == b"\x1b[");
+
+        for stderr_supports_color in [false, true] {
+            assert_eq!(
+                has_ansi(&cancel_output(
+                    Some(ColorMode::Always),
+                    stderr_supports_color
+                )),
+                !no_color,
+                "always colorizes unless NO_COLOR is set"
+            );
+            assert!(
+                !has_ansi(&cancel_output(
+                    Some(ColorMode::Never),
+                    stderr_supports_color
+                )),
+                "never doesn't colorize"
+            );
+            assert_eq!(
+                has_ansi(&cancel_output(Some(ColorMode::Auto), stderr_supports_color)),
+                stderr_supports_color && !no_color,
+                "auto colorizes if stderr supports color"
+            );
+            // The default profile's color setting is auto.
+            assert_eq!(
+                has_ansi(&cancel_output(None, stderr_supports_color)),
+                stderr_supports_color && !no_color,
+                "profile setting is used if color isn't set"
+            );
+        }
+
+        assert!(ColorMode::Always.should_colorize(false, false));
+        assert!(!ColorMode::Always.should_colorize(true, true));
+        assert!(ColorMode::Auto.should_colorize(true, false));
+        assert!(!ColorMode::Auto.should_colorize(true, true));
+        assert!(!ColorMode::Never.should_colorize(true, false));
+    }
+
+    fn fake_suite(test_names: &[&str]) -> RustTestSuite<'static> {
+        RustTestSuite {
+            binary_id: RustBinaryId::new("fake-package::fake-binary"),
+            binary_path: "/fake/binary".into(),
+            package: PACKAGE_GRAPH_FIXTURE
+                .metadata(&PackageId::new(
+                    "metadata-helper 0.1.0 (path+file:///Users/fakeuser/local/testcrates/metadata/metadata-helper)",
+                ))
+                .expect("package ID is valid"),
+            binary_name: "fake-binary".to_owned(),
+            kind: RustTestBinaryKind::LIB,
+            cwd: "/fake/cwd".into(),
+            build_platform: BuildPlatform::Target,
+            non_test_binaries: BTreeSet::new(),
+            status: RustTestSuiteStatus::Listed {
+                test_cases: test_names
+                    .iter()
+                    .map(|name| {
+                        (
+                            name.to_owned(),
+                            RustTestCaseSummary {
+                                ignored: false,
+                                filter_match: FilterMatch::Matches,
+                            },
+                        )
+                    })
+                    .collect(),
+            },
+        }
+    }
+
+    #[test]
+    fn running_snapshot_output() {
+        let suite = fake_suite(&["tests::stuck", "tests::flaky"]);
+        let tests: Vec<_> = suite
+            .status
+            .test_cases()
+            .map(|(name, test_info)| TestInstance::new(name, &suite, test_info))
+            .collect();
+
+        let test_list = TestList::empty();
+        let config = NextestConfig::default_config("/fake/dir");
+        let profile = config.profile(NextestConfig::DEFAULT_PROFILE).unwrap();
+        let build_platforms = BuildPlatforms::new(None).unwrap();
+        let profile = profile.apply_build_platforms(&build_platforms);
+
+        let mut buf: Vec<u8> = Vec::new();
+        let mut reporter = TestReporterBuilder::default().build(
+            &test_list,
+            &profile,
+            ReporterStderr::Buffer(&mut buf),
+        );
+        // Test cases are sorted by name, so tests[0] is flaky and tests[1] is stuck.
+        reporter
+            .report_event(TestEvent::RunningSnapshot {
+                run_elapsed: Duration::from_secs(90),
+                tests: vec![
+                    RunningTest {
+                        test_instance: tests[1],
+                        retry_data: RetryData {
+                            attempt: 1,
+                            total_attempts: 1,
+                        },
+                        elapsed: Duration::from_secs(60),
+                    },
+                    RunningTest {
+                        test_instance: tests[0],
+                        retry_data: RetryData {
+                            attempt: 2,
+                            total_attempts: 3,
+                        },
+                        elapsed: Duration::from_secs(5),
+                    },
+                ],
+            })
+            .expect("writing to a buffer succeeds");
+        drop(reporter);
+
+        let output = String::from_utf8(buf).expect("output is valid UTF-8");
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines.len(), 3, "header and one line per test: {output}");
+        assert!(
+            lines[0].contains("Snapshot") && lines[0].contains("2 tests running after 90.000s"),
+            "header line: {}",
+            lines[0]
+        );
+        assert!(
+            lines[1].contains("RUNNING") && lines[1].contains("tests::stuck"),
+            "tests are listed in the order given: {}",
+            lines[1]
+        );
+        assert!(
+            lines[2].contains("TRY 2 RUNNING") && lines[2].contains("tests::flaky"),
+            "retried tests show the attempt: {}",
+            lines[2]
+        );
+    }
+
+    #[test]
+    fn success_stderr() {
+        let suite = fake_suite(&["tests::noisy"]);
+        let test_instance = suite
+            .status
+            .test_cases()
+            .map(|(name, test_info)| TestInstance::new(name, &suite, test_info))
+            .next()
+            .unwrap();
+        let run_status = ExecuteStatus {
+            retry_data: RetryData {
+                attempt: 1,
+                total_attempts: 1,
+            },
+            slot: 0,
+            stdout: Bytes::from_static(b"test output\n"),
+            stderr: Bytes::from_static(b"warning: harness noise\n"),
+            result: ExecutionResult::Pass,
+            exit_code: Some(0),
+            start_time: SystemTime::UNIX_EPOCH,
+            time_taken: Duration::from_millis(100),
+            phases: None,
+            is_slow: false,
+            no_assertions: false,
+            trace_file: None,
+            delay_before_start: Duration::ZERO,
+        };
+
+        let test_list = TestList::empty();
+        let config = NextestConfig::default_config("/fake/dir");
+        let profile = config.profile(NextestConfig::DEFAULT_PROFILE).unwrap();
+        let build_platforms = BuildPlatforms::new(None).unwrap();
+        let profile = profile.apply_build_platforms(&build_platforms);
+
+        let output = |success_stderr: SuccessStderr| {
+            let mut stderr: Vec<u8> = Vec::new();
+            let mut reporter = TestReporterBuilder::default().build(
+                &test_list,
+                &profile,
+                ReporterStderr::Buffer(&mut stderr),
+            );
+            reporter.inner.success_stderr = success_stderr;
+            let describe = ExecutionDescription::Success {
+                single_status: &run_status,
+            };
+
+            let mut buf: Vec<u8> = Vec::new();
+            reporter
+                .inner
+                .write_status_line(test_instance, describe, &mut buf)
+                .expect("writing to a buffer succeeds");
+            reporter
+                .inner
+                .write_stdout_stderr(&test_instance, &run_status, false, &mut buf)
+                .expect("writing to a buffer succeeds");
+            String::from_utf8(buf).expect("output is valid UTF-8")
+        };
+
+        let shown = output(SuccessStderr::Show);
+        assert!(shown.contains("PASS"), "test is shown as passing: {shown}");
+        assert!(
+            shown.contains("test output") && shown.contains("harness noise"),
+            "stdout and stderr are shown: {shown}"
+        );
+
+        let hidden = output(SuccessStderr::Hide);
+        assert!(
+            hidden.contains("PASS"),
+            "test is shown as passing: {hidden}"
+        );
+        assert!(hidden.contains("test output"), "stdout is shown: {hidden}");
+        assert!(
+            !hidden.contains("STDERR") && !hidden.contains("harness noise"),
+            "stderr is hidden: {hidden}"
+        );
+
+        let warned = output(SuccessStderr::Warn);
+        assert!(
+            warned.lines().next().unwrap().contains("STDERR"),
+            "test is flagged: {warned}"
+        );
+        assert!(
+            warned.contains("harness noise"),
+            "stderr is shown: {warned}"
+        );
+    }
+
+    #[test]
+    fn topological_ranks() {
+        let ranks = package_topological_ranks(&PACKAGE_GRAPH_FIXTURE);
+        assert_eq!(
+            ranks.len(),
+            PACKAGE_GRAPH_FIXTURE.package_count(),
+            "every package has a rank"
+        );
+
+        let base_id = PackageId::new(
+            "metadata-base 0.1.0 (path+file:///Users/fakeuser/local/testcrates/metadata/metadata-base)",
+        );
+        let helper_id = PackageId::new(
+            "metadata-helper 0.1.0 (path+file:///Users/fakeuser/local/testcrates/metadata/metadata-helper)",
+        );
+        assert!(
+            ranks[&helper_id] < ranks[&base_id],
+            "metadata-helper is a dependency of metadata-base, so it's reported first"
+        );
+
+        // Ranks are stable across invocations.
+        assert_eq!(
+            ranks,
+            package_topological_ranks(&PACKAGE_GRAPH_FIXTURE),
+            "ranks are deterministic"
+        );
+    }
+
+    #[test]
+    fn percent_elapsed_of_timeout() {
+        let secs = Duration::from_secs;
+        assert_eq!(percent_elapsed(secs(30), secs(120)), 25);
+        assert_eq!(percent_elapsed(Duration::from_millis(59_999), secs(60)), 99);
+        assert_eq!(percent_elapsed(secs(90), secs(60)), 100, "capped at 100%");
+        assert_eq!(percent_elapsed(secs(1), Duration::ZERO), 100);
+    }
+
+    #[test]
+    fn truncate_output_short() {
+        let output = b"short output\n";
+        assert_eq!(
+            truncate_output(output, None),
+            TruncatedOutput::Full(output),
+            "output isn't truncated without a limit"
+        );
+        assert_eq!(
+            truncate_output(output, Some(64)),
+            TruncatedOutput::Full(output),
+            "output shorter than the limit is untouched"
+        );
+        assert_eq!(
+            truncate_output(output, Some(output.len())),
+            TruncatedOutput::Full(output),
+            "output exactly as long as the limit is untouched"
+        );
+    }
+
+    #[test]
+    fn truncate_output_head_and_tail() {
+        let output = b"0123456789abcdefghij";
+        assert_eq!(
+            truncate_output(output, Some(7)),
+            TruncatedOutput::Truncated {
+                head: b"012",
+                truncated_bytes: 13,
+                tail: b"ghij",
+            },
+        );
+
+        // "é" is 2 bytes and "€" is 3 bytes long. Neither is split.
+        let output = "aéééb€€€c".as_bytes();
+        let (head, truncated_bytes, tail) = match truncate_output(output, Some(8)) {
+            TruncatedOutput::Truncated {
+                head,
+                truncated_bytes,
+                tail,
+            } => (head, truncated_bytes, tail),
+            TruncatedOutput::Full(_) => panic!("output is truncated"),
+        };
+        assert_eq!(std::str::from_utf8(head).unwrap(), "aé");
+        assert_eq!(std::str::from_utf8(tail).unwrap(), "€c");
+        assert_eq!(head.len() + truncated_bytes + tail.len(), output.len());
+    }
 }
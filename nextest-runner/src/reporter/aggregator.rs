@@ -6,19 +6,31 @@
 #[cfg(any(unix, windows))]
 use crate::runner::AbortStatus;
 use crate::{
-    config::{NextestJunitConfig, NextestProfile},
+    config::{JunitGroupBy, NextestCsvConfig, NextestJunitConfig, NextestProfile, NextestReport},
     errors::WriteEventError,
     list::TestInstance,
     reporter::TestEvent,
-    runner::{ExecuteStatus, ExecutionDescription, ExecutionResult},
+    run_summary::{FinalSummary, RunSummary},
+    runner::{ExecuteStatus, ExecutionDescription, ExecutionResult, ExecutionStatuses},
+    timings::RecordedTimings,
 };
-use camino::Utf8PathBuf;
+use atomicwrites::{AtomicFile, OverwriteBehavior};
+use camino::{Utf8Path, Utf8PathBuf};
 use chrono::{DateTime, FixedOffset, Utc};
 use debug_ignore::DebugIgnore;
+use nextest_metadata::MismatchReason;
 use once_cell::sync::Lazy;
-use quick_junit::{NonSuccessKind, Output, Report, TestCase, TestCaseStatus, TestRerun, TestSuite};
+use quick_junit::{
+    NonSuccessKind, Output, Property, Report, TestCase, TestCaseStatus, TestRerun, TestSuite,
+};
 use regex::{Regex, RegexBuilder};
-use std::{borrow::Cow, collections::HashMap, fs::File, time::SystemTime};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+    time::{Duration, SystemTime},
+};
 
 #[derive(Clone, Debug)]
 #[allow(dead_code)]
@@ -26,20 +38,67 @@ pub(crate) struct EventAggregator<'cfg> {
     store_dir: Utf8PathBuf,
     // TODO: log information in a JSONable report (converting that to XML later) instead of directly
     // writing it to XML
-    junit: Option<MetadataJunit<'cfg>>,
+    junit: Vec<MetadataJunit<'cfg>>,
+    csv: Option<MetadataCsv>,
+    // Stored timings, followed by any JSON reports.
+    timings: Vec<MetadataTimings>,
+    summary: Option<MetadataSummary>,
 }
 
 impl<'cfg> EventAggregator<'cfg> {
     pub(crate) fn new(profile: &NextestProfile<'cfg>) -> Self {
+        let mut junit = Vec::new();
+        let mut timings: Vec<_> = profile
+            .stored_timings_path()
+            .map(MetadataTimings::new)
+            .into_iter()
+            .collect();
+        for report in profile.reports() {
+            match report {
+                NextestReport::Junit(config) => {
+                    junit.push(MetadataJunit::new(config, profile.clone()));
+                }
+                NextestReport::Json { path } => timings.push(MetadataTimings::new(path)),
+            }
+        }
+
+        Self {
+            store_dir: profile.store_dir().to_owned(),
+            junit,
+            csv: profile.csv().map(MetadataCsv::new),
+            timings,
+            summary: profile.summary_path().map(|path| MetadataSummary {
+                path,
+                profile_name: profile.name().to_owned(),
+                flaky_as_failure: profile.flaky_as_failure(),
+            }),
+        }
+    }
+
+    /// Creates an aggregator that doesn't write out any reports.
+    pub(crate) fn empty(profile: &NextestProfile<'cfg>) -> Self {
         Self {
             store_dir: profile.store_dir().to_owned(),
-            junit: profile.junit().map(MetadataJunit::new),
+            junit: Vec::new(),
+            csv: None,
+            timings: Vec::new(),
+            summary: None,
         }
     }
 
+    /// Writes `event` to every configured report.
     pub(crate) fn write_event(&mut self, event: TestEvent<'cfg>) -> Result<(), WriteEventError> {
-        if let Some(junit) = &mut self.junit {
-            junit.write_event(event)?;
+        if let Some(csv) = &mut self.csv {
+            csv.write_event(&event)?;
+        }
+        for timings in &mut self.timings {
+            timings.write_event(&event)?;
+        }
+        if let Some(summary) = &self.summary {
+            summary.write_event(&event)?;
+        }
+        for junit in &mut self.junit {
+            junit.write_event(event.clone())?;
         }
         Ok(())
     }
@@ -48,14 +107,23 @@ impl<'cfg> EventAggregator<'cfg> {
 #[derive(Clone, Debug)]
 struct MetadataJunit<'cfg> {
     config: NextestJunitConfig<'cfg>,
-    test_suites: DebugIgnore<HashMap<&'cfg str, TestSuite>>,
+    // Used to look up test groups, if test suites are grouped by them.
+    profile: NextestProfile<'cfg>,
+    test_suites: DebugIgnore<HashMap<String, TestSuite>>,
+    // Whether the partial report has been created for this run.
+    partial_started: bool,
 }
 
+/// The start of a partial JUnit report, written out before any test suites.
+const PARTIAL_HEADER: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites>\n";
+
 impl<'cfg> MetadataJunit<'cfg> {
-    fn new(config: NextestJunitConfig<'cfg>) -> Self {
+    fn new(config: NextestJunitConfig<'cfg>, profile: NextestProfile<'cfg>) -> Self {
         Self {
             config,
+            profile,
             test_suites: DebugIgnore(HashMap::new()),
+            partial_started: false,
         }
     }
 
@@ -63,9 +131,14 @@ impl<'cfg> MetadataJunit<'cfg> {
         match event {
             TestEvent::RunStarted { .. }
             | TestEvent::RunPaused { .. }
-            | TestEvent::RunContinued { .. } => {}
+            | TestEvent::RunContinued { .. }
+            | TestEvent::RunSchedulingPaused { .. }
+            | TestEvent::RunSchedulingResumed { .. }
+            | TestEvent::RunningSnapshot { .. }
+            | TestEvent::TestGroupSaturated { .. }
+            | TestEvent::TestGroupFreed { .. } => {}
             TestEvent::TestStarted { .. } => {}
-            TestEvent::TestSlow { .. } => {}
+            TestEvent::TestSlow { .. } | TestEvent::TestStillRunning { .. } => {}
             TestEvent::TestAttemptFailedWillRetry { .. } | TestEvent::TestRetryStarted { .. } => {
                 // Retries are recorded in TestFinished.
             }
@@ -106,21 +179,38 @@ impl<'cfg> MetadataJunit<'cfg> {
                         ExecutionResult::ExecFail => {
                             (NonSuccessKind::Error, "execution failure".into())
                         }
+                        ExecutionResult::UnexpectedPass => {
+                            (NonSuccessKind::Failure, "unexpected pass".into())
+                        }
                         ExecutionResult::Leak => (
                             NonSuccessKind::Error,
                             "test passed but leaked handles".into(),
                         ),
-                        ExecutionResult::Pass => {
+                        ExecutionResult::LeakFail => (
+                            NonSuccessKind::Failure,
+                            "test passed but leaked handles, treated as a failure".into(),
+                        ),
+                        ExecutionResult::Pass | ExecutionResult::ExpectedFail => {
                             unreachable!("this is a failure status")
                         }
                     }
                 }
 
-                let testsuite = self.testsuite_for(test_instance);
-
                 let (mut testcase_status, main_status, reruns) = match run_statuses.describe() {
                     ExecutionDescription::Success { single_status } => {
-                        (TestCaseStatus::success(), single_status, &[][..])
+                        let testcase_status =
+                            if single_status.result == ExecutionResult::ExpectedFail {
+                                // Like pytest, report expected failures as skipped so that they're
+                                // distinguishable from regular passes.
+                                let mut testcase_status = TestCaseStatus::skipped();
+                                testcase_status
+                                    .set_type("xfail")
+                                    .set_message("expected failure");
+                                testcase_status
+                            } else {
+                                TestCaseStatus::success()
+                            };
+                        (testcase_status, single_status, &[][..])
                     }
                     ExecutionDescription::Flaky {
                         last_status,
@@ -140,8 +230,8 @@ impl<'cfg> MetadataJunit<'cfg> {
 
                 for rerun in reruns {
                     let (kind, ty) = kind_ty(rerun);
-                    let stdout = String::from_utf8_lossy(&rerun.stdout);
-                    let stderr = String::from_utf8_lossy(&rerun.stderr);
+                    let stdout = junit_output(&rerun.stdout);
+                    let stderr = junit_output(&rerun.stderr);
                     let stack_trace = heuristic_extract_description(rerun.result, &stdout, &stderr);
 
                     let mut test_rerun = TestRerun::new(kind);
@@ -151,9 +241,11 @@ impl<'cfg> MetadataJunit<'cfg> {
                     test_rerun
                         .set_timestamp(to_datetime(rerun.start_time))
                         .set_time(rerun.time_taken)
-                        .set_type(ty)
-                        .set_system_out(stdout)
-                        .set_system_err(stderr);
+                        .set_type(ty);
+                    // Reruns are always failures.
+                    if junit_store_failure_output {
+                        test_rerun.set_system_out(stdout).set_system_err(stderr);
+                    }
                     // TODO: also publish time? it won't be standard JUnit (but maybe that's ok?)
                     testcase_status.add_rerun(test_rerun);
                 }
@@ -162,16 +254,21 @@ impl<'cfg> MetadataJunit<'cfg> {
                 testcase
                     .set_classname(test_instance.suite_info.binary_id.as_str())
                     .set_timestamp(to_datetime(main_status.start_time))
-                    .set_time(main_status.time_taken);
+                    .set_time(main_status.time_taken)
+                    .add_properties(retry_properties(&run_statuses));
+                if let Some(trace_file) = &main_status.trace_file {
+                    // Attach the trace by path, since traces can be much larger than the output.
+                    testcase.add_property(Property::new("nextest.trace-file", trace_file.as_str()));
+                }
 
                 // TODO: allure seems to want the output to be in a format where text files are
                 // written out to disk:
                 // https://github.com/allure-framework/allure2/blob/master/plugins/junit-xml-plugin/src/main/java/io/qameta/allure/junitxml/JunitXmlPlugin.java#L192-L196
                 // we may have to update this format to handle that.
                 let is_success = main_status.result.is_success();
+                let stdout = junit_output(&main_status.stdout);
+                let stderr = junit_output(&main_status.stderr);
                 if !is_success {
-                    let stdout = String::from_utf8_lossy(&main_status.stdout);
-                    let stderr = String::from_utf8_lossy(&main_status.stderr);
                     let description =
                         heuristic_extract_description(main_status.result, &stdout, &stderr);
                     if let Some(description) = description {
@@ -179,15 +276,30 @@ impl<'cfg> MetadataJunit<'cfg> {
                     }
                 }
 
+                // Standard output and standard error are stored as separate elements, so that
+                // consumers can tell them apart.
                 if (junit_store_success_output && is_success)
                     || (junit_store_failure_output && !is_success)
                 {
-                    testcase
-                        .set_system_out_lossy(&main_status.stdout)
-                        .set_system_err_lossy(&main_status.stderr);
+                    testcase.set_system_out(stdout).set_system_err(stderr);
                 }
 
-                testsuite.add_test_case(testcase);
+                self.add_test_case(test_instance, testcase)?;
+            }
+            TestEvent::TestSkipped {
+                test_instance,
+                reason: MismatchReason::Quarantined,
+            } => {
+                // Unlike tests that are filtered out, quarantined tests are part of every run, so
+                // it's fine to report them.
+                let mut testcase_status = TestCaseStatus::skipped();
+                testcase_status
+                    .set_type("quarantined")
+                    .set_message("test is quarantined in the profile");
+                let mut testcase = TestCase::new(test_instance.name, testcase_status);
+                testcase.set_classname(test_instance.suite_info.binary_id.as_str());
+
+                self.add_test_case(test_instance, testcase)?;
             }
             TestEvent::TestSkipped { .. } => {
                 // TODO: report skipped tests? causes issues if we want to aggregate runs across
@@ -201,7 +313,13 @@ impl<'cfg> MetadataJunit<'cfg> {
                 //
                 // testsuite.add_testcase(testcase);
             }
-            TestEvent::RunBeginCancel { .. } => {}
+            TestEvent::RunGlobalTimeout { .. }
+            | TestEvent::RunHealthCheckFailed { .. }
+            | TestEvent::RunMaxFailures { .. }
+            | TestEvent::FlakyConfirmationStarted { .. }
+            | TestEvent::FlakyConfirmationFinished { .. }
+            | TestEvent::RunBeginCancel { .. }
+            | TestEvent::RunLeakedProcesses { .. } => {}
             TestEvent::RunFinished {
                 run_id,
                 start_time,
@@ -223,26 +341,357 @@ impl<'cfg> MetadataJunit<'cfg> {
                     error,
                 })?;
 
-                let f = File::create(junit_path).map_err(|error| WriteEventError::Fs {
-                    file: junit_path.to_path_buf(),
-                    error,
-                })?;
-                report
-                    .serialize(f)
-                    .map_err(|error| WriteEventError::Junit {
-                        file: junit_path.to_path_buf(),
+                // Write to a temporary file and rename it into place, so that a run that's killed
+                // while the report is being written doesn't leave a truncated report behind.
+                AtomicFile::new(junit_path, OverwriteBehavior::AllowOverwrite)
+                    .write(|f| report.serialize(f))
+                    .map_err(|error| match error {
+                        atomicwrites::Error::Internal(error) => WriteEventError::Fs {
+                            file: junit_path.to_path_buf(),
+                            error,
+                        },
+                        atomicwrites::Error::User(error) => WriteEventError::Junit {
+                            file: junit_path.to_path_buf(),
+                            error,
+                        },
+                    })?;
+
+                // The complete report replaces the partial one.
+                if self.partial_started {
+                    let partial_path = self.partial_path();
+                    std::fs::remove_file(&partial_path).map_err(|error| WriteEventError::Fs {
+                        file: partial_path,
                         error,
                     })?;
+                    self.partial_started = false;
+                }
             }
         }
 
         Ok(())
     }
 
-    fn testsuite_for(&mut self, test_instance: TestInstance<'cfg>) -> &mut TestSuite {
+    /// Adds `testcase` to the test suite for `test_instance`, and appends it to the partial report.
+    fn add_test_case(
+        &mut self,
+        test_instance: TestInstance<'cfg>,
+        testcase: TestCase,
+    ) -> Result<(), WriteEventError> {
+        let name = self.testsuite_name(test_instance);
+
+        // In the partial report, each test case is written out in a test suite of its own, since
+        // the test suites that come before it can't be changed.
+        let mut partial_suite = TestSuite::new(name.as_str());
+        partial_suite.add_test_case(testcase.clone());
+        self.append_partial(&partial_suite)?;
+
         self.test_suites
-            .entry(test_instance.suite_info.binary_id.as_str())
-            .or_insert_with(|| TestSuite::new(test_instance.suite_info.binary_id.as_str()))
+            .entry(name)
+            .or_insert_with_key(|name| TestSuite::new(name.as_str()))
+            .add_test_case(testcase);
+        Ok(())
+    }
+
+    /// Returns the path to the partial report: the JUnit path with `.partial` appended.
+    fn partial_path(&self) -> Utf8PathBuf {
+        format!("{}.partial", self.config.path()).into()
+    }
+
+    /// Appends `suite` to the partial report, creating it if this is the first test suite.
+    ///
+    /// The partial report is written out as tests finish, so that if nextest is killed partway
+    /// through a run, the results so far can be salvaged. It's made into a valid report by
+    /// appending `</testsuites>`.
+    fn append_partial(&mut self, suite: &TestSuite) -> Result<(), WriteEventError> {
+        let partial_path = self.partial_path();
+        let mut buf = Vec::new();
+        if !self.partial_started {
+            buf.extend_from_slice(PARTIAL_HEADER.as_bytes());
+        }
+        suite
+            .serialize(&mut buf)
+            .map_err(|error| WriteEventError::Junit {
+                file: partial_path.clone(),
+                error,
+            })?;
+
+        let file = if self.partial_started {
+            OpenOptions::new().append(true).open(&partial_path)
+        } else {
+            let partial_dir = partial_path
+                .parent()
+                .expect("junit path must have a parent");
+            std::fs::create_dir_all(partial_dir).map_err(|error| WriteEventError::Fs {
+                file: partial_dir.to_path_buf(),
+                error,
+            })?;
+            File::create(&partial_path)
+        };
+        // Each test suite is written with a single call, so that a run that's killed leaves whole
+        // test suites behind.
+        file.and_then(|mut file| file.write_all(&buf))
+            .map_err(|error| WriteEventError::Fs {
+                file: partial_path,
+                error,
+            })?;
+        self.partial_started = true;
+        Ok(())
+    }
+
+    fn testsuite_name(&self, test_instance: TestInstance<'cfg>) -> String {
+        // Test cases always have the binary ID as their class name, so tests with the same name in
+        // different binaries can be told apart whatever the grouping.
+        match self.config.group_by() {
+            JunitGroupBy::Binary => test_instance.suite_info.binary_id.to_string(),
+            JunitGroupBy::Package => test_instance.suite_info.package.name().to_owned(),
+            JunitGroupBy::TestGroup => self
+                .profile
+                .settings_for(&test_instance.to_test_query())
+                .test_group()
+                .to_string(),
+        }
+    }
+}
+
+/// Converts the output of a test into text for a JUnit report.
+///
+/// ANSI escape sequences, such as color codes, are removed entirely. Other control characters
+/// that aren't valid in XML, such as NUL, are removed by [`Output::new`] when the output is added
+/// to the report, and the rest is escaped when the report is serialized.
+fn junit_output(output: &[u8]) -> String {
+    let output = strip_ansi_escapes::strip(output).expect("writing to a Vec is infallible");
+    String::from_utf8_lossy(&output).into_owned()
+}
+
+/// Returns JUnit properties that summarize the retries for a test.
+///
+/// Tests that weren't retried have `nextest.retries = 0` and `nextest.flaky = false`.
+fn retry_properties(run_statuses: &ExecutionStatuses) -> [Property; 2] {
+    let flaky = matches!(run_statuses.describe(), ExecutionDescription::Flaky { .. });
+    [
+        Property::new("nextest.retries", (run_statuses.len() - 1).to_string()),
+        Property::new("nextest.flaky", flaky.to_string()),
+    ]
+}
+
+#[derive(Clone, Debug)]
+struct MetadataCsv {
+    config: NextestCsvConfig,
+    rows: Vec<CsvRow>,
+    // Tests skipped because they were slow in the last run, as test IDs.
+    skipped_slow: Vec<String>,
+}
+
+impl MetadataCsv {
+    fn new(config: NextestCsvConfig) -> Self {
+        Self {
+            config,
+            rows: Vec::new(),
+            skipped_slow: Vec::new(),
+        }
+    }
+
+    fn write_event(&mut self, event: &TestEvent<'_>) -> Result<(), WriteEventError> {
+        match event {
+            TestEvent::TestFinished {
+                test_instance,
+                run_statuses,
+                ..
+            } => {
+                self.rows.push(CsvRow::new(
+                    test_instance.suite_info.binary_id.as_str(),
+                    test_instance.name,
+                    run_statuses,
+                ));
+            }
+            TestEvent::TestSkipped {
+                test_instance,
+                reason: MismatchReason::Slow,
+            } => {
+                self.skipped_slow.push(format!(
+                    "{} {}",
+                    test_instance.suite_info.binary_id, test_instance.name
+                ));
+            }
+            TestEvent::RunFinished { .. } => {
+                let csv_path = self.config.path();
+                if !self.skipped_slow.is_empty() {
+                    // Carry over the timings of tests skipped because they were slow last time, so
+                    // that they continue to be skipped in future runs.
+                    let previous = RecordedTimings::read_csv(csv_path)
+                        .map_err(WriteEventError::ReadTimings)?
+                        .unwrap_or_default();
+                    for test_id in self.skipped_slow.drain(..) {
+                        if let Some(timing) = previous.get_by_id(&test_id) {
+                            self.rows.push(CsvRow {
+                                test_id,
+                                duration: timing.duration,
+                                outcome: timing.outcome.clone().into(),
+                                attempts: timing.attempts,
+                            });
+                        }
+                    }
+                }
+
+                let csv_dir = csv_path.parent().expect("CSV path must have a parent");
+                std::fs::create_dir_all(csv_dir).map_err(|error| WriteEventError::Fs {
+                    file: csv_dir.to_path_buf(),
+                    error,
+                })?;
+
+                let f = File::create(csv_path).map_err(|error| WriteEventError::Fs {
+                    file: csv_path.to_path_buf(),
+                    error,
+                })?;
+                // Sort rows so that the output is stable across runs.
+                self.rows.sort_by(|a, b| a.test_id.cmp(&b.test_id));
+                write_csv(&self.rows, BufWriter::new(f)).map_err(|error| WriteEventError::Fs {
+                    file: csv_path.to_path_buf(),
+                    error,
+                })?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// Per-test timings and outcomes, written out as JSON at the end of each run.
+///
+/// This is used for both stored timings and JSON reports.
+#[derive(Clone, Debug)]
+struct MetadataTimings {
+    path: Utf8PathBuf,
+    summary: RunSummary,
+}
+
+impl MetadataTimings {
+    fn new(path: Utf8PathBuf) -> Self {
+        Self {
+            path,
+            summary: RunSummary::new(),
+        }
+    }
+
+    fn write_event(&mut self, event: &TestEvent<'_>) -> Result<(), WriteEventError> {
+        self.summary.record(event);
+        if !matches!(event, TestEvent::RunFinished { .. }) {
+            return Ok(());
+        }
+        write_json(&self.path, &self.summary)
+    }
+}
+
+/// A short summary of the run, written out as JSON to the profile's `summary-path`.
+#[derive(Clone, Debug)]
+struct MetadataSummary {
+    path: Utf8PathBuf,
+    profile_name: String,
+    flaky_as_failure: bool,
+}
+
+impl MetadataSummary {
+    fn write_event(&self, event: &TestEvent<'_>) -> Result<(), WriteEventError> {
+        if let TestEvent::RunFinished {
+            elapsed, run_stats, ..
+        } = event
+        {
+            let summary = FinalSummary::new(
+                &*self.profile_name,
+                *elapsed,
+                run_stats,
+                self.flaky_as_failure,
+            );
+            write_json(&self.path, &summary)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes `value` out as pretty-printed JSON to `path`, creating its parent directory if needed.
+fn write_json(path: &Utf8Path, value: &impl serde::Serialize) -> Result<(), WriteEventError> {
+    let dir = path.parent().expect("JSON path must have a parent");
+    std::fs::create_dir_all(dir).map_err(|error| WriteEventError::Fs {
+        file: dir.to_path_buf(),
+        error,
+    })?;
+
+    // Write to a temporary file and rename it into place, so that a run that's killed while
+    // the file is being written doesn't leave a truncated file behind.
+    AtomicFile::new(path, OverwriteBehavior::AllowOverwrite)
+        .write(|f| {
+            let mut writer = BufWriter::new(f);
+            serde_json::to_writer_pretty(&mut writer, value)?;
+            writer.flush()
+        })
+        .map_err(|error| WriteEventError::Fs {
+            file: path.to_path_buf(),
+            error: match error {
+                atomicwrites::Error::Internal(error) | atomicwrites::Error::User(error) => error,
+            },
+        })
+}
+
+/// A single row in the CSV timings file.
+#[derive(Clone, Debug)]
+struct CsvRow {
+    test_id: String,
+    duration: Duration,
+    outcome: Cow<'static, str>,
+    attempts: usize,
+}
+
+impl CsvRow {
+    fn new(binary_id: &str, test_name: &str, run_statuses: &ExecutionStatuses) -> Self {
+        let outcome = match run_statuses.describe() {
+            ExecutionDescription::Success { single_status } => match single_status.result {
+                ExecutionResult::Leak => "leak",
+                ExecutionResult::ExpectedFail => "expected-fail",
+                _ => "pass",
+            },
+            ExecutionDescription::Flaky { .. } => "flaky",
+            ExecutionDescription::Failure { last_status, .. } => match last_status.result {
+                ExecutionResult::Timeout => "timeout",
+                ExecutionResult::ExecFail => "exec-fail",
+                ExecutionResult::UnexpectedPass => "unexpected-pass",
+                ExecutionResult::LeakFail => "leak-fail",
+                _ => "fail",
+            },
+        };
+
+        Self {
+            test_id: format!("{binary_id} {test_name}"),
+            // The duration covers all attempts, including retries.
+            duration: run_statuses.iter().map(|status| status.time_taken).sum(),
+            outcome: outcome.into(),
+            attempts: run_statuses.len(),
+        }
+    }
+}
+
+fn write_csv(rows: &[CsvRow], mut writer: impl Write) -> std::io::Result<()> {
+    writeln!(writer, "test_id,duration_ms,outcome,attempts")?;
+    for row in rows {
+        writeln!(
+            writer,
+            "{},{},{},{}",
+            csv_escape(&row.test_id),
+            row.duration.as_millis(),
+            csv_escape(&row.outcome),
+            row.attempts,
+        )?;
+    }
+    writer.flush()
+}
+
+/// Escapes a field as per RFC 4180: fields containing commas, quotes or newlines are wrapped in
+/// quotes, with any quotes within them doubled.
+fn csv_escape(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\"")).into()
+    } else {
+        field.into()
     }
 }
 
@@ -362,6 +811,189 @@ fn heuristic_error_str(stderr: &str) -> Option<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::runner::{RetryData, RunStatistics, RunStats};
+    use bytes::Bytes;
+
+    #[test]
+    fn test_write_csv() {
+        fn status(result: ExecutionResult, attempt: usize, time_taken_ms: u64) -> ExecuteStatus {
+            ExecuteStatus {
+                retry_data: RetryData {
+                    attempt,
+                    total_attempts: 3,
+                },
+                slot: 0,
+                stdout: Bytes::new(),
+                stderr: Bytes::new(),
+                result,
+                exit_code: None,
+                start_time: SystemTime::UNIX_EPOCH,
+                time_taken: Duration::from_millis(time_taken_ms),
+                phases: None,
+                is_slow: false,
+                no_assertions: false,
+                trace_file: None,
+                delay_before_start: Duration::ZERO,
+            }
+        }
+        let fail = ExecutionResult::Fail {
+            abort_status: None,
+            leaked: false,
+        };
+
+        let mut rows = vec![
+            CsvRow::new(
+                "my-binary",
+                "tests::pass",
+                &ExecutionStatuses::new(vec![status(ExecutionResult::Pass, 1, 1500)]),
+            ),
+            CsvRow::new(
+                "my-binary",
+                "tests::flaky",
+                &ExecutionStatuses::new(vec![
+                    status(fail, 1, 100),
+                    status(ExecutionResult::Pass, 2, 250),
+                ]),
+            ),
+            CsvRow::new(
+                "my-binary",
+                "tests::case(a, \"b\")",
+                &ExecutionStatuses::new(vec![
+                    status(fail, 1, 10),
+                    status(fail, 2, 20),
+                    status(fail, 3, 30),
+                ]),
+            ),
+            CsvRow::new(
+                "other-binary",
+                "slow,test",
+                &ExecutionStatuses::new(vec![status(ExecutionResult::Timeout, 1, 60_000)]),
+            ),
+            CsvRow::new(
+                "other-binary",
+                "leaky",
+                &ExecutionStatuses::new(vec![status(ExecutionResult::Leak, 1, 5)]),
+            ),
+            CsvRow::new(
+                "other-binary",
+                "xfail",
+                &ExecutionStatuses::new(vec![status(ExecutionResult::ExpectedFail, 1, 7)]),
+            ),
+            CsvRow::new(
+                "other-binary",
+                "xpass",
+                &ExecutionStatuses::new(vec![status(ExecutionResult::UnexpectedPass, 1, 8)]),
+            ),
+        ];
+        rows.sort_by(|a, b| a.test_id.cmp(&b.test_id));
+
+        let mut buf = Vec::new();
+        write_csv(&rows, &mut buf).expect("writing to a Vec succeeds");
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            indoc::indoc! {r#"
+                test_id,duration_ms,outcome,attempts
+                "my-binary tests::case(a, ""b"")",60,fail,3
+                my-binary tests::flaky,350,flaky,2
+                my-binary tests::pass,1500,pass,1
+                other-binary leaky,5,leak,1
+                "other-binary slow,test",60000,timeout,1
+                other-binary xfail,7,expected-fail,1
+                other-binary xpass,8,unexpected-pass,1
+            "#}
+        );
+    }
+
+    #[test]
+    fn test_retry_properties() {
+        fn status(result: ExecutionResult, attempt: usize) -> ExecuteStatus {
+            ExecuteStatus {
+                retry_data: RetryData {
+                    attempt,
+                    total_attempts: 3,
+                },
+                slot: 0,
+                stdout: Bytes::new(),
+                stderr: Bytes::new(),
+                result,
+                exit_code: None,
+                start_time: SystemTime::UNIX_EPOCH,
+                time_taken: Duration::ZERO,
+                phases: None,
+                is_slow: false,
+                no_assertions: false,
+                trace_file: None,
+                delay_before_start: Duration::ZERO,
+            }
+        }
+        fn properties(statuses: Vec<ExecuteStatus>) -> Vec<(String, String)> {
+            retry_properties(&ExecutionStatuses::new(statuses))
+                .into_iter()
+                .map(|property| (property.name, property.value))
+                .collect()
+        }
+        let expected = |retries: &str, flaky: &str| {
+            vec![
+                ("nextest.retries".to_owned(), retries.to_owned()),
+                ("nextest.flaky".to_owned(), flaky.to_owned()),
+            ]
+        };
+        let fail = ExecutionResult::Fail {
+            abort_status: None,
+            leaked: false,
+        };
+
+        assert_eq!(
+            properties(vec![status(ExecutionResult::Pass, 1)]),
+            expected("0", "false"),
+            "passing test that wasn't retried"
+        );
+        assert_eq!(
+            properties(vec![status(fail, 1), status(ExecutionResult::Pass, 2)]),
+            expected("1", "true"),
+            "flaky test"
+        );
+        assert_eq!(
+            properties(vec![status(fail, 1), status(fail, 2), status(fail, 3)]),
+            expected("2", "false"),
+            "test that failed on every attempt"
+        );
+        assert_eq!(
+            properties(vec![status(fail, 1)]),
+            expected("0", "false"),
+            "failing test that wasn't retried"
+        );
+    }
+
+    #[test]
+    fn test_csv_escape() {
+        assert_eq!(csv_escape("plain"), "plain");
+        assert_eq!(csv_escape("a,b"), "\"a,b\"");
+        assert_eq!(csv_escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_escape("line\nbreak"), "\"line\nbreak\"");
+    }
+
+    #[test]
+    fn test_junit_output() {
+        let mut testcase = TestCase::new("my_test", TestCaseStatus::success());
+        testcase
+            .set_system_out(junit_output(b"\x1b[32mok\x1b[0m <done> & \x00more\n"))
+            .set_system_err(junit_output(b"\x1b[1;31merror\x1b[0m: bad\x07 input\n"));
+        let mut testsuite = TestSuite::new("my-binary");
+        testsuite.add_test_case(testcase);
+        let mut report = Report::new("nextest-run");
+        report.add_test_suite(testsuite);
+        let xml = report.to_string().expect("report serialized");
+
+        assert!(
+            xml.contains("<system-out>ok &lt;done&gt; &amp; more\n</system-out>"),
+            "system-out is escaped, with ANSI codes and NULs removed: {xml}"
+        );
+        assert!(
+            xml.contains("<system-err>error: bad input\n</system-err>"),
+            "system-err is stored separately, with control characters removed: {xml}"
+        );
+    }
 
     #[test]
     fn test_heuristic_extract_description() {
@@ -420,6 +1052,44 @@ note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace"#,
         }
     }
 
+    #[test]
+    fn test_write_stored_timings() {
+        let dir = camino_tempfile::tempdir().expect("created temp dir");
+        let path = dir.path().join("nested/timings.json");
+        let mut timings = MetadataTimings::new(path.clone());
+
+        let run_stats = RunStats {
+            initial_run_count: 3,
+            finished_count: 3,
+            passed: 2,
+            failed: 1,
+            ..RunStats::default()
+        };
+        let event = TestEvent::RunFinished {
+            run_id: uuid::Uuid::nil(),
+            start_time: SystemTime::UNIX_EPOCH,
+            elapsed: Duration::from_millis(1500),
+            run_stats,
+            statistics: RunStatistics::default(),
+        };
+
+        // An earlier file is replaced.
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "truncated").unwrap();
+        timings.write_event(&event).expect("timings written");
+
+        let contents = std::fs::read_to_string(&path).expect("timings file exists");
+        let summary: RunSummary =
+            serde_json::from_str(&contents).expect("timings file is a valid summary");
+        assert_eq!(summary.elapsed, Duration::from_millis(1500));
+        assert_eq!(summary.stats, run_stats);
+        assert_eq!(
+            std::fs::read_dir(path.parent().unwrap()).unwrap().count(),
+            1,
+            "no temporary files are left behind"
+        );
+    }
+
     #[test]
     fn test_heuristic_error_str() {
         let tests: &[(&str, &str)] = &[(
@@ -0,0 +1,224 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Estimating the time remaining in a test run, for display in the progress bar.
+
+use crate::{
+    list::{TestInstance, TestList},
+    timings::RecordedTimings,
+};
+use std::{collections::HashMap, time::Duration};
+
+/// Estimates the time remaining in a test run.
+///
+/// If timings were recorded by an earlier run, the estimate is the sum of the recorded durations
+/// of the tests that haven't finished yet, divided by the number of tests run at the same time.
+/// Tests without a recorded timing are assumed to take as long as the average test that has one.
+///
+/// Without recorded timings, the estimate is extrapolated from the average duration of the tests
+/// that have finished so far.
+#[derive(Clone, Debug)]
+pub(super) struct EtaEstimator {
+    // Recorded durations of the tests that haven't finished yet, keyed by test ID.
+    remaining_recorded: HashMap<String, Duration>,
+    // The sum of the durations in remaining_recorded.
+    remaining_recorded_total: Duration,
+    // The number of tests without a recorded duration that haven't finished yet.
+    remaining_unrecorded: usize,
+    // The average recorded duration of the tests in this run that have one.
+    recorded_average: Option<Duration>,
+    // The total time taken by the tests that have finished so far, and how many there are.
+    finished_total: Duration,
+    finished_count: usize,
+    test_threads: usize,
+}
+
+impl EtaEstimator {
+    /// Creates a new estimator for the tests in `test_list` that will be run.
+    pub(super) fn new(
+        test_list: &TestList<'_>,
+        timings: Option<&RecordedTimings>,
+        test_threads: usize,
+    ) -> Self {
+        let test_ids = test_list
+            .iter_tests()
+            .filter(|test_instance| test_instance.test_info.filter_match.is_match())
+            .map(|test_instance| test_id(&test_instance));
+        Self::from_test_ids(test_ids, timings, test_threads)
+    }
+
+    fn from_test_ids(
+        test_ids: impl IntoIterator<Item = String>,
+        timings: Option<&RecordedTimings>,
+        test_threads: usize,
+    ) -> Self {
+        let mut remaining_recorded = HashMap::new();
+        let mut remaining_unrecorded = 0;
+        for test_id in test_ids {
+            match timings.and_then(|timings| timings.get_by_id(&test_id)) {
+                Some(timing) => {
+                    remaining_recorded.insert(test_id, timing.duration);
+                }
+                None => remaining_unrecorded += 1,
+            }
+        }
+
+        let remaining_recorded_total = remaining_recorded.values().sum();
+        let recorded_average = (!remaining_recorded.is_empty())
+            .then(|| remaining_recorded_total / remaining_recorded.len() as u32);
+
+        Self {
+            remaining_recorded,
+            remaining_recorded_total,
+            remaining_unrecorded,
+            recorded_average,
+            finished_total: Duration::ZERO,
+            finished_count: 0,
+            test_threads: test_threads.max(1),
+        }
+    }
+
+    /// Records that a test has finished, taking `time_taken` across all its attempts.
+    pub(super) fn test_finished(&mut self, test_instance: &TestInstance<'_>, time_taken: Duration) {
+        self.test_id_finished(&test_id(test_instance), time_taken);
+    }
+
+    fn test_id_finished(&mut self, test_id: &str, time_taken: Duration) {
+        match self.remaining_recorded.remove(test_id) {
+            Some(duration) => {
+                self.remaining_recorded_total =
+                    self.remaining_recorded_total.saturating_sub(duration);
+            }
+            None => self.remaining_unrecorded = self.remaining_unrecorded.saturating_sub(1),
+        }
+        self.finished_total += time_taken;
+        self.finished_count += 1;
+    }
+
+    /// Returns the estimated time remaining, or `None` if there isn't enough information to make
+    /// an estimate yet.
+    pub(super) fn estimate(&self) -> Option<Duration> {
+        let remaining_count = self.remaining_recorded.len() + self.remaining_unrecorded;
+        if remaining_count == 0 {
+            return Some(Duration::ZERO);
+        }
+
+        let unrecorded_average = self.recorded_average.or_else(|| {
+            (self.finished_count > 0).then(|| self.finished_total / self.finished_count as u32)
+        })?;
+        let total =
+            self.remaining_recorded_total + unrecorded_average * self.remaining_unrecorded as u32;
+
+        // Fewer tests than test threads can't use up all the threads.
+        let concurrency = self.test_threads.min(remaining_count);
+        Some(total / concurrency as u32)
+    }
+}
+
+fn test_id(test_instance: &TestInstance<'_>) -> String {
+    format!(
+        "{} {}",
+        test_instance.suite_info.binary_id, test_instance.name
+    )
+}
+
+/// Formats an estimated time remaining for the progress bar, for example `1m 05s`.
+pub(super) fn format_eta(eta: Duration) -> String {
+    let secs = eta.as_secs();
+    let (hours, mins, secs) = (secs / 3600, (secs / 60) % 60, secs % 60);
+    if hours > 0 {
+        format!("{hours}h {mins:02}m {secs:02}s")
+    } else if mins > 0 {
+        format!("{mins}m {secs:02}s")
+    } else {
+        format!("{secs}s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn test_ids(names: &[&str]) -> Vec<String> {
+        names
+            .iter()
+            .map(|name| format!("my-package::my-binary {name}"))
+            .collect()
+    }
+
+    #[test]
+    fn estimate_with_recorded_timings() {
+        let timings = RecordedTimings::parse_csv(indoc::indoc! {"
+            test_id,duration_ms,outcome,attempts
+            my-package::my-binary a,10000,pass,1
+            my-package::my-binary b,20000,pass,1
+            my-package::my-binary c,30000,pass,1
+            my-package::my-binary d,40000,fail,1
+        "})
+        .expect("timings are valid");
+
+        let mut eta =
+            EtaEstimator::from_test_ids(test_ids(&["a", "b", "c", "d"]), Some(&timings), 2);
+        assert_eq!(
+            eta.estimate(),
+            Some(Duration::from_secs(50)),
+            "100s of tests over 2 threads"
+        );
+
+        // Actual durations don't matter when timings were recorded.
+        eta.test_id_finished("my-package::my-binary a", Duration::from_secs(1));
+        eta.test_id_finished("my-package::my-binary c", Duration::from_secs(1));
+        assert_eq!(
+            eta.estimate(),
+            Some(Duration::from_secs(30)),
+            "b and d remain: 60s over 2 threads"
+        );
+
+        eta.test_id_finished("my-package::my-binary d", Duration::from_secs(40));
+        assert_eq!(
+            eta.estimate(),
+            Some(Duration::from_secs(20)),
+            "a single remaining test only uses one thread"
+        );
+
+        eta.test_id_finished("my-package::my-binary b", Duration::from_secs(20));
+        assert_eq!(eta.estimate(), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn estimate_with_some_recorded_timings() {
+        let timings = RecordedTimings::parse_csv(indoc::indoc! {"
+            test_id,duration_ms,outcome,attempts
+            my-package::my-binary a,10000,pass,1
+            my-package::my-binary b,30000,pass,1
+        "})
+        .expect("timings are valid");
+
+        // c and d are new tests, and are assumed to take as long as the average recorded test.
+        let eta = EtaEstimator::from_test_ids(test_ids(&["a", "b", "c", "d"]), Some(&timings), 4);
+        assert_eq!(eta.estimate(), Some(Duration::from_secs(20)));
+    }
+
+    #[test]
+    fn estimate_without_recorded_timings() {
+        let mut eta = EtaEstimator::from_test_ids(test_ids(&["a", "b", "c", "d", "e"]), None, 2);
+        assert_eq!(eta.estimate(), None, "no estimate before any tests finish");
+
+        eta.test_id_finished("my-package::my-binary a", Duration::from_secs(4));
+        eta.test_id_finished("my-package::my-binary b", Duration::from_secs(8));
+        assert_eq!(
+            eta.estimate(),
+            Some(Duration::from_secs(9)),
+            "3 remaining tests at an average of 6s, over 2 threads"
+        );
+    }
+
+    #[test_case(Duration::from_millis(400), "0s" ; "under a second")]
+    #[test_case(Duration::from_secs(45), "45s" ; "seconds")]
+    #[test_case(Duration::from_secs(65), "1m 05s" ; "minutes")]
+    #[test_case(Duration::from_secs(3725), "1h 02m 05s" ; "hours")]
+    fn format_eta_cases(eta: Duration, expected: &str) {
+        assert_eq!(format_eta(eta), expected);
+    }
+}
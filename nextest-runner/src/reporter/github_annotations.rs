@@ -0,0 +1,115 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Emitting [GitHub Actions workflow
+//! commands](https://docs.github.com/en/actions/using-workflows/workflow-commands-for-github-actions)
+//! so that test failures show up as annotations.
+
+use super::aggregator::heuristic_extract_description;
+use crate::{
+    list::TestInstance,
+    runner::{ExecutionResult, ExecutionStatuses},
+};
+use std::io::{self, Write};
+
+/// The maximum number of characters of the failure line included in an annotation.
+const MAX_MESSAGE_CHARS: usize = 200;
+
+/// Writes an `::error` workflow command for a test that failed.
+///
+/// The message is the first line of the failure description extracted from the output of the last
+/// attempt, or a short description of the result if none could be found.
+pub(super) fn write_failure_annotation(
+    test_instance: &TestInstance<'_>,
+    run_statuses: &ExecutionStatuses,
+    mut writer: impl Write,
+) -> io::Result<()> {
+    let last_status = run_statuses.last_status();
+    let stdout = String::from_utf8_lossy(&last_status.stdout);
+    let stderr = String::from_utf8_lossy(&last_status.stderr);
+
+    let description = heuristic_extract_description(last_status.result, &stdout, &stderr);
+    let first_line = description
+        .as_deref()
+        .unwrap_or(&stderr)
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty());
+    let message = match first_line {
+        Some(line) => truncate_chars(line, MAX_MESSAGE_CHARS),
+        None => match last_status.result {
+            ExecutionResult::Timeout => "test timed out".into(),
+            ExecutionResult::ExecFail => "test failed to execute".into(),
+            ExecutionResult::UnexpectedPass => "test passed, but was expected to fail".into(),
+            ExecutionResult::LeakFail => "test passed, but leaked handles".into(),
+            ExecutionResult::Pass
+            | ExecutionResult::Leak
+            | ExecutionResult::Fail { .. }
+            | ExecutionResult::ExpectedFail => "test failed".into(),
+        },
+    };
+
+    let title = format!(
+        "{} {}",
+        test_instance.suite_info.binary_id, test_instance.name
+    );
+    writeln!(
+        writer,
+        "::error title={}::{}",
+        escape_property(&title),
+        escape_data(&message),
+    )
+}
+
+fn truncate_chars(line: &str, max_chars: usize) -> String {
+    match line.char_indices().nth(max_chars) {
+        Some((idx, _)) => format!("{}...", &line[..idx]),
+        None => line.to_owned(),
+    }
+}
+
+/// Escapes the message of a workflow command.
+///
+/// This matches `escapeData` in `@actions/core`.
+fn escape_data(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escapes the value of a workflow command property, such as `title`.
+///
+/// In addition to the characters escaped by [`escape_data`], `:` and `,` are escaped since they
+/// delimit properties. This matches `escapeProperty` in `@actions/core`.
+fn escape_property(s: &str) -> String {
+    escape_data(s).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("plain message", "plain message"; "no escaping")]
+    #[test_case("100% done", "100%25 done"; "percent")]
+    #[test_case("a\r\nb\nc", "a%0D%0Ab%0Ac"; "newlines")]
+    #[test_case("%0A", "%250A"; "already escaped")]
+    #[test_case("left: 1, right: 2", "left: 1, right: 2"; "colons and commas in data")]
+    fn test_escape_data(input: &str, expected: &str) {
+        assert_eq!(escape_data(input), expected);
+    }
+
+    #[test_case("crate::tests test_foo", "crate%3A%3Atests test_foo"; "colons")]
+    #[test_case("a,b", "a%2Cb"; "comma")]
+    #[test_case("50%\n::", "50%25%0A%3A%3A"; "mixed")]
+    fn test_escape_property(input: &str, expected: &str) {
+        assert_eq!(escape_property(input), expected);
+    }
+
+    #[test]
+    fn test_truncate_chars() {
+        assert_eq!(truncate_chars("short", 10), "short");
+        assert_eq!(truncate_chars("exactly10!", 10), "exactly10!");
+        assert_eq!(truncate_chars("éééééé", 3), "ééé...");
+    }
+}
@@ -0,0 +1,415 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Streaming test events as [JSON Lines](https://jsonlines.org/).
+//!
+//! Each [`TestEvent`] is written out as a single JSON object followed by a newline, and the
+//! output is flushed after every event so that downstream consumers see progress as it happens.
+
+use crate::{
+    list::TestInstance,
+    reporter::{CancelReason, TestEvent},
+    runner::{ExecutionDescription, ExecutionResult},
+};
+use nextest_metadata::MismatchReason;
+use serde::Serialize;
+use std::{
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+/// The version of the JSON Lines event format.
+///
+/// This is bumped whenever a field is removed or its meaning changes. Adding new fields or new
+/// event kinds is not considered a breaking change.
+pub const JSON_LINES_FORMAT_VERSION: u32 = 1;
+
+/// Writes test events as JSON Lines.
+#[derive(Clone, Debug)]
+pub(super) struct JsonLinesWriter {
+    // Timestamps are measured from this instant, so they're monotonic even if the system clock
+    // changes during the run.
+    start: Instant,
+}
+
+impl JsonLinesWriter {
+    pub(super) fn new() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+
+    /// Writes `event` as a single line to `writer`, then flushes it.
+    pub(super) fn write_event(
+        &self,
+        event: &TestEvent<'_>,
+        mut writer: impl Write,
+    ) -> io::Result<()> {
+        let line = JsonEvent::new(event, self.start.elapsed());
+        serde_json::to_writer(&mut writer, &line)?;
+        writer.write_all(b"\n")?;
+        writer.flush()
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct JsonEvent<'a> {
+    format_version: u32,
+    /// Seconds since the reporter was created.
+    timestamp: f64,
+    kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    run_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shuffle_seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    binary_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    test_name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    attempt: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total_attempts: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    slot: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<&'static str>,
+    /// Set for passing tests that reported running no assertions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    no_assertions: Option<bool>,
+    /// The trace written by the tracer set through an override, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    trace_file: Option<&'a str>,
+    /// Seconds taken by the test, elapsed so far for slow tests, or how long a test group was
+    /// saturated for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration: Option<f64>,
+    /// Seconds left before a slow test is terminated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    remaining: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    running: Option<usize>,
+    /// The number of tests that failed, once the maximum number of failures is reached.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    failed: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_failures: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    test_group: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_threads: Option<usize>,
+    /// Seconds since the start of the run at which a test group was saturated or freed up, or a
+    /// snapshot of running tests was taken.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    run_elapsed: Option<f64>,
+    /// The health-check command, if it failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    command: Option<&'a str>,
+    /// A description of why the health-check command failed, including its output.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<&'a str>,
+    /// Processes started by tests that were still running after those tests finished.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    processes: Option<Vec<JsonLeakedProcess<'a>>>,
+    /// Tests that were running when a snapshot was taken.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tests: Option<Vec<JsonRunningTest<'a>>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct JsonLeakedProcess<'a> {
+    binary_id: &'a str,
+    test_name: &'a str,
+    pid: u32,
+    command: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct JsonRunningTest<'a> {
+    binary_id: &'a str,
+    test_name: &'a str,
+    attempt: usize,
+    total_attempts: usize,
+    /// Seconds the current attempt has been running for.
+    duration: f64,
+}
+
+impl<'a> JsonEvent<'a> {
+    fn new(event: &'a TestEvent<'a>, timestamp: Duration) -> Self {
+        let mut json = Self {
+            format_version: JSON_LINES_FORMAT_VERSION,
+            timestamp: timestamp.as_secs_f64(),
+            ..Default::default()
+        };
+
+        match event {
+            TestEvent::RunStarted {
+                run_id,
+                shuffle_seed,
+                ..
+            } => {
+                json.kind = "run-started";
+                json.run_id = Some(run_id.to_string());
+                json.shuffle_seed = *shuffle_seed;
+            }
+            TestEvent::TestStarted {
+                test_instance,
+                running,
+                ..
+            } => {
+                json.kind = "test-started";
+                json.set_test(test_instance);
+                json.running = Some(*running);
+            }
+            TestEvent::TestSlow {
+                test_instance,
+                retry_data,
+                elapsed,
+                will_terminate,
+            } => {
+                json.kind = "test-slow";
+                json.set_test(test_instance);
+                json.attempt = Some(retry_data.attempt);
+                json.total_attempts = Some(retry_data.total_attempts);
+                json.duration = Some(elapsed.as_secs_f64());
+                if *will_terminate {
+                    json.status = Some("terminating");
+                }
+            }
+            TestEvent::TestStillRunning {
+                test_instance,
+                retry_data,
+                elapsed,
+                remaining,
+            } => {
+                json.kind = "test-still-running";
+                json.set_test(test_instance);
+                json.attempt = Some(retry_data.attempt);
+                json.total_attempts = Some(retry_data.total_attempts);
+                json.duration = Some(elapsed.as_secs_f64());
+                json.remaining = remaining.map(|remaining| remaining.as_secs_f64());
+            }
+            TestEvent::TestAttemptFailedWillRetry {
+                test_instance,
+                run_status,
+                ..
+            } => {
+                json.kind = "test-attempt-failed";
+                json.set_test(test_instance);
+                json.attempt = Some(run_status.retry_data.attempt);
+                json.total_attempts = Some(run_status.retry_data.total_attempts);
+                json.slot = Some(run_status.slot);
+                json.status = Some(result_str(run_status.result));
+                json.duration = Some(run_status.time_taken.as_secs_f64());
+            }
+            TestEvent::TestRetryStarted {
+                test_instance,
+                retry_data,
+            } => {
+                json.kind = "test-retry-started";
+                json.set_test(test_instance);
+                json.attempt = Some(retry_data.attempt);
+                json.total_attempts = Some(retry_data.total_attempts);
+            }
+            TestEvent::TestFinished {
+                test_instance,
+                run_statuses,
+                running,
+                ..
+            } => {
+                json.kind = "test-finished";
+                json.set_test(test_instance);
+                let last_status = run_statuses.last_status();
+                json.attempt = Some(last_status.retry_data.attempt);
+                json.total_attempts = Some(last_status.retry_data.total_attempts);
+                json.slot = Some(last_status.slot);
+                json.status = Some(match run_statuses.describe() {
+                    ExecutionDescription::Flaky { .. } => "flaky",
+                    ExecutionDescription::Success { .. } | ExecutionDescription::Failure { .. } => {
+                        result_str(last_status.result)
+                    }
+                });
+                json.no_assertions = last_status.no_assertions.then_some(true);
+                json.trace_file = last_status.trace_file.as_ref().map(|path| path.as_str());
+                json.duration = Some(last_status.time_taken.as_secs_f64());
+                json.running = Some(*running);
+            }
+            TestEvent::TestSkipped {
+                test_instance,
+                reason,
+            } => {
+                json.kind = "test-skipped";
+                json.set_test(test_instance);
+                json.reason = Some(match reason {
+                    MismatchReason::Ignored => "ignored",
+                    MismatchReason::String => "string",
+                    MismatchReason::Expression => "expression",
+                    MismatchReason::Partition => "partition",
+                    MismatchReason::Slow => "slow",
+                    MismatchReason::Quarantined => "quarantined",
+                    _ => "other",
+                });
+            }
+            TestEvent::RunGlobalTimeout {
+                global_timeout,
+                running,
+            } => {
+                json.kind = "run-global-timeout";
+                json.duration = Some(global_timeout.as_secs_f64());
+                json.running = Some(*running);
+            }
+            TestEvent::RunHealthCheckFailed {
+                command,
+                message,
+                running,
+            } => {
+                json.kind = "run-health-check-failed";
+                json.command = Some(command);
+                json.message = Some(message);
+                json.running = Some(*running);
+            }
+            TestEvent::RunMaxFailures {
+                failed,
+                max_failures,
+                running,
+            } => {
+                json.kind = "run-max-failures";
+                json.failed = Some(*failed);
+                json.max_failures = Some(max_failures.get());
+                json.running = Some(*running);
+            }
+            TestEvent::RunBeginCancel { running, reason } => {
+                json.kind = "run-begin-cancel";
+                json.reason = Some(cancel_reason_str(*reason));
+                json.running = Some(*running);
+            }
+            TestEvent::RunPaused { running } => {
+                json.kind = "run-paused";
+                json.running = Some(*running);
+            }
+            TestEvent::RunContinued { running } => {
+                json.kind = "run-continued";
+                json.running = Some(*running);
+            }
+            TestEvent::RunSchedulingPaused { running } => {
+                json.kind = "run-scheduling-paused";
+                json.running = Some(*running);
+            }
+            TestEvent::RunSchedulingResumed { running } => {
+                json.kind = "run-scheduling-resumed";
+                json.running = Some(*running);
+            }
+            TestEvent::RunningSnapshot { run_elapsed, tests } => {
+                json.kind = "running-snapshot";
+                json.run_elapsed = Some(run_elapsed.as_secs_f64());
+                json.running = Some(tests.len());
+                json.tests = Some(
+                    tests
+                        .iter()
+                        .map(|test| JsonRunningTest {
+                            binary_id: test.test_instance.suite_info.binary_id.as_str(),
+                            test_name: test.test_instance.name,
+                            attempt: test.retry_data.attempt,
+                            total_attempts: test.retry_data.total_attempts,
+                            duration: test.elapsed.as_secs_f64(),
+                        })
+                        .collect(),
+                );
+            }
+            TestEvent::TestGroupSaturated {
+                group,
+                max_threads,
+                run_elapsed,
+            } => {
+                json.kind = "test-group-saturated";
+                json.test_group = Some(group.as_str());
+                json.max_threads = Some(*max_threads);
+                json.run_elapsed = Some(run_elapsed.as_secs_f64());
+            }
+            TestEvent::TestGroupFreed {
+                group,
+                max_threads,
+                run_elapsed,
+                saturated_for,
+            } => {
+                json.kind = "test-group-freed";
+                json.test_group = Some(group.as_str());
+                json.max_threads = Some(*max_threads);
+                json.run_elapsed = Some(run_elapsed.as_secs_f64());
+                json.duration = Some(saturated_for.as_secs_f64());
+            }
+            TestEvent::FlakyConfirmationStarted { .. } => {
+                json.kind = "flaky-confirmation-started";
+            }
+            TestEvent::FlakyConfirmationFinished { test_instance, .. } => {
+                json.kind = "flaky-confirmation-finished";
+                json.set_test(test_instance);
+            }
+            TestEvent::RunLeakedProcesses { processes } => {
+                json.kind = "run-leaked-processes";
+                json.processes = Some(
+                    processes
+                        .iter()
+                        .map(|process| JsonLeakedProcess {
+                            binary_id: process.test_instance.suite_info.binary_id.as_str(),
+                            test_name: process.test_instance.name,
+                            pid: process.pid,
+                            command: &process.command,
+                        })
+                        .collect(),
+                );
+            }
+            TestEvent::RunFinished {
+                run_id,
+                elapsed,
+                run_stats,
+                ..
+            } => {
+                json.kind = "run-finished";
+                json.run_id = Some(run_id.to_string());
+                json.status = Some(if run_stats.is_success() {
+                    "pass"
+                } else {
+                    "fail"
+                });
+                json.duration = Some(elapsed.as_secs_f64());
+            }
+        }
+
+        json
+    }
+
+    fn set_test(&mut self, test_instance: &TestInstance<'a>) {
+        self.binary_id = Some(test_instance.suite_info.binary_id.as_str());
+        self.test_name = Some(test_instance.name);
+    }
+}
+
+pub(super) fn result_str(result: ExecutionResult) -> &'static str {
+    match result {
+        ExecutionResult::Pass => "pass",
+        ExecutionResult::Leak => "leak",
+        ExecutionResult::LeakFail => "leak-fail",
+        ExecutionResult::Fail { .. } => "fail",
+        ExecutionResult::ExecFail => "exec-fail",
+        ExecutionResult::Timeout => "timeout",
+        ExecutionResult::ExpectedFail => "expected-fail",
+        ExecutionResult::UnexpectedPass => "unexpected-pass",
+    }
+}
+
+fn cancel_reason_str(reason: CancelReason) -> &'static str {
+    match reason {
+        CancelReason::TestFailure => "test-failure",
+        CancelReason::GlobalTimeout => "global-timeout",
+        CancelReason::HealthCheck => "health-check",
+        CancelReason::ReportError => "report-error",
+        CancelReason::Signal => "signal",
+        CancelReason::Interrupt => "interrupt",
+    }
+}
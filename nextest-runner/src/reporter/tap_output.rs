@@ -0,0 +1,458 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Writing out test results as [TAP version 14](https://testanything.org/tap-version-14-specification.html).
+//!
+//! Each test, including skipped ones, becomes a single test point. Retries are collapsed into the
+//! final result of a test, with details in a YAML diagnostic block.
+
+use crate::{
+    list::TestInstance,
+    reporter::{CancelReason, TestEvent},
+    runner::{ExecuteStatus, ExecutionDescription, ExecutionResult, ExecutionStatuses},
+};
+use std::io::{self, Write};
+
+/// Writes test events as TAP.
+#[derive(Clone, Debug, Default)]
+pub(super) struct TapWriter {
+    // The number of test points written so far.
+    points: usize,
+    plan: usize,
+    cancel_reason: Option<CancelReason>,
+}
+
+impl TapWriter {
+    pub(super) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes `event` to `writer`, then flushes it.
+    pub(super) fn write_event(
+        &mut self,
+        event: &TestEvent<'_>,
+        mut writer: impl Write,
+    ) -> io::Result<()> {
+        match event {
+            TestEvent::RunStarted { test_list, .. } => {
+                // Skipped tests are reported as test points too, so the plan includes them.
+                self.write_header(test_list.test_count(), &mut writer)?;
+            }
+            TestEvent::TestFinished {
+                test_instance,
+                run_statuses,
+                ..
+            } => {
+                self.write_finished(test_instance, run_statuses, &mut writer)?;
+            }
+            TestEvent::TestSkipped {
+                test_instance,
+                reason,
+            } => {
+                self.points += 1;
+                writeln!(
+                    writer,
+                    "ok {} - {} # SKIP {reason}",
+                    self.points,
+                    description(test_instance),
+                )?;
+            }
+            TestEvent::RunBeginCancel { reason, .. } => {
+                self.cancel_reason = Some(self.cancel_reason.map_or(*reason, |r| r.max(*reason)));
+            }
+            TestEvent::RunFinished { .. } => {
+                if self.points < self.plan {
+                    // The plan can't be satisfied, so bail out to indicate that the run was
+                    // incomplete.
+                    let reason = match self.cancel_reason {
+                        Some(CancelReason::TestFailure) => "test failure",
+                        Some(CancelReason::GlobalTimeout) => "global timeout",
+                        Some(CancelReason::HealthCheck) => "health check failure",
+                        Some(CancelReason::ReportError) => "error reporting results",
+                        Some(CancelReason::Signal) => "signal",
+                        Some(CancelReason::Interrupt) => "interrupt",
+                        None => "not all tests were run",
+                    };
+                    writeln!(writer, "Bail out! run canceled: {reason}")?;
+                }
+            }
+            _ => {}
+        }
+
+        writer.flush()
+    }
+
+    fn write_header(&mut self, plan: usize, mut writer: impl Write) -> io::Result<()> {
+        self.plan = plan;
+        writeln!(writer, "TAP version 14")?;
+        writeln!(writer, "1..{plan}")
+    }
+
+    fn write_finished(
+        &mut self,
+        test_instance: &TestInstance<'_>,
+        run_statuses: &ExecutionStatuses,
+        mut writer: impl Write,
+    ) -> io::Result<()> {
+        self.points += 1;
+        let last_status = run_statuses.last_status();
+        // Expected failures are written as failing TODO points, which TAP consumers don't treat
+        // as failures.
+        let (ok, directive) = match last_status.result {
+            ExecutionResult::ExpectedFail => ("not ok", " # TODO expected failure"),
+            result if result.is_success() => ("ok", ""),
+            _ => ("not ok", ""),
+        };
+        writeln!(
+            writer,
+            "{ok} {} - {}{directive}",
+            self.points,
+            description(test_instance)
+        )?;
+
+        match run_statuses.describe() {
+            ExecutionDescription::Success { .. } => {}
+            ExecutionDescription::Flaky { .. } => {
+                writeln!(writer, "  ---")?;
+                write_yaml_fields(last_status, run_statuses.len(), &mut writer)?;
+                writeln!(
+                    writer,
+                    "  note: flaky, passed on attempt {} of {}",
+                    last_status.retry_data.attempt, last_status.retry_data.total_attempts,
+                )?;
+                writeln!(writer, "  ...")?;
+            }
+            ExecutionDescription::Failure { .. } => {
+                writeln!(writer, "  ---")?;
+                write_yaml_fields(last_status, run_statuses.len(), &mut writer)?;
+                if !last_status.stderr.is_empty() {
+                    writeln!(writer, "  stderr: |")?;
+                    for line in String::from_utf8_lossy(&last_status.stderr).lines() {
+                        writeln!(writer, "    {line}")?;
+                    }
+                }
+                writeln!(writer, "  ...")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn write_yaml_fields(
+    last_status: &ExecuteStatus,
+    attempts: usize,
+    mut writer: impl Write,
+) -> io::Result<()> {
+    let status = match last_status.result {
+        ExecutionResult::Pass => "pass",
+        ExecutionResult::Leak => "leak",
+        ExecutionResult::LeakFail => "leak-fail",
+        ExecutionResult::Fail { .. } => "fail",
+        ExecutionResult::ExecFail => "exec-fail",
+        ExecutionResult::Timeout => "timeout",
+        ExecutionResult::ExpectedFail => "expected-fail",
+        ExecutionResult::UnexpectedPass => "unexpected-pass",
+    };
+    writeln!(writer, "  status: {status}")?;
+    if let Some(exit_code) = last_status.exit_code {
+        writeln!(writer, "  exit_code: {exit_code}")?;
+    }
+    writeln!(writer, "  attempts: {attempts}")?;
+    writeln!(
+        writer,
+        "  duration_ms: {}",
+        last_status.time_taken.as_millis()
+    )
+}
+
+/// Returns the TAP description for a test, escaping characters that have a special meaning.
+fn description(test_instance: &TestInstance<'_>) -> String {
+    let description = format!(
+        "{} {}",
+        test_instance.suite_info.binary_id, test_instance.name
+    );
+    description.replace('\\', "\\\\").replace('#', "\\#")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        list::{RustTestSuite, RustTestSuiteStatus},
+        runner::{RetryData, RunStatistics, RunStats},
+    };
+    use bytes::Bytes;
+    use guppy::{graph::PackageGraph, CargoMetadata, PackageId};
+    use nextest_metadata::{
+        BuildPlatform, FilterMatch, MismatchReason, RustBinaryId, RustTestBinaryKind,
+        RustTestCaseSummary,
+    };
+    use once_cell::sync::Lazy;
+    use std::{
+        collections::{BTreeMap, BTreeSet},
+        time::{Duration, SystemTime},
+    };
+    use tap_parser::{DirectiveKey, TapDirective, TapParser, TapStatement};
+
+    static PACKAGE_GRAPH_FIXTURE: Lazy<PackageGraph> = Lazy::new(|| {
+        static FIXTURE_JSON: &str = include_str!("../../../fixtures/cargo-metadata.json");
+        let metadata = CargoMetadata::parse_json(FIXTURE_JSON).expect("fixture is valid JSON");
+        metadata
+            .build_graph()
+            .expect("fixture is valid PackageGraph")
+    });
+
+    fn make_status(
+        result: ExecutionResult,
+        attempt: usize,
+        total_attempts: usize,
+        stderr: &'static str,
+    ) -> ExecuteStatus {
+        ExecuteStatus {
+            retry_data: RetryData {
+                attempt,
+                total_attempts,
+            },
+            slot: 0,
+            stdout: Bytes::new(),
+            stderr: Bytes::from_static(stderr.as_bytes()),
+            result,
+            exit_code: result.is_success().then_some(0).or(Some(101)),
+            start_time: SystemTime::UNIX_EPOCH,
+            time_taken: Duration::from_millis(25),
+            phases: None,
+            is_slow: false,
+            no_assertions: false,
+            trace_file: None,
+            delay_before_start: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn tap_output_is_valid() {
+        let test_cases: BTreeMap<_, _> = ["test_pass", "test_flaky", "test_fail", "test_#skip"]
+            .into_iter()
+            .map(|name| {
+                (
+                    name.to_owned(),
+                    RustTestCaseSummary {
+                        ignored: false,
+                        filter_match: FilterMatch::Matches,
+                    },
+                )
+            })
+            .collect();
+        let suite = RustTestSuite {
+            binary_id: RustBinaryId::new("fake-package::fake-binary"),
+            binary_path: "/fake/binary".into(),
+            package: PACKAGE_GRAPH_FIXTURE
+                .metadata(&PackageId::new(
+                    "metadata-helper 0.1.0 (path+file:///Users/fakeuser/local/testcrates/metadata/metadata-helper)",
+                ))
+                .expect("package ID is valid"),
+            binary_name: "fake-binary".to_owned(),
+            kind: RustTestBinaryKind::LIB,
+            cwd: "/fake/cwd".into(),
+            build_platform: BuildPlatform::Target,
+            non_test_binaries: BTreeSet::new(),
+            status: RustTestSuiteStatus::Listed { test_cases },
+        };
+        let instance = |name: &str| {
+            let (name, test_info) = suite
+                .status
+                .test_cases()
+                .find(|(test_name, _)| *test_name == name)
+                .expect("test exists");
+            TestInstance::new(name, &suite, test_info)
+        };
+        let finished = |name: &str, statuses: Vec<ExecuteStatus>| TestEvent::TestFinished {
+            test_instance: instance(name),
+            success_output: crate::reporter::TestOutputDisplay::Never,
+            failure_output: crate::reporter::TestOutputDisplay::Never,
+            junit_store_success_output: false,
+            junit_store_failure_output: false,
+            run_statuses: ExecutionStatuses::new(statuses),
+            current_stats: RunStats::default(),
+            running: 0,
+            cancel_state: None,
+        };
+        let fail = ExecutionResult::Fail {
+            abort_status: None,
+            leaked: false,
+        };
+
+        let mut tap = TapWriter::new();
+        let mut buf = Vec::new();
+        tap.write_header(4, &mut buf).unwrap();
+        let events = [
+            TestEvent::TestSkipped {
+                test_instance: instance("test_#skip"),
+                reason: MismatchReason::Ignored,
+            },
+            finished(
+                "test_pass",
+                vec![make_status(ExecutionResult::Pass, 1, 1, "")],
+            ),
+            finished(
+                "test_flaky",
+                vec![
+                    make_status(fail, 1, 3, "first attempt failed\n"),
+                    make_status(ExecutionResult::Pass, 2, 3, ""),
+                ],
+            ),
+            finished(
+                "test_fail",
+                vec![
+                    make_status(fail, 1, 2, "first panic\n"),
+                    make_status(
+                        fail,
+                        2,
+                        2,
+                        "thread 'test_fail' panicked\n\n  at src/lib.rs:1\n",
+                    ),
+                ],
+            ),
+        ];
+        for event in &events {
+            tap.write_event(event, &mut buf).unwrap();
+        }
+
+        let output = String::from_utf8(buf).unwrap();
+        assert_eq!(
+            parse_tap14(&output),
+            ParsedTap {
+                plan: 4,
+                points: vec![
+                    (true, Some("does not match the run-ignored option")),
+                    (true, None),
+                    (true, None),
+                    (false, None),
+                ],
+                bailed_out: false,
+            },
+        );
+        // The skipped test has an escaped description and a SKIP directive, the flaky test is
+        // collapsed to its final result with a note, and the failing test has a YAML block with
+        // stderr from the last attempt. (The empty line in stderr is indented like the others.)
+        let expected = "TAP version 14\n\
+                        1..4\n\
+                        ok 1 - fake-package::fake-binary test_\\#skip # SKIP does not match the run-ignored option\n\
+                        ok 2 - fake-package::fake-binary test_pass\n\
+                        ok 3 - fake-package::fake-binary test_flaky\n\
+                        \x20 ---\n\
+                        \x20 status: pass\n\
+                        \x20 exit_code: 0\n\
+                        \x20 attempts: 2\n\
+                        \x20 duration_ms: 25\n\
+                        \x20 note: flaky, passed on attempt 2 of 3\n\
+                        \x20 ...\n\
+                        not ok 4 - fake-package::fake-binary test_fail\n\
+                        \x20 ---\n\
+                        \x20 status: fail\n\
+                        \x20 exit_code: 101\n\
+                        \x20 attempts: 2\n\
+                        \x20 duration_ms: 25\n\
+                        \x20 stderr: |\n\
+                        \x20   thread 'test_fail' panicked\n\
+                        \x20   \n\
+                        \x20     at src/lib.rs:1\n\
+                        \x20 ...\n";
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn tap_output_bails_out_on_cancel() {
+        let mut tap = TapWriter::new();
+        let mut buf = Vec::new();
+        tap.write_header(2, &mut buf).unwrap();
+        for event in [
+            TestEvent::RunBeginCancel {
+                running: 0,
+                reason: CancelReason::Interrupt,
+            },
+            TestEvent::RunFinished {
+                run_id: uuid::Uuid::nil(),
+                start_time: SystemTime::UNIX_EPOCH,
+                elapsed: Duration::ZERO,
+                run_stats: RunStats::default(),
+                statistics: RunStatistics::default(),
+            },
+        ] {
+            tap.write_event(&event, &mut buf).unwrap();
+        }
+
+        let output = String::from_utf8(buf).unwrap();
+        assert_eq!(
+            parse_tap14(&output),
+            ParsedTap {
+                plan: 2,
+                points: vec![],
+                bailed_out: true,
+            },
+        );
+        assert_eq!(
+            output,
+            "TAP version 14\n1..2\nBail out! run canceled: interrupt\n"
+        );
+    }
+
+    /// The parts of a TAP document that nextest's output is checked against.
+    #[derive(Debug, PartialEq, Eq)]
+    struct ParsedTap<'a> {
+        plan: usize,
+        /// Whether each test point is `ok`, along with the reason for its `# SKIP` directive if
+        /// it has one.
+        points: Vec<(bool, Option<&'a str>)>,
+        bailed_out: bool,
+    }
+
+    /// Parses `output` with an independent TAP 14 parser, rather than the one used to read the
+    /// output of test binaries.
+    #[track_caller]
+    fn parse_tap14(output: &str) -> ParsedTap<'_> {
+        assert!(
+            output.starts_with("TAP version 14\n"),
+            "version line:\n{output}"
+        );
+        let statements = TapParser::new()
+            .parse(output)
+            .unwrap_or_else(|error| panic!("output is valid TAP 14 ({error}):\n{output}"));
+
+        let mut plan = None;
+        let mut points = Vec::new();
+        let mut bailed_out = false;
+        for statement in statements {
+            assert!(!bailed_out, "nothing follows a bail out:\n{output}");
+            match statement {
+                TapStatement::Plan(tap_plan) => {
+                    assert!(plan.is_none(), "only one plan:\n{output}");
+                    plan = Some(tap_plan.count);
+                }
+                TapStatement::TestPoint(test) => {
+                    assert_eq!(
+                        test.number,
+                        points.len() + 1,
+                        "test points are numbered in order:\n{output}"
+                    );
+                    let skip_reason = match test.directive {
+                        Some(TapDirective {
+                            key: DirectiveKey::Skip,
+                            reason,
+                        }) => Some(reason.unwrap_or_default()),
+                        _ => None,
+                    };
+                    points.push((test.result, skip_reason));
+                }
+                TapStatement::Bailout(_) => bailed_out = true,
+                other => panic!("unexpected TAP statement {other:?}:\n{output}"),
+            }
+        }
+
+        ParsedTap {
+            plan: plan.expect("plan is present"),
+            points,
+            bailed_out,
+        }
+    }
+}
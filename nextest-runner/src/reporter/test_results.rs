@@ -0,0 +1,423 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Writing out one JSON line per completed test, for ingestion by live dashboards.
+//!
+//! Unlike the JSON Lines event stream, this format only has records for test completions, with a
+//! minimal set of fields. Tests that were canceled before they could finish still get a record,
+//! with the status `cancelled`.
+
+use crate::{
+    reporter::{json_lines::result_str, CancelReason, TestEvent},
+    runner::{ExecutionDescription, ExecutionStatuses},
+};
+use serde::Serialize;
+use std::{
+    collections::BTreeSet,
+    io::{self, Write},
+};
+
+/// Writes a JSON line for each completed test.
+#[derive(Clone, Debug, Default)]
+pub(super) struct TestResultsWriter {
+    // Tests that are expected to run but haven't completed yet, as (binary ID, test name).
+    pending: BTreeSet<(String, String)>,
+}
+
+impl TestResultsWriter {
+    pub(super) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes a record to `writer` if `event` completes one or more tests, then flushes it.
+    pub(super) fn write_event(
+        &mut self,
+        event: &TestEvent<'_>,
+        mut writer: impl Write,
+    ) -> io::Result<()> {
+        match event {
+            TestEvent::RunStarted { test_list, .. } => {
+                self.pending = test_list
+                    .iter_tests()
+                    .filter(|instance| instance.test_info.filter_match.is_match())
+                    .map(|instance| {
+                        (
+                            instance.suite_info.binary_id.to_string(),
+                            instance.name.to_owned(),
+                        )
+                    })
+                    .collect();
+            }
+            TestEvent::TestFinished {
+                test_instance,
+                run_statuses,
+                cancel_state,
+                ..
+            } => {
+                let binary_id = test_instance.suite_info.binary_id.to_string();
+                self.pending
+                    .remove(&(binary_id.clone(), test_instance.name.to_owned()));
+                let record = TestResult::finished(
+                    &binary_id,
+                    test_instance.name,
+                    run_statuses,
+                    *cancel_state,
+                );
+                write_record(&record, &mut writer)?;
+            }
+            TestEvent::TestSkipped { test_instance, .. } => {
+                // Skipped tests aren't completions, but they shouldn't be reported as canceled
+                // either.
+                self.pending.remove(&(
+                    test_instance.suite_info.binary_id.to_string(),
+                    test_instance.name.to_owned(),
+                ));
+            }
+            TestEvent::RunFinished { .. } => {
+                // Any tests that didn't get to run were canceled.
+                for (binary_id, test_name) in std::mem::take(&mut self.pending) {
+                    let record = TestResult {
+                        binary_id: &binary_id,
+                        test_name: &test_name,
+                        status: CANCELLED,
+                        duration: None,
+                        attempts: 0,
+                    };
+                    write_record(&record, &mut writer)?;
+                }
+            }
+            _ => {}
+        }
+
+        writer.flush()
+    }
+}
+
+const CANCELLED: &str = "cancelled";
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct TestResult<'a> {
+    binary_id: &'a str,
+    test_name: &'a str,
+    status: &'static str,
+    /// Seconds taken by the last attempt of the test. Not present for tests that never started.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration: Option<f64>,
+    attempts: usize,
+}
+
+impl<'a> TestResult<'a> {
+    fn finished(
+        binary_id: &'a str,
+        test_name: &'a str,
+        run_statuses: &ExecutionStatuses,
+        cancel_state: Option<CancelReason>,
+    ) -> Self {
+        let last_status = run_statuses.last_status();
+        let status = match run_statuses.describe() {
+            ExecutionDescription::Success { .. } => result_str(last_status.result),
+            ExecutionDescription::Flaky { .. } => "flaky",
+            // Tests that fail because they were killed by a signal or interrupt are canceled
+            // rather than failed.
+            ExecutionDescription::Failure { .. }
+                if matches!(
+                    cancel_state,
+                    Some(CancelReason::Signal | CancelReason::Interrupt)
+                ) =>
+            {
+                CANCELLED
+            }
+            ExecutionDescription::Failure { .. } => result_str(last_status.result),
+        };
+
+        Self {
+            binary_id,
+            test_name,
+            status,
+            duration: Some(last_status.time_taken.as_secs_f64()),
+            attempts: run_statuses.len(),
+        }
+    }
+}
+
+fn write_record(record: &TestResult<'_>, mut writer: impl Write) -> io::Result<()> {
+    serde_json::to_writer(&mut writer, record)?;
+    writer.write_all(b"\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        list::{RustTestSuite, RustTestSuiteStatus, TestInstance},
+        runner::{ExecuteStatus, ExecutionResult, RetryData, RunStatistics, RunStats},
+    };
+    use bytes::Bytes;
+    use guppy::{graph::PackageGraph, CargoMetadata, PackageId};
+    use nextest_metadata::{
+        BuildPlatform, FilterMatch, RustBinaryId, RustTestBinaryKind, RustTestCaseSummary,
+    };
+    use once_cell::sync::Lazy;
+    use std::{
+        collections::BTreeMap,
+        time::{Duration, SystemTime},
+    };
+
+    static PACKAGE_GRAPH_FIXTURE: Lazy<PackageGraph> = Lazy::new(|| {
+        static FIXTURE_JSON: &str = include_str!("../../../fixtures/cargo-metadata.json");
+        let metadata = CargoMetadata::parse_json(FIXTURE_JSON).expect("fixture is valid JSON");
+        metadata
+            .build_graph()
+            .expect("fixture is valid PackageGraph")
+    });
+
+    /// A writer that records what was flushed, so tests can check that records aren't buffered.
+    #[derive(Default)]
+    struct FlushTracker {
+        buf: Vec<u8>,
+        flushed: Vec<u8>,
+    }
+
+    impl Write for FlushTracker {
+        fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+            self.buf.extend_from_slice(data);
+            Ok(data.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.flushed.extend(self.buf.drain(..));
+            Ok(())
+        }
+    }
+
+    fn make_status(
+        result: ExecutionResult,
+        attempt: usize,
+        total_attempts: usize,
+    ) -> ExecuteStatus {
+        ExecuteStatus {
+            retry_data: RetryData {
+                attempt,
+                total_attempts,
+            },
+            slot: 0,
+            stdout: Bytes::new(),
+            stderr: Bytes::new(),
+            result,
+            exit_code: result.is_success().then_some(0).or(Some(101)),
+            start_time: SystemTime::UNIX_EPOCH,
+            time_taken: Duration::from_millis(250),
+            phases: None,
+            is_slow: false,
+            no_assertions: false,
+            trace_file: None,
+            delay_before_start: Duration::ZERO,
+        }
+    }
+
+    fn make_suite(names: &[&str]) -> RustTestSuite<'static> {
+        let test_cases: BTreeMap<_, _> = names
+            .iter()
+            .map(|name| {
+                (
+                    (*name).to_owned(),
+                    RustTestCaseSummary {
+                        ignored: false,
+                        filter_match: FilterMatch::Matches,
+                    },
+                )
+            })
+            .collect();
+        RustTestSuite {
+            binary_id: RustBinaryId::new("fake-package::fake-binary"),
+            binary_path: "/fake/binary".into(),
+            package: PACKAGE_GRAPH_FIXTURE
+                .metadata(&PackageId::new(
+                    "metadata-helper 0.1.0 (path+file:///Users/fakeuser/local/testcrates/metadata/metadata-helper)",
+                ))
+                .expect("package ID is valid"),
+            binary_name: "fake-binary".to_owned(),
+            kind: RustTestBinaryKind::LIB,
+            cwd: "/fake/cwd".into(),
+            build_platform: BuildPlatform::Target,
+            non_test_binaries: BTreeSet::new(),
+            status: RustTestSuiteStatus::Listed { test_cases },
+        }
+    }
+
+    fn instance<'a>(suite: &'a RustTestSuite<'a>, name: &str) -> TestInstance<'a> {
+        let (name, test_info) = suite
+            .status
+            .test_cases()
+            .find(|(test_name, _)| *test_name == name)
+            .expect("test exists");
+        TestInstance::new(name, suite, test_info)
+    }
+
+    fn finished<'a>(
+        suite: &'a RustTestSuite<'a>,
+        name: &str,
+        statuses: Vec<ExecuteStatus>,
+        cancel_state: Option<CancelReason>,
+    ) -> TestEvent<'a> {
+        TestEvent::TestFinished {
+            test_instance: instance(suite, name),
+            success_output: crate::reporter::TestOutputDisplay::Never,
+            failure_output: crate::reporter::TestOutputDisplay::Never,
+            junit_store_success_output: false,
+            junit_store_failure_output: false,
+            run_statuses: ExecutionStatuses::new(statuses),
+            current_stats: RunStats::default(),
+            running: 0,
+            cancel_state,
+        }
+    }
+
+    fn records(output: &[u8]) -> Vec<serde_json::Value> {
+        std::str::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("each line is a JSON object"))
+            .collect()
+    }
+
+    #[test]
+    fn one_record_per_completed_test() {
+        let suite = make_suite(&["test_pass", "test_flaky", "test_fail"]);
+        let fail = ExecutionResult::Fail {
+            abort_status: None,
+            leaked: false,
+        };
+        let events = [
+            TestEvent::TestSlow {
+                test_instance: instance(&suite, "test_pass"),
+                retry_data: RetryData {
+                    attempt: 1,
+                    total_attempts: 1,
+                },
+                elapsed: Duration::from_secs(60),
+                will_terminate: false,
+            },
+            finished(
+                &suite,
+                "test_pass",
+                vec![make_status(ExecutionResult::Pass, 1, 1)],
+                None,
+            ),
+            finished(
+                &suite,
+                "test_flaky",
+                vec![
+                    make_status(fail, 1, 2),
+                    make_status(ExecutionResult::Pass, 2, 2),
+                ],
+                None,
+            ),
+            finished(&suite, "test_fail", vec![make_status(fail, 1, 1)], None),
+        ];
+
+        let mut writer = TestResultsWriter::new();
+        let mut out = FlushTracker::default();
+        let mut expected_records = 0;
+        for event in &events {
+            writer.write_event(event, &mut out).unwrap();
+            if matches!(event, TestEvent::TestFinished { .. }) {
+                expected_records += 1;
+            }
+            assert!(out.buf.is_empty(), "output is flushed after every event");
+            assert_eq!(records(&out.flushed).len(), expected_records);
+        }
+
+        assert_eq!(
+            records(&out.flushed),
+            vec![
+                serde_json::json!({
+                    "binary-id": "fake-package::fake-binary",
+                    "test-name": "test_pass",
+                    "status": "pass",
+                    "duration": 0.25,
+                    "attempts": 1,
+                }),
+                serde_json::json!({
+                    "binary-id": "fake-package::fake-binary",
+                    "test-name": "test_flaky",
+                    "status": "flaky",
+                    "duration": 0.25,
+                    "attempts": 2,
+                }),
+                serde_json::json!({
+                    "binary-id": "fake-package::fake-binary",
+                    "test-name": "test_fail",
+                    "status": "fail",
+                    "duration": 0.25,
+                    "attempts": 1,
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn cancelled_tests_have_records() {
+        let suite = make_suite(&["test_killed", "test_pass", "test_unstarted"]);
+        let mut writer = TestResultsWriter::new();
+        writer.pending = ["test_killed", "test_pass", "test_unstarted"]
+            .into_iter()
+            .map(|name| ("fake-package::fake-binary".to_owned(), name.to_owned()))
+            .collect();
+
+        let killed = ExecutionResult::Fail {
+            abort_status: None,
+            leaked: false,
+        };
+        let events = [
+            TestEvent::RunBeginCancel {
+                running: 2,
+                reason: CancelReason::Interrupt,
+            },
+            finished(
+                &suite,
+                "test_pass",
+                vec![make_status(ExecutionResult::Pass, 1, 1)],
+                Some(CancelReason::Interrupt),
+            ),
+            finished(
+                &suite,
+                "test_killed",
+                vec![make_status(killed, 1, 1)],
+                Some(CancelReason::Interrupt),
+            ),
+            TestEvent::RunFinished {
+                run_id: uuid::Uuid::nil(),
+                start_time: SystemTime::UNIX_EPOCH,
+                elapsed: Duration::ZERO,
+                run_stats: RunStats::default(),
+                statistics: RunStatistics::default(),
+            },
+        ];
+        let mut out = FlushTracker::default();
+        for event in &events {
+            writer.write_event(event, &mut out).unwrap();
+            assert!(out.buf.is_empty(), "output is flushed after every event");
+        }
+
+        let statuses: Vec<_> = records(&out.flushed)
+            .into_iter()
+            .map(|record| {
+                (
+                    record["test-name"].as_str().unwrap().to_owned(),
+                    record["status"].as_str().unwrap().to_owned(),
+                    record.get("duration").is_some(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("test_pass".to_owned(), "pass".to_owned(), true),
+                ("test_killed".to_owned(), "cancelled".to_owned(), true),
+                ("test_unstarted".to_owned(), "cancelled".to_owned(), false),
+            ]
+        );
+    }
+}
@@ -1,18 +1,26 @@
 // Copyright (c) The nextest Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use super::{ArchiveEvent, BINARIES_METADATA_FILE_NAME, CARGO_METADATA_FILE_NAME};
+use super::{
+    ArchiveEvent, ArchiveManifest, ARCHIVE_MANIFEST_FILE_NAME, BINARIES_METADATA_FILE_NAME,
+    CARGO_METADATA_FILE_NAME,
+};
 use crate::{
     config::get_num_cpus,
     errors::{ArchiveCreateError, UnknownArchiveFormat},
     helpers::convert_rel_path_to_forward_slash,
-    list::{BinaryList, OutputFormat, SerializableFormat},
+    list::{
+        BinaryList, BinaryListState, OutputFormat, RustBuildMeta, RustTestBinary,
+        SerializableFormat, TestList,
+    },
     reuse_build::PathMapper,
 };
 use atomicwrites::{AtomicFile, OverwriteBehavior};
 use camino::{Utf8Path, Utf8PathBuf};
+use nextest_metadata::RustNonTestBinaryKind;
 use std::{
     collections::HashSet,
+    fs,
     io::{self, BufWriter, Write},
     time::{Instant, SystemTime},
 };
@@ -46,6 +54,153 @@ impl ArchiveFormat {
     }
 }
 
+/// Builds a self-contained archive of test binaries from one or more [`TestList`]s.
+///
+/// The archive contains the test binaries along with everything needed to run them without
+/// invoking Cargo: Cargo and binaries metadata, non-test binaries, dynamic libraries, and a
+/// [manifest](ArchiveManifest) recording the platform the binaries were built for. Archives can be
+/// read back with [`ArchiveReader`](super::ArchiveReader).
+#[derive(Clone, Debug)]
+pub struct ArchiveBuilder<'a> {
+    cargo_metadata: &'a str,
+    format: ArchiveFormat,
+    zstd_level: i32,
+    binary_list: Option<BinaryList>,
+}
+
+impl<'a> ArchiveBuilder<'a> {
+    /// The default Zstandard compression level.
+    pub const DEFAULT_ZSTD_LEVEL: i32 = 0;
+
+    /// Creates a new builder, given the Cargo metadata JSON for the workspace that the test
+    /// binaries were built from.
+    pub fn new(cargo_metadata: &'a str, format: ArchiveFormat) -> Self {
+        Self {
+            cargo_metadata,
+            format,
+            zstd_level: Self::DEFAULT_ZSTD_LEVEL,
+            binary_list: None,
+        }
+    }
+
+    /// Sets the Zstandard compression level.
+    pub fn set_zstd_level(&mut self, zstd_level: i32) -> &mut Self {
+        self.zstd_level = zstd_level;
+        self
+    }
+
+    /// Adds the test binaries in `test_list` to the archive, along with the non-test binaries and
+    /// dynamic libraries they depend on.
+    ///
+    /// Returns an error if `test_list` was built into a different target directory, or for a
+    /// different target platform, than test lists added earlier.
+    pub fn add_binaries_from(
+        &mut self,
+        test_list: &TestList<'_>,
+    ) -> Result<&mut Self, ArchiveCreateError> {
+        let binaries = test_list.iter().map(|suite| RustTestBinary {
+            id: suite.binary_id.clone(),
+            path: suite.binary_path.clone(),
+            package_id: suite.package.id().repr().to_owned(),
+            kind: suite.kind.clone(),
+            name: suite.binary_name.clone(),
+            build_platform: suite.build_platform,
+        });
+        self.add_binaries(test_list.rust_build_meta().to_binary_list_state(), binaries)?;
+        Ok(self)
+    }
+
+    /// Writes the archive to `output_file`, replacing it if it already exists.
+    ///
+    /// The file is written atomically, so a partially written archive is never observed at
+    /// `output_file`.
+    pub fn write_to(&self, output_file: &Utf8Path) -> Result<(), ArchiveCreateError> {
+        let binary_list = self
+            .binary_list
+            .as_ref()
+            .ok_or(ArchiveCreateError::NoBinaries)?;
+        // Paths in test lists have already been remapped, so there's nothing left to map.
+        archive_to_file(
+            binary_list,
+            self.cargo_metadata,
+            &PathMapper::noop(),
+            self.format,
+            self.zstd_level,
+            output_file,
+            |_| Ok(()),
+        )
+    }
+
+    fn add_binaries(
+        &mut self,
+        rust_build_meta: RustBuildMeta<BinaryListState>,
+        binaries: impl IntoIterator<Item = RustTestBinary>,
+    ) -> Result<(), ArchiveCreateError> {
+        let binary_list = match &mut self.binary_list {
+            Some(binary_list) => binary_list,
+            None => {
+                self.binary_list = Some(BinaryList {
+                    rust_build_meta,
+                    rust_binaries: binaries.into_iter().collect(),
+                });
+                return Ok(());
+            }
+        };
+
+        let existing_meta = &mut binary_list.rust_build_meta;
+        if existing_meta.target_directory != rust_build_meta.target_directory {
+            return Err(ArchiveCreateError::MismatchedTestList {
+                field: "target directory",
+                expected: existing_meta.target_directory.to_string(),
+                actual: rust_build_meta.target_directory.to_string(),
+            });
+        }
+        let triple_str = |meta: &RustBuildMeta<BinaryListState>| {
+            meta.target_triple
+                .as_ref()
+                .map_or("(host)", |triple| triple.platform.triple_str())
+                .to_owned()
+        };
+        if triple_str(existing_meta) != triple_str(&rust_build_meta) {
+            return Err(ArchiveCreateError::MismatchedTestList {
+                field: "target platform",
+                expected: triple_str(existing_meta),
+                actual: triple_str(&rust_build_meta),
+            });
+        }
+
+        existing_meta
+            .base_output_directories
+            .extend(rust_build_meta.base_output_directories);
+        for (package_id, non_test_binaries) in rust_build_meta.non_test_binaries {
+            existing_meta
+                .non_test_binaries
+                .entry(package_id)
+                .or_default()
+                .extend(non_test_binaries);
+        }
+        for (linked_path, requested_by) in rust_build_meta.linked_paths {
+            existing_meta
+                .linked_paths
+                .entry(linked_path)
+                .or_default()
+                .extend(requested_by);
+        }
+
+        for binary in binaries {
+            if !binary_list
+                .rust_binaries
+                .iter()
+                .any(|existing| existing.id == binary.id)
+            {
+                binary_list.rust_binaries.push(binary);
+            }
+        }
+
+        Ok(())
+    }
+}
+
 /// Archives test binaries along with metadata to the given file.
 ///
 /// The output file is a Zstandard-compressed tarball (`.tar.zst`).
@@ -154,7 +309,18 @@ impl<'a, W: Write> Archiver<'a, W> {
     }
 
     fn archive(mut self) -> Result<(W, usize), ArchiveCreateError> {
-        // Add the binaries metadata first so that while unarchiving, reports are instant.
+        // Add the manifest first so that while unarchiving, archives for other platforms are
+        // rejected before anything else is extracted.
+        let build_platforms = self
+            .binary_list
+            .rust_build_meta
+            .build_platforms()
+            .map_err(ArchiveCreateError::UnknownHostPlatform)?;
+        let manifest = serde_json::to_string_pretty(&ArchiveManifest::new(&build_platforms))
+            .expect("archive manifest serialization is infallible");
+        self.append_from_memory(ARCHIVE_MANIFEST_FILE_NAME, &manifest)?;
+
+        // Add the binaries metadata next so that while unarchiving, reports are instant.
         let binaries_metadata = self
             .binary_list
             .to_string(OutputFormat::Serializable(SerializableFormat::JsonPretty))
@@ -177,14 +343,16 @@ impl<'a, W: Write> Archiver<'a, W> {
             let rel_path = Utf8Path::new("target").join(rel_path);
             let rel_path = convert_rel_path_to_forward_slash(&rel_path);
 
-            self.append_path(&binary.path, &rel_path)?;
+            self.append_binary(&binary.path, &rel_path)?;
         }
-        for non_test_binary in self
+        for (package_id, non_test_binary) in self
             .binary_list
             .rust_build_meta
             .non_test_binaries
             .iter()
-            .flat_map(|(_, binaries)| binaries)
+            .flat_map(|(package_id, binaries)| {
+                binaries.iter().map(move |binary| (package_id, binary))
+            })
         {
             let src_path = self
                 .binary_list
@@ -196,7 +364,11 @@ impl<'a, W: Write> Archiver<'a, W> {
             let rel_path = Utf8Path::new("target").join(&non_test_binary.path);
             let rel_path = convert_rel_path_to_forward_slash(&rel_path);
 
-            self.append_path(&src_path, &rel_path)?;
+            if non_test_binary.kind == RustNonTestBinaryKind::DYLIB {
+                self.append_dylib(package_id, &src_path, &rel_path)?;
+            } else {
+                self.append_binary(&src_path, &rel_path)?;
+            }
         }
 
         // Write linked paths to the archive.
@@ -275,20 +447,33 @@ impl<'a, W: Write> Archiver<'a, W> {
         src_path: &Utf8Path,
     ) -> Result<(), ArchiveCreateError> {
         // In case of a symlink pointing to a directory, is_dir is false, but src.is_dir() will return true
-        for entry in
-            src_path
-                .read_dir_utf8()
-                .map_err(|error| ArchiveCreateError::InputFileRead {
-                    path: src_path.to_owned(),
-                    is_dir: Some(true),
-                    error,
-                })?
+        for entry in src_path
+            .read_dir()
+            .map_err(|error| ArchiveCreateError::InputFileRead {
+                path: src_path.to_owned(),
+                is_dir: Some(true),
+                error,
+            })?
         {
             let entry = entry.map_err(|error| ArchiveCreateError::DirEntryRead {
                 path: src_path.to_owned(),
                 error,
             })?;
-            let src = entry.path();
+            // Linked paths can contain arbitrary files, some of which may not have UTF-8 names.
+            // Archive entries must have UTF-8 paths, so skip these rather than failing the whole
+            // archive.
+            let src = match Utf8PathBuf::from_path_buf(entry.path()) {
+                Ok(src) => src,
+                Err(path) => {
+                    log::warn!(
+                        target: "nextest-runner",
+                        "ignoring `{}` in linked path `{src_path}`: file name is not valid UTF-8",
+                        path.display(),
+                    );
+                    continue;
+                }
+            };
+            let src = src.as_path();
             let file_type =
                 entry
                     .file_type()
@@ -306,6 +491,71 @@ impl<'a, W: Write> Archiver<'a, W> {
         Ok(())
     }
 
+    fn append_binary(&mut self, src: &Utf8Path, dest: &Utf8Path) -> Result<(), ArchiveCreateError> {
+        if self.added_files.contains(dest) {
+            return Ok(());
+        }
+
+        let map_err = |error| ArchiveCreateError::InputFileRead {
+            path: src.to_owned(),
+            is_dir: Some(false),
+            error,
+        };
+        let mut file = fs::File::open(src).map_err(map_err)?;
+        let metadata = file.metadata().map_err(map_err)?;
+
+        let mut header = tar::Header::new_gnu();
+        header.set_metadata(&metadata);
+        // Binaries must be executable once extracted, even if they were archived on a platform
+        // that doesn't track the executable bit (e.g. Windows).
+        header.set_mode(0o755);
+        header.set_cksum();
+
+        self.builder
+            .append_data(&mut header, dest, &mut file)
+            .map_err(map_err)?;
+        self.added_files.insert(dest.into());
+        Ok(())
+    }
+
+    fn append_dylib(
+        &mut self,
+        package_id: &str,
+        src: &Utf8Path,
+        dest: &Utf8Path,
+    ) -> Result<(), ArchiveCreateError> {
+        // A dynamic library might be missing if it was removed after the build, e.g. by a build
+        // script that cleans up after itself. Tests that don't load it can still run.
+        if !src.exists() {
+            log::warn!(
+                target: "nextest-runner",
+                "dynamic library `{src}` built by `{package_id}` doesn't exist, not archiving it",
+            );
+            return Ok(());
+        }
+        self.append_binary(src, dest)?;
+
+        // For dynamic libraries built by workspace crates, Cargo only reports the copy "uplifted"
+        // to the base output directory (e.g. debug/libfoo.so), but tests may also load the
+        // original in the deps directory (e.g. debug/deps/libfoo.so). Archive that copy as well.
+        let file_name = src.file_name().expect("dylib paths have a file name");
+        let parent = src.parent().expect("dylib paths have a parent");
+        if parent.file_name() != Some("deps") {
+            let deps_src = parent.join("deps").join(file_name);
+            if deps_src.is_file() {
+                let deps_dest = dest
+                    .parent()
+                    .expect("dylib archive paths have a parent")
+                    .join("deps")
+                    .join(file_name);
+                let deps_dest = convert_rel_path_to_forward_slash(&deps_dest);
+                self.append_binary(&deps_src, &deps_dest)?;
+            }
+        }
+
+        Ok(())
+    }
+
     fn append_path(&mut self, src: &Utf8Path, dest: &Utf8Path) -> Result<(), ArchiveCreateError> {
         // Check added_files to ensure we aren't adding duplicate files.
         if !self.added_files.contains(dest) {
@@ -325,6 +575,241 @@ impl<'a, W: Write> Archiver<'a, W> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::reuse_build::{ArchiveReader, ExtractDestination};
+    use camino_tempfile::Utf8TempDir;
+    use nextest_metadata::{BuildPlatform, RustNonTestBinarySummary, RustTestBinaryKind};
+    use target_spec::Platform;
+
+    static CARGO_METADATA_FIXTURE: &str = include_str!("../../../fixtures/cargo-metadata.json");
+
+    fn test_binary(path: &Utf8Path) -> RustTestBinary {
+        RustTestBinary {
+            id: "metadata-helper".into(),
+            path: path.to_owned(),
+            package_id: "metadata-helper 0.1.0 (path+file:///Users/fakeuser/local/testcrates/metadata/metadata-helper)".to_owned(),
+            kind: RustTestBinaryKind::LIB,
+            name: "metadata-helper".to_owned(),
+            build_platform: BuildPlatform::Target,
+        }
+    }
+
+    #[test]
+    fn test_archive_builder_roundtrip() {
+        let temp_dir = Utf8TempDir::new().expect("temp dir created");
+        let target_dir = temp_dir.path().join("target");
+        let binary_path = target_dir.join("debug/deps/metadata_helper-0123456789abcdef");
+        fs::create_dir_all(binary_path.parent().unwrap()).expect("deps dir created");
+        fs::write(&binary_path, "test binary contents").expect("binary written");
+        // The executable bit should be set on extraction even if it's missing on disk.
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&binary_path, fs::Permissions::from_mode(0o644))
+                .expect("permissions set");
+        }
+
+        let mut builder = ArchiveBuilder::new(CARGO_METADATA_FIXTURE, ArchiveFormat::TarZst);
+        // Adding the same binary twice, as from two test lists, archives it once.
+        for _ in 0..2 {
+            builder
+                .add_binaries(
+                    RustBuildMeta::new(&target_dir, None),
+                    [test_binary(&binary_path)],
+                )
+                .expect("test lists are compatible");
+        }
+        let archive_file = temp_dir.path().join("archive.tar.zst");
+        builder.write_to(&archive_file).expect("archive written");
+
+        let dest_dir = temp_dir.path().join("dest");
+        fs::create_dir(&dest_dir).expect("dest dir created");
+        let extracted = ArchiveReader::new(&archive_file, ArchiveFormat::TarZst)
+            .extract(
+                ExtractDestination::Destination {
+                    dir: dest_dir,
+                    overwrite: false,
+                },
+                |_| Ok(()),
+            )
+            .expect("archive extracted");
+
+        let manifest = extracted.manifest().expect("archive has a manifest");
+        assert_eq!(
+            manifest.host_platform,
+            Platform::current().unwrap().triple_str()
+        );
+        assert_eq!(manifest.target_platform, None);
+        assert_eq!(extracted.binary_list().rust_binaries.len(), 1);
+
+        let extracted_binary = extracted
+            .target_dir()
+            .join("debug/deps/metadata_helper-0123456789abcdef");
+        assert_eq!(
+            fs::read_to_string(&extracted_binary).expect("extracted binary read"),
+            "test binary contents"
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&extracted_binary)
+                .expect("extracted binary metadata read")
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o111, 0o111, "extracted binary is executable");
+        }
+    }
+
+    #[test]
+    fn test_archive_builder_dylibs() {
+        let temp_dir = Utf8TempDir::new().expect("temp dir created");
+        let target_dir = temp_dir.path().join("target");
+        let binary_path = target_dir.join("debug/deps/metadata_helper-0123456789abcdef");
+        fs::create_dir_all(binary_path.parent().unwrap()).expect("deps dir created");
+        fs::write(&binary_path, "test binary contents").expect("binary written");
+        // Cargo uplifts libfoo.so from the deps directory and only reports the uplifted copy.
+        fs::write(target_dir.join("debug/libfoo.so"), "uplifted").expect("dylib written");
+        fs::write(target_dir.join("debug/deps/libfoo.so"), "original").expect("dylib written");
+
+        let mut rust_build_meta = RustBuildMeta::new(&target_dir, None);
+        let dylib = |name: &str, path: &str| RustNonTestBinarySummary {
+            name: name.to_owned(),
+            kind: RustNonTestBinaryKind::DYLIB,
+            path: path.into(),
+        };
+        rust_build_meta.non_test_binaries.insert(
+            "foo 0.1.0 (path+file:///fake/foo)".to_owned(),
+            [
+                dylib("foo", "debug/libfoo.so"),
+                // Missing dynamic libraries are skipped with a warning.
+                dylib("missing", "debug/libmissing.so"),
+            ]
+            .into_iter()
+            .collect(),
+        );
+
+        let mut builder = ArchiveBuilder::new(CARGO_METADATA_FIXTURE, ArchiveFormat::TarZst);
+        builder
+            .add_binaries(rust_build_meta, [test_binary(&binary_path)])
+            .expect("test list is compatible");
+        let archive_file = temp_dir.path().join("archive.tar.zst");
+        builder.write_to(&archive_file).expect("archive written");
+
+        let dest_dir = temp_dir.path().join("dest");
+        fs::create_dir(&dest_dir).expect("dest dir created");
+        let extracted = ArchiveReader::new(&archive_file, ArchiveFormat::TarZst)
+            .extract(
+                ExtractDestination::Destination {
+                    dir: dest_dir,
+                    overwrite: false,
+                },
+                |_| Ok(()),
+            )
+            .expect("archive extracted");
+
+        let extracted_target_dir = extracted.target_dir();
+        assert_eq!(
+            fs::read_to_string(extracted_target_dir.join("debug/libfoo.so"))
+                .expect("uplifted dylib read"),
+            "uplifted"
+        );
+        assert_eq!(
+            fs::read_to_string(extracted_target_dir.join("debug/deps/libfoo.so"))
+                .expect("original dylib read"),
+            "original"
+        );
+        assert!(!extracted_target_dir.join("debug/libmissing.so").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_archive_builder_non_utf8_linked_file() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let temp_dir = Utf8TempDir::new().expect("temp dir created");
+        let target_dir = temp_dir.path().join("target");
+        let binary_path = target_dir.join("debug/deps/metadata_helper-0123456789abcdef");
+        fs::create_dir_all(binary_path.parent().unwrap()).expect("deps dir created");
+        fs::write(&binary_path, "test binary contents").expect("binary written");
+
+        let linked_dir = target_dir.join("debug/build/foo-0123456789abcdef/out");
+        fs::create_dir_all(&linked_dir).expect("linked dir created");
+        fs::write(linked_dir.join("libfoo.so"), "linked").expect("linked file written");
+        // Some file systems (e.g. APFS) reject non-UTF-8 file names, so skip the test there.
+        let non_utf8 = linked_dir
+            .as_std_path()
+            .join(OsStr::from_bytes(b"lib\xff\xfe.so"));
+        if fs::write(&non_utf8, "non-UTF-8").is_err() {
+            return;
+        }
+
+        let mut rust_build_meta = RustBuildMeta::new(&target_dir, None);
+        rust_build_meta.linked_paths.insert(
+            "debug/build/foo-0123456789abcdef/out".into(),
+            ["foo 0.1.0 (path+file:///fake/foo)".to_owned()]
+                .into_iter()
+                .collect(),
+        );
+
+        let mut builder = ArchiveBuilder::new(CARGO_METADATA_FIXTURE, ArchiveFormat::TarZst);
+        builder
+            .add_binaries(rust_build_meta, [test_binary(&binary_path)])
+            .expect("test list is compatible");
+        let archive_file = temp_dir.path().join("archive.tar.zst");
+        builder
+            .write_to(&archive_file)
+            .expect("non-UTF-8 file names in linked paths are skipped");
+
+        let extracted = ArchiveReader::new(&archive_file, ArchiveFormat::TarZst)
+            .extract(ExtractDestination::TempDir { persist: false }, |_| Ok(()))
+            .expect("archive extracted");
+        let extracted_dir = extracted
+            .target_dir()
+            .join("debug/build/foo-0123456789abcdef/out");
+        assert_eq!(
+            fs::read_to_string(extracted_dir.join("libfoo.so")).expect("linked file read"),
+            "linked"
+        );
+        assert_eq!(
+            fs::read_dir(&extracted_dir)
+                .expect("extracted linked dir read")
+                .count(),
+            1,
+            "only the UTF-8 file was archived"
+        );
+    }
+
+    #[test]
+    fn test_archive_builder_errors() {
+        let builder = ArchiveBuilder::new(CARGO_METADATA_FIXTURE, ArchiveFormat::TarZst);
+        assert!(matches!(
+            builder.write_to("archive.tar.zst".as_ref()),
+            Err(ArchiveCreateError::NoBinaries)
+        ));
+
+        let mut builder = ArchiveBuilder::new(CARGO_METADATA_FIXTURE, ArchiveFormat::TarZst);
+        builder
+            .add_binaries(
+                RustBuildMeta::new("/fake/target", None),
+                [test_binary("/fake/target/debug/deps/a".as_ref())],
+            )
+            .expect("first test list is always compatible");
+        let error = builder
+            .add_binaries(
+                RustBuildMeta::new("/other/target", None),
+                [test_binary("/other/target/debug/deps/a".as_ref())],
+            )
+            .expect_err("target directories differ");
+        assert!(
+            matches!(
+                &error,
+                ArchiveCreateError::MismatchedTestList {
+                    field: "target directory",
+                    ..
+                }
+            ),
+            "unexpected error: {error:?}"
+        );
+    }
 
     #[test]
     fn test_archive_format_autodetect() {
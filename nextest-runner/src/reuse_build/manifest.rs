@@ -0,0 +1,141 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{errors::ArchiveExtractError, platform::BuildPlatforms};
+use serde::{Deserialize, Serialize};
+use target_spec::Platform;
+
+/// The manifest stored within an archive, at [`ARCHIVE_MANIFEST_FILE_NAME`](super::ARCHIVE_MANIFEST_FILE_NAME).
+///
+/// The manifest records the platforms the archive was built for, so that an archive isn't
+/// accidentally run on a platform its binaries can't run on.
+#[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct ArchiveManifest {
+    /// The version of the manifest format.
+    pub format_version: u32,
+
+    /// The triple of the platform the archive was built on.
+    pub host_platform: String,
+
+    /// The triple of the platform the archive was cross-compiled for, if any.
+    #[serde(default)]
+    pub target_platform: Option<String>,
+}
+
+impl ArchiveManifest {
+    /// The current version of the manifest format.
+    pub const FORMAT_VERSION: u32 = 1;
+
+    /// Creates a new manifest for binaries built for the given platforms.
+    pub fn new(build_platforms: &BuildPlatforms) -> Self {
+        Self {
+            format_version: Self::FORMAT_VERSION,
+            host_platform: build_platforms.host.triple_str().to_owned(),
+            target_platform: build_platforms
+                .target
+                .as_ref()
+                .map(|target| target.platform.triple_str().to_owned()),
+        }
+    }
+
+    /// Returns the triple of the platform that the test binaries in the archive run on: the target
+    /// platform if the archive was cross-compiled, and the host platform otherwise.
+    pub fn test_platform(&self) -> &str {
+        self.target_platform
+            .as_deref()
+            .unwrap_or(&self.host_platform)
+    }
+
+    /// Checks that the binaries in this archive can run on `current`.
+    ///
+    /// Cross-compiled archives are often run through a [target
+    /// runner](https://nexte.st/book/target-runners) on a platform other than the one they were
+    /// built for. If `allow_cross_platform` is true, only archives that weren't cross-compiled are
+    /// checked.
+    pub fn check_platform(
+        &self,
+        current: &Platform,
+        allow_cross_platform: bool,
+    ) -> Result<(), ArchiveExtractError> {
+        if allow_cross_platform && self.target_platform.is_some() {
+            return Ok(());
+        }
+
+        let archive_platform = self.test_platform();
+        if archive_platform == current.triple_str() {
+            Ok(())
+        } else {
+            Err(ArchiveExtractError::PlatformMismatch {
+                archive_platform: archive_platform.to_owned(),
+                current_platform: current.triple_str().to_owned(),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use target_spec::TargetFeatures;
+    use test_case::test_case;
+
+    fn platform(triple: &str) -> Platform {
+        Platform::new(triple.to_owned(), TargetFeatures::Unknown).expect("triple is known")
+    }
+
+    #[test_case(
+        "x86_64-unknown-linux-gnu", None, false, None
+        ; "native on same platform"
+    )]
+    #[test_case(
+        "aarch64-apple-darwin", None, false, Some("aarch64-apple-darwin")
+        ; "native on different platform"
+    )]
+    #[test_case(
+        "aarch64-apple-darwin", None, true, Some("aarch64-apple-darwin")
+        ; "native on different platform with cross allowed"
+    )]
+    #[test_case(
+        "aarch64-apple-darwin", Some("x86_64-unknown-linux-gnu"), false, None
+        ; "cross-compiled for current platform"
+    )]
+    #[test_case(
+        "x86_64-unknown-linux-gnu", Some("aarch64-unknown-linux-gnu"), false, Some("aarch64-unknown-linux-gnu")
+        ; "cross-compiled for different platform"
+    )]
+    #[test_case(
+        "x86_64-unknown-linux-gnu", Some("aarch64-unknown-linux-gnu"), true, None
+        ; "cross-compiled for different platform with cross allowed"
+    )]
+    fn check_platform(
+        host_platform: &str,
+        target_platform: Option<&str>,
+        allow_cross_platform: bool,
+        mismatch: Option<&str>,
+    ) {
+        let manifest = ArchiveManifest {
+            format_version: ArchiveManifest::FORMAT_VERSION,
+            host_platform: host_platform.to_owned(),
+            target_platform: target_platform.map(|triple| triple.to_owned()),
+        };
+        let result =
+            manifest.check_platform(&platform("x86_64-unknown-linux-gnu"), allow_cross_platform);
+        match (result, mismatch) {
+            (Ok(()), None) => {}
+            (
+                Err(ArchiveExtractError::PlatformMismatch {
+                    archive_platform,
+                    current_platform,
+                }),
+                Some(expected),
+            ) => {
+                assert_eq!(archive_platform, expected);
+                assert_eq!(current_platform, "x86_64-unknown-linux-gnu");
+            }
+            (result, mismatch) => {
+                panic!("unexpected result {result:?}, expected mismatch: {mismatch:?}")
+            }
+        }
+    }
+}
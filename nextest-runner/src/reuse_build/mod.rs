@@ -5,27 +5,31 @@
 //!
 //! Nextest allows users to reuse builds done on one machine. This module contains support for that.
 //!
-//! The main data structures here are [`ReuseBuildInfo`] and [`PathMapper`].
+//! The main data structures here are [`ReuseBuildInfo`] and [`PathMapper`]. Self-contained archives
+//! are created with [`ArchiveBuilder`] and read back with [`ArchiveReader`].
 
 use crate::{
-    errors::{
-        ArchiveExtractError, ArchiveReadError, PathMapperConstructError, PathMapperConstructKind,
-    },
+    errors::{ArchiveExtractError, PathMapperConstructError, PathMapperConstructKind},
     list::BinaryList,
 };
 use camino::{Utf8Path, Utf8PathBuf};
 use camino_tempfile::Utf8TempDir;
 use guppy::graph::PackageGraph;
-use std::{fs, io, sync::Arc};
+use std::{io, sync::Arc};
 
 mod archive_reporter;
 mod archiver;
+mod manifest;
 mod unarchiver;
 
 pub use archive_reporter::*;
 pub use archiver::*;
+pub use manifest::*;
 pub use unarchiver::*;
 
+/// The name of the file in which the archive manifest is stored.
+pub const ARCHIVE_MANIFEST_FILE_NAME: &str = "target/nextest/archive-manifest.json";
+
 /// The name of the file in which Cargo metadata is stored.
 pub const CARGO_METADATA_FILE_NAME: &str = "target/nextest/cargo-metadata.json";
 
@@ -50,15 +54,26 @@ impl ReuseBuildInfo {
     pub fn new(
         cargo_metadata: Option<MetadataWithRemap<(String, PackageGraph)>>,
         binaries_metadata: Option<MetadataWithRemap<BinaryList>>,
+    ) -> Self {
+        Self::new_with_temp_dir(cargo_metadata, binaries_metadata, None)
+    }
+
+    fn new_with_temp_dir(
+        cargo_metadata: Option<MetadataWithRemap<(String, PackageGraph)>>,
+        binaries_metadata: Option<MetadataWithRemap<BinaryList>>,
+        temp_dir: Option<Utf8TempDir>,
     ) -> Self {
         Self {
             cargo_metadata,
             binaries_metadata,
-            _temp_dir: None,
+            _temp_dir: temp_dir,
         }
     }
 
     /// Extracts an archive and constructs a [`ReuseBuildInfo`] from it.
+    ///
+    /// Archives cross-compiled for another platform are allowed, since they may be run through a
+    /// target runner. See [`ArchiveReader`] for more control.
     pub fn extract_archive<F>(
         archive_file: &Utf8Path,
         format: ArchiveFormat,
@@ -69,32 +84,10 @@ impl ReuseBuildInfo {
     where
         F: for<'e> FnMut(ArchiveEvent<'e>) -> io::Result<()>,
     {
-        let mut file = fs::File::open(archive_file)
-            .map_err(|err| ArchiveExtractError::Read(ArchiveReadError::Io(err)))?;
-
-        let mut unarchiver = Unarchiver::new(&mut file, format);
-        let ExtractInfo {
-            dest_dir,
-            temp_dir,
-            binary_list,
-            cargo_metadata_json,
-            graph,
-        } = unarchiver.extract(dest, callback)?;
-
-        let cargo_metadata = MetadataWithRemap {
-            metadata: MetadataOrPath::metadata((cargo_metadata_json, graph)),
-            remap: workspace_remap.map(|p| p.to_owned()),
-        };
-        let binaries_metadata = MetadataWithRemap {
-            metadata: MetadataOrPath::metadata(binary_list),
-            remap: Some(dest_dir.join("target")),
-        };
-
-        Ok(Self {
-            cargo_metadata: Some(cargo_metadata),
-            binaries_metadata: Some(binaries_metadata),
-            _temp_dir: temp_dir,
-        })
+        let extracted = ArchiveReader::new(archive_file, format)
+            .set_allow_cross_platform(true)
+            .extract(dest, callback)?;
+        Ok(extracted.into_reuse_build_info(workspace_remap))
     }
 
     /// Returns the Cargo metadata.
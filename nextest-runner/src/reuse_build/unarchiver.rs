@@ -1,10 +1,16 @@
 // Copyright (c) The nextest Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use super::{ArchiveEvent, ArchiveFormat, BINARIES_METADATA_FILE_NAME, CARGO_METADATA_FILE_NAME};
+use super::{
+    ArchiveEvent, ArchiveFormat, ArchiveManifest, MetadataOrPath, MetadataWithRemap, PathMapper,
+    ReuseBuildInfo, ARCHIVE_MANIFEST_FILE_NAME, BINARIES_METADATA_FILE_NAME,
+    CARGO_METADATA_FILE_NAME,
+};
 use crate::{
-    errors::{ArchiveExtractError, ArchiveReadError},
-    list::BinaryList,
+    errors::{
+        ArchiveExtractError, ArchiveReadError, PathMapperConstructError, UnknownHostPlatform,
+    },
+    list::{BinaryList, RustBuildMeta, TestListState},
 };
 use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
 use camino_tempfile::Utf8TempDir;
@@ -15,25 +21,201 @@ use std::{
     io::{self, Seek},
     time::Instant,
 };
+use target_spec::Platform;
+
+/// Reads an archive created by [`ArchiveBuilder`](super::ArchiveBuilder) or `cargo nextest
+/// archive`, reconstructing the state needed to build a [`TestList`](crate::list::TestList)
+/// without invoking Cargo.
+#[derive(Clone, Debug)]
+pub struct ArchiveReader<'a> {
+    archive_file: &'a Utf8Path,
+    format: ArchiveFormat,
+    allow_cross_platform: bool,
+    strict_platform_check: bool,
+}
+
+impl<'a> ArchiveReader<'a> {
+    /// Creates a new reader for the archive at `archive_file`.
+    pub fn new(archive_file: &'a Utf8Path, format: ArchiveFormat) -> Self {
+        Self {
+            archive_file,
+            format,
+            allow_cross_platform: false,
+            strict_platform_check: false,
+        }
+    }
+
+    /// Sets whether archives cross-compiled for a platform other than the current one can be
+    /// extracted, for example to run them through a [target
+    /// runner](https://nexte.st/book/target-runners).
+    ///
+    /// Archives that weren't cross-compiled must always have been built on the current platform.
+    /// Defaults to false.
+    pub fn set_allow_cross_platform(&mut self, allow_cross_platform: bool) -> &mut Self {
+        self.allow_cross_platform = allow_cross_platform;
+        self
+    }
+
+    /// Sets whether an archive built for a platform other than the current one is rejected, rather
+    /// than extracted with a warning.
+    ///
+    /// The current platform is the one this version of nextest was built for, which may not match
+    /// the platform tests are built for even when the archive can run here. For example, a musl
+    /// build of nextest can run tests built for `x86_64-unknown-linux-gnu`. For this reason, a
+    /// mismatch is only an error if this is set. Defaults to false.
+    pub fn set_strict_platform_check(&mut self, strict_platform_check: bool) -> &mut Self {
+        self.strict_platform_check = strict_platform_check;
+        self
+    }
+
+    /// Extracts the archive to `dest`, calling `callback` as extraction progresses.
+    ///
+    /// If the archive's [manifest](ArchiveManifest) says it was built for a different platform,
+    /// a warning is logged, or an error is returned if
+    /// [`set_strict_platform_check`](Self::set_strict_platform_check) is set. Archives created by
+    /// older versions of nextest don't have a manifest, and aren't checked.
+    pub fn extract<F>(
+        &self,
+        dest: ExtractDestination,
+        callback: F,
+    ) -> Result<ExtractedArchive, ArchiveExtractError>
+    where
+        F: for<'e> FnMut(ArchiveEvent<'e>) -> io::Result<()>,
+    {
+        let current_platform = Platform::current().map_err(|error| {
+            ArchiveExtractError::UnknownHostPlatform(UnknownHostPlatform { error })
+        })?;
+        let mut file = fs::File::open(self.archive_file)
+            .map_err(|err| ArchiveExtractError::Read(ArchiveReadError::Io(err)))?;
+
+        let mut unarchiver = Unarchiver::new(&mut file, self.format);
+        let info = unarchiver.extract(dest, callback, |manifest| {
+            match manifest.check_platform(&current_platform, self.allow_cross_platform) {
+                Err(ArchiveExtractError::PlatformMismatch {
+                    archive_platform,
+                    current_platform,
+                }) if !self.strict_platform_check => {
+                    log::warn!(
+                        target: "nextest-runner",
+                        "archive was built for `{archive_platform}`, but nextest was built for \
+                         `{current_platform}`: tests in the archive may fail to run",
+                    );
+                    Ok(())
+                }
+                result => result,
+            }
+        })?;
 
+        Ok(ExtractedArchive { info })
+    }
+}
+
+/// The contents of an archive extracted by [`ArchiveReader`].
+///
+/// If the archive was extracted to a temporary directory that isn't persisted, the directory is
+/// removed when this is dropped.
 #[derive(Debug)]
-pub(crate) struct Unarchiver<'a> {
+pub struct ExtractedArchive {
+    info: ExtractInfo,
+}
+
+impl ExtractedArchive {
+    /// Returns the manifest stored in the archive, or `None` if the archive was created by an older
+    /// version of nextest.
+    pub fn manifest(&self) -> Option<&ArchiveManifest> {
+        self.info.manifest.as_ref()
+    }
+
+    /// Returns the directory the archive was extracted to.
+    pub fn dest_dir(&self) -> &Utf8Path {
+        &self.info.dest_dir
+    }
+
+    /// Returns the directory that the target directory in the archive was extracted to.
+    pub fn target_dir(&self) -> Utf8PathBuf {
+        self.info.dest_dir.join("target")
+    }
+
+    /// Returns the list of test binaries in the archive, with paths as they were on the machine
+    /// the archive was built on.
+    pub fn binary_list(&self) -> &BinaryList {
+        &self.info.binary_list
+    }
+
+    /// Returns the Cargo metadata JSON stored in the archive.
+    pub fn cargo_metadata_json(&self) -> &str {
+        &self.info.cargo_metadata_json
+    }
+
+    /// Returns the package graph for the workspace the archive was built from.
+    pub fn graph(&self) -> &PackageGraph {
+        &self.info.graph
+    }
+
+    /// Returns a [`PathMapper`] that maps paths in the archive to the extracted target directory,
+    /// and optionally the workspace to `workspace_remap`.
+    pub fn path_mapper(
+        &self,
+        workspace_remap: Option<&Utf8Path>,
+    ) -> Result<PathMapper, PathMapperConstructError> {
+        PathMapper::new(
+            self.info.graph.workspace().root(),
+            workspace_remap,
+            &self.info.binary_list.rust_build_meta.target_directory,
+            Some(&self.target_dir()),
+        )
+    }
+
+    /// Returns the Rust build metadata for the archive, with paths mapped by `path_mapper`.
+    pub fn rust_build_meta(&self, path_mapper: &PathMapper) -> RustBuildMeta<TestListState> {
+        self.info.binary_list.rust_build_meta.map_paths(path_mapper)
+    }
+
+    /// Converts this into a [`ReuseBuildInfo`], with the workspace optionally remapped to
+    /// `workspace_remap`.
+    pub fn into_reuse_build_info(self, workspace_remap: Option<&Utf8Path>) -> ReuseBuildInfo {
+        let ExtractInfo {
+            manifest: _,
+            dest_dir,
+            temp_dir,
+            binary_list,
+            cargo_metadata_json,
+            graph,
+        } = self.info;
+
+        let cargo_metadata = MetadataWithRemap {
+            metadata: MetadataOrPath::metadata((cargo_metadata_json, graph)),
+            remap: workspace_remap.map(|p| p.to_owned()),
+        };
+        let binaries_metadata = MetadataWithRemap {
+            metadata: MetadataOrPath::metadata(binary_list),
+            remap: Some(dest_dir.join("target")),
+        };
+
+        ReuseBuildInfo::new_with_temp_dir(Some(cargo_metadata), Some(binaries_metadata), temp_dir)
+    }
+}
+
+#[derive(Debug)]
+struct Unarchiver<'a> {
     file: &'a mut fs::File,
     format: ArchiveFormat,
 }
 
 impl<'a> Unarchiver<'a> {
-    pub(crate) fn new(file: &'a mut fs::File, format: ArchiveFormat) -> Self {
+    fn new(file: &'a mut fs::File, format: ArchiveFormat) -> Self {
         Self { file, format }
     }
 
-    pub(crate) fn extract<F>(
+    fn extract<F, C>(
         &mut self,
         dest: ExtractDestination,
         mut callback: F,
+        check_manifest: C,
     ) -> Result<ExtractInfo, ArchiveExtractError>
     where
         F: for<'e> FnMut(ArchiveEvent<'e>) -> io::Result<()>,
+        C: Fn(&ArchiveManifest) -> Result<(), ArchiveExtractError>,
     {
         let (dest_dir, temp_dir) = match dest {
             ExtractDestination::TempDir { persist } => {
@@ -83,11 +265,13 @@ impl<'a> Unarchiver<'a> {
             .rewind()
             .map_err(|error| ArchiveExtractError::Read(ArchiveReadError::Io(error)))?;
         let mut archive_reader =
-            ArchiveReader::new(self.file, self.format).map_err(ArchiveExtractError::Read)?;
+            TarReader::new(self.file, self.format).map_err(ArchiveExtractError::Read)?;
 
-        // Will be filled out by the for loop below\
+        // Will be filled out by the for loop below.
+        let mut manifest = None;
         let mut binary_list = None;
         let mut graph_data = None;
+        let manifest_path = Utf8Path::new(ARCHIVE_MANIFEST_FILE_NAME);
         let binaries_metadata_path = Utf8Path::new(BINARIES_METADATA_FILE_NAME);
         let cargo_metadata_path = Utf8Path::new(CARGO_METADATA_FILE_NAME);
 
@@ -107,9 +291,23 @@ impl<'a> Unarchiver<'a> {
                     error,
                 })?;
 
-            // For archives created by nextest, binaries_metadata_path should be towards the beginning
-            // so this should report the ExtractStarted event instantly.
-            if path == binaries_metadata_path {
+            if path == manifest_path {
+                // For archives created by nextest, the manifest is the first entry, so archives
+                // for other platforms are rejected before anything else is extracted.
+                let json = fs::read_to_string(dest_dir.join(manifest_path))
+                    .map_err(|error| ArchiveExtractError::WriteFile { path, error })?;
+                let this_manifest: ArchiveManifest =
+                    serde_json::from_str(&json).map_err(|error| {
+                        ArchiveExtractError::Read(ArchiveReadError::MetadataDeserializeError {
+                            path: manifest_path,
+                            error,
+                        })
+                    })?;
+                check_manifest(&this_manifest)?;
+                manifest = Some(this_manifest);
+            } else if path == binaries_metadata_path {
+                // For archives created by nextest, binaries_metadata_path should be towards the
+                // beginning so this should report the ExtractStarted event instantly.
                 // Try reading the binary list from the file on disk.
                 let mut file = fs::File::open(dest_dir.join(binaries_metadata_path))
                     .map_err(|error| ArchiveExtractError::WriteFile { path, error })?;
@@ -191,6 +389,7 @@ impl<'a> Unarchiver<'a> {
         .map_err(ArchiveExtractError::ReporterIo)?;
 
         Ok(ExtractInfo {
+            manifest,
             dest_dir,
             temp_dir,
             binary_list,
@@ -201,28 +400,31 @@ impl<'a> Unarchiver<'a> {
 }
 
 #[derive(Debug)]
-pub(crate) struct ExtractInfo {
+struct ExtractInfo {
+    /// The manifest read from the archive, if present.
+    manifest: Option<ArchiveManifest>,
+
     /// The destination directory.
-    pub dest_dir: Utf8PathBuf,
+    dest_dir: Utf8PathBuf,
 
     /// An optional [`Utf8TempDir`], used for cleanup.
-    pub temp_dir: Option<Utf8TempDir>,
+    temp_dir: Option<Utf8TempDir>,
 
     /// The [`BinaryList`] read from the archive.
-    pub binary_list: BinaryList,
+    binary_list: BinaryList,
 
     /// The Cargo metadata JSON.
-    pub cargo_metadata_json: String,
+    cargo_metadata_json: String,
 
     /// The [`PackageGraph`] read from the archive.
-    pub graph: PackageGraph,
+    graph: PackageGraph,
 }
 
-struct ArchiveReader<'a> {
+struct TarReader<'a> {
     archive: tar::Archive<zstd::Decoder<'static, io::BufReader<&'a mut fs::File>>>,
 }
 
-impl<'a> ArchiveReader<'a> {
+impl<'a> TarReader<'a> {
     fn new(file: &'a mut fs::File, format: ArchiveFormat) -> Result<Self, ArchiveReadError> {
         let archive = match format {
             ArchiveFormat::TarZst => {
@@ -318,3 +520,93 @@ pub enum ExtractDestination {
 }
 
 type ArchiveEntry<'r, 'a> = tar::Entry<'r, zstd::Decoder<'static, io::BufReader<&'a mut fs::File>>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use camino_tempfile::Utf8TempDir;
+
+    /// Writes a `.tar.zst` archive with the given raw entry paths and contents.
+    fn write_archive(path: &Utf8Path, entries: &[(&[u8], &str)]) {
+        let file = fs::File::create(path).expect("archive file created");
+        let encoder = zstd::Encoder::new(file, 0).expect("encoder created");
+        let mut builder = tar::Builder::new(encoder);
+        for (entry_path, contents) in entries {
+            let mut header = tar::Header::new_gnu();
+            // Set the name directly rather than through set_path, which rejects non-UTF-8 paths
+            // on some platforms.
+            header.as_gnu_mut().expect("header is GNU").name[..entry_path.len()]
+                .copy_from_slice(entry_path);
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o664);
+            header.set_cksum();
+            builder
+                .append(&header, contents.as_bytes())
+                .expect("entry appended");
+        }
+        builder
+            .into_inner()
+            .expect("archive written")
+            .finish()
+            .expect("zstd stream finished");
+    }
+
+    fn extract(archive_file: &Utf8Path) -> Result<ExtractedArchive, ArchiveExtractError> {
+        ArchiveReader::new(archive_file, ArchiveFormat::TarZst)
+            .set_strict_platform_check(true)
+            .extract(ExtractDestination::TempDir { persist: false }, |_| Ok(()))
+    }
+
+    #[test]
+    fn test_extract_non_utf8_path() {
+        let temp_dir = Utf8TempDir::new().expect("temp dir created");
+        let archive_file = temp_dir.path().join("archive.tar.zst");
+        write_archive(&archive_file, &[(&b"target/debug/\xff\xfe"[..], "")]);
+
+        match extract(&archive_file) {
+            Err(ArchiveExtractError::Read(ArchiveReadError::NonUtf8Path(path))) => {
+                assert_eq!(path, b"target/debug/\xff\xfe");
+            }
+            other => panic!("expected non-UTF-8 path error, found {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_extract_platform_mismatch() {
+        let current = Platform::current().expect("current platform is known");
+        let other = if current.triple_str() == "x86_64-unknown-linux-gnu" {
+            "aarch64-apple-darwin"
+        } else {
+            "x86_64-unknown-linux-gnu"
+        };
+        let manifest = format!(
+            r#"{{ "format-version": 1, "host-platform": "{other}", "target-platform": null }}"#
+        );
+
+        let temp_dir = Utf8TempDir::new().expect("temp dir created");
+        let archive_file = temp_dir.path().join("archive.tar.zst");
+        write_archive(
+            &archive_file,
+            &[(ARCHIVE_MANIFEST_FILE_NAME.as_bytes(), manifest.as_str())],
+        );
+
+        match extract(&archive_file) {
+            Err(ArchiveExtractError::PlatformMismatch {
+                archive_platform,
+                current_platform,
+            }) => {
+                assert_eq!(archive_platform, other);
+                assert_eq!(current_platform, current.triple_str());
+            }
+            other => panic!("expected platform mismatch error, found {other:?}"),
+        }
+
+        // Without a strict check, the archive is extracted anyway.
+        let extracted = ArchiveReader::new(&archive_file, ArchiveFormat::TarZst)
+            .extract(ExtractDestination::TempDir { persist: false }, |_| Ok(()));
+        assert!(
+            !matches!(extracted, Err(ArchiveExtractError::PlatformMismatch { .. })),
+            "platform mismatch is only a warning by default, found {extracted:?}"
+        );
+    }
+}
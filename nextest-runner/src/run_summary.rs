@@ -0,0 +1,965 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Summaries of test runs, and merging summaries from several shards.
+//!
+//! When a test suite is [partitioned](crate::partition) across several machines, each shard
+//! records a [`RunSummary`] from its test events with [`RunSummary::record`], and serializes it
+//! (for example, as JSON). The summaries are then combined with [`RunSummary::merge`] into a
+//! summary for the whole test suite, and a JUnit report for it can be produced with
+//! [`RunSummary::to_junit_report`].
+//!
+//! Results can also be sliced by a dimension that nextest doesn't know about, such as which team
+//! owns a test, with [`RunSummary::aggregate_by`]. The values for each test are provided by a
+//! [`TestMetadata`] implementation, for example a [`TestMetadataMap`] read from a sidecar file.
+
+use crate::{
+    errors::{MergeSummariesError, OverlappingTest},
+    reporter::TestEvent,
+    runner::{ExecutionDescription, ExecutionResult, ExecutionStatuses, FinalRunStatus, RunStats},
+};
+use nextest_metadata::RustBinaryId;
+use quick_junit::{NonSuccessKind, Property, Report, TestCase, TestCaseStatus, TestSuite};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::Duration,
+};
+
+/// A summary of a test run, or of one shard of a test run.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RunSummary {
+    /// The number of tests in the test list, including tests that weren't run.
+    pub test_count: usize,
+
+    /// The amount of time the run took.
+    ///
+    /// For merged summaries, this is the longest time taken by any of the shards.
+    #[serde(with = "humantime_serde")]
+    pub elapsed: Duration,
+
+    /// Statistics for the run.
+    pub stats: RunStats,
+
+    /// The tests that finished running, keyed by binary ID and then by test name.
+    pub tests: BTreeMap<RustBinaryId, BTreeMap<String, TestSummary>>,
+}
+
+impl RunSummary {
+    /// Creates a new, empty summary.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records `event` into this summary.
+    ///
+    /// The test count is taken from [`TestEvent::RunStarted`], finished tests from
+    /// [`TestEvent::TestFinished`], and statistics from [`TestEvent::RunFinished`]. Other events
+    /// are ignored.
+    pub fn record(&mut self, event: &TestEvent<'_>) {
+        match event {
+            TestEvent::RunStarted { test_list, .. } => {
+                self.test_count = test_list.test_count();
+            }
+            TestEvent::TestFinished {
+                test_instance,
+                run_statuses,
+                ..
+            } => {
+                self.insert(
+                    test_instance.suite_info.binary_id.clone(),
+                    test_instance.name,
+                    TestSummary::new(run_statuses),
+                );
+            }
+            TestEvent::RunFinished {
+                elapsed, run_stats, ..
+            } => {
+                self.elapsed = *elapsed;
+                self.stats = *run_stats;
+            }
+            _ => {}
+        }
+    }
+
+    /// Records the summary of a test that finished running.
+    pub fn insert(
+        &mut self,
+        binary_id: RustBinaryId,
+        test_name: impl Into<String>,
+        summary: TestSummary,
+    ) {
+        self.tests
+            .entry(binary_id)
+            .or_default()
+            .insert(test_name.into(), summary);
+    }
+
+    /// Returns an iterator over the tests that finished running, sorted by binary ID and test
+    /// name.
+    pub fn iter_tests(&self) -> impl Iterator<Item = (&RustBinaryId, &str, &TestSummary)> + '_ {
+        self.tests.iter().flat_map(|(binary_id, tests)| {
+            tests
+                .iter()
+                .map(move |(test_name, summary)| (binary_id, test_name.as_str(), summary))
+        })
+    }
+
+    /// Merges summaries from several shards of a test run into a summary for the whole run.
+    ///
+    /// Statistics are added up, except for the number of skipped tests, which is the number of
+    /// tests that weren't selected by any of the shards.
+    ///
+    /// Returns an error if a test was run in more than one summary, or if the summaries were
+    /// produced from test lists with different numbers of tests. Both of these usually indicate
+    /// that the shards were misconfigured.
+    pub fn merge(
+        summaries: impl IntoIterator<Item = RunSummary>,
+    ) -> Result<RunSummary, MergeSummariesError> {
+        let mut merged = RunSummary::default();
+        let mut locations: BTreeMap<(RustBinaryId, String), Vec<usize>> = BTreeMap::new();
+
+        for (index, summary) in summaries.into_iter().enumerate() {
+            if index == 0 {
+                merged.test_count = summary.test_count;
+            } else if summary.test_count != merged.test_count {
+                return Err(MergeSummariesError::TestCountMismatch {
+                    index,
+                    expected: merged.test_count,
+                    actual: summary.test_count,
+                });
+            }
+
+            merged.elapsed = merged.elapsed.max(summary.elapsed);
+            add_stats(&mut merged.stats, &summary.stats);
+
+            for (binary_id, tests) in summary.tests {
+                for (test_name, test_summary) in tests {
+                    locations
+                        .entry((binary_id.clone(), test_name.clone()))
+                        .or_default()
+                        .push(index);
+                    merged.insert(binary_id.clone(), test_name, test_summary);
+                }
+            }
+        }
+
+        let overlapping: Vec<_> = locations
+            .into_iter()
+            .filter(|(_, summary_indexes)| summary_indexes.len() > 1)
+            .map(
+                |((binary_id, test_name), summary_indexes)| OverlappingTest {
+                    binary_id,
+                    test_name,
+                    summary_indexes,
+                },
+            )
+            .collect();
+        if !overlapping.is_empty() {
+            return Err(MergeSummariesError::OverlappingTests { overlapping });
+        }
+
+        // Each shard skips the tests selected by the other shards, so the skipped counts can't be
+        // added up.
+        merged.stats.skipped = merged
+            .test_count
+            .saturating_sub(merged.stats.initial_run_count + merged.stats.quarantined);
+
+        Ok(merged)
+    }
+
+    /// Returns the tests that failed in this summary.
+    ///
+    /// This can be used as a baseline for a later run with
+    /// [`TestRunnerBuilder::set_known_failures`](crate::runner::TestRunnerBuilder::set_known_failures),
+    /// so that only new failures cause the run to be canceled.
+    pub fn known_failures(&self) -> KnownFailures {
+        let mut known_failures = KnownFailures::new();
+        for (binary_id, test_name, summary) in self.iter_tests() {
+            if !summary.status.is_success() {
+                known_failures.insert(binary_id.clone(), test_name);
+            }
+        }
+        known_failures
+    }
+
+    /// Returns a JUnit report for this summary, with a test suite for each binary.
+    ///
+    /// Summaries don't store test output, so unlike reports written out during a test run, this
+    /// report doesn't include the output or failure descriptions of tests.
+    pub fn to_junit_report(&self, report_name: &str) -> Report {
+        let mut report = Report::new(report_name);
+        report.set_time(self.elapsed);
+        report.add_test_suites(self.tests.iter().map(|(binary_id, tests)| {
+            let mut test_suite = TestSuite::new(binary_id.as_str());
+            test_suite.add_test_cases(tests.iter().map(|(test_name, summary)| {
+                let mut test_case = TestCase::new(test_name, summary.status.to_junit_status());
+                test_case
+                    .set_classname(binary_id.as_str())
+                    .set_time(summary.duration)
+                    .add_properties([
+                        Property::new(
+                            "nextest.retries",
+                            summary.attempts.saturating_sub(1).to_string(),
+                        ),
+                        Property::new(
+                            "nextest.flaky",
+                            (summary.status == TestSummaryStatus::Flaky).to_string(),
+                        ),
+                    ]);
+                test_case
+            }));
+            test_suite
+        }));
+        report
+    }
+
+    /// Aggregates the tests in this summary by the value of the metadata `key`, as provided by
+    /// `metadata`.
+    ///
+    /// Tests without a value for `key` are aggregated into the [`UNKNOWN_METADATA_VALUE`] bucket.
+    pub fn aggregate_by(
+        &self,
+        key: &str,
+        metadata: &dyn TestMetadata,
+    ) -> BTreeMap<String, AggregateSummary> {
+        let mut buckets: BTreeMap<String, AggregateSummary> = BTreeMap::new();
+        for (binary_id, test_name, summary) in self.iter_tests() {
+            let value = metadata
+                .value(binary_id, test_name, key)
+                .unwrap_or(UNKNOWN_METADATA_VALUE);
+            buckets.entry(value.to_owned()).or_default().add(summary);
+        }
+        buckets
+    }
+}
+
+fn add_stats(total: &mut RunStats, stats: &RunStats) {
+    // Destructure so that new fields have to be handled here.
+    let RunStats {
+        initial_run_count,
+        finished_count,
+        passed,
+        passed_slow,
+        flaky,
+        failed,
+        failed_slow,
+        timed_out,
+        leaky,
+        exec_failed,
+        expected_failures,
+        unexpected_passes,
+        // This is computed separately in RunSummary::merge.
+        skipped: _,
+        quarantined,
+        no_assertions,
+    } = *stats;
+
+    total.initial_run_count += initial_run_count;
+    total.finished_count += finished_count;
+    total.passed += passed;
+    total.passed_slow += passed_slow;
+    total.flaky += flaky;
+    total.failed += failed;
+    total.failed_slow += failed_slow;
+    total.timed_out += timed_out;
+    total.leaky += leaky;
+    total.exec_failed += exec_failed;
+    total.expected_failures += expected_failures;
+    total.unexpected_passes += unexpected_passes;
+    total.quarantined += quarantined;
+    total.no_assertions += no_assertions;
+}
+
+/// A set of tests that are known to fail, for example because they failed in an earlier run.
+///
+/// Failures of these tests don't count towards fail-fast. They're still reported as failures, and
+/// still cause the run to fail. A known failure that now passes is reported as a pass as usual.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct KnownFailures {
+    tests: BTreeMap<RustBinaryId, BTreeSet<String>>,
+}
+
+impl KnownFailures {
+    /// Creates a new, empty set of known failures.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks a test as known to fail.
+    pub fn insert(&mut self, binary_id: RustBinaryId, test_name: impl Into<String>) {
+        self.tests
+            .entry(binary_id)
+            .or_default()
+            .insert(test_name.into());
+    }
+
+    /// Returns true if the test is known to fail.
+    pub fn contains(&self, binary_id: &RustBinaryId, test_name: &str) -> bool {
+        self.tests
+            .get(binary_id)
+            .map_or(false, |tests| tests.contains(test_name))
+    }
+
+    /// Returns the number of tests that are known to fail.
+    pub fn len(&self) -> usize {
+        self.tests.values().map(|tests| tests.len()).sum()
+    }
+
+    /// Returns true if no tests are known to fail.
+    pub fn is_empty(&self) -> bool {
+        self.tests.values().all(|tests| tests.is_empty())
+    }
+}
+
+/// A short summary of a test run, written out to the profile's `summary-path`.
+///
+/// Unlike [`RunSummary`], this only has counts rather than the results of individual tests, so
+/// it's easy to read from shell scripts.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct FinalSummary {
+    /// The name of the profile the tests were run with.
+    pub profile: String,
+
+    /// The final status of the run.
+    pub status: FinalRunStatus,
+
+    /// The exit code that `cargo nextest run` exits with for this status.
+    pub exit_code: i32,
+
+    /// True if the run was canceled before all tests finished.
+    pub canceled: bool,
+
+    /// The amount of time the run took.
+    #[serde(with = "humantime_serde")]
+    pub elapsed: Duration,
+
+    /// The number of tests that finished running.
+    pub run: usize,
+
+    /// The number of tests that passed, including flaky tests.
+    pub passed: usize,
+
+    /// The number of tests that failed, timed out, or couldn't be executed.
+    pub failed: usize,
+
+    /// The number of tests that passed on retry.
+    pub flaky: usize,
+
+    /// The number of tests that were skipped.
+    pub skipped: usize,
+}
+
+impl FinalSummary {
+    /// Creates a summary of a run with the given statistics.
+    ///
+    /// `flaky_as_failure` corresponds to the profile's `flaky-as-failure` setting, and is used to
+    /// determine the final status.
+    pub fn new(
+        profile: impl Into<String>,
+        elapsed: Duration,
+        run_stats: &RunStats,
+        flaky_as_failure: bool,
+    ) -> Self {
+        let status = run_stats.final_status(flaky_as_failure);
+        Self {
+            profile: profile.into(),
+            status,
+            exit_code: status.exit_code(),
+            canceled: run_stats.initial_run_count > run_stats.finished_count,
+            elapsed,
+            run: run_stats.finished_count,
+            passed: run_stats.passed,
+            failed: run_stats.failed + run_stats.timed_out + run_stats.exec_failed,
+            flaky: run_stats.flaky,
+            skipped: run_stats.skipped,
+        }
+    }
+}
+
+/// The summary of a single test that finished running.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TestSummary {
+    /// The final status of the test.
+    pub status: TestSummaryStatus,
+
+    /// The number of times the test was run, including retries.
+    pub attempts: usize,
+
+    /// The time taken by the last attempt.
+    #[serde(with = "humantime_serde")]
+    pub duration: Duration,
+}
+
+impl TestSummary {
+    /// Returns the summary corresponding to these execution statuses.
+    pub fn new(run_statuses: &ExecutionStatuses) -> Self {
+        Self {
+            status: TestSummaryStatus::new(run_statuses),
+            attempts: run_statuses.len(),
+            duration: run_statuses.last_status().time_taken,
+        }
+    }
+}
+
+/// The bucket that tests without a value for a metadata key are aggregated into by
+/// [`RunSummary::aggregate_by`].
+pub const UNKNOWN_METADATA_VALUE: &str = "unknown";
+
+/// A source of metadata for tests, used by [`RunSummary::aggregate_by`].
+pub trait TestMetadata {
+    /// Returns the value of the metadata `key` for a test, or `None` if the test doesn't have a
+    /// value for it.
+    fn value(&self, binary_id: &RustBinaryId, test_name: &str, key: &str) -> Option<&str>;
+}
+
+/// A mapping from tests to metadata, typically read from a sidecar file.
+///
+/// The mapping is keyed by test ID (the binary ID and the test name, separated by a space) or by
+/// binary ID, with each entry being a map of metadata keys to values. Values for a test ID take
+/// precedence over values for its binary ID. For example, as JSON:
+///
+/// ```json
+/// {
+///     "my-crate": { "team": "storage" },
+///     "my-crate tests::network_io": { "team": "networking" }
+/// }
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct TestMetadataMap {
+    entries: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl TestMetadataMap {
+    /// Creates a new, empty mapping.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the metadata `key` to `value` for a test ID or binary ID.
+    pub fn insert(
+        &mut self,
+        id: impl Into<String>,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> &mut Self {
+        self.entries
+            .entry(id.into())
+            .or_default()
+            .insert(key.into(), value.into());
+        self
+    }
+}
+
+impl TestMetadata for TestMetadataMap {
+    fn value(&self, binary_id: &RustBinaryId, test_name: &str, key: &str) -> Option<&str> {
+        let lookup = |id: &str| self.entries.get(id)?.get(key).map(|value| value.as_str());
+        lookup(&format!("{binary_id} {test_name}")).or_else(|| lookup(binary_id.as_str()))
+    }
+}
+
+/// Results for the tests sharing a metadata value, as returned by [`RunSummary::aggregate_by`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct AggregateSummary {
+    /// The number of tests that passed, including flaky tests and expected failures.
+    pub passed: usize,
+
+    /// The number of tests that failed.
+    pub failed: usize,
+
+    /// The total time taken by the last attempt of each test.
+    #[serde(with = "humantime_serde")]
+    pub duration: Duration,
+}
+
+impl AggregateSummary {
+    /// Returns the number of tests aggregated into this summary.
+    pub fn test_count(&self) -> usize {
+        self.passed + self.failed
+    }
+
+    fn add(&mut self, summary: &TestSummary) {
+        if summary.status.is_success() {
+            self.passed += 1;
+        } else {
+            self.failed += 1;
+        }
+        self.duration += summary.duration;
+    }
+}
+
+/// The final status of a test, as recorded in a [`TestSummary`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TestSummaryStatus {
+    /// The test passed.
+    Pass,
+
+    /// The test passed but leaked handles.
+    Leak,
+
+    /// The test passed but leaked handles, and leaks were configured to be treated as failures.
+    LeakFail,
+
+    /// The test failed, then passed on a retry.
+    Flaky,
+
+    /// The test failed.
+    Fail,
+
+    /// An error occurred while executing the test.
+    ExecFail,
+
+    /// The test was terminated due to timeout.
+    Timeout,
+
+    /// The test was expected to fail, and it failed (XFAIL).
+    ExpectedFail,
+
+    /// The test was expected to fail, but it passed (XPASS).
+    UnexpectedPass,
+}
+
+impl TestSummaryStatus {
+    /// Returns the status corresponding to these execution statuses.
+    pub fn new(run_statuses: &ExecutionStatuses) -> Self {
+        if let ExecutionDescription::Flaky { .. } = run_statuses.describe() {
+            return Self::Flaky;
+        }
+        match run_statuses.last_status().result {
+            ExecutionResult::Pass => Self::Pass,
+            ExecutionResult::Leak => Self::Leak,
+            ExecutionResult::LeakFail => Self::LeakFail,
+            ExecutionResult::Fail { .. } => Self::Fail,
+            ExecutionResult::ExecFail => Self::ExecFail,
+            ExecutionResult::Timeout => Self::Timeout,
+            ExecutionResult::ExpectedFail => Self::ExpectedFail,
+            ExecutionResult::UnexpectedPass => Self::UnexpectedPass,
+        }
+    }
+
+    /// Returns true if this status is considered a success.
+    pub fn is_success(self) -> bool {
+        match self {
+            Self::Pass | Self::Leak | Self::Flaky | Self::ExpectedFail => true,
+            Self::Fail | Self::LeakFail | Self::ExecFail | Self::Timeout | Self::UnexpectedPass => {
+                false
+            }
+        }
+    }
+
+    fn to_junit_status(self) -> TestCaseStatus {
+        let (kind, ty) = match self {
+            Self::Pass | Self::Leak | Self::Flaky => return TestCaseStatus::success(),
+            Self::ExpectedFail => {
+                let mut status = TestCaseStatus::skipped();
+                status.set_type("xfail").set_message("expected failure");
+                return status;
+            }
+            Self::Fail => (NonSuccessKind::Failure, "test failure"),
+            Self::LeakFail => (
+                NonSuccessKind::Failure,
+                "test passed but leaked handles, treated as a failure",
+            ),
+            Self::Timeout => (NonSuccessKind::Failure, "test timeout"),
+            Self::UnexpectedPass => (NonSuccessKind::Failure, "unexpected pass"),
+            Self::ExecFail => (NonSuccessKind::Error, "execution failure"),
+        };
+        let mut status = TestCaseStatus::non_success(kind);
+        status.set_type(ty);
+        status
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+    use maplit::btreemap;
+
+    fn test_summary(status: TestSummaryStatus) -> TestSummary {
+        TestSummary {
+            status,
+            attempts: if status == TestSummaryStatus::Flaky {
+                2
+            } else {
+                1
+            },
+            duration: Duration::from_millis(100),
+        }
+    }
+
+    fn shard(
+        test_count: usize,
+        elapsed_secs: u64,
+        tests: &[(&str, TestSummaryStatus)],
+        stats: RunStats,
+    ) -> RunSummary {
+        let binary_id = RustBinaryId::new("my-crate::tests");
+        let mut summary = RunSummary {
+            test_count,
+            elapsed: Duration::from_secs(elapsed_secs),
+            stats,
+            ..RunSummary::default()
+        };
+        for &(test_name, status) in tests {
+            summary.insert(binary_id.clone(), test_name, test_summary(status));
+        }
+        summary
+    }
+
+    #[test]
+    fn final_summary() {
+        let run_stats = RunStats {
+            initial_run_count: 5,
+            finished_count: 5,
+            passed: 3,
+            flaky: 1,
+            failed: 1,
+            timed_out: 1,
+            skipped: 2,
+            ..RunStats::default()
+        };
+        let summary = FinalSummary::new("ci", Duration::from_millis(1500), &run_stats, false);
+        assert_eq!(
+            serde_json::to_value(&summary).unwrap(),
+            serde_json::json!({
+                "profile": "ci",
+                "status": "failed",
+                "exit-code": 100,
+                "canceled": false,
+                "elapsed": "1s 500ms",
+                "run": 5,
+                "passed": 3,
+                "failed": 2,
+                "flaky": 1,
+                "skipped": 2,
+            })
+        );
+
+        // Flaky tests only affect the status with flaky-as-failure.
+        let run_stats = RunStats {
+            initial_run_count: 2,
+            finished_count: 2,
+            passed: 2,
+            flaky: 1,
+            ..RunStats::default()
+        };
+        let summary = FinalSummary::new("ci", Duration::ZERO, &run_stats, false);
+        assert_eq!(
+            (summary.status, summary.exit_code),
+            (FinalRunStatus::Success, 0)
+        );
+        let summary = FinalSummary::new("ci", Duration::ZERO, &run_stats, true);
+        assert_eq!(
+            (summary.status, summary.exit_code),
+            (FinalRunStatus::Flaky, 106)
+        );
+
+        // A canceled run is a failure, even if no tests failed.
+        let run_stats = RunStats {
+            initial_run_count: 3,
+            finished_count: 1,
+            passed: 1,
+            ..RunStats::default()
+        };
+        let summary = FinalSummary::new("ci", Duration::ZERO, &run_stats, false);
+        assert!(summary.canceled);
+        assert_eq!(
+            (summary.status, summary.exit_code),
+            (FinalRunStatus::Failed, 100)
+        );
+    }
+
+    #[test]
+    fn merge_summaries() {
+        // Ten tests in total, two of which aren't run by any shard.
+        let summaries = vec![
+            shard(
+                10,
+                5,
+                &[
+                    ("test_a", TestSummaryStatus::Pass),
+                    ("test_b", TestSummaryStatus::Fail),
+                    ("test_c", TestSummaryStatus::Flaky),
+                ],
+                RunStats {
+                    initial_run_count: 3,
+                    finished_count: 3,
+                    passed: 2,
+                    flaky: 1,
+                    failed: 1,
+                    skipped: 7,
+                    ..RunStats::default()
+                },
+            ),
+            shard(
+                10,
+                8,
+                &[
+                    ("test_d", TestSummaryStatus::Pass),
+                    ("test_e", TestSummaryStatus::Timeout),
+                    ("test_f", TestSummaryStatus::ExpectedFail),
+                ],
+                RunStats {
+                    initial_run_count: 3,
+                    finished_count: 3,
+                    passed: 2,
+                    timed_out: 1,
+                    expected_failures: 1,
+                    skipped: 7,
+                    ..RunStats::default()
+                },
+            ),
+            shard(
+                10,
+                3,
+                &[
+                    ("test_g", TestSummaryStatus::Pass),
+                    ("test_h", TestSummaryStatus::Pass),
+                ],
+                RunStats {
+                    initial_run_count: 2,
+                    finished_count: 2,
+                    passed: 2,
+                    skipped: 8,
+                    ..RunStats::default()
+                },
+            ),
+        ];
+
+        let merged = RunSummary::merge(summaries).expect("summaries don't overlap");
+        assert_eq!(merged.test_count, 10);
+        assert_eq!(merged.elapsed, Duration::from_secs(8));
+        assert_eq!(
+            merged.stats,
+            RunStats {
+                initial_run_count: 8,
+                finished_count: 8,
+                passed: 6,
+                flaky: 1,
+                failed: 1,
+                timed_out: 1,
+                expected_failures: 1,
+                skipped: 2,
+                ..RunStats::default()
+            }
+        );
+        assert!(!merged.stats.is_success());
+        assert_eq!(merged.iter_tests().count(), 8);
+
+        let report = merged.to_junit_report("merged");
+        assert_eq!(report.tests, 8);
+        assert_eq!(report.failures, 2);
+        assert_eq!(report.errors, 0);
+        assert_eq!(report.test_suites.len(), 1);
+        assert_eq!(report.test_suites[0].disabled, 1);
+    }
+
+    #[test]
+    fn merge_no_summaries() {
+        assert_eq!(
+            RunSummary::merge(Vec::new()),
+            Ok(RunSummary::default()),
+            "merging no summaries produces an empty summary"
+        );
+    }
+
+    #[test]
+    fn merge_overlapping_tests() {
+        let summaries = vec![
+            shard(
+                3,
+                1,
+                &[
+                    ("test_a", TestSummaryStatus::Pass),
+                    ("test_b", TestSummaryStatus::Pass),
+                ],
+                RunStats::default(),
+            ),
+            shard(
+                3,
+                1,
+                &[("test_c", TestSummaryStatus::Pass)],
+                RunStats::default(),
+            ),
+            // Misconfigured shard that runs everything.
+            shard(
+                3,
+                1,
+                &[
+                    ("test_a", TestSummaryStatus::Pass),
+                    ("test_b", TestSummaryStatus::Fail),
+                    ("test_c", TestSummaryStatus::Pass),
+                ],
+                RunStats::default(),
+            ),
+        ];
+
+        let binary_id = RustBinaryId::new("my-crate::tests");
+        let overlapping_test = |test_name: &str, summary_indexes: Vec<usize>| OverlappingTest {
+            binary_id: binary_id.clone(),
+            test_name: test_name.to_owned(),
+            summary_indexes,
+        };
+        assert_eq!(
+            RunSummary::merge(summaries),
+            Err(MergeSummariesError::OverlappingTests {
+                overlapping: vec![
+                    overlapping_test("test_a", vec![0, 2]),
+                    overlapping_test("test_b", vec![0, 2]),
+                    overlapping_test("test_c", vec![1, 2]),
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn merge_test_count_mismatch() {
+        let summaries = vec![
+            shard(3, 1, &[], RunStats::default()),
+            shard(3, 1, &[], RunStats::default()),
+            shard(4, 1, &[], RunStats::default()),
+        ];
+        assert_eq!(
+            RunSummary::merge(summaries),
+            Err(MergeSummariesError::TestCountMismatch {
+                index: 2,
+                expected: 3,
+                actual: 4,
+            })
+        );
+    }
+
+    #[test]
+    fn aggregate_by_metadata() {
+        // Ownership of tests by team, as a sidecar file would provide.
+        let metadata: TestMetadataMap = serde_json::from_str(indoc! {r#"
+            {
+                "my-crate::tests": { "team": "storage", "tier": "1" },
+                "my-crate::tests test_b": { "team": "networking" },
+                "my-crate::tests test_d": { "tier": "2" },
+                "other-crate::tests": { "team": "networking" }
+            }
+        "#})
+        .expect("metadata deserialized");
+
+        let mut summary = shard(
+            5,
+            1,
+            &[
+                ("test_a", TestSummaryStatus::Pass),
+                ("test_b", TestSummaryStatus::Fail),
+                ("test_c", TestSummaryStatus::Flaky),
+                ("test_d", TestSummaryStatus::Timeout),
+            ],
+            RunStats::default(),
+        );
+        summary.insert(
+            RustBinaryId::new("other-crate::tests"),
+            "test_e",
+            test_summary(TestSummaryStatus::Pass),
+        );
+        summary.insert(
+            RustBinaryId::new("unowned-crate::tests"),
+            "test_f",
+            test_summary(TestSummaryStatus::ExecFail),
+        );
+
+        let aggregate = |passed, failed| AggregateSummary {
+            passed,
+            failed,
+            duration: Duration::from_millis(100) * (passed + failed) as u32,
+        };
+        assert_eq!(
+            summary.aggregate_by("team", &metadata),
+            btreemap! {
+                "networking".to_owned() => aggregate(1, 1),
+                "storage".to_owned() => aggregate(2, 1),
+                UNKNOWN_METADATA_VALUE.to_owned() => aggregate(0, 1),
+            }
+        );
+        assert_eq!(
+            summary.aggregate_by("tier", &metadata),
+            btreemap! {
+                "1".to_owned() => aggregate(2, 1),
+                "2".to_owned() => aggregate(0, 1),
+                UNKNOWN_METADATA_VALUE.to_owned() => aggregate(1, 1),
+            }
+        );
+        assert_eq!(
+            summary.aggregate_by("missing-key", &metadata),
+            btreemap! {
+                UNKNOWN_METADATA_VALUE.to_owned() => aggregate(3, 3),
+            },
+            "tests without metadata fall into the unknown bucket"
+        );
+    }
+
+    #[test]
+    fn known_failures_from_summary() {
+        let summary = shard(
+            6,
+            1,
+            &[
+                ("test_pass", TestSummaryStatus::Pass),
+                ("test_flaky", TestSummaryStatus::Flaky),
+                ("test_xfail", TestSummaryStatus::ExpectedFail),
+                ("test_fail", TestSummaryStatus::Fail),
+                ("test_timeout", TestSummaryStatus::Timeout),
+                ("test_xpass", TestSummaryStatus::UnexpectedPass),
+            ],
+            RunStats::default(),
+        );
+        let known_failures = summary.known_failures();
+        assert_eq!(known_failures.len(), 3);
+
+        let binary_id = RustBinaryId::new("my-crate::tests");
+        for test_name in ["test_fail", "test_timeout", "test_xpass"] {
+            assert!(
+                known_failures.contains(&binary_id, test_name),
+                "{test_name} is a known failure"
+            );
+        }
+        for test_name in ["test_pass", "test_flaky", "test_xfail", "test_missing"] {
+            assert!(
+                !known_failures.contains(&binary_id, test_name),
+                "{test_name} isn't a known failure"
+            );
+        }
+        assert!(
+            !known_failures.contains(&RustBinaryId::new("other-crate"), "test_fail"),
+            "known failures are specific to a binary"
+        );
+    }
+
+    #[test]
+    fn summary_roundtrip() {
+        let summary = shard(
+            5,
+            2,
+            &[
+                ("test_a", TestSummaryStatus::Flaky),
+                ("test_b", TestSummaryStatus::UnexpectedPass),
+            ],
+            RunStats {
+                initial_run_count: 2,
+                finished_count: 2,
+                passed: 1,
+                flaky: 1,
+                failed: 1,
+                unexpected_passes: 1,
+                skipped: 3,
+                ..RunStats::default()
+            },
+        );
+        let json = serde_json::to_string(&summary).expect("summary serialized");
+        let deserialized: RunSummary = serde_json::from_str(&json).expect("summary deserialized");
+        assert_eq!(summary, deserialized);
+    }
+}
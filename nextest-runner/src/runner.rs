@@ -6,28 +6,48 @@
 //! The main structure in this module is [`TestRunner`].
 
 use crate::{
-    config::{NextestProfile, RetryPolicy, TestGroup, TestSettings, TestThreads},
+    config::{
+        CustomTestGroup, FailFast, FlakyConfirmation, NextestProfile, OutcomeSource,
+        PreFlightCommand, RetryPolicy, TestExpectation, TestGroup, TestGroupConfig, TestOrder,
+        TestSettings, TestThreads,
+    },
     double_spawn::DoubleSpawnInfo,
-    errors::{ConfigureHandleInheritanceError, TestRunnerBuildError},
-    list::{TestExecuteContext, TestInstance, TestList},
+    errors::{ConfigureHandleInheritanceError, PreFlightError, TestRunnerBuildError},
+    list::{is_harness_arg, RustTestSuite, TestExecuteContext, TestInstance, TestList},
     reporter::{CancelReason, FinalStatusLevel, StatusLevel, TestEvent, TestOutputDisplay},
-    signal::{JobControlEvent, ShutdownEvent, SignalEvent, SignalHandler, SignalHandlerKind},
+    run_summary::KnownFailures,
+    signal::{
+        JobControlEvent, ShutdownEvent, SignalCount, SignalCounter, SignalEvent, SignalHandler,
+        SignalHandlerKind,
+    },
     target_runner::TargetRunner,
-    time::{StopwatchEnd, StopwatchStart},
+    test_command::SPAWN_LOCK,
+    time::{StopwatchEnd, StopwatchPhases, StopwatchStart},
 };
 use async_scoped::TokioScope;
 use bytes::Bytes;
+use camino::{Utf8Path, Utf8PathBuf};
 use future_queue::StreamExt;
 use futures::{future::try_join, prelude::*};
-use nextest_metadata::{FilterMatch, MismatchReason};
-use rand::{distributions::OpenClosed01, thread_rng, Rng};
+use nextest_metadata::{FilterMatch, MismatchReason, NextestExitCode};
+use rand::{
+    distributions::OpenClosed01, rngs::StdRng, seq::SliceRandom, thread_rng, Rng, SeedableRng,
+};
+use serde::{Deserialize, Serialize};
 use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet},
     convert::Infallible,
+    fmt,
     marker::PhantomData,
     num::NonZeroUsize,
+    panic::AssertUnwindSafe,
     process::Stdio,
-    sync::atomic::{AtomicBool, Ordering},
-    time::{Duration, SystemTime},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime},
 };
 use tokio::{
     io::{AsyncRead, AsyncReadExt},
@@ -42,6 +62,31 @@ struct BackoffIter {
     policy: RetryPolicy,
     current_factor: f64,
     remaining_attempts: usize,
+    last_failure: Option<FailureReason>,
+}
+
+/// Why an attempt failed, as compared by `reset-backoff-on-change`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum FailureReason {
+    ExitCode(i32),
+    Abort(AbortStatus),
+    Other(ExecutionResult),
+}
+
+impl FailureReason {
+    fn new(status: &ExecuteStatus) -> Self {
+        match (status.result, status.exit_code) {
+            (
+                ExecutionResult::Fail {
+                    abort_status: Some(abort_status),
+                    ..
+                },
+                _,
+            ) => Self::Abort(abort_status),
+            (_, Some(exit_code)) => Self::ExitCode(exit_code),
+            (result, None) => Self::Other(result),
+        }
+    }
 }
 
 impl BackoffIter {
@@ -53,6 +98,7 @@ impl BackoffIter {
             policy,
             current_factor: 1.,
             remaining_attempts,
+            last_failure: None,
         }
     }
 
@@ -83,6 +129,23 @@ impl BackoffIter {
         }
     }
 
+    /// Called with each failing attempt that's about to be retried. If the policy resets the
+    /// backoff when the reason for failure changes and this attempt failed for a different reason
+    /// than the previous one, the delay goes back to its initial value.
+    fn record_failure(&mut self, status: &ExecuteStatus) {
+        let reason = FailureReason::new(status);
+        if let RetryPolicy::Exponential {
+            reset_backoff_on_change: true,
+            ..
+        } = self.policy
+        {
+            if matches!(self.last_failure, Some(last) if last != reason) {
+                self.current_factor = 1.;
+            }
+        }
+        self.last_failure = Some(reason);
+    }
+
     fn apply_jitter(duration: Duration) -> Duration {
         let jitter: f64 = thread_rng().sample(OpenClosed01);
         // Apply jitter in the range (0.5, 1].
@@ -106,13 +169,518 @@ impl Iterator for BackoffIter {
     }
 }
 
+/// Tracks how much of the global timeout is left, not counting time spent with the run paused.
+#[derive(Debug)]
+struct GlobalBudget {
+    timeout: Duration,
+    stopwatch: Mutex<StopwatchStart>,
+}
+
+impl GlobalBudget {
+    fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            stopwatch: Mutex::new(crate::time::stopwatch()),
+        }
+    }
+
+    fn remaining(&self) -> Duration {
+        let elapsed = self.stopwatch.lock().unwrap().end().duration;
+        self.timeout.saturating_sub(elapsed)
+    }
+
+    #[cfg(unix)]
+    fn pause(&self) {
+        let mut stopwatch = self.stopwatch.lock().unwrap();
+        if !stopwatch.is_paused() {
+            stopwatch.pause();
+        }
+    }
+
+    #[cfg(unix)]
+    fn resume(&self) {
+        let mut stopwatch = self.stopwatch.lock().unwrap();
+        if stopwatch.is_paused() {
+            stopwatch.resume();
+        }
+    }
+}
+
+/// Assigns each running test attempt to a slot, numbered from 0 up to the number of test threads.
+///
+/// At most as many attempts as there are test threads run at the same time, so there's always a
+/// free slot when an attempt starts.
+#[derive(Debug)]
+struct SlotAssigner {
+    free: Mutex<BTreeSet<usize>>,
+}
+
+impl SlotAssigner {
+    fn new(test_threads: usize) -> Self {
+        Self {
+            free: Mutex::new((0..test_threads).collect()),
+        }
+    }
+
+    /// Takes the lowest-numbered free slot.
+    fn acquire(&self) -> usize {
+        self.free
+            .lock()
+            .unwrap()
+            .pop_first()
+            .expect("fewer attempts are running than there are test threads")
+    }
+
+    fn release(&self, slot: usize) {
+        self.free.lock().unwrap().insert(slot);
+    }
+}
+
+/// Returns true if there's enough time left to retry a test, after waiting for `delay`.
+///
+/// `estimate` is how long the test is expected to take, based on an earlier attempt. If it isn't
+/// known, the test is retried as long as any time remains.
+fn retry_fits_in_budget(remaining: Duration, estimate: Option<Duration>, delay: Duration) -> bool {
+    match estimate {
+        Some(estimate) => remaining >= delay.saturating_add(estimate),
+        None => remaining > delay,
+    }
+}
+
+/// How long a test group must stay unsaturated before [`TestEvent::TestGroupFreed`] is sent.
+///
+/// In a busy group, a finishing test is usually replaced by a new one right away. Without this,
+/// every such replacement would produce a pair of events.
+const GROUP_FREED_COALESCE_WINDOW: Duration = Duration::from_millis(100);
+
+/// Tracks the number of threads in use by each custom test group, to report when groups are
+/// saturated and freed up.
+///
+/// Times are measured from the start of the run.
+#[derive(Debug)]
+struct GroupSaturation {
+    groups: BTreeMap<CustomTestGroup, GroupSaturationState>,
+}
+
+#[derive(Debug)]
+struct GroupSaturationState {
+    max_threads: usize,
+    in_use: usize,
+    // Set once the group has been reported as saturated.
+    saturated_since: Option<Duration>,
+    // Set if the group has been freed up, but this hasn't been reported yet.
+    freed_at: Option<Duration>,
+}
+
+impl GroupSaturation {
+    fn new(groups: impl IntoIterator<Item = (CustomTestGroup, usize)>) -> Self {
+        Self {
+            groups: groups
+                .into_iter()
+                .map(|(group, max_threads)| {
+                    let state = GroupSaturationState {
+                        max_threads,
+                        in_use: 0,
+                        saturated_since: None,
+                        freed_at: None,
+                    };
+                    (group, state)
+                })
+                .collect(),
+        }
+    }
+
+    /// Records that a test in `group` started, returning an event if the group is now saturated.
+    fn test_started<'a>(
+        &mut self,
+        group: &CustomTestGroup,
+        threads_required: usize,
+        now: Duration,
+    ) -> Option<TestEvent<'a>> {
+        let state = self.groups.get_mut(group)?;
+        // Tests that require more threads than the group has are run on their own.
+        state.in_use += threads_required.min(state.max_threads);
+        if state.in_use < state.max_threads {
+            return None;
+        }
+
+        if state.freed_at.take().is_some() {
+            // The group was freed up and saturated again within the coalescing window: it's still
+            // considered to be saturated from the original time.
+            return None;
+        }
+        if state.saturated_since.is_some() {
+            return None;
+        }
+        state.saturated_since = Some(now);
+        Some(TestEvent::TestGroupSaturated {
+            group: group.clone(),
+            max_threads: state.max_threads,
+            run_elapsed: now,
+        })
+    }
+
+    /// Records that a test in `group` finished.
+    ///
+    /// If this frees up the group, [`Self::take_freed`] reports it once the coalescing window has
+    /// passed.
+    fn test_finished(&mut self, group: &CustomTestGroup, threads_required: usize, now: Duration) {
+        let state = match self.groups.get_mut(group) {
+            Some(state) => state,
+            None => return,
+        };
+        state.in_use = state
+            .in_use
+            .saturating_sub(threads_required.min(state.max_threads));
+        if state.in_use < state.max_threads
+            && state.saturated_since.is_some()
+            && state.freed_at.is_none()
+        {
+            state.freed_at = Some(now);
+        }
+    }
+
+    /// Returns the time at which the next freed event is due, if any.
+    fn next_deadline(&self) -> Option<Duration> {
+        self.groups
+            .values()
+            .filter_map(|state| state.freed_at)
+            .min()
+            .map(|freed_at| freed_at + GROUP_FREED_COALESCE_WINDOW)
+    }
+
+    /// Returns events for groups that have been freed up for at least the coalescing window as of
+    /// `now`, or for all freed up groups if `flush` is true.
+    fn take_freed<'a>(&mut self, now: Duration, flush: bool) -> Vec<TestEvent<'a>> {
+        let mut events: Vec<_> = self
+            .groups
+            .iter_mut()
+            .filter_map(|(group, state)| {
+                let freed_at = state.freed_at?;
+                if !flush && now < freed_at + GROUP_FREED_COALESCE_WINDOW {
+                    return None;
+                }
+                state.freed_at = None;
+                let saturated_since = state
+                    .saturated_since
+                    .take()
+                    .expect("freed groups were saturated");
+                Some((
+                    freed_at,
+                    TestEvent::TestGroupFreed {
+                        group: group.clone(),
+                        max_threads: state.max_threads,
+                        run_elapsed: freed_at,
+                        saturated_for: freed_at.saturating_sub(saturated_since),
+                    },
+                ))
+            })
+            .collect();
+        // Report groups in the order they were freed up.
+        events.sort_by_key(|(freed_at, _)| *freed_at);
+        events.into_iter().map(|(_, event)| event).collect()
+    }
+}
+
+/// Runs setup and teardown commands for custom test groups that have them.
+///
+/// The setup command for a group is run just before the first test in the group starts, and the
+/// teardown command once the last test in the group finishes. Groups whose tests never finish (for
+/// example, because the run was canceled) are torn down with [`Self::teardown_remaining`].
+#[derive(Debug)]
+struct GroupScripts<'a> {
+    cwd: &'a Utf8Path,
+    groups: BTreeMap<CustomTestGroup, GroupScriptState<'a>>,
+}
+
+#[derive(Debug)]
+struct GroupScriptState<'a> {
+    config: &'a TestGroupConfig,
+    // The environment produced by the setup command, or an error message if it failed. This is
+    // initialized once the group has been set up.
+    setup_result: tokio::sync::OnceCell<Result<Vec<(String, String)>, String>>,
+    // The number of tests in the group that haven't finished yet, and whether the group has been
+    // torn down.
+    remaining: Mutex<(usize, bool)>,
+}
+
+impl<'a> GroupScripts<'a> {
+    fn new<'b>(
+        profile: &NextestProfile<'a>,
+        test_instances: impl IntoIterator<Item = TestInstance<'b>>,
+    ) -> Self {
+        let mut groups: BTreeMap<_, _> = profile
+            .test_group_config()
+            .iter()
+            .filter(|(_, config)| config.has_scripts())
+            .map(|(group, config)| {
+                let state = GroupScriptState {
+                    config,
+                    setup_result: tokio::sync::OnceCell::new(),
+                    remaining: Mutex::new((0, false)),
+                };
+                (group.clone(), state)
+            })
+            .collect();
+
+        if !groups.is_empty() {
+            for test_instance in test_instances {
+                if !test_instance.test_info.filter_match.is_match() {
+                    continue;
+                }
+                let settings = profile.settings_for(&test_instance.to_test_query());
+                if settings.quarantined() {
+                    continue;
+                }
+                if let TestGroup::Custom(group) = settings.test_group() {
+                    if let Some(state) = groups.get_mut(group) {
+                        state.remaining.get_mut().expect("lock isn't poisoned").0 += 1;
+                    }
+                }
+            }
+        }
+
+        Self {
+            cwd: profile.workspace_root(),
+            groups,
+        }
+    }
+
+    /// Sets up `group` if it hasn't been set up yet, returning the environment that tests in the
+    /// group should be run with, or an error message if the setup command failed.
+    async fn setup(&self, group: Option<&CustomTestGroup>) -> Result<&[(String, String)], &str> {
+        let (group, state) = match group.and_then(|group| self.groups.get_key_value(group)) {
+            Some(entry) => entry,
+            None => return Ok(&[]),
+        };
+
+        let result = state
+            .setup_result
+            .get_or_init(|| async {
+                let command = match &state.config.setup {
+                    Some(command) => command,
+                    None => return Ok(Vec::new()),
+                };
+                log::debug!("running setup command `{command}` for test group `{group}`");
+                let output = run_group_script(command, self.cwd, group, "setup").await?;
+                Ok(parse_setup_env(&output.stdout))
+            })
+            .await;
+        result.as_ref().map(Vec::as_slice).map_err(String::as_str)
+    }
+
+    /// Records that a test in `group` finished, tearing the group down if this was the last one.
+    async fn test_finished(&self, group: Option<&CustomTestGroup>) {
+        let (group, state) = match group.and_then(|group| self.groups.get_key_value(group)) {
+            Some(entry) => entry,
+            None => return,
+        };
+
+        let should_tear_down = {
+            let mut remaining = state.remaining.lock().expect("lock isn't poisoned");
+            remaining.0 = remaining.0.saturating_sub(1);
+            let should_tear_down = remaining.0 == 0 && !remaining.1;
+            remaining.1 |= should_tear_down;
+            should_tear_down
+        };
+        if should_tear_down {
+            self.teardown(group, state).await;
+        }
+    }
+
+    /// Tears down all groups that were set up, but haven't been torn down yet.
+    async fn teardown_remaining(&self) {
+        for (group, state) in &self.groups {
+            let should_tear_down = {
+                let mut remaining = state.remaining.lock().expect("lock isn't poisoned");
+                let should_tear_down = state.setup_result.initialized() && !remaining.1;
+                remaining.1 |= should_tear_down;
+                should_tear_down
+            };
+            if should_tear_down {
+                self.teardown(group, state).await;
+            }
+        }
+    }
+
+    async fn teardown(&self, group: &CustomTestGroup, state: &GroupScriptState<'_>) {
+        // Tear down the group even if setup failed, since setup may have partially completed.
+        if let Some(command) = &state.config.teardown {
+            log::debug!("running teardown command `{command}` for test group `{group}`");
+            if let Err(message) = run_group_script(command, self.cwd, group, "teardown").await {
+                log::warn!("{message}");
+            }
+        }
+    }
+}
+
+/// Runs a setup or teardown command for a test group, returning an error message if it failed.
+async fn run_group_script(
+    command: &PreFlightCommand,
+    cwd: &Utf8Path,
+    group: &CustomTestGroup,
+    kind: &str,
+) -> Result<std::process::Output, String> {
+    let output = spawn_and_collect_output(
+        tokio::process::Command::new(command.program())
+            .args(command.args())
+            .current_dir(cwd)
+            .stdin(Stdio::null()),
+    )
+    .await
+    .map_err(|error| {
+        format!("error spawning {kind} command `{command}` for test group `{group}`: {error}")
+    })?;
+    if output.status.success() {
+        Ok(output)
+    } else {
+        Err(format!(
+            "{kind} command `{command}` for test group `{group}` failed with {}\n\
+             --- stdout:\n{}\n--- stderr:\n{}",
+            output.status,
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr),
+        ))
+    }
+}
+
+/// Like `Command::output`, but spawns the command while holding [`SPAWN_LOCK`], since tests may
+/// be running at the same time.
+async fn spawn_and_collect_output(
+    command: &mut tokio::process::Command,
+) -> std::io::Result<std::process::Output> {
+    command.stdout(Stdio::piped()).stderr(Stdio::piped());
+    let child = {
+        let _guard = SPAWN_LOCK.read().unwrap();
+        command.spawn()?
+    };
+    child.wait_with_output().await
+}
+
+/// Runs the health-check command, returning an error message if it failed.
+async fn run_health_check(command: &PreFlightCommand, cwd: &Utf8Path) -> Result<(), String> {
+    let output = spawn_and_collect_output(
+        tokio::process::Command::new(command.program())
+            .args(command.args())
+            .current_dir(cwd)
+            .stdin(Stdio::null())
+            .kill_on_drop(true),
+    )
+    .await
+    .map_err(|error| format!("error spawning health-check command: {error}"))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "exited with {}\n--- stdout:\n{}\n--- stderr:\n{}",
+            output.status,
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr),
+        ))
+    }
+}
+
+/// Parses lines of the form `KEY=VALUE` in the standard output of a setup command. Other lines are
+/// ignored.
+fn parse_setup_env(stdout: &[u8]) -> Vec<(String, String)> {
+    String::from_utf8_lossy(stdout)
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            let valid_key = !key.is_empty() && !key.contains(char::is_whitespace);
+            valid_key.then(|| (key.to_owned(), value.to_owned()))
+        })
+        .collect()
+}
+
+/// A hook that rewrites the path to a test binary just before it's executed.
+///
+/// This is useful in setups where the binary that should be executed is at a different location
+/// from the one Cargo reports, for example with custom target directories or remote execution
+/// stubs. The resolver is only used to run tests: to change the path used while listing tests,
+/// set [`RustTestArtifact::binary_path`](crate::list::RustTestArtifact::binary_path) instead.
+#[derive(Clone)]
+pub struct BinaryPathResolver {
+    resolve: Arc<dyn Fn(&RustTestSuite<'_>) -> Utf8PathBuf + Send + Sync>,
+}
+
+impl BinaryPathResolver {
+    /// Creates a new resolver from a function that returns the path to execute for a test suite.
+    pub fn new<F>(resolve: F) -> Self
+    where
+        F: Fn(&RustTestSuite<'_>) -> Utf8PathBuf + Send + Sync + 'static,
+    {
+        Self {
+            resolve: Arc::new(resolve),
+        }
+    }
+
+    /// Returns the path to execute for this test suite.
+    pub fn resolve(&self, test_suite: &RustTestSuite<'_>) -> Utf8PathBuf {
+        (self.resolve)(test_suite)
+    }
+}
+
+impl fmt::Debug for BinaryPathResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BinaryPathResolver").finish_non_exhaustive()
+    }
+}
+
+/// Receives notifications as tests start and finish.
+///
+/// This is a typed alternative to matching on the [`TestEvent`]s passed to
+/// [`TestRunner::execute`], for example to collect telemetry. Observers are registered with
+/// [`TestRunnerBuilder::add_observer`], and are called synchronously, before the corresponding
+/// event is passed to the callback.
+///
+/// If an observer panics, the panic is logged and the run continues.
+pub trait TestObserver: Send {
+    /// Called when an attempt of a test is started, including each retry.
+    fn test_started(&mut self, test_instance: TestInstance<'_>, retry_data: RetryData) {
+        let _ = (test_instance, retry_data);
+    }
+
+    /// Called when a test has finished, with the statuses of all its attempts.
+    fn test_finished(&mut self, test_instance: TestInstance<'_>, run_statuses: &ExecutionStatuses) {
+        let _ = (test_instance, run_statuses);
+    }
+}
+
+#[derive(Default)]
+struct TestObservers(Vec<Box<dyn TestObserver>>);
+
+impl TestObservers {
+    fn notify(&mut self, method: &str, mut f: impl FnMut(&mut dyn TestObserver)) {
+        for observer in &mut self.0 {
+            let res = std::panic::catch_unwind(AssertUnwindSafe(|| f(observer.as_mut())));
+            if res.is_err() {
+                log::warn!("test observer panicked in `{method}`, ignoring");
+            }
+        }
+    }
+}
+
+impl fmt::Debug for TestObservers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TestObservers")
+            .field("len", &self.0.len())
+            .finish()
+    }
+}
+
 /// Test runner options.
 #[derive(Debug, Default)]
 pub struct TestRunnerBuilder {
     no_capture: bool,
     retries: Option<RetryPolicy>,
-    fail_fast: Option<bool>,
+    fail_fast: Option<FailFast>,
     test_threads: Option<TestThreads>,
+    shuffle_seed: Option<u64>,
+    test_order: Option<TestOrder>,
+    binary_path_resolver: Option<BinaryPathResolver>,
+    known_failures: KnownFailures,
+    observers: TestObservers,
 }
 
 impl TestRunnerBuilder {
@@ -131,18 +699,84 @@ impl TestRunnerBuilder {
     }
 
     /// Sets the fail-fast value for this test runner.
-    pub fn set_fail_fast(&mut self, fail_fast: bool) -> &mut Self {
+    ///
+    /// A boolean can be converted into a [`FailFast`]: `true` cancels the run on the first
+    /// failure, and `false` never cancels it because of failures.
+    pub fn set_fail_fast(&mut self, fail_fast: FailFast) -> &mut Self {
         self.fail_fast = Some(fail_fast);
         self
     }
 
+    /// Sets tests that are known to fail, for example because they failed in a baseline run.
+    ///
+    /// Failures of these tests don't count towards fail-fast, so the run is only canceled because
+    /// of tests that weren't already failing. A baseline can be obtained from an earlier run with
+    /// [`RunSummary::known_failures`](crate::run_summary::RunSummary::known_failures).
+    pub fn set_known_failures(&mut self, known_failures: KnownFailures) -> &mut Self {
+        self.known_failures = known_failures;
+        self
+    }
+
     /// Sets the number of tests to run simultaneously.
+    ///
+    /// This is still capped by the profile's `global-max-threads`, if set.
     pub fn set_test_threads(&mut self, test_threads: TestThreads) -> &mut Self {
         self.test_threads = Some(test_threads);
         self
     }
 
+    /// Runs tests in a shuffled order, determined by `seed`.
+    ///
+    /// The same seed always results in the same order for a given test list. This takes
+    /// precedence over the test order set through [`Self::set_test_order`] or the profile.
+    pub fn set_shuffle_seed(&mut self, seed: u64) -> &mut Self {
+        self.shuffle_seed = Some(seed);
+        self
+    }
+
+    /// Sets the order in which tests are run, overriding the `test-order` set in the profile.
+    pub fn set_test_order(&mut self, test_order: TestOrder) -> &mut Self {
+        self.test_order = Some(test_order);
+        self
+    }
+
+    /// Sets a hook to rewrite the paths of test binaries before they're executed.
+    pub fn set_binary_path_resolver(&mut self, resolver: BinaryPathResolver) -> &mut Self {
+        self.binary_path_resolver = Some(resolver);
+        self
+    }
+
+    /// Adds an observer that's notified as tests start and finish.
+    ///
+    /// Observers are called in the order they're added.
+    pub fn add_observer(&mut self, observer: impl TestObserver + 'static) -> &mut Self {
+        self.observers.0.push(Box::new(observer));
+        self
+    }
+
     /// Creates a new test runner.
+    ///
+    /// This doesn't invoke Cargo, so it can be used to embed nextest given a [`TestList`] built
+    /// some other way. The minimal inputs are:
+    ///
+    /// * `test_list`, the tests to run. Building one requires [`RustTestArtifact`] instances and
+    ///   an [`EnvironmentMap`], which can be [empty](EnvironmentMap::empty) if Cargo configs
+    ///   shouldn't be read.
+    /// * `profile`, the settings to run tests with. If there's no config file,
+    ///   [`NextestConfig::default_config`] provides the default profile.
+    /// * `target_runner`, which can be [`TargetRunner::empty`] to run test binaries directly.
+    /// * `handler_kind`, which should be [`SignalHandlerKind::Noop`] if the embedding application
+    ///   handles signals itself.
+    /// * `double_spawn`, which can be [`DoubleSpawnInfo::disabled`] since double-spawning requires
+    ///   the current executable to be `cargo-nextest`.
+    ///
+    /// The run is then started with [`TestRunner::execute`], which calls a callback with each
+    /// [`TestEvent`]. Events can be passed to a [`TestReporter`](crate::reporter::TestReporter),
+    /// or handled directly.
+    ///
+    /// [`RustTestArtifact`]: crate::list::RustTestArtifact
+    /// [`EnvironmentMap`]: crate::cargo_config::EnvironmentMap
+    /// [`NextestConfig::default_config`]: crate::config::NextestConfig::default_config
     pub fn build<'a>(
         self,
         test_list: &'a TestList,
@@ -158,13 +792,33 @@ impl TestRunnerBuilder {
                 .unwrap_or_else(|| profile.test_threads())
                 .compute(),
         };
+        // The global cap applies even if the number of test threads was set explicitly. Test
+        // groups, exclusive tests and threads-required all work within the capped number.
+        let test_threads = match profile.global_max_threads() {
+            Some(global_max_threads) => test_threads.min(global_max_threads),
+            None => test_threads,
+        };
         let fail_fast = self.fail_fast.unwrap_or_else(|| profile.fail_fast());
+        let shuffle_seed = self.shuffle_seed.or_else(|| {
+            self.test_order
+                .unwrap_or_else(|| profile.test_order())
+                .resolve_seed()
+        });
+
+        for arg in profile.test_args() {
+            if is_harness_arg(arg) {
+                log::warn!(
+                    "test-args contains `{arg}`, which conflicts with the arguments nextest \
+                     passes to test binaries"
+                );
+            }
+        }
 
         let runtime = Runtime::new().map_err(TestRunnerBuildError::TokioRuntimeCreate)?;
         let _guard = runtime.enter();
 
         // This must be called from within the guard.
-        let handler = handler_kind.build()?;
+        let handler = handler_kind.build();
 
         Ok(TestRunner {
             inner: TestRunnerInner {
@@ -173,13 +827,18 @@ impl TestRunnerBuilder {
                 test_threads,
                 force_retries: self.retries,
                 fail_fast,
+                known_failures: self.known_failures,
+                shuffle_seed,
                 test_list,
+                binary_path_resolver: self.binary_path_resolver,
                 double_spawn,
                 target_runner,
                 runtime,
                 run_id: Uuid::new_v4(),
+                leaked_processes: Mutex::new(Vec::new()),
             },
             handler,
+            observers: self.observers,
         })
     }
 }
@@ -191,6 +850,7 @@ impl TestRunnerBuilder {
 pub struct TestRunner<'a> {
     inner: TestRunnerInner<'a>,
     handler: SignalHandler,
+    observers: TestObservers,
 }
 
 impl<'a> TestRunner<'a> {
@@ -208,6 +868,37 @@ impl<'a> TestRunner<'a> {
         .expect("Err branch is infallible")
     }
 
+    /// Runs the pre-flight command configured for this profile, if any, in `cwd`.
+    ///
+    /// This should be called once before executing tests. The output of the command is captured,
+    /// and returned as part of the error if the command fails.
+    pub fn run_pre_flight(&self, cwd: &Utf8Path) -> Result<(), PreFlightError> {
+        let pre_flight = match self.inner.profile.pre_flight() {
+            Some(pre_flight) => pre_flight,
+            None => return Ok(()),
+        };
+
+        let output = std::process::Command::new(pre_flight.program())
+            .args(pre_flight.args())
+            .current_dir(cwd)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|error| PreFlightError::Spawn {
+                command: pre_flight.to_string(),
+                error,
+            })?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(PreFlightError::Failed {
+                command: pre_flight.to_string(),
+                exit_status: output.status,
+                stdout: output.stdout,
+                stderr: output.stderr,
+            })
+        }
+    }
+
     /// Executes the listed tests, each one in its own process.
     ///
     /// Accepts a callback that is called with the results of each test. If the callback returns an
@@ -217,7 +908,10 @@ impl<'a> TestRunner<'a> {
         F: FnMut(TestEvent<'a>) -> Result<(), E> + Send,
         E: Send,
     {
-        let run_stats = self.inner.try_execute(&mut self.handler, callback);
+        let observers = std::mem::take(&mut self.observers);
+        let run_stats = self
+            .inner
+            .try_execute(&mut self.handler, observers, callback);
         // On Windows, the stdout and stderr futures might spawn processes that keep the runner
         // stuck indefinitely if it's dropped the normal way. Shut it down aggressively, being OK
         // with leaked resources.
@@ -233,18 +927,24 @@ struct TestRunnerInner<'a> {
     test_threads: usize,
     // This is Some if the user specifies a retry policy over the command-line.
     force_retries: Option<RetryPolicy>,
-    fail_fast: bool,
+    fail_fast: FailFast,
+    known_failures: KnownFailures,
+    shuffle_seed: Option<u64>,
     test_list: &'a TestList<'a>,
+    binary_path_resolver: Option<BinaryPathResolver>,
     double_spawn: DoubleSpawnInfo,
     target_runner: TargetRunner,
     runtime: Runtime,
     run_id: Uuid,
+    // Processes that were still running when the tests that started them finished.
+    leaked_processes: Mutex<Vec<LeakedProcess<'a>>>,
 }
 
 impl<'a> TestRunnerInner<'a> {
     fn try_execute<E, F>(
         &self,
         signal_handler: &mut SignalHandler,
+        observers: TestObservers,
         callback: F,
     ) -> Result<RunStats, E>
     where
@@ -259,37 +959,71 @@ impl<'a> TestRunnerInner<'a> {
         let canceled = AtomicBool::new(false);
         let canceled_ref = &canceled;
 
+        // Retries that wouldn't finish before the global timeout are skipped.
+        let global_budget = self.profile.global_timeout().map(GlobalBudget::new);
+        let global_budget_ref = &global_budget;
+
+        let group_scripts = GroupScripts::new(&self.profile, self.test_list.iter_tests());
+        let group_scripts_ref = &group_scripts;
+
+        let slots = SlotAssigner::new(self.test_threads);
+        let slots_ref = &slots;
+
+        // Tests wait on this lock before starting, and the write side is held while a health check
+        // is running so that no new tests are started until it has passed.
+        let health_check = self.profile.health_check();
+        let health_gate = Arc::new(tokio::sync::RwLock::new(()));
+        let health_gate_ref = &health_gate;
+        // Similarly, the write side of this lock is held while the run is paused with SIGUSR2.
+        let pause_gate = Arc::new(tokio::sync::RwLock::new(()));
+        let pause_gate_ref = &pause_gate;
+        let health_check_finished = AtomicUsize::new(0);
+        let health_check_finished_ref = &health_check_finished;
+        let run_count = self.test_list.run_count();
+
         let mut ctx = CallbackContext::new(
             callback,
+            observers,
             self.run_id,
             self.test_list.run_count(),
             self.fail_fast,
+            GroupSaturation::new(
+                self.profile
+                    .test_group_config()
+                    .iter()
+                    .map(|(group, config)| (group.clone(), config.max_threads.compute())),
+            ),
+            self.profile.slow_test_report_count(),
+            self.known_failures.clone(),
         );
 
         // Send the initial event.
         // (Don't need to set the canceled atomic if this fails because the run hasn't started
         // yet.)
-        ctx.run_started(self.test_list)?;
+        ctx.run_started(self.test_list, self.shuffle_seed)?;
 
         // Stores the first error that occurred. This error is propagated up.
         let mut first_error = None;
 
         let ctx_mut = &mut ctx;
         let first_error_mut = &mut first_error;
+        let signal_handler_mut = &mut *signal_handler;
 
         let _guard = self.runtime.enter();
 
         // Messages sent over this channel include:
         // - SIGSTOP/SIGCONT
+        // - SIGUSR1
         // - Shutdown signals (once)
         // - Signals twice
         // 32 should be more than enough.
         let (forward_sender, _forward_receiver) =
-            tokio::sync::broadcast::channel::<SignalForwardEvent>(32);
+            tokio::sync::broadcast::channel::<SignalForwardEvent<'a>>(32);
         let forward_sender_ref = &forward_sender;
 
         TokioScope::scope_and_block(move |scope| {
             let (run_sender, mut run_receiver) = tokio::sync::mpsc::unbounded_channel();
+            let (health_sender, mut health_receiver) = tokio::sync::mpsc::unbounded_channel();
             let (cancellation_sender, _cancellation_receiver) = tokio::sync::broadcast::channel(1);
             {
                 let cancellation_sender = cancellation_sender.clone();
@@ -301,21 +1035,54 @@ impl<'a> TestRunnerInner<'a> {
                     .iter()
                     .map(|(group_name, config)| (group_name, config.max_threads.compute()));
 
-                let run_fut = futures::stream::iter(self.test_list.iter_tests())
+                let mut test_instances: Vec<_> = self.test_list.iter_tests().collect();
+                if let Some(seed) = self.shuffle_seed {
+                    test_instances.shuffle(&mut StdRng::seed_from_u64(seed));
+                }
+                // Start higher-priority tests first. The sort is stable, so tests with the same
+                // priority stay in binary and test name order (or in shuffled order).
+                test_instances.sort_by_cached_key(|test_instance| {
+                    Reverse(
+                        self.profile
+                            .settings_for(&test_instance.to_test_query())
+                            .priority(),
+                    )
+                });
+
+                let run_fut = futures::stream::iter(test_instances)
                     .map(move |test_instance| {
                         let this_run_sender = run_sender.clone();
+                        let this_health_sender = health_sender.clone();
                         let mut cancellation_receiver = cancellation_sender.subscribe();
 
                         let query = test_instance.to_test_query();
                         let settings = self.profile.settings_for(&query);
-                        let threads_required =
-                            settings.threads_required().compute(self.test_threads);
+                        // Exclusive tests take up all the test threads, so that they're only
+                        // started once all other running tests have finished, and no other tests
+                        // are started until they're done. Tests whose output isn't captured are
+                        // run the same way, so that their output isn't interleaved with that of
+                        // other tests.
+                        let threads_required = if settings.exclusive() || !settings.capture() {
+                            self.test_threads
+                        } else {
+                            settings.threads_required().compute(self.test_threads)
+                        };
                         let test_group = match settings.test_group() {
                             TestGroup::Global => None,
                             TestGroup::Custom(name) => Some(name.clone()),
                         };
+                        // Used to track test group saturation.
+                        let event_test_group = test_group.clone();
+                        // Used to run setup and teardown commands for the group.
+                        let script_test_group = test_group.clone();
 
                         let fut = async move {
+                            // Wait for any running health check to finish. (This doesn't need to
+                            // hold on to the lock, just to see that it's free.)
+                            drop(health_gate_ref.read().await);
+                            // Also wait for the run to be resumed, if it's paused.
+                            drop(pause_gate_ref.read().await);
+
                             // Subscribe to the receiver *before* checking canceled_ref. The ordering is
                             // important to avoid race conditions with the code that first sets
                             // canceled_ref and then sends the notification.
@@ -342,9 +1109,28 @@ impl<'a> TestRunnerInner<'a> {
                                 return;
                             }
 
+                            if settings.quarantined() {
+                                // Failure to send means the receiver was dropped.
+                                let _ = this_run_sender.send(InternalTestEvent::Skipped {
+                                    test_instance,
+                                    reason: MismatchReason::Quarantined,
+                                });
+                                return;
+                            }
+
                             // Failure to send means the receiver was dropped.
                             let _ =
-                                this_run_sender.send(InternalTestEvent::Started { test_instance });
+                                this_run_sender.send(InternalTestEvent::Started {
+                                    test_instance,
+                                    retry_data: RetryData {
+                                        attempt: 1,
+                                        total_attempts,
+                                    },
+                                    test_group: event_test_group.clone(),
+                                    threads_required,
+                                });
+
+                            let group_env = group_scripts_ref.setup(script_test_group.as_ref()).await;
 
                             let mut run_statuses = vec![];
                             let mut delay = Duration::ZERO;
@@ -366,30 +1152,58 @@ impl<'a> TestRunnerInner<'a> {
                                     });
                                 }
 
+                                let slot = slots_ref.acquire();
                                 let run_status = self
                                     .run_test(
                                         test_instance,
                                         retry_data,
                                         &settings,
+                                        group_env,
                                         &this_run_sender,
                                         &mut this_forward_receiver,
                                         delay,
                                     )
                                     .await
-                                    .into_external(retry_data);
+                                    .into_external(retry_data, slot);
+                                slots_ref.release(slot);
 
                                 if run_status.result.is_success() {
                                     // The test succeeded.
                                     run_statuses.push(run_status);
                                     break;
                                 } else if retry_data.attempt < retry_data.total_attempts
+                                    && run_status.is_retryable(settings.retry_on_exit_codes())
                                     && !canceled_ref.load(Ordering::Acquire)
                                 {
                                     // Retry this test: send a retry event, then retry the loop.
+                                    backoff_iter.record_failure(&run_status);
                                     delay = backoff_iter
                                         .next()
                                         .expect("backoff delay must be non-empty");
 
+                                    if let Some(budget) = global_budget_ref {
+                                        // Estimate how long the retry will take from the first
+                                        // attempt that actually ran.
+                                        let estimate = run_statuses
+                                            .iter()
+                                            .chain(std::iter::once(&run_status))
+                                            .find(|status| {
+                                                status.result != ExecutionResult::ExecFail
+                                            })
+                                            .map(|status| status.time_taken);
+                                        let remaining = budget.remaining();
+                                        if !retry_fits_in_budget(remaining, estimate, delay) {
+                                            log::debug!(
+                                                "not retrying {} {}: {remaining:?} left before \
+                                                 the global timeout, estimated to need {estimate:?}",
+                                                test_instance.suite_info.binary_id,
+                                                test_instance.name,
+                                            );
+                                            run_statuses.push(run_status);
+                                            break;
+                                        }
+                                    }
+
                                     let _ = this_run_sender.send(
                                         InternalTestEvent::AttemptFailedWillRetry {
                                             test_instance,
@@ -400,13 +1214,17 @@ impl<'a> TestRunnerInner<'a> {
                                     );
                                     run_statuses.push(run_status);
 
-                                    tokio::select! {
-                                        _ = tokio::time::sleep(delay) => {}
-                                        // Cancel the sleep if the run is cancelled.
-                                        _ = cancellation_receiver.recv() => {
-                                            // Don't need to do anything special for this because
-                                            // cancellation_receiver gets a message after
-                                            // canceled_ref is set.
+                                    // A zero delay means the test is retried immediately, without
+                                    // yielding to the runtime.
+                                    if !delay.is_zero() {
+                                        tokio::select! {
+                                            _ = tokio::time::sleep(delay) => {}
+                                            // Cancel the sleep if the run is cancelled.
+                                            _ = cancellation_receiver.recv() => {
+                                                // Don't need to do anything special for this because
+                                                // cancellation_receiver gets a message after
+                                                // canceled_ref is set.
+                                            }
                                         }
                                     }
                                 } else {
@@ -422,6 +1240,8 @@ impl<'a> TestRunnerInner<'a> {
                             // In either case, the test is finished.
                             let _ = this_run_sender.send(InternalTestEvent::Finished {
                                 test_instance,
+                                test_group: event_test_group,
+                                threads_required,
                                 success_output: settings.success_output(),
                                 failure_output: settings.failure_output(),
                                 junit_store_success_output: settings.junit_store_success_output(),
@@ -429,6 +1249,33 @@ impl<'a> TestRunnerInner<'a> {
                                 run_statuses: ExecutionStatuses::new(run_statuses),
                             });
 
+                            group_scripts_ref.test_finished(script_test_group.as_ref()).await;
+
+                            if let Some(health_check) = health_check {
+                                let finished =
+                                    health_check_finished_ref.fetch_add(1, Ordering::AcqRel) + 1;
+                                // Don't bother running the health check after the last test.
+                                if finished % health_check.every.get() == 0
+                                    && finished < run_count
+                                    && !canceled_ref.load(Ordering::Acquire)
+                                {
+                                    // Take the write lock before this test's slot is freed up, so
+                                    // the next test doesn't start until the health check has
+                                    // passed. The lock is released once the result has been
+                                    // handled, which includes canceling the run if it failed.
+                                    let guard = Arc::clone(health_gate_ref).write_owned().await;
+                                    let command = health_check.command.clone();
+                                    let cwd = self.profile.workspace_root().to_owned();
+                                    // Run the health check separately, so that it doesn't take up
+                                    // a test slot.
+                                    tokio::spawn(async move {
+                                        let result = run_health_check(&command, &cwd).await;
+                                        // Failure to send means the receiver was dropped.
+                                        let _ = this_health_sender.send((command, result, guard));
+                                    });
+                                }
+                            }
+
                             // Drain the forward receiver of any messages, including those that are
                             // related to SIGTSTP.
                             loop {
@@ -461,8 +1308,22 @@ impl<'a> TestRunnerInner<'a> {
             }
             let exec_fut = async move {
                 let mut signals_done = false;
+                let mut health_checks_done = false;
+                // Held while a failed health check is being handled.
+                let mut health_gate_guard = None;
+                // Held while the run is paused.
+                let mut pause_gate_guard = None;
+
+                // Use a pausable_sleep so that time spent with the run paused doesn't count
+                // towards the global timeout.
+                let global_timeout = self.profile.global_timeout();
+                let global_sleep =
+                    crate::time::pausable_sleep(global_timeout.unwrap_or(Duration::ZERO));
+                tokio::pin!(global_sleep);
+                let mut global_timeout_done = global_timeout.is_none();
 
                 loop {
+                    let group_freed_timeout = ctx_mut.group_freed_timeout();
                     let internal_event = tokio::select! {
                         internal_event = run_receiver.recv() => {
                             match internal_event {
@@ -473,8 +1334,44 @@ impl<'a> TestRunnerInner<'a> {
                                 }
                             }
                         },
-                        internal_event = signal_handler.recv(), if !signals_done => {
+                        internal_event = signal_handler_mut.recv(), if !signals_done => {
                             match internal_event {
+                                #[cfg(unix)]
+                                Some(SignalEvent::Info) => {
+                                    // There are test_threads or fewer tests running so this buffer
+                                    // is big enough.
+                                    let (sender, mut receiver) =
+                                        tokio::sync::mpsc::channel(self.test_threads);
+                                    let mut running_tests = forward_sender_ref
+                                        .send(SignalForwardEvent::Info(sender))
+                                        .expect(
+                                        "at least one receiver stays open so this should never error out",
+                                    );
+                                    // One event to account for the receiver held open at the top.
+                                    running_tests -= 1;
+
+                                    // As with stopping tests, a test might exit before it gets to
+                                    // respond, so don't wait more than 100ms for responses.
+                                    let sleep = tokio::time::sleep(Duration::from_millis(100));
+                                    tokio::pin!(sleep);
+
+                                    let mut tests = Vec::with_capacity(running_tests);
+                                    while running_tests > 0 {
+                                        tokio::select! {
+                                            recv = receiver.recv() => {
+                                                match recv {
+                                                    Some(test) => {
+                                                        tests.push(test);
+                                                        running_tests -= 1;
+                                                    }
+                                                    None => break,
+                                                }
+                                            }
+                                            _ = &mut sleep => break,
+                                        }
+                                    }
+                                    InternalEvent::RunningSnapshot(tests)
+                                }
                                 Some(event) => InternalEvent::Signal(event),
                                 None => {
                                     signals_done = true;
@@ -482,6 +1379,35 @@ impl<'a> TestRunnerInner<'a> {
                                 }
                             }
                         },
+                        health_check = health_receiver.recv(), if !health_checks_done => {
+                            match health_check {
+                                Some((_, Ok(()), _guard)) => {
+                                    // The health check passed, so tests can resume.
+                                    continue;
+                                }
+                                Some((command, Err(message), guard)) => {
+                                    health_gate_guard = Some(guard);
+                                    InternalEvent::HealthCheckFailed {
+                                        command: command.to_string(),
+                                        message,
+                                    }
+                                }
+                                None => {
+                                    health_checks_done = true;
+                                    continue;
+                                }
+                            }
+                        },
+                        _ = &mut global_sleep, if !global_timeout_done => {
+                            global_timeout_done = true;
+                            InternalEvent::GlobalTimeout(
+                                global_timeout.expect("global timeout is set if the sleep is polled"),
+                            )
+                        },
+                        _ = tokio::time::sleep(group_freed_timeout.unwrap_or_default()),
+                            if group_freed_timeout.is_some() => {
+                            InternalEvent::GroupFreedTimeout
+                        },
                     };
 
                     match ctx_mut.handle_event(internal_event) {
@@ -522,13 +1448,26 @@ impl<'a> TestRunnerInner<'a> {
                                 };
                             }
 
-                            // Now stop nextest itself.
+                            if !global_sleep.is_paused() {
+                                global_sleep.as_mut().pause();
+                            }
+                            if let Some(budget) = global_budget_ref {
+                                budget.pause();
+                            }
+
+                            // Now stop nextest itself.
                             imp::raise_stop();
                         }
                         #[cfg(unix)]
                         Ok(Some(JobControlEvent::Continue)) => {
                             // Nextest has been resumed. Resume all the tests as well.
                             let _ = forward_sender_ref.send(SignalForwardEvent::Continue);
+                            if global_sleep.is_paused() {
+                                global_sleep.as_mut().resume();
+                            }
+                            if let Some(budget) = global_budget_ref {
+                                budget.resume();
+                            }
                         }
                         #[cfg(not(unix))]
                         Ok(Some(_)) => {
@@ -552,8 +1491,11 @@ impl<'a> TestRunnerInner<'a> {
                                     }
                                     let _ = ctx_mut.begin_cancel(CancelReason::ReportError);
                                 }
-                                InternalError::TestFailureCanceled(err) => {
-                                    // A test failure has caused cancellation to begin.
+                                InternalError::TestFailureCanceled(err)
+                                | InternalError::GlobalTimeoutCanceled(err)
+                                | InternalError::HealthCheckCanceled(err) => {
+                                    // A test failure, the global timeout or a failed health check
+                                    // has caused cancellation to begin.
                                     if first_error_mut.is_none() {
                                         *first_error_mut = err;
                                     }
@@ -575,6 +1517,22 @@ impl<'a> TestRunnerInner<'a> {
                             }
                         }
                     }
+
+                    // If a health check failed, the run has now been canceled, so tests waiting
+                    // for it can go ahead (and skip themselves).
+                    drop(health_gate_guard.take());
+
+                    // Sync up the pause gate with the pause state. Tests only hold the read side
+                    // of the lock for an instant, so this doesn't block the loop, and finishing
+                    // tests continue to be handled while the run is paused. Cancellation resumes
+                    // the run, so tests that are waiting can skip themselves.
+                    if ctx_mut.is_paused() {
+                        if pause_gate_guard.is_none() {
+                            pause_gate_guard = Some(Arc::clone(pause_gate_ref).write_owned().await);
+                        }
+                    } else {
+                        drop(pause_gate_guard.take());
+                    }
                 }
             };
 
@@ -582,6 +1540,34 @@ impl<'a> TestRunnerInner<'a> {
             scope.spawn_cancellable(exec_fut, || ());
         });
 
+        // Groups with tests that never finished, for example because the run was canceled, still
+        // need to be torn down.
+        self.runtime.block_on(group_scripts.teardown_remaining());
+
+        // All tests have finished, so any groups that are still pending are freed up.
+        if let Err(err) = ctx.report_groups_freed(true) {
+            if first_error.is_none() {
+                first_error = Some(err);
+            }
+        }
+
+        // Re-run flaky tests to measure how often they fail, unless the run was canceled.
+        if let Some(confirmation) = self.profile.flaky_confirmation() {
+            if first_error.is_none() && ctx.cancel_state.is_none() && !ctx.flaky_tests.is_empty() {
+                if let Err(err) =
+                    self.confirm_flaky(&mut ctx, confirmation, signal_handler, &forward_sender)
+                {
+                    first_error = Some(err);
+                }
+            }
+        }
+
+        if let Err(err) = self.report_leaked_processes(&mut ctx) {
+            if first_error.is_none() {
+                first_error = Some(err);
+            }
+        }
+
         match ctx.run_finished() {
             Ok(()) => {}
             Err(err) => {
@@ -601,14 +1587,201 @@ impl<'a> TestRunnerInner<'a> {
     // Helper methods
     // ---
 
+    /// Reports processes started by tests that are still running, if `detect-leaked-processes` is
+    /// enabled.
+    fn report_leaked_processes<F, E>(&self, ctx: &mut CallbackContext<'a, F, E>) -> Result<(), E>
+    where
+        F: FnMut(TestEvent<'a>) -> Result<(), E> + Send,
+    {
+        let config = self.profile.leaked_processes();
+        if !config.enabled {
+            return Ok(());
+        }
+
+        let processes = std::mem::take(&mut *self.leaked_processes.lock().unwrap());
+        let processes: Vec<_> = processes
+            .into_iter()
+            .filter(|process| !config.is_ignored(&process.command))
+            .collect();
+        if processes.is_empty() {
+            return Ok(());
+        }
+        (ctx.callback)(TestEvent::RunLeakedProcesses { processes })
+    }
+
+    /// Runs each flaky test in isolation `confirmation.runs` times, reporting how often it failed.
+    ///
+    /// Tests are run one at a time. No further runs are started once the confirmation timeout has
+    /// elapsed or a shutdown signal has been received.
+    fn confirm_flaky<F, E>(
+        &self,
+        ctx: &mut CallbackContext<'a, F, E>,
+        confirmation: FlakyConfirmation,
+        signal_handler: &mut SignalHandler,
+        forward_sender: &tokio::sync::broadcast::Sender<SignalForwardEvent<'a>>,
+    ) -> Result<(), E>
+    where
+        F: FnMut(TestEvent<'a>) -> Result<(), E> + Send,
+    {
+        // Test groups have already been torn down at this point, so tests that rely on a group's
+        // setup command can't be run again.
+        let mut flaky_tests = std::mem::take(&mut ctx.flaky_tests);
+        flaky_tests.retain(|test_instance| {
+            match self
+                .profile
+                .settings_for(&test_instance.to_test_query())
+                .test_group()
+            {
+                TestGroup::Custom(group) => self
+                    .profile
+                    .test_group_config()
+                    .get(group)
+                    .map_or(true, |config| config.setup.is_none()),
+                TestGroup::Global => true,
+            }
+        });
+        if flaky_tests.is_empty() {
+            return Ok(());
+        }
+        let total_runs = confirmation.runs.get();
+        (ctx.callback)(TestEvent::FlakyConfirmationStarted {
+            test_count: flaky_tests.len(),
+            runs: total_runs,
+        })?;
+
+        // Events sent by individual runs (e.g. slow test notifications) aren't reported.
+        let (run_sender, _run_receiver) = tokio::sync::mpsc::unbounded_channel();
+        let mut forward_receiver = forward_sender.subscribe();
+
+        self.runtime.block_on(async {
+            // The confirmation budget is paused along with the run on job control, like the global
+            // timeout.
+            let budget = crate::time::pausable_sleep(confirmation.timeout);
+            tokio::pin!(budget);
+            let mut budget_elapsed = false;
+            let mut signals_done = false;
+            let mut shutdown = false;
+
+            for test_instance in flaky_tests {
+                let settings = self.profile.settings_for(&test_instance.to_test_query());
+                let mut runs = 0;
+                let mut failures = 0;
+
+                while runs < total_runs && !shutdown {
+                    if budget_elapsed || budget.as_mut().now_or_never().is_some() {
+                        budget_elapsed = true;
+                        break;
+                    }
+
+                    let retry_data = RetryData {
+                        attempt: runs + 1,
+                        total_attempts: total_runs,
+                    };
+                    let run_fut = self.run_test(
+                        test_instance,
+                        retry_data,
+                        &settings,
+                        Ok(&[]),
+                        &run_sender,
+                        &mut forward_receiver,
+                        Duration::ZERO,
+                    );
+                    tokio::pin!(run_fut);
+
+                    let run_status = loop {
+                        tokio::select! {
+                            run_status = &mut run_fut => break run_status,
+                            _ = &mut budget, if !budget_elapsed => {
+                                // The budget ran out partway through this run: kill the test, and
+                                // wait for it to exit before moving on.
+                                budget_elapsed = true;
+                                let _ = forward_sender.send(SignalForwardEvent::Shutdown(
+                                    ShutdownForwardEvent::Twice,
+                                ));
+                            }
+                            internal_event = signal_handler.recv(), if !signals_done => {
+                                match internal_event {
+                                    Some(SignalEvent::Shutdown(event)) => {
+                                        // Pass the signal on to the running test, and don't start
+                                        // any further runs.
+                                        shutdown = true;
+                                        let forward_event =
+                                            ctx.record_signal(event).to_forward_event(event);
+                                        let _ = forward_sender
+                                            .send(SignalForwardEvent::Shutdown(forward_event));
+                                    }
+                                    #[cfg(unix)]
+                                    Some(SignalEvent::JobControl(JobControlEvent::Stop)) => {
+                                        // Debounce stop signals.
+                                        if !budget.is_paused() {
+                                            // Stop the running test, then nextest itself. As in
+                                            // the main loop, don't wait more than 100ms for the
+                                            // test to stop, since it may be exiting.
+                                            let (sender, mut receiver) =
+                                                tokio::sync::mpsc::channel(1);
+                                            let _ = forward_sender
+                                                .send(SignalForwardEvent::Stop(sender));
+                                            let _ = tokio::time::timeout(
+                                                Duration::from_millis(100),
+                                                receiver.recv(),
+                                            )
+                                            .await;
+                                            budget.as_mut().pause();
+                                            imp::raise_stop();
+                                        }
+                                    }
+                                    #[cfg(unix)]
+                                    Some(SignalEvent::JobControl(JobControlEvent::Continue)) => {
+                                        // Debounce continue signals.
+                                        if budget.is_paused() {
+                                            let _ =
+                                                forward_sender.send(SignalForwardEvent::Continue);
+                                            budget.as_mut().resume();
+                                        }
+                                    }
+                                    #[cfg(unix)]
+                                    Some(SignalEvent::Info | SignalEvent::TogglePause) => {
+                                        // Snapshots and pausing aren't supported during
+                                        // confirmation runs.
+                                    }
+                                    None => signals_done = true,
+                                }
+                            }
+                        }
+                    };
+
+                    if budget_elapsed && !run_status.result.is_success() {
+                        // This run was cut short, so it doesn't say anything about the test.
+                        break;
+                    }
+
+                    runs += 1;
+                    if !run_status.result.is_success() {
+                        failures += 1;
+                    }
+                }
+
+                (ctx.callback)(TestEvent::FlakyConfirmationFinished {
+                    test_instance,
+                    runs,
+                    failures,
+                })?;
+            }
+
+            Ok(())
+        })
+    }
+
     /// Run an individual test in its own process.
+    #[allow(clippy::too_many_arguments)]
     async fn run_test(
         &self,
         test: TestInstance<'a>,
         retry_data: RetryData,
         settings: &TestSettings,
+        group_env: Result<&[(String, String)], &str>,
         run_sender: &UnboundedSender<InternalTestEvent<'a>>,
-        forward_receiver: &mut tokio::sync::broadcast::Receiver<SignalForwardEvent>,
+        forward_receiver: &mut tokio::sync::broadcast::Receiver<SignalForwardEvent<'a>>,
         delay_before_start: Duration,
     ) -> InternalExecuteStatus {
         let mut stopwatch = crate::time::stopwatch();
@@ -619,6 +1792,7 @@ impl<'a> TestRunnerInner<'a> {
                 retry_data,
                 &mut stopwatch,
                 settings,
+                group_env,
                 run_sender,
                 forward_receiver,
                 delay_before_start,
@@ -626,18 +1800,67 @@ impl<'a> TestRunnerInner<'a> {
             .await
         {
             Ok(run_status) => run_status,
-            Err(_) => InternalExecuteStatus {
-                // TODO: can we return more information in stdout/stderr? investigate this
+            Err(error) => InternalExecuteStatus {
+                // Report the error through stderr so that it's shown along with the failure.
                 stdout: Bytes::new(),
-                stderr: Bytes::new(),
+                stderr: Bytes::from(format!("error running test: {error}\n")),
                 result: ExecutionResult::ExecFail,
+                exit_code: None,
                 stopwatch_end: stopwatch.end(),
+                // Phases aren't tracked if the test couldn't be run.
+                phases: None,
                 is_slow: false,
+                no_assertions: false,
+                trace_file: None,
                 delay_before_start,
             },
         }
     }
 
+    /// Returns the path that the tracer for this attempt of `test` writes its trace to, creating
+    /// its parent directory and removing any trace left over from an earlier run.
+    fn prepare_trace_file(
+        &self,
+        test: TestInstance<'a>,
+        retry_data: RetryData,
+    ) -> std::io::Result<Utf8PathBuf> {
+        // Binary IDs and test names can contain characters like `/` and `:`, which aren't valid in
+        // file names on all platforms.
+        let file_name: String = format!(
+            "{}-{}-{}.trace",
+            test.suite_info.binary_id, test.name, retry_data.attempt
+        )
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+        let trace_dir = self.profile.store_dir().join("traces");
+        std::fs::create_dir_all(&trace_dir).map_err(|error| {
+            std::io::Error::new(
+                error.kind(),
+                format!("failed to create trace directory `{trace_dir}`: {error}"),
+            )
+        })?;
+
+        let trace_file = trace_dir.join(file_name);
+        match std::fs::remove_file(&trace_file) {
+            Ok(()) => {}
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+            Err(error) => {
+                return Err(std::io::Error::new(
+                    error.kind(),
+                    format!("failed to remove old trace file `{trace_file}`: {error}"),
+                ));
+            }
+        }
+        Ok(trace_file)
+    }
+
     #[allow(clippy::too_many_arguments)]
     async fn run_test_inner(
         &self,
@@ -645,40 +1868,113 @@ impl<'a> TestRunnerInner<'a> {
         retry_data: RetryData,
         stopwatch: &mut StopwatchStart,
         settings: &TestSettings,
+        group_env: Result<&[(String, String)], &str>,
         run_sender: &UnboundedSender<InternalTestEvent<'a>>,
-        forward_receiver: &mut tokio::sync::broadcast::Receiver<SignalForwardEvent>,
+        forward_receiver: &mut tokio::sync::broadcast::Receiver<SignalForwardEvent<'a>>,
         delay_before_start: Duration,
     ) -> std::io::Result<InternalExecuteStatus> {
+        // If the test group's setup command failed, the test can't be run.
+        let group_env =
+            group_env.map_err(|message| std::io::Error::new(std::io::ErrorKind::Other, message))?;
+
         let ctx = TestExecuteContext {
             double_spawn: &self.double_spawn,
             target_runner: &self.target_runner,
         };
-        let mut cmd = test.make_command(&ctx, self.test_list);
+        let binary_path = match &self.binary_path_resolver {
+            Some(resolver) => resolver.resolve(test.suite_info),
+            None => test.suite_info.binary_path.clone(),
+        };
+        let (trace_file, tracer_args) = match settings.tracer() {
+            Some(tracer) => {
+                let trace_file = self.prepare_trace_file(test, retry_data)?;
+                let tracer_args = tracer.args_for(&trace_file);
+                (Some(trace_file), tracer_args)
+            }
+            None => (None, Vec::new()),
+        };
+        let mut cmd = test.make_command(
+            &ctx,
+            self.test_list,
+            &binary_path,
+            self.profile.test_args(),
+            settings
+                .tracer()
+                .map(|tracer| (tracer.program(), tracer_args.as_slice())),
+        );
         let command_mut = cmd.command_mut();
 
+        if let Some(cwd) = settings.cwd() {
+            // Fail the test rather than silently running it in the default directory.
+            if !cwd.is_dir() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("working directory `{cwd}` set through an override is not a directory"),
+                ));
+            }
+            command_mut.current_dir(cwd);
+        }
+
         // Debug environment variable for testing.
         command_mut.env("__NEXTEST_ATTEMPT", format!("{}", retry_data.attempt));
         command_mut.env("NEXTEST_RUN_ID", format!("{}", self.run_id));
-        command_mut.stdin(Stdio::null());
+        command_mut.envs(settings.canonical_env().vars());
+        command_mut.envs(group_env.iter().map(|(key, value)| (key, value)));
+        command_mut.stdin(settings.stdin().to_stdio());
         imp::set_process_group(command_mut);
 
         // If creating a job fails, we might be on an old system. Ignore this -- job objects are a
         // best-effort thing.
         let job = imp::Job::create().ok();
 
-        if !self.no_capture {
-            // Capture stdout and stderr.
-            command_mut
-                .stdout(std::process::Stdio::piped())
-                .stderr(std::process::Stdio::piped());
+        let mut pty = None;
+        if !self.no_capture && settings.capture() {
+            if settings.pty() {
+                // Attach stdout and stderr to a pseudo-terminal, and capture its output.
+                let new_pty = imp::Pty::open().map_err(|error| {
+                    std::io::Error::new(
+                        error.kind(),
+                        format!("failed to open a pseudo-terminal: {error}"),
+                    )
+                })?;
+                command_mut
+                    .stdout(new_pty.stdio()?)
+                    .stderr(new_pty.stdio()?);
+                pty = Some(new_pty);
+            } else {
+                // Capture stdout and stderr.
+                command_mut
+                    .stdout(std::process::Stdio::piped())
+                    .stderr(std::process::Stdio::piped());
+            }
         };
 
-        let mut child = cmd.spawn()?;
+        let mut child = cmd.spawn().map_err(|error| {
+            std::io::Error::new(
+                error.kind(),
+                format!("failed to spawn test binary `{binary_path}`: {error}"),
+            )
+        })?;
 
         // If assigning the child to the job fails, ignore this. This can happen if the process has
         // exited.
+        stopwatch.mark_spawned();
+
         let _ = imp::assign_process_to_job(&child, job.as_ref());
 
+        let leak_check_group = if self.profile.leaked_processes().enabled {
+            child.id()
+        } else {
+            None
+        };
+
+        // The test's process group has the same ID as the test process.
+        let process_group = if settings.wait_for_process_group() {
+            child.id()
+        } else {
+            None
+        };
+
         let mut status: Option<ExecutionResult> = None;
         let slow_timeout = settings.slow_timeout();
         let leak_timeout = settings.leak_timeout();
@@ -691,14 +1987,27 @@ impl<'a> TestRunnerInner<'a> {
 
         let mut timeout_hit = 0;
 
+        // Once the test is slow, progress is reported at this interval until it finishes.
+        let slow_report_interval = self.profile.slow_report_interval();
+        let report_sleep =
+            crate::time::pausable_sleep(slow_report_interval.unwrap_or(Duration::ZERO));
+        tokio::pin!(report_sleep);
+        let mut report_active = false;
+
         let child_stdout = child.stdout.take();
         let child_stderr = child.stderr.take();
+        // The combined output of a test run under a pseudo-terminal is captured as its stdout.
+        let pty_output = pty.map(imp::Pty::read_output);
         let mut stdout = bytes::BytesMut::new();
         let mut stderr = bytes::BytesMut::new();
+        // The times at which the first bytes arrived on stdout and stderr.
+        let mut stdout_first_at = None;
+        let mut stderr_first_at = None;
 
-        let (res, leaked) = {
+        let (res, leaked, stopwatch_end) = {
             async fn read_all_to_bytes(
                 bytes: &mut bytes::BytesMut,
+                first_at: &mut Option<Instant>,
                 mut input: &mut (dyn AsyncRead + Unpin + Send),
             ) -> std::io::Result<()> {
                 // Reborrow it as AsyncReadExt::read_buf expects
@@ -711,13 +2020,28 @@ impl<'a> TestRunnerInner<'a> {
                     if bytes_read == 0 {
                         break Ok(());
                     }
+                    first_at.get_or_insert_with(Instant::now);
                 }
             }
 
+            async fn read_pty_to_bytes(
+                bytes: &mut bytes::BytesMut,
+                first_at: &mut Option<Instant>,
+                receiver: &mut tokio::sync::mpsc::UnboundedReceiver<std::io::Result<Vec<u8>>>,
+            ) -> std::io::Result<()> {
+                while let Some(chunk) = receiver.recv().await {
+                    bytes.extend_from_slice(&chunk?);
+                    first_at.get_or_insert_with(Instant::now);
+                }
+                Ok(())
+            }
+
             // Set up futures for reading from stdout and stderr.
             let stdout_fut = async {
                 if let Some(mut child_stdout) = child_stdout {
-                    read_all_to_bytes(&mut stdout, &mut child_stdout).await
+                    read_all_to_bytes(&mut stdout, &mut stdout_first_at, &mut child_stdout).await
+                } else if let Some(mut pty_output) = pty_output {
+                    read_pty_to_bytes(&mut stdout, &mut stdout_first_at, &mut pty_output).await
                 } else {
                     Ok(())
                 }
@@ -725,7 +2049,7 @@ impl<'a> TestRunnerInner<'a> {
 
             let stderr_fut = async {
                 if let Some(mut child_stderr) = child_stderr {
-                    read_all_to_bytes(&mut stderr, &mut child_stderr).await
+                    read_all_to_bytes(&mut stderr, &mut stderr_first_at, &mut child_stderr).await
                 } else {
                     Ok(())
                 }
@@ -741,7 +2065,7 @@ impl<'a> TestRunnerInner<'a> {
                         collect_output_done = true;
                         res?;
                     }
-                    res = child.wait() => {
+                    res = imp::wait_for_exit(&mut child, process_group) => {
                         // The test finished executing.
                         break res;
                     }
@@ -771,7 +2095,10 @@ impl<'a> TestRunnerInner<'a> {
                             // attempt to terminate the slow test.
                             // as there is a race between shutting down a slow test and its own completion
                             // we silently ignore errors to avoid printing false warnings.
-                            imp::terminate_child(&mut child, TerminateMode::Timeout(slow_timeout.grace_period), forward_receiver, job.as_ref()).await;
+                            //
+                            // A test that exits by itself during the grace period is still recorded
+                            // as having timed out, with the exit status it chose rather than SIGKILL.
+                            imp::terminate_child(&mut child, TerminateMode::Timeout(slow_timeout.grace_period), forward_receiver, job.as_ref(), process_group).await;
                             status = Some(ExecutionResult::Timeout);
                             if slow_timeout.grace_period.is_zero() {
                                 break child.wait().await;
@@ -779,7 +2106,29 @@ impl<'a> TestRunnerInner<'a> {
                             // Don't break here to give the wait task a chance to finish.
                         } else {
                             interval_sleep.as_mut().reset_original_duration();
+                            if slow_report_interval.is_some() && !report_active {
+                                report_active = true;
+                                report_sleep.as_mut().reset_original_duration();
+                            }
+                        }
+                    }
+                    _ = &mut report_sleep, if report_active && status.is_none() => {
+                        // Don't report progress for a test that has already exited but hasn't
+                        // been waited for yet. (If waiting for the process group, the test
+                        // process exiting doesn't mean the test is done.)
+                        if process_group.is_some() || matches!(child.try_wait(), Ok(None)) {
+                            let elapsed = stopwatch.end().duration;
+                            let remaining = slow_timeout
+                                .timeout()
+                                .map(|timeout| timeout.saturating_sub(elapsed));
+                            let _ = run_sender.send(InternalTestEvent::StillRunning {
+                                test_instance: test,
+                                retry_data,
+                                elapsed,
+                                remaining,
+                            });
                         }
+                        report_sleep.as_mut().reset_original_duration();
                     }
                     recv = forward_receiver.recv() => {
                         // The sender stays open longer than the whole loop, and the buffer is big
@@ -794,6 +2143,7 @@ impl<'a> TestRunnerInner<'a> {
                                 // debounced in the main signal handler.
                                 stopwatch.pause();
                                 interval_sleep.as_mut().pause();
+                                report_sleep.as_mut().pause();
                                 imp::job_control_child(&child, JobControlEvent::Stop);
                                 // The receiver being dead probably means the main thread panicked
                                 // or similar.
@@ -806,11 +2156,21 @@ impl<'a> TestRunnerInner<'a> {
                                 if stopwatch.is_paused() {
                                     stopwatch.resume();
                                     interval_sleep.as_mut().resume();
+                                    report_sleep.as_mut().resume();
                                     imp::job_control_child(&child, JobControlEvent::Continue);
                                 }
                             }
+                            SignalForwardEvent::Info(sender) => {
+                                // The receiver being dead means that the snapshot was already
+                                // reported without this test.
+                                let _ = sender.send(RunningTest {
+                                    test_instance: test,
+                                    retry_data,
+                                    elapsed: stopwatch.end().duration,
+                                }).await;
+                            }
                             SignalForwardEvent::Shutdown(event) => {
-                                imp::terminate_child(&mut child, TerminateMode::Signal(event), forward_receiver, job.as_ref()).await;
+                                imp::terminate_child(&mut child, TerminateMode::Signal(event), forward_receiver, job.as_ref(), process_group).await;
                             }
                         }
 
@@ -824,7 +2184,7 @@ impl<'a> TestRunnerInner<'a> {
             let leaked = loop {
                 // Ignore stop and continue events here since the leak timeout should be very small.
                 // TODO: we may want to consider them.
-                let sleep = tokio::time::sleep(leak_timeout);
+                let sleep = tokio::time::sleep(leak_timeout.period);
 
                 tokio::select! {
                     res = &mut collect_output_fut, if !collect_output_done => {
@@ -840,15 +2200,61 @@ impl<'a> TestRunnerInner<'a> {
                 }
             };
 
-            (res, leaked)
+            // The output grace period isn't counted towards the test's duration.
+            let stopwatch_end = stopwatch.end();
+
+            // Processes spawned by a leaky test may still be writing to its standard output and
+            // standard error. Keep collecting their output for up to the output grace period, or
+            // until the pipes are closed. This doesn't affect whether the test is marked leaky.
+            if leaked && !leak_timeout.output_grace_period.is_zero() {
+                let sleep = tokio::time::sleep(leak_timeout.output_grace_period);
+                tokio::select! {
+                    res = &mut collect_output_fut => {
+                        res?;
+                    }
+                    () = sleep => {}
+                }
+            }
+
+            (res, leaked, stopwatch_end)
         };
 
+        // Check for leaked processes right away, since the test's process group ID may be reused
+        // once every process in it has exited.
+        if let Some(pgid) = leak_check_group {
+            let processes = imp::find_leaked_processes(test, pgid).await;
+            self.leaked_processes.lock().unwrap().extend(processes);
+        }
+
         let output = res?;
         let exit_status = output;
 
+        let first_output_at = match (stdout_first_at, stderr_first_at) {
+            (Some(stdout_at), Some(stderr_at)) => Some(stdout_at.min(stderr_at)),
+            (stdout_at, stderr_at) => stdout_at.or(stderr_at),
+        };
+        let phases = stopwatch_end
+            .phases(first_output_at)
+            .map(ExecutePhases::from);
+
+        // Tests can be configured to pass when they're terminated by some signals, for example if
+        // they test signal handlers that re-raise the signal. Timeouts are already accounted for in
+        // `status`, so tests killed by nextest aren't affected.
+        #[cfg(unix)]
+        let success_signal = {
+            use std::os::unix::process::ExitStatusExt;
+            exit_status
+                .signal()
+                .map_or(false, |signal| settings.success_signals().contains(signal))
+        };
+        #[cfg(not(unix))]
+        let success_signal = false;
+
         let status = status.unwrap_or_else(|| {
-            if exit_status.success() {
-                if leaked {
+            if exit_status.success() || success_signal {
+                if leaked && leak_timeout.fail {
+                    ExecutionResult::LeakFail
+                } else if leaked {
                     ExecutionResult::Leak
                 } else {
                     ExecutionResult::Pass
@@ -875,12 +2281,41 @@ impl<'a> TestRunnerInner<'a> {
             }
         });
 
+        let status = match settings.outcome_source() {
+            OutcomeSource::ExitCode => status,
+            OutcomeSource::Tap => match crate::tap::tap_result(status, &stdout) {
+                Ok(status) => status,
+                Err(error) => {
+                    log::warn!(
+                        "for {} {}, failed to parse standard output as TAP, \
+                         using the exit code instead: {error}",
+                        test.suite_info.binary_id,
+                        test.name,
+                    );
+                    status
+                }
+            },
+        };
+        // Errors running the test are turned into ExecFail by run_test, so they're never treated
+        // as expected failures.
+        let status = match settings.expect() {
+            TestExpectation::Pass => status,
+            TestExpectation::Fail => status.expect_fail(),
+        };
+        let no_assertions = settings.detect_no_assertions()
+            && crate::assertion_count::is_pass_without_assertions(status, &stdout);
+
         Ok(InternalExecuteStatus {
             stdout: stdout.freeze(),
             stderr: stderr.freeze(),
             result: status,
-            stopwatch_end: stopwatch.end(),
+            exit_code: exit_status.code(),
+            stopwatch_end,
+            phases,
             is_slow,
+            no_assertions,
+            // Tracers may not write a trace if, for example, they fail to start.
+            trace_file: trace_file.filter(|trace_file| trace_file.is_file()),
             delay_before_start,
         })
     }
@@ -912,7 +2347,7 @@ pub struct ExecutionStatuses {
 
 #[allow(clippy::len_without_is_empty)] // RunStatuses is never empty
 impl ExecutionStatuses {
-    fn new(statuses: Vec<ExecuteStatus>) -> Self {
+    pub(crate) fn new(statuses: Vec<ExecuteStatus>) -> Self {
         Self { statuses }
     }
 
@@ -1052,48 +2487,166 @@ impl<'a> ExecutionDescription<'a> {
 pub struct ExecuteStatus {
     /// Retry-related data.
     pub retry_data: RetryData,
+    /// The slot this attempt ran in, numbered from 0 up to the number of test threads.
+    ///
+    /// No two attempts that run at the same time share a slot, so this can be used to lay out
+    /// attempts on a timeline. Retries of a test may run in a different slot from earlier
+    /// attempts.
+    pub slot: usize,
     /// Standard output for this test.
     pub stdout: Bytes,
     /// Standard error for this test.
     pub stderr: Bytes,
     /// The result of execution this test: pass, fail or execution error.
     pub result: ExecutionResult,
-    /// The time at which the test started.
+    /// The exit code of the test process, if it exited with one.
+    ///
+    /// This is `None` if the test could not be executed, or if it was terminated by a signal.
+    pub exit_code: Option<i32>,
+    /// The time at which the test started, according to the system clock.
     pub start_time: SystemTime,
     /// The time it took for the test to run.
+    ///
+    /// This is measured using the monotonic clock, so it isn't affected by changes to the system
+    /// clock while the test is running.
     pub time_taken: Duration,
+    /// A breakdown of `time_taken` into the phases of the test's lifecycle.
+    ///
+    /// This is `None` if the test process couldn't be spawned.
+    pub phases: Option<ExecutePhases>,
     /// Whether this test counts as slow.
     pub is_slow: bool,
+    /// Whether this attempt passed, but the test harness reported that it ran no assertions.
+    ///
+    /// This is only set if `detect-no-assertions` is enabled for the test.
+    pub no_assertions: bool,
+    /// The file that the tracer for this attempt wrote its trace to, if the test was run under a
+    /// `tracer` set through an override.
+    ///
+    /// The trace is kept separate from the test's standard output and standard error.
+    pub trace_file: Option<Utf8PathBuf>,
     /// The delay will be non-zero if this is a retry and delay was specified.
     pub delay_before_start: Duration,
 }
 
+impl ExecuteStatus {
+    /// Returns why this attempt finished, along with its exit code or abort status.
+    pub fn finish_reason(&self) -> FinishReason {
+        match self.result {
+            ExecutionResult::Pass => FinishReason::Pass,
+            ExecutionResult::Leak => FinishReason::Leak,
+            ExecutionResult::LeakFail => FinishReason::LeakFail,
+            ExecutionResult::Fail {
+                abort_status: Some(abort_status),
+                leaked,
+            } => FinishReason::Aborted {
+                abort_status,
+                leaked,
+            },
+            ExecutionResult::Fail {
+                abort_status: None,
+                leaked,
+            } => FinishReason::Failed {
+                exit_code: self.exit_code,
+                leaked,
+            },
+            ExecutionResult::ExecFail => FinishReason::ExecFail,
+            ExecutionResult::Timeout => FinishReason::Timeout,
+            ExecutionResult::ExpectedFail => FinishReason::ExpectedFail,
+            ExecutionResult::UnexpectedPass => FinishReason::UnexpectedPass,
+        }
+    }
+
+    /// Returns true if this failed attempt is eligible for a retry, given the list of exit codes
+    /// that retries are restricted to.
+    ///
+    /// An empty list means that any failure may be retried. Tests that didn't exit with an exit
+    /// code, such as tests that were terminated by a signal or timed out, never match a non-empty
+    /// list.
+    pub fn is_retryable(&self, retry_on_exit_codes: &[i32]) -> bool {
+        if retry_on_exit_codes.is_empty() {
+            return true;
+        }
+        match (self.result, self.exit_code) {
+            (
+                ExecutionResult::Fail {
+                    abort_status: None, ..
+                },
+                Some(exit_code),
+            ) => retry_on_exit_codes.contains(&exit_code),
+            _ => false,
+        }
+    }
+}
+
+/// The phases of a single execution of a test, for distinguishing scheduling latency from the time
+/// the test itself takes to run.
+///
+/// `queued_to_spawned` and `spawned_to_exited` add up to [`ExecuteStatus::time_taken`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ExecutePhases {
+    /// The time between nextest starting this attempt and the test process being spawned.
+    ///
+    /// This includes preparing the command and spawning the process, but not the delay before a
+    /// retry.
+    pub queued_to_spawned: Duration,
+    /// The time between the test process being spawned and the first byte of output arriving on
+    /// its captured standard output or standard error.
+    ///
+    /// This is `None` if output wasn't captured, or the test didn't produce any output before it
+    /// exited.
+    pub spawned_to_first_output: Option<Duration>,
+    /// The time between the test process being spawned and it exiting, not counting any time the
+    /// test was paused.
+    pub spawned_to_exited: Duration,
+}
+
+impl From<StopwatchPhases> for ExecutePhases {
+    fn from(phases: StopwatchPhases) -> Self {
+        Self {
+            queued_to_spawned: phases.queued_to_spawned,
+            spawned_to_first_output: phases.spawned_to_first_output,
+            spawned_to_exited: phases.spawned_to_exited,
+        }
+    }
+}
+
 struct InternalExecuteStatus {
     stdout: Bytes,
     stderr: Bytes,
     result: ExecutionResult,
+    exit_code: Option<i32>,
     stopwatch_end: StopwatchEnd,
+    phases: Option<ExecutePhases>,
     is_slow: bool,
+    no_assertions: bool,
+    trace_file: Option<Utf8PathBuf>,
     delay_before_start: Duration,
 }
 
 impl InternalExecuteStatus {
-    fn into_external(self, retry_data: RetryData) -> ExecuteStatus {
+    fn into_external(self, retry_data: RetryData, slot: usize) -> ExecuteStatus {
         ExecuteStatus {
             retry_data,
+            slot,
             stdout: self.stdout,
             stderr: self.stderr,
             result: self.result,
+            exit_code: self.exit_code,
             start_time: self.stopwatch_end.start_time,
             time_taken: self.stopwatch_end.duration,
+            phases: self.phases,
             is_slow: self.is_slow,
+            no_assertions: self.no_assertions,
+            trace_file: self.trace_file,
             delay_before_start: self.delay_before_start,
         }
     }
 }
 
 /// Statistics for a test run.
-#[derive(Copy, Clone, Default, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Default, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
 pub struct RunStats {
     /// The total number of tests that were expected to be run at the beginning.
     ///
@@ -1127,8 +2680,30 @@ pub struct RunStats {
     /// The number of tests that encountered an execution failure.
     pub exec_failed: usize,
 
+    /// The number of tests that were expected to fail and did so (XFAIL).
+    ///
+    /// These tests are also counted in `passed`.
+    pub expected_failures: usize,
+
+    /// The number of tests that were expected to fail but passed (XPASS).
+    ///
+    /// These tests are also counted in `failed`.
+    pub unexpected_passes: usize,
+
     /// The number of tests that were skipped.
     pub skipped: usize,
+
+    /// The number of tests that were quarantined through the profile's `quarantine` list.
+    ///
+    /// Quarantined tests aren't run, so they aren't counted in `initial_run_count` or `skipped`.
+    #[serde(default)]
+    pub quarantined: usize,
+
+    /// The number of tests that passed, but reported that they ran no assertions.
+    ///
+    /// These tests are also counted in `passed`.
+    #[serde(default)]
+    pub no_assertions: usize,
 }
 
 impl RunStats {
@@ -1154,6 +2729,20 @@ impl RunStats {
         self.failed > 0 || self.exec_failed > 0 || self.timed_out > 0
     }
 
+    /// Returns the final status of this run.
+    ///
+    /// If `flaky_as_failure` is true, a run that would otherwise be a success is marked flaky if
+    /// any tests only passed on retry. This corresponds to the `flaky-as-failure` profile setting.
+    pub fn final_status(&self, flaky_as_failure: bool) -> FinalRunStatus {
+        if !self.is_success() {
+            FinalRunStatus::Failed
+        } else if flaky_as_failure && self.flaky > 0 {
+            FinalRunStatus::Flaky
+        } else {
+            FinalRunStatus::Success
+        }
+    }
+
     fn on_test_finished(&mut self, run_statuses: &ExecutionStatuses) {
         self.finished_count += 1;
         // run_statuses is guaranteed to have at least one element.
@@ -1174,6 +2763,9 @@ impl RunStats {
                 if run_statuses.len() > 1 {
                     self.flaky += 1;
                 }
+                if last_status.no_assertions {
+                    self.no_assertions += 1;
+                }
             }
             ExecutionResult::Leak => {
                 self.passed += 1;
@@ -1184,9 +2776,29 @@ impl RunStats {
                 if run_statuses.len() > 1 {
                     self.flaky += 1;
                 }
+                if last_status.no_assertions {
+                    self.no_assertions += 1;
+                }
+            }
+            ExecutionResult::Fail { .. } | ExecutionResult::LeakFail => {
+                self.failed += 1;
+                if last_status.is_slow {
+                    self.failed_slow += 1;
+                }
+            }
+            ExecutionResult::ExpectedFail => {
+                self.passed += 1;
+                self.expected_failures += 1;
+                if last_status.is_slow {
+                    self.passed_slow += 1;
+                }
+                if run_statuses.len() > 1 {
+                    self.flaky += 1;
+                }
             }
-            ExecutionResult::Fail { .. } => {
+            ExecutionResult::UnexpectedPass => {
                 self.failed += 1;
+                self.unexpected_passes += 1;
                 if last_status.is_slow {
                     self.failed_slow += 1;
                 }
@@ -1197,10 +2809,109 @@ impl RunStats {
     }
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
-enum SignalCount {
-    Once,
-    Twice,
+/// The final status of a test run, as returned by [`RunStats::final_status`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FinalRunStatus {
+    /// The run succeeded.
+    ///
+    /// Some tests may have been flaky, if flaky tests aren't treated as failures.
+    Success,
+
+    /// No tests failed, but some tests only passed on retry, and flaky tests are treated as
+    /// failures.
+    Flaky,
+
+    /// Some tests failed, or the run was canceled.
+    Failed,
+}
+
+impl FinalRunStatus {
+    /// Returns the exit code that `cargo nextest run` exits with for this status.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Self::Success => 0,
+            Self::Flaky => NextestExitCode::TEST_RUN_FLAKY,
+            Self::Failed => NextestExitCode::TEST_RUN_FAILED,
+        }
+    }
+}
+
+/// Timing statistics for the tests in a run, reported once the run has finished.
+///
+/// Each test that was run contributes the time taken by its last attempt. Skipped tests aren't
+/// included.
+#[derive(Clone, Debug, Default)]
+pub struct RunStatistics<'a> {
+    // Sorted in ascending order.
+    durations: Vec<Duration>,
+    // Sorted slowest first.
+    slowest: Vec<(TestInstance<'a>, Duration)>,
+}
+
+impl<'a> RunStatistics<'a> {
+    fn new(mut timings: Vec<(TestInstance<'a>, Duration)>, slowest_count: usize) -> Self {
+        // Break ties by test name so that the slowest tests are listed in a stable order.
+        timings.sort_by(|(a, a_duration), (b, b_duration)| {
+            b_duration
+                .cmp(a_duration)
+                .then_with(|| a.sort_key().cmp(&b.sort_key()))
+        });
+        let durations = timings
+            .iter()
+            .rev()
+            .map(|(_, duration)| *duration)
+            .collect();
+        timings.truncate(slowest_count);
+        Self {
+            durations,
+            slowest: timings,
+        }
+    }
+
+    /// Returns the number of tests these statistics were computed from.
+    pub fn test_count(&self) -> usize {
+        self.durations.len()
+    }
+
+    /// Returns the mean time taken by a test, or `None` if no tests were run.
+    pub fn mean(&self) -> Option<Duration> {
+        if self.durations.is_empty() {
+            return None;
+        }
+        let total: Duration = self.durations.iter().sum();
+        Some(total / self.durations.len() as u32)
+    }
+
+    /// Returns the given percentile of the time taken by tests, or `None` if no tests were run.
+    ///
+    /// This uses the nearest-rank method: the result is the shortest time taken by a test such
+    /// that at least `percentile` percent of tests took no longer than it. The result is always
+    /// the time taken by an actual test, so with 3 tests, the 50th percentile is the middle one
+    /// and the 90th and 99th percentiles are the slowest one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `percentile` is greater than 100.
+    pub fn percentile(&self, percentile: u8) -> Option<Duration> {
+        assert!(
+            percentile <= 100,
+            "percentile {percentile} must be between 0 and 100"
+        );
+        if self.durations.is_empty() {
+            return None;
+        }
+        // This is ceil(percentile * n / 100), with the 0th percentile mapped to the fastest test.
+        let rank = (usize::from(percentile) * self.durations.len() + 99) / 100;
+        Some(self.durations[rank.max(1) - 1])
+    }
+
+    /// Returns the slowest tests in this run along with the time they took, slowest first.
+    ///
+    /// The number of tests returned is at most the profile's `slow-test-report-count`.
+    pub fn slowest(&self) -> &[(TestInstance<'a>, Duration)] {
+        &self.slowest
+    }
 }
 
 impl SignalCount {
@@ -1213,12 +2924,16 @@ impl SignalCount {
 }
 
 #[derive(Clone, Debug)]
-enum SignalForwardEvent {
+enum SignalForwardEvent<'a> {
     // The mpsc sender is used by each test to indicate that the stop signal has been sent.
     #[cfg(unix)]
     Stop(tokio::sync::mpsc::Sender<()>),
     #[cfg(unix)]
     Continue,
+    // The mpsc sender is used by each test to report how long it's been running for. Snapshots are
+    // only requested on Unix, but the variant is kept elsewhere for the lifetime parameter.
+    #[cfg_attr(not(unix), allow(dead_code))]
+    Info(tokio::sync::mpsc::Sender<RunningTest<'a>>),
     Shutdown(ShutdownForwardEvent),
 }
 
@@ -1228,25 +2943,48 @@ enum ShutdownForwardEvent {
     Twice,
 }
 
-struct CallbackContext<F, E> {
+struct CallbackContext<'a, F, E> {
     callback: F,
+    observers: TestObservers,
     run_id: Uuid,
     stopwatch: StopwatchStart,
     run_stats: RunStats,
-    fail_fast: bool,
+    fail_fast: FailFast,
+    // The number of tests that failed, counted towards fail_fast.
+    failed: usize,
+    // Failures of these tests aren't counted towards fail_fast.
+    known_failures: KnownFailures,
     running: usize,
     cancel_state: Option<CancelReason>,
-    signal_count: Option<SignalCount>,
+    signal_counter: SignalCounter,
+    // Whether new tests are being held back with SIGUSR2.
+    paused: bool,
+    // The time taken by the last attempt of each test that finished.
+    timings: Vec<(TestInstance<'a>, Duration)>,
+    slow_test_report_count: usize,
+    // Tests that failed, then passed on a retry.
+    flaky_tests: Vec<TestInstance<'a>>,
+    group_saturation: GroupSaturation,
     phantom: PhantomData<E>,
 }
 
-impl<'a, F, E> CallbackContext<F, E>
+impl<'a, F, E> CallbackContext<'a, F, E>
 where
     F: FnMut(TestEvent<'a>) -> Result<(), E> + Send,
 {
-    fn new(callback: F, run_id: Uuid, initial_run_count: usize, fail_fast: bool) -> Self {
+    fn new(
+        callback: F,
+        observers: TestObservers,
+        run_id: Uuid,
+        initial_run_count: usize,
+        fail_fast: FailFast,
+        group_saturation: GroupSaturation,
+        slow_test_report_count: usize,
+        known_failures: KnownFailures,
+    ) -> Self {
         Self {
             callback,
+            observers,
             run_id,
             stopwatch: crate::time::stopwatch(),
             run_stats: RunStats {
@@ -1254,17 +2992,25 @@ where
                 ..RunStats::default()
             },
             fail_fast,
+            failed: 0,
+            known_failures,
             running: 0,
             cancel_state: None,
-            signal_count: None,
+            signal_counter: SignalCounter::default(),
+            paused: false,
+            timings: Vec::new(),
+            slow_test_report_count,
+            flaky_tests: Vec::new(),
+            group_saturation,
             phantom: PhantomData,
         }
     }
 
-    fn run_started(&mut self, test_list: &'a TestList) -> Result<(), E> {
+    fn run_started(&mut self, test_list: &'a TestList, shuffle_seed: Option<u64>) -> Result<(), E> {
         (self.callback)(TestEvent::RunStarted {
             test_list,
             run_id: self.run_id,
+            shuffle_seed,
         })
     }
 
@@ -1281,14 +3027,33 @@ where
         event: InternalEvent<'a>,
     ) -> Result<Option<JobControlEvent>, InternalError<E>> {
         match event {
-            InternalEvent::Test(InternalTestEvent::Started { test_instance }) => {
+            InternalEvent::Test(InternalTestEvent::Started {
+                test_instance,
+                retry_data,
+                test_group,
+                threads_required,
+            }) => {
                 self.running += 1;
+                self.observers.notify("test_started", |observer| {
+                    observer.test_started(test_instance, retry_data)
+                });
                 self.callback(TestEvent::TestStarted {
                     test_instance,
                     current_stats: self.run_stats,
                     running: self.running,
                     cancel_state: self.cancel_state,
-                })
+                })?;
+
+                if let Some(group) = test_group {
+                    let now = self.stopwatch.end().duration;
+                    if let Some(event) =
+                        self.group_saturation
+                            .test_started(&group, threads_required, now)
+                    {
+                        self.callback(event)?;
+                    }
+                }
+                Ok(None)
             }
             InternalEvent::Test(InternalTestEvent::Slow {
                 test_instance,
@@ -1301,6 +3066,17 @@ where
                 elapsed,
                 will_terminate,
             }),
+            InternalEvent::Test(InternalTestEvent::StillRunning {
+                test_instance,
+                retry_data,
+                elapsed,
+                remaining,
+            }) => self.callback(TestEvent::TestStillRunning {
+                test_instance,
+                retry_data,
+                elapsed,
+                remaining,
+            }),
             InternalEvent::Test(InternalTestEvent::AttemptFailedWillRetry {
                 test_instance,
                 failure_output,
@@ -1315,12 +3091,19 @@ where
             InternalEvent::Test(InternalTestEvent::RetryStarted {
                 test_instance,
                 retry_data,
-            }) => self.callback(TestEvent::TestRetryStarted {
-                test_instance,
-                retry_data,
-            }),
+            }) => {
+                self.observers.notify("test_started", |observer| {
+                    observer.test_started(test_instance, retry_data)
+                });
+                self.callback(TestEvent::TestRetryStarted {
+                    test_instance,
+                    retry_data,
+                })
+            }
             InternalEvent::Test(InternalTestEvent::Finished {
                 test_instance,
+                test_group,
+                threads_required,
                 success_output,
                 failure_output,
                 junit_store_success_output,
@@ -1329,9 +3112,34 @@ where
             }) => {
                 self.running -= 1;
                 self.run_stats.on_test_finished(&run_statuses);
+                self.timings
+                    .push((test_instance, run_statuses.last_status().time_taken));
+                if let Some(group) = test_group {
+                    let now = self.stopwatch.end().duration;
+                    self.group_saturation
+                        .test_finished(&group, threads_required, now);
+                }
+
+                // should this run be canceled because of a failure? (Failures of tests that are
+                // known to fail don't count.)
+                let fail_cancel = if run_statuses.last_status().result.is_success()
+                    || self
+                        .known_failures
+                        .contains(&test_instance.suite_info.binary_id, test_instance.name)
+                {
+                    false
+                } else {
+                    self.failed += 1;
+                    self.fail_fast.should_cancel(self.failed)
+                };
+
+                if matches!(run_statuses.describe(), ExecutionDescription::Flaky { .. }) {
+                    self.flaky_tests.push(test_instance);
+                }
 
-                // should this run be canceled because of a failure?
-                let fail_cancel = self.fail_fast && !run_statuses.last_status().result.is_success();
+                self.observers.notify("test_finished", |observer| {
+                    observer.test_finished(test_instance, &run_statuses)
+                });
 
                 self.callback(TestEvent::TestFinished {
                     test_instance,
@@ -1346,7 +3154,16 @@ where
                 })?;
 
                 if fail_cancel {
-                    // A test failed: start cancellation.
+                    // Enough tests failed: start cancellation.
+                    if self.cancel_state < Some(CancelReason::TestFailure) {
+                        if let Some(max_failures) = self.fail_fast.max_failures {
+                            self.callback(TestEvent::RunMaxFailures {
+                                failed: self.failed,
+                                max_failures,
+                                running: self.running,
+                            })?;
+                        }
+                    }
                     Err(InternalError::TestFailureCanceled(
                         self.begin_cancel(CancelReason::TestFailure).err(),
                     ))
@@ -1358,14 +3175,21 @@ where
                 test_instance,
                 reason,
             }) => {
-                self.run_stats.skipped += 1;
+                if reason == MismatchReason::Quarantined {
+                    // Quarantined tests match the filter, so they're included in the initial run
+                    // count. Take them out so that the run isn't considered incomplete.
+                    self.run_stats.initial_run_count -= 1;
+                    self.run_stats.quarantined += 1;
+                } else {
+                    self.run_stats.skipped += 1;
+                }
                 self.callback(TestEvent::TestSkipped {
                     test_instance,
                     reason,
                 })
             }
             InternalEvent::Signal(SignalEvent::Shutdown(event)) => {
-                let signal_count = self.increment_signal_count();
+                let signal_count = self.record_signal(event);
                 let forward_event = signal_count.to_forward_event(event);
 
                 let cancel_reason = match event {
@@ -1379,6 +3203,75 @@ where
                     self.begin_cancel(cancel_reason).err(),
                 ))
             }
+            InternalEvent::GlobalTimeout(global_timeout) => {
+                if self.cancel_state >= Some(CancelReason::GlobalTimeout) {
+                    // The run is already being canceled for an equal or more severe reason.
+                    return Ok(None);
+                }
+
+                self.callback(TestEvent::RunGlobalTimeout {
+                    global_timeout,
+                    running: self.running,
+                })?;
+                Err(InternalError::GlobalTimeoutCanceled(
+                    self.begin_cancel(CancelReason::GlobalTimeout).err(),
+                ))
+            }
+            InternalEvent::HealthCheckFailed { command, message } => {
+                if self.cancel_state >= Some(CancelReason::HealthCheck) {
+                    // The run is already being canceled for an equal or more severe reason.
+                    return Ok(None);
+                }
+
+                self.callback(TestEvent::RunHealthCheckFailed {
+                    command,
+                    message,
+                    running: self.running,
+                })?;
+                Err(InternalError::HealthCheckCanceled(
+                    self.begin_cancel(CancelReason::HealthCheck).err(),
+                ))
+            }
+            InternalEvent::GroupFreedTimeout => {
+                self.report_groups_freed(false)
+                    .map_err(InternalError::Error)?;
+                Ok(None)
+            }
+            #[cfg(unix)]
+            InternalEvent::Signal(SignalEvent::Info) => {
+                // Snapshots are collected from running tests before being handled, as
+                // InternalEvent::RunningSnapshot.
+                Ok(None)
+            }
+            #[cfg(unix)]
+            InternalEvent::Signal(SignalEvent::TogglePause) => {
+                // Once the run has been canceled, no new tests are started anyway.
+                if self.cancel_state.is_some() {
+                    return Ok(None);
+                }
+                self.paused = !self.paused;
+                let event = if self.paused {
+                    TestEvent::RunSchedulingPaused {
+                        running: self.running,
+                    }
+                } else {
+                    TestEvent::RunSchedulingResumed {
+                        running: self.running,
+                    }
+                };
+                self.callback(event)?;
+                Ok(None)
+            }
+            #[cfg(unix)]
+            InternalEvent::RunningSnapshot(mut tests) => {
+                // Longest-running tests first, since those are the most likely to be stuck.
+                tests.sort_by(|a, b| b.elapsed.cmp(&a.elapsed));
+                self.callback(TestEvent::RunningSnapshot {
+                    run_elapsed: self.stopwatch.end().duration,
+                    tests,
+                })?;
+                Ok(None)
+            }
             #[cfg(unix)]
             InternalEvent::Signal(SignalEvent::JobControl(JobControlEvent::Stop)) => {
                 // Debounce stop signals.
@@ -1408,22 +3301,34 @@ where
         }
     }
 
-    fn increment_signal_count(&mut self) -> SignalCount {
-        let new_count = match self.signal_count {
-            None => SignalCount::Once,
-            Some(SignalCount::Once) => SignalCount::Twice,
-            Some(SignalCount::Twice) => {
-                // The process was signaled 3 times. Time to panic.
-                panic!("Signaled 3 times, exiting immediately");
-            }
-        };
-        self.signal_count = Some(new_count);
-        new_count
+    fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    fn record_signal(&mut self, event: ShutdownEvent) -> SignalCount {
+        self.signal_counter.record(event, Instant::now())
+    }
+
+    /// Returns the time until the next test group freed event is due, if any.
+    fn group_freed_timeout(&self) -> Option<Duration> {
+        let deadline = self.group_saturation.next_deadline()?;
+        Some(deadline.saturating_sub(self.stopwatch.end().duration))
+    }
+
+    /// Reports test groups that have been freed up, or all of them if `flush` is true.
+    fn report_groups_freed(&mut self, flush: bool) -> Result<(), E> {
+        let now = self.stopwatch.end().duration;
+        for event in self.group_saturation.take_freed(now, flush) {
+            (self.callback)(event)?;
+        }
+        Ok(())
     }
 
     /// Begin cancellation of a test run. Report it if the current cancel state is less than
     /// the required one.
     fn begin_cancel(&mut self, reason: CancelReason) -> Result<(), E> {
+        // Tests that are waiting to be started need to be let through so they can skip themselves.
+        self.paused = false;
         if self.cancel_state < Some(reason) {
             self.cancel_state = Some(reason);
             (self.callback)(TestEvent::RunBeginCancel {
@@ -1441,6 +3346,10 @@ where
             run_id: self.run_id,
             elapsed: stopwatch_end.duration,
             run_stats: self.run_stats,
+            statistics: RunStatistics::new(
+                std::mem::take(&mut self.timings),
+                self.slow_test_report_count,
+            ),
         })
     }
 }
@@ -1450,12 +3359,23 @@ where
 enum InternalEvent<'a> {
     Test(InternalTestEvent<'a>),
     Signal(SignalEvent),
+    GlobalTimeout(Duration),
+    HealthCheckFailed {
+        command: String,
+        message: String,
+    },
+    GroupFreedTimeout,
+    #[cfg(unix)]
+    RunningSnapshot(Vec<RunningTest<'a>>),
 }
 
 #[derive(Debug)]
 enum InternalTestEvent<'a> {
     Started {
         test_instance: TestInstance<'a>,
+        retry_data: RetryData,
+        test_group: Option<CustomTestGroup>,
+        threads_required: usize,
     },
     Slow {
         test_instance: TestInstance<'a>,
@@ -1463,6 +3383,12 @@ enum InternalTestEvent<'a> {
         elapsed: Duration,
         will_terminate: bool,
     },
+    StillRunning {
+        test_instance: TestInstance<'a>,
+        retry_data: RetryData,
+        elapsed: Duration,
+        remaining: Option<Duration>,
+    },
     AttemptFailedWillRetry {
         test_instance: TestInstance<'a>,
         failure_output: TestOutputDisplay,
@@ -1475,6 +3401,8 @@ enum InternalTestEvent<'a> {
     },
     Finished {
         test_instance: TestInstance<'a>,
+        test_group: Option<CustomTestGroup>,
+        threads_required: usize,
         success_output: TestOutputDisplay,
         failure_output: TestOutputDisplay,
         junit_store_success_output: bool,
@@ -1491,9 +3419,41 @@ enum InternalTestEvent<'a> {
 enum InternalError<E> {
     Error(E),
     TestFailureCanceled(Option<E>),
+    GlobalTimeoutCanceled(Option<E>),
+    HealthCheckCanceled(Option<E>),
     SignalCanceled(ShutdownForwardEvent, Option<E>),
 }
 
+/// A process started by a test that was still running after the test finished.
+///
+/// Reported through [`TestEvent::RunLeakedProcesses`] if `detect-leaked-processes` is enabled.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LeakedProcess<'a> {
+    /// The test that started the process, as determined by the process group it's in.
+    pub test_instance: TestInstance<'a>,
+
+    /// The process ID.
+    pub pid: u32,
+
+    /// The command line of the process.
+    pub command: String,
+}
+
+/// A test that was running when a snapshot of running tests was requested.
+///
+/// Reported through [`TestEvent::RunningSnapshot`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RunningTest<'a> {
+    /// The test that is running.
+    pub test_instance: TestInstance<'a>,
+
+    /// The attempt that is running.
+    pub retry_data: RetryData,
+
+    /// How long the current attempt has been running for.
+    pub elapsed: Duration,
+}
+
 /// Whether a test passed, failed or an error occurred while executing the test.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum ExecutionResult {
@@ -1505,6 +3465,10 @@ pub enum ExecutionResult {
     ///
     /// This is treated as a pass.
     Leak,
+    /// The test passed but leaked handles, and the leak timeout was configured with `fail = true`.
+    ///
+    /// This is treated as a failure.
+    LeakFail,
     /// The test failed.
     Fail {
         /// The abort status of the test, if any (for example, the signal on Unix).
@@ -1519,20 +3483,101 @@ pub enum ExecutionResult {
     ExecFail,
     /// The test was terminated due to timeout.
     Timeout,
+    /// The test was expected to fail, and it failed (XFAIL).
+    ///
+    /// This is treated as a pass.
+    ExpectedFail,
+    /// The test was expected to fail, but it passed (XPASS).
+    ///
+    /// This is treated as a failure.
+    UnexpectedPass,
 }
 
 impl ExecutionResult {
     /// Returns true if the test was successful.
     pub fn is_success(self) -> bool {
         match self {
-            ExecutionResult::Pass | ExecutionResult::Leak => true,
-            ExecutionResult::Fail { .. } | ExecutionResult::ExecFail | ExecutionResult::Timeout => {
-                false
+            ExecutionResult::Pass | ExecutionResult::Leak | ExecutionResult::ExpectedFail => true,
+            ExecutionResult::Fail { .. }
+            | ExecutionResult::LeakFail
+            | ExecutionResult::ExecFail
+            | ExecutionResult::Timeout
+            | ExecutionResult::UnexpectedPass => false,
+        }
+    }
+
+    /// Reclassifies the result of a test that is expected to fail.
+    ///
+    /// Timeouts and execution failures are left as they are, since they don't indicate that the
+    /// test failed in the way it's expected to.
+    fn expect_fail(self) -> Self {
+        match self {
+            // A test that leaked handles still passed, even if leaks are treated as failures.
+            ExecutionResult::Pass | ExecutionResult::Leak | ExecutionResult::LeakFail => {
+                ExecutionResult::UnexpectedPass
             }
+            ExecutionResult::Fail { .. } => ExecutionResult::ExpectedFail,
+            ExecutionResult::ExecFail
+            | ExecutionResult::Timeout
+            | ExecutionResult::ExpectedFail
+            | ExecutionResult::UnexpectedPass => self,
         }
     }
 }
 
+/// Why a test finished, or was skipped, with any associated data.
+///
+/// This combines an [`ExecutionResult`] with the exit code of the test, so that consumers can
+/// match on why a test failed. Returned by [`ExecuteStatus::finish_reason`] and
+/// [`TestEvent::finish_reason`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum FinishReason {
+    /// The test passed.
+    Pass,
+
+    /// The test passed, but leaked subprocesses or handles.
+    Leak,
+
+    /// The test passed, but leaked subprocesses or handles, and the leak timeout was configured
+    /// with `fail = true`.
+    LeakFail,
+
+    /// The test exited with a failing exit code, or with [TAP output](OutcomeSource::Tap), reported
+    /// failing test points.
+    Failed {
+        /// The exit code of the test. This is only `None` if it couldn't be determined.
+        exit_code: Option<i32>,
+
+        /// Whether the test also leaked subprocesses or handles.
+        leaked: bool,
+    },
+
+    /// The test was terminated by a signal on Unix, or aborted on Windows.
+    Aborted {
+        /// The signal or Windows NT status the test was aborted with.
+        abort_status: AbortStatus,
+
+        /// Whether the test also leaked subprocesses or handles.
+        leaked: bool,
+    },
+
+    /// The test was terminated because it timed out.
+    Timeout,
+
+    /// An error occurred while executing the test.
+    ExecFail,
+
+    /// The test was expected to fail, and it failed.
+    ExpectedFail,
+
+    /// The test was expected to fail, but it passed.
+    UnexpectedPass,
+
+    /// The test was skipped, and not run.
+    Skipped(MismatchReason),
+}
+
 /// A regular exit code or Windows NT abort status for a test.
 ///
 /// Returned as part of the [`ExecutionResult::Fail`] variant.
@@ -1614,6 +3659,38 @@ mod imp {
         // TODO: set process group on Windows for better ctrl-C handling.
     }
 
+    /// Pseudo-terminals aren't supported on Windows, so this can never be created.
+    pub(super) struct Pty(Infallible);
+
+    impl Pty {
+        pub(super) fn open() -> std::io::Result<Self> {
+            // TODO: use ConPTY to support this on Windows.
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "running tests under a pseudo-terminal (`pty = true`) is only supported on Unix",
+            ))
+        }
+
+        pub(super) fn stdio(&self) -> std::io::Result<Stdio> {
+            match self.0 {}
+        }
+
+        pub(super) fn read_output(
+            self,
+        ) -> tokio::sync::mpsc::UnboundedReceiver<std::io::Result<Vec<u8>>> {
+            match self.0 {}
+        }
+    }
+
+    pub(super) async fn find_leaked_processes<'a>(
+        _test_instance: TestInstance<'a>,
+        _pgid: u32,
+    ) -> Vec<LeakedProcess<'a>> {
+        // Process groups aren't supported on Windows, and processes in a test's job object are
+        // terminated along with it.
+        Vec::new()
+    }
+
     pub(super) fn assign_process_to_job(
         child: &tokio::process::Child,
         job: Option<&Job>,
@@ -1635,17 +3712,40 @@ mod imp {
         Ok(())
     }
 
+    pub(super) async fn wait_for_exit(
+        child: &mut Child,
+        _process_group: Option<u32>,
+    ) -> std::io::Result<std::process::ExitStatus> {
+        // Process groups aren't supported on Windows.
+        child.wait().await
+    }
+
     pub(super) async fn terminate_child(
         child: &mut Child,
         mode: TerminateMode,
-        _forward_receiver: &mut tokio::sync::broadcast::Receiver<SignalForwardEvent>,
+        _forward_receiver: &mut tokio::sync::broadcast::Receiver<SignalForwardEvent<'_>>,
         job: Option<&Job>,
+        _process_group: Option<u32>,
     ) {
         // Ignore signal events since Windows propagates them to child processes (this may change if
         // we start assigning processes to groups on Windows).
-        if !matches!(mode, TerminateMode::Timeout(_)) {
-            return;
+        let grace_period = match mode {
+            TerminateMode::Timeout(grace_period) => grace_period,
+            TerminateMode::Signal(_) => return,
+        };
+
+        // There's no equivalent to SIGTERM on Windows, so give the test the grace period to exit by
+        // itself before killing it.
+        if !grace_period.is_zero() {
+            tokio::select! {
+                _ = child.wait() => {
+                    // The process exited.
+                    return;
+                }
+                _ = tokio::time::sleep(grace_period) => {}
+            }
         }
+
         if let Some(job) = job {
             let handle = job.handle();
             unsafe {
@@ -1720,13 +3820,265 @@ mod imp {
         unsafe { libc::raise(SIGSTOP) };
     }
 
+    /// A pseudo-terminal that a test's standard output and standard error are attached to.
+    ///
+    /// The terminal isn't made the test's controlling terminal, since the test is put in its own
+    /// process group rather than its own session. This is enough for `isatty` to return true.
+    pub(super) struct Pty {
+        master: std::fs::File,
+        slave: std::fs::File,
+    }
+
+    impl Pty {
+        pub(super) fn open() -> std::io::Result<Self> {
+            use std::os::unix::io::{AsRawFd, FromRawFd};
+
+            let mut master = -1;
+            let mut slave = -1;
+            // openpty can't mark the new file descriptors close-on-exec atomically, so make sure
+            // that no process is spawned until that's done. Otherwise, a test spawned at the same
+            // time could inherit the terminal and keep it open after this test exits.
+            let spawn_guard = SPAWN_LOCK.write().unwrap();
+            let ret = unsafe {
+                libc::openpty(
+                    &mut master,
+                    &mut slave,
+                    std::ptr::null_mut(),
+                    std::ptr::null_mut(),
+                    std::ptr::null_mut(),
+                )
+            };
+            if ret != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            // openpty returns two new file descriptors, which are owned by these files from now on.
+            let (master, slave) = unsafe {
+                (
+                    std::fs::File::from_raw_fd(master),
+                    std::fs::File::from_raw_fd(slave),
+                )
+            };
+
+            for file in [&master, &slave] {
+                if unsafe { libc::fcntl(file.as_raw_fd(), libc::F_SETFD, libc::FD_CLOEXEC) } == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+            drop(spawn_guard);
+
+            // Don't translate "\n" into "\r\n", so that the captured output looks the same as it
+            // would through a pipe.
+            unsafe {
+                let mut termios = std::mem::MaybeUninit::<libc::termios>::uninit();
+                if libc::tcgetattr(slave.as_raw_fd(), termios.as_mut_ptr()) == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                let mut termios = termios.assume_init();
+                termios.c_oflag &= !libc::ONLCR;
+                if libc::tcsetattr(slave.as_raw_fd(), libc::TCSANOW, &termios) == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+
+            Ok(Self { master, slave })
+        }
+
+        /// Returns a handle to the terminal, to attach to the test's standard output or standard
+        /// error.
+        pub(super) fn stdio(&self) -> std::io::Result<Stdio> {
+            Ok(self.slave.try_clone()?.into())
+        }
+
+        /// Starts reading the output written to the terminal, once the test has been spawned.
+        ///
+        /// There's no way to read from the terminal asynchronously without more tokio features, so
+        /// it's read on a separate thread. The thread exits once every process that has the
+        /// terminal open closes it, or shortly after the receiver is dropped. The receiver is
+        /// dropped once the leak timeout expires, so processes leaked by the test that keep the
+        /// terminal open don't keep the thread around.
+        pub(super) fn read_output(
+            self,
+        ) -> tokio::sync::mpsc::UnboundedReceiver<std::io::Result<Vec<u8>>> {
+            use std::{io::Read, os::unix::io::AsRawFd};
+
+            // How often the thread checks whether the receiver has been dropped.
+            const PTY_POLL_INTERVAL_MS: libc::c_int = 100;
+
+            let Self { mut master, slave } = self;
+            // Only the test's processes should have the terminal open now.
+            drop(slave);
+
+            let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+            std::thread::spawn(move || {
+                let mut buf = [0u8; 4096];
+                loop {
+                    // Wait for output with a timeout, so that the receiver being dropped is noticed
+                    // even if nothing is ever written to the terminal again.
+                    let mut pollfd = libc::pollfd {
+                        fd: master.as_raw_fd(),
+                        events: libc::POLLIN,
+                        revents: 0,
+                    };
+                    let ret = unsafe { libc::poll(&mut pollfd, 1, PTY_POLL_INTERVAL_MS) };
+                    if sender.is_closed() {
+                        break;
+                    }
+                    if ret == 0 {
+                        continue;
+                    } else if ret == -1 {
+                        let error = std::io::Error::last_os_error();
+                        if error.kind() == std::io::ErrorKind::Interrupted {
+                            continue;
+                        }
+                        let _ = sender.send(Err(error));
+                        break;
+                    }
+
+                    match master.read(&mut buf) {
+                        Ok(0) => break,
+                        Ok(bytes_read) => {
+                            if sender.send(Ok(buf[..bytes_read].to_vec())).is_err() {
+                                break;
+                            }
+                        }
+                        Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {}
+                        // On Linux, reading from the master returns EIO once the terminal has been
+                        // closed by every other process, rather than EOF.
+                        Err(error) if error.raw_os_error() == Some(libc::EIO) => break,
+                        Err(error) => {
+                            let _ = sender.send(Err(error));
+                            break;
+                        }
+                    }
+                }
+            });
+            receiver
+        }
+    }
+
+    /// Waits for the child to exit and, if `process_group` is set, for every other process in
+    /// that group to exit as well.
+    pub(super) async fn wait_for_exit(
+        child: &mut Child,
+        process_group: Option<u32>,
+    ) -> std::io::Result<std::process::ExitStatus> {
+        let exit_status = child.wait().await?;
+        if let Some(pgid) = process_group {
+            wait_for_process_group(pgid).await;
+        }
+        Ok(exit_status)
+    }
+
+    async fn wait_for_process_group(pgid: u32) {
+        // There's no way to be notified when a process group becomes empty, so poll for it.
+        const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+        loop {
+            // Signal 0 doesn't send anything, but checks whether any process in the group exists.
+            let ret = unsafe { libc::kill(-(pgid as i32), 0) };
+            if ret == -1 && std::io::Error::last_os_error().raw_os_error() == Some(libc::ESRCH) {
+                break;
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Returns the processes still running in the process group of a test that has finished.
+    ///
+    /// This must be called as soon as the test finishes, and after the test process has been
+    /// waited for.
+    pub(super) async fn find_leaked_processes<'a>(
+        test_instance: TestInstance<'a>,
+        pgid: u32,
+    ) -> Vec<LeakedProcess<'a>> {
+        // A process group ID can't be reused while any process is still in the group, so if the
+        // group is empty there's nothing to report. This avoids listing processes for most tests.
+        let ret = unsafe { libc::kill(-(pgid as i32), 0) };
+        if ret == -1 && std::io::Error::last_os_error().raw_os_error() == Some(libc::ESRCH) {
+            return Vec::new();
+        }
+
+        let processes = match list_processes().await {
+            Ok(processes) => processes,
+            Err(error) => {
+                log::warn!(
+                    target: "nextest-runner",
+                    "error listing processes to detect leaked processes: {error}",
+                );
+                return Vec::new();
+            }
+        };
+
+        let processes: Vec<_> = processes
+            .into_iter()
+            .filter(|process| process.pgid == pgid)
+            .collect();
+        // The test process led the group and has already exited. If a process with the group's ID
+        // is running, the group emptied out after the check above and its ID was reused.
+        if processes.iter().any(|process| process.pid == pgid) {
+            return Vec::new();
+        }
+
+        processes
+            .into_iter()
+            .map(|process| LeakedProcess {
+                test_instance,
+                pid: process.pid,
+                command: process.command,
+            })
+            .collect()
+    }
+
+    #[derive(Debug, Eq, PartialEq)]
+    pub(super) struct ProcessInfo {
+        pub(super) pid: u32,
+        pub(super) pgid: u32,
+        pub(super) command: String,
+    }
+
+    pub(super) async fn list_processes() -> std::io::Result<Vec<ProcessInfo>> {
+        // ps is available on every Unix platform nextest supports, unlike /proc.
+        let output = spawn_and_collect_output(
+            tokio::process::Command::new("ps")
+                .args(["-A", "-o", "pid=", "-o", "pgid=", "-o", "args="])
+                .stdin(Stdio::null()),
+        )
+        .await?;
+        if !output.status.success() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("ps exited with {}", output.status),
+            ));
+        }
+        Ok(parse_ps_output(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    pub(super) fn parse_ps_output(output: &str) -> Vec<ProcessInfo> {
+        output
+            .lines()
+            .filter_map(|line| {
+                let line = line.trim_start();
+                let (pid, rest) = line.split_once(char::is_whitespace)?;
+                let (pgid, command) = rest.trim_start().split_once(char::is_whitespace)?;
+                Some(ProcessInfo {
+                    pid: pid.parse().ok()?,
+                    pgid: pgid.parse().ok()?,
+                    command: command.trim().to_owned(),
+                })
+            })
+            .collect()
+    }
+
     pub(super) async fn terminate_child(
         child: &mut Child,
         mode: TerminateMode,
-        forward_receiver: &mut tokio::sync::broadcast::Receiver<SignalForwardEvent>,
+        forward_receiver: &mut tokio::sync::broadcast::Receiver<SignalForwardEvent<'_>>,
         _job: Option<&Job>,
+        process_group: Option<u32>,
     ) {
-        if let Some(pid) = child.id() {
+        // If the test process has already exited but its process group is being waited on, the
+        // rest of the group still needs to be terminated.
+        if let Some(pid) = child.id().or(process_group) {
             let pid = pid as i32;
             let mut grace_period = Duration::from_secs(10);
             let term_signal = match mode {
@@ -1760,7 +4112,7 @@ mod imp {
             tokio::pin!(sleep);
             loop {
                 tokio::select! {
-                    _ = child.wait() => {
+                    _ = wait_for_exit(child, process_group) => {
                         // The process exited.
                         break;
                     }
@@ -1778,11 +4130,14 @@ mod imp {
                             }
                             SignalForwardEvent::Continue => {
                                 // Possible to receive a Continue at the beginning of execution.
-                                if !sleep.is_paused() {
+                                if sleep.is_paused() {
                                     sleep.as_mut().resume();
                                 }
                                 imp::job_control_child(child, JobControlEvent::Continue);
                             }
+                            SignalForwardEvent::Info(_) => {
+                                // Tests that are being terminated aren't included in snapshots.
+                            }
                             SignalForwardEvent::Shutdown(_) => {
                                 // Receiving a shutdown signal while in this state always means kill
                                 // immediately.
@@ -1844,6 +4199,779 @@ mod tests {
         assert_eq!(runner.inner.test_threads, 1, "tests run serially");
     }
 
+    #[test]
+    fn slot_assigner_reuses_freed_slots() {
+        let slots = SlotAssigner::new(3);
+        assert_eq!(slots.acquire(), 0);
+        assert_eq!(slots.acquire(), 1);
+        assert_eq!(slots.acquire(), 2);
+
+        // A retry that starts after slot 1 is freed runs in slot 1, not the slot it ran in before.
+        slots.release(1);
+        assert_eq!(slots.acquire(), 1);
+        slots.release(0);
+        slots.release(2);
+        assert_eq!(slots.acquire(), 0);
+    }
+
+    #[test]
+    fn run_completes_without_signal_handler() {
+        let test_list = TestList::empty();
+        let config = NextestConfig::default_config("/fake/dir");
+        let profile = config.profile(NextestConfig::DEFAULT_PROFILE).unwrap();
+        let build_platforms = BuildPlatforms::new(None).unwrap();
+        let mut runner = TestRunnerBuilder::default()
+            .build(
+                &test_list,
+                profile.apply_build_platforms(&build_platforms),
+                SignalHandlerKind::Noop,
+                DoubleSpawnInfo::disabled(),
+                TargetRunner::empty(),
+            )
+            .unwrap();
+        // Simulate a sandboxed environment where signal handlers can't be installed.
+        runner.handler = SignalHandler::unavailable(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            "operation not permitted",
+        ));
+
+        let mut run_finished = false;
+        let run_stats = runner.execute(|event| {
+            if let TestEvent::RunFinished { .. } = event {
+                run_finished = true;
+            }
+        });
+        assert!(
+            run_finished,
+            "run completes with a handler that never fires"
+        );
+        assert!(run_stats.is_success(), "empty run is successful");
+    }
+
+    #[test]
+    fn backoff_iter_fixed() {
+        let delays: Vec<_> = BackoffIter::new(RetryPolicy::Fixed {
+            count: 3,
+            delay: Duration::from_millis(500),
+            jitter: false,
+        })
+        .collect();
+        assert_eq!(
+            delays,
+            vec![Duration::from_millis(500); 3],
+            "fixed backoff produces a constant delay for each retry"
+        );
+
+        let delays: Vec<_> = BackoffIter::new(RetryPolicy::new_without_delay(2)).collect();
+        assert_eq!(
+            delays,
+            vec![Duration::ZERO; 2],
+            "no delay means immediate retries"
+        );
+    }
+
+    #[test]
+    fn backoff_iter_exponential() {
+        let delays: Vec<_> = BackoffIter::new(RetryPolicy::Exponential {
+            count: 4,
+            delay: Duration::from_secs(1),
+            jitter: false,
+            max_delay: Some(Duration::from_secs(5)),
+            reset_backoff_on_change: false,
+        })
+        .collect();
+        assert_eq!(
+            delays,
+            vec![
+                Duration::from_secs(1),
+                Duration::from_secs(2),
+                Duration::from_secs(4),
+                Duration::from_secs(5),
+            ],
+            "exponential backoff doubles and is capped at max-delay"
+        );
+    }
+
+    #[test]
+    fn backoff_iter_reset_on_change() {
+        let make_status = |exit_code| ExecuteStatus {
+            retry_data: RetryData {
+                attempt: 1,
+                total_attempts: 5,
+            },
+            slot: 0,
+            stdout: Bytes::new(),
+            stderr: Bytes::new(),
+            result: ExecutionResult::Fail {
+                abort_status: None,
+                leaked: false,
+            },
+            exit_code: Some(exit_code),
+            start_time: SystemTime::UNIX_EPOCH,
+            time_taken: Duration::ZERO,
+            phases: None,
+            is_slow: false,
+            no_assertions: false,
+            trace_file: None,
+            delay_before_start: Duration::ZERO,
+        };
+        let delays = |reset_backoff_on_change| {
+            let mut backoff_iter = BackoffIter::new(RetryPolicy::Exponential {
+                count: 4,
+                delay: Duration::from_secs(1),
+                jitter: false,
+                max_delay: None,
+                reset_backoff_on_change,
+            });
+            [1, 1, 2, 2]
+                .into_iter()
+                .map(|exit_code| {
+                    backoff_iter.record_failure(&make_status(exit_code));
+                    backoff_iter.next().expect("retries remaining")
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            delays(true),
+            vec![
+                Duration::from_secs(1),
+                Duration::from_secs(2),
+                Duration::from_secs(1),
+                Duration::from_secs(2),
+            ],
+            "the delay is reset when the exit code changes"
+        );
+        assert_eq!(
+            delays(false),
+            vec![
+                Duration::from_secs(1),
+                Duration::from_secs(2),
+                Duration::from_secs(4),
+                Duration::from_secs(8),
+            ],
+            "without reset-backoff-on-change, the delay keeps growing"
+        );
+    }
+
+    #[cfg(all(unix, process_group))]
+    #[test]
+    fn terminate_child_exits_within_grace_period() {
+        let runtime = Runtime::new().unwrap();
+        runtime.block_on(async {
+            // This process exits with a distinctive code on SIGTERM.
+            let mut cmd = std::process::Command::new("sh");
+            cmd.args(["-c", "trap 'exit 42' TERM; while true; do sleep 0.05; done"]);
+            imp::set_process_group(&mut cmd);
+            let mut child = tokio::process::Command::from(cmd).spawn().unwrap();
+            // Give the shell some time to set up the trap.
+            tokio::time::sleep(Duration::from_millis(200)).await;
+
+            let (_forward_sender, mut forward_receiver) = tokio::sync::broadcast::channel(1);
+            let start = std::time::Instant::now();
+            imp::terminate_child(
+                &mut child,
+                TerminateMode::Timeout(Duration::from_secs(60)),
+                &mut forward_receiver,
+                None,
+                None,
+            )
+            .await;
+            assert!(
+                start.elapsed() < Duration::from_secs(30),
+                "terminate_child returned as soon as the process exited"
+            );
+
+            let exit_status = child.wait().await.unwrap();
+            assert_eq!(
+                exit_status.code(),
+                Some(42),
+                "process exited by itself during the grace period rather than being killed"
+            );
+        });
+    }
+
+    #[cfg(all(unix, process_group))]
+    #[test]
+    fn wait_for_exit_waits_for_process_group() {
+        let runtime = Runtime::new().unwrap();
+        runtime.block_on(async {
+            let temp_dir = camino_tempfile::tempdir().unwrap();
+            let marker = temp_dir.path().join("marker");
+
+            // The shell exits immediately, leaving behind a forked child that's still running.
+            let spawn = || {
+                let mut cmd = std::process::Command::new("sh");
+                cmd.args([
+                    "-c",
+                    &format!("(sleep 0.5; touch '{marker}') > /dev/null 2>&1 & exit 0"),
+                ]);
+                imp::set_process_group(&mut cmd);
+                tokio::process::Command::from(cmd).spawn().unwrap()
+            };
+
+            let mut child = spawn();
+            let process_group = child.id();
+            let exit_status = imp::wait_for_exit(&mut child, process_group).await.unwrap();
+            assert!(exit_status.success(), "shell exited successfully");
+            assert!(
+                marker.exists(),
+                "forked child finished before wait_for_exit returned"
+            );
+
+            std::fs::remove_file(&marker).unwrap();
+            let mut child = spawn();
+            imp::wait_for_exit(&mut child, None).await.unwrap();
+            assert!(
+                !marker.exists(),
+                "without a process group, wait_for_exit doesn't wait for forked children"
+            );
+            // Let the forked child finish before the temp dir is removed.
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn pty_output_is_a_tty() {
+        let runtime = Runtime::new().unwrap();
+        runtime.block_on(async {
+            let pty = imp::Pty::open().unwrap();
+            let mut cmd = std::process::Command::new("sh");
+            cmd.args([
+                "-c",
+                "if [ -t 1 ] && [ -t 2 ]; then echo 'stdout is a tty'; fi; \
+                 echo 'to stderr' >&2",
+            ]);
+            cmd.stdin(Stdio::null())
+                .stdout(pty.stdio().unwrap())
+                .stderr(pty.stdio().unwrap());
+            let mut child = tokio::process::Command::from(cmd).spawn().unwrap();
+
+            let mut receiver = pty.read_output();
+            let mut output = Vec::new();
+            while let Some(chunk) = receiver.recv().await {
+                output.extend_from_slice(&chunk.unwrap());
+            }
+            assert!(
+                child.wait().await.unwrap().success(),
+                "shell exited successfully"
+            );
+
+            assert_eq!(
+                String::from_utf8_lossy(&output),
+                "stdout is a tty\nto stderr\n",
+                "stdout and stderr are merged, in order, without newline translation"
+            );
+        });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn pty_reader_stops_when_receiver_dropped() {
+        let runtime = Runtime::new().unwrap();
+        runtime.block_on(async {
+            let temp_dir = camino_tempfile::tempdir().unwrap();
+            let result = temp_dir.path().join("result");
+
+            // The shell exits immediately, leaving behind a forked child that keeps the terminal
+            // open without writing to it for a while.
+            let pty = imp::Pty::open().unwrap();
+            let mut cmd = std::process::Command::new("sh");
+            cmd.args([
+                "-c",
+                &format!(
+                    "(sleep 1; if echo late 2> /dev/null; then echo wrote > '{result}'; \
+                     else echo closed > '{result}'; fi) & exit 0"
+                ),
+            ]);
+            cmd.stdin(Stdio::null())
+                .stdout(pty.stdio().unwrap())
+                .stderr(pty.stdio().unwrap());
+            let mut child = tokio::process::Command::from(cmd).spawn().unwrap();
+            let receiver = pty.read_output();
+            assert!(
+                child.wait().await.unwrap().success(),
+                "shell exited successfully"
+            );
+
+            // This is what happens once the leak timeout expires.
+            drop(receiver);
+
+            let mut contents = String::new();
+            for _ in 0..100 {
+                if let Ok(c) = std::fs::read_to_string(&result) {
+                    contents = c;
+                    if contents.ends_with('\n') {
+                        break;
+                    }
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+            assert_eq!(
+                contents, "closed\n",
+                "the reader thread closed the terminal without waiting for the leaked process"
+            );
+        });
+    }
+
+    #[cfg(all(unix, process_group))]
+    #[test]
+    fn list_processes_finds_leaked_process() {
+        let runtime = Runtime::new().unwrap();
+
+        // The shell exits immediately, leaving behind a child in its process group.
+        let mut cmd = std::process::Command::new("sh");
+        cmd.args(["-c", "sleep 30 > /dev/null 2>&1 & exit 0"]);
+        imp::set_process_group(&mut cmd);
+        let mut child = cmd.spawn().unwrap();
+        let pgid = child.id();
+        assert!(child.wait().unwrap().success(), "shell exited successfully");
+
+        // The forked child might not have exec'd sleep yet, so poll for it.
+        let mut leaked = Vec::new();
+        for _ in 0..100 {
+            leaked = runtime
+                .block_on(imp::list_processes())
+                .expect("processes listed")
+                .into_iter()
+                .filter(|process| process.pgid == pgid)
+                .collect();
+            if leaked.iter().any(|process| process.command == "sleep 30") {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        // Clean up the leaked process before asserting.
+        unsafe { libc::kill(-(pgid as i32), libc::SIGKILL) };
+
+        assert_eq!(
+            leaked.len(),
+            1,
+            "exactly one process left in the group: {leaked:?}"
+        );
+        assert_eq!(leaked[0].command, "sleep 30");
+        assert_ne!(leaked[0].pid, pgid, "leaked process isn't the shell");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn parse_ps_output() {
+        let output = "    1     1 /sbin/init splash\n 4242  4240 sleep  30 \nnot a process line\n";
+        assert_eq!(
+            imp::parse_ps_output(output),
+            vec![
+                imp::ProcessInfo {
+                    pid: 1,
+                    pgid: 1,
+                    command: "/sbin/init splash".to_owned(),
+                },
+                imp::ProcessInfo {
+                    pid: 4242,
+                    pgid: 4240,
+                    command: "sleep  30".to_owned(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn retry_budget() {
+        let secs = Duration::from_secs;
+
+        // Plenty of time left.
+        assert!(retry_fits_in_budget(
+            secs(60),
+            Some(secs(5)),
+            Duration::ZERO
+        ));
+        assert!(retry_fits_in_budget(secs(60), Some(secs(5)), secs(10)));
+        // Near the deadline, the retry would be cut off.
+        assert!(!retry_fits_in_budget(
+            secs(4),
+            Some(secs(5)),
+            Duration::ZERO
+        ));
+        assert!(!retry_fits_in_budget(secs(10), Some(secs(5)), secs(6)));
+        // A retry that would finish exactly at the deadline is still run.
+        assert!(retry_fits_in_budget(secs(5), Some(secs(5)), Duration::ZERO));
+
+        // With an unknown duration, retry as long as there's time left after the delay.
+        assert!(retry_fits_in_budget(
+            Duration::from_millis(1),
+            None,
+            Duration::ZERO
+        ));
+        assert!(!retry_fits_in_budget(Duration::ZERO, None, Duration::ZERO));
+        assert!(!retry_fits_in_budget(secs(1), None, secs(1)));
+
+        let budget = GlobalBudget::new(secs(3600));
+        let remaining = budget.remaining();
+        assert!(
+            remaining <= secs(3600) && remaining > secs(3590),
+            "remaining time is close to the full budget: {remaining:?}"
+        );
+        assert_eq!(
+            GlobalBudget::new(Duration::ZERO).remaining(),
+            Duration::ZERO,
+            "remaining time saturates at zero"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn toggle_pause() {
+        let mut events = Vec::new();
+        let mut ctx = CallbackContext::new(
+            |event: TestEvent<'_>| {
+                events.push(event);
+                Ok::<_, std::convert::Infallible>(())
+            },
+            TestObservers::default(),
+            Uuid::nil(),
+            0,
+            FailFast { max_failures: None },
+            GroupSaturation::new([]),
+            10,
+            KnownFailures::new(),
+        );
+        let toggle = |ctx: &mut CallbackContext<'_, _, _>| {
+            assert!(
+                matches!(
+                    ctx.handle_event(InternalEvent::Signal(SignalEvent::TogglePause)),
+                    Ok(None)
+                ),
+                "toggling the pause state succeeds"
+            );
+            ctx.is_paused()
+        };
+
+        assert!(toggle(&mut ctx), "first signal pauses the run");
+        assert!(!toggle(&mut ctx), "second signal resumes the run");
+        assert!(toggle(&mut ctx), "third signal pauses the run again");
+
+        assert!(ctx.begin_cancel(CancelReason::Signal).is_ok());
+        assert!(!ctx.is_paused(), "canceling the run resumes it");
+        assert!(
+            !toggle(&mut ctx),
+            "signals are ignored once the run is canceled"
+        );
+        drop(ctx);
+
+        let kinds: Vec<_> = events
+            .iter()
+            .map(|event| match event {
+                TestEvent::RunSchedulingPaused { .. } => "paused",
+                TestEvent::RunSchedulingResumed { .. } => "resumed",
+                TestEvent::RunBeginCancel { .. } => "cancel",
+                other => panic!("unexpected event {other:?}"),
+            })
+            .collect();
+        assert_eq!(kinds, ["paused", "resumed", "paused", "cancel"]);
+    }
+
+    #[test]
+    fn run_statistics_percentiles() {
+        let ms = Duration::from_millis;
+        let statistics = |durations: &[u64]| RunStatistics {
+            durations: durations.iter().map(|&d| ms(d)).collect(),
+            slowest: Vec::new(),
+        };
+
+        let empty = statistics(&[]);
+        assert_eq!(empty.test_count(), 0);
+        assert_eq!(empty.mean(), None);
+        assert_eq!(empty.percentile(50), None);
+
+        let one = statistics(&[40]);
+        assert_eq!(one.mean(), Some(ms(40)));
+        for percentile in [0, 50, 90, 99, 100] {
+            assert_eq!(one.percentile(percentile), Some(ms(40)), "p{percentile}");
+        }
+
+        // With a small number of tests, percentiles are always the time taken by an actual test.
+        let three = statistics(&[10, 20, 60]);
+        assert_eq!(three.mean(), Some(ms(30)));
+        assert_eq!(three.percentile(0), Some(ms(10)));
+        assert_eq!(three.percentile(33), Some(ms(10)));
+        assert_eq!(three.percentile(34), Some(ms(20)));
+        assert_eq!(three.percentile(50), Some(ms(20)));
+        assert_eq!(three.percentile(90), Some(ms(60)));
+        assert_eq!(three.percentile(99), Some(ms(60)));
+        assert_eq!(three.percentile(100), Some(ms(60)));
+
+        let hundred = statistics(&(1..=100).collect::<Vec<_>>());
+        assert_eq!(hundred.percentile(50), Some(ms(50)));
+        assert_eq!(hundred.percentile(90), Some(ms(90)));
+        assert_eq!(hundred.percentile(99), Some(ms(99)));
+    }
+
+    #[test]
+    fn group_saturation() {
+        let ms = Duration::from_millis;
+        let group = CustomTestGroup::new("serial".into()).unwrap();
+        let mut saturation = GroupSaturation::new([(group.clone(), 2)]);
+
+        // Filling up the group produces a single saturated event.
+        assert!(saturation.test_started(&group, 1, ms(0)).is_none());
+        match saturation.test_started(&group, 1, ms(10)) {
+            Some(TestEvent::TestGroupSaturated {
+                max_threads,
+                run_elapsed,
+                ..
+            }) => {
+                assert_eq!(max_threads, 2);
+                assert_eq!(run_elapsed, ms(10));
+            }
+            other => panic!("expected saturated event, found {other:?}"),
+        }
+
+        // A test finishing and being replaced right away is coalesced.
+        saturation.test_finished(&group, 1, ms(50));
+        assert_eq!(
+            saturation.next_deadline(),
+            Some(ms(50) + GROUP_FREED_COALESCE_WINDOW)
+        );
+        assert!(saturation.take_freed(ms(55), false).is_empty());
+        assert!(saturation.test_started(&group, 1, ms(60)).is_none());
+        assert_eq!(saturation.next_deadline(), None);
+
+        // Freeing up the group is only reported once the coalescing window has passed, with the
+        // time at which it was freed.
+        saturation.test_finished(&group, 1, ms(100));
+        saturation.test_finished(&group, 1, ms(120));
+        assert!(saturation.take_freed(ms(150), false).is_empty());
+        let events = saturation.take_freed(ms(100) + GROUP_FREED_COALESCE_WINDOW, false);
+        match events.as_slice() {
+            [TestEvent::TestGroupFreed {
+                run_elapsed,
+                saturated_for,
+                ..
+            }] => {
+                assert_eq!(*run_elapsed, ms(100));
+                assert_eq!(*saturated_for, ms(90));
+            }
+            other => panic!("expected a single freed event, found {other:?}"),
+        }
+        assert_eq!(saturation.next_deadline(), None);
+
+        // A test that requires more threads than the group has saturates it on its own, and
+        // pending events are reported when flushed.
+        assert!(saturation.test_started(&group, 4, ms(200)).is_some());
+        saturation.test_finished(&group, 4, ms(300));
+        assert_eq!(saturation.take_freed(ms(300), true).len(), 1);
+
+        // Tests in other groups are ignored.
+        let other = CustomTestGroup::new("other".into()).unwrap();
+        assert!(saturation.test_started(&other, 8, ms(400)).is_none());
+        saturation.test_finished(&other, 8, ms(500));
+        assert_eq!(saturation.next_deadline(), None);
+    }
+
+    #[test]
+    fn setup_env_parsing() {
+        let stdout =
+            b"Starting database...\nDB_PORT=5432\nDB_URL=postgres://localhost:5432/db?a=b\n\
+            =empty-key\nNOT A VAR=1\nEMPTY=\n";
+        assert_eq!(
+            parse_setup_env(stdout),
+            vec![
+                ("DB_PORT".to_owned(), "5432".to_owned()),
+                (
+                    "DB_URL".to_owned(),
+                    "postgres://localhost:5432/db?a=b".to_owned()
+                ),
+                ("EMPTY".to_owned(), "".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn expect_fail_classification() {
+        let fail = ExecutionResult::Fail {
+            abort_status: None,
+            leaked: false,
+        };
+        assert_eq!(fail.expect_fail(), ExecutionResult::ExpectedFail);
+        assert_eq!(
+            ExecutionResult::Pass.expect_fail(),
+            ExecutionResult::UnexpectedPass
+        );
+        assert_eq!(
+            ExecutionResult::Leak.expect_fail(),
+            ExecutionResult::UnexpectedPass
+        );
+        assert_eq!(
+            ExecutionResult::LeakFail.expect_fail(),
+            ExecutionResult::UnexpectedPass
+        );
+        // Errors and timeouts aren't the failure the test is expected to have.
+        assert_eq!(
+            ExecutionResult::ExecFail.expect_fail(),
+            ExecutionResult::ExecFail
+        );
+        assert_eq!(
+            ExecutionResult::Timeout.expect_fail(),
+            ExecutionResult::Timeout
+        );
+
+        assert!(ExecutionResult::ExpectedFail.is_success());
+        assert!(!ExecutionResult::UnexpectedPass.is_success());
+    }
+
+    #[test]
+    fn execute_status_is_retryable() {
+        let make_status = |result, exit_code| ExecuteStatus {
+            retry_data: RetryData {
+                attempt: 1,
+                total_attempts: 3,
+            },
+            slot: 0,
+            stdout: Bytes::new(),
+            stderr: Bytes::new(),
+            result,
+            exit_code,
+            start_time: SystemTime::UNIX_EPOCH,
+            time_taken: Duration::ZERO,
+            phases: None,
+            is_slow: false,
+            no_assertions: false,
+            trace_file: None,
+            delay_before_start: Duration::ZERO,
+        };
+        let fail = ExecutionResult::Fail {
+            abort_status: None,
+            leaked: false,
+        };
+
+        assert!(
+            make_status(fail, Some(101)).is_retryable(&[]),
+            "empty list => any failure is retried"
+        );
+        assert!(
+            make_status(ExecutionResult::Timeout, None).is_retryable(&[]),
+            "empty list => timeouts are retried"
+        );
+        assert!(
+            make_status(fail, Some(75)).is_retryable(&[75, 111]),
+            "exit code in list => retried"
+        );
+        assert!(
+            !make_status(fail, Some(101)).is_retryable(&[75, 111]),
+            "exit code not in list => not retried"
+        );
+        assert!(
+            !make_status(ExecutionResult::Timeout, None).is_retryable(&[75]),
+            "timeout => never matches list"
+        );
+        assert!(
+            !make_status(ExecutionResult::ExecFail, None).is_retryable(&[75]),
+            "exec fail => never matches list"
+        );
+        #[cfg(unix)]
+        assert!(
+            !make_status(
+                ExecutionResult::Fail {
+                    abort_status: Some(AbortStatus::UnixSignal(libc::SIGSEGV)),
+                    leaked: false,
+                },
+                None,
+            )
+            .is_retryable(&[75]),
+            "signal-terminated => never matches list"
+        );
+    }
+
+    #[test]
+    fn execute_status_finish_reason() {
+        let make_status = |result, exit_code| ExecuteStatus {
+            retry_data: RetryData {
+                attempt: 1,
+                total_attempts: 1,
+            },
+            slot: 0,
+            stdout: Bytes::new(),
+            stderr: Bytes::new(),
+            result,
+            exit_code,
+            start_time: SystemTime::UNIX_EPOCH,
+            time_taken: Duration::ZERO,
+            phases: None,
+            is_slow: false,
+            no_assertions: false,
+            trace_file: None,
+            delay_before_start: Duration::ZERO,
+        };
+
+        assert_eq!(
+            make_status(ExecutionResult::Pass, Some(0)).finish_reason(),
+            FinishReason::Pass,
+        );
+        assert_eq!(
+            make_status(ExecutionResult::Leak, Some(0)).finish_reason(),
+            FinishReason::Leak,
+        );
+        assert_eq!(
+            make_status(
+                ExecutionResult::Fail {
+                    abort_status: None,
+                    leaked: false,
+                },
+                Some(101),
+            )
+            .finish_reason(),
+            FinishReason::Failed {
+                exit_code: Some(101),
+                leaked: false,
+            },
+        );
+        assert_eq!(
+            make_status(
+                ExecutionResult::Fail {
+                    abort_status: None,
+                    leaked: true,
+                },
+                Some(1),
+            )
+            .finish_reason(),
+            FinishReason::Failed {
+                exit_code: Some(1),
+                leaked: true,
+            },
+        );
+        #[cfg(unix)]
+        assert_eq!(
+            make_status(
+                ExecutionResult::Fail {
+                    abort_status: Some(AbortStatus::UnixSignal(libc::SIGSEGV)),
+                    leaked: false,
+                },
+                None,
+            )
+            .finish_reason(),
+            FinishReason::Aborted {
+                abort_status: AbortStatus::UnixSignal(libc::SIGSEGV),
+                leaked: false,
+            },
+        );
+        assert_eq!(
+            make_status(ExecutionResult::Timeout, None).finish_reason(),
+            FinishReason::Timeout,
+        );
+        assert_eq!(
+            make_status(ExecutionResult::ExecFail, None).finish_reason(),
+            FinishReason::ExecFail,
+        );
+        assert_eq!(
+            make_status(ExecutionResult::ExpectedFail, Some(101)).finish_reason(),
+            FinishReason::ExpectedFail,
+        );
+        assert_eq!(
+            make_status(ExecutionResult::UnexpectedPass, Some(0)).finish_reason(),
+            FinishReason::UnexpectedPass,
+        );
+    }
+
     #[test]
     fn test_is_success() {
         assert!(RunStats::default().is_success(), "empty run => success");
@@ -1963,4 +5091,70 @@ mod tests {
             "skipped => not considered a failure"
         );
     }
+
+    #[test]
+    fn test_final_status() {
+        let make_status = |attempt, result| ExecuteStatus {
+            retry_data: RetryData {
+                attempt,
+                total_attempts: 2,
+            },
+            slot: 0,
+            stdout: Bytes::new(),
+            stderr: Bytes::new(),
+            result,
+            exit_code: None,
+            start_time: SystemTime::UNIX_EPOCH,
+            time_taken: Duration::ZERO,
+            phases: None,
+            is_slow: false,
+            no_assertions: false,
+            trace_file: None,
+            delay_before_start: Duration::ZERO,
+        };
+        let fail = ExecutionResult::Fail {
+            abort_status: None,
+            leaked: false,
+        };
+
+        // A test that passes on its second attempt is flaky.
+        let mut run_stats = RunStats {
+            initial_run_count: 2,
+            ..RunStats::default()
+        };
+        run_stats.on_test_finished(&ExecutionStatuses::new(vec![make_status(
+            1,
+            ExecutionResult::Pass,
+        )]));
+        run_stats.on_test_finished(&ExecutionStatuses::new(vec![
+            make_status(1, fail),
+            make_status(2, ExecutionResult::Pass),
+        ]));
+        assert_eq!(run_stats.passed, 2);
+        assert_eq!(run_stats.flaky, 1, "test passed on retry => flaky");
+        assert!(run_stats.is_success(), "flaky tests still pass");
+
+        assert_eq!(
+            run_stats.final_status(false),
+            FinalRunStatus::Success,
+            "flaky tests aren't failures by default"
+        );
+        assert_eq!(
+            run_stats.final_status(true),
+            FinalRunStatus::Flaky,
+            "flaky-as-failure => flaky"
+        );
+
+        run_stats.failed = 1;
+        assert_eq!(
+            run_stats.final_status(true),
+            FinalRunStatus::Failed,
+            "failures take precedence over flaky tests"
+        );
+        assert_eq!(
+            RunStats::default().final_status(true),
+            FinalRunStatus::Success,
+            "no flaky tests => success"
+        );
+    }
 }
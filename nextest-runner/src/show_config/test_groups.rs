@@ -22,6 +22,8 @@ use std::{
 pub struct ShowTestGroups<'a> {
     test_list: &'a TestList<'a>,
     indexed_overrides: BTreeMap<TestGroup, IndexMap<OverrideId, ShowTestGroupsData<'a>>>,
+    // Tests put in custom groups by the groups' own match filters.
+    group_matches: BTreeMap<CustomTestGroup, TestListDisplayFilter<'a>>,
     test_group_config: &'a BTreeMap<CustomTestGroup, TestGroupConfig>,
     // This is Some iff settings.show_default is true.
     non_overrides: Option<TestListDisplayFilter<'a>>,
@@ -61,6 +63,7 @@ impl<'a> ShowTestGroups<'a> {
                         .then(|| (group, IndexMap::new()))
                 })
                 .collect();
+        let mut group_matches = BTreeMap::new();
         let mut non_overrides = settings.show_default.then(TestListDisplayFilter::new);
 
         for suite in test_list.iter() {
@@ -81,6 +84,16 @@ impl<'a> ShowTestGroups<'a> {
                             .or_insert_with(|| ShowTestGroupsData::new(source));
                         data.matching_tests.insert(&suite.binary_id, test_name);
                     }
+                    SettingSource::TestGroup => {
+                        if let TestGroup::Custom(group) = test_group {
+                            if settings.mode.matches_group(test_group) {
+                                group_matches
+                                    .entry(group.clone())
+                                    .or_insert_with(TestListDisplayFilter::new)
+                                    .insert(&suite.binary_id, test_name);
+                            }
+                        }
+                    }
                     SettingSource::Profile => {
                         if let Some(non_overrides) = non_overrides.as_mut() {
                             if settings.mode.matches_group(&TestGroup::Global) {
@@ -95,6 +108,7 @@ impl<'a> ShowTestGroups<'a> {
         Self {
             test_list,
             indexed_overrides,
+            group_matches,
             test_group_config: profile.test_group_config(),
             non_overrides,
         }
@@ -188,6 +202,28 @@ impl<'a> ShowTestGroups<'a> {
                 writer = inner_writer.into_inner();
             }
 
+            // Show tests that are in a custom group because of the group's match filter.
+            if let TestGroup::Custom(group) = test_group {
+                if let Some(matching_tests) = self.group_matches.get(group) {
+                    any_printed = true;
+                    write!(writer, "  * from the group's match filter")?;
+                    if let Some(filter) = &self.test_group_config[group].match_filter {
+                        write!(writer, " {}", QuotedDisplay(filter).style(styles.filter))?;
+                    }
+                    writeln!(writer, ":")?;
+
+                    let mut inner_writer = indent_write::io::IndentWriter::new(INDENT, writer);
+                    self.test_list.write_human_with_filter(
+                        matching_tests,
+                        &mut inner_writer,
+                        false,
+                        colorize,
+                    )?;
+                    inner_writer.flush()?;
+                    writer = inner_writer.into_inner();
+                }
+            }
+
             // Also show tests that don't match an override if they match the global config below.
             if test_group == &TestGroup::Global {
                 if let Some(non_overrides) = &self.non_overrides {
@@ -3,7 +3,12 @@
 
 //! Support for handling signals in nextest.
 
-use crate::errors::SignalHandlerSetupError;
+use std::time::{Duration, Instant};
+
+/// How soon after an interrupt a second one escalates to killing running tests.
+///
+/// Other shutdown signals, such as SIGTERM, always escalate on the second signal.
+pub(crate) const FORCE_KILL_WINDOW: Duration = Duration::from_secs(5);
 
 /// The kind of signal handling to set up for a test run.
 ///
@@ -13,6 +18,9 @@ use crate::errors::SignalHandlerSetupError;
 pub enum SignalHandlerKind {
     /// The standard signal handler. Capture interrupt and termination signals depending on the
     /// platform.
+    ///
+    /// If signal handlers can't be installed, for example in some sandboxed environments, a
+    /// warning is logged and the run continues without them.
     Standard,
 
     /// A no-op signal handler. Useful for tests.
@@ -20,10 +28,10 @@ pub enum SignalHandlerKind {
 }
 
 impl SignalHandlerKind {
-    pub(crate) fn build(self) -> Result<SignalHandler, SignalHandlerSetupError> {
+    pub(crate) fn build(self) -> SignalHandler {
         match self {
             Self::Standard => SignalHandler::new(),
-            Self::Noop => Ok(SignalHandler::noop()),
+            Self::Noop => SignalHandler::noop(),
         }
     }
 }
@@ -36,12 +44,17 @@ pub(crate) struct SignalHandler {
 
 impl SignalHandler {
     /// Creates a new `SignalHandler` that handles Ctrl-C and other signals.
+    ///
+    /// If signal handlers can't be installed, this logs a warning and returns a handler that never
+    /// receives any signals.
     #[cfg(any(unix, windows))]
-    pub(crate) fn new() -> Result<Self, SignalHandlerSetupError> {
-        let signals = imp::Signals::new()?;
-        Ok(Self {
-            signals: Some(signals),
-        })
+    pub(crate) fn new() -> Self {
+        match imp::Signals::new() {
+            Ok(signals) => Self {
+                signals: Some(signals),
+            },
+            Err(err) => Self::unavailable(err),
+        }
     }
 
     /// Creates a new `SignalReceiver` that does nothing.
@@ -49,6 +62,15 @@ impl SignalHandler {
         Self { signals: None }
     }
 
+    /// Creates a `SignalHandler` for when installing signal handlers failed with `err`.
+    pub(crate) fn unavailable(err: std::io::Error) -> Self {
+        log::warn!(
+            "error setting up signal handler ({err}), \
+             so the run can't be canceled with Ctrl-C or other signals"
+        );
+        Self::noop()
+    }
+
     pub(crate) async fn recv(&mut self) -> Option<SignalEvent> {
         match &mut self.signals {
             Some(signals) => signals.recv().await,
@@ -62,7 +84,7 @@ mod imp {
     use super::*;
     use tokio::signal::unix::{signal, Signal, SignalKind};
 
-    /// Signals for SIGINT, SIGTERM and SIGHUP on Unix.
+    /// Signals for SIGINT, SIGTERM, SIGHUP, job control, SIGUSR1 and SIGUSR2 on Unix.
     #[derive(Debug)]
     pub(super) struct Signals {
         sigint: SignalWithDone,
@@ -70,6 +92,8 @@ mod imp {
         sigterm: SignalWithDone,
         sigtstp: SignalWithDone,
         sigcont: SignalWithDone,
+        sigusr1: SignalWithDone,
+        sigusr2: SignalWithDone,
     }
 
     impl Signals {
@@ -79,6 +103,8 @@ mod imp {
             let sigterm = SignalWithDone::new(SignalKind::terminate())?;
             let sigtstp = SignalWithDone::new(SignalKind::from_raw(libc::SIGTSTP))?;
             let sigcont = SignalWithDone::new(SignalKind::from_raw(libc::SIGCONT))?;
+            let sigusr1 = SignalWithDone::new(SignalKind::user_defined1())?;
+            let sigusr2 = SignalWithDone::new(SignalKind::user_defined2())?;
 
             Ok(Self {
                 sigint,
@@ -86,6 +112,8 @@ mod imp {
                 sigterm,
                 sigtstp,
                 sigcont,
+                sigusr1,
+                sigusr2,
             })
         }
 
@@ -122,6 +150,21 @@ mod imp {
                             None => self.sigcont.done = true,
                         }
                     }
+                    recv = self.sigusr1.signal.recv(), if !self.sigusr1.done => {
+                        match recv {
+                            Some(()) => break Some(SignalEvent::Info),
+                            None => self.sigusr1.done = true,
+                        }
+                    }
+                    // SIGTSTP can't double as the pause signal: it's the shell's Ctrl-Z, and the
+                    // shell expects nextest and its tests to stop so that it can take back the
+                    // terminal. SIGUSR2 has no meaning to shells, so it's used instead.
+                    recv = self.sigusr2.signal.recv(), if !self.sigusr2.done => {
+                        match recv {
+                            Some(()) => break Some(SignalEvent::TogglePause),
+                            None => self.sigusr2.done = true,
+                        }
+                    }
                     else => {
                         break None
                     }
@@ -188,6 +231,14 @@ pub(crate) enum SignalEvent {
     #[cfg(unix)]
     JobControl(JobControlEvent),
     Shutdown(ShutdownEvent),
+    // A request to report the tests that are currently running, without otherwise affecting the
+    // run.
+    #[cfg(unix)]
+    Info,
+    // A request to stop starting new tests if they're currently being started, or to start them
+    // again if they aren't. Tests that are already running aren't affected.
+    #[cfg(unix)]
+    TogglePause,
 }
 
 // A job-control related signal event.
@@ -208,3 +259,101 @@ pub(crate) enum ShutdownEvent {
     Term,
     Interrupt,
 }
+
+/// The number of shutdown signals received, as far as escalation is concerned.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub(crate) enum SignalCount {
+    /// Running tests are sent the signal and given a grace period to exit.
+    Once,
+
+    /// Running tests are killed immediately.
+    Twice,
+}
+
+/// Tracks shutdown signals received during a run.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SignalCounter {
+    last: Option<(SignalCount, Instant)>,
+}
+
+impl SignalCounter {
+    /// Records a shutdown signal received at `now`, returning the resulting count.
+    ///
+    /// An interrupt received more than [`FORCE_KILL_WINDOW`] after the previous signal counts as
+    /// a first signal again, so that a stray Ctrl-C doesn't kill tests that are shutting down.
+    pub(crate) fn record(&mut self, event: ShutdownEvent, now: Instant) -> SignalCount {
+        let new_count = match self.last {
+            None => SignalCount::Once,
+            Some((SignalCount::Once, at)) => {
+                if event == ShutdownEvent::Interrupt
+                    && now.saturating_duration_since(at) > FORCE_KILL_WINDOW
+                {
+                    SignalCount::Once
+                } else {
+                    SignalCount::Twice
+                }
+            }
+            Some((SignalCount::Twice, _)) => {
+                // The process was signaled 3 times. Time to panic.
+                panic!("Signaled 3 times, exiting immediately");
+            }
+        };
+        self.last = Some((new_count, now));
+        new_count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_interrupt_escalates_within_window() {
+        let start = Instant::now();
+        let mut counter = SignalCounter::default();
+        assert_eq!(
+            counter.record(ShutdownEvent::Interrupt, start),
+            SignalCount::Once
+        );
+        assert_eq!(
+            counter.record(ShutdownEvent::Interrupt, start + Duration::from_secs(1)),
+            SignalCount::Twice
+        );
+    }
+
+    #[test]
+    fn late_interrupt_starts_over() {
+        let start = Instant::now();
+        let mut counter = SignalCounter::default();
+        assert_eq!(
+            counter.record(ShutdownEvent::Interrupt, start),
+            SignalCount::Once
+        );
+
+        // The window is measured from the most recent interrupt.
+        let late = start + FORCE_KILL_WINDOW + Duration::from_secs(1);
+        assert_eq!(
+            counter.record(ShutdownEvent::Interrupt, late),
+            SignalCount::Once
+        );
+        assert_eq!(
+            counter.record(ShutdownEvent::Interrupt, late + Duration::from_secs(1)),
+            SignalCount::Twice
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn second_term_always_escalates() {
+        let start = Instant::now();
+        let mut counter = SignalCounter::default();
+        assert_eq!(
+            counter.record(ShutdownEvent::Term, start),
+            SignalCount::Once
+        );
+        assert_eq!(
+            counter.record(ShutdownEvent::Term, start + Duration::from_secs(60)),
+            SignalCount::Twice
+        );
+    }
+}
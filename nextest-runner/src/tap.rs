@@ -0,0 +1,285 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Parsing of [TAP](https://testanything.org/) output printed by tests.
+//!
+//! This is used for tests configured with `outcome-source = "tap"`, which encode their outcome in
+//! their standard output rather than in their exit code.
+
+use crate::runner::ExecutionResult;
+use thiserror::Error;
+
+/// A summary of the TAP output produced by a test.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) struct TapSummary {
+    /// The number of test points that passed, including skipped points and failing TODO points.
+    pub(crate) passed: usize,
+
+    /// The number of test points that failed.
+    pub(crate) failed: usize,
+
+    /// Whether the output contained a `Bail out!` line.
+    pub(crate) bailed_out: bool,
+}
+
+impl TapSummary {
+    /// Returns true if the TAP output indicates success.
+    pub(crate) fn is_success(&self) -> bool {
+        self.failed == 0 && !self.bailed_out
+    }
+}
+
+/// An error that occurred while parsing TAP output.
+#[derive(Clone, Debug, Eq, PartialEq, Error)]
+pub(crate) enum TapParseError {
+    /// No plan line (`1..N`) was found.
+    #[error("no plan line (`1..N`) found")]
+    MissingPlan,
+
+    /// More than one plan line was found.
+    #[error("multiple plan lines found (line {line})")]
+    MultiplePlans {
+        /// The 1-indexed line number of the second plan.
+        line: usize,
+    },
+
+    /// The plan line couldn't be parsed.
+    #[error("invalid plan `{plan}` (line {line})")]
+    InvalidPlan {
+        /// The plan line.
+        plan: String,
+
+        /// The 1-indexed line number of the plan.
+        line: usize,
+    },
+
+    /// The number of test points didn't match the plan.
+    #[error("plan expected {expected} test points, but found {actual}")]
+    PlanMismatch {
+        /// The number of test points in the plan.
+        expected: usize,
+
+        /// The number of test points found.
+        actual: usize,
+    },
+}
+
+/// Parses `output` as TAP.
+///
+/// Lines that aren't part of the TAP grammar (including indented lines, which are used for
+/// subtests and YAML diagnostics) are ignored, as recommended by the TAP specification.
+pub(crate) fn parse_tap(output: &str) -> Result<TapSummary, TapParseError> {
+    let mut summary = TapSummary::default();
+    let mut plan = None;
+
+    for (idx, line) in output.lines().enumerate() {
+        let line_number = idx + 1;
+
+        if let Some(rest) = line.strip_prefix("1..") {
+            if plan.is_some() {
+                return Err(TapParseError::MultiplePlans { line: line_number });
+            }
+            // The plan may be followed by a directive, e.g. "1..0 # SKIP no tests".
+            let count = rest.split('#').next().unwrap_or_default().trim();
+            let count = count
+                .parse::<usize>()
+                .map_err(|_| TapParseError::InvalidPlan {
+                    plan: line.to_owned(),
+                    line: line_number,
+                })?;
+            plan = Some(count);
+        } else if let Some(rest) = line.strip_prefix("not ok") {
+            if is_test_point_rest(rest) {
+                if is_todo(rest) {
+                    // Failing TODO points don't count as failures.
+                    summary.passed += 1;
+                } else {
+                    summary.failed += 1;
+                }
+            }
+        } else if let Some(rest) = line.strip_prefix("ok") {
+            if is_test_point_rest(rest) {
+                summary.passed += 1;
+            }
+        } else if line.starts_with("Bail out!") {
+            summary.bailed_out = true;
+        }
+    }
+
+    if summary.bailed_out {
+        // A bailed out run doesn't need to match its plan.
+        return Ok(summary);
+    }
+
+    let expected = plan.ok_or(TapParseError::MissingPlan)?;
+    let actual = summary.passed + summary.failed;
+    if expected != actual {
+        return Err(TapParseError::PlanMismatch { expected, actual });
+    }
+
+    Ok(summary)
+}
+
+/// Derives the result of a test from its TAP output.
+///
+/// Only tests that exited successfully are reclassified: a test that failed as per its exit code
+/// is always treated as failed.
+pub(crate) fn tap_result(
+    result: ExecutionResult,
+    stdout: &[u8],
+) -> Result<ExecutionResult, TapParseError> {
+    let leaked = match result {
+        ExecutionResult::Pass => false,
+        ExecutionResult::Leak | ExecutionResult::LeakFail => true,
+        other => return Ok(other),
+    };
+
+    let summary = parse_tap(&String::from_utf8_lossy(stdout))?;
+    if summary.is_success() {
+        Ok(result)
+    } else {
+        Ok(ExecutionResult::Fail {
+            abort_status: None,
+            leaked,
+        })
+    }
+}
+
+/// Returns true if `rest`, the part of a line after "ok" or "not ok", makes it a test point.
+///
+/// This rules out lines like "okay".
+fn is_test_point_rest(rest: &str) -> bool {
+    rest.is_empty() || rest.starts_with(' ')
+}
+
+/// Returns true if the test point has a TODO directive.
+fn is_todo(rest: &str) -> bool {
+    rest.split_once('#')
+        .and_then(|(_, directive)| directive.trim_start().get(..4))
+        .map_or(false, |keyword| keyword.eq_ignore_ascii_case("todo"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+    use test_case::test_case;
+
+    #[test_case(
+        indoc! {"
+            TAP version 13
+            1..3
+            ok 1 - first
+            ok 2 - second # SKIP not on this platform
+            ok 3
+        "},
+        TapSummary { passed: 3, failed: 0, bailed_out: false }
+        ; "all passing"
+    )]
+    #[test_case(
+        indoc! {"
+            ok 1 - first
+            not ok 2 - second
+              ---
+              message: 'assertion failed'
+              ...
+            not ok 3 - third # TODO not implemented yet
+            1..3
+        "},
+        TapSummary { passed: 2, failed: 1, bailed_out: false }
+        ; "failure with trailing plan"
+    )]
+    #[test_case(
+        indoc! {"
+            1..0 # SKIP nothing to test
+        "},
+        TapSummary { passed: 0, failed: 0, bailed_out: false }
+        ; "empty plan"
+    )]
+    #[test_case(
+        indoc! {"
+            1..5
+            ok 1
+            Bail out! database unavailable
+        "},
+        TapSummary { passed: 1, failed: 0, bailed_out: true }
+        ; "bail out"
+    )]
+    #[test_case(
+        indoc! {"
+            some unrelated log line
+            okay, starting up
+            1..1
+            ok 1
+        "},
+        TapSummary { passed: 1, failed: 0, bailed_out: false }
+        ; "non-TAP lines are ignored"
+    )]
+    fn parse_valid(input: &str, expected: TapSummary) {
+        assert_eq!(parse_tap(input), Ok(expected));
+    }
+
+    #[test_case(
+        "ok 1\nok 2\n",
+        TapParseError::MissingPlan
+        ; "missing plan"
+    )]
+    #[test_case(
+        "1..2\nok 1\n1..2\nok 2\n",
+        TapParseError::MultiplePlans { line: 3 }
+        ; "multiple plans"
+    )]
+    #[test_case(
+        "1..two\nok 1\nok 2\n",
+        TapParseError::InvalidPlan { plan: "1..two".to_owned(), line: 1 }
+        ; "invalid plan"
+    )]
+    #[test_case(
+        "1..3\nok 1\nnot ok 2\n",
+        TapParseError::PlanMismatch { expected: 3, actual: 2 }
+        ; "plan mismatch"
+    )]
+    fn parse_invalid(input: &str, expected: TapParseError) {
+        assert_eq!(parse_tap(input), Err(expected));
+    }
+
+    #[test]
+    fn not_ok_fails_despite_exit_zero() {
+        let stdout = b"1..2\nok 1 - first\nnot ok 2 - second\n";
+        assert_eq!(
+            tap_result(ExecutionResult::Pass, stdout),
+            Ok(ExecutionResult::Fail {
+                abort_status: None,
+                leaked: false,
+            }),
+        );
+        assert_eq!(
+            tap_result(ExecutionResult::Leak, stdout),
+            Ok(ExecutionResult::Fail {
+                abort_status: None,
+                leaked: true,
+            }),
+        );
+
+        let stdout = b"1..2\nok 1 - first\nok 2 - second\n";
+        assert_eq!(
+            tap_result(ExecutionResult::Pass, stdout),
+            Ok(ExecutionResult::Pass)
+        );
+    }
+
+    #[test]
+    fn exit_code_failures_are_kept() {
+        let fail = ExecutionResult::Fail {
+            abort_status: None,
+            leaked: false,
+        };
+        // Even if the TAP output indicates success (or is malformed), a failing exit code wins.
+        assert_eq!(tap_result(fail, b"1..1\nok 1\n"), Ok(fail));
+        assert_eq!(tap_result(fail, b"garbage"), Ok(fail));
+        assert_eq!(
+            tap_result(ExecutionResult::Timeout, b"1..1\nok 1\n"),
+            Ok(ExecutionResult::Timeout)
+        );
+    }
+}
@@ -5,20 +5,25 @@
 
 use crate::{
     cargo_config::{CargoConfig, CargoConfigSource, CargoConfigs, DiscoveredConfig, Runner},
+    config::TargetRunnerRule,
     errors::TargetRunnerError,
     platform::BuildPlatforms,
 };
 use camino::{Utf8Path, Utf8PathBuf};
-use nextest_metadata::BuildPlatform;
+use nextest_metadata::{BuildPlatform, RustTestBinaryKind};
 use std::fmt;
 use target_spec::Platform;
 
+const WORKSPACE_ROOT_PLACEHOLDER: &str = "{workspace-root}";
+
 /// A [target runner](https://doc.rust-lang.org/cargo/reference/config.html#targettriplerunner)
 /// used to execute a test binary rather than the default of executing natively.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct TargetRunner {
     host: Option<PlatformRunner>,
     target: Option<PlatformRunner>,
+    rules: Vec<(TargetRunnerRule, PlatformRunner)>,
+    extra_args: Vec<String>,
 }
 
 impl TargetRunner {
@@ -35,7 +40,12 @@ impl TargetRunner {
             None => host.clone(),
         };
 
-        Ok(Self { host, target })
+        Ok(Self {
+            host,
+            target,
+            rules: Vec::new(),
+            extra_args: Vec::new(),
+        })
     }
 
     /// Creates an empty target runner that does not delegate to any runner binaries.
@@ -43,9 +53,64 @@ impl TargetRunner {
         Self {
             host: None,
             target: None,
+            rules: Vec::new(),
+            extra_args: Vec::new(),
         }
     }
 
+    /// Adds rules from the `[target-runner]` section of the nextest config, which select target
+    /// runners for test binaries by kind and name.
+    ///
+    /// Relative paths to runner binaries are resolved against `workspace_root`. Test binaries that
+    /// don't match any rule continue to use the target runner configured through Cargo, if any.
+    pub fn with_rules(
+        mut self,
+        rules: &[TargetRunnerRule],
+        workspace_root: &Utf8Path,
+    ) -> Result<Self, TargetRunnerError> {
+        self.rules = rules
+            .iter()
+            .enumerate()
+            .map(|(index, rule)| {
+                let runner = PlatformRunner::parse_runner(
+                    PlatformRunnerSource::NextestConfig { index },
+                    rule.runner.clone(),
+                    workspace_root,
+                )?;
+                Ok((rule.clone(), runner))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(self)
+    }
+
+    /// Sets additional arguments to pass to every target runner, from the `target-runner-args` key
+    /// of the nextest config.
+    ///
+    /// These arguments are inserted after the runner's own arguments, and before the path to the
+    /// test binary. Occurrences of `{workspace-root}` within them are replaced with
+    /// `workspace_root`.
+    pub fn with_extra_args(
+        mut self,
+        args: impl IntoIterator<Item = impl AsRef<str>>,
+        workspace_root: &Utf8Path,
+    ) -> Self {
+        self.extra_args = args
+            .into_iter()
+            .map(|arg| {
+                arg.as_ref()
+                    .replace(WORKSPACE_ROOT_PLACEHOLDER, workspace_root.as_str())
+            })
+            .collect();
+        self
+    }
+
+    /// Returns the additional arguments set through [`Self::with_extra_args`], with placeholders
+    /// substituted.
+    #[inline]
+    pub fn extra_args(&self) -> impl Iterator<Item = &str> {
+        self.extra_args.iter().map(AsRef::as_ref)
+    }
+
     /// Returns the target [`PlatformRunner`].
     #[inline]
     pub fn target(&self) -> Option<&PlatformRunner> {
@@ -67,6 +132,24 @@ impl TargetRunner {
         }
     }
 
+    /// Returns the [`PlatformRunner`] for a test binary with the given build platform, kind and
+    /// name.
+    ///
+    /// The first matching rule added through [`Self::with_rules`] is used, falling back to
+    /// [`Self::for_build_platform`] if no rules match.
+    pub fn for_binary(
+        &self,
+        build_platform: BuildPlatform,
+        kind: &RustTestBinaryKind,
+        binary_name: &str,
+    ) -> Option<&PlatformRunner> {
+        self.rules
+            .iter()
+            .find(|(rule, _)| rule.matches(kind, binary_name))
+            .map(|(_, runner)| runner)
+            .or_else(|| self.for_build_platform(build_platform))
+    }
+
     /// Returns the platform runners for all build platforms.
     #[inline]
     pub fn all_build_platforms(&self) -> [(BuildPlatform, Option<&PlatformRunner>); 2] {
@@ -309,6 +392,13 @@ pub enum PlatformRunnerSource {
         /// If `target.'cfg(target_os = "linux")'.runner` is used, this is `cfg(target_os = "linux")`.
         target_table: String,
     },
+
+    /// The platform runner was defined by a rule in the `[target-runner]` section of the nextest
+    /// config.
+    NextestConfig {
+        /// The index of the rule within `target-runner.rules`.
+        index: usize,
+    },
 }
 
 impl PlatformRunnerSource {
@@ -317,6 +407,8 @@ impl PlatformRunnerSource {
         match self {
             Self::Env(_) => cwd,
             Self::CargoConfig { source, .. } => source.resolve_dir(cwd),
+            // The caller passes in the workspace root for rules in the nextest config.
+            Self::NextestConfig { .. } => cwd,
         }
     }
 }
@@ -339,6 +431,12 @@ impl fmt::Display for PlatformRunnerSource {
             } => {
                 write!(f, "`target.{target_table}.runner` within `{path}`")
             }
+            Self::NextestConfig { index } => {
+                write!(
+                    f,
+                    "`target-runner.rules[{index}].runner` within nextest config"
+                )
+            }
         }
     }
 }
@@ -579,6 +677,99 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_for_binary_rules() {
+        let workspace_root = Utf8Path::new("/fake/workspace");
+        let rules = [
+            TargetRunnerRule {
+                kind: Some(RustTestBinaryKind::BENCH),
+                binary: None,
+                runner: Runner::Simple("taskset -c 0".to_owned()),
+            },
+            TargetRunnerRule {
+                kind: Some(RustTestBinaryKind::TEST),
+                binary: Some("integration".to_owned()),
+                runner: Runner::List(vec!["./scripts/wrap.sh".to_owned(), "--quiet".to_owned()]),
+            },
+        ];
+        let cargo_runner = PlatformRunner {
+            runner_binary: "wine".into(),
+            args: vec![],
+            source: PlatformRunnerSource::Env("CARGO_TARGET_X86_64_PC_WINDOWS_MSVC_RUNNER".into()),
+        };
+        let target_runner = TargetRunner {
+            host: Some(cargo_runner.clone()),
+            target: Some(cargo_runner.clone()),
+            rules: Vec::new(),
+            extra_args: Vec::new(),
+        }
+        .with_rules(&rules, workspace_root)
+        .expect("rules are valid");
+
+        assert_eq!(
+            target_runner.for_binary(
+                BuildPlatform::Target,
+                &RustTestBinaryKind::BENCH,
+                "my-bench"
+            ),
+            Some(&PlatformRunner {
+                runner_binary: "taskset".into(),
+                args: vec!["-c".into(), "0".into()],
+                source: PlatformRunnerSource::NextestConfig { index: 0 },
+            }),
+        );
+        assert_eq!(
+            target_runner.for_binary(
+                BuildPlatform::Target,
+                &RustTestBinaryKind::TEST,
+                "integration"
+            ),
+            Some(&PlatformRunner {
+                runner_binary: workspace_root.join("./scripts/wrap.sh"),
+                args: vec!["--quiet".into()],
+                source: PlatformRunnerSource::NextestConfig { index: 1 },
+            }),
+        );
+
+        // Binaries that don't match any rules fall back to the Cargo runner.
+        assert_eq!(
+            target_runner.for_binary(BuildPlatform::Target, &RustTestBinaryKind::TEST, "other"),
+            Some(&cargo_runner),
+        );
+        assert_eq!(
+            target_runner.for_binary(BuildPlatform::Target, &RustTestBinaryKind::LIB, "my-lib"),
+            Some(&cargo_runner),
+        );
+        assert_eq!(
+            TargetRunner::empty()
+                .with_rules(&rules, workspace_root)
+                .expect("rules are valid")
+                .for_binary(BuildPlatform::Host, &RustTestBinaryKind::LIB, "my-lib"),
+            None,
+        );
+
+        let error = TargetRunner::empty()
+            .with_rules(
+                &[TargetRunnerRule {
+                    kind: None,
+                    binary: None,
+                    runner: Runner::List(vec![]),
+                }],
+                workspace_root,
+            )
+            .expect_err("empty runner is invalid");
+        assert!(
+            matches!(
+                error,
+                TargetRunnerError::BinaryNotSpecified {
+                    key: PlatformRunnerSource::NextestConfig { index: 0 },
+                    ..
+                }
+            ),
+            "unexpected error: {error:?}"
+        );
+    }
+
     fn setup_temp_dir() -> Result<Utf8TempDir> {
         let dir = camino_tempfile::Builder::new()
             .tempdir()
@@ -13,8 +13,16 @@ use once_cell::sync::Lazy;
 use std::{
     collections::{BTreeSet, HashMap},
     ffi::{OsStr, OsString},
+    sync::RwLock,
 };
 
+/// Held for reading while spawning a process during a test run, and for writing while creating
+/// file descriptors that can't be atomically marked close-on-exec.
+///
+/// Without this, a process spawned at the same time as such a file descriptor is created could
+/// inherit it.
+pub(crate) static SPAWN_LOCK: RwLock<()> = RwLock::new(());
+
 #[derive(Clone, Debug)]
 pub(crate) struct LocalExecuteContext<'a> {
     pub(crate) double_spawn: &'a DoubleSpawnInfo,
@@ -182,7 +190,10 @@ impl TestCommand {
 
     pub(crate) fn spawn(self) -> std::io::Result<tokio::process::Child> {
         let mut command = tokio::process::Command::from(self.command);
-        let res = command.spawn();
+        let res = {
+            let _guard = SPAWN_LOCK.read().unwrap();
+            command.spawn()
+        };
         if let Some(ctx) = self.double_spawn {
             ctx.finish();
         }
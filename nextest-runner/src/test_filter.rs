@@ -10,14 +10,17 @@
 // result
 
 use crate::{
+    config::TestSuiteConfig,
     errors::TestFilterBuilderError,
     helpers::convert_build_platform,
     list::RustTestArtifact,
     partition::{Partitioner, PartitionerBuilder},
+    timings::RecordedTimings,
 };
 use aho_corasick::AhoCorasick;
 use nextest_filtering::{BinaryQuery, FilteringExpr, TestQuery};
 use nextest_metadata::{FilterMatch, MismatchReason};
+use std::time::Duration;
 
 /// Whether to run ignored tests.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Default)]
@@ -42,6 +45,9 @@ pub struct TestFilterBuilder {
     partitioner_builder: Option<PartitionerBuilder>,
     name_match: NameMatch,
     exprs: Vec<FilteringExpr>,
+    suite_expr: Option<FilteringExpr>,
+    default_filter: Option<FilteringExpr>,
+    skip_slower_than: Option<(Duration, RecordedTimings)>,
 }
 
 #[derive(Clone, Debug)]
@@ -95,6 +101,9 @@ impl TestFilterBuilder {
             partitioner_builder,
             name_match,
             exprs,
+            suite_expr: None,
+            default_filter: None,
+            skip_slower_than: None,
         })
     }
 
@@ -105,9 +114,47 @@ impl TestFilterBuilder {
             partitioner_builder: None,
             name_match: NameMatch::EmptyPatterns,
             exprs: Vec::new(),
+            suite_expr: None,
+            default_filter: None,
+            skip_slower_than: None,
         }
     }
 
+    /// Only selects tests in the given test suite.
+    ///
+    /// Tests must match both the suite's filter and any other filters, so that filters can be used
+    /// to select tests within a suite.
+    pub fn set_suite(&mut self, suite: &TestSuiteConfig<'_>) -> &mut Self {
+        self.suite_expr = suite.filter().cloned();
+        self
+    }
+
+    /// Only selects tests that match a profile's `default-filter`.
+    ///
+    /// Like a suite's filter, tests must match both the default filter and any other filters. If no
+    /// other filters are specified, the default filter alone selects tests.
+    pub fn set_default_filter(&mut self, default_filter: Option<&FilteringExpr>) -> &mut Self {
+        self.default_filter = default_filter.cloned();
+        self
+    }
+
+    /// Returns the filters that every selected test must match, regardless of other filters.
+    fn required_exprs(&self) -> impl Iterator<Item = &FilteringExpr> {
+        self.suite_expr.iter().chain(&self.default_filter)
+    }
+
+    /// Skips tests that took longer than `threshold` in an earlier run, as recorded in `timings`.
+    ///
+    /// Tests without a recorded timing, for example because they're new, aren't skipped.
+    pub fn set_skip_slower_than(
+        &mut self,
+        threshold: Duration,
+        timings: RecordedTimings,
+    ) -> &mut Self {
+        self.skip_slower_than = Some((threshold, timings));
+        self
+    }
+
     /// Returns a value indicating whether this binary should or should not be run to obtain the
     /// list of tests within it.
     ///
@@ -120,6 +167,12 @@ impl TestFilterBuilder {
             binary_name: &test_binary.binary_name,
             platform: convert_build_platform(test_binary.build_platform),
         };
+        for required_expr in self.required_exprs() {
+            // If this is a definite mismatch, then no test in this binary can be selected.
+            if !required_expr.matches_binary(&query).unwrap_or(true) {
+                return false;
+            }
+        }
         if self.exprs.is_empty() {
             // No expressions means match all tests.
             return true;
@@ -201,10 +254,11 @@ impl<'filter> TestFilter<'filter> {
                     }
                 }
             })
+            .or_else(|| self.filter_slow_mismatch(test_binary, test_name))
             // Note that partition-based filtering MUST come after all other kinds of filtering,
             // so that count-based bucketing applies after ignored, name and expression matching.
             // This also means that mutable count state must be maintained by the partitioner.
-            .or_else(|| self.filter_partition_mismatch(test_name))
+            .or_else(|| self.filter_partition_mismatch(test_binary, test_name))
             .unwrap_or(FilterMatch::Matches)
     }
 
@@ -256,6 +310,11 @@ impl<'filter> TestFilter<'filter> {
             },
             test_name,
         };
+        for required_expr in self.builder.required_exprs() {
+            if !required_expr.matches_test(&query) {
+                return FilterNameMatch::Mismatch(MismatchReason::Expression);
+            }
+        }
         if self.builder.exprs.is_empty() {
             FilterNameMatch::MatchEmptyPatterns
         } else if self
@@ -270,9 +329,25 @@ impl<'filter> TestFilter<'filter> {
         }
     }
 
-    fn filter_partition_mismatch(&mut self, test_name: &str) -> Option<FilterMatch> {
+    fn filter_slow_mismatch(
+        &self,
+        test_binary: &RustTestArtifact<'_>,
+        test_name: &str,
+    ) -> Option<FilterMatch> {
+        let (threshold, timings) = self.builder.skip_slower_than.as_ref()?;
+        let timing = timings.get(&test_binary.binary_id, test_name)?;
+        (timing.duration > *threshold).then_some(FilterMatch::Mismatch {
+            reason: MismatchReason::Slow,
+        })
+    }
+
+    fn filter_partition_mismatch(
+        &mut self,
+        test_binary: &RustTestArtifact<'_>,
+        test_name: &str,
+    ) -> Option<FilterMatch> {
         let partition_match = match &mut self.partitioner {
-            Some(partitioner) => partitioner.test_matches(test_name),
+            Some(partitioner) => partitioner.test_matches(&test_binary.binary_id, test_name),
             None => true,
         };
         if partition_match {
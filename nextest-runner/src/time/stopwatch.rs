@@ -6,6 +6,13 @@
 //! Tests need to track a start time and a duration. For that we use a combination of a `SystemTime`
 //! (realtime clock) and an `Instant` (monotonic clock). Once the stopwatch transitions to the "end"
 //! state, we can report the elapsed time using the monotonic clock.
+//!
+//! The realtime clock is only used for the start time shown in reports. Durations are never
+//! computed from it, since it can jump forwards or backwards while tests are running, for example
+//! if NTP adjusts the system clock.
+//!
+//! The stopwatch can also record when the test process was spawned, which together with the time
+//! the test first produced output is used to break the time taken down into phases.
 
 use std::time::{Duration, Instant, SystemTime};
 
@@ -20,6 +27,7 @@ pub(crate) struct StopwatchStart {
     instant: Instant,
     paused_time: Duration,
     pause_state: StopwatchPauseState,
+    spawned_at: Option<Instant>,
 }
 
 impl StopwatchStart {
@@ -31,9 +39,15 @@ impl StopwatchStart {
             instant: Instant::now(),
             paused_time: Duration::ZERO,
             pause_state: StopwatchPauseState::Running,
+            spawned_at: None,
         }
     }
 
+    /// Records that the test process was spawned.
+    pub(crate) fn mark_spawned(&mut self) {
+        self.spawned_at = Some(Instant::now());
+    }
+
     pub(crate) fn is_paused(&self) -> bool {
         matches!(self.pause_state, StopwatchPauseState::Paused { .. })
     }
@@ -67,14 +81,62 @@ impl StopwatchStart {
         StopwatchEnd {
             start_time: self.start_time,
             duration: self.instant.elapsed() - self.paused_time,
+            // The process is always spawned before the stopwatch can be paused, so the paused time
+            // doesn't need to be accounted for here.
+            spawned: self
+                .spawned_at
+                .map(|spawned_at| spawned_at.duration_since(self.instant)),
+            instant: self.instant,
         }
     }
 }
 
 #[derive(Clone, Debug)]
 pub(crate) struct StopwatchEnd {
+    /// The wall-clock time at which the stopwatch was started, for use as a timestamp.
     pub(crate) start_time: SystemTime,
+    /// The time elapsed since the stopwatch was started, not counting any time it was paused.
+    ///
+    /// This is measured using the monotonic clock.
     pub(crate) duration: Duration,
+    // The time between the start of the stopwatch and the process being spawned, if it was.
+    spawned: Option<Duration>,
+    instant: Instant,
+}
+
+impl StopwatchEnd {
+    /// Breaks the time taken down into phases, given the time at which the test first produced
+    /// output.
+    ///
+    /// Returns `None` if the test process was never spawned.
+    pub(crate) fn phases(&self, first_output_at: Option<Instant>) -> Option<StopwatchPhases> {
+        let queued_to_spawned = self.spawned?;
+        let spawned_to_exited = self.duration.saturating_sub(queued_to_spawned);
+        // Output produced after the test exited, for example by leaked processes, isn't counted.
+        let spawned_to_first_output = first_output_at
+            .map(|first_output_at| {
+                first_output_at
+                    .saturating_duration_since(self.instant)
+                    .saturating_sub(queued_to_spawned)
+            })
+            .filter(|first_output| *first_output <= spawned_to_exited);
+
+        Some(StopwatchPhases {
+            queued_to_spawned,
+            spawned_to_first_output,
+            spawned_to_exited,
+        })
+    }
+}
+
+/// The phases of a test's lifecycle, as recorded by a stopwatch.
+///
+/// `queued_to_spawned` and `spawned_to_exited` add up to the duration of the stopwatch.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct StopwatchPhases {
+    pub(crate) queued_to_spawned: Duration,
+    pub(crate) spawned_to_first_output: Option<Duration>,
+    pub(crate) spawned_to_exited: Duration,
 }
 
 #[derive(Clone, Debug)]
@@ -114,4 +176,63 @@ mod tests {
             "difference between unpaused_end and end ({difference:?}) is at least 450ms"
         )
     }
+
+    #[test]
+    fn stopwatch_ignores_wall_clock() {
+        let mut start = stopwatch();
+        // Simulate the system clock jumping backwards after the stopwatch was started, by moving
+        // the recorded wall-clock start time forward by a day.
+        let start_time = start.start_time + Duration::from_secs(86400);
+        start.start_time = start_time;
+
+        std::thread::sleep(Duration::from_millis(50));
+        let end = start.end();
+        assert_eq!(end.start_time, start_time, "start time is kept as is");
+        assert!(
+            end.duration >= Duration::from_millis(50) && end.duration < Duration::from_secs(3600),
+            "duration ({:?}) is measured using the monotonic clock",
+            end.duration
+        );
+    }
+
+    #[test]
+    fn stopwatch_phases() {
+        let mut start = stopwatch();
+        assert!(
+            start.end().phases(None).is_none(),
+            "no phases before the process is spawned"
+        );
+
+        std::thread::sleep(Duration::from_millis(50));
+        start.mark_spawned();
+        std::thread::sleep(Duration::from_millis(50));
+        let first_output_at = Instant::now();
+        std::thread::sleep(Duration::from_millis(50));
+
+        let end = start.end();
+        let phases = end
+            .phases(Some(first_output_at))
+            .expect("process was spawned");
+        assert!(phases.queued_to_spawned >= Duration::from_millis(50));
+        let first_output = phases
+            .spawned_to_first_output
+            .expect("first output was recorded");
+        assert!(first_output >= Duration::from_millis(50));
+        assert!(
+            first_output <= phases.spawned_to_exited,
+            "first output ({first_output:?}) is before exit ({:?})",
+            phases.spawned_to_exited
+        );
+        assert_eq!(
+            phases.queued_to_spawned + phases.spawned_to_exited,
+            end.duration
+        );
+
+        // Output produced after the stopwatch was stopped isn't counted.
+        std::thread::sleep(Duration::from_millis(10));
+        let phases = end
+            .phases(Some(Instant::now()))
+            .expect("process was spawned");
+        assert_eq!(phases.spawned_to_first_output, None);
+    }
 }
@@ -0,0 +1,335 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Per-test timings recorded by earlier test runs.
+//!
+//! Nextest can write out per-test timings to a [CSV file](https://nexte.st/book/csv-timings) at
+//! the end of each run. The main structure in this module is [`RecordedTimings`], which reads
+//! those timings back in.
+
+use crate::errors::{ReadTimingsError, TimingsParseError};
+use camino::Utf8Path;
+use nextest_metadata::RustBinaryId;
+use std::{collections::HashMap, io, str::FromStr, time::Duration};
+
+/// Per-test timings read from a CSV timings file written by an earlier run.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RecordedTimings {
+    // Keyed by test ID: the binary ID and the test name, separated by a space.
+    timings: HashMap<String, RecordedTiming>,
+}
+
+impl RecordedTimings {
+    /// Reads timings from the CSV file at `path`.
+    ///
+    /// Returns `Ok(None)` if the file doesn't exist, for example because no runs have been
+    /// recorded yet.
+    pub fn read_csv(path: &Utf8Path) -> Result<Option<Self>, ReadTimingsError> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => {
+                return Err(ReadTimingsError::Read {
+                    path: path.to_owned(),
+                    error,
+                })
+            }
+        };
+        Self::parse_csv(&contents)
+            .map(Some)
+            .map_err(|error| ReadTimingsError::Parse {
+                path: path.to_owned(),
+                error,
+            })
+    }
+
+    /// Parses timings from the contents of a CSV timings file.
+    pub fn parse_csv(input: &str) -> Result<Self, TimingsParseError> {
+        let mut records = CsvRecords::new(input);
+        let header = match records.next() {
+            Some(header) => header?,
+            None => return Ok(Self::default()),
+        };
+        let column = |name: &'static str| {
+            header
+                .iter()
+                .position(|field| field == name)
+                .ok_or(TimingsParseError::MissingColumn { name })
+        };
+        let test_id_col = column("test_id")?;
+        let duration_col = column("duration_ms")?;
+        let outcome_col = column("outcome")?;
+        let attempts_col = column("attempts")?;
+
+        let mut timings = HashMap::new();
+        for record in records {
+            let record = record?;
+            if record.len() != header.len() {
+                return Err(TimingsParseError::FieldCount {
+                    line: record.line,
+                    expected: header.len(),
+                    actual: record.len(),
+                });
+            }
+            let duration_ms: u64 = record.parse_field(duration_col, "duration_ms")?;
+            let attempts = record.parse_field(attempts_col, "attempts")?;
+            timings.insert(
+                record[test_id_col].to_string(),
+                RecordedTiming {
+                    duration: Duration::from_millis(duration_ms),
+                    outcome: record[outcome_col].to_string(),
+                    attempts,
+                },
+            );
+        }
+
+        Ok(Self { timings })
+    }
+
+    /// Returns the recorded timing for a test, or `None` if no timing was recorded for it.
+    pub fn get(&self, binary_id: &RustBinaryId, test_name: &str) -> Option<&RecordedTiming> {
+        self.get_by_id(&format!("{binary_id} {test_name}"))
+    }
+
+    /// Returns the recorded timing for a test ID: the binary ID and the test name, separated by a
+    /// space.
+    pub fn get_by_id(&self, test_id: &str) -> Option<&RecordedTiming> {
+        self.timings.get(test_id)
+    }
+
+    /// Returns the number of tests with recorded timings.
+    pub fn len(&self) -> usize {
+        self.timings.len()
+    }
+
+    /// Returns true if no timings were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.timings.is_empty()
+    }
+}
+
+/// The timing recorded for a single test, as part of [`RecordedTimings`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RecordedTiming {
+    /// The total time taken by the test, across all attempts.
+    pub duration: Duration,
+
+    /// The outcome of the test, for example `pass` or `fail`.
+    pub outcome: String,
+
+    /// The number of times the test was run, including retries.
+    pub attempts: usize,
+}
+
+/// A CSV record, along with the line it started on.
+struct CsvRecord {
+    line: usize,
+    fields: Vec<String>,
+}
+
+impl CsvRecord {
+    fn parse_field<T: FromStr>(
+        &self,
+        col: usize,
+        name: &'static str,
+    ) -> Result<T, TimingsParseError> {
+        self.fields[col]
+            .parse()
+            .map_err(|_| TimingsParseError::InvalidField {
+                line: self.line,
+                name,
+                value: self.fields[col].clone(),
+            })
+    }
+}
+
+impl std::ops::Deref for CsvRecord {
+    type Target = [String];
+
+    fn deref(&self) -> &Self::Target {
+        &self.fields
+    }
+}
+
+/// An iterator over records in an RFC 4180 CSV file.
+///
+/// Fields may be wrapped in double quotes, in which case they can contain commas, newlines and
+/// doubled quotes.
+struct CsvRecords<'a> {
+    input: &'a str,
+    pos: usize,
+    line: usize,
+}
+
+impl<'a> CsvRecords<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            pos: 0,
+            line: 1,
+        }
+    }
+
+    fn parse_record(&mut self) -> Result<CsvRecord, TimingsParseError> {
+        let start_line = self.line;
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut chars = self.input[self.pos..].char_indices().peekable();
+        let mut in_quotes = false;
+        let mut consumed = self.input.len() - self.pos;
+
+        while let Some((idx, c)) = chars.next() {
+            if in_quotes {
+                match c {
+                    '"' if matches!(chars.peek(), Some((_, '"'))) => {
+                        chars.next();
+                        field.push('"');
+                    }
+                    '"' => in_quotes = false,
+                    '\n' => {
+                        self.line += 1;
+                        field.push(c);
+                    }
+                    _ => field.push(c),
+                }
+                continue;
+            }
+
+            match c {
+                '"' if field.is_empty() => in_quotes = true,
+                ',' => fields.push(std::mem::take(&mut field)),
+                '\r' if matches!(chars.peek(), Some((_, '\n'))) => {}
+                '\n' => {
+                    self.line += 1;
+                    consumed = idx + 1;
+                    break;
+                }
+                _ => field.push(c),
+            }
+        }
+
+        if in_quotes {
+            // Stop iterating: the rest of the input is part of the unterminated field.
+            self.pos = self.input.len();
+            return Err(TimingsParseError::UnterminatedQuote { line: start_line });
+        }
+        fields.push(field);
+        self.pos += consumed;
+        Ok(CsvRecord {
+            line: start_line,
+            fields,
+        })
+    }
+}
+
+impl<'a> Iterator for CsvRecords<'a> {
+    type Item = Result<CsvRecord, TimingsParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Skip over blank lines, including a trailing newline at the end of the file.
+        while self.input[self.pos..].starts_with(['\n', '\r']) {
+            if self.input[self.pos..].starts_with('\n') {
+                self.line += 1;
+            }
+            self.pos += 1;
+        }
+        if self.pos >= self.input.len() {
+            return None;
+        }
+        Some(self.parse_record())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+    use test_case::test_case;
+
+    #[test]
+    fn parse_csv() {
+        let input = indoc! {r#"
+            test_id,duration_ms,outcome,attempts
+            "my-binary tests::case(a, ""b"")",60,fail,3
+            my-binary tests::flaky,350,flaky,2
+            "other-binary multi
+            line",7,pass,1
+            other-binary slow,60000,timeout,1
+        "#};
+        let timings = RecordedTimings::parse_csv(input).expect("input is valid");
+        assert_eq!(timings.len(), 4);
+
+        let binary_id = RustBinaryId::new("my-binary");
+        assert_eq!(
+            timings.get(&binary_id, "tests::case(a, \"b\")"),
+            Some(&RecordedTiming {
+                duration: Duration::from_millis(60),
+                outcome: "fail".to_owned(),
+                attempts: 3,
+            })
+        );
+        assert_eq!(
+            timings
+                .get(&binary_id, "tests::flaky")
+                .map(|timing| timing.duration),
+            Some(Duration::from_millis(350))
+        );
+        assert_eq!(
+            timings
+                .get_by_id("other-binary multi\nline")
+                .map(|timing| timing.duration),
+            Some(Duration::from_millis(7))
+        );
+        assert_eq!(
+            timings
+                .get_by_id("other-binary slow")
+                .map(|timing| timing.outcome.as_str()),
+            Some("timeout")
+        );
+        assert_eq!(timings.get(&binary_id, "tests::missing"), None);
+    }
+
+    #[test_case("", 0; "empty")]
+    #[test_case("test_id,duration_ms,outcome,attempts\n", 0; "header only")]
+    #[test_case("test_id,duration_ms,outcome,attempts\r\nb t,5,pass,1\r\n", 1; "crlf")]
+    #[test_case("attempts,outcome,duration_ms,test_id\n1,pass,5,b t\n", 1; "reordered columns")]
+    fn parse_csv_valid(input: &str, count: usize) {
+        let timings = RecordedTimings::parse_csv(input).expect("input is valid");
+        assert_eq!(timings.len(), count);
+        if count > 0 {
+            assert_eq!(
+                timings.get_by_id("b t").map(|timing| timing.duration),
+                Some(Duration::from_millis(5))
+            );
+        }
+    }
+
+    #[test_case(
+        "test_id,outcome,attempts\n",
+        TimingsParseError::MissingColumn { name: "duration_ms" }
+        ; "missing column"
+    )]
+    #[test_case(
+        "test_id,duration_ms,outcome,attempts\nb t,5,pass\n",
+        TimingsParseError::FieldCount { line: 2, expected: 4, actual: 3 }
+        ; "too few fields"
+    )]
+    #[test_case(
+        "test_id,duration_ms,outcome,attempts\nb t,5s,pass,1\n",
+        TimingsParseError::InvalidField { line: 2, name: "duration_ms", value: "5s".to_owned() }
+        ; "invalid duration"
+    )]
+    #[test_case(
+        "test_id,duration_ms,outcome,attempts\nb t,5,pass,-1\n",
+        TimingsParseError::InvalidField { line: 2, name: "attempts", value: "-1".to_owned() }
+        ; "invalid attempts"
+    )]
+    #[test_case(
+        "test_id,duration_ms,outcome,attempts\n\"b t,5,pass,1\n",
+        TimingsParseError::UnterminatedQuote { line: 2 }
+        ; "unterminated quote"
+    )]
+    fn parse_csv_invalid(input: &str, expected: TimingsParseError) {
+        assert_eq!(RecordedTimings::parse_csv(input), Err(expected));
+    }
+}
@@ -2,23 +2,44 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::fixtures::*;
+use camino::Utf8Path;
 use cfg_if::cfg_if;
 use color_eyre::eyre::Result;
 use nextest_filtering::FilteringExpr;
-use nextest_metadata::{BuildPlatform, FilterMatch, MismatchReason};
+use nextest_metadata::{
+    BuildPlatform, FilterMatch, MismatchReason, NextestExitCode, RustBinaryId, RustTestBinaryKind,
+};
 use nextest_runner::{
-    config::{NextestConfig, RetryPolicy},
+    cargo_config::EnvironmentMap,
+    config::{get_num_cpus, NextestConfig, ReportFormat, RetryPolicy, TestThreads},
     double_spawn::DoubleSpawnInfo,
-    list::BinaryList,
+    errors::PreFlightError,
+    list::{BinaryList, TestExecuteContext, TestInstance, TestList, TestListEvent},
+    order_check::RunOutcomes,
+    partition::PartitionerBuilder,
     platform::BuildPlatforms,
-    reporter::heuristic_extract_description,
-    runner::{ExecutionDescription, ExecutionResult, TestRunnerBuilder},
+    reporter::{
+        heuristic_extract_description, CancelReason, EventFormat, ReporterStderr, StatusLevel,
+        TestEvent, TestOutputDisplay, TestReporterBuilder, JSON_LINES_FORMAT_VERSION,
+    },
+    run_summary::{FinalSummary, KnownFailures, RunSummary},
+    runner::{
+        configure_handle_inheritance, AbortStatus, BinaryPathResolver, ExecutionDescription,
+        ExecutionResult, ExecutionStatuses, FinalRunStatus, FinishReason, RetryData, TestObserver,
+        TestRunnerBuilder,
+    },
     signal::SignalHandlerKind,
     target_runner::TargetRunner,
     test_filter::{RunIgnored, TestFilterBuilder},
+    timings::RecordedTimings,
 };
 use pretty_assertions::assert_eq;
-use std::{io::Cursor, time::Duration};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    io::Cursor,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
 use test_case::test_case;
 
 #[test]
@@ -313,6 +334,195 @@ fn test_filter_expr_with_string_filters() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_filter_expr_kind() -> Result<()> {
+    set_env_vars();
+
+    let expr = FilteringExpr::parse("kind(bench)".to_owned(), &PACKAGE_GRAPH)
+        .expect("filter expression is valid");
+    let test_filter =
+        TestFilterBuilder::new(RunIgnored::Default, None, Vec::<String>::new(), vec![expr])
+            .unwrap();
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty());
+
+    let mut matched = BTreeSet::new();
+    for test in test_list.iter_tests() {
+        if test.test_info.filter_match.is_match() {
+            assert_eq!(
+                test.suite_info.kind,
+                RustTestBinaryKind::BENCH,
+                "expected test {test:?} to only match if it's in a bench binary"
+            );
+            matched.insert((test.suite_info.binary_id.as_str(), test.name));
+        } else {
+            assert_ne!(
+                test.suite_info.kind,
+                RustTestBinaryKind::BENCH,
+                "expected test {test:?} in a bench binary to match"
+            );
+        }
+    }
+    assert_eq!(
+        matched,
+        [
+            ("nextest-tests::bench/my-bench", "bench_add_two"),
+            ("nextest-tests::bench/my-bench", "tests::test_execute_bin"),
+        ]
+        .into_iter()
+        .collect(),
+        "only tests in bench binaries are selected"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_skip_slower_than() -> Result<()> {
+    set_env_vars();
+
+    let timings_path = workspace_root()
+        .parent()
+        .unwrap()
+        .join("nextest-tests-timings.csv");
+    let timings = RecordedTimings::read_csv(&timings_path)?.expect("fixture timings exist");
+
+    let mut test_filter = TestFilterBuilder::any(RunIgnored::Default);
+    test_filter.set_skip_slower_than(Duration::from_secs(2), timings);
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty());
+
+    // test_success took exactly 2 seconds, so it isn't skipped. Tests without recorded timings
+    // are always included.
+    let slow_tests = [
+        ("nextest-tests", "tests::unit_test_success"),
+        ("nextest-tests::basic", "test_flaky_mod_4"),
+    ];
+    let mut skipped = 0;
+    for test in test_list.iter_tests() {
+        let expected_test = get_expected_test(&test.suite_info.binary_id, test.name);
+        let expected = if expected_test.status.is_ignored() {
+            // Ignored tests are reported as such, even if they were also slow.
+            FilterMatch::Mismatch {
+                reason: MismatchReason::Ignored,
+            }
+        } else if slow_tests.contains(&(test.suite_info.binary_id.as_str(), test.name)) {
+            skipped += 1;
+            FilterMatch::Mismatch {
+                reason: MismatchReason::Slow,
+            }
+        } else {
+            FilterMatch::Matches
+        };
+        assert_eq!(
+            test.test_info.filter_match, expected,
+            "for test {test:?}, filter match is as expected"
+        );
+    }
+    assert_eq!(skipped, slow_tests.len(), "all slow tests were found");
+
+    Ok(())
+}
+
+#[test]
+fn test_list_progress() -> Result<()> {
+    set_env_vars();
+
+    // Binaries in other packages are skipped.
+    let expr = FilteringExpr::parse("package(nextest-tests)".to_owned(), &PACKAGE_GRAPH)
+        .expect("filter expression is valid");
+    let test_filter =
+        TestFilterBuilder::new(RunIgnored::Default, None, Vec::<String>::new(), vec![expr])
+            .unwrap();
+
+    let double_spawn = DoubleSpawnInfo::disabled();
+    let target_runner = TargetRunner::empty();
+    let ctx = TestExecuteContext {
+        double_spawn: &double_spawn,
+        target_runner: &target_runner,
+    };
+
+    let mut binary_count = None;
+    let mut started = BTreeSet::new();
+    let mut finished = BTreeMap::new();
+    let mut skipped = BTreeSet::new();
+    let test_list = TestList::new_with_progress(
+        &ctx,
+        FIXTURE_TARGETS.test_artifacts.values().cloned(),
+        FIXTURE_TARGETS.rust_build_meta.clone(),
+        &test_filter,
+        FIXTURE_TARGETS.env.to_owned(),
+        get_num_cpus(),
+        Some(Duration::from_millis(10)),
+        |event| match event {
+            TestListEvent::ListStarted {
+                binary_count: count,
+            } => {
+                assert_eq!(binary_count, None, "ListStarted is sent once");
+                binary_count = Some(count);
+            }
+            TestListEvent::BinaryStarted { binary_id } => {
+                assert!(
+                    started.insert(binary_id.clone()),
+                    "{binary_id} started only once"
+                );
+            }
+            TestListEvent::BinarySlow { binary_id, .. } => {
+                assert!(
+                    started.contains(binary_id) && !finished.contains_key(binary_id),
+                    "{binary_id} is slow while it's being listed"
+                );
+            }
+            TestListEvent::BinaryFinished {
+                binary_id,
+                test_count,
+                ..
+            } => {
+                assert!(started.contains(binary_id), "{binary_id} started");
+                finished.insert(binary_id.clone(), test_count);
+            }
+            TestListEvent::BinarySkipped { binary_id } => {
+                assert!(
+                    skipped.insert(binary_id.clone()),
+                    "{binary_id} skipped only once"
+                );
+            }
+        },
+    )?;
+
+    assert_eq!(binary_count, Some(FIXTURE_TARGETS.test_artifacts.len()));
+    assert_eq!(
+        started.len() + skipped.len(),
+        FIXTURE_TARGETS.test_artifacts.len(),
+        "every binary is either listed or skipped"
+    );
+    assert_eq!(started, finished.keys().cloned().collect::<BTreeSet<_>>());
+    assert!(
+        !skipped.is_empty(),
+        "binaries in other packages are skipped"
+    );
+
+    for (binary_id, artifact) in &FIXTURE_TARGETS.test_artifacts {
+        let is_listed = artifact.package.name() == "nextest-tests";
+        assert_eq!(
+            finished.contains_key(binary_id),
+            is_listed,
+            "{binary_id} is listed if it's in nextest-tests"
+        );
+        assert_eq!(skipped.contains(binary_id), !is_listed);
+    }
+    for suite in test_list.iter() {
+        if let Some(test_count) = finished.get(&suite.binary_id) {
+            assert_eq!(
+                *test_count,
+                suite.status.test_count(),
+                "{} test count",
+                suite.binary_id
+            );
+        }
+    }
+
+    Ok(())
+}
+
 /// Test that filter expressions without regular substring filters behave as expected.
 #[test]
 fn test_filter_expr_without_string_filters() -> Result<()> {
@@ -597,3 +807,2114 @@ fn test_termination() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_retry_budget() -> Result<()> {
+    set_env_vars();
+
+    let (events, _) = run_profile(
+        RunIgnored::All,
+        "test(=test_slow_timeout_2) | test(=test_failure_assert)",
+        "with-retry-budget",
+        |_| {},
+    );
+
+    let finished = finished_tests(&events);
+    for (test_name, expected_attempts) in [
+        // This test fails quickly, so all its retries fit in the budget.
+        ("test_failure_assert", 4),
+        // This test takes 1 second to time out, so retrying it would overrun the global timeout.
+        ("test_slow_timeout_2", 1),
+    ] {
+        let run_statuses = finished
+            .get(test_name)
+            .unwrap_or_else(|| panic!("{test_name} should have been run"));
+        assert_eq!(
+            run_statuses.len(),
+            expected_attempts,
+            "{test_name} should have been run {expected_attempts} times"
+        );
+        assert!(!run_statuses.last_status().result.is_success());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_slow_reports() -> Result<()> {
+    set_env_vars();
+
+    let (events, _) = run_profile(
+        RunIgnored::All,
+        "test(=test_slow_timeout) | test(=test_success)",
+        "with-slow-reports",
+        |_| {},
+    );
+
+    let mut reports = Vec::new();
+    let mut finished = Vec::new();
+    for event in &events {
+        match event {
+            TestEvent::TestStillRunning {
+                test_instance,
+                elapsed,
+                remaining,
+                ..
+            } => {
+                assert!(
+                    !finished.contains(&test_instance.name),
+                    "{} reported as running after it finished",
+                    test_instance.name
+                );
+                reports.push((test_instance.name, *elapsed, *remaining));
+            }
+            TestEvent::TestFinished { test_instance, .. } => {
+                finished.push(test_instance.name);
+            }
+            _ => {}
+        }
+    }
+
+    // The test is slow after 250ms and terminated after 1s, so there's time for a few reports in
+    // between.
+    assert!(!reports.is_empty(), "slow test was reported as running");
+    let mut last_elapsed = Duration::ZERO;
+    for (name, elapsed, remaining) in reports {
+        assert_eq!(name, "test_slow_timeout", "only the slow test is reported");
+        assert!(elapsed > last_elapsed, "elapsed time increases");
+        last_elapsed = elapsed;
+        let remaining = remaining.expect("test has a termination deadline");
+        assert_eq!(
+            remaining,
+            Duration::from_secs(1).saturating_sub(elapsed),
+            "remaining time is relative to the termination deadline"
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_flaky_confirmation() -> Result<()> {
+    set_env_vars();
+
+    let (events, run_stats) = run_profile(
+        RunIgnored::Default,
+        "test(test_flaky_mod)",
+        "with-flaky-confirmation",
+        |_| {},
+    );
+
+    let started = events.iter().find_map(|event| match event {
+        TestEvent::FlakyConfirmationStarted { test_count, runs } => Some((*test_count, *runs)),
+        _ => None,
+    });
+    let mut flake_rates: Vec<_> = events
+        .iter()
+        .filter_map(|event| match event {
+            TestEvent::FlakyConfirmationFinished {
+                test_instance,
+                runs,
+                failures,
+            } => Some((test_instance.name, *runs, *failures)),
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(run_stats.flaky, 2, "both flaky tests passed on a retry");
+    assert_eq!(started, Some((2, 12)), "both flaky tests are confirmed");
+
+    // Confirmation runs are numbered 1 through 12, and test_flaky_mod_N passes on attempts that
+    // are multiples of N.
+    flake_rates.sort_unstable();
+    assert_eq!(
+        flake_rates,
+        vec![("test_flaky_mod_4", 12, 9), ("test_flaky_mod_6", 12, 10)],
+    );
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_binary_path_resolver() -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    set_env_vars();
+
+    // A wrapper script that records its arguments, then runs the real binary.
+    let temp_dir = camino_tempfile::tempdir()?;
+    let marker_path = temp_dir.path().join("marker");
+    let wrapper_path = temp_dir.path().join("wrapper.sh");
+    let test_list = make_filtered_test_list(RunIgnored::Default, "test(=test_success)");
+    let basic_binary_path = test_list
+        .iter()
+        .find(|suite| suite.binary_id.as_str() == "nextest-tests::basic")
+        .expect("nextest-tests::basic is present")
+        .binary_path
+        .clone();
+    std::fs::write(
+        &wrapper_path,
+        format!("#!/bin/sh\necho \"$@\" >> '{marker_path}'\nexec '{basic_binary_path}' \"$@\"\n"),
+    )?;
+    std::fs::set_permissions(&wrapper_path, std::fs::Permissions::from_mode(0o755))?;
+
+    let resolved_path = wrapper_path.clone();
+    let (events, run_stats) = run_test_list(
+        test_list,
+        load_profile(NextestConfig::DEFAULT_PROFILE),
+        |builder| {
+            builder.set_binary_path_resolver(BinaryPathResolver::new(move |suite| {
+                if suite.binary_id.as_str() == "nextest-tests::basic" {
+                    resolved_path.clone()
+                } else {
+                    suite.binary_path.clone()
+                }
+            }));
+        },
+    );
+
+    assert!(run_stats.is_success(), "run should succeed: {run_stats:?}");
+    let basic_success = events
+        .iter()
+        .find_map(|event| match event {
+            TestEvent::TestFinished {
+                test_instance,
+                run_statuses,
+                ..
+            } if test_instance.suite_info.binary_id.as_str() == "nextest-tests::basic" => {
+                Some(run_statuses)
+            }
+            _ => None,
+        })
+        .expect("test_success in nextest-tests::basic was run");
+    assert_eq!(
+        basic_success.last_status().result,
+        ExecutionResult::Pass,
+        "test_success passed through the wrapper"
+    );
+
+    let marker = std::fs::read_to_string(&marker_path)?;
+    assert_eq!(marker, "--exact test_success --nocapture\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_binary_path_resolver_missing() -> Result<()> {
+    set_env_vars();
+
+    let temp_dir = camino_tempfile::tempdir()?;
+    let missing_path = temp_dir.path().join("does-not-exist");
+    let resolved_path = missing_path.clone();
+    let (events, run_stats) = run_profile(
+        RunIgnored::Default,
+        "test(test_success)",
+        NextestConfig::DEFAULT_PROFILE,
+        |builder| {
+            builder
+                .set_binary_path_resolver(BinaryPathResolver::new(move |_| resolved_path.clone()));
+        },
+    );
+
+    assert!(!run_stats.is_success(), "run should fail");
+    for run_statuses in finished_tests(&events).values() {
+        let last_status = run_statuses.last_status();
+        assert_eq!(last_status.result, ExecutionResult::ExecFail);
+        let stderr = String::from_utf8_lossy(&last_status.stderr);
+        assert!(
+            stderr.contains(&format!("failed to spawn test binary `{missing_path}`")),
+            "stderr mentions the missing path: {stderr}"
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_missing_cwd() -> Result<()> {
+    set_env_vars();
+
+    let (events, run_stats) = run_profile(
+        RunIgnored::Default,
+        "test(test_success)",
+        "with-missing-cwd",
+        |_| {},
+    );
+
+    assert!(!run_stats.is_success(), "run should fail");
+    let missing_cwd = workspace_root().join("nonexistent-dir");
+    let finished = finished_tests(&events);
+    for (name, run_statuses) in &finished {
+        let last_status = run_statuses.last_status();
+        if *name == "test_success" {
+            // The test isn't run in the default directory instead.
+            assert_eq!(last_status.result, ExecutionResult::ExecFail);
+            let stderr = String::from_utf8_lossy(&last_status.stderr);
+            assert!(
+                stderr.contains(&format!("working directory `{missing_cwd}`")),
+                "stderr mentions the missing directory: {stderr}"
+            );
+        } else {
+            assert_eq!(
+                last_status.result,
+                ExecutionResult::Pass,
+                "{name} isn't affected by the override"
+            );
+        }
+    }
+    assert!(finished.len() > 1, "tests other than test_success were run");
+
+    Ok(())
+}
+
+#[test]
+fn test_leak_fail() -> Result<()> {
+    set_env_vars();
+
+    for (profile_name, expected) in [
+        (NextestConfig::DEFAULT_PROFILE, ExecutionResult::Leak),
+        ("with-leak-fail", ExecutionResult::LeakFail),
+    ] {
+        let (events, run_stats) = run_profile(
+            RunIgnored::Default,
+            "test(test_subprocess_doesnt_exit)",
+            profile_name,
+            |_| {},
+        );
+
+        let finished = finished_tests(&events);
+        assert_eq!(
+            finished.keys().copied().collect::<Vec<_>>(),
+            ["test_subprocess_doesnt_exit"],
+            "for profile {profile_name}, one test was run"
+        );
+        assert_eq!(
+            finished["test_subprocess_doesnt_exit"].last_status().result,
+            expected,
+            "for profile {profile_name}, leaked test has the expected result"
+        );
+
+        if expected == ExecutionResult::Leak {
+            assert!(run_stats.is_success(), "leaky test passes by default");
+            assert_eq!(run_stats.leaky, 1);
+        } else {
+            assert!(!run_stats.is_success(), "leaky test fails with fail = true");
+            assert_eq!(run_stats.failed, 1);
+            assert_eq!(run_stats.leaky, 0);
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_output_grace_period() -> Result<()> {
+    set_env_vars();
+
+    for (profile_name, expect_trailing_output) in [
+        (NextestConfig::DEFAULT_PROFILE, false),
+        ("with-output-grace-period", true),
+    ] {
+        let (events, _) = run_profile(
+            RunIgnored::Default,
+            "test(test_subprocess_writes_after_exit)",
+            profile_name,
+            |_| {},
+        );
+
+        let finished = finished_tests(&events);
+        assert_eq!(
+            finished.keys().copied().collect::<Vec<_>>(),
+            ["test_subprocess_writes_after_exit"],
+            "for profile {profile_name}, one test was run"
+        );
+        let last_status = finished["test_subprocess_writes_after_exit"].last_status();
+        assert_eq!(
+            last_status.result,
+            ExecutionResult::Leak,
+            "for profile {profile_name}, the output grace period doesn't affect leak detection"
+        );
+        let stdout = String::from_utf8_lossy(&last_status.stdout);
+        assert_eq!(
+            stdout.contains("trailing output from subprocess"),
+            expect_trailing_output,
+            "for profile {profile_name}, trailing output captured: {stdout}"
+        );
+        // The subprocess writes its output about a second after the test exits, which is well
+        // after the leak timeout.
+        assert!(
+            last_status.time_taken < Duration::from_secs(1),
+            "for profile {profile_name}, the output grace period isn't counted towards the \
+             test's duration (took {:?})",
+            last_status.time_taken
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_run_without_cargo() -> Result<()> {
+    set_env_vars();
+
+    // Build a test list from the fixture binaries directly, without reading Cargo configs or a
+    // nextest config file.
+    let test_filter = TestFilterBuilder::new(
+        RunIgnored::Default,
+        None,
+        vec!["test_success", "test_failure_assert"],
+        vec![],
+    )
+    .unwrap();
+    let double_spawn = DoubleSpawnInfo::disabled();
+    let target_runner = TargetRunner::empty();
+    let ctx = TestExecuteContext {
+        double_spawn: &double_spawn,
+        target_runner: &target_runner,
+    };
+    let test_list = TestList::new(
+        &ctx,
+        FIXTURE_TARGETS.test_artifacts.values().cloned(),
+        FIXTURE_TARGETS.rust_build_meta.clone(),
+        &test_filter,
+        EnvironmentMap::empty(),
+        1,
+    )?;
+
+    let config = NextestConfig::default_config(workspace_root());
+    let profile = config
+        .profile(NextestConfig::DEFAULT_PROFILE)
+        .expect("default profile exists")
+        .apply_build_platforms(&BuildPlatforms::new(None).unwrap());
+    let (events, run_stats) = run_test_list(&test_list, profile, |_| {});
+
+    assert!(
+        events
+            .iter()
+            .any(|event| matches!(event, TestEvent::RunStarted { .. })),
+        "run-started event was sent"
+    );
+    assert!(
+        events
+            .iter()
+            .any(|event| matches!(event, TestEvent::RunFinished { .. })),
+        "run-finished event was sent"
+    );
+    let finished: BTreeMap<_, _> = finished_tests(&events)
+        .into_iter()
+        .map(|(name, run_statuses)| (name, run_statuses.last_status().result))
+        .collect();
+    assert_eq!(
+        finished,
+        [
+            (
+                "test_failure_assert",
+                ExecutionResult::Fail {
+                    abort_status: None,
+                    leaked: false,
+                },
+            ),
+            ("test_success", ExecutionResult::Pass),
+        ]
+        .into_iter()
+        .collect(),
+    );
+    assert_eq!(run_stats.passed, 1);
+    assert_eq!(run_stats.failed, 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_capture_override() -> Result<()> {
+    set_env_vars();
+
+    let (events, run_stats) = run_profile(
+        RunIgnored::Default,
+        "test(test_failure_assert) | test(test_failure_error)",
+        "with-capture-override",
+        |_| {},
+    );
+
+    assert_eq!(run_stats.failed, 2, "both tests fail");
+    for (name, run_statuses) in finished_tests(&events) {
+        let last_status = run_statuses.last_status();
+        let stderr = String::from_utf8_lossy(&last_status.stderr);
+        match name {
+            // test_failure_assert inherits stdio, so its panic message isn't captured.
+            "test_failure_assert" => {
+                assert!(
+                    last_status.stdout.is_empty() && last_status.stderr.is_empty(),
+                    "for {name}, output isn't captured (stderr: {stderr})"
+                );
+            }
+            "test_failure_error" => {
+                assert!(
+                    stderr.contains("this is an error"),
+                    "for {name}, output is captured (stderr: {stderr})"
+                );
+            }
+            other => panic!("unexpected test {other}"),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_pty_override() -> Result<()> {
+    set_env_vars();
+
+    let (events, run_stats) = run_profile(
+        RunIgnored::Default,
+        "test(test_failure_assert) | test(test_failure_error)",
+        "with-pty",
+        |_| {},
+    );
+
+    assert_eq!(run_stats.failed, 2, "both tests fail");
+    for (name, run_statuses) in finished_tests(&events) {
+        let last_status = run_statuses.last_status();
+        let stdout = String::from_utf8_lossy(&last_status.stdout);
+        let stderr = String::from_utf8_lossy(&last_status.stderr);
+        match name {
+            // test_failure_assert runs under a pseudo-terminal, so its panic message is captured
+            // along with the rest of its output.
+            "test_failure_assert" => {
+                assert!(
+                    stdout.contains("running 1 test") && stdout.contains("this is an assertion"),
+                    "for {name}, stdout and stderr are merged (stdout: {stdout})"
+                );
+                assert!(
+                    stderr.is_empty(),
+                    "for {name}, nothing is captured as stderr (stderr: {stderr})"
+                );
+            }
+            "test_failure_error" => {
+                assert!(
+                    stderr.contains("this is an error"),
+                    "for {name}, stderr is captured separately (stderr: {stderr})"
+                );
+            }
+            other => panic!("unexpected test {other}"),
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs `test_stdin_read_to_end` under `profile_name`, returning its standard output.
+fn run_stdin_read_to_end(profile_name: &str) -> String {
+    let (events, run_stats) = run_profile(
+        RunIgnored::Default,
+        "test(=test_stdin_read_to_end)",
+        profile_name,
+        |_| {},
+    );
+
+    assert_eq!(run_stats.passed, 1, "test_stdin_read_to_end passed");
+    let finished = finished_tests(&events);
+    let last_status = finished["test_stdin_read_to_end"].last_status();
+    assert!(
+        !last_status.is_slow,
+        "test_stdin_read_to_end didn't wait for input"
+    );
+    String::from_utf8_lossy(&last_status.stdout).into_owned()
+}
+
+#[test]
+fn test_stdin_default() -> Result<()> {
+    set_env_vars();
+
+    // By default, stdin is at end-of-file, so reading it to the end doesn't hang.
+    let stdout = run_stdin_read_to_end(NextestConfig::DEFAULT_PROFILE);
+    assert!(
+        stdout.contains("read 0 bytes from stdin"),
+        "stdin is empty by default (stdout: {stdout})"
+    );
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_stdin_inherit() -> Result<()> {
+    set_env_vars();
+
+    const INPUT: &str = "input from nextest\n";
+
+    // Replace this process's stdin with a pipe containing INPUT, so that the test inherits it.
+    // Nothing else in this process reads from stdin.
+    let saved_stdin = unsafe {
+        let mut fds = [0; 2];
+        assert_eq!(libc::pipe(fds.as_mut_ptr()), 0, "pipe created");
+        let written = libc::write(fds[1], INPUT.as_ptr().cast(), INPUT.len());
+        assert_eq!(written, INPUT.len() as isize, "input written to pipe");
+        libc::close(fds[1]);
+        let saved_stdin = libc::dup(0);
+        assert!(saved_stdin >= 0, "stdin saved");
+        assert_eq!(libc::dup2(fds[0], 0), 0, "pipe attached to stdin");
+        libc::close(fds[0]);
+        saved_stdin
+    };
+
+    let stdout = run_stdin_read_to_end("with-stdin-inherit");
+
+    unsafe {
+        libc::dup2(saved_stdin, 0);
+        libc::close(saved_stdin);
+    }
+
+    assert!(
+        stdout.contains(&format!(
+            "read {} bytes from stdin: {:?}",
+            INPUT.len(),
+            INPUT
+        )),
+        "stdin is inherited with the override (stdout: {stdout})"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_shuffle_seed() -> Result<()> {
+    set_env_vars();
+
+    let run = |shuffle_seed: Option<u64>| {
+        let (events, _) = run_profile(
+            RunIgnored::Default,
+            "package(nextest-tests) & test(/^test_(success|failure|flaky_mod|cwd|result_failure)/)",
+            NextestConfig::DEFAULT_PROFILE,
+            |builder| {
+                builder.set_test_threads(TestThreads::Count(1));
+                if let Some(seed) = shuffle_seed {
+                    builder.set_shuffle_seed(seed);
+                }
+            },
+        );
+
+        let mut started = Vec::new();
+        let mut outcomes = RunOutcomes::new();
+        for event in &events {
+            if let TestEvent::TestStarted { test_instance, .. } = event {
+                started.push(test_instance.name);
+            }
+            outcomes.record(event);
+        }
+        (started, outcomes)
+    };
+
+    let (in_order, in_order_outcomes) = run(None);
+    let (shuffled, shuffled_outcomes) = run(Some(42));
+    let (shuffled_again, _) = run(Some(42));
+
+    assert!(in_order.len() > 2, "enough tests to shuffle: {in_order:?}");
+    assert_eq!(shuffled, shuffled_again, "same seed results in same order");
+    assert_ne!(in_order, shuffled, "seed 42 changes the order");
+    let mut sorted = shuffled.clone();
+    sorted.sort();
+    let mut expected = in_order.clone();
+    expected.sort();
+    assert_eq!(sorted, expected, "shuffling runs the same tests");
+
+    // None of these tests depend on order, and flaky tests are deterministic based on the attempt
+    // number.
+    assert_eq!(in_order_outcomes.len(), in_order.len());
+    assert_eq!(in_order_outcomes.compare(&shuffled_outcomes), []);
+
+    Ok(())
+}
+
+#[test_case("with-pre-flight-pass", true; "passes")]
+#[test_case("with-pre-flight-fail", false; "fails")]
+fn test_pre_flight(profile_name: &str, should_pass: bool) -> Result<()> {
+    set_env_vars();
+
+    let profile = load_profile(profile_name);
+    assert!(profile.pre_flight().is_some(), "pre-flight is configured");
+
+    let runner = TestRunnerBuilder::default()
+        .build(
+            make_filtered_test_list(RunIgnored::Default, "test(=test_success)"),
+            profile,
+            SignalHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    match runner.run_pre_flight(&workspace_root()) {
+        Ok(()) => {
+            assert!(should_pass, "pre-flight command should have failed");
+            configure_handle_inheritance(false)?;
+            let run_stats = runner.execute(|_| {});
+            assert_eq!(
+                run_stats.finished_count, 1,
+                "tests run after pre-flight passes"
+            );
+            assert!(run_stats.is_success());
+        }
+        Err(PreFlightError::Failed {
+            command,
+            exit_status,
+            stderr,
+            ..
+        }) => {
+            assert!(!should_pass, "pre-flight command should have passed");
+            assert_eq!(command, "cargo nextest-fixture-nonexistent-command");
+            assert!(!exit_status.success());
+            assert!(
+                !stderr.is_empty(),
+                "output of the failed pre-flight command is captured"
+            );
+        }
+        Err(error) => panic!("unexpected pre-flight error: {error}"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_observers() -> Result<()> {
+    set_env_vars();
+
+    #[derive(Clone, Default)]
+    struct CountingObserver {
+        // (starts, finishes, attempts of finished tests)
+        counts: Arc<Mutex<(usize, usize, usize)>>,
+    }
+
+    impl TestObserver for CountingObserver {
+        fn test_started(&mut self, _test_instance: TestInstance<'_>, _retry_data: RetryData) {
+            self.counts.lock().unwrap().0 += 1;
+        }
+
+        fn test_finished(
+            &mut self,
+            _test_instance: TestInstance<'_>,
+            run_statuses: &ExecutionStatuses,
+        ) {
+            let mut counts = self.counts.lock().unwrap();
+            counts.1 += 1;
+            counts.2 += run_statuses.len();
+        }
+    }
+
+    struct PanickingObserver;
+
+    impl TestObserver for PanickingObserver {
+        fn test_started(&mut self, _test_instance: TestInstance<'_>, _retry_data: RetryData) {
+            panic!("observer panicked");
+        }
+    }
+
+    let observer = CountingObserver::default();
+    let (_, run_stats) = run_profile(
+        RunIgnored::Default,
+        "test(=test_success) | test(=test_failure_assert) | test(=test_flaky_mod_4)",
+        "with-retries",
+        |builder| {
+            // A panicking observer doesn't stop the run, or other observers from being called.
+            builder.add_observer(PanickingObserver);
+            builder.add_observer(observer.clone());
+        },
+    );
+    assert_eq!(run_stats.finished_count, 3, "all tests finished");
+
+    let (starts, finishes, attempts) = *observer.counts.lock().unwrap();
+    assert_eq!(finishes, 3, "each test finished once");
+    // test_flaky_mod_4 passes on its fourth attempt, and test_failure_assert is tried 3 times.
+    assert_eq!(attempts, 1 + 4 + 3);
+    assert_eq!(
+        starts, attempts,
+        "each attempt, including retries, was started"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_finish_reasons() -> Result<()> {
+    set_env_vars();
+
+    let finish_reasons = |run_ignored, expr: &str, profile_name: &str| {
+        let (events, _) = run_profile(run_ignored, expr, profile_name, |_| {});
+        events
+            .iter()
+            .filter_map(|event| {
+                let reason = event.finish_reason()?;
+                let name = match event {
+                    TestEvent::TestFinished { test_instance, .. }
+                    | TestEvent::TestSkipped { test_instance, .. } => test_instance.name,
+                    _ => unreachable!("only finished and skipped tests have finish reasons"),
+                };
+                Some((name, reason))
+            })
+            .collect::<BTreeMap<_, _>>()
+    };
+
+    cfg_if! {
+        if #[cfg(unix)] {
+            let segfault_status = AbortStatus::UnixSignal(libc::SIGSEGV);
+        } else if #[cfg(windows)] {
+            let segfault_status = AbortStatus::WindowsNtStatus(
+                windows::Win32::Foundation::STATUS_ACCESS_VIOLATION,
+            );
+        }
+    }
+
+    let reasons = finish_reasons(
+        RunIgnored::Default,
+        "test(=test_success) | test(=test_failure_assert) | test(=test_segfault) \
+         | test(=test_subprocess_doesnt_exit) | test(=test_ignored)",
+        NextestConfig::DEFAULT_PROFILE,
+    );
+    for (name, expected) in [
+        ("test_success", FinishReason::Pass),
+        (
+            "test_failure_assert",
+            FinishReason::Failed {
+                exit_code: Some(101),
+                leaked: false,
+            },
+        ),
+        (
+            "test_segfault",
+            FinishReason::Aborted {
+                abort_status: segfault_status,
+                leaked: false,
+            },
+        ),
+        ("test_subprocess_doesnt_exit", FinishReason::Leak),
+        (
+            "test_ignored",
+            FinishReason::Skipped(MismatchReason::Ignored),
+        ),
+    ] {
+        assert_eq!(
+            reasons.get(name),
+            Some(&expected),
+            "{name} has the expected finish reason"
+        );
+    }
+
+    let reasons = finish_reasons(
+        RunIgnored::IgnoredOnly,
+        "test(=test_slow_timeout)",
+        "with-termination",
+    );
+    assert_eq!(
+        reasons.get("test_slow_timeout"),
+        Some(&FinishReason::Timeout),
+        "test_slow_timeout timed out"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_execute_phases() -> Result<()> {
+    set_env_vars();
+
+    let (events, _) = run_profile(
+        RunIgnored::Default,
+        "test(=test_success) | test(=test_failure_assert) | test(=test_execute_bin)",
+        NextestConfig::DEFAULT_PROFILE,
+        |_| {},
+    );
+
+    let finished = finished_tests(&events);
+    assert!(!finished.is_empty(), "tests were run");
+    for (name, run_statuses) in finished {
+        let run_status = run_statuses.last_status();
+        let phases = run_status
+            .phases
+            .unwrap_or_else(|| panic!("{name} was spawned, so it has phases"));
+
+        assert_eq!(
+            phases.queued_to_spawned + phases.spawned_to_exited,
+            run_status.time_taken,
+            "{name}: phases add up to the time taken"
+        );
+        // The libtest harness always prints a header to standard output before it exits.
+        let first_output = phases
+            .spawned_to_first_output
+            .unwrap_or_else(|| panic!("{name} produced output"));
+        assert!(
+            first_output <= phases.spawned_to_exited,
+            "{name}: first output ({first_output:?}) arrived before the test exited ({:?})",
+            phases.spawned_to_exited,
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_json_lines_reporter() -> Result<()> {
+    set_env_vars();
+
+    let profile = load_profile("with-retries");
+    let mut buf = Vec::new();
+    report_profile(
+        "test(=test_success) | test(=test_failure_assert) | test(=test_flaky_mod_4)",
+        &profile,
+        TestReporterBuilder::default().set_event_format(EventFormat::JsonLines),
+        ReporterStderr::Buffer(&mut buf),
+    );
+
+    let lines: Vec<serde_json::Value> = std::str::from_utf8(&buf)?
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+    assert_eq!(lines.first().unwrap()["kind"], "run-started");
+    assert_eq!(lines.last().unwrap()["kind"], "run-finished");
+
+    let mut last_timestamp = 0.0;
+    for line in &lines {
+        assert_eq!(line["format-version"], JSON_LINES_FORMAT_VERSION, "{line}");
+        let timestamp = line["timestamp"].as_f64().unwrap();
+        assert!(timestamp >= last_timestamp, "timestamps are monotonic");
+        last_timestamp = timestamp;
+    }
+
+    let finished: std::collections::BTreeMap<_, _> = lines
+        .iter()
+        .filter(|line| line["kind"] == "test-finished")
+        .map(|line| {
+            assert!(line["duration"].is_f64(), "{line} has a duration");
+            (
+                line["test-name"].as_str().unwrap(),
+                line["status"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    let expected: std::collections::BTreeMap<_, _> = [
+        ("test_failure_assert", "fail"),
+        ("test_flaky_mod_4", "flaky"),
+        ("test_success", "pass"),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(finished, expected);
+
+    let started = lines
+        .iter()
+        .filter(|line| line["kind"] == "test-started")
+        .count();
+    assert_eq!(started, 3, "each test started once");
+
+    // Every attempt, including ones that were retried, records the slot it ran in.
+    let test_threads = profile.test_threads().compute();
+    let attempts = lines
+        .iter()
+        .filter(|line| line["kind"] == "test-attempt-failed" || line["kind"] == "test-finished")
+        .inspect(|line| {
+            let slot = line["slot"].as_u64().expect("attempt has a slot") as usize;
+            assert!(
+                slot < test_threads,
+                "{line} has a slot below {test_threads}"
+            );
+        })
+        .count();
+    // test_flaky_mod_4 passes on its fourth attempt, and test_failure_assert is tried 3 times.
+    assert_eq!(attempts, 1 + 4 + 3, "each attempt records a slot");
+
+    Ok(())
+}
+
+#[test]
+fn test_immediate_failure_output() -> Result<()> {
+    set_env_vars();
+
+    /// Records each write separately, to check that events are written out at once.
+    struct ChunkWriter(Arc<Mutex<Vec<String>>>);
+
+    impl std::io::Write for ChunkWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0
+                .lock()
+                .unwrap()
+                .push(String::from_utf8_lossy(buf).into_owned());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let chunks = Arc::new(Mutex::new(Vec::new()));
+    report_profile(
+        "test(=test_success) | test(=test_failure_assert) | test(=test_cwd)",
+        &load_profile(NextestConfig::DEFAULT_PROFILE),
+        TestReporterBuilder::default()
+            .set_failure_output(TestOutputDisplay::Immediate)
+            .set_status_level(StatusLevel::Pass),
+        ReporterStderr::Writer(Box::new(ChunkWriter(chunks.clone()))),
+    );
+
+    let chunks = chunks.lock().unwrap();
+    let fail_index = chunks
+        .iter()
+        .position(|chunk| chunk.contains("FAIL [") && chunk.contains("test_failure_assert"))
+        .expect("failure is reported");
+    let fail_chunk = &chunks[fail_index];
+
+    // The failing test's output is written out along with its status line, rather than at the end
+    // of the run.
+    let status_line = fail_chunk.find("FAIL [").unwrap();
+    let output_header = fail_chunk
+        .find("STDERR:")
+        .expect("output is shown immediately");
+    assert!(status_line < output_header, "{fail_chunk}");
+    assert!(fail_chunk.contains("this is an assertion"), "{fail_chunk}");
+
+    let summary_index = chunks
+        .iter()
+        .position(|chunk| chunk.contains("Summary ["))
+        .expect("summary is reported");
+    assert!(
+        fail_index < summary_index,
+        "failure is reported before the summary"
+    );
+    assert!(
+        !chunks[summary_index].contains("this is an assertion"),
+        "output isn't shown again at the end of the run"
+    );
+
+    // Other events are written out separately.
+    assert!(
+        !fail_chunk.contains("PASS ["),
+        "events aren't batched together: {fail_chunk}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_status_level_none() -> Result<()> {
+    set_env_vars();
+
+    let mut buf = Vec::new();
+    report_profile(
+        "test(=test_success) | test(=test_failure_assert) | test(=test_cwd)",
+        &load_profile(NextestConfig::DEFAULT_PROFILE),
+        TestReporterBuilder::default()
+            .set_failure_output(TestOutputDisplay::Immediate)
+            .set_status_level(StatusLevel::None),
+        ReporterStderr::Buffer(&mut buf),
+    );
+
+    let output = String::from_utf8(buf)?;
+    let (during_run, summary) = output.split_once("Summary [").expect("summary is reported");
+    assert!(
+        !during_run.contains("PASS [") && !during_run.contains("FAIL ["),
+        "no per-test status lines are shown: {during_run}"
+    );
+    assert!(
+        during_run.contains("test_failure_assert") && during_run.contains("this is an assertion"),
+        "output of failing tests is still shown: {during_run}"
+    );
+    assert!(
+        !during_run.contains("test_success"),
+        "passing tests aren't shown: {during_run}"
+    );
+    assert!(
+        summary.contains("3 tests run: 2 passed") && summary.contains("1 failed"),
+        "final tally is shown: {summary}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_junit_retries() -> Result<()> {
+    set_env_vars();
+
+    let profile = load_profile("with-junit");
+    let junit_path = profile
+        .junit()
+        .expect("with-junit has a JUnit report")
+        .path()
+        .to_owned();
+    if junit_path.exists() {
+        std::fs::remove_file(&junit_path)?;
+    }
+
+    let mut buf = Vec::new();
+    report_profile(
+        "test(=test_flaky_mod_4) | test(=test_failure_assert)",
+        &profile,
+        &TestReporterBuilder::default(),
+        ReporterStderr::Buffer(&mut buf),
+    );
+
+    let junit = std::fs::read_to_string(&junit_path)?;
+    let testcase = |name: &str| {
+        let start = junit
+            .find(&format!("<testcase name=\"{name}\""))
+            .unwrap_or_else(|| panic!("{name} is in the report"));
+        let end = start + junit[start..].find("</testcase>").unwrap();
+        &junit[start..end]
+    };
+
+    // test_flaky_mod_4 passed on its fourth try, so the three earlier attempts are reported as
+    // flaky failures, along with their output.
+    let flaky = testcase("test_flaky_mod_4");
+    assert_eq!(flaky.matches("<flakyFailure").count(), 3, "{flaky}");
+    assert!(!flaky.contains("<rerunFailure"), "{flaky}");
+    assert!(
+        flaky.contains("Failed because attempt 1 % 4 != 0"),
+        "first attempt's output is stored: {flaky}"
+    );
+
+    // test_failure_assert failed every time, so its retries are reported as rerun failures.
+    let failure = testcase("test_failure_assert");
+    assert_eq!(failure.matches("<failure").count(), 1, "{failure}");
+    assert_eq!(failure.matches("<rerunFailure").count(), 2, "{failure}");
+    assert!(!failure.contains("<flakyFailure"), "{failure}");
+
+    Ok(())
+}
+
+#[test_case("junit-by-binary", &[
+    ("nextest-derive::proc-macro/nextest-derive", 1),
+    ("nextest-tests", 1),
+    ("nextest-tests::basic", 1),
+    ("nextest-tests::other", 1),
+] ; "by binary")]
+#[test_case("junit-by-package", &[("nextest-derive", 1), ("nextest-tests", 3)] ; "by package")]
+#[test_case("junit-by-test-group", &[("@global", 2), ("flaky", 2)] ; "by test group")]
+fn test_junit_group_by(profile_name: &str, expected: &[(&str, usize)]) -> Result<()> {
+    set_env_vars();
+
+    let profile = load_profile(profile_name);
+    let junit_path = profile
+        .junit()
+        .expect("profile has a JUnit report")
+        .path()
+        .to_owned();
+    if junit_path.exists() {
+        std::fs::remove_file(&junit_path)?;
+    }
+
+    let mut buf = Vec::new();
+    report_profile(
+        "test(=test_success) | test(=other_test_success) | test(=tests::unit_test_success) \
+         | test(=it_works)",
+        &profile,
+        &TestReporterBuilder::default(),
+        ReporterStderr::Buffer(&mut buf),
+    );
+
+    // Map each test suite's name to the number of test cases in it.
+    let junit = std::fs::read_to_string(&junit_path)?;
+    let suites: BTreeMap<_, _> = junit
+        .split("<testsuite name=\"")
+        .skip(1)
+        .map(|suite| {
+            let name = &suite[..suite.find('"').unwrap()];
+            (name, suite.matches("<testcase ").count())
+        })
+        .collect();
+    assert_eq!(
+        suites,
+        expected.iter().copied().collect::<BTreeMap<_, _>>(),
+        "for profile {profile_name}, test suites match"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_multiple_reports() -> Result<()> {
+    set_env_vars();
+
+    let profile = load_profile("with-reports");
+    let reports = profile.reports();
+    let formats: Vec<_> = reports.iter().map(|report| report.format()).collect();
+    assert_eq!(formats, [ReportFormat::Junit, ReportFormat::Json]);
+    for report in &reports {
+        if report.path().exists() {
+            std::fs::remove_file(report.path())?;
+        }
+    }
+
+    let mut buf = Vec::new();
+    report_profile(
+        "test(=test_success) | test(=test_failure_assert)",
+        &profile,
+        &TestReporterBuilder::default(),
+        ReporterStderr::Buffer(&mut buf),
+    );
+
+    // Both reports are written out from the same run.
+    let junit = std::fs::read_to_string(reports[0].path())?;
+    for name in ["test_success", "test_failure_assert"] {
+        assert!(
+            junit.contains(&format!("<testcase name=\"{name}\"")),
+            "{name} is in the JUnit report"
+        );
+    }
+    let summary: RunSummary = serde_json::from_str(&std::fs::read_to_string(reports[1].path())?)?;
+    assert_eq!(summary.stats.passed, 1);
+    assert_eq!(summary.stats.failed, 1);
+    let basic = &summary.tests[&RustBinaryId::from("nextest-tests::basic")];
+    assert_eq!(
+        basic.keys().map(|name| name.as_str()).collect::<Vec<_>>(),
+        ["test_failure_assert", "test_success"],
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_summary_path() -> Result<()> {
+    set_env_vars();
+
+    const FILTER: &str =
+        "test(=test_success) | test(=test_failure_assert) | test(=test_flaky_mod_4)";
+
+    let profile = load_profile("with-summary");
+    let summary_path = profile.summary_path().expect("summary-path is set");
+    assert_eq!(summary_path, profile.store_dir().join("summary.json"));
+    if summary_path.exists() {
+        std::fs::remove_file(&summary_path)?;
+    }
+
+    let mut buf = Vec::new();
+    let run_stats = report_profile(
+        FILTER,
+        &profile,
+        &TestReporterBuilder::default(),
+        ReporterStderr::Buffer(&mut buf),
+    );
+
+    let summary: FinalSummary = serde_json::from_str(&std::fs::read_to_string(&summary_path)?)?;
+    let expected = FinalSummary {
+        profile: "with-summary".to_owned(),
+        status: FinalRunStatus::Failed,
+        exit_code: NextestExitCode::TEST_RUN_FAILED,
+        canceled: false,
+        // The time taken can't be predicted.
+        elapsed: summary.elapsed,
+        run: 3,
+        // test_flaky_mod_4 passes on retry.
+        passed: 2,
+        failed: 1,
+        flaky: 1,
+        skipped: make_filtered_test_list(RunIgnored::Default, FILTER).skip_count(),
+    };
+    assert_eq!(summary, expected);
+    assert_eq!(
+        summary.status,
+        run_stats.final_status(profile.flaky_as_failure()),
+        "status matches the run"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_junit_partial() -> Result<()> {
+    set_env_vars();
+
+    let test_list = make_filtered_test_list(
+        RunIgnored::Default,
+        "test(=test_success) | test(=test_cwd) | test(=test_stdin_closed)",
+    );
+    let profile = load_profile("junit-partial");
+    let junit_path = profile
+        .junit()
+        .expect("junit-partial has a JUnit report")
+        .path()
+        .to_owned();
+    let partial_path = format!("{junit_path}.partial");
+    for path in [junit_path.as_str(), partial_path.as_str()] {
+        if Utf8Path::new(path).exists() {
+            std::fs::remove_file(path)?;
+        }
+    }
+
+    let run = |finished_limit: usize| -> Result<()> {
+        let mut buf = Vec::new();
+        let mut reporter = TestReporterBuilder::default().build(
+            test_list,
+            &profile,
+            ReporterStderr::Buffer(&mut buf),
+        );
+        let runner = TestRunnerBuilder::default()
+            .build(
+                test_list,
+                profile.clone(),
+                SignalHandlerKind::Noop,
+                DoubleSpawnInfo::disabled(),
+                TargetRunner::empty(),
+            )
+            .unwrap();
+        configure_handle_inheritance(false)?;
+        let mut finished = 0;
+        runner.try_execute(|event| {
+            // Simulate nextest being killed once enough tests have finished, by not passing on any
+            // later events.
+            if finished == finished_limit {
+                return Ok(());
+            }
+            if matches!(event, TestEvent::TestFinished { .. }) {
+                finished += 1;
+            }
+            reporter.report_event(event)
+        })?;
+        Ok(())
+    };
+
+    // Interrupted after 2 tests: only the partial report is written, with those 2 tests.
+    run(2)?;
+    assert!(!junit_path.exists(), "complete report isn't written out");
+    let partial = std::fs::read_to_string(&partial_path)?;
+    assert!(
+        partial.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites>\n"),
+        "{partial}"
+    );
+    assert_eq!(partial.matches("<testcase ").count(), 2, "{partial}");
+    assert_eq!(
+        partial.matches("<testsuite ").count(),
+        partial.matches("</testsuite>").count(),
+        "partial report only has whole test suites: {partial}"
+    );
+    assert!(!partial.contains("</testsuites>"), "{partial}");
+
+    // A complete run replaces the partial report with the complete one.
+    run(usize::MAX)?;
+    assert!(
+        !Utf8Path::new(&partial_path).exists(),
+        "partial report is removed"
+    );
+    let junit = std::fs::read_to_string(&junit_path)?;
+    assert_eq!(junit.matches("<testcase ").count(), 3, "{junit}");
+    assert!(junit.trim_end().ends_with("</testsuites>"), "{junit}");
+
+    Ok(())
+}
+
+#[test]
+fn test_suite() -> Result<()> {
+    set_env_vars();
+
+    let config = load_config();
+    let suite = config.suite("flaky").expect("flaky suite is defined");
+
+    let mut test_filter = TestFilterBuilder::any(RunIgnored::Default);
+    test_filter.set_suite(&suite);
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty());
+
+    // The default profile doesn't retry tests, so the flaky tests only pass with the suite's
+    // settings layered over it.
+    let profile = config
+        .profile(NextestConfig::DEFAULT_PROFILE)
+        .expect("default config is valid")
+        .with_suite(&suite)
+        .apply_build_platforms(&BuildPlatforms::new(None).unwrap());
+    let (events, run_stats) = run_test_list(&test_list, profile, |_| {});
+
+    for (name, reason) in skipped_tests(&events) {
+        assert!(
+            !name.starts_with("test_flaky_mod"),
+            "{name} is in the suite, but was skipped: {reason}"
+        );
+    }
+    let finished: BTreeMap<_, _> = finished_tests(&events)
+        .into_iter()
+        .map(|(name, run_statuses)| {
+            assert!(
+                matches!(run_statuses.describe(), ExecutionDescription::Flaky { .. }),
+                "{name} should be flaky"
+            );
+            (name, run_statuses.len())
+        })
+        .collect();
+    assert_eq!(
+        finished,
+        [("test_flaky_mod_4", 4), ("test_flaky_mod_6", 6)]
+            .into_iter()
+            .collect(),
+        "only tests in the suite are run, with the suite's retries"
+    );
+    assert!(run_stats.is_success(), "run should be marked successful");
+
+    Ok(())
+}
+
+#[test]
+fn test_default_filter() -> Result<()> {
+    set_env_vars();
+
+    let config = load_config();
+    let profile = config
+        .profile("with-default-filter")
+        .expect("with-default-filter config is valid");
+    assert!(
+        config
+            .profile(NextestConfig::DEFAULT_PROFILE)
+            .expect("default config is valid")
+            .default_filter()
+            .is_none(),
+        "the default profile doesn't have a default filter"
+    );
+
+    // With no other filters, the default filter alone selects tests.
+    let mut test_filter = TestFilterBuilder::any(RunIgnored::Default);
+    test_filter.set_default_filter(profile.default_filter());
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty());
+
+    let mut matched = BTreeSet::new();
+    for test in test_list.iter_tests() {
+        match test.test_info.filter_match {
+            FilterMatch::Matches => {
+                assert!(
+                    !test.name.contains("test_flaky"),
+                    "{} is excluded by the default filter",
+                    test.name
+                );
+                matched.insert(test.name);
+            }
+            FilterMatch::Mismatch { reason } => {
+                if test.name.contains("test_flaky") {
+                    assert_eq!(reason, MismatchReason::Expression);
+                }
+            }
+        }
+    }
+    assert!(
+        matched.contains("test_success"),
+        "tests not excluded by the default filter are selected"
+    );
+
+    // Filters are intersected with the default filter.
+    let expr = FilteringExpr::parse(
+        "test(=test_success) | test(=test_flaky_mod_4)".to_owned(),
+        &PACKAGE_GRAPH,
+    )
+    .expect("filter expression is valid");
+    let mut test_filter =
+        TestFilterBuilder::new(RunIgnored::Default, None, Vec::<String>::new(), vec![expr])
+            .unwrap();
+    test_filter.set_default_filter(profile.default_filter());
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty());
+
+    let matched: BTreeSet<_> = test_list
+        .iter_tests()
+        .filter(|test| test.test_info.filter_match.is_match())
+        .map(|test| (test.suite_info.binary_id.as_str(), test.name))
+        .collect();
+    assert_eq!(
+        matched,
+        [("nextest-tests::basic", "test_success")]
+            .into_iter()
+            .collect(),
+        "only tests matched by both filters are selected"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_quarantine() -> Result<()> {
+    set_env_vars();
+
+    let (events, run_stats) = run_profile(
+        RunIgnored::Default,
+        "test(=test_success) | test(=test_failure_assert) | test(=test_flaky_mod_4)",
+        "with-quarantine",
+        |_| {},
+    );
+
+    let quarantined: BTreeSet<_> = skipped_tests(&events)
+        .into_iter()
+        .filter(|(_, reason)| *reason == MismatchReason::Quarantined)
+        .map(|(name, _)| name)
+        .collect();
+    assert_eq!(
+        quarantined,
+        ["test_failure_assert", "test_flaky_mod_4"]
+            .into_iter()
+            .collect(),
+        "quarantined tests are reported as such"
+    );
+    assert_eq!(
+        finished_tests(&events).into_keys().collect::<BTreeSet<_>>(),
+        ["test_success"].into_iter().collect(),
+        "quarantined tests aren't run"
+    );
+
+    assert_eq!(run_stats.quarantined, 2);
+    assert_eq!(run_stats.initial_run_count, 1);
+    assert_eq!(run_stats.finished_count, 1);
+    assert_eq!(
+        run_stats.failed, 0,
+        "quarantined failing test isn't counted"
+    );
+    assert!(run_stats.is_success(), "run should be marked successful");
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_success_signals() -> Result<()> {
+    set_env_vars();
+
+    let (events, _) = run_profile(
+        RunIgnored::Default,
+        "test(test_segfault) | test(test_failure_assert)",
+        "with-success-signals",
+        |_| {},
+    );
+
+    let finished = finished_tests(&events);
+    let segfault_status = finished
+        .get("test_segfault")
+        .expect("test_segfault was run")
+        .last_status();
+    assert_eq!(
+        segfault_status.result,
+        ExecutionResult::Pass,
+        "test terminated by an allowlisted signal passes"
+    );
+    assert!(
+        !finished["test_failure_assert"]
+            .last_status()
+            .result
+            .is_success(),
+        "test_failure_assert isn't affected by the override"
+    );
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_tracer() -> Result<()> {
+    set_env_vars();
+
+    let (events, run_stats) = run_profile(
+        RunIgnored::Default,
+        "test(test_success) | test(test_cwd)",
+        "with-tracer",
+        |_| {},
+    );
+
+    assert!(run_stats.is_success(), "run should be marked successful");
+    let finished = finished_tests(&events);
+    assert!(
+        finished.contains_key("test_success"),
+        "test_success was run"
+    );
+    for (name, run_statuses) in finished {
+        let last_status = run_statuses.last_status();
+        assert_eq!(
+            last_status.result,
+            ExecutionResult::Pass,
+            "{name} passes under the tracer"
+        );
+        if name == "test_success" {
+            let trace_file = last_status
+                .trace_file
+                .as_ref()
+                .expect("test_success was traced");
+            let trace = std::fs::read_to_string(trace_file)?;
+            assert_eq!(trace, "traced\n", "trace is written to the trace file");
+            let stdout = String::from_utf8_lossy(&last_status.stdout);
+            assert!(
+                !stdout.contains("traced"),
+                "trace isn't mixed into the test's output: {stdout}"
+            );
+        } else {
+            assert_eq!(
+                last_status.trace_file, None,
+                "{name} isn't affected by the override"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_github_annotations() -> Result<()> {
+    set_env_vars();
+
+    let mut buf = Vec::new();
+    report_profile(
+        "test(=test_success) | test(=test_failure_assert) | test(=test_flaky_mod_4)",
+        &load_profile("with-retries"),
+        TestReporterBuilder::default().set_github_annotations(true),
+        ReporterStderr::Buffer(&mut buf),
+    );
+
+    let output = std::str::from_utf8(&buf)?;
+    let annotations: Vec<_> = output
+        .lines()
+        .filter(|line| line.starts_with("::"))
+        .collect();
+    // Flaky tests eventually pass, so only the hard failure is annotated.
+    assert_eq!(annotations.len(), 1, "exactly one annotation:\n{output}");
+    assert!(
+        annotations[0].starts_with(
+            "::error title=nextest-tests%3A%3Abasic test_failure_assert::\
+             thread 'test_failure_assert' panicked at "
+        ),
+        "annotation has escaped title and first failure line: {}",
+        annotations[0]
+    );
+    assert!(
+        output.contains("FAIL"),
+        "annotations are written alongside human-readable output:\n{output}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_exclusive() -> Result<()> {
+    set_env_vars();
+
+    let (events, _) = run_profile(RunIgnored::Default, "all()", "with-exclusive", |_| {});
+    assert_ran_alone(&events, &["test_success", "test_cwd"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_whole_machine() -> Result<()> {
+    set_env_vars();
+
+    let (events, _) = run_profile(RunIgnored::Default, "all()", "with-whole-machine", |_| {});
+    // test_cwd is also in a test group, which doesn't let it run alongside other tests.
+    assert_ran_alone(&events, &["test_success", "test_cwd"]);
+
+    Ok(())
+}
+
+/// Returns the binary ID, test name, and the time span each finished test was executing for.
+fn test_spans<'a>(events: &[TestEvent<'a>]) -> Vec<(&'a str, &'a str, SystemTime, SystemTime)> {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            TestEvent::TestFinished {
+                test_instance,
+                run_statuses,
+                ..
+            } => {
+                let start = run_statuses.iter().next().unwrap().start_time;
+                let last_status = run_statuses.last_status();
+                let end = last_status.start_time + last_status.time_taken;
+                Some((
+                    test_instance.suite_info.binary_id.as_str(),
+                    test_instance.name,
+                    start,
+                    end,
+                ))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Asserts that the given tests in `nextest-tests::basic` didn't overlap with any other test.
+fn assert_ran_alone(events: &[TestEvent<'_>], alone_names: &[&str]) {
+    let spans = test_spans(events);
+
+    for &alone_name in alone_names {
+        let &(alone_binary_id, _, alone_start, alone_end) = spans
+            .iter()
+            .find(|(binary_id, name, ..)| {
+                *name == alone_name && *binary_id == "nextest-tests::basic"
+            })
+            .unwrap_or_else(|| panic!("{alone_name} should have been run"));
+
+        for &(binary_id, name, start, end) in &spans {
+            if name == alone_name && binary_id == alone_binary_id {
+                continue;
+            }
+            assert!(
+                end <= alone_start || start >= alone_end,
+                "{binary_id} {name} ({start:?} to {end:?}) overlaps with test {alone_name}, \
+                 which should have run alone ({alone_start:?} to {alone_end:?})"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_global_max_threads() -> Result<()> {
+    set_env_vars();
+
+    let (events, _) = run_profile(
+        RunIgnored::Default,
+        "all()",
+        "with-global-max-threads",
+        |builder| {
+            // The cap also applies to an explicitly set number of test threads.
+            builder.set_test_threads(TestThreads::Count(16));
+        },
+    );
+
+    // Sweep over the start and end times of each run test to find the most tests that were
+    // running at once. Ends sort before starts at the same time, since the test that ended freed
+    // up its thread.
+    let mut edges: Vec<_> = test_spans(&events)
+        .into_iter()
+        .flat_map(|(_, _, start, end)| [(start, 1), (end, -1)])
+        .collect();
+    assert!(edges.len() > 2 * 8, "more tests were run than the cap");
+    edges.sort();
+
+    let mut running = 0;
+    let mut max_running = 0;
+    for (_, delta) in edges {
+        running += delta;
+        max_running = max_running.max(running);
+    }
+    assert!(
+        max_running <= 8,
+        "at most 8 tests ran at once, but found {max_running}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_priority() -> Result<()> {
+    set_env_vars();
+
+    let (events, _) = run_profile(
+        RunIgnored::Default,
+        "test(=test_success) | test(=test_cwd) | test(=test_failure_assert) \
+         | test(=test_execute_bin) | test(=test_stdin_closed)",
+        "with-priority",
+        |_| {},
+    );
+
+    let started: Vec<_> = events
+        .iter()
+        .filter_map(|event| match event {
+            TestEvent::TestStarted { test_instance, .. } => Some(test_instance.name),
+            _ => None,
+        })
+        .collect();
+
+    // With a single test thread, tests are started in dispatch order: by descending priority,
+    // then by test name. The first matching override wins, so test_stdin_closed has priority 10.
+    assert_eq!(
+        started,
+        [
+            "test_stdin_closed",
+            "test_cwd",
+            "test_execute_bin",
+            "test_success",
+            "test_failure_assert",
+        ],
+        "tests are started in priority order"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_shuffle_with_seed() -> Result<()> {
+    set_env_vars();
+
+    let expr = FilteringExpr::parse(
+        "test(=test_success) | test(=test_cwd) | test(=test_failure_assert) \
+         | test(=test_execute_bin) | test(=test_stdin_closed)"
+            .to_owned(),
+        &PACKAGE_GRAPH,
+    )
+    .unwrap();
+    // Partitioning is applied before shuffling, so only tests in this shard are run.
+    let test_filter = TestFilterBuilder::new(
+        RunIgnored::Default,
+        Some(PartitionerBuilder::Count {
+            shard: 1,
+            total_shards: 2,
+        }),
+        Vec::<String>::new(),
+        vec![expr],
+    )
+    .unwrap();
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty());
+
+    let mut expected: Vec<_> = test_list
+        .iter_tests()
+        .filter(|test| test.test_info.filter_match.is_match())
+        .map(|test| test.name)
+        .collect();
+    expected.sort_unstable();
+
+    let mut orders = Vec::new();
+    for _ in 0..2 {
+        let (events, _) = run_test_list(&test_list, load_profile("with-shuffle"), |_| {});
+
+        let mut seed = None;
+        let mut started = Vec::new();
+        for event in &events {
+            match event {
+                TestEvent::RunStarted { shuffle_seed, .. } => seed = *shuffle_seed,
+                TestEvent::TestStarted { test_instance, .. } => started.push(test_instance.name),
+                _ => {}
+            }
+        }
+
+        assert_eq!(seed, Some(12345), "seed from the profile is reported");
+        let mut sorted = started.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, expected, "only tests in this shard are run");
+        orders.push(started);
+    }
+
+    assert_eq!(
+        orders[0], orders[1],
+        "the same seed results in the same order"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_max_failures() -> Result<()> {
+    set_env_vars();
+
+    // Six tests fail: test_success unexpectedly passes, and the others fail outright.
+    let (events, run_stats) = run_profile(
+        RunIgnored::All,
+        "test(=test_failure_assert) | test(=test_failure_error) \
+         | test(=test_failure_should_panic) | test(=test_ignored_fail) \
+         | test(=test_result_failure) | test(=test_success)",
+        "with-max-failures",
+        |_| {},
+    );
+
+    let mut finished = 0;
+    let mut max_failures_event = None;
+    let mut cancel_reason = None;
+    for event in &events {
+        match event {
+            TestEvent::TestFinished { .. } => {
+                assert_eq!(cancel_reason, None, "no tests finish after cancellation");
+                finished += 1;
+            }
+            TestEvent::RunMaxFailures {
+                failed,
+                max_failures,
+                ..
+            } => {
+                max_failures_event = Some((finished, *failed, max_failures.get()));
+            }
+            TestEvent::RunBeginCancel { reason, .. } => {
+                cancel_reason = Some(*reason);
+            }
+            _ => {}
+        }
+    }
+
+    assert_eq!(
+        max_failures_event,
+        Some((5, 5, 5)),
+        "cancellation began after the fifth failure"
+    );
+    assert_eq!(cancel_reason, Some(CancelReason::TestFailure));
+    assert_eq!(run_stats.finished_count, 5, "the sixth test wasn't run");
+    assert_eq!(run_stats.initial_run_count, 6);
+    assert!(run_stats.any_failed(), "run failed");
+
+    Ok(())
+}
+
+#[test_case(None; "all failures known")]
+#[test_case(Some("test_failure_error"); "new failure")]
+fn test_known_failures(new_failure: Option<&str>) -> Result<()> {
+    set_env_vars();
+
+    // The same six failing tests as test_max_failures.
+    let test_list = make_filtered_test_list(
+        RunIgnored::All,
+        "test(=test_failure_assert) | test(=test_failure_error) \
+         | test(=test_failure_should_panic) | test(=test_ignored_fail) \
+         | test(=test_result_failure) | test(=test_success)",
+    );
+
+    // Every test other than new_failure is known to fail.
+    let mut known_failures = KnownFailures::new();
+    for test_instance in test_list.iter_tests() {
+        if Some(test_instance.name) != new_failure {
+            known_failures.insert(
+                test_instance.suite_info.binary_id.clone(),
+                test_instance.name,
+            );
+        }
+    }
+
+    let (events, run_stats) =
+        run_test_list(test_list, load_profile("with-max-failures"), |builder| {
+            builder
+                .set_fail_fast(true.into())
+                .set_known_failures(known_failures);
+        });
+
+    let mut last_finished = None;
+    let mut max_failures_event = None;
+    for event in &events {
+        match event {
+            TestEvent::TestFinished { test_instance, .. } => {
+                last_finished = Some(test_instance.name);
+            }
+            TestEvent::RunMaxFailures { failed, .. } => {
+                max_failures_event = Some((last_finished, *failed));
+            }
+            _ => {}
+        }
+    }
+
+    match new_failure {
+        None => {
+            assert_eq!(
+                max_failures_event, None,
+                "known failures don't cancel the run"
+            );
+            assert_eq!(run_stats.finished_count, 6, "all tests were run");
+        }
+        Some(new_failure) => {
+            assert_eq!(
+                max_failures_event,
+                Some((Some(new_failure), 1)),
+                "the new failure canceled the run, and only it was counted"
+            );
+        }
+    }
+    assert!(run_stats.any_failed(), "known failures still fail the run");
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test_case("with-health-check", "NEXTEST_FIXTURE_HEALTH_CHECK_LOG", false; "passes")]
+#[test_case("with-health-check-fail", "NEXTEST_FIXTURE_HEALTH_CHECK_FAIL_LOG", true; "fails")]
+fn test_health_check(profile_name: &str, log_var: &str, should_fail: bool) -> Result<()> {
+    set_env_vars();
+    let temp_dir = camino_tempfile::tempdir()?;
+    let log_path = temp_dir.path().join("health-check.log");
+    std::env::set_var(log_var, &log_path);
+
+    let (events, run_stats) = run_profile(
+        RunIgnored::Default,
+        "test(=test_success) | test(=test_failure_assert) | test(=test_failure_error) \
+         | test(=test_success_should_panic) | test(=test_failure_should_panic) \
+         | test(=test_cwd)",
+        profile_name,
+        |_| {},
+    );
+
+    let mut finished = 0;
+    let mut health_check_failed = None;
+    let mut cancel_reason = None;
+    for event in &events {
+        match event {
+            TestEvent::TestStarted { .. } => {
+                assert_eq!(
+                    health_check_failed, None,
+                    "no tests start after the health check fails"
+                );
+            }
+            TestEvent::TestFinished { .. } => {
+                finished += 1;
+            }
+            TestEvent::RunHealthCheckFailed { message, .. } => {
+                health_check_failed = Some((finished, message));
+            }
+            TestEvent::RunBeginCancel { reason, .. } => {
+                cancel_reason = Some(*reason);
+            }
+            _ => {}
+        }
+    }
+
+    // With one test thread, the health check runs after the second and fourth tests, but not after
+    // the sixth (last) one.
+    let health_check_runs = std::fs::read_to_string(&log_path)?.lines().count();
+    assert_eq!(health_check_runs, 2, "health check ran every 2 tests");
+
+    if should_fail {
+        let (finished_at, message) =
+            health_check_failed.expect("health check failure was reported");
+        assert_eq!(finished_at, 4, "health check failed after the fourth test");
+        assert!(message.contains("exited with"), "message: {message}");
+        assert_eq!(cancel_reason, Some(CancelReason::HealthCheck));
+        assert_eq!(run_stats.finished_count, 4, "no further tests were run");
+        assert_eq!(run_stats.initial_run_count, 6);
+    } else {
+        assert_eq!(health_check_failed, None);
+        assert_eq!(cancel_reason, None);
+        assert_eq!(run_stats.finished_count, 6, "all tests were run");
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_group_scripts() -> Result<()> {
+    set_env_vars();
+    let temp_dir = camino_tempfile::tempdir()?;
+    let log_path = temp_dir.path().join("group-scripts.log");
+    // Both profiles use the with-scripts group, so they're run one after the other here.
+    std::env::set_var("NEXTEST_FIXTURE_GROUP_SCRIPTS_LOG", &log_path);
+
+    let run = |profile_name: &str, filter: &str| {
+        let (events, run_stats) = run_profile(RunIgnored::Default, filter, profile_name, |_| {});
+        let log = std::fs::read_to_string(&log_path).expect("group scripts log was written");
+        std::fs::remove_file(&log_path).expect("group scripts log removed");
+        let outputs: HashMap<_, _> = finished_tests(&events)
+            .into_iter()
+            .map(|(name, run_statuses)| {
+                let last_status = run_statuses.last_status();
+                let stdout = String::from_utf8_lossy(&last_status.stdout).into_owned();
+                (name, (last_status.result, stdout))
+            })
+            .collect();
+        (log, outputs, run_stats)
+    };
+
+    // The group is set up once before its first test, and torn down after its last test even
+    // though test_failure_assert failed.
+    let (log, outputs, run_stats) = run(
+        "with-group-scripts",
+        "test(=test_cargo_env_vars) | test(=test_failure_assert) | test(=test_success)",
+    );
+    assert_eq!(log, "setup\nteardown\n", "setup and teardown each ran once");
+    assert_eq!(run_stats.finished_count, 3, "all tests were run");
+    assert_eq!(run_stats.failed, 1, "test_failure_assert failed");
+    let (result, stdout) = &outputs["test_cargo_env_vars"];
+    assert_eq!(*result, ExecutionResult::Pass);
+    assert!(
+        stdout.contains("NEXTEST_FIXTURE_GROUP_SCRIPTS_ENV = from-setup"),
+        "environment from the setup command was passed to the test (stdout: {stdout})"
+    );
+
+    // With fail-fast, test_success is never started, so the group is torn down once the run is
+    // canceled.
+    let (log, outputs, run_stats) = run(
+        "with-group-scripts-fail-fast",
+        "test(=test_failure_assert) | test(=test_success)",
+    );
+    assert_eq!(log, "setup\nteardown\n", "setup and teardown each ran once");
+    assert_eq!(
+        run_stats.finished_count, 1,
+        "only test_failure_assert was run"
+    );
+    assert!(
+        outputs.contains_key("test_failure_assert"),
+        "test_failure_assert was run"
+    );
+    assert!(
+        !outputs.contains_key("test_success"),
+        "test_success wasn't run"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_group_saturation() -> Result<()> {
+    set_env_vars();
+
+    let (events, _) = run_profile(
+        RunIgnored::All,
+        "test(=test_slow_timeout_2) | test(=test_success) | test(=test_cwd)",
+        "with-group-saturation",
+        |_| {},
+    );
+
+    let mut saturated = Vec::new();
+    let mut freed = Vec::new();
+    for event in &events {
+        match event {
+            TestEvent::TestGroupSaturated {
+                group,
+                max_threads,
+                run_elapsed,
+            } => {
+                saturated.push((group.as_str(), *max_threads, *run_elapsed));
+            }
+            TestEvent::TestGroupFreed {
+                group,
+                max_threads,
+                run_elapsed,
+                saturated_for,
+            } => {
+                freed.push((group.as_str(), *max_threads, *run_elapsed, *saturated_for));
+            }
+            _ => {}
+        }
+    }
+
+    // The flaky group is saturated exactly once, while test_slow_timeout_2 is running.
+    assert_eq!(saturated.len(), 1, "group saturated once: {saturated:?}");
+    assert_eq!(freed.len(), 1, "group freed once: {freed:?}");
+    let (saturated_group, saturated_max_threads, saturated_at) = saturated[0];
+    let (freed_group, freed_max_threads, freed_at, saturated_for) = freed[0];
+    assert_eq!(saturated_group, "flaky");
+    assert_eq!(freed_group, "flaky");
+    assert_eq!(saturated_max_threads, 4);
+    assert_eq!(freed_max_threads, 4);
+    assert_eq!(saturated_for, freed_at - saturated_at);
+    // test_slow_timeout_2 sleeps for 1.5 seconds.
+    assert!(
+        saturated_for >= Duration::from_millis(1500),
+        "group saturated for as long as the test ran: {saturated_for:?}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_expected_failures() -> Result<()> {
+    set_env_vars();
+
+    let (events, run_stats) = run_profile(
+        RunIgnored::Default,
+        "test(=test_failure_assert) | test(=test_success) | test(=test_cwd)",
+        "with-expected-failures",
+        |_| {},
+    );
+
+    let finished = finished_tests(&events);
+    for (name, run_statuses) in &finished {
+        let expected = match *name {
+            // test_failure_assert fails, as expected.
+            "test_failure_assert" => ExecutionResult::ExpectedFail,
+            // test_success passes, but is expected to fail.
+            "test_success" => ExecutionResult::UnexpectedPass,
+            "test_cwd" => ExecutionResult::Pass,
+            other => panic!("unexpected test {other}"),
+        };
+        assert_eq!(
+            run_statuses.last_status().result,
+            expected,
+            "for {name}, result matches"
+        );
+    }
+
+    assert_eq!(finished.len(), 3, "all matching tests were run");
+    assert_eq!(run_stats.passed, 2, "test_cwd and the XFAIL test passed");
+    assert_eq!(run_stats.expected_failures, 1);
+    assert_eq!(run_stats.failed, 1, "the XPASS test failed");
+    assert_eq!(run_stats.unexpected_passes, 1);
+    assert!(!run_stats.is_success(), "an unexpected pass fails the run");
+
+    Ok(())
+}
@@ -5,22 +5,25 @@ use camino::{Utf8Path, Utf8PathBuf};
 use duct::cmd;
 use guppy::{graph::PackageGraph, MetadataCommand};
 use maplit::btreemap;
+use nextest_filtering::FilteringExpr;
 use nextest_metadata::{FilterMatch, MismatchReason, RustBinaryId};
 use nextest_runner::{
     cargo_config::{CargoConfigs, EnvironmentMap},
-    config::{get_num_cpus, NextestConfig},
+    config::{get_num_cpus, NextestConfig, NextestProfile},
     double_spawn::DoubleSpawnInfo,
     list::{
         BinaryList, RustBuildMeta, RustTestArtifact, TestExecuteContext, TestList, TestListState,
     },
-    reporter::TestEvent,
+    platform::BuildPlatforms,
+    reporter::{ReporterStderr, TestEvent, TestReporterBuilder},
     reuse_build::PathMapper,
     runner::{
         configure_handle_inheritance, AbortStatus, ExecutionResult, ExecutionStatuses, RunStats,
-        TestRunner,
+        TestRunner, TestRunnerBuilder,
     },
+    signal::SignalHandlerKind,
     target_runner::TargetRunner,
-    test_filter::TestFilterBuilder,
+    test_filter::{RunIgnored, TestFilterBuilder},
 };
 use once_cell::sync::Lazy;
 use std::{
@@ -117,12 +120,15 @@ pub(crate) static EXPECTED_TESTS: Lazy<BTreeMap<RustBinaryId, Vec<TestFixture>>>
                 TestFixture { name: "test_flaky_mod_6", status: FixtureStatus::Flaky { pass_attempt: 6 } },
                 TestFixture { name: "test_ignored", status: FixtureStatus::IgnoredPass },
                 TestFixture { name: "test_ignored_fail", status: FixtureStatus::IgnoredFail },
+                TestFixture { name: "test_relative_cwd", status: FixtureStatus::Pass },
                 TestFixture { name: "test_result_failure", status: FixtureStatus::Fail },
                 TestFixture { name: "test_slow_timeout", status: FixtureStatus::IgnoredPass },
                 TestFixture { name: "test_slow_timeout_2", status: FixtureStatus::IgnoredPass },
                 TestFixture { name: "test_slow_timeout_subprocess", status: FixtureStatus::IgnoredPass },
                 TestFixture { name: "test_stdin_closed", status: FixtureStatus::Pass },
+                TestFixture { name: "test_stdin_read_to_end", status: FixtureStatus::Pass },
                 TestFixture { name: "test_subprocess_doesnt_exit", status: FixtureStatus::Leak },
+                TestFixture { name: "test_subprocess_writes_after_exit", status: FixtureStatus::Leak },
                 TestFixture { name: "test_success", status: FixtureStatus::Pass },
                 TestFixture { name: "test_success_should_panic", status: FixtureStatus::Pass },
             ],
@@ -444,3 +450,130 @@ pub(crate) fn execute_collect(
 
     (instance_statuses, run_stats)
 }
+
+/// Lists the fixture tests matching `filter`, a filter expression.
+///
+/// The list is leaked, so that events from running it can be returned from [`run_profile`].
+pub(crate) fn make_filtered_test_list(
+    run_ignored: RunIgnored,
+    filter: &str,
+) -> &'static TestList<'static> {
+    let expr = FilteringExpr::parse(filter.to_owned(), &PACKAGE_GRAPH)
+        .expect("filter expression is valid");
+    let test_filter =
+        TestFilterBuilder::new(run_ignored, None, Vec::<String>::new(), vec![expr]).unwrap();
+    Box::leak(Box::new(
+        FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty()),
+    ))
+}
+
+/// Loads the fixture profile with the given name, for the host platform.
+///
+/// The config is leaked, for the same reason as in [`make_filtered_test_list`].
+pub(crate) fn load_profile(profile_name: &str) -> NextestProfile<'static> {
+    let config: &'static NextestConfig = Box::leak(Box::new(load_config()));
+    config
+        .profile(profile_name)
+        .unwrap_or_else(|_| panic!("{profile_name} config is valid"))
+        .apply_build_platforms(&BuildPlatforms::new(None).unwrap())
+}
+
+/// Runs the fixture tests matching `filter` with the given profile.
+///
+/// `configure` can set options on the runner before it's built. Returns every event sent during
+/// the run, in order, along with the run's statistics.
+pub(crate) fn run_profile(
+    run_ignored: RunIgnored,
+    filter: &str,
+    profile_name: &str,
+    configure: impl FnOnce(&mut TestRunnerBuilder),
+) -> (Vec<TestEvent<'static>>, RunStats) {
+    run_test_list(
+        make_filtered_test_list(run_ignored, filter),
+        load_profile(profile_name),
+        configure,
+    )
+}
+
+/// Like [`run_profile`], but for an already-built test list and profile.
+pub(crate) fn run_test_list<'a>(
+    test_list: &'a TestList<'a>,
+    profile: NextestProfile<'a>,
+    configure: impl FnOnce(&mut TestRunnerBuilder),
+) -> (Vec<TestEvent<'a>>, RunStats) {
+    let mut builder = TestRunnerBuilder::default();
+    configure(&mut builder);
+    let runner = builder
+        .build(
+            test_list,
+            profile,
+            SignalHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    configure_handle_inheritance(false).expect("configuring handle inheritance on Windows failed");
+    let mut events = Vec::new();
+    let run_stats = runner.execute(|event| events.push(event));
+    (events, run_stats)
+}
+
+/// Runs the fixture tests matching `filter` with `profile`, passing events to a reporter built by
+/// `reporter_builder`.
+pub(crate) fn report_profile<'a>(
+    filter: &str,
+    profile: &NextestProfile<'a>,
+    reporter_builder: &TestReporterBuilder,
+    output: ReporterStderr<'a>,
+) -> RunStats {
+    let test_list = make_filtered_test_list(RunIgnored::Default, filter);
+    let mut reporter = reporter_builder.build(test_list, profile, output);
+    let runner = TestRunnerBuilder::default()
+        .build(
+            test_list,
+            profile.clone(),
+            SignalHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    configure_handle_inheritance(false).expect("configuring handle inheritance on Windows failed");
+    runner
+        .try_execute(|event| reporter.report_event(event))
+        .expect("events were reported")
+}
+
+/// Returns the statuses of the tests that finished during a run, keyed by test name.
+pub(crate) fn finished_tests<'e, 'a>(
+    events: &'e [TestEvent<'a>],
+) -> BTreeMap<&'a str, &'e ExecutionStatuses> {
+    let mut finished = BTreeMap::new();
+    for event in events {
+        if let TestEvent::TestFinished {
+            test_instance,
+            run_statuses,
+            ..
+        } = event
+        {
+            let prev = finished.insert(test_instance.name, run_statuses);
+            assert!(prev.is_none(), "{} finished only once", test_instance.name);
+        }
+    }
+    finished
+}
+
+/// Returns the tests that were skipped during a run, keyed by test name.
+pub(crate) fn skipped_tests<'a>(events: &[TestEvent<'a>]) -> BTreeMap<&'a str, MismatchReason> {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            TestEvent::TestSkipped {
+                test_instance,
+                reason,
+            } => Some((test_instance.name, *reason)),
+            _ => None,
+        })
+        .collect()
+}
@@ -5,8 +5,9 @@ use thiserror::Error;
 
 /// An error that occurs while serializing a [`Report`](crate::Report).
 ///
-/// Returned by [`Report::serialize`](crate::Report::serialize) and
-/// [`Report::to_string`](crate::Report::to_string).
+/// Returned by [`Report::serialize`](crate::Report::serialize),
+/// [`Report::to_string`](crate::Report::to_string) and
+/// [`TestSuite::serialize`](crate::TestSuite::serialize).
 #[derive(Debug, Error)]
 #[error("error serializing JUnit report")]
 pub struct SerializeError {
@@ -1,7 +1,10 @@
 // Copyright (c) The nextest Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use crate::{serialize::serialize_report, SerializeError};
+use crate::{
+    serialize::{serialize_report, serialize_test_suite_standalone},
+    SerializeError,
+};
 use chrono::{DateTime, FixedOffset};
 use indexmap::map::IndexMap;
 use std::{io, iter, time::Duration};
@@ -264,6 +267,14 @@ impl TestSuite {
     pub fn set_system_err_lossy(&mut self, system_err: impl AsRef<[u8]>) -> &mut Self {
         self.set_system_err(String::from_utf8_lossy(system_err.as_ref()))
     }
+
+    /// Serialize this test suite to the given writer, as a standalone `<testsuite>` element.
+    ///
+    /// No XML declaration is written. This is useful for writing out a report incrementally, one
+    /// test suite at a time, inside a `<testsuites>` element.
+    pub fn serialize(&self, writer: impl io::Write) -> Result<(), SerializeError> {
+        serialize_test_suite_standalone(self, writer)
+    }
 }
 
 /// Represents a single test case.
@@ -299,6 +310,11 @@ pub struct TestCase {
     /// Data written to standard error while the test case was executed.
     pub system_err: Option<Output>,
 
+    /// Custom properties set for this test case.
+    ///
+    /// Test case properties aren't part of the JUnit spec, but are understood by many tools.
+    pub properties: Vec<Property>,
+
     /// Other fields that may be set as attributes, such as "classname".
     pub extra: IndexMap<String, String>,
 }
@@ -315,6 +331,7 @@ impl TestCase {
             status,
             system_out: None,
             system_err: None,
+            properties: vec![],
             extra: IndexMap::new(),
         }
     }
@@ -368,6 +385,23 @@ impl TestCase {
     pub fn set_system_err_lossy(&mut self, system_err: impl AsRef<[u8]>) -> &mut Self {
         self.set_system_err(String::from_utf8_lossy(system_err.as_ref()))
     }
+
+    /// Adds a property to this test case.
+    pub fn add_property(&mut self, property: impl Into<Property>) -> &mut Self {
+        self.properties.push(property.into());
+        self
+    }
+
+    /// Adds several properties to this test case.
+    pub fn add_properties(
+        &mut self,
+        properties: impl IntoIterator<Item = impl Into<Property>>,
+    ) -> &mut Self {
+        for property in properties {
+            self.add_property(property);
+        }
+        self
+    }
 }
 
 /// Represents the success or failure of a test case.
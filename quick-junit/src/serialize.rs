@@ -45,6 +45,18 @@ pub(crate) fn serialize_report(
     Ok(writer.write_indent()?)
 }
 
+pub(crate) fn serialize_test_suite_standalone(
+    test_suite: &TestSuite,
+    writer: impl io::Write,
+) -> Result<(), SerializeError> {
+    let mut writer = Writer::new_with_indent(writer, b' ', 4);
+
+    serialize_test_suite(test_suite, &mut writer)?;
+
+    // Add a trailing newline.
+    Ok(writer.write_indent()?)
+}
+
 pub(crate) fn serialize_report_impl(
     report: &Report,
     writer: &mut Writer<impl io::Write>,
@@ -180,6 +192,7 @@ fn serialize_test_case(
         status,
         system_out,
         system_err,
+        properties,
         extra,
     } = test_case;
 
@@ -204,6 +217,14 @@ fn serialize_test_case(
     }
     writer.write_event(Event::Start(testcase_tag))?;
 
+    if !properties.is_empty() {
+        serialize_empty_start_tag(PROPERTIES_TAG, writer)?;
+        for property in properties {
+            serialize_property(property, writer)?;
+        }
+        serialize_end_tag(PROPERTIES_TAG, writer)?;
+    }
+
     match status {
         TestCaseStatus::Success { flaky_runs } => {
             for rerun in flaky_runs {
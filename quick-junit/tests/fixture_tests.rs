@@ -107,7 +107,9 @@ fn basic_report() -> Report {
     test_case_status.add_rerun(test_rerun);
 
     let mut test_case = TestCase::new("testcase4", test_case_status);
-    test_case.set_time(Duration::from_millis(661661));
+    test_case
+        .set_time(Duration::from_millis(661661))
+        .add_property(("step", "foobar"));
     test_suite.add_test_case(test_case);
 
     // ---